@@ -0,0 +1,316 @@
+//! Wasmer's engines are pluggable: the `wasmer` API only relies on the
+//! `Engine` and `Artifact` traits (defined in the `wasmer_engine`
+//! crate), so new execution strategies (interpreters, hardware
+//! offload...) can be experimented with outside of Wasmer.
+//!
+//! This example implements the smallest possible engine: a "null
+//! engine" that validates and translates the modules, lets Wasmer
+//! instantiate them (creating their memories, tables and globals with
+//! the `Tunables` of the store, and initializing them), but doesn't
+//! execute their code. Calling a function raises a trap instead.
+//!
+//! An actual engine would produce code where the null engine produces
+//! the `null_function` and `null_trampoline` below:
+//!
+//!   * the compiled functions, called with the `vmctx` of their
+//!     instance first, then their parameters,
+//!   * a trampoline per signature, called by the host with a pointer
+//!     to the parameters and results of the function it calls.
+//!
+//! An interpreter can be implemented with these trampolines too, by
+//! mapping the function pointers it gets to the functions of its
+//! modules.
+//!
+//! You can run the example directly by executing in Wasmer root:
+//!
+//! ```shell
+//! cargo run --example engine-null --release --features "compiler"
+//! ```
+//!
+//! Ready?
+
+use std::sync::Arc;
+use wasmer::vm::{
+    FunctionBodyPtr, MemoryStyle, ModuleInfo, SignatureRegistry, TableStyle, VMContext,
+    VMFunctionBody, VMSharedSignatureIndex, VMTrampoline,
+};
+use wasmer::{
+    imports, raise_user_trap, wat2wasm, Artifact, CompileError, DeserializeError, Engine, EngineId,
+    Features, Instance, Module, RuntimeError, SerializeError, Store, Target, Tunables, Value,
+};
+use wasmer_compiler::ModuleEnvironment;
+use wasmer_types::entity::{BoxedSlice, PrimaryMap};
+use wasmer_types::{
+    FunctionIndex, FunctionType, LocalFunctionIndex, MemoryIndex, OwnedDataInitializer,
+    SignatureIndex, TableIndex,
+};
+
+/// The engine: it validates and compiles the modules into artifacts,
+/// and owns the signatures shared by their instances.
+#[derive(Clone)]
+struct NullEngine {
+    signatures: Arc<SignatureRegistry>,
+    target: Arc<Target>,
+    id: EngineId,
+}
+
+impl NullEngine {
+    fn new() -> Self {
+        Self {
+            signatures: Arc::new(SignatureRegistry::new()),
+            target: Arc::new(Target::default()),
+            id: EngineId::default(),
+        }
+    }
+}
+
+impl Engine for NullEngine {
+    fn target(&self) -> &Target {
+        &self.target
+    }
+
+    fn register_signature(&self, func_type: &FunctionType) -> VMSharedSignatureIndex {
+        self.signatures.register(func_type)
+    }
+
+    fn lookup_signature(&self, sig: VMSharedSignatureIndex) -> Option<FunctionType> {
+        self.signatures.lookup(sig)
+    }
+
+    fn validate(&self, binary: &[u8]) -> Result<(), CompileError> {
+        wasmer_compiler::wasmparser::validate(binary)
+            .map_err(|error| CompileError::Validate(error.to_string()))
+    }
+
+    fn compile(
+        &self,
+        binary: &[u8],
+        tunables: &dyn Tunables,
+    ) -> Result<Arc<dyn Artifact>, CompileError> {
+        Ok(Arc::new(NullArtifact::new(self, binary, tunables)?))
+    }
+
+    unsafe fn deserialize(&self, _bytes: &[u8]) -> Result<Arc<dyn Artifact>, DeserializeError> {
+        Err(DeserializeError::Generic(
+            "the null engine can't deserialize modules".to_string(),
+        ))
+    }
+
+    fn id(&self) -> &EngineId {
+        &self.id
+    }
+
+    fn cloned(&self) -> Arc<dyn Engine + Send + Sync> {
+        Arc::new(self.clone())
+    }
+}
+
+/// A compiled module. The `Artifact` trait provides the instantiation,
+/// based on what the artifact returns.
+struct NullArtifact {
+    module: Arc<ModuleInfo>,
+    features: Features,
+    data_initializers: Box<[OwnedDataInitializer]>,
+    memory_styles: PrimaryMap<MemoryIndex, MemoryStyle>,
+    table_styles: PrimaryMap<TableIndex, TableStyle>,
+    finished_functions: BoxedSlice<LocalFunctionIndex, FunctionBodyPtr>,
+    finished_function_call_trampolines: BoxedSlice<SignatureIndex, VMTrampoline>,
+    finished_dynamic_function_trampolines: BoxedSlice<FunctionIndex, FunctionBodyPtr>,
+    signatures: BoxedSlice<SignatureIndex, VMSharedSignatureIndex>,
+}
+
+/// Raises the trap returned by the calls as a `RuntimeError`.
+fn trap() -> ! {
+    unsafe {
+        raise_user_trap(Box::new(RuntimeError::new(
+            "the null engine doesn't execute code",
+        )))
+    }
+}
+
+/// The body of all the functions, which is only called directly by
+/// Wasmer for the start function, with no parameters.
+extern "C" fn null_function(_vmctx: *mut VMContext) {
+    trap()
+}
+
+/// The trampoline of all the signatures.
+extern "C" fn null_trampoline(
+    _vmctx: *mut VMContext,
+    _callee: *const VMFunctionBody,
+    _values: *mut u128,
+) {
+    trap()
+}
+
+impl NullArtifact {
+    fn new(
+        engine: &NullEngine,
+        binary: &[u8],
+        tunables: &dyn Tunables,
+    ) -> Result<Self, CompileError> {
+        let translation = ModuleEnvironment::new()
+            .translate(binary)
+            .map_err(CompileError::Wasm)?;
+        let module = translation.module;
+
+        // The tunables of the store decide how the memories and the
+        // tables are created.
+        let memory_styles = module
+            .memories
+            .values()
+            .map(|memory_type| tunables.memory_style(memory_type))
+            .collect();
+        let table_styles = module
+            .tables
+            .values()
+            .map(|table_type| tunables.table_style(table_type))
+            .collect();
+        let data_initializers = translation
+            .data_initializers
+            .iter()
+            .map(OwnedDataInitializer::new)
+            .collect();
+
+        let body = FunctionBodyPtr(null_function as *const VMFunctionBody);
+        let num_local_functions = module.functions.len() - module.num_imported_functions;
+        let finished_functions = (0..num_local_functions)
+            .map(|_| body)
+            .collect::<PrimaryMap<_, _>>()
+            .into_boxed_slice();
+        let finished_function_call_trampolines = (0..module.signatures.len())
+            .map(|_| null_trampoline as VMTrampoline)
+            .collect::<PrimaryMap<_, _>>()
+            .into_boxed_slice();
+        let finished_dynamic_function_trampolines = (0..module.num_imported_functions)
+            .map(|_| body)
+            .collect::<PrimaryMap<_, _>>()
+            .into_boxed_slice();
+        let signatures = module
+            .signatures
+            .values()
+            .map(|signature| engine.register_signature(signature))
+            .collect::<PrimaryMap<_, _>>()
+            .into_boxed_slice();
+
+        Ok(Self {
+            module: Arc::new(module),
+            features: Features::default(),
+            data_initializers,
+            memory_styles,
+            table_styles,
+            finished_functions,
+            finished_function_call_trampolines,
+            finished_dynamic_function_trampolines,
+            signatures,
+        })
+    }
+}
+
+impl Artifact for NullArtifact {
+    fn module(&self) -> Arc<ModuleInfo> {
+        self.module.clone()
+    }
+
+    fn module_ref(&self) -> &ModuleInfo {
+        &self.module
+    }
+
+    fn module_mut(&mut self) -> Option<&mut ModuleInfo> {
+        Arc::get_mut(&mut self.module)
+    }
+
+    fn register_frame_info(&self) {
+        // An engine generating code registers its functions with
+        // `wasmer_engine::register_frame_info` here, so the traps
+        // raised in them have a backtrace. There is no code here.
+    }
+
+    fn features(&self) -> &Features {
+        &self.features
+    }
+
+    fn memory_styles(&self) -> &PrimaryMap<MemoryIndex, MemoryStyle> {
+        &self.memory_styles
+    }
+
+    fn table_styles(&self) -> &PrimaryMap<TableIndex, TableStyle> {
+        &self.table_styles
+    }
+
+    fn data_initializers(&self) -> &[OwnedDataInitializer] {
+        &self.data_initializers
+    }
+
+    fn finished_functions(&self) -> &BoxedSlice<LocalFunctionIndex, FunctionBodyPtr> {
+        &self.finished_functions
+    }
+
+    fn finished_function_call_trampolines(&self) -> &BoxedSlice<SignatureIndex, VMTrampoline> {
+        &self.finished_function_call_trampolines
+    }
+
+    fn finished_dynamic_function_trampolines(&self) -> &BoxedSlice<FunctionIndex, FunctionBodyPtr> {
+        &self.finished_dynamic_function_trampolines
+    }
+
+    fn signatures(&self) -> &BoxedSlice<SignatureIndex, VMSharedSignatureIndex> {
+        &self.signatures
+    }
+
+    fn serialize(&self) -> Result<Vec<u8>, SerializeError> {
+        Err(SerializeError::Generic(
+            "the null engine can't serialize modules".to_string(),
+        ))
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Let's declare the Wasm module with the text representation.
+    let wasm_bytes = wat2wasm(
+        r#"
+(module
+  (memory (export "memory") 1)
+  (data (i32.const 0) "Hello")
+  (global (export "answer") i32 (i32.const 42))
+  (func (export "sum") (param i32 i32) (result i32)
+    local.get 0
+    local.get 1
+    i32.add))
+"#
+        .as_bytes(),
+    )?;
+
+    // The store uses the engine like any other.
+    let store = Store::new(&NullEngine::new());
+
+    println!("Compiling module...");
+    let module = Module::new(&store, wasm_bytes)?;
+
+    println!("Instantiating module...");
+    let instance = Instance::new(&module, &imports! {})?;
+
+    // The instance has been initialized by Wasmer.
+    let memory = instance.exports.get_memory("memory")?;
+    let hello = unsafe { &memory.data_unchecked()[..5] };
+    println!("The memory starts with {:?}", std::str::from_utf8(hello)?);
+    assert_eq!(hello, b"Hello");
+    let answer = instance.exports.get_global("answer")?;
+    assert_eq!(answer.get(), Value::I32(42));
+
+    // But its code can't be executed.
+    let sum = instance.exports.get_function("sum")?;
+    println!("Calling `sum` function...");
+    let error = sum
+        .call(&[Value::I32(1), Value::I32(2)])
+        .expect_err("the null engine executed code");
+    println!("The call failed: {}", error.message());
+    assert_eq!(error.message(), "the null engine doesn't execute code");
+
+    Ok(())
+}
+
+#[test]
+fn test_engine_null() -> Result<(), Box<dyn std::error::Error>> {
+    main()
+}
@@ -0,0 +1,73 @@
+use std::fmt;
+use thiserror::Error;
+use wasm_bindgen::{JsCast, JsValue};
+
+/// The WebAssembly.CompileError object indicates an error during
+/// WebAssembly decoding or validation.
+///
+/// This is based on the [Wasm Compile Error][compile-error] API.
+///
+/// [compile-error]: https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/WebAssembly/CompileError
+#[derive(Error, Debug)]
+pub enum CompileError {
+    /// The host failed to compile the module.
+    #[error("Compilation error: {0}")]
+    Codegen(String),
+
+    /// The module doesn't validate.
+    #[error("Validation error: {0}")]
+    Validate(String),
+
+    /// The module uses a feature the `js` backend doesn't support.
+    #[error("Feature {0} is not yet supported")]
+    UnsupportedFeature(String),
+}
+
+/// A runtime error: a trap of a WebAssembly function, or an error
+/// thrown by a host function.
+#[derive(Clone, Error)]
+pub struct RuntimeError {
+    message: String,
+}
+
+impl RuntimeError {
+    /// Creates a new generic `RuntimeError` with the given `message`.
+    pub fn new<I: Into<String>>(message: I) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+
+    /// Returns a reference the `message` stored in `Trap`.
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+}
+
+impl fmt::Debug for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RuntimeError")
+            .field("message", &self.message)
+            .finish()
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "RuntimeError: {}", self.message)
+    }
+}
+
+/// Returns the message of the JavaScript exception `value`.
+pub(crate) fn js_error_message(value: &JsValue) -> String {
+    match value.dyn_ref::<js_sys::Error>() {
+        Some(error) => String::from(error.message()),
+        None => value.as_string().unwrap_or_else(|| format!("{:?}", value)),
+    }
+}
+
+impl From<JsValue> for RuntimeError {
+    fn from(value: JsValue) -> Self {
+        Self::new(js_error_message(&value))
+    }
+}
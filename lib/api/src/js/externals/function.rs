@@ -0,0 +1,202 @@
+use crate::js::error::RuntimeError;
+use crate::js::exports::{ExportError, Exportable};
+use crate::js::externals::Extern;
+use crate::js::store::{Store, StoreObject};
+use crate::js::types::{val_from_js, val_to_js, FunctionType, Val};
+use std::fmt;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// A WebAssembly `function` instance.
+///
+/// A function instance is the runtime representation of a function.
+/// It effectively is a closure of the original function (defined in either
+/// the host or the WebAssembly module) over the runtime `Instance` of its
+/// originating `Module`.
+///
+/// The module instance is used to resolve references to other definitions
+/// during execution of the function.
+///
+/// With the `js` backend, the function is a JavaScript function: an
+/// exported WebAssembly function, or a closure calling a host function.
+///
+/// Spec: <https://webassembly.github.io/spec/core/exec/runtime.html#function-instances>
+#[derive(Clone)]
+pub struct Function {
+    store: Store,
+    ty: FunctionType,
+    pub(crate) function: js_sys::Function,
+}
+
+/// Returns a JavaScript function passing its arguments as an array to
+/// `function`.
+///
+/// WebAssembly calls host functions with positional arguments, which a
+/// Rust closure can't take in any number.
+fn variadic(function: &JsValue) -> js_sys::Function {
+    let factory = js_sys::Function::new_with_args(
+        "f",
+        "return function() { return f(Array.prototype.slice.call(arguments)); };",
+    );
+    factory
+        .call1(&JsValue::UNDEFINED, function)
+        .expect("the factory of variadic functions doesn't throw")
+        .unchecked_into()
+}
+
+impl Function {
+    /// Creates a new host `Function` (dynamic) with the provided signature.
+    ///
+    /// The host creates the JavaScript function with the `Function`
+    /// constructor, so the `Content-Security-Policy` of a page must allow
+    /// `unsafe-eval`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// # use wasmer::{Function, FunctionType, Type, Store, Value};
+    /// # let store = Store::default();
+    /// let signature = FunctionType::new(vec![Type::I32, Type::I32], vec![Type::I32]);
+    ///
+    /// let f = Function::new(&store, &signature, |args| {
+    ///     let sum = args[0].unwrap_i32() + args[1].unwrap_i32();
+    ///     Ok(vec![Value::I32(sum)])
+    /// });
+    /// ```
+    pub fn new<FT, F>(store: &Store, ty: FT, func: F) -> Self
+    where
+        FT: Into<FunctionType>,
+        F: Fn(&[Val]) -> Result<Vec<Val>, RuntimeError> + 'static,
+    {
+        let ty = ty.into();
+        let signature = ty.clone();
+        let closure = Closure::wrap(Box::new(move |arguments: js_sys::Array| {
+            let params = signature
+                .params()
+                .iter()
+                .enumerate()
+                .map(|(index, ty)| val_from_js(*ty, &arguments.get(index as u32)))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|error| js_sys::Error::new(&error.message()))?;
+            let results = func(&params).map_err(|error| js_sys::Error::new(&error.message()))?;
+            results_to_js(&results).map_err(|error| js_sys::Error::new(&error.message()))
+        })
+            as Box<dyn FnMut(js_sys::Array) -> Result<JsValue, js_sys::Error>>);
+        Self {
+            store: store.clone(),
+            ty,
+            function: variadic(&closure.into_js_value()),
+        }
+    }
+
+    /// Wraps the JavaScript function `function` of type `ty`.
+    pub(crate) fn from_js(store: &Store, function: js_sys::Function, ty: FunctionType) -> Self {
+        Self {
+            store: store.clone(),
+            ty,
+            function,
+        }
+    }
+
+    /// Returns the [`FunctionType`] of the `Function`.
+    pub fn ty(&self) -> &FunctionType {
+        &self.ty
+    }
+
+    /// Returns the [`Store`] where the `Function` belongs.
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+
+    /// Returns the number of parameters that this function takes.
+    pub fn param_arity(&self) -> usize {
+        self.ty.params().len()
+    }
+
+    /// Returns the number of results this function produces.
+    pub fn result_arity(&self) -> usize {
+        self.ty.results().len()
+    }
+
+    /// Call the `Function` function.
+    ///
+    /// Depending on where the Function is defined, it will call it.
+    /// 1. If the function is defined inside a WebAssembly, it will call the trampoline
+    ///    for the function signature.
+    /// 2. If the function is defined in the host (in a native way), it will
+    ///    call the trampoline.
+    pub fn call(&self, params: &[Val]) -> Result<Box<[Val]>, RuntimeError> {
+        let expected = self.ty.params();
+        if params.len() != expected.len()
+            || params
+                .iter()
+                .zip(expected)
+                .any(|(param, ty)| param.ty() != *ty)
+        {
+            return Err(RuntimeError::new(format!(
+                "Parameters of type {:?} did not match signature {}",
+                params.iter().map(Val::ty).collect::<Vec<_>>(),
+                self.ty
+            )));
+        }
+        let arguments = js_sys::Array::new();
+        for param in params {
+            arguments.push(&val_to_js(param)?);
+        }
+        let result = js_sys::Reflect::apply(&self.function, &JsValue::UNDEFINED, &arguments)?;
+        let results = self.ty.results();
+        let results = match results.len() {
+            0 => vec![],
+            1 => vec![val_from_js(results[0], &result)?],
+            _ => {
+                let result = js_sys::Array::from(&result);
+                results
+                    .iter()
+                    .enumerate()
+                    .map(|(index, ty)| val_from_js(*ty, &result.get(index as u32)))
+                    .collect::<Result<Vec<_>, _>>()?
+            }
+        };
+        Ok(results.into_boxed_slice())
+    }
+}
+
+/// Converts the results of a host function into the JavaScript value
+/// WebAssembly expects: nothing, a value, or an array of values.
+fn results_to_js(results: &[Val]) -> Result<JsValue, RuntimeError> {
+    match results {
+        [] => Ok(JsValue::UNDEFINED),
+        [result] => val_to_js(result),
+        results => {
+            let array = js_sys::Array::new();
+            for result in results {
+                array.push(&val_to_js(result)?);
+            }
+            Ok(array.into())
+        }
+    }
+}
+
+impl StoreObject for Function {
+    fn comes_from_same_store(&self, store: &Store) -> bool {
+        Store::same(&self.store, store)
+    }
+}
+
+impl<'a> Exportable<'a> for Function {
+    fn get_self_from_extern(_extern: &'a Extern) -> Result<&'a Self, ExportError> {
+        match _extern {
+            Extern::Function(func) => Ok(func),
+            _ => Err(ExportError::IncompatibleType),
+        }
+    }
+}
+
+impl fmt::Debug for Function {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("Function")
+            .field("ty", &self.ty)
+            .finish()
+    }
+}
@@ -0,0 +1,168 @@
+use crate::js::error::{js_error_message, RuntimeError};
+use crate::js::exports::{ExportError, Exportable};
+use crate::js::externals::Extern;
+use crate::js::store::{Store, StoreObject};
+use crate::js::types::{val_from_js, val_to_js, GlobalType, Mutability, Val};
+use js_sys::{Object, Reflect, WebAssembly};
+use std::fmt;
+use wasm_bindgen::JsValue;
+
+/// A WebAssembly `global` instance.
+///
+/// A global instance is the runtime representation of a global variable.
+/// It consists of an individual value and a flag indicating whether it is mutable.
+///
+/// Spec: <https://webassembly.github.io/spec/core/exec/runtime.html#global-instances>
+#[derive(Clone)]
+pub struct Global {
+    store: Store,
+    ty: GlobalType,
+    pub(crate) global: WebAssembly::Global,
+}
+
+impl Global {
+    /// Create a new `Global` with the initial value [`Val`].
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// # use wasmer::{Global, Mutability, Store, Value};
+    /// # let store = Store::default();
+    /// #
+    /// let g = Global::new(&store, Value::I32(1));
+    ///
+    /// assert_eq!(g.get(), Value::I32(1));
+    /// assert_eq!(g.ty().mutability, Mutability::Const);
+    /// ```
+    pub fn new(store: &Store, val: Val) -> Self {
+        Self::from_value(store, val, Mutability::Const).unwrap()
+    }
+
+    /// Create a mutable `Global` with the initial value [`Val`].
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// # use wasmer::{Global, Mutability, Store, Value};
+    /// # let store = Store::default();
+    /// #
+    /// let g = Global::new_mut(&store, Value::I32(1));
+    ///
+    /// assert_eq!(g.get(), Value::I32(1));
+    /// assert_eq!(g.ty().mutability, Mutability::Var);
+    /// ```
+    pub fn new_mut(store: &Store, val: Val) -> Self {
+        Self::from_value(store, val, Mutability::Var).unwrap()
+    }
+
+    /// Create a `Global` with the initial value [`Val`] and the provided [`Mutability`].
+    fn from_value(store: &Store, val: Val, mutability: Mutability) -> Result<Self, RuntimeError> {
+        if !val.comes_from_same_store(store) {
+            return Err(RuntimeError::new("cross-`Store` globals are not supported"));
+        }
+        let ty = GlobalType {
+            ty: val.ty(),
+            mutability,
+        };
+        let descriptor = Object::new();
+        Reflect::set(
+            &descriptor,
+            &JsValue::from_str("value"),
+            &JsValue::from_str(&ty.ty.to_string()),
+        )?;
+        Reflect::set(
+            &descriptor,
+            &JsValue::from_str("mutable"),
+            &JsValue::from_bool(mutability.is_mutable()),
+        )?;
+        let global = WebAssembly::Global::new(&descriptor, &val_to_js(&val)?)?;
+        Ok(Self::from_js(store, global, ty))
+    }
+
+    /// Wraps the JavaScript global `global` of type `ty`.
+    pub(crate) fn from_js(store: &Store, global: WebAssembly::Global, ty: GlobalType) -> Self {
+        Self {
+            store: store.clone(),
+            ty,
+            global,
+        }
+    }
+
+    /// Returns the [`GlobalType`] of the `Global`.
+    pub fn ty(&self) -> &GlobalType {
+        &self.ty
+    }
+
+    /// Returns the [`Store`] where the `Global` belongs.
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+
+    /// Retrieves the current value [`Val`] that the Global has.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is a reference other than the null
+    /// `externref`, which the `js` backend can't receive.
+    pub fn get(&self) -> Val {
+        val_from_js(self.ty.ty, &self.global.value())
+            .unwrap_or_else(|error| panic!("{}", error.message()))
+    }
+
+    /// Sets a custom value [`Val`] to the runtime Global.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the global is immutable, or if the value
+    /// has another type.
+    pub fn set(&self, val: Val) -> Result<(), RuntimeError> {
+        if !val.comes_from_same_store(&self.store) {
+            return Err(RuntimeError::new("cross-`Store` values are not supported"));
+        }
+        if !self.ty.mutability.is_mutable() {
+            return Err(RuntimeError::new(format!(
+                "Attempted to set an immutable global: {}",
+                self.ty
+            )));
+        }
+        if val.ty() != self.ty.ty {
+            return Err(RuntimeError::new(format!(
+                "Attempted to operate on a global of type {} as a global of type {}",
+                self.ty.ty,
+                val.ty()
+            )));
+        }
+        Reflect::set(&self.global, &JsValue::from_str("value"), &val_to_js(&val)?)
+            .map_err(|error| RuntimeError::new(js_error_message(&error)))?;
+        Ok(())
+    }
+
+    /// Returns whether or not these two globals refer to the same data.
+    pub fn same(&self, other: &Self) -> bool {
+        self.global == other.global
+    }
+}
+
+impl fmt::Debug for Global {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter
+            .debug_struct("Global")
+            .field("ty", &self.ty())
+            .finish()
+    }
+}
+
+impl StoreObject for Global {
+    fn comes_from_same_store(&self, store: &Store) -> bool {
+        Store::same(&self.store, store)
+    }
+}
+
+impl<'a> Exportable<'a> for Global {
+    fn get_self_from_extern(_extern: &'a Extern) -> Result<&'a Self, ExportError> {
+        match _extern {
+            Extern::Global(global) => Ok(global),
+            _ => Err(ExportError::IncompatibleType),
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use crate::js::error::js_error_message;
+use crate::js::exports::{ExportError, Exportable};
+use crate::js::externals::Extern;
+use crate::js::store::{Store, StoreObject};
+use crate::js::types::MemoryType;
+use js_sys::{Object, Reflect, Uint8Array, WebAssembly};
+use std::convert::TryInto;
+use thiserror::Error;
+use wasm_bindgen::{JsCast, JsValue};
+use wasmer_types::{MemoryAccessError, Pages, WASM_PAGE_SIZE};
+
+/// Error type describing things that can go wrong when operating on
+/// the memories of the `js` backend.
+#[derive(Error, Debug, Clone, PartialEq, Hash)]
+pub enum MemoryError {
+    /// The operation would cause the size of the memory to exceed the maximum or would cause
+    /// an overflow leading to unindexable memory.
+    #[error("The memory could not grow: current size {} pages, requested increase: {} pages", current.0, attempted_delta.0)]
+    CouldNotGrow {
+        /// The current size in pages.
+        current: Pages,
+        /// The attempted amount to grow by in pages.
+        attempted_delta: Pages,
+    },
+    /// An error thrown by the JavaScript `WebAssembly` API.
+    #[error("A generic error occurred: {0}")]
+    Generic(String),
+}
+
+/// A WebAssembly `memory` instance.
+///
+/// A memory instance is the runtime representation of a linear memory.
+/// It consists of a vector of bytes and an optional maximum size.
+///
+/// The length of the vector always is a multiple of the WebAssembly
+/// page size, which is defined to be the constant 65536 – abbreviated 64Ki.
+/// Like in a memory type, the maximum size in a memory instance is
+/// given in units of this page size.
+///
+/// A memory created by the host or in WebAssembly code will be accessible and
+/// mutable from both host and WebAssembly.
+///
+/// Spec: <https://webassembly.github.io/spec/core/exec/runtime.html#memory-instances>
+#[derive(Debug, Clone)]
+pub struct Memory {
+    store: Store,
+    ty: MemoryType,
+    pub(crate) memory: WebAssembly::Memory,
+}
+
+impl Memory {
+    /// Creates a new host `Memory` from the provided [`MemoryType`].
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// # use wasmer::{Memory, MemoryType, Pages, Store, Type, Value};
+    /// # let store = Store::default();
+    /// #
+    /// let m = Memory::new(&store, MemoryType::new(1, None, false)).unwrap();
+    /// ```
+    pub fn new(store: &Store, ty: MemoryType) -> Result<Self, MemoryError> {
+        let descriptor = Object::new();
+        let set = |key: &str, value: JsValue| {
+            Reflect::set(&descriptor, &JsValue::from_str(key), &value)
+                .map_err(|error| MemoryError::Generic(js_error_message(&error)))
+        };
+        set("initial", JsValue::from_f64(ty.minimum.0 as f64))?;
+        if let Some(maximum) = ty.maximum {
+            set("maximum", JsValue::from_f64(maximum.0 as f64))?;
+        }
+        set("shared", JsValue::from_bool(ty.shared))?;
+        let memory = WebAssembly::Memory::new(&descriptor)
+            .map_err(|error| MemoryError::Generic(js_error_message(&error)))?;
+        Ok(Self::from_js(store, memory, ty))
+    }
+
+    /// Wraps the JavaScript memory `memory` of type `ty`.
+    pub(crate) fn from_js(store: &Store, memory: WebAssembly::Memory, ty: MemoryType) -> Self {
+        Self {
+            store: store.clone(),
+            ty,
+            memory,
+        }
+    }
+
+    /// Returns the [`MemoryType`] of the `Memory`.
+    ///
+    /// The minimum is the size of the memory when it was created: the
+    /// JavaScript `WebAssembly` API doesn't expose the type of a memory.
+    pub fn ty(&self) -> &MemoryType {
+        &self.ty
+    }
+
+    /// Returns the [`Store`] where the `Memory` belongs.
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+
+    /// Returns a JavaScript view of the bytes of the `Memory`.
+    ///
+    /// The view is detached once the memory grows, so it must be
+    /// requested again afterwards.
+    pub fn uint8view(&self) -> Uint8Array {
+        Uint8Array::new(&self.memory.buffer())
+    }
+
+    /// Returns the size (in bytes) of the `Memory`.
+    pub fn data_size(&self) -> u64 {
+        self.uint8view().length().into()
+    }
+
+    /// Returns the size (in [`Pages`]) of the `Memory`.
+    pub fn size(&self) -> Pages {
+        Pages((self.data_size() / WASM_PAGE_SIZE as u64) as u32)
+    }
+
+    /// Grow memory by the specified amount of WebAssembly [`Pages`] and return
+    /// the previous memory size.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if memory can't be grown by the specified amount
+    /// of pages.
+    pub fn grow<IntoPages>(&self, delta: IntoPages) -> Result<Pages, MemoryError>
+    where
+        IntoPages: Into<Pages>,
+    {
+        let delta = delta.into();
+        let current = self.size();
+        let could_not_grow = MemoryError::CouldNotGrow {
+            current,
+            attempted_delta: delta,
+        };
+        let maximum = self.ty.maximum.unwrap_or(Pages::max_value());
+        match current.checked_add(delta) {
+            Some(pages) if pages <= maximum => {}
+            _ => return Err(could_not_grow),
+        }
+        // `WebAssembly.Memory.prototype.grow` throws when the host can't
+        // allocate the pages, which a direct call would turn into a panic.
+        let grow = Reflect::get(&self.memory, &JsValue::from_str("grow"))
+            .map_err(|error| MemoryError::Generic(js_error_message(&error)))?
+            .unchecked_into::<js_sys::Function>();
+        let previous = grow
+            .call1(&self.memory, &JsValue::from_f64(delta.0 as f64))
+            .map_err(|_| could_not_grow)?;
+        Ok(Pages(previous.as_f64().unwrap_or_default() as u32))
+    }
+
+    /// Copies the bytes of the memory starting at `offset` to `buf`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryAccessError::OutOfBounds`] if the bytes aren't
+    /// all in the memory, and then nothing is copied.
+    pub fn read(&self, offset: u64, buf: &mut [u8]) -> Result<(), MemoryAccessError> {
+        let (start, end) = self.range(offset, buf.len())?;
+        self.uint8view().subarray(start, end).copy_to(buf);
+        Ok(())
+    }
+
+    /// Copies the bytes of `data` to the memory, starting at `offset`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryAccessError::OutOfBounds`] if the bytes aren't
+    /// all in the memory, and then nothing is copied.
+    pub fn write(&self, offset: u64, data: &[u8]) -> Result<(), MemoryAccessError> {
+        let (start, end) = self.range(offset, data.len())?;
+        self.uint8view().subarray(start, end).copy_from(data);
+        Ok(())
+    }
+
+    /// Returns the bounds of the `len` bytes starting at `offset`, if
+    /// they are all in the memory.
+    fn range(&self, offset: u64, len: usize) -> Result<(u32, u32), MemoryAccessError> {
+        let end = offset
+            .checked_add(len as u64)
+            .filter(|end| *end <= self.data_size())
+            .ok_or(MemoryAccessError::OutOfBounds)?;
+        let start = offset
+            .try_into()
+            .map_err(|_| MemoryAccessError::OutOfBounds)?;
+        let end = end.try_into().map_err(|_| MemoryAccessError::OutOfBounds)?;
+        Ok((start, end))
+    }
+
+    /// Returns whether or not these two memories refer to the same data.
+    pub fn same(&self, other: &Self) -> bool {
+        self.memory == other.memory
+    }
+}
+
+impl StoreObject for Memory {
+    fn comes_from_same_store(&self, store: &Store) -> bool {
+        Store::same(&self.store, store)
+    }
+}
+
+impl<'a> Exportable<'a> for Memory {
+    fn get_self_from_extern(_extern: &'a Extern) -> Result<&'a Self, ExportError> {
+        match _extern {
+            Extern::Memory(memory) => Ok(memory),
+            _ => Err(ExportError::IncompatibleType),
+        }
+    }
+}
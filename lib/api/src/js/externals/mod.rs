@@ -0,0 +1,130 @@
+mod function;
+mod global;
+mod memory;
+mod table;
+
+pub use self::function::Function;
+pub use self::global::Global;
+pub use self::memory::{Memory, MemoryError};
+pub use self::table::Table;
+
+use crate::js::exports::{ExportError, Exportable};
+use crate::js::store::{Store, StoreObject};
+use crate::js::types::ExternType;
+use std::fmt;
+use wasm_bindgen::{JsCast, JsValue};
+
+/// An `Extern` is the runtime representation of an entity that
+/// can be imported or exported.
+///
+/// Spec: <https://webassembly.github.io/spec/core/exec/runtime.html#external-values>
+#[derive(Clone)]
+pub enum Extern {
+    /// A external [`Function`].
+    Function(Function),
+    /// A external [`Global`].
+    Global(Global),
+    /// A external [`Table`].
+    Table(Table),
+    /// A external [`Memory`].
+    Memory(Memory),
+}
+
+impl Extern {
+    /// Return the undelying type of the inner `Extern`.
+    pub fn ty(&self) -> ExternType {
+        match self {
+            Self::Function(ft) => ExternType::Function(ft.ty().clone()),
+            Self::Memory(ft) => ExternType::Memory(*ft.ty()),
+            Self::Table(tt) => ExternType::Table(*tt.ty()),
+            Self::Global(gt) => ExternType::Global(*gt.ty()),
+        }
+    }
+
+    /// Create an `Extern` of type `ty` from the JavaScript value
+    /// `value` exported by an instance.
+    pub(crate) fn from_js(store: &Store, ty: &ExternType, value: JsValue) -> Option<Self> {
+        Some(match ty {
+            ExternType::Function(ty) => {
+                Self::Function(Function::from_js(store, value.dyn_into().ok()?, ty.clone()))
+            }
+            ExternType::Memory(ty) => {
+                Self::Memory(Memory::from_js(store, value.dyn_into().ok()?, *ty))
+            }
+            ExternType::Global(ty) => {
+                Self::Global(Global::from_js(store, value.dyn_into().ok()?, *ty))
+            }
+            ExternType::Table(ty) => {
+                Self::Table(Table::from_js(store, value.dyn_into().ok()?, *ty))
+            }
+        })
+    }
+
+    /// Returns the JavaScript object of the `Extern`, to import it.
+    pub(crate) fn to_js(&self) -> JsValue {
+        match self {
+            Self::Function(f) => f.function.clone().into(),
+            Self::Global(g) => g.global.clone().into(),
+            Self::Memory(m) => m.memory.clone().into(),
+            Self::Table(t) => t.table.clone().into(),
+        }
+    }
+}
+
+impl<'a> Exportable<'a> for Extern {
+    fn get_self_from_extern(_extern: &'a Self) -> Result<&'a Self, ExportError> {
+        // Since this is already an extern, we can just return it.
+        Ok(_extern)
+    }
+}
+
+impl StoreObject for Extern {
+    fn comes_from_same_store(&self, store: &Store) -> bool {
+        let my_store = match self {
+            Self::Function(f) => f.store(),
+            Self::Global(g) => g.store(),
+            Self::Memory(m) => m.store(),
+            Self::Table(t) => t.store(),
+        };
+        Store::same(my_store, store)
+    }
+}
+
+impl fmt::Debug for Extern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Function(_) => "Function(...)",
+                Self::Global(_) => "Global(...)",
+                Self::Memory(_) => "Memory(...)",
+                Self::Table(_) => "Table(...)",
+            }
+        )
+    }
+}
+
+impl From<Function> for Extern {
+    fn from(r: Function) -> Self {
+        Self::Function(r)
+    }
+}
+
+impl From<Global> for Extern {
+    fn from(r: Global) -> Self {
+        Self::Global(r)
+    }
+}
+
+impl From<Memory> for Extern {
+    fn from(r: Memory) -> Self {
+        Self::Memory(r)
+    }
+}
+
+impl From<Table> for Extern {
+    fn from(r: Table) -> Self {
+        Self::Table(r)
+    }
+}
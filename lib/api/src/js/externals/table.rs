@@ -0,0 +1,175 @@
+use crate::js::error::RuntimeError;
+use crate::js::exports::{ExportError, Exportable};
+use crate::js::externals::Extern;
+use crate::js::store::{Store, StoreObject};
+use crate::js::types::{val_to_js, TableType, Val, ValType};
+use js_sys::{Object, Reflect, WebAssembly};
+use wasm_bindgen::JsValue;
+use wasmer_types::ExternRef;
+
+/// A WebAssembly `table` instance.
+///
+/// The `Table` struct is an array-like structure representing a WebAssembly Table,
+/// which stores function references.
+///
+/// A table created by the host or in WebAssembly code will be accessible and
+/// mutable from both host and WebAssembly.
+///
+/// Spec: <https://webassembly.github.io/spec/core/exec/runtime.html#table-instances>
+#[derive(Debug, Clone)]
+pub struct Table {
+    store: Store,
+    ty: TableType,
+    pub(crate) table: WebAssembly::Table,
+}
+
+/// Converts `val` into an element of a table of `store`.
+fn table_item(store: &Store, val: &Val) -> Result<JsValue, RuntimeError> {
+    if let Val::ExternRef(ExternRef::Ref(_)) | Val::ExternRef(ExternRef::Other(_)) = val {
+        return Err(RuntimeError::new(
+            "tables only hold function references and null references",
+        ));
+    }
+    if !val.comes_from_same_store(store) {
+        return Err(RuntimeError::new("cross-`Store` values are not supported"));
+    }
+    val_to_js(val)
+}
+
+impl Table {
+    /// Creates a new `Table` with the provided [`TableType`] definition.
+    ///
+    /// All the elements in the table will be set to the `init` value.
+    pub fn new(store: &Store, ty: TableType, init: Val) -> Result<Self, RuntimeError> {
+        if ty.ty != ValType::FuncRef {
+            return Err(RuntimeError::new(format!(
+                "tables of `{}` are not supported by the `js` backend",
+                ty.ty
+            )));
+        }
+        let item = table_item(store, &init)?;
+        let descriptor = Object::new();
+        Reflect::set(
+            &descriptor,
+            &JsValue::from_str("element"),
+            &JsValue::from_str("anyfunc"),
+        )?;
+        Reflect::set(
+            &descriptor,
+            &JsValue::from_str("initial"),
+            &JsValue::from_f64(ty.minimum as f64),
+        )?;
+        if let Some(maximum) = ty.maximum {
+            Reflect::set(
+                &descriptor,
+                &JsValue::from_str("maximum"),
+                &JsValue::from_f64(maximum as f64),
+            )?;
+        }
+        let table = WebAssembly::Table::new(&descriptor)?;
+        for index in 0..ty.minimum {
+            table_set(&table, index, &item)?;
+        }
+        Ok(Self::from_js(store, table, ty))
+    }
+
+    /// Wraps the JavaScript table `table` of type `ty`.
+    pub(crate) fn from_js(store: &Store, table: WebAssembly::Table, ty: TableType) -> Self {
+        Self {
+            store: store.clone(),
+            ty,
+            table,
+        }
+    }
+
+    /// Returns the [`TableType`] of the `Table`.
+    pub fn ty(&self) -> &TableType {
+        &self.ty
+    }
+
+    /// Returns the [`Store`] where the `Table` belongs.
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+
+    /// Retrieves an element of the table at the provided `index`.
+    ///
+    /// Returns `None` if the `index` is out of bounds, or if the element
+    /// is a function: the JavaScript `WebAssembly` API doesn't expose
+    /// the type of a function.
+    pub fn get(&self, index: u32) -> Option<Val> {
+        let item = self.table.get(index).ok()?;
+        if item.is_null() {
+            Some(Val::ExternRef(ExternRef::Null))
+        } else {
+            None
+        }
+    }
+
+    /// Sets an element `val` in the Table at the provided `index`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `index` is out of bounds for the table,
+    /// or if `val` can't be stored in the table.
+    pub fn set(&self, index: u32, val: Val) -> Result<(), RuntimeError> {
+        let item = table_item(&self.store, &val)?;
+        table_set(&self.table, index, &item)
+    }
+
+    /// Retrieves the size of the `Table` (in elements)
+    pub fn size(&self) -> u32 {
+        self.table.length()
+    }
+
+    /// Grows the size of the `Table` by `delta`, initializating
+    /// the elements with the provided `init` value.
+    ///
+    /// It returns the previous size of the `Table` in case is able
+    /// to grow the Table successfully.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `delta` is out of bounds for the table.
+    pub fn grow(&self, delta: u32, init: Val) -> Result<u32, RuntimeError> {
+        let item = table_item(&self.store, &init)?;
+        let len = self
+            .table
+            .grow(delta)
+            .map_err(|_| RuntimeError::new(format!("failed to grow table by `{}`", delta)))?;
+        for index in len..len + delta {
+            table_set(&self.table, index, &item)?;
+        }
+        Ok(len)
+    }
+
+    /// Returns whether or not these two tables refer to the same data.
+    pub fn same(&self, other: &Self) -> bool {
+        self.table == other.table
+    }
+}
+
+/// Sets the element `index` of `table` to `item`.
+///
+/// `WebAssembly.Table.prototype.set` throws when the index is out of
+/// bounds, which a direct call would turn into a panic.
+fn table_set(table: &WebAssembly::Table, index: u32, item: &JsValue) -> Result<(), RuntimeError> {
+    let set: js_sys::Function = Reflect::get(table, &JsValue::from_str("set"))?.into();
+    set.call2(table, &JsValue::from_f64(index as f64), item)?;
+    Ok(())
+}
+
+impl StoreObject for Table {
+    fn comes_from_same_store(&self, store: &Store) -> bool {
+        Store::same(&self.store, store)
+    }
+}
+
+impl<'a> Exportable<'a> for Table {
+    fn get_self_from_extern(_extern: &'a Extern) -> Result<&'a Self, ExportError> {
+        match _extern {
+            Extern::Table(table) => Ok(table),
+            _ => Err(ExportError::IncompatibleType),
+        }
+    }
+}
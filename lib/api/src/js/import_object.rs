@@ -0,0 +1,286 @@
+//! The import module contains the implementation data structures and helper functions used to
+//! manipulate and access a wasm module's imports including memories, tables, globals, and
+//! functions.
+use crate::js::exports::Exports;
+use crate::js::externals::Extern;
+use js_sys::{Object, Reflect};
+use std::borrow::{Borrow, BorrowMut};
+use std::collections::VecDeque;
+use std::collections::{hash_map::Entry, HashMap};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use wasm_bindgen::JsValue;
+
+/// The `LikeNamespace` trait represents objects that act as a namespace for imports.
+/// For example, an `Instance` or `Namespace` could be
+/// considered namespaces that could provide imports to an instance.
+pub trait LikeNamespace {
+    /// Gets an export by name.
+    fn get_namespace_export(&self, name: &str) -> Option<Extern>;
+    /// Gets all exports in the namespace.
+    fn get_namespace_exports(&self) -> Vec<(String, Extern)>;
+}
+
+/// All of the import data used when instantiating.
+///
+/// It's suggested that you use the [`imports!`] macro
+/// instead of creating an `ImportObject` by hand.
+///
+/// [`imports!`]: macro.imports.html
+///
+/// # Usage:
+/// ```ignore
+/// use wasmer::{Exports, ImportObject, Function};
+///
+/// let mut import_object = ImportObject::new();
+/// let mut env = Exports::new();
+///
+/// env.insert("foo", Function::new_native(foo));
+/// import_object.register("env", env);
+///
+/// fn foo(n: i32) -> i32 {
+///     n
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct ImportObject {
+    map: Arc<Mutex<HashMap<String, Box<dyn LikeNamespace>>>>,
+}
+
+impl ImportObject {
+    /// Create a new `ImportObject`.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Gets an export given a module and a name
+    ///
+    /// # Usage
+    /// ```ignore
+    /// # use wasmer::{ImportObject, Instance, Namespace};
+    /// let mut import_object = ImportObject::new();
+    /// import_object.get_export("module", "name");
+    /// ```
+    pub fn get_export(&self, module: &str, name: &str) -> Option<Extern> {
+        let guard = self.map.lock().unwrap();
+        let map_ref = guard.borrow();
+        if map_ref.contains_key(module) {
+            let namespace = map_ref[module].as_ref();
+            return namespace.get_namespace_export(name);
+        }
+        None
+    }
+
+    /// Returns true if the ImportObject contains namespace with the provided name.
+    pub fn contains_namespace(&self, name: &str) -> bool {
+        self.map.lock().unwrap().borrow().contains_key(name)
+    }
+
+    /// Register anything that implements `LikeNamespace` as a namespace.
+    ///
+    /// # Usage:
+    /// ```ignore
+    /// # use wasmer::{ImportObject, Instance, Namespace};
+    /// let mut import_object = ImportObject::new();
+    ///
+    /// import_object.register("namespace0", instance);
+    /// import_object.register("namespace1", namespace);
+    /// // ...
+    /// ```
+    pub fn register<S, N>(&mut self, name: S, namespace: N) -> Option<Box<dyn LikeNamespace>>
+    where
+        S: Into<String>,
+        N: LikeNamespace + 'static,
+    {
+        let mut guard = self.map.lock().unwrap();
+        let map = guard.borrow_mut();
+
+        match map.entry(name.into()) {
+            Entry::Vacant(empty) => {
+                empty.insert(Box::new(namespace));
+                None
+            }
+            Entry::Occupied(mut occupied) => Some(occupied.insert(Box::new(namespace))),
+        }
+    }
+
+    fn get_objects(&self) -> VecDeque<((String, String), Extern)> {
+        let mut out = VecDeque::new();
+        let guard = self.map.lock().unwrap();
+        let map = guard.borrow();
+        for (name, ns) in map.iter() {
+            for (id, exp) in ns.get_namespace_exports() {
+                out.push_back(((name.clone(), id), exp));
+            }
+        }
+        out
+    }
+
+    /// Returns the JavaScript object of the imports, to instantiate a
+    /// module with the JavaScript `WebAssembly` API.
+    pub(crate) fn to_js_object(&self) -> Result<Object, JsValue> {
+        let imports = Object::new();
+        for ((module, name), export) in self.get_objects() {
+            let namespace = match Reflect::get(&imports, &JsValue::from_str(&module))? {
+                namespace if namespace.is_undefined() => {
+                    let namespace = Object::new();
+                    Reflect::set(&imports, &JsValue::from_str(&module), &namespace)?;
+                    namespace.into()
+                }
+                namespace => namespace,
+            };
+            Reflect::set(&namespace, &JsValue::from_str(&name), &export.to_js())?;
+        }
+        Ok(imports)
+    }
+}
+
+/// Iterator for an `ImportObject`'s exports.
+pub struct ImportObjectIterator {
+    elements: VecDeque<((String, String), Extern)>,
+}
+
+impl Iterator for ImportObjectIterator {
+    type Item = ((String, String), Extern);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.elements.pop_front()
+    }
+}
+
+impl IntoIterator for ImportObject {
+    type IntoIter = ImportObjectIterator;
+    type Item = ((String, String), Extern);
+
+    fn into_iter(self) -> Self::IntoIter {
+        ImportObjectIterator {
+            elements: self.get_objects(),
+        }
+    }
+}
+
+impl fmt::Debug for ImportObject {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        enum SecretOption {
+            None,
+            Some,
+        }
+
+        impl fmt::Debug for SecretOption {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                match self {
+                    Self::None => write!(f, "None"),
+                    Self::Some => write!(f, "Some(...)"),
+                }
+            }
+        }
+
+        enum SecretMap {
+            Empty,
+            Some(usize),
+        }
+
+        impl SecretMap {
+            fn new(len: usize) -> Self {
+                if len == 0 {
+                    Self::Empty
+                } else {
+                    Self::Some(len)
+                }
+            }
+        }
+
+        impl fmt::Debug for SecretMap {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                match self {
+                    Self::Empty => write!(f, "(empty)"),
+                    Self::Some(len) => write!(f, "(... {} item(s) ...)", len),
+                }
+            }
+        }
+
+        f.debug_struct("ImportObject")
+            .field(
+                "map",
+                &SecretMap::new(self.map.lock().unwrap().borrow().len()),
+            )
+            .finish()
+    }
+}
+
+impl LikeNamespace for Exports {
+    fn get_namespace_export(&self, name: &str) -> Option<Extern> {
+        self.get_extern(name).cloned()
+    }
+
+    fn get_namespace_exports(&self) -> Vec<(String, Extern)> {
+        self.iter()
+            .map(|(name, export)| (name.clone(), export.clone()))
+            .collect()
+    }
+}
+
+// The import! macro for ImportObject
+
+/// Generate an [`ImportObject`] easily with the `imports!` macro.
+///
+/// [`ImportObject`]: struct.ImportObject.html
+///
+/// # Usage
+///
+/// ```ignore
+/// # use wasmer::{Function, Store};
+/// # let store = Store::default();
+/// use wasmer::imports;
+///
+/// let import_object = imports! {
+///     "env" => {
+///         "foo" => Function::new_native(&store, foo)
+///     },
+/// };
+///
+/// fn foo(n: i32) -> i32 {
+///     n
+/// }
+/// ```
+#[macro_export]
+macro_rules! imports {
+    ( $( $ns_name:expr => $ns:tt ),* $(,)? ) => {
+        {
+            let mut import_object = $crate::ImportObject::new();
+
+            $({
+                let namespace = $crate::import_namespace!($ns);
+
+                import_object.register($ns_name, namespace);
+            })*
+
+            import_object
+        }
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! namespace {
+    ($( $import_name:expr => $import_item:expr ),* $(,)? ) => {
+        $crate::import_namespace!( { $( $import_name => $import_item, )* } )
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! import_namespace {
+    ( { $( $import_name:expr => $import_item:expr ),* $(,)? } ) => {{
+        let mut namespace = $crate::Exports::new();
+
+        $(
+            namespace.insert($import_name, $import_item);
+        )*
+
+        namespace
+    }};
+
+    ( $namespace:ident ) => {
+        $namespace
+    };
+}
@@ -0,0 +1,134 @@
+use crate::js::error::{js_error_message, RuntimeError};
+use crate::js::exports::Exports;
+use crate::js::externals::Extern;
+use crate::js::import_object::ImportObject;
+use crate::js::module::Module;
+use crate::js::store::Store;
+use js_sys::{Reflect, WebAssembly};
+use std::fmt;
+use thiserror::Error;
+use wasm_bindgen::{JsCast, JsValue};
+
+/// A WebAssembly Instance is a stateful, executable
+/// instance of a WebAssembly [`Module`].
+///
+/// Instance objects contain all the exported WebAssembly
+/// functions, memories, tables and globals that allow
+/// interacting with WebAssembly.
+///
+/// Spec: <https://webassembly.github.io/spec/core/exec/runtime.html#module-instances>
+#[derive(Clone)]
+pub struct Instance {
+    instance: WebAssembly::Instance,
+    module: Module,
+    /// The exports for an instance.
+    pub exports: Exports,
+}
+
+/// An error while instantiating a module.
+///
+/// This is not a common WebAssembly error, however
+/// we need to differentiate from a `LinkError` (an error
+/// that happens while linking, on instantiation) and a
+/// Trap that occurs when calling the WebAssembly module
+/// start function.
+#[derive(Error, Debug)]
+pub enum InstantiationError {
+    /// A linking ocurred during instantiation.
+    #[error("Link error: {0}")]
+    Link(String),
+
+    /// A runtime error occured while invoking the start function
+    #[error(transparent)]
+    Start(RuntimeError),
+}
+
+impl Instance {
+    /// Creates a new `Instance` from a WebAssembly [`Module`] and a
+    /// set of imports, an [`ImportObject`].
+    ///
+    /// ```ignore
+    /// # use wasmer::{imports, Store, Module, Global, Value, Instance};
+    /// # fn main() -> anyhow::Result<()> {
+    /// let store = Store::default();
+    /// let module = Module::new(&store, "(module)")?;
+    /// let imports = imports!{
+    ///   "host" => {
+    ///     "var" => Global::new(&store, Value::I32(2))
+    ///   }
+    /// };
+    /// let instance = Instance::new(&module, &imports)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// The function can return [`InstantiationError`]s.
+    ///
+    /// Those are, as defined by the spec:
+    ///  * Link errors that happen when plugging the imports into the instance
+    ///  * Runtime errors that happen when running the module `start` function.
+    pub fn new(module: &Module, imports: &ImportObject) -> Result<Self, InstantiationError> {
+        let imports = imports
+            .to_js_object()
+            .map_err(|error| InstantiationError::Link(js_error_message(&error)))?;
+        let instance = WebAssembly::Instance::new(&module.module, &imports).map_err(|error| {
+            // The host throws a `WebAssembly.LinkError` when the imports
+            // don't match, and the error of the start function otherwise.
+            if error.is_instance_of::<WebAssembly::LinkError>() {
+                InstantiationError::Link(js_error_message(&error))
+            } else {
+                InstantiationError::Start(error.into())
+            }
+        })?;
+
+        let store = module.store();
+        let instance_exports = instance.exports();
+        let exports = module
+            .exports()
+            .map(|export_type| {
+                let name = export_type.name();
+                let value = Reflect::get(&instance_exports, &JsValue::from_str(name))
+                    .map_err(|error| InstantiationError::Link(js_error_message(&error)))?;
+                let extern_ = Extern::from_js(store, export_type.ty(), value).ok_or_else(|| {
+                    InstantiationError::Link(format!(
+                        "the export `{}` doesn't have the type {:?}",
+                        name,
+                        export_type.ty()
+                    ))
+                })?;
+                Ok((name.to_string(), extern_))
+            })
+            .collect::<Result<Exports, InstantiationError>>()?;
+
+        Ok(Self {
+            instance,
+            module: module.clone(),
+            exports,
+        })
+    }
+
+    /// Gets the [`Module`] associated with this instance.
+    pub fn module(&self) -> &Module {
+        &self.module
+    }
+
+    /// Returns the [`Store`] where the `Instance` belongs.
+    pub fn store(&self) -> &Store {
+        self.module.store()
+    }
+
+    /// Returns the JavaScript `WebAssembly.Instance` of this instance.
+    pub fn raw(&self) -> &WebAssembly::Instance {
+        &self.instance
+    }
+}
+
+impl fmt::Debug for Instance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Instance")
+            .field("exports", &self.exports)
+            .finish()
+    }
+}
@@ -0,0 +1,35 @@
+//! The implementation of the `wasmer` API on top of the JavaScript
+//! `WebAssembly` API, through `wasm-bindgen`. It's used on the
+//! `wasm32` target with the `js` feature: the host JavaScript engine
+//! compiles and runs the modules.
+//!
+//! It covers the types shared by every embedding: the stores, the
+//! modules, the instances, the imports and exports and the externals.
+//! The features relying on the Wasmer runtime, like the native
+//! functions, the tunables, the engines or the compilers, aren't
+//! available.
+
+mod error;
+mod exports;
+mod externals;
+mod import_object;
+mod instance;
+mod module;
+mod store;
+mod types;
+
+pub use crate::js::error::{CompileError, RuntimeError};
+pub use crate::js::exports::{ExportError, Exportable, Exports, ExportsIterator};
+pub use crate::js::externals::{Extern, Function, Global, Memory, MemoryError, Table};
+pub use crate::js::import_object::{ImportObject, ImportObjectIterator, LikeNamespace};
+pub use crate::js::instance::{Instance, InstantiationError};
+pub use crate::js::module::Module;
+pub use crate::js::store::{Store, StoreObject};
+pub use crate::js::types::{
+    ExportType, ExternType, FunctionType, GlobalType, ImportType, MemoryType, Mutability,
+    TableType, Val, ValType,
+};
+pub use crate::js::types::{Val as Value, ValType as Type};
+pub use wasmer_types::{
+    Bytes, ExternRef, MemoryAccessError, Pages, WASM_MAX_PAGES, WASM_MIN_PAGES, WASM_PAGE_SIZE,
+};
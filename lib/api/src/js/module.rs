@@ -0,0 +1,328 @@
+use crate::js::error::{js_error_message, CompileError};
+use crate::js::store::Store;
+use crate::js::types::{
+    ExportType, ExternType, FunctionType, GlobalType, ImportType, MemoryType, TableType, ValType,
+};
+use js_sys::{Uint8Array, WebAssembly};
+use std::fmt;
+use std::sync::Arc;
+use wasmer_types::Pages;
+use wasmparser::{
+    ExternalKind, ImportSectionEntryType, MemoryType as WPMemoryType, NameSectionReader, Parser,
+    Payload, TypeDef,
+};
+
+/// A WebAssembly Module contains stateless WebAssembly
+/// code that has already been compiled and can be instantiated
+/// multiple times.
+///
+/// With the `js` backend, the module is compiled by the JavaScript
+/// `WebAssembly` API, and its imports and exports are read from the
+/// binary, which the API doesn't describe precisely.
+///
+/// ## Cloning a module
+///
+/// Cloning a module is cheap: it does a shallow copy of the compiled
+/// contents rather than a deep copy.
+#[derive(Clone)]
+pub struct Module {
+    store: Store,
+    pub(crate) module: WebAssembly::Module,
+    types: Arc<ModuleTypes>,
+    name: Option<String>,
+}
+
+/// The imports and exports of a module, and its name.
+#[derive(Default)]
+struct ModuleTypes {
+    name: Option<String>,
+    imports: Vec<ImportType>,
+    exports: Vec<ExportType>,
+}
+
+impl Module {
+    /// Creates a new WebAssembly Module given the configuration
+    /// in the store.
+    ///
+    /// If the provided bytes are not WebAssembly-like (start with `b"\0asm"`),
+    /// and the "wat" feature is enabled for this crate, this function will try to
+    /// to convert the bytes assuming they correspond to the WebAssembly text
+    /// format.
+    ///
+    /// ## Errors
+    ///
+    /// Creating a WebAssembly module from bytecode can result in a
+    /// [`CompileError`] if the bytes aren't a valid module, or if the
+    /// host can't compile it.
+    #[allow(unreachable_code)]
+    pub fn new(store: &Store, bytes: impl AsRef<[u8]>) -> Result<Self, CompileError> {
+        #[cfg(feature = "wat")]
+        let bytes = wat::parse_bytes(bytes.as_ref())
+            .map_err(|e| CompileError::Validate(format!("Error when converting wat: {}", e)))?;
+
+        Self::from_binary(store, bytes.as_ref())
+    }
+
+    /// Creates a new WebAssembly module from a binary.
+    ///
+    /// Opposed to [`Module::new`], this function is not compatible with
+    /// the WebAssembly text format (if the "wat" feature is enabled for
+    /// this crate).
+    pub fn from_binary(store: &Store, binary: &[u8]) -> Result<Self, CompileError> {
+        Self::validate(store, binary)?;
+        let types = ModuleTypes::parse(binary)?;
+        let module = WebAssembly::Module::new(&Uint8Array::from(binary))
+            .map_err(|error| CompileError::Codegen(js_error_message(&error)))?;
+        Ok(Self {
+            store: store.clone(),
+            module,
+            name: types.name.clone(),
+            types: Arc::new(types),
+        })
+    }
+
+    /// Validates a new WebAssembly Module given the configuration
+    /// in the Store.
+    ///
+    /// The module is validated by the host, with the WebAssembly
+    /// features it supports.
+    pub fn validate(_store: &Store, binary: &[u8]) -> Result<(), CompileError> {
+        match WebAssembly::validate(&Uint8Array::from(binary)) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(CompileError::Validate(
+                "the host doesn't consider the module valid".to_string(),
+            )),
+            Err(error) => Err(CompileError::Validate(js_error_message(&error))),
+        }
+    }
+
+    /// Returns the name of the current module.
+    ///
+    /// This name is normally set in the WebAssembly bytecode by some
+    /// compilers, but can be also overwritten using the [`Module::set_name`] method.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Sets the name of the current module.
+    /// This is normally useful for stacktraces and debugging.
+    ///
+    /// It always returns `true`: the JavaScript `WebAssembly` API
+    /// doesn't know the name of a module.
+    pub fn set_name(&mut self, name: &str) -> bool {
+        self.name = Some(name.to_string());
+        true
+    }
+
+    /// Returns an iterator over the imported types in the Module.
+    ///
+    /// The order of the imports is guaranteed to be the same as in the
+    /// WebAssembly bytecode.
+    pub fn imports(&self) -> impl ExactSizeIterator<Item = ImportType> + '_ {
+        self.types.imports.iter().cloned()
+    }
+
+    /// Returns an iterator over the exported types in the Module.
+    ///
+    /// The order of the exports is guaranteed to be the same as in the
+    /// WebAssembly bytecode.
+    pub fn exports(&self) -> impl ExactSizeIterator<Item = ExportType> + '_ {
+        self.types.exports.iter().cloned()
+    }
+
+    /// Returns the [`Store`] where the `Module` belongs.
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+}
+
+impl ModuleTypes {
+    /// Reads the imports, the exports and the name of the module
+    /// `binary`.
+    fn parse(binary: &[u8]) -> Result<Self, CompileError> {
+        let invalid =
+            |error: wasmparser::BinaryReaderError| CompileError::Validate(error.to_string());
+        let unsupported = |what: &str| CompileError::UnsupportedFeature(what.to_string());
+        let value_type = |ty: wasmparser::Type| match ty {
+            wasmparser::Type::I32 => Ok(ValType::I32),
+            wasmparser::Type::I64 => Ok(ValType::I64),
+            wasmparser::Type::F32 => Ok(ValType::F32),
+            wasmparser::Type::F64 => Ok(ValType::F64),
+            wasmparser::Type::V128 => Ok(ValType::V128),
+            wasmparser::Type::ExternRef => Ok(ValType::ExternRef),
+            wasmparser::Type::FuncRef => Ok(ValType::FuncRef),
+            ty => Err(CompileError::Validate(format!(
+                "unknown value type {:?}",
+                ty
+            ))),
+        };
+        let memory_type = |ty: WPMemoryType| match ty {
+            WPMemoryType::M32 { limits, shared } => Ok(MemoryType {
+                minimum: Pages(limits.initial),
+                maximum: limits.maximum.map(Pages),
+                shared,
+            }),
+            WPMemoryType::M64 { .. } => Err(unsupported("64-bit memories")),
+        };
+
+        let mut module = Self::default();
+        let mut signatures = vec![];
+        let mut functions = vec![];
+        let mut tables = vec![];
+        let mut memories = vec![];
+        let mut globals = vec![];
+        for payload in Parser::new(0).parse_all(binary) {
+            match payload.map_err(invalid)? {
+                Payload::TypeSection(types) => {
+                    for entry in types {
+                        match entry.map_err(invalid)? {
+                            TypeDef::Func(ty) => signatures.push(FunctionType::new(
+                                ty.params
+                                    .iter()
+                                    .map(|ty| value_type(*ty))
+                                    .collect::<Result<Vec<_>, _>>()?,
+                                ty.returns
+                                    .iter()
+                                    .map(|ty| value_type(*ty))
+                                    .collect::<Result<Vec<_>, _>>()?,
+                            )),
+                            _ => return Err(unsupported("module linking")),
+                        }
+                    }
+                }
+                Payload::ImportSection(imports) => {
+                    for entry in imports {
+                        let import = entry.map_err(invalid)?;
+                        let ty = match import.ty {
+                            ImportSectionEntryType::Function(index) => {
+                                let ty =
+                                    signatures.get(index as usize).cloned().ok_or_else(|| {
+                                        CompileError::Validate(format!("unknown type {}", index))
+                                    })?;
+                                functions.push(ty.clone());
+                                ExternType::Function(ty)
+                            }
+                            ImportSectionEntryType::Table(ty) => {
+                                let ty = TableType {
+                                    ty: value_type(ty.element_type)?,
+                                    minimum: ty.limits.initial,
+                                    maximum: ty.limits.maximum,
+                                };
+                                tables.push(ty);
+                                ExternType::Table(ty)
+                            }
+                            ImportSectionEntryType::Memory(ty) => {
+                                let ty = memory_type(ty)?;
+                                memories.push(ty);
+                                ExternType::Memory(ty)
+                            }
+                            ImportSectionEntryType::Global(ty) => {
+                                let ty = GlobalType {
+                                    ty: value_type(ty.content_type)?,
+                                    mutability: ty.mutable.into(),
+                                };
+                                globals.push(ty);
+                                ExternType::Global(ty)
+                            }
+                            ImportSectionEntryType::Module(_)
+                            | ImportSectionEntryType::Instance(_) => {
+                                return Err(unsupported("module linking"))
+                            }
+                        };
+                        module.imports.push(ImportType::new(
+                            import.module,
+                            import.field.unwrap_or_default(),
+                            ty,
+                        ));
+                    }
+                }
+                Payload::FunctionSection(entries) => {
+                    for entry in entries {
+                        let index = entry.map_err(invalid)?;
+                        functions.push(signatures.get(index as usize).cloned().ok_or_else(
+                            || CompileError::Validate(format!("unknown type {}", index)),
+                        )?);
+                    }
+                }
+                Payload::TableSection(entries) => {
+                    for entry in entries {
+                        let ty = entry.map_err(invalid)?;
+                        tables.push(TableType {
+                            ty: value_type(ty.element_type)?,
+                            minimum: ty.limits.initial,
+                            maximum: ty.limits.maximum,
+                        });
+                    }
+                }
+                Payload::MemorySection(entries) => {
+                    for entry in entries {
+                        memories.push(memory_type(entry.map_err(invalid)?)?);
+                    }
+                }
+                Payload::GlobalSection(entries) => {
+                    for entry in entries {
+                        let ty = entry.map_err(invalid)?.ty;
+                        globals.push(GlobalType {
+                            ty: value_type(ty.content_type)?,
+                            mutability: ty.mutable.into(),
+                        });
+                    }
+                }
+                Payload::ExportSection(entries) => {
+                    for entry in entries {
+                        let export = entry.map_err(invalid)?;
+                        let index = export.index as usize;
+                        let ty = match export.kind {
+                            ExternalKind::Function => {
+                                functions.get(index).cloned().map(ExternType::Function)
+                            }
+                            ExternalKind::Table => {
+                                tables.get(index).copied().map(ExternType::Table)
+                            }
+                            ExternalKind::Memory => {
+                                memories.get(index).copied().map(ExternType::Memory)
+                            }
+                            ExternalKind::Global => {
+                                globals.get(index).copied().map(ExternType::Global)
+                            }
+                            ExternalKind::Type | ExternalKind::Module | ExternalKind::Instance => {
+                                return Err(unsupported("module linking"))
+                            }
+                        }
+                        .ok_or_else(|| {
+                            CompileError::Validate(format!(
+                                "the export `{}` refers to an unknown item",
+                                export.field
+                            ))
+                        })?;
+                        module.exports.push(ExportType::new(export.field, ty));
+                    }
+                }
+                Payload::CustomSection {
+                    name: "name",
+                    data,
+                    data_offset,
+                } => {
+                    let mut names = NameSectionReader::new(data, data_offset).map_err(invalid)?;
+                    while let Ok(subsection) = names.read() {
+                        if let wasmparser::Name::Module(name) = subsection {
+                            if let Ok(name) = name.get_name() {
+                                module.name = Some(name.to_string());
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(module)
+    }
+}
+
+impl fmt::Debug for Module {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Module")
+            .field("name", &self.name())
+            .finish()
+    }
+}
@@ -0,0 +1,53 @@
+use std::fmt;
+use std::sync::Arc;
+
+/// The store represents all global state that can be manipulated by
+/// WebAssembly programs. It consists of the runtime representation
+/// of all instances of functions, tables, memories, and globals that
+/// have been allocated during the lifetime of the abstract machine.
+///
+/// With the `js` backend, the host JavaScript engine owns this state:
+/// the store only tells apart the objects created in different stores.
+///
+/// Spec: <https://webassembly.github.io/spec/core/exec/runtime.html#store>
+#[derive(Clone)]
+pub struct Store {
+    id: Arc<()>,
+}
+
+impl Store {
+    /// Creates a new `Store`.
+    pub fn new() -> Self {
+        Self { id: Arc::new(()) }
+    }
+
+    /// Checks whether two stores are identical. A store is considered
+    /// equal to another store if both have the same identity.
+    pub fn same(a: &Self, b: &Self) -> bool {
+        Arc::ptr_eq(&a.id, &b.id)
+    }
+}
+
+impl PartialEq for Store {
+    fn eq(&self, other: &Self) -> bool {
+        Self::same(self, other)
+    }
+}
+
+impl Default for Store {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Store {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Store").finish()
+    }
+}
+
+/// A trait representing any object that lives in the `Store`.
+pub trait StoreObject {
+    /// Return true if the object `Store` is the same as the provided `Store`.
+    fn comes_from_same_store(&self, store: &Store) -> bool;
+}
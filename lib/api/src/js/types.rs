@@ -0,0 +1,104 @@
+use crate::js::error::RuntimeError;
+use crate::js::externals::Function;
+use crate::js::store::{Store, StoreObject};
+use wasm_bindgen::{JsCast, JsValue};
+pub use wasmer_types::{
+    ExportType, ExternType, FunctionType, GlobalType, ImportType, MemoryType, Mutability,
+    TableType, Type as ValType,
+};
+use wasmer_types::{ExternRef, Value};
+
+/// WebAssembly computations manipulate values of basic value types:
+/// * Integers (32 or 64 bit width)
+/// * Floating-point (32 or 64 bit width)
+/// * Vectors (128 bits, with 32 or 64 bit lanes)
+///
+/// Spec: <https://webassembly.github.io/spec/core/exec/runtime.html#values>
+pub type Val = Value<Function>;
+
+impl StoreObject for Val {
+    fn comes_from_same_store(&self, store: &Store) -> bool {
+        match self {
+            Self::FuncRef(f) => Store::same(store, f.store()),
+            Self::ExternRef(ExternRef::Ref(_)) | Self::ExternRef(ExternRef::Other(_)) => false,
+            Self::ExternRef(ExternRef::Null) => true,
+            Self::I32(_) | Self::I64(_) | Self::F32(_) | Self::F64(_) | Self::V128(_) => true,
+        }
+    }
+}
+
+impl From<Function> for Val {
+    fn from(val: Function) -> Self {
+        Self::FuncRef(val)
+    }
+}
+
+/// Calls the global JavaScript function `name`, like `BigInt`, with
+/// `arg`.
+fn call_global(name: &str, arg: &JsValue) -> Result<JsValue, RuntimeError> {
+    let function = js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str(name))?;
+    let function = function
+        .dyn_into::<js_sys::Function>()
+        .map_err(|_| RuntimeError::new(format!("`{}` isn't a function of the host", name)))?;
+    Ok(function.call1(&JsValue::UNDEFINED, arg)?)
+}
+
+/// Converts `val` into the JavaScript value the host passes to
+/// WebAssembly.
+///
+/// The `i64` values are JavaScript `BigInt`s. The `v128` values can't
+/// cross the JavaScript boundary, and only the null `externref` can.
+pub(crate) fn val_to_js(val: &Val) -> Result<JsValue, RuntimeError> {
+    Ok(match val {
+        Val::I32(value) => JsValue::from_f64(*value as f64),
+        Val::I64(value) => call_global("BigInt", &JsValue::from_str(&value.to_string()))?,
+        Val::F32(value) => JsValue::from_f64(*value as f64),
+        Val::F64(value) => JsValue::from_f64(*value),
+        Val::FuncRef(function) => function.function.clone().into(),
+        Val::ExternRef(ExternRef::Null) => JsValue::NULL,
+        Val::ExternRef(_) => {
+            return Err(RuntimeError::new(
+                "only the null `externref` is supported by the `js` backend",
+            ))
+        }
+        Val::V128(_) => {
+            return Err(RuntimeError::new(
+                "`v128` values can't be passed to the JavaScript `WebAssembly` API",
+            ))
+        }
+    })
+}
+
+/// Converts the JavaScript value `value` returned by WebAssembly into
+/// a value of type `ty`.
+pub(crate) fn val_from_js(ty: ValType, value: &JsValue) -> Result<Val, RuntimeError> {
+    let number = || {
+        value
+            .as_f64()
+            .ok_or_else(|| RuntimeError::new(format!("expected a number, got {:?}", value)))
+    };
+    Ok(match ty {
+        ValType::I32 => Val::I32(number()? as i32),
+        ValType::I64 => {
+            let string = call_global("String", value)?
+                .as_string()
+                .unwrap_or_default();
+            Val::I64(
+                string.parse().map_err(|_| {
+                    RuntimeError::new(format!("expected a `BigInt`, got {:?}", value))
+                })?,
+            )
+        }
+        ValType::F32 => Val::F32(number()? as f32),
+        ValType::F64 => Val::F64(number()?),
+        ValType::ExternRef if value.is_null() => Val::ExternRef(ExternRef::Null),
+        // The JavaScript `WebAssembly` API doesn't expose the type of a
+        // function, so the `funcref`s can't be received.
+        ValType::ExternRef | ValType::FuncRef | ValType::V128 => {
+            return Err(RuntimeError::new(format!(
+                "`{}` values can't be received from the JavaScript `WebAssembly` API",
+                ty
+            )))
+        }
+    })
+}
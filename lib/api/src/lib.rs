@@ -221,6 +221,11 @@
 //! - `llvm` - enable Wasmer's LLVM compiler. (See [wasmer-llvm][])
 //! - `singlepass` - enable Wasmer's Singlepass compiler. (See [wasmer-singlepass][])
 //! - `wat` - enable `wasmer` to parse the WebAssembly text format.
+//! - `js` - run on the JavaScript `WebAssembly` API, through
+//!   `wasm-bindgen`, rather than on the Wasmer runtime. It's required
+//!   on the `wasm32` target, e.g. `wasm32-unknown-unknown`, and only
+//!   covers the stores, modules, instances, imports, exports and
+//!   externals. Disable the default features along with it.
 //!
 //! The features that set defaults come in sets that are mutually exclusive.
 //!
@@ -253,120 +258,34 @@
 //! [wasmer-llvm]: https://docs.rs/wasmer-llvm/*/wasmer_llvm/
 //! [wasmer-wasi]: https://docs.rs/wasmer-wasi/*/wasmer_wasi/
 
-mod env;
-mod exports;
-mod externals;
-mod import_object;
-mod instance;
-mod module;
-mod native;
-mod ptr;
-mod store;
-mod tunables;
-mod types;
-mod utils;
-
-/// Implement [`WasmerEnv`] for your type with `#[derive(WasmerEnv)]`.
-///
-/// See the [`WasmerEnv`] trait for more information.
-pub use wasmer_derive::WasmerEnv;
-
-#[doc(hidden)]
-pub mod internals {
-    //! We use the internals module for exporting types that are only
-    //! intended to use in internal crates such as the compatibility crate
-    //! `wasmer-vm`. Please don't use any of this types directly, as
-    //! they might change frequently or be removed in the future.
-
-    #[cfg(feature = "deprecated")]
-    pub use crate::externals::{UnsafeMutableEnv, WithUnsafeMutableEnv};
-    pub use crate::externals::{WithEnv, WithoutEnv};
-}
-
-pub use crate::env::{HostEnvInitError, LazyInit, WasmerEnv};
-pub use crate::exports::{ExportError, Exportable, Exports, ExportsIterator};
-pub use crate::externals::{
-    Extern, FromToNativeWasmType, Function, Global, HostFunction, Memory, Table, WasmTypeList,
-};
-pub use crate::import_object::{ImportObject, ImportObjectIterator, LikeNamespace};
-pub use crate::instance::{Instance, InstantiationError};
-pub use crate::module::Module;
-pub use crate::native::NativeFunc;
-pub use crate::ptr::{Array, Item, WasmPtr};
-pub use crate::store::{Store, StoreObject};
-pub use crate::tunables::BaseTunables;
-pub use crate::types::{
-    ExportType, ExternRef, ExternType, FunctionType, GlobalType, HostInfo, HostRef, ImportType,
-    MemoryType, Mutability, TableType, Val, ValType,
-};
-pub use crate::types::{Val as Value, ValType as Type};
-pub use crate::utils::is_wasm;
-pub use target_lexicon::{Architecture, CallingConvention, OperatingSystem, Triple, HOST};
-#[cfg(feature = "compiler")]
-pub use wasmer_compiler::{
-    wasmparser, CompilerConfig, FunctionMiddleware, MiddlewareError, MiddlewareReaderState,
-    ModuleMiddleware,
-};
-pub use wasmer_compiler::{
-    CompileError, CpuFeature, Features, ParseCpuFeatureError, Target, WasmError, WasmResult,
-};
-pub use wasmer_engine::{
-    ChainableNamedResolver, DeserializeError, Engine, Export, FrameInfo, LinkError, NamedResolver,
-    NamedResolverChain, Resolver, RuntimeError, SerializeError, Tunables,
-};
-pub use wasmer_types::{
-    Atomically, Bytes, ExportIndex, GlobalInit, LocalFunctionIndex, MemoryView, Pages, ValueType,
-    WASM_MAX_PAGES, WASM_MIN_PAGES, WASM_PAGE_SIZE,
-};
-
-// TODO: should those be moved into wasmer::vm as well?
-pub use wasmer_vm::{raise_user_trap, MemoryError, VMExport};
-pub mod vm {
-    //! The vm module re-exports wasmer-vm types.
-
-    pub use wasmer_vm::{
-        catch_traps, Memory, MemoryError, MemoryStyle, Table, TableStyle, VMFunctionEnvironment,
-        VMMemoryDefinition, VMTableDefinition,
-    };
-}
-
-#[cfg(feature = "wat")]
-pub use wat::parse_bytes as wat2wasm;
+#[cfg(all(feature = "js", not(target_arch = "wasm32")))]
+compile_error!(
+    "The `js` feature is only available on the `wasm32` target, e.g. `wasm32-unknown-unknown`."
+);
 
-// The compilers are mutually exclusive
-#[cfg(any(
-    all(
-        feature = "default-llvm",
-        any(feature = "default-cranelift", feature = "default-singlepass")
-    ),
-    all(feature = "default-cranelift", feature = "default-singlepass")
-))]
+#[cfg(all(not(feature = "js"), target_arch = "wasm32"))]
 compile_error!(
-    r#"The `default-singlepass`, `default-cranelift` and `default-llvm` features are mutually exclusive.
-If you wish to use more than one compiler, you can simply create the own store. Eg.:
+    "The Wasmer runtime doesn't run on the `wasm32` target: enable the `js` feature to run on the JavaScript `WebAssembly` API instead."
+);
 
-```
-use wasmer::{Store, JIT, Singlepass};
+mod utils;
 
-let engine = JIT::new(Singlepass::default()).engine();
-let store = Store::new(&engine);
-```"#
-);
+#[cfg(not(feature = "js"))]
+mod sys;
 
-#[cfg(feature = "singlepass")]
-pub use wasmer_compiler_singlepass::Singlepass;
+#[cfg(not(feature = "js"))]
+pub use sys::*;
 
-#[cfg(feature = "cranelift")]
-pub use wasmer_compiler_cranelift::{Cranelift, CraneliftOptLevel};
+#[cfg(feature = "js")]
+mod js;
 
-#[cfg(feature = "llvm")]
-pub use wasmer_compiler_llvm::{LLVMOptLevel, LLVM};
+#[cfg(feature = "js")]
+pub use js::*;
 
-#[cfg(feature = "jit")]
-pub use wasmer_engine_jit::{JITArtifact, JITEngine, JIT};
+pub use crate::utils::is_wasm;
 
-#[cfg(feature = "native")]
-pub use wasmer_engine_native::{Native, NativeArtifact, NativeEngine};
+#[cfg(feature = "wat")]
+pub use wat::parse_bytes as wat2wasm;
 
 /// Version number of this crate.
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
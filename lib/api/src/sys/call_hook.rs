@@ -0,0 +1,331 @@
+//! Hooks reporting the calls between the host and WebAssembly.
+//!
+//! The hook of a [`Store`] is reported the calls of the exported
+//! WebAssembly functions made from the host, and the calls of the host
+//! functions made from WebAssembly while the store is executing. It
+//! doesn't depend on the compiler, as it's run by the functions of the
+//! API entering and leaving WebAssembly.
+//!
+//! [`Store`]: crate::Store
+
+use crate::sys::externals::WasmTypeList;
+use crate::sys::labels::Labels;
+use crate::{Val, ValType};
+use std::cell::RefCell;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use wasmer_engine::FRAME_INFO;
+
+/// The transition between the host and WebAssembly reported to a
+/// [`CallHook`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallHookKind {
+    /// The host is calling a WebAssembly function.
+    CallingWasm,
+    /// A WebAssembly function called by the host returned.
+    ReturningFromWasm,
+    /// WebAssembly is calling a host function.
+    CallingHost,
+    /// A host function called by WebAssembly returned.
+    ReturningFromHost,
+}
+
+/// A call transition reported to a [`CallHook`].
+#[derive(Debug)]
+pub struct CallHookEvent<'a> {
+    kind: CallHookKind,
+    function: usize,
+    name: Option<&'a str>,
+    values: Option<&'a [Val]>,
+    labels: &'a Labels,
+}
+
+impl<'a> CallHookEvent<'a> {
+    /// The kind of transition.
+    pub fn kind(&self) -> CallHookKind {
+        self.kind
+    }
+
+    /// An opaque identifier of the called function: the address of
+    /// its code, or of its environment for the dynamic host functions
+    /// (created with [`Function::new`] or
+    /// [`Function::new_with_env`]).
+    ///
+    /// [`Function::new`]: crate::Function::new
+    /// [`Function::new_with_env`]: crate::Function::new_with_env
+    pub fn function(&self) -> usize {
+        self.function
+    }
+
+    /// The name of the called function, if known: the name of the
+    /// WebAssembly functions (see [`FrameInfo::function_name`]), or
+    /// the Rust type name of the native host functions.
+    ///
+    /// [`FrameInfo::function_name`]: crate::FrameInfo::function_name
+    pub fn name(&self) -> Option<&str> {
+        self.name
+    }
+
+    /// The arguments of the call, or its results when returning, if
+    /// the hook captures them. There are no results when the call
+    /// failed.
+    pub fn values(&self) -> Option<&[Val]> {
+        self.values
+    }
+
+    /// The labels of the instance of the WebAssembly function called
+    /// from the host, or of its store for the host functions. The
+    /// host functions called from WebAssembly get the labels of the
+    /// call from the host in progress.
+    ///
+    /// See [`Instance::labels`].
+    ///
+    /// [`Instance::labels`]: crate::Instance::labels
+    pub fn labels(&self) -> &Labels {
+        self.labels
+    }
+}
+
+/// A hook reported the calls between the host and WebAssembly, set
+/// with [`Store::set_call_hook`].
+///
+/// [`Store::set_call_hook`]: crate::Store::set_call_hook
+#[derive(Clone)]
+pub struct CallHook {
+    #[allow(clippy::type_complexity)]
+    func: Arc<dyn Fn(&CallHookEvent) + Send + Sync>,
+    capture_values: bool,
+    labels: Arc<Labels>,
+}
+
+impl CallHook {
+    /// Creates a hook running `func` for every transition.
+    pub fn new<F>(func: F) -> Self
+    where
+        F: Fn(&CallHookEvent) + Send + Sync + 'static,
+    {
+        Self {
+            func: Arc::new(func),
+            capture_values: false,
+            labels: Default::default(),
+        }
+    }
+
+    /// Whether the arguments and results of the calls are passed to
+    /// the hook. It's disabled by default, as they have to be copied.
+    pub fn capture_values(mut self, capture_values: bool) -> Self {
+        self.capture_values = capture_values;
+        self
+    }
+
+    /// The hook reporting the events with `labels`.
+    pub(crate) fn labeled(mut self, labels: Labels) -> Self {
+        self.labels = Arc::new(labels);
+        self
+    }
+
+    fn report(
+        &self,
+        kind: CallHookKind,
+        function: usize,
+        name: Option<&str>,
+        values: impl FnOnce() -> Option<Vec<Val>>,
+    ) {
+        let values = if self.capture_values { values() } else { None };
+        (self.func)(&CallHookEvent {
+            kind,
+            function,
+            name,
+            values: values.as_deref(),
+            labels: &self.labels,
+        })
+    }
+
+    /// Reports the call of the WebAssembly function at `address` from
+    /// the host.
+    pub(crate) fn calling_wasm(
+        &self,
+        address: usize,
+        params: impl FnOnce() -> Vec<Val>,
+    ) -> WasmCall {
+        let name = FRAME_INFO.read().unwrap().lookup_function_name(address);
+        self.report(CallHookKind::CallingWasm, address, name.as_deref(), || {
+            Some(params())
+        });
+        WasmCall {
+            hook: self.clone(),
+            address,
+            name,
+        }
+    }
+}
+
+/// A call of a WebAssembly function in progress, see
+/// [`CallHook::calling_wasm`].
+pub(crate) struct WasmCall {
+    hook: CallHook,
+    address: usize,
+    name: Option<String>,
+}
+
+impl WasmCall {
+    /// Reports the return of the call, with its results.
+    pub(crate) fn returned(self, results: impl FnOnce() -> Vec<Val>) {
+        self.hook.report(
+            CallHookKind::ReturningFromWasm,
+            self.address,
+            self.name.as_deref(),
+            || Some(results()),
+        );
+    }
+
+    /// Reports the return of the call, which failed.
+    pub(crate) fn failed(self) {
+        self.hook.report(
+            CallHookKind::ReturningFromWasm,
+            self.address,
+            self.name.as_deref(),
+            || None,
+        );
+    }
+}
+
+impl fmt::Debug for CallHook {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CallHook")
+            .field("capture_values", &self.capture_values)
+            .finish()
+    }
+}
+
+#[derive(Default)]
+struct CallHookState {
+    /// Whether `hook` is set, read without locking it on every call.
+    is_set: AtomicBool,
+    hook: RwLock<Option<CallHook>>,
+}
+
+/// The hook of a store, shared by its clones.
+#[derive(Clone, Default)]
+pub(crate) struct CallHookSlot(Arc<CallHookState>);
+
+impl CallHookSlot {
+    pub(crate) fn get(&self) -> Option<CallHook> {
+        if !self.0.is_set.load(Ordering::Acquire) {
+            return None;
+        }
+        self.0.hook.read().unwrap().clone()
+    }
+
+    pub(crate) fn set(&self, hook: Option<CallHook>) {
+        let mut current = self.0.hook.write().unwrap();
+        self.0.is_set.store(hook.is_some(), Ordering::Release);
+        *current = hook;
+    }
+}
+
+thread_local! {
+    /// The hook of the store executing on this thread.
+    static CURRENT_HOOK: RefCell<Option<CallHook>> = RefCell::new(None);
+}
+
+/// Makes a hook the one reported the host calls made on this thread,
+/// until it's dropped.
+pub(crate) struct CallHookScope {
+    /// The hook to restore, if the scope replaced it.
+    previous: Option<Option<CallHook>>,
+}
+
+impl CallHookScope {
+    pub(crate) fn enter(hook: Option<CallHook>) -> Self {
+        let previous = CURRENT_HOOK.with(|current| {
+            // Without hooks, the calls leave the thread untouched.
+            if hook.is_none() && current.borrow().is_none() {
+                None
+            } else {
+                Some(current.replace(hook))
+            }
+        });
+        Self { previous }
+    }
+}
+
+impl Drop for CallHookScope {
+    fn drop(&mut self) {
+        if let Some(previous) = self.previous.take() {
+            CURRENT_HOOK.with(|current| *current.borrow_mut() = previous);
+        }
+    }
+}
+
+/// Reports the call of the native host function `Func` from
+/// WebAssembly, run by `call`, to the hook of the current store.
+pub(crate) fn native_host_call<Func, Rets, E>(
+    function: usize,
+    args: impl FnOnce() -> Vec<Val>,
+    call: impl FnOnce() -> Result<Rets, E>,
+) -> Result<Rets, E>
+where
+    Rets: WasmTypeList,
+{
+    let hook = match CURRENT_HOOK.with(|current| current.borrow().clone()) {
+        Some(hook) => hook,
+        None => return call(),
+    };
+    let name = std::any::type_name::<Func>();
+    hook.report(CallHookKind::CallingHost, function, Some(name), || {
+        Some(args())
+    });
+    match call() {
+        Ok(rets) => {
+            let mut array = rets.into_array();
+            hook.report(
+                CallHookKind::ReturningFromHost,
+                function,
+                Some(name),
+                || Some(values_from_binaries(array.as_mut(), Rets::wasm_types())),
+            );
+            Ok(Rets::from_array(array))
+        }
+        Err(e) => {
+            hook.report(
+                CallHookKind::ReturningFromHost,
+                function,
+                Some(name),
+                || None,
+            );
+            Err(e)
+        }
+    }
+}
+
+/// Reports the call of a dynamic host function from WebAssembly, run
+/// by `call`, to the hook of the current store.
+pub(crate) fn dynamic_host_call<E>(
+    function: usize,
+    args: &[Val],
+    call: impl FnOnce() -> Result<Vec<Val>, E>,
+) -> Result<Vec<Val>, E> {
+    let hook = match CURRENT_HOOK.with(|current| current.borrow().clone()) {
+        Some(hook) => hook,
+        None => return call(),
+    };
+    hook.report(CallHookKind::CallingHost, function, None, || {
+        Some(args.to_vec())
+    });
+    let result = call();
+    hook.report(CallHookKind::ReturningFromHost, function, None, || {
+        result.as_ref().ok().cloned()
+    });
+    result
+}
+
+/// Reads values stored in their binary form.
+pub(crate) fn values_from_binaries(binaries: &[i128], types: &[ValType]) -> Vec<Val> {
+    binaries
+        .iter()
+        .zip(types)
+        .map(|(binary, ty)| unsafe { Val::read_value_from(binary, *ty) })
+        .collect()
+}
@@ -0,0 +1,900 @@
+//! The canonical ABI: how the values of the component model are
+//! lifted from and lowered to the core values and the memory of a
+//! core instance.
+
+use super::values::ComponentVal;
+use crate::{Function, Memory, RuntimeError, Val, ValType};
+use std::convert::TryInto;
+use wasmer_compiler::component::{ComponentFuncType, ComponentValType};
+
+/// The maximum number of core parameters of a function: more
+/// parameters are passed in memory.
+const MAX_FLAT_PARAMS: usize = 16;
+
+/// The maximum number of core results of a function: more results
+/// are returned in memory.
+const MAX_FLAT_RESULTS: usize = 1;
+
+/// The cases of a variant-like type: `variant`, `enum`, `option` and
+/// `result`.
+fn cases(ty: &ComponentValType) -> Option<Vec<Option<&ComponentValType>>> {
+    Some(match ty {
+        ComponentValType::Variant(cases) => cases.iter().map(|(_, ty)| ty.as_ref()).collect(),
+        ComponentValType::Enum(names) => names.iter().map(|_| None).collect(),
+        ComponentValType::Option(ty) => vec![None, Some(&**ty)],
+        ComponentValType::Result { ok, err } => vec![ok.as_deref(), err.as_deref()],
+        _ => return None,
+    })
+}
+
+/// The fields of a record-like type: `record` and `tuple`.
+fn fields(ty: &ComponentValType) -> Option<Vec<&ComponentValType>> {
+    Some(match ty {
+        ComponentValType::Record(fields) => fields.iter().map(|(_, ty)| ty).collect(),
+        ComponentValType::Tuple(types) => types.iter().collect(),
+        _ => return None,
+    })
+}
+
+fn discriminant_size(cases: usize) -> u32 {
+    match cases {
+        0..=0xff => 1,
+        0x100..=0xffff => 2,
+        _ => 4,
+    }
+}
+
+fn flags_size(flags: usize) -> u32 {
+    match flags {
+        0..=8 => 1,
+        9..=16 => 2,
+        _ => 4 * ((flags as u32 + 31) / 32),
+    }
+}
+
+fn align_to(offset: u32, align: u32) -> u32 {
+    (offset + align - 1) / align * align
+}
+
+pub(crate) fn alignment(ty: &ComponentValType) -> u32 {
+    match ty {
+        ComponentValType::Bool | ComponentValType::S8 | ComponentValType::U8 => 1,
+        ComponentValType::S16 | ComponentValType::U16 => 2,
+        ComponentValType::S32
+        | ComponentValType::U32
+        | ComponentValType::Float32
+        | ComponentValType::Char
+        | ComponentValType::String
+        | ComponentValType::List(_)
+        | ComponentValType::Own(_)
+        | ComponentValType::Borrow(_) => 4,
+        ComponentValType::S64 | ComponentValType::U64 | ComponentValType::Float64 => 8,
+        ComponentValType::Flags(flags) => flags_size(flags.len().min(32)),
+        _ => {
+            if let Some(fields) = fields(ty) {
+                fields.into_iter().map(alignment).max().unwrap_or(1)
+            } else {
+                let cases = cases(ty).unwrap();
+                cases
+                    .iter()
+                    .flatten()
+                    .map(|ty| alignment(ty))
+                    .fold(discriminant_size(cases.len()), u32::max)
+            }
+        }
+    }
+}
+
+pub(crate) fn size(ty: &ComponentValType) -> u32 {
+    match ty {
+        ComponentValType::Bool | ComponentValType::S8 | ComponentValType::U8 => 1,
+        ComponentValType::S16 | ComponentValType::U16 => 2,
+        ComponentValType::S32
+        | ComponentValType::U32
+        | ComponentValType::Float32
+        | ComponentValType::Char
+        | ComponentValType::Own(_)
+        | ComponentValType::Borrow(_) => 4,
+        ComponentValType::S64
+        | ComponentValType::U64
+        | ComponentValType::Float64
+        | ComponentValType::String
+        | ComponentValType::List(_) => 8,
+        ComponentValType::Flags(flags) => flags_size(flags.len()),
+        _ => {
+            let size = if let Some(fields) = fields(ty) {
+                fields.into_iter().fold(0, |size, field| {
+                    align_to(size, alignment(field)) + self::size(field)
+                })
+            } else {
+                let cases = cases(ty).unwrap();
+                let payload = cases.iter().flatten().map(|ty| self::size(ty)).max();
+                align_to(discriminant_size(cases.len()), payload_alignment(ty))
+                    + payload.unwrap_or(0)
+            };
+            align_to(size, alignment(ty))
+        }
+    }
+}
+
+/// The alignment of the payloads of a variant-like type.
+fn payload_alignment(ty: &ComponentValType) -> u32 {
+    cases(ty)
+        .unwrap()
+        .into_iter()
+        .flatten()
+        .map(alignment)
+        .max()
+        .unwrap_or(1)
+}
+
+fn join(a: ValType, b: ValType) -> ValType {
+    match (a, b) {
+        _ if a == b => a,
+        (ValType::I32, ValType::F32) | (ValType::F32, ValType::I32) => ValType::I32,
+        _ => ValType::I64,
+    }
+}
+
+pub(crate) fn flatten(ty: &ComponentValType, out: &mut Vec<ValType>) {
+    match ty {
+        ComponentValType::Bool
+        | ComponentValType::S8
+        | ComponentValType::U8
+        | ComponentValType::S16
+        | ComponentValType::U16
+        | ComponentValType::S32
+        | ComponentValType::U32
+        | ComponentValType::Char
+        | ComponentValType::Own(_)
+        | ComponentValType::Borrow(_) => out.push(ValType::I32),
+        ComponentValType::S64 | ComponentValType::U64 => out.push(ValType::I64),
+        ComponentValType::Float32 => out.push(ValType::F32),
+        ComponentValType::Float64 => out.push(ValType::F64),
+        ComponentValType::String | ComponentValType::List(_) => {
+            out.extend(&[ValType::I32, ValType::I32])
+        }
+        ComponentValType::Flags(flags) => {
+            out.extend(std::iter::repeat(ValType::I32).take((flags.len() + 31) / 32))
+        }
+        _ => {
+            if let Some(fields) = fields(ty) {
+                fields.into_iter().for_each(|field| flatten(field, out));
+            } else {
+                out.push(ValType::I32);
+                out.extend(variant_payload_types(ty));
+            }
+        }
+    }
+}
+
+/// The core types of the payloads of a variant-like type, joined.
+fn variant_payload_types(ty: &ComponentValType) -> Vec<ValType> {
+    let mut joined: Vec<ValType> = Vec::new();
+    for case in cases(ty).unwrap().into_iter().flatten() {
+        let mut types = Vec::new();
+        flatten(case, &mut types);
+        for (index, ty) in types.into_iter().enumerate() {
+            match joined.get_mut(index) {
+                Some(joined) => *joined = join(*joined, ty),
+                None => joined.push(ty),
+            }
+        }
+    }
+    joined
+}
+
+fn flatten_all(types: &[ComponentValType]) -> Vec<ValType> {
+    let mut out = Vec::new();
+    types.iter().for_each(|ty| flatten(ty, &mut out));
+    out
+}
+
+/// Returns the core parameters and results of a function of type
+/// `ty`, lifted from a core function if `lift`, or lowered to a core
+/// function.
+pub(crate) fn core_signature(ty: &ComponentFuncType, lift: bool) -> (Vec<ValType>, Vec<ValType>) {
+    let param_types = ty
+        .params
+        .iter()
+        .map(|(_, ty)| ty.clone())
+        .collect::<Vec<_>>();
+    let mut params = flatten_all(&param_types);
+    if params.len() > MAX_FLAT_PARAMS {
+        params = vec![ValType::I32];
+    }
+    let mut results = flatten_all(&ty.results);
+    if results.len() > MAX_FLAT_RESULTS {
+        if lift {
+            results = vec![ValType::I32];
+        } else {
+            params.push(ValType::I32);
+            results = Vec::new();
+        }
+    }
+    (params, results)
+}
+
+/// Converts a core value of the joined type of the payloads to the
+/// type of a case.
+fn unjoin(val: Val, ty: ValType) -> Result<Val, RuntimeError> {
+    Ok(match (val, ty) {
+        (Val::I32(x), ValType::I32) => Val::I32(x),
+        (Val::I64(x), ValType::I64) => Val::I64(x),
+        (Val::F32(x), ValType::F32) => Val::F32(x),
+        (Val::F64(x), ValType::F64) => Val::F64(x),
+        (Val::I64(x), ValType::I32) => Val::I32(x as i32),
+        (Val::I32(x), ValType::F32) => Val::F32(f32::from_bits(x as u32)),
+        (Val::I64(x), ValType::F32) => Val::F32(f32::from_bits(x as u32)),
+        (Val::I64(x), ValType::F64) => Val::F64(f64::from_bits(x as u64)),
+        _ => return Err(RuntimeError::new("invalid core value of a variant")),
+    })
+}
+
+/// Converts a core value of the type of a case to the joined type of
+/// the payloads.
+fn join_val(val: Val, ty: ValType) -> Val {
+    match (val, ty) {
+        (Val::I32(x), ValType::I64) => Val::I64(i64::from(x as u32)),
+        (Val::F32(x), ValType::I32) => Val::I32(x.to_bits() as i32),
+        (Val::F32(x), ValType::I64) => Val::I64(i64::from(x.to_bits())),
+        (Val::F64(x), ValType::I64) => Val::I64(x.to_bits() as i64),
+        (val, _) => val,
+    }
+}
+
+fn zero(ty: ValType) -> Val {
+    match ty {
+        ValType::I64 => Val::I64(0),
+        ValType::F32 => Val::F32(0.0),
+        ValType::F64 => Val::F64(0.0),
+        _ => Val::I32(0),
+    }
+}
+
+fn mismatch(ty: &ComponentValType, val: &ComponentVal) -> RuntimeError {
+    RuntimeError::new(format!("expected a value of type `{}`, got {:?}", ty, val))
+}
+
+fn out_of_bounds() -> RuntimeError {
+    RuntimeError::new("out of bounds memory access")
+}
+
+fn i32_of(val: Option<Val>) -> Result<i32, RuntimeError> {
+    match val {
+        Some(Val::I32(x)) => Ok(x),
+        _ => Err(RuntimeError::new("expected an `i32` core value")),
+    }
+}
+
+/// The memory and the allocator of a core instance, with which the
+/// values are lifted and lowered.
+#[derive(Clone, Default)]
+pub(crate) struct Abi {
+    pub(crate) memory: Option<Memory>,
+    pub(crate) realloc: Option<Function>,
+}
+
+impl Abi {
+    fn memory(&self) -> Result<&Memory, RuntimeError> {
+        self.memory
+            .as_ref()
+            .ok_or_else(|| RuntimeError::new("the canonical options have no memory"))
+    }
+
+    fn read<const N: usize>(&self, ptr: u32) -> Result<[u8; N], RuntimeError> {
+        let mut bytes = [0; N];
+        self.memory()?
+            .read(ptr.into(), &mut bytes)
+            .map_err(|_| out_of_bounds())?;
+        Ok(bytes)
+    }
+
+    fn write(&self, ptr: u32, bytes: &[u8]) -> Result<(), RuntimeError> {
+        self.memory()?
+            .write(ptr.into(), bytes)
+            .map_err(|_| out_of_bounds())
+    }
+
+    fn alloc(&self, align: u32, size: u32) -> Result<u32, RuntimeError> {
+        let realloc = self
+            .realloc
+            .as_ref()
+            .ok_or_else(|| RuntimeError::new("the canonical options have no `realloc`"))?;
+        let results = realloc.call(&[
+            Val::I32(0),
+            Val::I32(0),
+            Val::I32(align as i32),
+            Val::I32(size as i32),
+        ])?;
+        let ptr = i32_of(results.get(0).cloned())? as u32;
+        if ptr % align != 0 {
+            return Err(RuntimeError::new("`realloc` returned an unaligned pointer"));
+        }
+        Ok(ptr)
+    }
+
+    /// Loads a value of type `ty` stored at `ptr`.
+    fn load(&self, ty: &ComponentValType, ptr: u32) -> Result<ComponentVal, RuntimeError> {
+        if ptr % alignment(ty) != 0 {
+            return Err(RuntimeError::new("unaligned pointer"));
+        }
+        let u32_at = |ptr| self.read::<4>(ptr).map(u32::from_le_bytes);
+        Ok(match ty {
+            ComponentValType::Bool => ComponentVal::Bool(self.read::<1>(ptr)?[0] != 0),
+            ComponentValType::S8 => ComponentVal::S8(self.read::<1>(ptr)?[0] as i8),
+            ComponentValType::U8 => ComponentVal::U8(self.read::<1>(ptr)?[0]),
+            ComponentValType::S16 => ComponentVal::S16(i16::from_le_bytes(self.read(ptr)?)),
+            ComponentValType::U16 => ComponentVal::U16(u16::from_le_bytes(self.read(ptr)?)),
+            ComponentValType::S32 => ComponentVal::S32(i32::from_le_bytes(self.read(ptr)?)),
+            ComponentValType::U32 => ComponentVal::U32(u32_at(ptr)?),
+            ComponentValType::S64 => ComponentVal::S64(i64::from_le_bytes(self.read(ptr)?)),
+            ComponentValType::U64 => ComponentVal::U64(u64::from_le_bytes(self.read(ptr)?)),
+            ComponentValType::Float32 => ComponentVal::Float32(f32::from_le_bytes(self.read(ptr)?)),
+            ComponentValType::Float64 => ComponentVal::Float64(f64::from_le_bytes(self.read(ptr)?)),
+            ComponentValType::Char => ComponentVal::Char(Self::char(u32_at(ptr)?)?),
+            ComponentValType::Own(_) => ComponentVal::Own(u32_at(ptr)?),
+            ComponentValType::Borrow(_) => ComponentVal::Borrow(u32_at(ptr)?),
+            ComponentValType::String => self.load_string(u32_at(ptr)?, u32_at(ptr + 4)?)?,
+            ComponentValType::List(element) => {
+                self.load_list(element, u32_at(ptr)?, u32_at(ptr + 4)?)?
+            }
+            ComponentValType::Flags(flags) => {
+                let mut bytes = vec![0; flags_size(flags.len()) as usize];
+                self.memory()?
+                    .read(ptr.into(), &mut bytes)
+                    .map_err(|_| out_of_bounds())?;
+                bytes.resize((bytes.len() + 3) / 4 * 4, 0);
+                let bits = bytes
+                    .chunks(4)
+                    .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+                    .collect::<Vec<_>>();
+                Self::lift_flags(flags, &bits)
+            }
+            _ => {
+                if let Some(fields) = fields(ty) {
+                    let mut offset = ptr;
+                    let mut values = Vec::new();
+                    for field in fields {
+                        offset = align_to(offset, alignment(field));
+                        values.push(self.load(field, offset)?);
+                        offset += size(field);
+                    }
+                    Self::make_record(ty, values)
+                } else {
+                    let cases = cases(ty).unwrap();
+                    let case = match discriminant_size(cases.len()) {
+                        1 => u32::from(self.read::<1>(ptr)?[0]),
+                        2 => u32::from(u16::from_le_bytes(self.read(ptr)?)),
+                        _ => u32_at(ptr)?,
+                    };
+                    let payload_ty = cases
+                        .get(case as usize)
+                        .ok_or_else(|| RuntimeError::new("invalid variant discriminant"))?;
+                    let payload_ptr =
+                        ptr + align_to(discriminant_size(cases.len()), payload_alignment(ty));
+                    let payload = match payload_ty {
+                        Some(payload_ty) => Some(self.load(payload_ty, payload_ptr)?),
+                        None => None,
+                    };
+                    Self::make_variant(ty, case, payload)
+                }
+            }
+        })
+    }
+
+    fn char(code: u32) -> Result<char, RuntimeError> {
+        std::char::from_u32(code).ok_or_else(|| RuntimeError::new("invalid `char`"))
+    }
+
+    fn load_string(&self, ptr: u32, len: u32) -> Result<ComponentVal, RuntimeError> {
+        let mut bytes = vec![0; len as usize];
+        self.memory()?
+            .read(ptr.into(), &mut bytes)
+            .map_err(|_| out_of_bounds())?;
+        String::from_utf8(bytes)
+            .map(ComponentVal::String)
+            .map_err(|_| RuntimeError::new("invalid UTF-8 string"))
+    }
+
+    fn load_list(
+        &self,
+        element: &ComponentValType,
+        ptr: u32,
+        len: u32,
+    ) -> Result<ComponentVal, RuntimeError> {
+        let element_size = size(element);
+        if u64::from(element_size) * u64::from(len) + u64::from(ptr) > self.memory()?.data_size() {
+            return Err(out_of_bounds());
+        }
+        (0..len)
+            .map(|index| self.load(element, ptr + index * element_size))
+            .collect::<Result<_, _>>()
+            .map(ComponentVal::List)
+    }
+
+    /// Stores `val` of type `ty` at `ptr`.
+    fn store(
+        &self,
+        ty: &ComponentValType,
+        val: &ComponentVal,
+        ptr: u32,
+    ) -> Result<(), RuntimeError> {
+        if ptr % alignment(ty) != 0 {
+            return Err(RuntimeError::new("unaligned pointer"));
+        }
+        match (ty, val) {
+            (ComponentValType::Bool, ComponentVal::Bool(x)) => self.write(ptr, &[*x as u8]),
+            (ComponentValType::S8, ComponentVal::S8(x)) => self.write(ptr, &x.to_le_bytes()),
+            (ComponentValType::U8, ComponentVal::U8(x)) => self.write(ptr, &[*x]),
+            (ComponentValType::S16, ComponentVal::S16(x)) => self.write(ptr, &x.to_le_bytes()),
+            (ComponentValType::U16, ComponentVal::U16(x)) => self.write(ptr, &x.to_le_bytes()),
+            (ComponentValType::S32, ComponentVal::S32(x)) => self.write(ptr, &x.to_le_bytes()),
+            (ComponentValType::U32, ComponentVal::U32(x))
+            | (ComponentValType::Own(_), ComponentVal::Own(x))
+            | (ComponentValType::Borrow(_), ComponentVal::Borrow(x)) => {
+                self.write(ptr, &x.to_le_bytes())
+            }
+            (ComponentValType::S64, ComponentVal::S64(x)) => self.write(ptr, &x.to_le_bytes()),
+            (ComponentValType::U64, ComponentVal::U64(x)) => self.write(ptr, &x.to_le_bytes()),
+            (ComponentValType::Float32, ComponentVal::Float32(x)) => {
+                self.write(ptr, &x.to_le_bytes())
+            }
+            (ComponentValType::Float64, ComponentVal::Float64(x)) => {
+                self.write(ptr, &x.to_le_bytes())
+            }
+            (ComponentValType::Char, ComponentVal::Char(x)) => {
+                self.write(ptr, &(*x as u32).to_le_bytes())
+            }
+            (ComponentValType::String, ComponentVal::String(_))
+            | (ComponentValType::List(_), ComponentVal::List(_)) => {
+                let (data, len) = self.store_sequence(ty, val)?;
+                self.write(ptr, &data.to_le_bytes())?;
+                self.write(ptr + 4, &len.to_le_bytes())
+            }
+            (ComponentValType::Flags(flags), ComponentVal::Flags(_)) => {
+                let bits = Self::lower_flags(flags, val)?;
+                let size = flags_size(flags.len()) as usize;
+                let bytes = bits
+                    .iter()
+                    .flat_map(|word| word.to_le_bytes().to_vec())
+                    .take(size)
+                    .collect::<Vec<_>>();
+                self.write(ptr, &bytes)
+            }
+            _ => {
+                if let Some(fields) = fields(ty) {
+                    let values = Self::record_values(ty, val)?;
+                    let mut offset = ptr;
+                    for (field, value) in fields.into_iter().zip(values) {
+                        offset = align_to(offset, alignment(field));
+                        self.store(field, value, offset)?;
+                        offset += size(field);
+                    }
+                    Ok(())
+                } else if let Some(cases) = cases(ty) {
+                    let (case, payload) = Self::variant_case(ty, val)?;
+                    let discriminant = case.to_le_bytes();
+                    self.write(
+                        ptr,
+                        &discriminant[..discriminant_size(cases.len()) as usize],
+                    )?;
+                    let payload_ptr =
+                        ptr + align_to(discriminant_size(cases.len()), payload_alignment(ty));
+                    match (cases[case as usize], payload) {
+                        (Some(payload_ty), Some(payload)) => {
+                            self.store(payload_ty, payload, payload_ptr)
+                        }
+                        (None, None) => Ok(()),
+                        _ => Err(mismatch(ty, val)),
+                    }
+                } else {
+                    Err(mismatch(ty, val))
+                }
+            }
+        }
+    }
+
+    /// Stores the elements of a string or a list in a new allocation,
+    /// and returns its address and its length.
+    fn store_sequence(
+        &self,
+        ty: &ComponentValType,
+        val: &ComponentVal,
+    ) -> Result<(u32, u32), RuntimeError> {
+        let too_long = || RuntimeError::new("list too long");
+        match (ty, val) {
+            (ComponentValType::String, ComponentVal::String(string)) => {
+                let len: u32 = string.len().try_into().map_err(|_| too_long())?;
+                let ptr = if len == 0 { 1 } else { self.alloc(1, len)? };
+                self.write(ptr, string.as_bytes())?;
+                Ok((ptr, len))
+            }
+            (ComponentValType::List(element), ComponentVal::List(values)) => {
+                let len: u32 = values.len().try_into().map_err(|_| too_long())?;
+                let element_size = size(element);
+                let total = element_size.checked_mul(len).ok_or_else(too_long)?;
+                let ptr = if total == 0 {
+                    alignment(element)
+                } else {
+                    self.alloc(alignment(element), total)?
+                };
+                for (index, value) in values.iter().enumerate() {
+                    self.store(element, value, ptr + index as u32 * element_size)?;
+                }
+                Ok((ptr, len))
+            }
+            _ => Err(mismatch(ty, val)),
+        }
+    }
+
+    /// Lifts a value of type `ty` from the core values `flat`.
+    fn lift_flat(
+        &self,
+        ty: &ComponentValType,
+        flat: &mut impl Iterator<Item = Val>,
+    ) -> Result<ComponentVal, RuntimeError> {
+        let next = |flat: &mut dyn Iterator<Item = Val>| {
+            flat.next()
+                .ok_or_else(|| RuntimeError::new("missing core values"))
+        };
+        let i32_next = |flat: &mut dyn Iterator<Item = Val>| match next(flat)? {
+            Val::I32(x) => Ok(x),
+            _ => Err(RuntimeError::new("expected an `i32` core value")),
+        };
+        let i64_next = |flat: &mut dyn Iterator<Item = Val>| match next(flat)? {
+            Val::I64(x) => Ok(x),
+            _ => Err(RuntimeError::new("expected an `i64` core value")),
+        };
+        Ok(match ty {
+            ComponentValType::Bool => ComponentVal::Bool(i32_next(flat)? != 0),
+            ComponentValType::S8 => ComponentVal::S8(i32_next(flat)? as i8),
+            ComponentValType::U8 => ComponentVal::U8(i32_next(flat)? as u8),
+            ComponentValType::S16 => ComponentVal::S16(i32_next(flat)? as i16),
+            ComponentValType::U16 => ComponentVal::U16(i32_next(flat)? as u16),
+            ComponentValType::S32 => ComponentVal::S32(i32_next(flat)?),
+            ComponentValType::U32 => ComponentVal::U32(i32_next(flat)? as u32),
+            ComponentValType::S64 => ComponentVal::S64(i64_next(flat)?),
+            ComponentValType::U64 => ComponentVal::U64(i64_next(flat)? as u64),
+            ComponentValType::Float32 => match next(flat)? {
+                Val::F32(x) => ComponentVal::Float32(x),
+                _ => return Err(RuntimeError::new("expected an `f32` core value")),
+            },
+            ComponentValType::Float64 => match next(flat)? {
+                Val::F64(x) => ComponentVal::Float64(x),
+                _ => return Err(RuntimeError::new("expected an `f64` core value")),
+            },
+            ComponentValType::Char => ComponentVal::Char(Self::char(i32_next(flat)? as u32)?),
+            ComponentValType::Own(_) => ComponentVal::Own(i32_next(flat)? as u32),
+            ComponentValType::Borrow(_) => ComponentVal::Borrow(i32_next(flat)? as u32),
+            ComponentValType::String => {
+                let ptr = i32_next(flat)? as u32;
+                self.load_string(ptr, i32_next(flat)? as u32)?
+            }
+            ComponentValType::List(element) => {
+                let ptr = i32_next(flat)? as u32;
+                self.load_list(element, ptr, i32_next(flat)? as u32)?
+            }
+            ComponentValType::Flags(flags) => {
+                let bits = (0..(flags.len() + 31) / 32)
+                    .map(|_| i32_next(flat).map(|x| x as u32))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Self::lift_flags(flags, &bits)
+            }
+            _ => {
+                if let Some(fields) = fields(ty) {
+                    let values = fields
+                        .into_iter()
+                        .map(|field| self.lift_flat(field, flat))
+                        .collect::<Result<_, _>>()?;
+                    Self::make_record(ty, values)
+                } else {
+                    let cases = cases(ty).unwrap();
+                    let case = i32_next(flat)? as u32;
+                    let joined = variant_payload_types(ty);
+                    let payloads = (0..joined.len())
+                        .map(|_| next(flat))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    let payload_ty = cases
+                        .get(case as usize)
+                        .ok_or_else(|| RuntimeError::new("invalid variant discriminant"))?;
+                    let payload = match payload_ty {
+                        Some(payload_ty) => {
+                            let mut types = Vec::new();
+                            flatten(payload_ty, &mut types);
+                            let values = payloads
+                                .into_iter()
+                                .zip(types)
+                                .map(|(val, ty)| unjoin(val, ty))
+                                .collect::<Result<Vec<_>, _>>()?;
+                            Some(self.lift_flat(payload_ty, &mut values.into_iter())?)
+                        }
+                        None => None,
+                    };
+                    Self::make_variant(ty, case, payload)
+                }
+            }
+        })
+    }
+
+    /// Lowers `val` of type `ty` to core values, appended to `out`.
+    fn lower_flat(
+        &self,
+        ty: &ComponentValType,
+        val: &ComponentVal,
+        out: &mut Vec<Val>,
+    ) -> Result<(), RuntimeError> {
+        match (ty, val) {
+            (ComponentValType::Bool, ComponentVal::Bool(x)) => out.push(Val::I32(*x as i32)),
+            (ComponentValType::S8, ComponentVal::S8(x)) => out.push(Val::I32(i32::from(*x))),
+            (ComponentValType::U8, ComponentVal::U8(x)) => out.push(Val::I32(i32::from(*x))),
+            (ComponentValType::S16, ComponentVal::S16(x)) => out.push(Val::I32(i32::from(*x))),
+            (ComponentValType::U16, ComponentVal::U16(x)) => out.push(Val::I32(i32::from(*x))),
+            (ComponentValType::S32, ComponentVal::S32(x)) => out.push(Val::I32(*x)),
+            (ComponentValType::U32, ComponentVal::U32(x))
+            | (ComponentValType::Own(_), ComponentVal::Own(x))
+            | (ComponentValType::Borrow(_), ComponentVal::Borrow(x)) => {
+                out.push(Val::I32(*x as i32))
+            }
+            (ComponentValType::S64, ComponentVal::S64(x)) => out.push(Val::I64(*x)),
+            (ComponentValType::U64, ComponentVal::U64(x)) => out.push(Val::I64(*x as i64)),
+            (ComponentValType::Float32, ComponentVal::Float32(x)) => out.push(Val::F32(*x)),
+            (ComponentValType::Float64, ComponentVal::Float64(x)) => out.push(Val::F64(*x)),
+            (ComponentValType::Char, ComponentVal::Char(x)) => out.push(Val::I32(*x as i32)),
+            (ComponentValType::String, ComponentVal::String(_))
+            | (ComponentValType::List(_), ComponentVal::List(_)) => {
+                let (ptr, len) = self.store_sequence(ty, val)?;
+                out.push(Val::I32(ptr as i32));
+                out.push(Val::I32(len as i32));
+            }
+            (ComponentValType::Flags(flags), ComponentVal::Flags(_)) => out.extend(
+                Self::lower_flags(flags, val)?
+                    .into_iter()
+                    .map(|word| Val::I32(word as i32)),
+            ),
+            _ => {
+                if let Some(fields) = fields(ty) {
+                    let values = Self::record_values(ty, val)?;
+                    for (field, value) in fields.into_iter().zip(values) {
+                        self.lower_flat(field, value, out)?;
+                    }
+                } else if let Some(cases) = cases(ty) {
+                    let (case, payload) = Self::variant_case(ty, val)?;
+                    out.push(Val::I32(case as i32));
+                    let joined = variant_payload_types(ty);
+                    let mut values = Vec::new();
+                    match (cases[case as usize], payload) {
+                        (Some(payload_ty), Some(payload)) => {
+                            self.lower_flat(payload_ty, payload, &mut values)?
+                        }
+                        (None, None) => {}
+                        _ => return Err(mismatch(ty, val)),
+                    }
+                    let count = values.len();
+                    out.extend(
+                        values
+                            .into_iter()
+                            .zip(&joined)
+                            .map(|(val, ty)| join_val(val, *ty)),
+                    );
+                    out.extend(joined[count..].iter().map(|ty| zero(*ty)));
+                } else {
+                    return Err(mismatch(ty, val));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn lift_flags(flags: &[String], bits: &[u32]) -> ComponentVal {
+        ComponentVal::Flags(
+            flags
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| bits[index / 32] & (1 << (index % 32)) != 0)
+                .map(|(_, name)| name.clone())
+                .collect(),
+        )
+    }
+
+    fn lower_flags(flags: &[String], val: &ComponentVal) -> Result<Vec<u32>, RuntimeError> {
+        let mut bits = vec![0u32; (flags.len() + 31) / 32];
+        if let ComponentVal::Flags(names) = val {
+            for name in names {
+                let index = flags
+                    .iter()
+                    .position(|flag| flag == name)
+                    .ok_or_else(|| RuntimeError::new(format!("unknown flag `{}`", name)))?;
+                bits[index / 32] |= 1 << (index % 32);
+            }
+        }
+        Ok(bits)
+    }
+
+    fn make_record(ty: &ComponentValType, values: Vec<ComponentVal>) -> ComponentVal {
+        match ty {
+            ComponentValType::Record(fields) => ComponentVal::Record(
+                fields
+                    .iter()
+                    .map(|(name, _)| name.clone())
+                    .zip(values)
+                    .collect(),
+            ),
+            _ => ComponentVal::Tuple(values),
+        }
+    }
+
+    /// Returns the values of the fields of a record-like value, in the
+    /// order of the fields of its type.
+    fn record_values<'a>(
+        ty: &ComponentValType,
+        val: &'a ComponentVal,
+    ) -> Result<Vec<&'a ComponentVal>, RuntimeError> {
+        match (ty, val) {
+            (ComponentValType::Record(fields), ComponentVal::Record(values)) => fields
+                .iter()
+                .map(|(name, _)| {
+                    values
+                        .iter()
+                        .find(|(field, _)| field == name)
+                        .map(|(_, value)| value)
+                        .ok_or_else(|| RuntimeError::new(format!("missing field `{}`", name)))
+                })
+                .collect(),
+            (ComponentValType::Tuple(types), ComponentVal::Tuple(values))
+                if types.len() == values.len() =>
+            {
+                Ok(values.iter().collect())
+            }
+            _ => Err(mismatch(ty, val)),
+        }
+    }
+
+    fn make_variant(
+        ty: &ComponentValType,
+        case: u32,
+        payload: Option<ComponentVal>,
+    ) -> ComponentVal {
+        let payload = payload.map(Box::new);
+        match ty {
+            ComponentValType::Variant(cases) => {
+                ComponentVal::Variant(cases[case as usize].0.clone(), payload)
+            }
+            ComponentValType::Enum(names) => ComponentVal::Enum(names[case as usize].clone()),
+            ComponentValType::Option(_) => ComponentVal::Option(payload),
+            _ if case == 0 => ComponentVal::Result(Ok(payload)),
+            _ => ComponentVal::Result(Err(payload)),
+        }
+    }
+
+    /// Returns the index of the case of a variant-like value, and its
+    /// payload.
+    fn variant_case<'a>(
+        ty: &ComponentValType,
+        val: &'a ComponentVal,
+    ) -> Result<(u32, Option<&'a ComponentVal>), RuntimeError> {
+        let position = |names: &mut dyn Iterator<Item = &String>, name: &str| {
+            names
+                .position(|case| case == name)
+                .map(|case| case as u32)
+                .ok_or_else(|| RuntimeError::new(format!("unknown case `{}`", name)))
+        };
+        match (ty, val) {
+            (ComponentValType::Variant(cases), ComponentVal::Variant(name, payload)) => Ok((
+                position(&mut cases.iter().map(|(name, _)| name), name)?,
+                payload.as_deref(),
+            )),
+            (ComponentValType::Enum(names), ComponentVal::Enum(name)) => {
+                Ok((position(&mut names.iter(), name)?, None))
+            }
+            (ComponentValType::Option(_), ComponentVal::Option(None)) => Ok((0, None)),
+            (ComponentValType::Option(_), ComponentVal::Option(Some(payload))) => {
+                Ok((1, Some(payload)))
+            }
+            (ComponentValType::Result { .. }, ComponentVal::Result(Ok(payload))) => {
+                Ok((0, payload.as_deref()))
+            }
+            (ComponentValType::Result { .. }, ComponentVal::Result(Err(payload))) => {
+                Ok((1, payload.as_deref()))
+            }
+            _ => Err(mismatch(ty, val)),
+        }
+    }
+
+    /// Lowers the parameters of a call to a lifted function.
+    pub(crate) fn lower_params(
+        &self,
+        types: &[ComponentValType],
+        vals: &[ComponentVal],
+    ) -> Result<Vec<Val>, RuntimeError> {
+        if types.len() != vals.len() {
+            return Err(RuntimeError::new(format!(
+                "expected {} parameters, got {}",
+                types.len(),
+                vals.len()
+            )));
+        }
+        if flatten_all(types).len() > MAX_FLAT_PARAMS {
+            let tuple = ComponentValType::Tuple(types.to_vec());
+            let ptr = self.alloc(alignment(&tuple), size(&tuple))?;
+            self.store(&tuple, &ComponentVal::Tuple(vals.to_vec()), ptr)?;
+            return Ok(vec![Val::I32(ptr as i32)]);
+        }
+        let mut out = Vec::new();
+        for (ty, val) in types.iter().zip(vals) {
+            self.lower_flat(ty, val, &mut out)?;
+        }
+        Ok(out)
+    }
+
+    /// Lifts the results of a call to a lifted function.
+    pub(crate) fn lift_results(
+        &self,
+        types: &[ComponentValType],
+        core_results: &[Val],
+    ) -> Result<Vec<ComponentVal>, RuntimeError> {
+        if flatten_all(types).len() > MAX_FLAT_RESULTS {
+            let ptr = i32_of(core_results.get(0).cloned())? as u32;
+            return match self.load(&ComponentValType::Tuple(types.to_vec()), ptr)? {
+                ComponentVal::Tuple(values) => Ok(values),
+                _ => unreachable!(),
+            };
+        }
+        let mut flat = core_results.iter().cloned();
+        types
+            .iter()
+            .map(|ty| self.lift_flat(ty, &mut flat))
+            .collect()
+    }
+
+    /// Lifts the parameters of a call to a lowered function.
+    pub(crate) fn lift_params(
+        &self,
+        types: &[ComponentValType],
+        core_params: &[Val],
+    ) -> Result<Vec<ComponentVal>, RuntimeError> {
+        if flatten_all(types).len() > MAX_FLAT_PARAMS {
+            let ptr = i32_of(core_params.get(0).cloned())? as u32;
+            return match self.load(&ComponentValType::Tuple(types.to_vec()), ptr)? {
+                ComponentVal::Tuple(values) => Ok(values),
+                _ => unreachable!(),
+            };
+        }
+        let mut flat = core_params.iter().cloned();
+        types
+            .iter()
+            .map(|ty| self.lift_flat(ty, &mut flat))
+            .collect()
+    }
+
+    /// Lowers the results of a call to a lowered function, which are
+    /// returned or stored at the address passed as its last parameter.
+    pub(crate) fn lower_results(
+        &self,
+        types: &[ComponentValType],
+        vals: &[ComponentVal],
+        core_params: &[Val],
+    ) -> Result<Vec<Val>, RuntimeError> {
+        if types.len() != vals.len() {
+            return Err(RuntimeError::new(format!(
+                "expected {} results, got {}",
+                types.len(),
+                vals.len()
+            )));
+        }
+        if flatten_all(types).len() > MAX_FLAT_RESULTS {
+            let ptr = i32_of(core_params.last().cloned())? as u32;
+            self.store(
+                &ComponentValType::Tuple(types.to_vec()),
+                &ComponentVal::Tuple(vals.to_vec()),
+                ptr,
+            )?;
+            return Ok(Vec::new());
+        }
+        let mut out = Vec::new();
+        for (ty, val) in types.iter().zip(vals) {
+            self.lower_flat(ty, val, &mut out)?;
+        }
+        Ok(out)
+    }
+}
@@ -0,0 +1,608 @@
+//! The components of the component model.
+//!
+//! A [`Component`] is made of core modules, linked together by the
+//! definitions of the component. Its imports are provided by a
+//! [`Linker`], as host functions taking and returning
+//! [`ComponentVal`]s, and its exported functions are called with
+//! [`ComponentVal`]s as well: the values are lifted from and lowered
+//! to the core values and the memories of the core instances with the
+//! canonical ABI.
+//!
+//! ```ignore
+//! let component = Component::new(&store, bytes)?;
+//! let mut linker = Linker::new(&store);
+//! linker.func_wrap("example:host/log", "log", |(message,): (String,)| {
+//!     println!("{}", message);
+//!     Ok(())
+//! });
+//! let instance = linker.instantiate(&component)?;
+//! let greet = instance.get_func("greet")?.typed::<(String,), (String,)>()?;
+//! let (greeting,) = greet.call(("world".to_string(),))?;
+//! ```
+
+mod abi;
+mod values;
+
+pub use self::values::{ComponentVal, ComponentValue, ComponentValues};
+
+use self::abi::Abi;
+use crate::sys::exports::{ExportError, Exports};
+use crate::sys::externals::{Extern, Function};
+use crate::sys::import_object::ImportObject;
+use crate::sys::instance::{Instance, InstantiationError};
+use crate::sys::module::Module;
+use crate::sys::store::Store;
+use crate::sys::types::{FunctionType, ValType};
+use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use thiserror::Error;
+use wasmer_compiler::component::{
+    translate_component, CanonicalOptions, ComponentExternType, ComponentFuncType, ComponentItem,
+    ComponentTranslation, CoreFuncDef, CoreInstanceDef, CoreItem, FuncDef, InstanceDef,
+    StringEncoding,
+};
+use wasmer_compiler::CompileError;
+use wasmer_engine::RuntimeError;
+
+/// An error while instantiating a component.
+#[derive(Error, Debug)]
+pub enum ComponentError {
+    /// The linker doesn't define an import of the component.
+    #[error("unknown import `{name}` of `{interface}`")]
+    UnknownImport {
+        /// The name of the imported instance, empty for the functions
+        /// imported by the component itself.
+        interface: String,
+        /// The name of the function.
+        name: String,
+    },
+
+    /// A core instance of the component failed to be instantiated.
+    #[error(transparent)]
+    Instantiation(#[from] InstantiationError),
+
+    /// A core instance doesn't have an export the component refers to.
+    #[error(transparent)]
+    Export(#[from] ExportError),
+
+    /// The component uses a feature which isn't supported yet.
+    #[error("unsupported feature: {0}")]
+    Unsupported(String),
+}
+
+/// A compiled component: its definitions, and its core modules.
+#[derive(Clone)]
+pub struct Component {
+    translation: Arc<ComponentTranslation>,
+    modules: Vec<Module>,
+}
+
+impl Component {
+    /// Parses a component, and compiles its core modules.
+    pub fn new(store: &Store, bytes: impl AsRef<[u8]>) -> Result<Self, CompileError> {
+        let mut translation = translate_component(bytes.as_ref())?;
+        let modules = translation
+            .modules
+            .drain(..)
+            .map(|module| Module::new(store, module))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            translation: Arc::new(translation),
+            modules,
+        })
+    }
+
+    /// Returns the names and the types of the imports of the
+    /// component.
+    pub fn imports(&self) -> &[(String, ComponentExternType)] {
+        &self.translation.imports
+    }
+
+    /// Returns the names of the exports of the component.
+    pub fn exports(&self) -> impl Iterator<Item = &str> {
+        self.translation
+            .exports
+            .iter()
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Returns the core modules of the component.
+    pub fn modules(&self) -> &[Module] {
+        &self.modules
+    }
+}
+
+impl fmt::Debug for Component {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Component")
+            .field("imports", &self.translation.imports)
+            .field("exports", &self.translation.exports)
+            .finish()
+    }
+}
+
+type HostFunc =
+    Arc<dyn Fn(&[ComponentVal]) -> Result<Vec<ComponentVal>, RuntimeError> + Send + Sync>;
+
+/// Defines the imports of the components, and instantiates them.
+///
+/// The functions are defined by the name of the imported instance
+/// exporting them, like `wasi:cli/environment@0.2.0`, or by an empty
+/// name for the functions imported by the component itself.
+#[derive(Clone)]
+pub struct Linker {
+    store: Store,
+    funcs: HashMap<(String, String), HostFunc>,
+    lowered: Arc<ImportObject>,
+}
+
+impl Linker {
+    /// Creates a linker without any definition.
+    pub fn new(store: &Store) -> Self {
+        Self {
+            store: store.clone(),
+            funcs: HashMap::new(),
+            lowered: Arc::new(ImportObject::new()),
+        }
+    }
+
+    /// Defines the function `name` of `interface`, taking and
+    /// returning [`ComponentVal`]s.
+    pub fn func_new<F>(&mut self, interface: &str, name: &str, func: F) -> &mut Self
+    where
+        F: Fn(&[ComponentVal]) -> Result<Vec<ComponentVal>, RuntimeError> + Send + Sync + 'static,
+    {
+        self.funcs
+            .insert((interface.to_string(), name.to_string()), Arc::new(func));
+        self
+    }
+
+    /// Defines the function `name` of `interface`, taking and
+    /// returning Rust values.
+    pub fn func_wrap<P, R, F>(&mut self, interface: &str, name: &str, func: F) -> &mut Self
+    where
+        P: ComponentValues,
+        R: ComponentValues,
+        F: Fn(P) -> Result<R, RuntimeError> + Send + Sync + 'static,
+    {
+        self.func_new(interface, name, move |params| {
+            let results = func(P::from_component_vals(params.to_vec())?)?;
+            Ok(results.into_component_vals())
+        })
+    }
+
+    /// Defines functions which are already lowered to core functions,
+    /// by the name of their interface and their name, like the ones of
+    /// `wasmer-wasi-preview2`. The `[resource-drop]<resource>` functions
+    /// define how the resources are dropped.
+    ///
+    /// The functions defined with [`Linker::func_new`] take
+    /// precedence over these.
+    pub fn define_lowered(&mut self, import_object: ImportObject) -> &mut Self {
+        self.lowered = Arc::new(import_object);
+        self
+    }
+
+    fn lowered(&self, interface: &str, name: &str) -> Option<Function> {
+        match self.lowered.get_export(interface, name) {
+            Some(export) => match Extern::from_vm_export(&self.store, export) {
+                Extern::Function(func) => Some(func),
+                _ => None,
+            },
+            None => None,
+        }
+    }
+
+    /// Instantiates the core modules of `component`, and links them
+    /// with the definitions of the linker.
+    pub fn instantiate(&self, component: &Component) -> Result<ComponentInstance, ComponentError> {
+        let mut instantiator = Instantiator {
+            linker: self,
+            component,
+            core_instances: Vec::new(),
+            core_funcs: HashMap::new(),
+            funcs: HashMap::new(),
+        };
+        for index in 0..component.translation.core_instances.len() {
+            let instance = instantiator.core_instance(index as u32)?;
+            instantiator.core_instances.push(instance);
+        }
+
+        let mut funcs = HashMap::new();
+        let mut instances = HashMap::new();
+        for (name, item) in component.translation.exports.iter() {
+            match item {
+                ComponentItem::Func(index) => {
+                    funcs.insert(name.clone(), instantiator.func(*index)?);
+                }
+                ComponentItem::Instance(index) => {
+                    instances.insert(name.clone(), instantiator.instance_funcs(*index)?);
+                }
+                ComponentItem::Type(_) => {}
+            }
+        }
+        Ok(ComponentInstance {
+            funcs,
+            instances,
+            core_instances: instantiator.core_instances.into_iter().flatten().collect(),
+        })
+    }
+}
+
+/// Resolves the definitions of a component, in the order of their
+/// index spaces.
+struct Instantiator<'a> {
+    linker: &'a Linker,
+    component: &'a Component,
+    /// The core instances, `None` for the ones made of exports.
+    core_instances: Vec<Option<Instance>>,
+    core_funcs: HashMap<u32, Function>,
+    funcs: HashMap<u32, ComponentFunc>,
+}
+
+impl<'a> Instantiator<'a> {
+    fn core_instance(&mut self, index: u32) -> Result<Option<Instance>, ComponentError> {
+        let component = self.component;
+        match &component.translation.core_instances[index as usize] {
+            CoreInstanceDef::Instantiate { module, args } => {
+                let mut import_object = ImportObject::new();
+                for (name, instance) in args {
+                    import_object.register(name, self.core_instance_exports(*instance)?);
+                }
+                let module = &component.modules[*module as usize];
+                Ok(Some(Instance::new(module, &import_object)?))
+            }
+            // The items of an instance made of exports are resolved
+            // when it's used as the imports of another instance.
+            CoreInstanceDef::FromExports(_) => Ok(None),
+        }
+    }
+
+    fn core_instance_exports(&mut self, index: u32) -> Result<Exports, ComponentError> {
+        let translation = self.component.translation.clone();
+        match &translation.core_instances[index as usize] {
+            CoreInstanceDef::Instantiate { .. } => Ok(self.core_instances[index as usize]
+                .as_ref()
+                .expect("the core instances are instantiated in order")
+                .exports
+                .clone()),
+            CoreInstanceDef::FromExports(items) => {
+                let mut exports = Exports::new();
+                for (name, item) in items {
+                    exports.insert(name, self.core_item(*item)?);
+                }
+                Ok(exports)
+            }
+        }
+    }
+
+    fn core_export(&self, instance: u32, name: &str) -> Result<Extern, ComponentError> {
+        let export = match &self.core_instances[instance as usize] {
+            Some(instance) => instance.exports.get_extern(name).cloned(),
+            None => None,
+        };
+        Ok(export.ok_or_else(|| ExportError::Missing(name.to_string()))?)
+    }
+
+    fn core_item(&mut self, item: CoreItem) -> Result<Extern, ComponentError> {
+        let component = self.component;
+        let translation = &component.translation;
+        let export = match item {
+            CoreItem::Func(index) => return Ok(Extern::Function(self.core_func(index)?)),
+            CoreItem::Table(index) => &translation.core_tables[index as usize],
+            CoreItem::Memory(index) => &translation.core_memories[index as usize],
+            CoreItem::Global(index) => &translation.core_globals[index as usize],
+        };
+        self.core_export(export.instance, &export.name)
+    }
+
+    fn core_func(&mut self, index: u32) -> Result<Function, ComponentError> {
+        if let Some(func) = self.core_funcs.get(&index) {
+            return Ok(func.clone());
+        }
+        let translation = self.component.translation.clone();
+        let func = match &translation.core_funcs[index as usize] {
+            CoreFuncDef::Export(export) => {
+                match self.core_export(export.instance, &export.name)? {
+                    Extern::Function(func) => func,
+                    _ => return Err(ExportError::IncompatibleType.into()),
+                }
+            }
+            CoreFuncDef::Lower { func, options } => self.lower(*func, options)?,
+            CoreFuncDef::ResourceDrop(resource) => {
+                let interface = resource.interface.as_deref().unwrap_or("");
+                let name = format!("[resource-drop]{}", resource.name);
+                match self.linker.lowered(interface, &name) {
+                    Some(func) => func,
+                    None => Function::new(
+                        &self.linker.store,
+                        FunctionType::new(vec![ValType::I32], vec![]),
+                        |_| Ok(vec![]),
+                    ),
+                }
+            }
+        };
+        self.core_funcs.insert(index, func.clone());
+        Ok(func)
+    }
+
+    fn abi(&mut self, options: &CanonicalOptions) -> Result<Abi, ComponentError> {
+        if options.string_encoding != StringEncoding::Utf8 {
+            return Err(ComponentError::Unsupported(
+                "string encodings other than UTF-8".to_string(),
+            ));
+        }
+        let memory = match options.memory {
+            Some(index) => match self.core_item(CoreItem::Memory(index))? {
+                Extern::Memory(memory) => Some(memory),
+                _ => return Err(ExportError::IncompatibleType.into()),
+            },
+            None => None,
+        };
+        let realloc = match options.realloc {
+            Some(index) => Some(self.core_func(index)?),
+            None => None,
+        };
+        Ok(Abi { memory, realloc })
+    }
+
+    /// Lowers the function `index` of the component to a core function.
+    fn lower(
+        &mut self,
+        index: u32,
+        options: &CanonicalOptions,
+    ) -> Result<Function, ComponentError> {
+        let translation = self.component.translation.clone();
+        let def = &translation.funcs[index as usize];
+        // The functions imported from an interface may be defined
+        // already lowered.
+        if let FuncDef::InstanceExport { instance, name, .. } = def {
+            if let InstanceDef::Import {
+                name: interface, ..
+            } = &translation.instances[*instance as usize]
+            {
+                let key = (interface.clone(), name.clone());
+                if !self.linker.funcs.contains_key(&key) {
+                    if let Some(func) = self.linker.lowered(interface, name) {
+                        return Ok(func);
+                    }
+                }
+            }
+        }
+        let func = self.func(index)?;
+        let abi = self.abi(options)?;
+        let (params, results) = abi::core_signature(&func.ty, false);
+        Ok(Function::new(
+            &self.linker.store,
+            FunctionType::new(params, results),
+            move |core_params| {
+                let param_types = func.param_types();
+                let params = abi.lift_params(&param_types, core_params)?;
+                let results = func.call(&params)?;
+                abi.lower_results(&func.ty.results, &results, core_params)
+            },
+        ))
+    }
+
+    fn host_func(&self, interface: &str, name: &str) -> Result<HostFunc, ComponentError> {
+        self.linker
+            .funcs
+            .get(&(interface.to_string(), name.to_string()))
+            .cloned()
+            .ok_or_else(|| ComponentError::UnknownImport {
+                interface: interface.to_string(),
+                name: name.to_string(),
+            })
+    }
+
+    fn func(&mut self, index: u32) -> Result<ComponentFunc, ComponentError> {
+        if let Some(func) = self.funcs.get(&index) {
+            return Ok(func.clone());
+        }
+        let translation = self.component.translation.clone();
+        let def = &translation.funcs[index as usize];
+        let inner = match def {
+            FuncDef::Import { name, .. } => FuncImpl::Host(self.host_func("", name)?),
+            FuncDef::InstanceExport { instance, name, .. } => {
+                match &translation.instances[*instance as usize] {
+                    InstanceDef::Import {
+                        name: interface, ..
+                    } => FuncImpl::Host(self.host_func(interface, name)?),
+                    InstanceDef::FromExports(items) => {
+                        match items.iter().find(|(export, _)| export == name) {
+                            Some((_, ComponentItem::Func(func))) => self.func(*func)?.inner,
+                            _ => return Err(ExportError::Missing(name.clone()).into()),
+                        }
+                    }
+                }
+            }
+            FuncDef::Lift {
+                core_func, options, ..
+            } => {
+                let post_return = match options.post_return {
+                    Some(index) => Some(self.core_func(index)?),
+                    None => None,
+                };
+                FuncImpl::Lifted {
+                    func: self.core_func(*core_func)?,
+                    abi: self.abi(options)?,
+                    post_return,
+                }
+            }
+        };
+        let func = ComponentFunc {
+            ty: Arc::new(def.ty().clone()),
+            inner,
+        };
+        self.funcs.insert(index, func.clone());
+        Ok(func)
+    }
+
+    fn instance_funcs(
+        &mut self,
+        index: u32,
+    ) -> Result<HashMap<String, ComponentFunc>, ComponentError> {
+        let translation = self.component.translation.clone();
+        let mut funcs = HashMap::new();
+        match &translation.instances[index as usize] {
+            InstanceDef::Import {
+                name: interface,
+                ty,
+            } => {
+                for (name, ty) in ty.exports.iter() {
+                    if let ComponentExternType::Func(ty) = ty {
+                        let func = ComponentFunc {
+                            ty: Arc::new(ty.clone()),
+                            inner: FuncImpl::Host(self.host_func(interface, name)?),
+                        };
+                        funcs.insert(name.clone(), func);
+                    }
+                }
+            }
+            InstanceDef::FromExports(items) => {
+                for (name, item) in items {
+                    if let ComponentItem::Func(func) = item {
+                        funcs.insert(name.clone(), self.func(*func)?);
+                    }
+                }
+            }
+        }
+        Ok(funcs)
+    }
+}
+
+/// An instance of a component.
+#[derive(Clone)]
+pub struct ComponentInstance {
+    funcs: HashMap<String, ComponentFunc>,
+    instances: HashMap<String, HashMap<String, ComponentFunc>>,
+    core_instances: Vec<Instance>,
+}
+
+impl ComponentInstance {
+    /// Returns the function exported as `name`.
+    pub fn get_func(&self, name: &str) -> Result<ComponentFunc, ExportError> {
+        self.funcs
+            .get(name)
+            .cloned()
+            .ok_or_else(|| ExportError::Missing(name.to_string()))
+    }
+
+    /// Returns the function `name` of the instance exported as
+    /// `instance`, like `wasi:cli/run@0.2.0`.
+    pub fn get_instance_func(
+        &self,
+        instance: &str,
+        name: &str,
+    ) -> Result<ComponentFunc, ExportError> {
+        self.instances
+            .get(instance)
+            .ok_or_else(|| ExportError::Missing(instance.to_string()))?
+            .get(name)
+            .cloned()
+            .ok_or_else(|| ExportError::Missing(format!("{}#{}", instance, name)))
+    }
+
+    /// Returns the core instances of the component, in the order of
+    /// their definitions.
+    pub fn core_instances(&self) -> &[Instance] {
+        &self.core_instances
+    }
+}
+
+impl fmt::Debug for ComponentInstance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ComponentInstance")
+            .field("funcs", &self.funcs.keys().collect::<Vec<_>>())
+            .field("instances", &self.instances.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+#[derive(Clone)]
+enum FuncImpl {
+    Host(HostFunc),
+    Lifted {
+        func: Function,
+        abi: Abi,
+        post_return: Option<Function>,
+    },
+}
+
+/// A function of a component.
+#[derive(Clone)]
+pub struct ComponentFunc {
+    ty: Arc<ComponentFuncType>,
+    inner: FuncImpl,
+}
+
+impl ComponentFunc {
+    /// Returns the type of the function.
+    pub fn ty(&self) -> &ComponentFuncType {
+        &self.ty
+    }
+
+    fn param_types(&self) -> Vec<wasmer_compiler::component::ComponentValType> {
+        self.ty.params.iter().map(|(_, ty)| ty.clone()).collect()
+    }
+
+    /// Calls the function with `params`, and returns its results.
+    pub fn call(&self, params: &[ComponentVal]) -> Result<Vec<ComponentVal>, RuntimeError> {
+        match &self.inner {
+            FuncImpl::Host(func) => func(params),
+            FuncImpl::Lifted {
+                func,
+                abi,
+                post_return,
+            } => {
+                let core_params = abi.lower_params(&self.param_types(), params)?;
+                let core_results = func.call(&core_params)?;
+                let results = abi.lift_results(&self.ty.results, &core_results)?;
+                if let Some(post_return) = post_return {
+                    post_return.call(&core_results)?;
+                }
+                Ok(results)
+            }
+        }
+    }
+
+    /// Returns a version of the function taking and returning Rust
+    /// values.
+    ///
+    /// The types aren't checked until the function is called.
+    pub fn typed<P, R>(&self) -> Result<TypedComponentFunc<P, R>, RuntimeError>
+    where
+        P: ComponentValues,
+        R: ComponentValues,
+    {
+        Ok(TypedComponentFunc {
+            func: self.clone(),
+            _phantom: PhantomData,
+        })
+    }
+}
+
+impl fmt::Debug for ComponentFunc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ComponentFunc")
+            .field("ty", &self.ty)
+            .finish()
+    }
+}
+
+/// A function of a component, taking and returning Rust values.
+#[derive(Clone)]
+pub struct TypedComponentFunc<P, R> {
+    func: ComponentFunc,
+    _phantom: PhantomData<fn(P) -> R>,
+}
+
+impl<P: ComponentValues, R: ComponentValues> TypedComponentFunc<P, R> {
+    /// Calls the function with `params`, and returns its results.
+    pub fn call(&self, params: P) -> Result<R, RuntimeError> {
+        let results = self.func.call(&params.into_component_vals())?;
+        R::from_component_vals(results)
+    }
+}
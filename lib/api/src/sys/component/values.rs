@@ -0,0 +1,239 @@
+use crate::RuntimeError;
+
+/// A value of the component model.
+///
+/// The handles of the resources are passed as is between the host
+/// and the component: the host owns the table of the resources.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ComponentVal {
+    /// `bool`
+    Bool(bool),
+    /// `s8`
+    S8(i8),
+    /// `u8`
+    U8(u8),
+    /// `s16`
+    S16(i16),
+    /// `u16`
+    U16(u16),
+    /// `s32`
+    S32(i32),
+    /// `u32`
+    U32(u32),
+    /// `s64`
+    S64(i64),
+    /// `u64`
+    U64(u64),
+    /// `f32`
+    Float32(f32),
+    /// `f64`
+    Float64(f64),
+    /// `char`
+    Char(char),
+    /// `string`
+    String(String),
+    /// `list<T>`
+    List(Vec<ComponentVal>),
+    /// `record`, with the values of its fields in order.
+    Record(Vec<(String, ComponentVal)>),
+    /// `tuple<...>`
+    Tuple(Vec<ComponentVal>),
+    /// `variant`, with the name of the case and its payload.
+    Variant(String, Option<Box<ComponentVal>>),
+    /// `enum`, with the name of the case.
+    Enum(String),
+    /// `option<T>`
+    Option(Option<Box<ComponentVal>>),
+    /// `result<T, E>`, with the optional payloads of its cases.
+    Result(Result<Option<Box<ComponentVal>>, Option<Box<ComponentVal>>>),
+    /// `flags`, with the names of the flags which are set.
+    Flags(Vec<String>),
+    /// `own<R>`, with the handle of the resource.
+    Own(u32),
+    /// `borrow<R>`, with the handle of the resource.
+    Borrow(u32),
+}
+
+fn unexpected(expected: &str, val: &ComponentVal) -> RuntimeError {
+    RuntimeError::new(format!(
+        "expected a value of type `{}`, got {:?}",
+        expected, val
+    ))
+}
+
+/// A Rust type which converts to and from a [`ComponentVal`], so it
+/// can be passed to and returned by the typed functions.
+pub trait ComponentValue: Sized {
+    /// Converts the value to a [`ComponentVal`].
+    fn into_component_val(self) -> ComponentVal;
+
+    /// Converts a [`ComponentVal`] to a value of this type.
+    fn from_component_val(val: ComponentVal) -> Result<Self, RuntimeError>;
+}
+
+macro_rules! primitive_component_value {
+    ($($ty:ty => $variant:ident, $name:expr;)*) => {
+        $(
+            impl ComponentValue for $ty {
+                fn into_component_val(self) -> ComponentVal {
+                    ComponentVal::$variant(self)
+                }
+
+                fn from_component_val(val: ComponentVal) -> Result<Self, RuntimeError> {
+                    match val {
+                        ComponentVal::$variant(value) => Ok(value),
+                        val => Err(unexpected($name, &val)),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+primitive_component_value! {
+    bool => Bool, "bool";
+    i8 => S8, "s8";
+    u8 => U8, "u8";
+    i16 => S16, "s16";
+    u16 => U16, "u16";
+    i32 => S32, "s32";
+    u32 => U32, "u32";
+    i64 => S64, "s64";
+    u64 => U64, "u64";
+    f32 => Float32, "f32";
+    f64 => Float64, "f64";
+    char => Char, "char";
+    String => String, "string";
+}
+
+impl ComponentValue for ComponentVal {
+    fn into_component_val(self) -> ComponentVal {
+        self
+    }
+
+    fn from_component_val(val: ComponentVal) -> Result<Self, RuntimeError> {
+        Ok(val)
+    }
+}
+
+impl<T: ComponentValue> ComponentValue for Vec<T> {
+    fn into_component_val(self) -> ComponentVal {
+        ComponentVal::List(self.into_iter().map(T::into_component_val).collect())
+    }
+
+    fn from_component_val(val: ComponentVal) -> Result<Self, RuntimeError> {
+        match val {
+            ComponentVal::List(values) => values.into_iter().map(T::from_component_val).collect(),
+            val => Err(unexpected("list", &val)),
+        }
+    }
+}
+
+impl<T: ComponentValue> ComponentValue for Option<T> {
+    fn into_component_val(self) -> ComponentVal {
+        ComponentVal::Option(self.map(|value| Box::new(value.into_component_val())))
+    }
+
+    fn from_component_val(val: ComponentVal) -> Result<Self, RuntimeError> {
+        match val {
+            ComponentVal::Option(value) => {
+                value.map(|value| T::from_component_val(*value)).transpose()
+            }
+            val => Err(unexpected("option", &val)),
+        }
+    }
+}
+
+/// The payload of a case of `result`: `()` for a case without a
+/// payload.
+fn into_payload<T: ComponentValue>(value: T) -> Option<Box<ComponentVal>> {
+    match value.into_component_val() {
+        ComponentVal::Tuple(values) if values.is_empty() => None,
+        val => Some(Box::new(val)),
+    }
+}
+
+fn from_payload<T: ComponentValue>(payload: Option<Box<ComponentVal>>) -> Result<T, RuntimeError> {
+    T::from_component_val(payload.map_or(ComponentVal::Tuple(Vec::new()), |val| *val))
+}
+
+impl<T: ComponentValue, E: ComponentValue> ComponentValue for Result<T, E> {
+    fn into_component_val(self) -> ComponentVal {
+        ComponentVal::Result(match self {
+            Ok(value) => Ok(into_payload(value)),
+            Err(error) => Err(into_payload(error)),
+        })
+    }
+
+    fn from_component_val(val: ComponentVal) -> Result<Self, RuntimeError> {
+        match val {
+            ComponentVal::Result(Ok(payload)) => Ok(Ok(from_payload(payload)?)),
+            ComponentVal::Result(Err(payload)) => Ok(Err(from_payload(payload)?)),
+            val => Err(unexpected("result", &val)),
+        }
+    }
+}
+
+/// The parameters or the results of a typed function: a tuple of
+/// [`ComponentValue`]s.
+pub trait ComponentValues: Sized {
+    /// Converts the values to [`ComponentVal`]s.
+    fn into_component_vals(self) -> Vec<ComponentVal>;
+
+    /// Converts [`ComponentVal`]s to values of these types.
+    fn from_component_vals(vals: Vec<ComponentVal>) -> Result<Self, RuntimeError>;
+}
+
+macro_rules! count {
+    () => { 0 };
+    ($head:ident $($tail:ident)*) => { 1 + count!($($tail)*) };
+}
+
+macro_rules! tuple_component_values {
+    ($($ty:ident)*) => {
+        #[allow(non_snake_case)]
+        impl<$($ty: ComponentValue),*> ComponentValues for ($($ty,)*) {
+            fn into_component_vals(self) -> Vec<ComponentVal> {
+                let ($($ty,)*) = self;
+                vec![$($ty.into_component_val()),*]
+            }
+
+            #[allow(unused_variables, unused_mut)]
+            fn from_component_vals(vals: Vec<ComponentVal>) -> Result<Self, RuntimeError> {
+                if vals.len() != count!($($ty)*) {
+                    return Err(RuntimeError::new(format!(
+                        "expected {} values, got {}",
+                        count!($($ty)*),
+                        vals.len()
+                    )));
+                }
+                let mut vals = vals.into_iter();
+                Ok(($($ty::from_component_val(vals.next().unwrap())?,)*))
+            }
+        }
+
+        #[allow(non_snake_case)]
+        impl<$($ty: ComponentValue),*> ComponentValue for ($($ty,)*) {
+            fn into_component_val(self) -> ComponentVal {
+                ComponentVal::Tuple(self.into_component_vals())
+            }
+
+            fn from_component_val(val: ComponentVal) -> Result<Self, RuntimeError> {
+                match val {
+                    ComponentVal::Tuple(vals) => Self::from_component_vals(vals),
+                    val => Err(unexpected("tuple", &val)),
+                }
+            }
+        }
+    };
+}
+
+tuple_component_values!();
+tuple_component_values!(A1);
+tuple_component_values!(A1 A2);
+tuple_component_values!(A1 A2 A3);
+tuple_component_values!(A1 A2 A3 A4);
+tuple_component_values!(A1 A2 A3 A4 A5);
+tuple_component_values!(A1 A2 A3 A4 A5 A6);
+tuple_component_values!(A1 A2 A3 A4 A5 A6 A7);
+tuple_component_values!(A1 A2 A3 A4 A5 A6 A7 A8);
@@ -0,0 +1,396 @@
+//! A log of the recent events of a store, for post-mortem debugging.
+//!
+//! When enabled with [`Store::enable_event_log`], a store keeps its
+//! last events (instantiations, traps, memory grows and host call
+//! failures) in a ring buffer, so they can be included in a bug
+//! report with [`Store::debug_dump`] when something goes wrong,
+//! without the overhead of tracing every call. The dump includes the
+//! labels of the store, and the ones of the instance that trapped. It's
+//! passed to the handler set with [`Store::set_debug_dump_handler`]
+//! when a call traps or a host function panics.
+//!
+//! [`Store::enable_event_log`]: crate::Store::enable_event_log
+//! [`Store::debug_dump`]: crate::Store::debug_dump
+//! [`Store::set_debug_dump_handler`]: crate::Store::set_debug_dump_handler
+
+use crate::sys::labels::{LabelSet, Labels};
+use crate::{MemoryType, Pages, RuntimeError};
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::ptr::NonNull;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use wasmer_vm::{Memory, MemoryError, MemoryStyle, VMMemoryDefinition};
+
+/// An event logged by a store.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StoreEvent {
+    /// A module was instantiated.
+    Instantiated {
+        /// The name of the module, if any.
+        module: Option<String>,
+    },
+    /// The instantiation of a module failed.
+    InstantiationFailed {
+        /// The name of the module, if any.
+        module: Option<String>,
+        /// The error.
+        error: String,
+    },
+    /// A call of a WebAssembly function from the host trapped.
+    Trapped {
+        /// The message of the trap.
+        message: String,
+    },
+    /// A memory grew.
+    MemoryGrown {
+        /// The size of the memory before growing.
+        from: Pages,
+        /// The size of the memory after growing.
+        to: Pages,
+    },
+    /// A memory failed to grow.
+    MemoryGrowFailed {
+        /// The requested number of pages to add.
+        delta: Pages,
+        /// The error.
+        error: String,
+    },
+    /// A host function called by WebAssembly returned an error.
+    HostCallFailed {
+        /// The message of the error.
+        message: String,
+    },
+    /// A host function called by WebAssembly panicked.
+    HostCallPanicked {
+        /// The message of the panic, if it's a string.
+        message: Option<String>,
+    },
+}
+
+impl fmt::Display for StoreEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let module_name =
+            |module: &Option<String>| module.clone().unwrap_or_else(|| "<module>".to_string());
+        match self {
+            Self::Instantiated { module } => write!(f, "instantiated {}", module_name(module)),
+            Self::InstantiationFailed { module, error } => {
+                write!(
+                    f,
+                    "failed to instantiate {}: {}",
+                    module_name(module),
+                    error
+                )
+            }
+            Self::Trapped { message } => write!(f, "trapped: {}", message),
+            Self::MemoryGrown { from, to } => write!(f, "memory grew from {:?} to {:?}", from, to),
+            Self::MemoryGrowFailed { delta, error } => {
+                write!(f, "memory failed to grow by {:?}: {}", delta, error)
+            }
+            Self::HostCallFailed { message } => write!(f, "host function failed: {}", message),
+            Self::HostCallPanicked { message } => write!(
+                f,
+                "host function panicked: {}",
+                message.as_deref().unwrap_or("<non-string payload>")
+            ),
+        }
+    }
+}
+
+/// The ring buffer of the events of a store.
+#[derive(Debug)]
+pub(crate) struct EventLog {
+    capacity: usize,
+    start: Instant,
+    events: Mutex<VecDeque<(Duration, StoreEvent, Labels)>>,
+}
+
+impl EventLog {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            start: Instant::now(),
+            events: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    fn record(&self, event: StoreEvent, labels: Labels) {
+        if self.capacity == 0 {
+            return;
+        }
+        let elapsed = self.start.elapsed();
+        let mut events = self.events.lock().unwrap();
+        if events.len() == self.capacity {
+            events.pop_front();
+        }
+        events.push_back((elapsed, event, labels));
+    }
+}
+
+type DumpHandler = Arc<dyn Fn(&str) + Send + Sync>;
+
+struct EventLogState {
+    log: RwLock<Option<Arc<EventLog>>>,
+    dump_handler: RwLock<Option<DumpHandler>>,
+    labels: LabelSet,
+}
+
+/// The event log of a store, shared by its clones.
+#[derive(Clone)]
+pub(crate) struct EventLogSlot(Arc<EventLogState>);
+
+impl EventLogSlot {
+    /// Creates a disabled log, dumped with the labels of the store
+    /// `labels`.
+    pub(crate) fn new(labels: LabelSet) -> Self {
+        Self(Arc::new(EventLogState {
+            log: RwLock::new(None),
+            dump_handler: RwLock::new(None),
+            labels,
+        }))
+    }
+
+    pub(crate) fn enable(&self, capacity: usize) {
+        *self.0.log.write().unwrap() = Some(Arc::new(EventLog::new(capacity)));
+    }
+
+    pub(crate) fn disable(&self) {
+        *self.0.log.write().unwrap() = None;
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.0.log.read().unwrap().is_some()
+    }
+
+    fn get(&self) -> Option<Arc<EventLog>> {
+        self.0.log.read().unwrap().clone()
+    }
+
+    pub(crate) fn set_dump_handler(&self, handler: Option<DumpHandler>) {
+        *self.0.dump_handler.write().unwrap() = handler;
+    }
+
+    /// Records `event`, built only if the log is enabled.
+    pub(crate) fn record(&self, event: impl FnOnce() -> StoreEvent) {
+        if let Some(log) = self.0.log.read().unwrap().as_ref() {
+            log.record(event(), Labels::new());
+        }
+    }
+
+    /// Records `event` with the labels of the instance it comes from,
+    /// built only if the log is enabled.
+    pub(crate) fn record_labeled(&self, event: impl FnOnce() -> (StoreEvent, Labels)) {
+        if let Some(log) = self.0.log.read().unwrap().as_ref() {
+            let (event, labels) = event();
+            log.record(event, labels);
+        }
+    }
+
+    /// Records the trap of a call from the host, and passes the dump
+    /// to the dump handler.
+    pub(crate) fn trapped(&self, error: &RuntimeError) {
+        self.record_labeled(|| {
+            let event = StoreEvent::Trapped {
+                message: error.message(),
+            };
+            (event, error.labels().clone())
+        });
+        self.handle_dump();
+    }
+
+    /// Passes the dump to the dump handler, if the log is enabled.
+    fn handle_dump(&self) {
+        let handler = self.0.dump_handler.read().unwrap().clone();
+        if let Some(handler) = handler {
+            if self.is_enabled() {
+                handler(&self.dump());
+            }
+        }
+    }
+
+    pub(crate) fn events(&self) -> Vec<StoreEvent> {
+        self.get()
+            .map(|log| {
+                let events = log.events.lock().unwrap();
+                events.iter().map(|(_, event, _)| event.clone()).collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn dump(&self) -> String {
+        let log = match self.get() {
+            Some(log) => log,
+            None => return "event log disabled\n".to_string(),
+        };
+        let labels = self.0.labels.get();
+        let events = log.events.lock().unwrap();
+        let mut dump = String::new();
+        if !labels.is_empty() {
+            dump.push_str(&format!("labels: {}\n", format_labels(&labels)));
+        }
+        dump.push_str(&format!(
+            "last {} events (of at most {}):\n",
+            events.len(),
+            log.capacity
+        ));
+        for (elapsed, event, labels) in events.iter() {
+            dump.push_str(&format!(
+                "[{:>4}.{:06}s] {}",
+                elapsed.as_secs(),
+                elapsed.subsec_micros(),
+                event
+            ));
+            if !labels.is_empty() {
+                dump.push_str(&format!(" {{{}}}", format_labels(labels)));
+            }
+            dump.push('\n');
+        }
+        dump
+    }
+}
+
+impl fmt::Debug for EventLogSlot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EventLogSlot")
+            .field("log", &self.get())
+            .finish()
+    }
+}
+
+/// Formats labels as `key=value` pairs.
+fn format_labels(labels: &Labels) -> String {
+    labels
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+thread_local! {
+    /// The event log of the store executing on this thread.
+    static CURRENT_LOG: RefCell<Option<EventLogSlot>> = RefCell::new(None);
+}
+
+/// Makes the log of a store the one recording the host call failures
+/// on this thread, until it's dropped.
+pub(crate) struct EventLogScope {
+    /// The log to restore, if the scope replaced it.
+    previous: Option<Option<EventLogSlot>>,
+}
+
+impl EventLogScope {
+    pub(crate) fn enter(log: &EventLogSlot) -> Self {
+        let log = if log.is_enabled() {
+            Some(log.clone())
+        } else {
+            None
+        };
+        let previous = CURRENT_LOG.with(|current| {
+            // Without logs, the calls leave the thread untouched.
+            if log.is_none() && current.borrow().is_none() {
+                None
+            } else {
+                Some(current.replace(log))
+            }
+        });
+        Self { previous }
+    }
+}
+
+impl Drop for EventLogScope {
+    fn drop(&mut self) {
+        if let Some(previous) = self.previous.take() {
+            CURRENT_LOG.with(|current| *current.borrow_mut() = previous);
+        }
+    }
+}
+
+/// Records the failure of a host function called by WebAssembly in
+/// the log of the current store.
+pub(crate) fn host_call_failed(error: &(dyn Error + 'static)) {
+    record_current(|| StoreEvent::HostCallFailed {
+        // Leave the trace of the runtime errors out.
+        message: match error.downcast_ref::<RuntimeError>() {
+            Some(error) => error.message(),
+            None => error.to_string(),
+        },
+    });
+}
+
+/// Records the panic of a host function called by WebAssembly in the
+/// log of the current store, and passes the dump to its dump handler.
+pub(crate) fn host_call_panicked(panic: &(dyn Any + Send)) {
+    let log = CURRENT_LOG.with(|current| current.borrow().clone());
+    if let Some(log) = log {
+        log.record(|| StoreEvent::HostCallPanicked {
+            message: panic
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned()),
+        });
+        log.handle_dump();
+    }
+}
+
+fn record_current(event: impl FnOnce() -> StoreEvent) {
+    CURRENT_LOG.with(|current| {
+        if let Some(log) = current.borrow().as_ref() {
+            log.record(event);
+        }
+    });
+}
+
+/// Wraps `memory` so its grows are logged in `log`, if it's enabled.
+/// The memories created while the log is disabled aren't wrapped.
+pub(crate) fn logged_memory(log: &EventLogSlot, memory: Arc<dyn Memory>) -> Arc<dyn Memory> {
+    if !log.is_enabled() {
+        return memory;
+    }
+    Arc::new(LoggedMemory {
+        inner: memory,
+        log: log.clone(),
+    })
+}
+
+/// A memory logging its grows.
+#[derive(Debug)]
+struct LoggedMemory {
+    inner: Arc<dyn Memory>,
+    log: EventLogSlot,
+}
+
+impl Memory for LoggedMemory {
+    fn ty(&self) -> &MemoryType {
+        self.inner.ty()
+    }
+
+    fn style(&self) -> &MemoryStyle {
+        self.inner.style()
+    }
+
+    fn size(&self) -> Pages {
+        self.inner.size()
+    }
+
+    fn grow(&self, delta: Pages) -> Result<Pages, MemoryError> {
+        let result = self.inner.grow(delta);
+        self.log.record(|| match &result {
+            Ok(from) => StoreEvent::MemoryGrown {
+                from: *from,
+                to: self.inner.size(),
+            },
+            Err(error) => StoreEvent::MemoryGrowFailed {
+                delta,
+                error: error.to_string(),
+            },
+        });
+        result
+    }
+
+    fn vmmemory(&self) -> NonNull<VMMemoryDefinition> {
+        self.inner.vmmemory()
+    }
+}
@@ -1,6 +1,6 @@
-use crate::externals::{Extern, Function, Global, Memory, Table};
-use crate::import_object::LikeNamespace;
-use crate::native::NativeFunc;
+use crate::sys::externals::{Extern, Function, Global, Memory, Table};
+use crate::sys::import_object::LikeNamespace;
+use crate::sys::native::NativeFunc;
 use crate::WasmTypeList;
 use indexmap::IndexMap;
 use std::fmt;
@@ -1,10 +1,14 @@
-use crate::exports::{ExportError, Exportable};
-use crate::externals::Extern;
-use crate::store::Store;
-use crate::types::Val;
+use crate::sys::call_hook::{self, CallHookScope};
+use crate::sys::event_log::{self, EventLogScope};
+use crate::sys::exports::{ExportError, Exportable};
+use crate::sys::externals::Extern;
+use crate::sys::labels::InstanceLabels;
+use crate::sys::store::Store;
+use crate::sys::types::Val;
 use crate::FunctionType;
 use crate::NativeFunc;
 use crate::RuntimeError;
+use crate::Type;
 use crate::WasmerEnv;
 pub use inner::{FromToNativeWasmType, HostFunction, WasmTypeList, WithEnv, WithoutEnv};
 #[cfg(feature = "deprecated")]
@@ -13,6 +17,7 @@ pub use inner::{UnsafeMutableEnv, WithUnsafeMutableEnv};
 use std::cmp::max;
 use std::fmt;
 use std::sync::Arc;
+use wasmer_compiler::CALL_DEPTH_GLOBAL;
 use wasmer_engine::{Export, ExportFunction, ExportFunctionMetadata};
 use wasmer_vm::{
     raise_user_trap, resume_panic, wasmer_call_trampoline, wasmer_call_trampoline_unchecked,
@@ -313,6 +318,10 @@ impl Function {
     ///
     /// let f = Function::new_native(&store, sum);
     /// ```
+    ///
+    /// On Apple Silicon, a function taking more arguments than the
+    /// registers hold is called through the dynamic trampolines, as
+    /// a function created with [`Function::new`].
     pub fn new_native<F, Args, Rets, Env>(store: &Store, func: F) -> Self
     where
         F: HostFunction<Args, Rets, WithoutEnv, Env>,
@@ -330,6 +339,17 @@ impl Function {
         };
         let signature = function.ty();
 
+        if needs_dynamic_trampoline(&signature) {
+            let call: StaticHostFunctionCall = call_static_host_function::<Args, Rets>;
+            let address = address as usize;
+            return Self::new(store, signature, move |args| unsafe {
+                let vmctx = VMFunctionEnvironment {
+                    host_env: std::ptr::null_mut() as *mut _,
+                };
+                Ok(call(address as _, vmctx, args))
+            });
+        }
+
         Self {
             store: store.clone(),
             definition: FunctionDefinition::Host(HostFunctionDefinition { has_env: false }),
@@ -373,6 +393,10 @@ impl Function {
     ///
     /// let f = Function::new_native_with_env(&store, env, sum_and_multiply);
     /// ```
+    ///
+    /// On Apple Silicon, a function taking more arguments than the
+    /// registers hold is called through the dynamic trampolines, as
+    /// a function created with [`Function::new_with_env`].
     pub fn new_native_with_env<F, Args, Rets, Env>(store: &Store, env: Env, func: F) -> Self
     where
         F: HostFunction<Args, Rets, WithEnv, Env>,
@@ -385,12 +409,23 @@ impl Function {
         }
         let function = inner::Function::<Args, Rets>::new(func);
         let address = function.address();
+        let signature = function.ty();
+
+        if needs_dynamic_trampoline(&signature) {
+            let call: StaticHostFunctionCall = call_static_host_function::<Args, Rets>;
+            let address = address as usize;
+            return Self::new_with_env(store, signature, env, move |env: &Env, args| unsafe {
+                let vmctx = VMFunctionEnvironment {
+                    host_env: env as *const Env as *mut _,
+                };
+                Ok(call(address as _, vmctx, args))
+            });
+        }
 
         let (host_env, metadata) =
             build_export_function_metadata::<Env>(env, Env::init_with_instance);
 
         let vmctx = VMFunctionEnvironment { host_env };
-        let signature = function.ty();
 
         Self {
             store: store.clone(),
@@ -530,7 +565,26 @@ impl Function {
             }
         }
 
+        let instance_ref = self.exported.vm_function.instance_ref.as_ref();
+        let _execution = instance_ref
+            .map(|instance| self.store.enter_instance(instance))
+            .transpose()?;
+        let labels = || InstanceLabels::of_function(self.store.label_set(), instance_ref);
+        let hook = self.store.call_hook().map(|hook| hook.labeled(labels()));
+        let _hook_scope = CallHookScope::enter(hook.clone());
+        let _log_scope = EventLogScope::enter(self.store.event_log());
+        let wasm_call = hook.map(|hook| {
+            hook.calling_wasm(self.exported.vm_function.address as usize, || {
+                params.to_vec()
+            })
+        });
+
         // Call the trampoline.
+        // A trap doesn't unwind the depth of the calls counted by the
+        // instance, so it's restored on exit.
+        let _call_depth =
+            instance_ref.and_then(|instance| instance.save_middleware_globals(CALL_DEPTH_GLOBAL));
+        let _stack_limit = self.store.stack_limit().enter();
         if trampoline_checked {
             if let Err(error) = unsafe {
                 wasmer_call_trampoline(
@@ -540,7 +594,12 @@ impl Function {
                     values_vec.as_mut_ptr() as *mut u8,
                 )
             } {
-                return Err(RuntimeError::from_trap(error));
+                if let Some(wasm_call) = wasm_call {
+                    wasm_call.failed();
+                }
+                let error = RuntimeError::from_trap(error).with_labels(labels);
+                self.store.event_log().trapped(&error);
+                return Err(error);
             }
         } else {
             unsafe {
@@ -560,6 +619,9 @@ impl Function {
                 results[index] = Val::read_value_from(ptr, value_type);
             }
         }
+        if let Some(wasm_call) = wasm_call {
+            wasm_call.returned(|| results.to_vec());
+        }
 
         Ok(())
     }
@@ -853,6 +915,64 @@ impl Function {
     }
 }
 
+/// A call of a static host function from Rust: see
+/// [`call_static_host_function`].
+type StaticHostFunctionCall =
+    unsafe fn(*const VMFunctionBody, VMFunctionEnvironment, &[Val]) -> Vec<Val>;
+
+/// Whether a static host function of type `signature` must be called
+/// through the dynamic trampolines, rather than directly by the
+/// WebAssembly code.
+///
+/// On Apple Silicon, the arguments which don't fit in the registers
+/// are passed on the stack packed to their natural size, whereas
+/// Cranelift passes them in 8-byte slots, as the standard AArch64
+/// calling convention does. The host functions taking that many
+/// arguments are thus called from Rust, which follows the calling
+/// convention of the host.
+fn needs_dynamic_trampoline(signature: &FunctionType) -> bool {
+    if !cfg!(all(target_os = "macos", target_arch = "aarch64")) {
+        return false;
+    }
+    // The environment takes the first integer register.
+    let (mut integers, mut floats) = (1, 0);
+    for param in signature.params() {
+        match param {
+            Type::F32 | Type::F64 | Type::V128 => floats += 1,
+            Type::I32 | Type::I64 | Type::ExternRef | Type::FuncRef => integers += 1,
+        }
+    }
+    // AArch64 passes up to 8 integer and 8 floating-point arguments
+    // in registers.
+    integers > 8 || floats > 8
+}
+
+/// Calls the static host function at `address`, taking `Args` and
+/// returning `Rets`, with `env` and `args`, and returns its results.
+///
+/// # Safety
+///
+/// `address` must be such a static host function, and `args` must
+/// match `Args`.
+unsafe fn call_static_host_function<Args: WasmTypeList, Rets: WasmTypeList>(
+    address: *const VMFunctionBody,
+    env: VMFunctionEnvironment,
+    args: &[Val],
+) -> Vec<Val> {
+    let mut params = Args::empty_array();
+    for (param, arg) in params.as_mut().iter_mut().zip(args) {
+        arg.write_value_to(param);
+    }
+    let results = Args::from_array(params).call_static_host_function::<Rets>(address, env);
+    let mut results = Rets::from_c_struct(results).into_array();
+    results
+        .as_mut()
+        .iter()
+        .zip(Rets::wasm_types())
+        .map(|(result, ty)| Val::read_value_from(result, *ty))
+        .collect()
+}
+
 impl<'a> Exportable<'a> for Function {
     fn to_export(&self) -> Export {
         self.exported.clone().into()
@@ -962,7 +1082,10 @@ impl<T: VMDynamicFunction> VMDynamicFunctionCall<T> for VMDynamicFunctionContext
             for (i, ty) in func_ty.params().iter().enumerate() {
                 args.push(Val::read_value_from(values_vec.add(i), *ty));
             }
-            let returns = self.ctx.call(&args)?;
+            let returns =
+                call_hook::dynamic_host_call(self as *const Self as usize, &args, || {
+                    self.ctx.call(&args)
+                })?;
 
             // We need to dynamically check that the returns
             // match the expected types, as well as expected length.
@@ -982,8 +1105,14 @@ impl<T: VMDynamicFunction> VMDynamicFunctionCall<T> for VMDynamicFunctionContext
 
         match result {
             Ok(Ok(())) => {}
-            Ok(Err(trap)) => raise_user_trap(Box::new(trap)),
-            Err(panic) => resume_panic(panic),
+            Ok(Err(trap)) => {
+                event_log::host_call_failed(&trap);
+                raise_user_trap(Box::new(trap))
+            }
+            Err(panic) => {
+                event_log::host_call_panicked(&*panic);
+                resume_panic(panic)
+            }
         }
     }
 }
@@ -997,7 +1126,10 @@ mod inner {
     use std::marker::PhantomData;
     use std::panic::{self, AssertUnwindSafe};
     use wasmer_types::{FunctionType, NativeWasmType, Type};
-    use wasmer_vm::{raise_user_trap, resume_panic, VMFunctionBody};
+    use wasmer_vm::{raise_user_trap, resume_panic, VMFunctionBody, VMFunctionEnvironment};
+
+    use crate::sys::call_hook;
+    use crate::sys::event_log;
 
     /// A trait to convert a Rust value to a `WasmNativeType` value,
     /// or to convert `WasmNativeType` value to a Rust value.
@@ -1159,6 +1291,20 @@ mod inner {
         /// Get the Wasm types for the tuple (list) of currently
         /// represented values.
         fn wasm_types() -> &'static [Type];
+
+        /// Calls the static host function at `address` with `env`
+        /// and the tuple (list) of values, from Rust, so with the C
+        /// calling convention of the host, and returns its results.
+        ///
+        /// # Safety
+        ///
+        /// `address` must be a static host function taking `env` and
+        /// the represented values, and returning `Rets`.
+        unsafe fn call_static_host_function<Rets: WasmTypeList>(
+            self,
+            address: *const VMFunctionBody,
+            env: VMFunctionEnvironment,
+        ) -> Rets::CStruct;
     }
 
     /// The `IntoResult` trait turns a `WasmTypeList` into a
@@ -1435,6 +1581,19 @@ mod inner {
                         ),*
                     ]
                 }
+
+                #[allow(unused_parens, non_snake_case)]
+                unsafe fn call_static_host_function<Rets: WasmTypeList>(
+                    self,
+                    address: *const VMFunctionBody,
+                    env: VMFunctionEnvironment,
+                ) -> Rets::CStruct {
+                    // Unpack items of the tuple.
+                    let ( $( $x ),* ) = self;
+
+                    let function = std::mem::transmute::<_, unsafe extern "C" fn( VMFunctionEnvironment, $( $x::Native, )* ) -> Rets::CStruct>(address);
+                    function( env, $( FromToNativeWasmType::to_native($x), )* )
+                }
             }
 
             // Implement `HostFunction` for a function that has the same arity than the tuple.
@@ -1464,13 +1623,24 @@ mod inner {
                     {
                         let func: &Func = unsafe { &*(&() as *const () as *const Func) };
                         let result = panic::catch_unwind(AssertUnwindSafe(|| {
-                            func( $( FromToNativeWasmType::from_native($x) ),* ).into_result()
+                            $( let $x: $x = FromToNativeWasmType::from_native($x); )*
+                            call_hook::native_host_call::<Func, Rets, _>(
+                                func_wrapper::< $( $x, )* Rets, RetsAsResult, Func > as usize,
+                                || vec![ $( $x.to_native().to_value() ),* ],
+                                || func( $( $x ),* ).into_result(),
+                            )
                         }));
 
                         match result {
                             Ok(Ok(result)) => return result.into_c_struct(),
-                            Ok(Err(trap)) => unsafe { raise_user_trap(Box::new(trap)) },
-                            Err(panic) => unsafe { resume_panic(panic) },
+                            Ok(Err(trap)) => {
+                                event_log::host_call_failed(&trap);
+                                unsafe { raise_user_trap(Box::new(trap)) }
+                            }
+                            Err(panic) => {
+                                event_log::host_call_panicked(&*panic);
+                                unsafe { resume_panic(panic) }
+                            }
                         }
                     }
 
@@ -1508,13 +1678,24 @@ mod inner {
                         let func: &Func = unsafe { &*(&() as *const () as *const Func) };
 
                         let result = panic::catch_unwind(AssertUnwindSafe(|| {
-                            func(env, $( FromToNativeWasmType::from_native($x) ),* ).into_result()
+                            $( let $x: $x = FromToNativeWasmType::from_native($x); )*
+                            call_hook::native_host_call::<Func, Rets, _>(
+                                func_wrapper::< $( $x, )* Rets, RetsAsResult, Env, Func > as usize,
+                                || vec![ $( $x.to_native().to_value() ),* ],
+                                || func(env, $( $x ),* ).into_result(),
+                            )
                         }));
 
                         match result {
                             Ok(Ok(result)) => return result.into_c_struct(),
-                            Ok(Err(trap)) => unsafe { raise_user_trap(Box::new(trap)) },
-                            Err(panic) => unsafe { resume_panic(panic) },
+                            Ok(Err(trap)) => {
+                                event_log::host_call_failed(&trap);
+                                unsafe { raise_user_trap(Box::new(trap)) }
+                            }
+                            Err(panic) => {
+                                event_log::host_call_panicked(&*panic);
+                                unsafe { resume_panic(panic) }
+                            }
                         }
                     }
 
@@ -1554,13 +1735,24 @@ mod inner {
                         let func: &Func = unsafe { &*(&() as *const () as *const Func) };
 
                         let result = panic::catch_unwind(AssertUnwindSafe(|| {
-                            func(env, $( FromToNativeWasmType::from_native($x) ),* ).into_result()
+                            $( let $x: $x = FromToNativeWasmType::from_native($x); )*
+                            call_hook::native_host_call::<Func, Rets, _>(
+                                func_wrapper::< $( $x, )* Rets, RetsAsResult, Env, Func > as usize,
+                                || vec![ $( $x.to_native().to_value() ),* ],
+                                || func(env, $( $x ),* ).into_result(),
+                            )
                         }));
 
                         match result {
                             Ok(Ok(result)) => return result.into_c_struct(),
-                            Ok(Err(trap)) => unsafe { raise_user_trap(Box::new(trap)) },
-                            Err(panic) => unsafe { resume_panic(panic) },
+                            Ok(Err(trap)) => {
+                                event_log::host_call_failed(&trap);
+                                unsafe { raise_user_trap(Box::new(trap)) }
+                            }
+                            Err(panic) => {
+                                event_log::host_call_panicked(&*panic);
+                                unsafe { resume_panic(panic) }
+                            }
                         }
                     }
 
@@ -1647,6 +1839,14 @@ mod inner {
         fn wasm_types() -> &'static [Type] {
             &[]
         }
+
+        unsafe fn call_static_host_function<Rets: WasmTypeList>(
+            self,
+            _: *const VMFunctionBody,
+            _: VMFunctionEnvironment,
+        ) -> Rets::CStruct {
+            unreachable!()
+        }
     }
 
     #[cfg(test)]
@@ -1,13 +1,15 @@
-use crate::exports::{ExportError, Exportable};
-use crate::externals::Extern;
-use crate::store::{Store, StoreObject};
-use crate::types::Val;
+use crate::sys::exports::{ExportError, Exportable};
+use crate::sys::externals::{Extern, FromToNativeWasmType};
+use crate::sys::store::{Store, StoreObject};
+use crate::sys::types::{Val, ValFuncRef, ValType};
 use crate::GlobalType;
 use crate::Mutability;
 use crate::RuntimeError;
 use std::fmt;
+use std::marker::PhantomData;
 use std::sync::Arc;
 use wasmer_engine::{Export, ExportGlobal};
+use wasmer_types::{ExternRef, NativeWasmType};
 use wasmer_vm::{Global as RuntimeGlobal, VMExportGlobal};
 
 /// A WebAssembly `global` instance.
@@ -15,6 +17,11 @@ use wasmer_vm::{Global as RuntimeGlobal, VMExportGlobal};
 /// A global instance is the runtime representation of a global variable.
 /// It consists of an individual value and a flag indicating whether it is mutable.
 ///
+/// A global created by the host can be imported by many instances of
+/// the same [`Store`] at once: they all read and write the same value,
+/// as does the host with [`Global::get`] and [`Global::set`], so it
+/// can hold a configuration shared by the instances.
+///
 /// Spec: <https://webassembly.github.io/spec/core/exec/runtime.html#global-instances>
 #[derive(Clone)]
 pub struct Global {
@@ -67,9 +74,13 @@ impl Global {
             ty: val.ty(),
         });
         unsafe {
-            global
-                .set_unchecked(val.clone())
-                .map_err(|e| RuntimeError::new(format!("create global for {:?}: {}", val, e)))?;
+            match &val {
+                Val::FuncRef(_) => {
+                    global.set_anyfunc_unchecked(Some(val.into_checked_anyfunc(store)?))
+                }
+                _ => global.set_unchecked(val.clone()),
+            }
+            .map_err(|e| RuntimeError::new(format!("create global for {:?}: {}", val, e)))?;
         };
 
         Ok(Self {
@@ -114,6 +125,9 @@ impl Global {
 
     /// Retrieves the current value [`Val`] that the Global has.
     ///
+    /// The function of a `funcref` global can be called from the host,
+    /// and a null reference is returned as a null `externref`.
+    ///
     /// # Example
     ///
     /// ```
@@ -125,6 +139,14 @@ impl Global {
     /// assert_eq!(g.get(), Value::I32(1));
     /// ```
     pub fn get(&self) -> Val {
+        if self.ty().ty == ValType::FuncRef {
+            // A null function reference is returned as a null `externref`,
+            // like the null elements of the tables.
+            return match self.global.get_anyfunc().unwrap() {
+                Some(anyfunc) => Val::from_checked_anyfunc(anyfunc, &self.store),
+                None => Val::ExternRef(ExternRef::Null),
+            };
+        }
         self.global.get()
     }
 
@@ -174,13 +196,55 @@ impl Global {
             return Err(RuntimeError::new("cross-`Store` values are not supported"));
         }
         unsafe {
-            self.global
-                .set(val)
-                .map_err(|e| RuntimeError::new(format!("{}", e)))?;
+            match val {
+                Val::FuncRef(_) => self
+                    .global
+                    .set_anyfunc(Some(val.into_checked_anyfunc(&self.store)?)),
+                Val::ExternRef(ExternRef::Null) if self.ty().ty == ValType::FuncRef => {
+                    self.global.set_anyfunc(None)
+                }
+                _ => self.global.set(val),
+            }
+            .map_err(|e| RuntimeError::new(format!("{}", e)))?;
         }
         Ok(())
     }
 
+    /// Returns a view of this global with the Rust type `T`, to get
+    /// and set its value without converting it from and to a [`Val`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Global, Store, Value};
+    /// # let store = Store::default();
+    /// #
+    /// let g = Global::new_mut(&store, Value::I64(1));
+    /// let typed = g.typed::<i64>().unwrap();
+    ///
+    /// typed.set(2).unwrap();
+    /// assert_eq!(typed.get(), 2);
+    /// assert_eq!(g.get(), Value::I64(2));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `T` doesn't match the type of the global.
+    pub fn typed<T: FromToNativeWasmType>(&self) -> Result<TypedGlobal<T>, RuntimeError> {
+        let ty = self.ty().ty;
+        if ty != T::Native::WASM_TYPE {
+            return Err(RuntimeError::new(format!(
+                "the global has type {} rather than {}",
+                ty,
+                T::Native::WASM_TYPE
+            )));
+        }
+        Ok(TypedGlobal {
+            global: self.clone(),
+            _phantom: PhantomData,
+        })
+    }
+
     pub(crate) fn from_vm_export(store: &Store, wasmer_export: ExportGlobal) -> Self {
         Self {
             store: store.clone(),
@@ -215,6 +279,43 @@ impl fmt::Debug for Global {
     }
 }
 
+/// A [`Global`] holding values of the Rust type `T`, created with
+/// [`Global::typed`].
+#[derive(Clone)]
+pub struct TypedGlobal<T> {
+    global: Global,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: FromToNativeWasmType> TypedGlobal<T> {
+    /// Returns the current value of the global.
+    pub fn get(&self) -> T {
+        let mut binary = 0i128;
+        unsafe { self.global.get().write_value_to(&mut binary) };
+        T::from_native(T::Native::from_binary(binary))
+    }
+
+    /// Sets the value of the global.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the global is immutable.
+    pub fn set(&self, value: T) -> Result<(), RuntimeError> {
+        self.global.set(value.to_native().to_value())
+    }
+
+    /// Returns the underlying [`Global`].
+    pub fn global(&self) -> &Global {
+        &self.global
+    }
+}
+
+impl<T> fmt::Debug for TypedGlobal<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.global.fmt(f)
+    }
+}
+
 impl<'a> Exportable<'a> for Global {
     fn to_export(&self) -> Export {
         ExportGlobal {
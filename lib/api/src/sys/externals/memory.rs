@@ -1,13 +1,17 @@
-use crate::exports::{ExportError, Exportable};
-use crate::externals::Extern;
-use crate::store::Store;
+use crate::sys::event_log;
+use crate::sys::exports::{ExportError, Exportable};
+use crate::sys::externals::Extern;
+use crate::sys::memory_growth::{self, GrowthSubscription, MemoryGrowth};
+use crate::sys::store::Store;
 use crate::{MemoryType, MemoryView};
 use std::convert::TryInto;
 use std::slice;
 use std::sync::Arc;
 use wasmer_engine::{Export, ExportMemory};
-use wasmer_types::{Pages, ValueType};
-use wasmer_vm::{Memory as RuntimeMemory, MemoryError, VMExportMemory};
+use wasmer_types::{MemoryAccessError, Pages, ValueType};
+use wasmer_vm::{
+    CustomMemory, Memory as RuntimeMemory, MemoryError, MemoryStorage, VMExportMemory,
+};
 
 /// A WebAssembly `memory` instance.
 ///
@@ -22,6 +26,11 @@ use wasmer_vm::{Memory as RuntimeMemory, MemoryError, VMExportMemory};
 /// A memory created by the host or in WebAssembly code will be accessible and
 /// mutable from both host and WebAssembly.
 ///
+/// A memory created by the host can be imported by many instances of
+/// the same [`Store`] at once: they all see the same bytes, even once
+/// one of them grows it, so it can serve as a scratch space shared by
+/// the instances.
+///
 /// Spec: <https://webassembly.github.io/spec/core/exec/runtime.html#memory-instances>
 #[derive(Debug, Clone)]
 pub struct Memory {
@@ -33,7 +42,7 @@ impl Memory {
     /// Creates a new host `Memory` from the provided [`MemoryType`].
     ///
     /// This function will construct the `Memory` using the store
-    /// [`BaseTunables`][crate::tunables::BaseTunables].
+    /// [`BaseTunables`][crate::BaseTunables].
     ///
     /// # Example
     ///
@@ -54,6 +63,55 @@ impl Memory {
         })
     }
 
+    /// Creates a new host `Memory` whose bytes are held by `storage`,
+    /// like a buffer or a file mapping of the host, so it can be paged
+    /// to disk or shared with another process. The bytes of `storage`
+    /// in the minimum size of the memory are kept.
+    ///
+    /// The [`Tunables`] of the store must give the memory the
+    /// [`MemoryStyle::Dynamic`] style without an offset guard, as the
+    /// storage doesn't have guard pages. The modules importing the
+    /// memory must be compiled with the same tunables. A memory of an
+    /// instance can also be held by a custom storage, by returning a
+    /// [`CustomMemory`] from [`Tunables::create_vm_memory`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{BaseTunables, Memory, MemoryType, Pages, Store, JIT, Cranelift};
+    /// # use wasmer::vm::BoundsCheck;
+    /// # let engine = JIT::new(Cranelift::default()).engine();
+    /// let tunables = BaseTunables {
+    ///     static_memory_bound: Pages(0),
+    ///     static_memory_offset_guard_size: 0,
+    ///     dynamic_memory_offset_guard_size: 0,
+    ///     memory_bounds_check: BoundsCheck::GuardPages,
+    /// };
+    /// let store = Store::new_with_tunables(&engine, tunables);
+    /// let storage = vec![0; 65536];
+    /// let m = Memory::from_custom(&store, MemoryType::new(1, None, false), storage).unwrap();
+    /// ```
+    ///
+    /// [`Tunables`]: crate::Tunables
+    /// [`Tunables::create_vm_memory`]: crate::Tunables::create_vm_memory
+    /// [`MemoryStyle::Dynamic`]: crate::vm::MemoryStyle::Dynamic
+    /// [`CustomMemory`]: crate::vm::CustomMemory
+    pub fn from_custom(
+        store: &Store,
+        ty: MemoryType,
+        storage: impl MemoryStorage,
+    ) -> Result<Self, MemoryError> {
+        let style = store.tunables().memory_style(&ty);
+        let memory = Arc::new(CustomMemory::new(&ty, &style, storage)?);
+        Ok(Self {
+            store: store.clone(),
+            memory: event_log::logged_memory(
+                store.event_log(),
+                memory_growth::notifying_memory(store.growth_subscribers(), memory),
+            ),
+        })
+    }
+
     /// Returns the [`MemoryType`] of the `Memory`.
     ///
     /// # Example
@@ -184,7 +242,9 @@ impl Memory {
     /// Return a "view" of the currently accessible memory. By
     /// default, the view is unsynchronized, using regular memory
     /// accesses. You can force a memory view to use atomic accesses
-    /// by calling the [`MemoryView::atomically`] method.
+    /// by calling the [`MemoryView::atomically`] method. The values of
+    /// another type in a part of the view are viewed with
+    /// [`MemoryView::slice`].
     ///
     /// # Notes:
     ///
@@ -220,6 +280,99 @@ impl Memory {
         unsafe { MemoryView::new(base as _, length as u32) }
     }
 
+    /// Copies the bytes of the memory starting at `offset` to `buf`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Memory, MemoryType, Store};
+    /// # let store = Store::default();
+    /// #
+    /// let m = Memory::new(&store, MemoryType::new(1, None, false)).unwrap();
+    /// m.write(0x1000, b"hello").unwrap();
+    ///
+    /// let mut buf = [0; 5];
+    /// m.read(0x1000, &mut buf).unwrap();
+    /// assert_eq!(&buf, b"hello");
+    /// assert!(m.read(0xfffe, &mut buf).is_err());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryAccessError::OutOfBounds`] if the bytes aren't
+    /// all in the memory, and then nothing is copied.
+    ///
+    /// # Concurrency
+    ///
+    /// The range is checked against the size of the memory when the
+    /// bytes are copied: the pages added by a growth of the memory
+    /// are accessible once the growth returns. The bytes are copied
+    /// without synchronization, so the bytes written at the same time
+    /// by another thread, to a shared memory, may be read partially.
+    /// A memory which isn't shared may move when it grows, so it must
+    /// not be grown by another thread while its bytes are copied.
+    pub fn read(&self, offset: u64, buf: &mut [u8]) -> Result<(), MemoryAccessError> {
+        let offset = offset
+            .try_into()
+            .map_err(|_| MemoryAccessError::OutOfBounds)?;
+        self.view::<u8>().slice(offset, buf.len())?.copy_to(buf);
+        Ok(())
+    }
+
+    /// Copies the bytes of `data` to the memory, starting at `offset`.
+    ///
+    /// See [`Memory::read`] for an example, and how it behaves when
+    /// the memory is grown or accessed by another thread.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryAccessError::OutOfBounds`] if the bytes aren't
+    /// all in the memory, and then nothing is copied.
+    pub fn write(&self, offset: u64, data: &[u8]) -> Result<(), MemoryAccessError> {
+        let offset = offset
+            .try_into()
+            .map_err(|_| MemoryAccessError::OutOfBounds)?;
+        self.view::<u8>().slice(offset, data.len())?.copy_from(data);
+        Ok(())
+    }
+
+    /// Subscribes `callback` to the growth of the memory, until the
+    /// returned [`GrowthSubscription`] is dropped, so the views and
+    /// the pointers into the bytes of the memory can be invalidated:
+    /// the memory may move when it grows.
+    ///
+    /// The callback is called when the memory grows, by
+    /// [`Memory::grow`] or by the `memory.grow` instruction, on the
+    /// thread growing it, before the growth returns. It must not panic,
+    /// as it may be called from WebAssembly code. The callback is only
+    /// called if the memory was created in the store of this `Memory`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Memory, MemoryType, Pages, Store};
+    /// # use std::sync::{Arc, Mutex};
+    /// # let store = Store::default();
+    /// #
+    /// let m = Memory::new(&store, MemoryType::new(1, None, false)).unwrap();
+    /// let sizes = Arc::new(Mutex::new(vec![]));
+    /// let sizes_ = sizes.clone();
+    /// let subscription = m.subscribe_growth(move |growth| {
+    ///     sizes_.lock().unwrap().push((growth.old_size, growth.new_size));
+    /// });
+    /// m.grow(2).unwrap();
+    ///
+    /// assert_eq!(*sizes.lock().unwrap(), vec![(Pages(1), Pages(3))]);
+    /// ```
+    pub fn subscribe_growth<F>(&self, callback: F) -> GrowthSubscription
+    where
+        F: Fn(&MemoryGrowth) + Send + Sync + 'static,
+    {
+        self.store
+            .growth_subscribers()
+            .subscribe(self.memory.vmmemory(), Arc::new(callback))
+    }
+
     pub(crate) fn from_vm_export(store: &Store, wasmer_export: ExportMemory) -> Self {
         Self {
             store: store.clone(),
@@ -2,6 +2,7 @@ pub(crate) mod function;
 mod global;
 mod memory;
 mod table;
+mod table_interposer;
 
 pub use self::function::{
     FromToNativeWasmType, Function, HostFunction, WasmTypeList, WithEnv, WithoutEnv,
@@ -9,12 +10,13 @@ pub use self::function::{
 
 #[cfg(feature = "deprecated")]
 pub use self::function::{UnsafeMutableEnv, WithUnsafeMutableEnv};
-pub use self::global::Global;
+pub use self::global::{Global, TypedGlobal};
 pub use self::memory::Memory;
 pub use self::table::Table;
+pub use self::table_interposer::{IndirectCallAction, TableInterposer};
 
-use crate::exports::{ExportError, Exportable};
-use crate::store::{Store, StoreObject};
+use crate::sys::exports::{ExportError, Exportable};
+use crate::sys::store::{Store, StoreObject};
 use crate::ExternType;
 use std::fmt;
 use wasmer_engine::Export;
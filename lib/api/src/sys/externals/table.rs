@@ -1,7 +1,8 @@
-use crate::exports::{ExportError, Exportable};
-use crate::externals::Extern;
-use crate::store::Store;
-use crate::types::{Val, ValFuncRef};
+use crate::sys::exports::{ExportError, Exportable};
+use crate::sys::externals::table_interposer::{IndirectCallAction, TableInterposer};
+use crate::sys::externals::Extern;
+use crate::sys::store::Store;
+use crate::sys::types::{Val, ValFuncRef};
 use crate::RuntimeError;
 use crate::TableType;
 use std::sync::Arc;
@@ -37,7 +38,7 @@ impl Table {
     /// All the elements in the table will be set to the `init` value.
     ///
     /// This function will construct the `Table` using the store
-    /// [`BaseTunables`][crate::tunables::BaseTunables].
+    /// [`BaseTunables`][crate::BaseTunables].
     pub fn new(store: &Store, ty: TableType, init: Val) -> Result<Self, RuntimeError> {
         let item = init.into_checked_anyfunc(store)?;
         let tunables = store.tunables();
@@ -68,12 +69,34 @@ impl Table {
     }
 
     /// Retrieves an element of the table at the provided `index`.
+    ///
+    /// The functions of the table, defined by the instances or by the
+    /// host, can be called from the host with [`Function::call`], or
+    /// with the typed [`Function::native`]. A null element is returned
+    /// as a null `externref`.
+    ///
+    /// [`Function::call`]: crate::Function::call
+    /// [`Function::native`]: crate::Function::native
     pub fn get(&self, index: u32) -> Option<Val> {
         let item = self.table.get(index)?;
         Some(ValFuncRef::from_checked_anyfunc(item, &self.store))
     }
 
     /// Sets an element `val` in the Table at the provided `index`.
+    ///
+    /// The element can be any function of the store, including the
+    /// host functions created with [`Function::new_native`], so the
+    /// host can patch the tables of an instance after instantiating
+    /// it. The dynamic host functions created with [`Function::new`]
+    /// can't be stored in a table.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `index` is out of bounds for the table,
+    /// or if `val` can't be stored in the table.
+    ///
+    /// [`Function::new_native`]: crate::Function::new_native
+    /// [`Function::new`]: crate::Function::new
     pub fn set(&self, index: u32, val: Val) -> Result<(), RuntimeError> {
         let item = val.into_checked_anyfunc(&self.store)?;
         set_table_item(self.table.as_ref(), index, item)
@@ -109,6 +132,20 @@ impl Table {
         }
     }
 
+    /// Sets the `len` elements of the `Table` starting at `index` to
+    /// `val`, as the `table.fill` instruction.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the range is out of bounds for the table,
+    /// or if `val` can't be stored in the table (see [`Table::set`]).
+    pub fn fill(&self, index: u32, val: Val, len: u32) -> Result<(), RuntimeError> {
+        let item = val.into_checked_anyfunc(&self.store)?;
+        self.table
+            .fill(index, item, len)
+            .map_err(RuntimeError::from_trap)
+    }
+
     /// Copies the `len` elements of `src_table` starting at `src_index`
     /// to the destination table `dst_table` at index `dst_index`.
     ///
@@ -139,6 +176,55 @@ impl Table {
         Ok(())
     }
 
+    /// Interposes the host `policy` on every `call_indirect` going through
+    /// this `Table`.
+    ///
+    /// The policy receives the table index targeted by the guest and decides
+    /// whether the call is allowed, denied (the guest traps) or redirected
+    /// to the function stored at another index. The interposition lasts
+    /// until the returned [`TableInterposer`] is dropped.
+    ///
+    /// The interposition stubs are compiled with the [`Store`] engine, so
+    /// this requires an engine with a compiler attached.
+    ///
+    /// # Usage
+    ///
+    /// ```
+    /// # use wasmer::{imports, wat2wasm, IndirectCallAction, Instance, Module, Store, Value};
+    /// # let store = Store::default();
+    /// # let wasm_bytes = wat2wasm(r#"
+    /// # (module
+    /// #   (type $t (func (result i32)))
+    /// #   (table (export "table") 2 funcref)
+    /// #   (elem (i32.const 0) $one $two)
+    /// #   (func $one (result i32) i32.const 1)
+    /// #   (func $two (result i32) i32.const 2)
+    /// #   (func (export "call") (param i32) (result i32)
+    /// #     local.get 0
+    /// #     call_indirect (type $t)))
+    /// # "#.as_bytes()).unwrap();
+    /// # let module = Module::new(&store, wasm_bytes).unwrap();
+    /// # let instance = Instance::new(&module, &imports! {}).unwrap();
+    /// let table = instance.exports.get_table("table").unwrap();
+    /// let call = instance.exports.get_function("call").unwrap();
+    ///
+    /// let _interposer = table
+    ///     .interpose(|index| match index {
+    ///         0 => IndirectCallAction::Redirect(1),
+    ///         _ => IndirectCallAction::Deny,
+    ///     })
+    ///     .unwrap();
+    ///
+    /// assert_eq!(call.call(&[Value::I32(0)]).unwrap().to_vec(), vec![Value::I32(2)]);
+    /// assert!(call.call(&[Value::I32(1)]).is_err());
+    /// ```
+    pub fn interpose<F>(&self, policy: F) -> Result<TableInterposer, RuntimeError>
+    where
+        F: Fn(u32) -> IndirectCallAction + Send + Sync + 'static,
+    {
+        TableInterposer::new(self, Arc::new(policy))
+    }
+
     pub(crate) fn from_vm_export(store: &Store, wasmer_export: ExportTable) -> Self {
         Self {
             store: store.clone(),
@@ -0,0 +1,362 @@
+//! Host interposition of `call_indirect` through a [`Table`].
+//!
+//! Interposing a table replaces every function reference stored in it
+//! with a small stub compiled on the fly. Each stub asks the host
+//! policy what to do with a call targeting its slot and then forwards
+//! the call through a shadow copy of the original table, so the
+//! signature check of `call_indirect` still applies to the final
+//! target.
+
+use crate::sys::exports::Exports;
+use crate::sys::externals::{Function, Table};
+use crate::sys::import_object::ImportObject;
+use crate::sys::instance::Instance;
+use crate::sys::module::Module;
+use crate::sys::types::{Val, ValType};
+use crate::{FunctionType, RuntimeError, TableType, WasmerEnv};
+use std::fmt;
+use std::sync::Arc;
+
+/// The decision taken by a host policy for an intercepted `call_indirect`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndirectCallAction {
+    /// Perform the call to the original target.
+    Allow,
+    /// Refuse the call: the guest traps with a descriptive error.
+    Deny,
+    /// Call the function originally stored at the given table index
+    /// instead. The redirected function must have the signature the
+    /// guest expects, otherwise the call traps.
+    Redirect(u32),
+}
+
+type IndirectCallPolicy = dyn Fn(u32) -> IndirectCallAction + Send + Sync;
+
+#[derive(Clone)]
+struct InterposerEnv {
+    policy: Arc<IndirectCallPolicy>,
+    size: u32,
+}
+
+impl WasmerEnv for InterposerEnv {}
+
+fn policy_trampoline(env: &InterposerEnv, index: u32) -> Result<u32, RuntimeError> {
+    match (env.policy)(index) {
+        IndirectCallAction::Allow => Ok(index),
+        IndirectCallAction::Deny => Err(RuntimeError::new(format!(
+            "indirect call to table index {} denied by host policy",
+            index
+        ))),
+        IndirectCallAction::Redirect(target) if target < env.size => Ok(target),
+        IndirectCallAction::Redirect(target) => Err(RuntimeError::new(format!(
+            "indirect call to table index {} redirected out of bounds (to {})",
+            index, target
+        ))),
+    }
+}
+
+/// A guard over a [`Table`] whose indirect calls are interposed by the host.
+///
+/// It's created with [`Table::interpose`]. Dropping the guard puts the
+/// original function references back in every slot that still holds an
+/// interposition stub.
+///
+/// # Limitations
+///
+/// Only the entries present when the table is interposed are covered:
+/// elements written afterwards (by the guest or the host) are called
+/// directly. Call [`TableInterposer::refresh`] to cover them.
+pub struct TableInterposer {
+    table: Table,
+    shadow: Table,
+    stubs: Vec<(u32, Function)>,
+    policy: Arc<IndirectCallPolicy>,
+}
+
+impl TableInterposer {
+    pub(crate) fn new(
+        table: &Table,
+        policy: Arc<IndirectCallPolicy>,
+    ) -> Result<Self, RuntimeError> {
+        if table.ty().ty != ValType::FuncRef {
+            return Err(RuntimeError::new("only `funcref` tables can be interposed"));
+        }
+        let store = table.store();
+        let shadow = Table::new(
+            store,
+            TableType::new(ValType::FuncRef, table.size(), None),
+            Val::null(),
+        )?;
+        let mut interposer = Self {
+            table: table.clone(),
+            shadow,
+            stubs: vec![],
+            policy,
+        };
+        interposer.install()?;
+        Ok(interposer)
+    }
+
+    /// Returns the interposed [`Table`].
+    pub fn table(&self) -> &Table {
+        &self.table
+    }
+
+    /// Interposes again all the entries of the table, including the
+    /// ones written or added by growing the table since the last time.
+    pub fn refresh(&mut self) -> Result<(), RuntimeError> {
+        self.restore();
+        let size = self.table.size();
+        if size > self.shadow.size() {
+            self.shadow.grow(size - self.shadow.size(), Val::null())?;
+        }
+        self.install()
+    }
+
+    /// Copies the current entries into the shadow table and replaces
+    /// every non-null entry with its stub.
+    fn install(&mut self) -> Result<(), RuntimeError> {
+        let size = self.table.size();
+        Table::copy(&self.shadow, 0, &self.table, 0, size)?;
+
+        let targets = (0..size)
+            .filter_map(|index| match self.table.get(index) {
+                Some(Val::FuncRef(function)) => Some((index, function.ty().clone())),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if targets.is_empty() {
+            return Ok(());
+        }
+
+        let store = self.table.store();
+        let module =
+            Module::from_binary(store, &encode_stub_module(&targets, size)).map_err(|e| {
+                RuntimeError::new(format!("failed to compile interposition stubs: {}", e))
+            })?;
+        let env = InterposerEnv {
+            policy: self.policy.clone(),
+            size,
+        };
+        let mut namespace = Exports::new();
+        namespace.insert(
+            "policy",
+            Function::new_native_with_env(store, env, policy_trampoline),
+        );
+        namespace.insert("table", self.shadow.clone());
+        let mut import_object = ImportObject::new();
+        import_object.register("host", namespace);
+        let instance = Instance::new(&module, &import_object)
+            .map_err(|e| RuntimeError::new(format!("failed to link interposition stubs: {}", e)))?;
+
+        for (index, _) in targets {
+            let stub = instance
+                .exports
+                .get_function(&index.to_string())
+                .map_err(|e| RuntimeError::new(e.to_string()))?
+                .clone();
+            self.table.set(index, Val::FuncRef(stub.clone()))?;
+            self.stubs.push((index, stub));
+        }
+        Ok(())
+    }
+
+    /// Puts the original entries back where the stubs weren't overwritten.
+    fn restore(&mut self) {
+        for (index, stub) in self.stubs.drain(..) {
+            let still_interposed = match self.table.get(index) {
+                Some(Val::FuncRef(current)) => {
+                    current.checked_anyfunc().func_ptr == stub.checked_anyfunc().func_ptr
+                }
+                _ => false,
+            };
+            if still_interposed {
+                if let Some(original) = self.shadow.get(index) {
+                    let _ = self.table.set(index, original);
+                }
+            }
+        }
+    }
+}
+
+impl Drop for TableInterposer {
+    fn drop(&mut self) {
+        self.restore();
+    }
+}
+
+impl fmt::Debug for TableInterposer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TableInterposer")
+            .field("interposed_entries", &self.stubs.len())
+            .finish()
+    }
+}
+
+/// Encodes a module importing `host.policy` and `host.table`, exporting
+/// one stub per target, named after its table index.
+fn encode_stub_module(targets: &[(u32, FunctionType)], table_size: u32) -> Vec<u8> {
+    // Type 0 is the policy: `(param i32) (result i32)`.
+    let mut types: Vec<FunctionType> =
+        vec![FunctionType::new(vec![ValType::I32], vec![ValType::I32])];
+    let mut stub_types = Vec::with_capacity(targets.len());
+    for (_, ty) in targets {
+        let type_index = match types.iter().position(|t| t == ty) {
+            Some(position) => position,
+            None => {
+                types.push(ty.clone());
+                types.len() - 1
+            }
+        };
+        stub_types.push(type_index as u32);
+    }
+
+    let mut module = b"\0asm\x01\0\0\0".to_vec();
+
+    let mut section = vec![];
+    write_u32(&mut section, types.len() as u32);
+    for ty in &types {
+        section.push(0x60);
+        write_u32(&mut section, ty.params().len() as u32);
+        section.extend(ty.params().iter().map(|t| encode_type(*t)));
+        write_u32(&mut section, ty.results().len() as u32);
+        section.extend(ty.results().iter().map(|t| encode_type(*t)));
+    }
+    write_section(&mut module, 1, &section);
+
+    let mut section = vec![];
+    write_u32(&mut section, 2);
+    write_name(&mut section, "host");
+    write_name(&mut section, "policy");
+    section.extend(&[0x00, 0x00]);
+    write_name(&mut section, "host");
+    write_name(&mut section, "table");
+    section.extend(&[0x01, 0x70, 0x00]);
+    write_u32(&mut section, table_size);
+    write_section(&mut module, 2, &section);
+
+    let mut section = vec![];
+    write_u32(&mut section, stub_types.len() as u32);
+    for type_index in &stub_types {
+        write_u32(&mut section, *type_index);
+    }
+    write_section(&mut module, 3, &section);
+
+    let mut section = vec![];
+    write_u32(&mut section, targets.len() as u32);
+    for (function_index, (index, _)) in targets.iter().enumerate() {
+        write_name(&mut section, &index.to_string());
+        section.push(0x00);
+        // The imported policy takes the function index 0.
+        write_u32(&mut section, function_index as u32 + 1);
+    }
+    write_section(&mut module, 7, &section);
+
+    let mut section = vec![];
+    write_u32(&mut section, targets.len() as u32);
+    for ((index, ty), type_index) in targets.iter().zip(stub_types) {
+        let mut body = vec![0x00];
+        for param in 0..ty.params().len() {
+            body.push(0x20);
+            write_u32(&mut body, param as u32);
+        }
+        body.push(0x41);
+        write_i32(&mut body, *index as i32);
+        body.extend(&[0x10, 0x00, 0x11]);
+        write_u32(&mut body, type_index);
+        body.extend(&[0x00, 0x0b]);
+        write_u32(&mut section, body.len() as u32);
+        section.extend(body);
+    }
+    write_section(&mut module, 10, &section);
+
+    module
+}
+
+fn encode_type(ty: ValType) -> u8 {
+    match ty {
+        ValType::I32 => 0x7f,
+        ValType::I64 => 0x7e,
+        ValType::F32 => 0x7d,
+        ValType::F64 => 0x7c,
+        ValType::V128 => 0x7b,
+        ValType::FuncRef => 0x70,
+        ValType::ExternRef => 0x6f,
+    }
+}
+
+fn write_section(module: &mut Vec<u8>, id: u8, contents: &[u8]) {
+    module.push(id);
+    write_u32(module, contents.len() as u32);
+    module.extend(contents);
+}
+
+fn write_name(bytes: &mut Vec<u8>, name: &str) {
+    write_u32(bytes, name.len() as u32);
+    bytes.extend(name.as_bytes());
+}
+
+fn write_u32(bytes: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+fn write_i32(bytes: &mut Vec<u8>, mut value: i32) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        let done = (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0);
+        if done {
+            bytes.push(byte);
+            return;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn leb128_encoding() {
+        let mut bytes = vec![];
+        write_u32(&mut bytes, 624_485);
+        assert_eq!(bytes, [0xe5, 0x8e, 0x26]);
+
+        let mut bytes = vec![];
+        write_i32(&mut bytes, -123_456);
+        assert_eq!(bytes, [0xc0, 0xbb, 0x78]);
+
+        let mut bytes = vec![];
+        write_i32(&mut bytes, 64);
+        assert_eq!(bytes, [0xc0, 0x00]);
+    }
+
+    #[cfg(feature = "compiler")]
+    #[test]
+    fn stub_module_is_valid() {
+        let targets = vec![
+            (
+                0,
+                FunctionType::new(vec![ValType::I32, ValType::F64], vec![]),
+            ),
+            (3, FunctionType::new(vec![], vec![ValType::I64])),
+            (
+                200,
+                FunctionType::new(vec![ValType::I32, ValType::F64], vec![]),
+            ),
+        ];
+        let bytes = encode_stub_module(&targets, 201);
+        wasmer_compiler::wasmparser::Validator::new()
+            .validate_all(&bytes)
+            .unwrap();
+    }
+}
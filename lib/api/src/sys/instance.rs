@@ -0,0 +1,622 @@
+use crate::sys::call_hook::CallHookScope;
+use crate::sys::event_log::{EventLogScope, StoreEvent};
+use crate::sys::exports::Exports;
+use crate::sys::externals::{Extern, Function, Global};
+use crate::sys::labels::{InstanceLabels, LabelSet, Labels};
+use crate::sys::module::Module;
+use crate::sys::snapshot::{InstanceSnapshot, SnapshotError};
+use crate::sys::store::Store;
+use crate::sys::types::{ExternType, ImportType, Val};
+use crate::{HostEnvInitError, LinkError, RuntimeError};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use thiserror::Error;
+use wasmer_compiler::FUNCTION_COUNTERS;
+use wasmer_engine::{Export, Resolver};
+use wasmer_types::{ExportIndex, FunctionIndex, LocalFunctionIndex};
+use wasmer_vm::{ImportFunctionEnv, InstanceHandle, VMContext, VMFunctionKind};
+
+/// A WebAssembly Instance is a stateful, executable
+/// instance of a WebAssembly [`Module`].
+///
+/// Instance objects contain all the exported WebAssembly
+/// functions, memories, tables and globals that allow
+/// interacting with WebAssembly.
+///
+/// Spec: <https://webassembly.github.io/spec/core/exec/runtime.html#module-instances>
+#[derive(Clone)]
+pub struct Instance {
+    handle: Arc<Mutex<InstanceHandle>>,
+    module: Module,
+    labels: LabelSet,
+    teardown: Arc<Teardown>,
+    resolved_imports: Arc<Vec<(ImportType, Extern)>>,
+    /// The exports for an instance.
+    pub exports: Exports,
+}
+
+/// Records the exports a resolver returns for the imports of a module.
+struct RecordingResolver<'a> {
+    resolver: &'a dyn Resolver,
+    resolved: RefCell<Vec<(u32, String, String, Export)>>,
+}
+
+impl Resolver for RecordingResolver<'_> {
+    fn resolve(&self, index: u32, module: &str, field: &str) -> Option<Export> {
+        let export = self.resolver.resolve(index, module, field)?;
+        self.resolved.borrow_mut().push((
+            index,
+            module.to_string(),
+            field.to_string(),
+            export.clone(),
+        ));
+        Some(export)
+    }
+}
+
+/// The callbacks run once the last clone of an instance is dropped.
+#[derive(Default)]
+struct Teardown(Mutex<Vec<Box<dyn FnOnce() + Send>>>);
+
+impl Drop for Teardown {
+    fn drop(&mut self) {
+        for callback in self.0.get_mut().unwrap().drain(..) {
+            callback();
+        }
+    }
+}
+
+#[cfg(test)]
+mod send_test {
+    use super::*;
+
+    fn is_send<T: Send>() -> bool {
+        true
+    }
+
+    #[test]
+    fn instance_is_send() {
+        assert!(is_send::<Instance>());
+    }
+}
+
+/// An error while instantiating a module.
+///
+/// This is not a common WebAssembly error, however
+/// we need to differentiate from a `LinkError` (an error
+/// that happens while linking, on instantiation), a
+/// Trap that occurs when calling the WebAssembly module
+/// start function, and an error when initializing the user's
+/// host environments.
+#[derive(Error, Debug)]
+pub enum InstantiationError {
+    /// A linking ocurred during instantiation.
+    #[error(transparent)]
+    Link(LinkError),
+
+    /// A runtime error occured while invoking the start function
+    #[error(transparent)]
+    Start(RuntimeError),
+
+    /// Error occurred when initializing the host environment.
+    #[error(transparent)]
+    HostEnvInitialization(HostEnvInitError),
+}
+
+impl From<wasmer_engine::InstantiationError> for InstantiationError {
+    fn from(other: wasmer_engine::InstantiationError) -> Self {
+        match other {
+            wasmer_engine::InstantiationError::Link(e) => Self::Link(e),
+            wasmer_engine::InstantiationError::Start(e) => Self::Start(e),
+        }
+    }
+}
+
+impl From<HostEnvInitError> for InstantiationError {
+    fn from(other: HostEnvInitError) -> Self {
+        Self::HostEnvInitialization(other)
+    }
+}
+
+/// An error while replacing the host env of an imported function with
+/// [`Instance::replace_import_env`].
+#[derive(Error, Debug)]
+pub enum ImportEnvError {
+    /// The instance has no function import of this name.
+    #[error("unknown function import `{module}`.`{name}`")]
+    UnknownImport {
+        /// The module of the import.
+        module: String,
+        /// The name of the import.
+        name: String,
+    },
+
+    /// The imported function, or the new one, has no host env.
+    #[error("the function import `{module}`.`{name}` has no host env")]
+    NoEnv {
+        /// The module of the import.
+        module: String,
+        /// The name of the import.
+        name: String,
+    },
+
+    /// The new function isn't the imported host function, with a
+    /// host env of the same type.
+    #[error("the function can't replace the host env of the import `{module}`.`{name}`")]
+    IncompatibleFunction {
+        /// The module of the import.
+        module: String,
+        /// The name of the import.
+        name: String,
+    },
+
+    /// Error occurred when initializing the new host env.
+    #[error(transparent)]
+    HostEnvInitialization(HostEnvInitError),
+}
+
+impl From<HostEnvInitError> for ImportEnvError {
+    fn from(other: HostEnvInitError) -> Self {
+        Self::HostEnvInitialization(other)
+    }
+}
+
+impl Instance {
+    /// Creates a new `Instance` from a WebAssembly [`Module`] and a
+    /// set of imports resolved by the [`Resolver`].
+    ///
+    /// The resolver can be anything that implements the [`Resolver`] trait,
+    /// so you can plug custom resolution for the imports, if you wish not
+    /// to use [`ImportObject`].
+    ///
+    /// The [`ImportObject`] is the easiest way to provide imports to the instance.
+    ///
+    /// [`ImportObject`]: crate::ImportObject
+    ///
+    /// ```
+    /// # use wasmer::{imports, Store, Module, Global, Value, Instance};
+    /// # fn main() -> anyhow::Result<()> {
+    /// let store = Store::default();
+    /// let module = Module::new(&store, "(module)")?;
+    /// let imports = imports!{
+    ///   "host" => {
+    ///     "var" => Global::new(&store, Value::I32(2))
+    ///   }
+    /// };
+    /// let instance = Instance::new(&module, &imports)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// The function can return [`InstantiationError`]s.
+    ///
+    /// Those are, as defined by the spec:
+    ///  * Link errors that happen when plugging the imports into the instance
+    ///  * Runtime errors that happen when running the module `start` function.
+    pub fn new(module: &Module, resolver: &dyn Resolver) -> Result<Self, InstantiationError> {
+        let result = Self::instantiate(module, resolver);
+        module.store().event_log().record(|| match &result {
+            Ok(_) => StoreEvent::Instantiated {
+                module: module.name().map(str::to_string),
+            },
+            Err(error) => StoreEvent::InstantiationFailed {
+                module: module.name().map(str::to_string),
+                error: error.to_string(),
+            },
+        });
+        result
+    }
+
+    fn instantiate(module: &Module, resolver: &dyn Resolver) -> Result<Self, InstantiationError> {
+        let store = module.store();
+        let labels = InstanceLabels {
+            store: store.label_set().clone(),
+            instance: LabelSet::default(),
+        };
+        let mut reservation = store
+            .limits_slot()
+            .reserve_instance()
+            .map_err(|error| InstantiationError::Link(LinkError::Resource(error)))?;
+        let recorder = RecordingResolver {
+            resolver,
+            resolved: RefCell::new(Vec::new()),
+        };
+        let handle = {
+            // Report the host calls of the start function.
+            let hook = store.call_hook().map(|hook| hook.labeled(store.labels()));
+            let _hook_scope = CallHookScope::enter(hook);
+            let _log_scope = EventLogScope::enter(store.event_log());
+            module
+                .instantiate(&recorder, labels.clone())
+                .map_err(|error| match error {
+                    InstantiationError::Start(error) => {
+                        InstantiationError::Start(error.with_labels(|| labels.get()))
+                    }
+                    error => error,
+                })?
+        };
+        let exports = module
+            .exports()
+            .map(|export| {
+                let name = export.name().to_string();
+                let export = handle.lookup(&name).expect("export");
+                let extern_ = Extern::from_vm_export(store, export.into());
+                (name, extern_)
+            })
+            .collect::<Exports>();
+        let imports = module.imports().collect::<Vec<_>>();
+        let resolved_imports = recorder
+            .resolved
+            .into_inner()
+            .into_iter()
+            .filter_map(|(index, module, field, export)| {
+                let import = match imports.get(index as usize) {
+                    Some(import) if import.module() == module && import.name() == field => import,
+                    _ => imports
+                        .iter()
+                        .find(|import| import.module() == module && import.name() == field)?,
+                };
+                Some((import.clone(), Extern::from_vm_export(store, export)))
+            })
+            .collect();
+
+        let handle = Arc::new(Mutex::new(handle));
+        reservation.fill(&handle);
+        store
+            .inventory()
+            .add_instance(&handle, module.record(), labels.clone());
+        let instance = Self {
+            handle,
+            module: module.clone(),
+            labels: labels.instance,
+            teardown: Default::default(),
+            resolved_imports: Arc::new(resolved_imports),
+            exports,
+        };
+
+        // # Safety
+        // `initialize_host_envs` should be called after instantiation but before
+        // returning an `Instance` to the user. We set up the host environments
+        // via `WasmerEnv::init_with_instance`.
+        //
+        // This usage is correct because we pass a valid pointer to `instance` and the
+        // correct error type returned by `WasmerEnv::init_with_instance` as a generic
+        // parameter.
+        unsafe {
+            instance
+                .handle
+                .lock()
+                .unwrap()
+                .initialize_host_envs::<HostEnvInitError>(&instance as *const _ as *const _)?;
+        }
+
+        Ok(instance)
+    }
+
+    /// Gets the [`Module`] associated with this instance.
+    pub fn module(&self) -> &Module {
+        &self.module
+    }
+
+    /// Returns the imports of this instance, with the items which were
+    /// resolved for them, in the order they were resolved.
+    ///
+    /// It tells which item of a chain of resolvers satisfied each
+    /// import.
+    pub fn resolved_imports(&self) -> impl Iterator<Item = (&ImportType, &Extern)> {
+        self.resolved_imports
+            .iter()
+            .map(|(import, extern_)| (import, extern_))
+    }
+
+    /// Returns the [`Store`] where the `Instance` belongs.
+    pub fn store(&self) -> &Store {
+        self.module.store()
+    }
+
+    /// Identifies this instance among the ones alive: it's shared by
+    /// the clones of this instance.
+    pub(crate) fn id(&self) -> usize {
+        Arc::as_ptr(&self.teardown) as usize
+    }
+
+    /// Calls `callback` once the last clone of this instance is
+    /// dropped.
+    pub(crate) fn on_teardown(&self, callback: impl FnOnce() + Send + 'static) {
+        self.teardown.0.lock().unwrap().push(Box::new(callback));
+    }
+
+    /// Returns whether a thread is currently executing the code of
+    /// this instance, entered through its exported functions or its
+    /// start function.
+    ///
+    /// Calls coming from another instance through its imports aren't
+    /// tracked.
+    pub fn is_executing(&self) -> bool {
+        self.handle.lock().unwrap().is_executing()
+    }
+
+    /// Waits until no thread is executing the code of this instance,
+    /// for at most `timeout`, so its memories can be dropped or its
+    /// imports rebound safely. Returns `false` if the timeout elapsed
+    /// first.
+    ///
+    /// It must not be called from a host function called by this
+    /// instance, as the call in progress would make it time out.
+    ///
+    /// See [`Instance::is_executing`] for what is tracked.
+    pub fn wait_idle(&self, timeout: Duration) -> bool {
+        // Don't lock the handle while waiting.
+        let instance_ref = self.handle.lock().unwrap().instance_ref();
+        instance_ref.wait_idle(timeout)
+    }
+
+    /// Replaces the host env of the imported function `module`.`name`
+    /// with a clone of the one of `function`, e.g. to rotate the
+    /// credentials of a host API. The clone is initialized with this
+    /// instance, as at instantiation.
+    ///
+    /// `function` must be the imported host function, created again
+    /// with a host env of the same type. A dynamic function may have a
+    /// different closure, as long as its type is the same.
+    ///
+    /// # Synchronization
+    ///
+    /// The host env is replaced atomically, even when the instance is
+    /// executing on other threads: each call of the import made by the
+    /// code of the instance uses either the previous host env or the
+    /// new one. The calls in progress finish with the previous one.
+    ///
+    /// The references to the import taken before keep the previous
+    /// host env: the elements of the tables, the exports of this
+    /// instance re-exporting the import and the results of
+    /// [`Instance::resolved_imports`]. The previous host env is kept
+    /// until the instance is dropped.
+    ///
+    /// ```
+    /// # use wasmer::{imports, Function, Instance, Module, Store, WasmerEnv};
+    /// # fn main() -> anyhow::Result<()> {
+    /// #[derive(WasmerEnv, Clone)]
+    /// struct Env {
+    ///     token: i32,
+    /// }
+    ///
+    /// fn token(env: &Env) -> i32 {
+    ///     env.token
+    /// }
+    ///
+    /// let store = Store::default();
+    /// let module = Module::new(
+    ///     &store,
+    ///     r#"(module
+    ///       (import "host" "token" (func $token (result i32)))
+    ///       (func (export "token") (result i32) (call $token)))"#,
+    /// )?;
+    /// let instance = Instance::new(
+    ///     &module,
+    ///     &imports! {
+    ///         "host" => { "token" => Function::new_native_with_env(&store, Env { token: 1 }, token) },
+    ///     },
+    /// )?;
+    /// let get_token = instance.exports.get_native_function::<(), i32>("token")?;
+    /// assert_eq!(get_token.call()?, 1);
+    ///
+    /// instance.replace_import_env(
+    ///     "host",
+    ///     "token",
+    ///     &Function::new_native_with_env(&store, Env { token: 2 }, token),
+    /// )?;
+    /// assert_eq!(get_token.call()?, 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn replace_import_env(
+        &self,
+        module: &str,
+        name: &str,
+        function: &Function,
+    ) -> Result<(), ImportEnvError> {
+        let error_names = || (module.to_string(), name.to_string());
+        let index = self
+            .module
+            .imports()
+            .filter(|import| matches!(import.ty(), ExternType::Function(_)))
+            .position(|import| import.module() == module && import.name() == name)
+            .map(|index| FunctionIndex::from_u32(index as u32));
+        let imported = self
+            .resolved_imports
+            .iter()
+            .find_map(|(import, extern_)| match extern_ {
+                Extern::Function(imported)
+                    if import.module() == module && import.name() == name =>
+                {
+                    Some(imported)
+                }
+                _ => None,
+            });
+        let (index, imported) = match (index, imported) {
+            (Some(index), Some(imported)) => (index, imported),
+            _ => {
+                let (module, name) = error_names();
+                return Err(ImportEnvError::UnknownImport { module, name });
+            }
+        };
+        if imported.exported.metadata.is_none() || function.exported.metadata.is_none() {
+            let (module, name) = error_names();
+            return Err(ImportEnvError::NoEnv { module, name });
+        }
+
+        let (old, new) = (&imported.exported, &function.exported);
+        let compatible = old.vm_function.kind == new.vm_function.kind
+            && old.vm_function.signature == new.vm_function.signature
+            && old.has_same_host_env_type(new)
+            // The host function of a static function is its body, while
+            // the one of a dynamic function is in its host env.
+            && (old.vm_function.kind == VMFunctionKind::Dynamic
+                || old.vm_function.address == new.vm_function.address);
+        if !compatible {
+            let (module, name) = error_names();
+            return Err(ImportEnvError::IncompatibleFunction { module, name });
+        }
+
+        let mut env = new.import_env();
+        // # Safety
+        // The host env is initialized with a valid pointer to this
+        // instance and the error type of `WasmerEnv::init_with_instance`,
+        // like in `Instance::new`, and it's of the type of the current
+        // host env of the import, as checked above.
+        unsafe {
+            env.initialize::<HostEnvInitError>(self as *const _ as *const _)?;
+            if !self
+                .handle
+                .lock()
+                .unwrap()
+                .replace_imported_function_env(index, env)
+            {
+                let (module, name) = error_names();
+                return Err(ImportEnvError::NoEnv { module, name });
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets the label `key` of this instance to `value`, overriding the
+    /// one of its store, if any. See [`Store::set_label`].
+    ///
+    /// The labels are shared by the clones of this instance.
+    ///
+    /// ```
+    /// # use wasmer::{imports, Instance, Module, Store};
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// # let module = Module::new(&store, "(module)")?;
+    /// let instance = Instance::new(&module, &imports! {})?;
+    /// instance.set_label("tenant", "acme");
+    /// assert_eq!(instance.labels()["tenant"], "acme");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_label(&self, key: impl Into<String>, value: impl Into<String>) {
+        self.labels.set(key.into(), value.into());
+    }
+
+    /// Removes the label `key` of this instance, returning its value.
+    /// The label of its store applies again, if any.
+    pub fn remove_label(&self, key: &str) -> Option<String> {
+        self.labels.remove(key)
+    }
+
+    /// Returns the labels of this instance: the ones of its store,
+    /// overridden by the ones set on the instance.
+    pub fn labels(&self) -> Labels {
+        InstanceLabels {
+            store: self.store().label_set().clone(),
+            instance: self.labels.clone(),
+        }
+        .get()
+    }
+
+    /// Returns the number of calls of each local function of this
+    /// instance, by the index of the function in the module, when its
+    /// module was compiled with the function counters of the engine
+    /// enabled. It's empty otherwise.
+    ///
+    /// The functions are named by [`Module::function_name`].
+    pub fn function_counts(&self) -> HashMap<u32, u64> {
+        let module_info = self.module.info();
+        self.counters(FUNCTION_COUNTERS)
+            .into_iter()
+            .enumerate()
+            .map(|(index, count)| {
+                let index = module_info.func_index(LocalFunctionIndex::from_u32(index as u32));
+                (index.as_u32(), count)
+            })
+            .collect()
+    }
+
+    /// Returns the values of the counters registered under `name` by
+    /// a middleware of the compiler, in the order they were injected
+    /// with `ModuleItemInjector::add_counters`. It's empty if the
+    /// module has no such counters.
+    pub fn counters(&self, name: &str) -> Vec<u64> {
+        self.middleware_globals(name)
+            .iter()
+            .filter_map(|counter| counter.get().i64())
+            .map(|count| count as u64)
+            .collect()
+    }
+
+    /// Resets the counters registered under `name` by a middleware of
+    /// the compiler to 0, see [`Instance::counters`].
+    pub fn reset_counters(&self, name: &str) {
+        for counter in self.middleware_globals(name) {
+            counter
+                .set(Val::I64(0))
+                .expect("the counters are mutable `i64` globals");
+        }
+    }
+
+    /// Returns the globals registered under `name` by a middleware of
+    /// the compiler, which aren't exported.
+    fn middleware_globals(&self, name: &str) -> Vec<Global> {
+        let globals = match self.module.info().middleware_globals.get(name) {
+            Some(globals) => globals.clone(),
+            None => return Vec::new(),
+        };
+        globals
+            .into_iter()
+            .filter_map(
+                |index| match self.lookup_by_declaration(ExportIndex::Global(index)) {
+                    Extern::Global(global) => Some(global),
+                    _ => None,
+                },
+            )
+            .collect()
+    }
+
+    /// Takes a snapshot of the memories, mutable globals and tables of
+    /// this instance, to be restored with [`Instance::restore`] in
+    /// another instance of the same module.
+    ///
+    /// The instance must not be executing. The `externref`s and the
+    /// functions of other instances in its globals and tables can't be
+    /// snapshotted.
+    pub fn snapshot(&self) -> Result<InstanceSnapshot, SnapshotError> {
+        InstanceSnapshot::take(self)
+    }
+
+    /// Restores a snapshot taken with [`Instance::snapshot`], usually
+    /// in a fresh instance of the same module. The memories and the
+    /// tables grow to the size they had in the snapshot.
+    ///
+    /// The instance must not be executing. It's left untouched when
+    /// the snapshot doesn't match it.
+    pub fn restore(&self, snapshot: &InstanceSnapshot) -> Result<(), SnapshotError> {
+        snapshot.restore(self)
+    }
+
+    /// Returns the function, memory, global or table declared (or
+    /// imported) by the module at `index`.
+    pub(crate) fn lookup_by_declaration(&self, index: ExportIndex) -> Extern {
+        let export = self.handle.lock().unwrap().lookup_by_declaration(&index);
+        Extern::from_vm_export(self.store(), export.into())
+    }
+
+    #[doc(hidden)]
+    pub fn vmctx_ptr(&self) -> *mut VMContext {
+        self.handle.lock().unwrap().vmctx_ptr()
+    }
+}
+
+impl fmt::Debug for Instance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Instance")
+            .field("exports", &self.exports)
+            .finish()
+    }
+}
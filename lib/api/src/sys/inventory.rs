@@ -0,0 +1,205 @@
+//! The modules and instances held by a store.
+//!
+//! The store keeps a weak reference to each module and instance
+//! created in it, so [`Store::modules`] and [`Store::instances`] can
+//! report what a long-running store currently holds, without keeping
+//! them alive.
+//!
+//! [`Store::modules`]: crate::Store::modules
+//! [`Store::instances`]: crate::Store::instances
+
+use crate::sys::labels::{InstanceLabels, Labels};
+use crate::Bytes;
+use std::fmt;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::SystemTime;
+use wasmer_types::ExportIndex;
+use wasmer_vm::{InstanceHandle, VMExport};
+
+/// A module held by a store, returned by [`Store::modules`].
+///
+/// [`Store::modules`]: crate::Store::modules
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleEntry {
+    /// The name of the module, if any (see [`Module::name`]).
+    ///
+    /// [`Module::name`]: crate::Module::name
+    pub name: Option<String>,
+
+    /// When the module was compiled or deserialized.
+    pub created: SystemTime,
+
+    /// The size of the data segments of the module, copied into the
+    /// memories of each of its instances.
+    pub data_size: Bytes,
+
+    /// The number of instances of the module that are alive.
+    pub instances: usize,
+}
+
+/// An instance held by a store, returned by [`Store::instances`].
+///
+/// [`Store::instances`]: crate::Store::instances
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstanceEntry {
+    /// The name of the module of the instance, if any.
+    pub module: Option<String>,
+
+    /// The labels of the instance, including the ones of the store.
+    pub labels: Labels,
+
+    /// When the instance was created.
+    pub created: SystemTime,
+
+    /// The size of the memories defined by the instance. The imported
+    /// memories are counted by the instances defining them.
+    pub memory_size: Bytes,
+
+    /// Whether a function of the instance is being executed.
+    pub is_executing: bool,
+}
+
+/// The record of a module, shared by its clones.
+#[derive(Debug)]
+pub(crate) struct ModuleRecord {
+    name: Mutex<Option<String>>,
+    created: SystemTime,
+    data_size: Bytes,
+}
+
+impl ModuleRecord {
+    pub(crate) fn set_name(&self, name: &str) {
+        *self.name.lock().unwrap() = Some(name.to_string());
+    }
+}
+
+struct InstanceRecord {
+    handle: Weak<Mutex<InstanceHandle>>,
+    module: Weak<ModuleRecord>,
+    labels: InstanceLabels,
+    created: SystemTime,
+}
+
+#[derive(Default)]
+struct Entries {
+    modules: Vec<Weak<ModuleRecord>>,
+    instances: Vec<InstanceRecord>,
+}
+
+/// The modules and instances created in a store, shared by the clones
+/// of the store.
+#[derive(Clone, Default)]
+pub(crate) struct Inventory(Arc<Mutex<Entries>>);
+
+impl Inventory {
+    /// Records a new module, returning the record to be kept by the
+    /// module and its clones.
+    pub(crate) fn add_module(&self, name: Option<&str>, data_size: Bytes) -> Arc<ModuleRecord> {
+        let record = Arc::new(ModuleRecord {
+            name: Mutex::new(name.map(str::to_string)),
+            created: SystemTime::now(),
+            data_size,
+        });
+        let mut entries = self.0.lock().unwrap();
+        entries.modules.retain(|module| module.strong_count() > 0);
+        entries.modules.push(Arc::downgrade(&record));
+        record
+    }
+
+    /// Records a new instance of the module of `module`.
+    pub(crate) fn add_instance(
+        &self,
+        handle: &Arc<Mutex<InstanceHandle>>,
+        module: &Arc<ModuleRecord>,
+        labels: InstanceLabels,
+    ) {
+        let mut entries = self.0.lock().unwrap();
+        entries
+            .instances
+            .retain(|instance| instance.handle.strong_count() > 0);
+        entries.instances.push(InstanceRecord {
+            handle: Arc::downgrade(handle),
+            module: Arc::downgrade(module),
+            labels,
+            created: SystemTime::now(),
+        });
+    }
+
+    pub(crate) fn modules(&self) -> Vec<ModuleEntry> {
+        let entries = self.0.lock().unwrap();
+        entries
+            .modules
+            .iter()
+            .filter_map(Weak::upgrade)
+            .map(|module| ModuleEntry {
+                name: module.name.lock().unwrap().clone(),
+                created: module.created,
+                data_size: module.data_size,
+                instances: entries
+                    .instances
+                    .iter()
+                    .filter(|instance| {
+                        instance.handle.strong_count() > 0
+                            && instance.module.as_ptr() == Arc::as_ptr(&module)
+                    })
+                    .count(),
+            })
+            .collect()
+    }
+
+    pub(crate) fn instances(&self) -> Vec<InstanceEntry> {
+        // The handles are locked once the inventory is unlocked, so
+        // an instance being created doesn't wait for them.
+        let instances = self
+            .0
+            .lock()
+            .unwrap()
+            .instances
+            .iter()
+            .filter_map(|instance| {
+                let handle = instance.handle.upgrade()?;
+                let module = instance
+                    .module
+                    .upgrade()
+                    .and_then(|module| module.name.lock().unwrap().clone());
+                Some((handle, module, instance.labels.clone(), instance.created))
+            })
+            .collect::<Vec<_>>();
+        instances
+            .into_iter()
+            .map(|(handle, module, labels, created)| {
+                let handle = handle.lock().unwrap();
+                InstanceEntry {
+                    module,
+                    labels: labels.get(),
+                    created,
+                    memory_size: memory_size(&handle),
+                    is_executing: handle.is_executing(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// The size of the memories defined by an instance.
+fn memory_size(handle: &InstanceHandle) -> Bytes {
+    let module = handle.module_ref();
+    let bytes = module
+        .memories
+        .keys()
+        .filter(|index| module.local_memory_index(*index).is_some())
+        .map(
+            |index| match handle.lookup_by_declaration(&ExportIndex::Memory(index)) {
+                VMExport::Memory(memory) => Bytes::from(memory.from.size()).0,
+                _ => 0,
+            },
+        )
+        .sum();
+    Bytes(bytes)
+}
+
+impl fmt::Debug for Inventory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Inventory").finish()
+    }
+}
@@ -0,0 +1,65 @@
+//! Labels identifying the stores and the instances.
+//!
+//! The labels set with [`Store::set_label`] and [`Instance::set_label`]
+//! are attached to what's reported about them, so a trap or an event
+//! can be attributed to a tenant or a request when many instances run
+//! in the same process: the [`RuntimeError`]s of the calls, the events
+//! of the [`CallHook`] and the events logged for [`Store::debug_dump`].
+//!
+//! [`Store::set_label`]: crate::Store::set_label
+//! [`Instance::set_label`]: crate::Instance::set_label
+//! [`RuntimeError`]: crate::RuntimeError
+//! [`CallHook`]: crate::CallHook
+//! [`Store::debug_dump`]: crate::Store::debug_dump
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+use wasmer_vm::InstanceRef;
+
+/// Labels, by key.
+pub type Labels = BTreeMap<String, String>;
+
+/// The labels of a store or an instance, shared by its clones.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LabelSet(Arc<RwLock<Labels>>);
+
+impl LabelSet {
+    pub(crate) fn set(&self, key: String, value: String) {
+        self.0.write().unwrap().insert(key, value);
+    }
+
+    pub(crate) fn remove(&self, key: &str) -> Option<String> {
+        self.0.write().unwrap().remove(key)
+    }
+
+    pub(crate) fn get(&self) -> Labels {
+        self.0.read().unwrap().clone()
+    }
+}
+
+/// The labels of an instance, kept as the host state of its VM
+/// instance so they're found from its exported functions.
+#[derive(Debug, Clone)]
+pub(crate) struct InstanceLabels {
+    pub(crate) store: LabelSet,
+    pub(crate) instance: LabelSet,
+}
+
+impl InstanceLabels {
+    /// The labels of the store, overridden by the ones of the
+    /// instance.
+    pub(crate) fn get(&self) -> Labels {
+        let mut labels = self.store.get();
+        labels.extend(self.instance.get());
+        labels
+    }
+
+    /// The labels of the instance of a function, or of its store for
+    /// the host functions.
+    pub(crate) fn of_function(store: &LabelSet, instance_ref: Option<&InstanceRef>) -> Labels {
+        match instance_ref.and_then(|instance| instance.host_state().downcast_ref::<Self>()) {
+            Some(labels) => labels.get(),
+            None => store.get(),
+        }
+    }
+}
@@ -0,0 +1,279 @@
+//! The limits of the resources created in a store.
+//!
+//! A host running the modules of several tenants in their own stores
+//! can bound what each of them creates with [`Store::set_limits`]: the
+//! number of instances, memories and tables alive in the store, and
+//! the initial size of each memory and table. The limits are checked
+//! when the resources are created, so a module exceeding them fails to
+//! instantiate. They don't apply to the growth of the memories and
+//! tables, which the [`Tunables`] can bound.
+//!
+//! [`Store::set_limits`]: crate::Store::set_limits
+//! [`Tunables`]: crate::Tunables
+
+use crate::{MemoryType, Pages, TableType};
+use std::sync::{Arc, Mutex, Weak};
+use wasmer_vm::{InstanceHandle, Memory, MemoryError, Table};
+
+/// The limits of the resources created in a store, set with
+/// [`Store::set_limits`]. All the resources are unlimited by default.
+///
+/// # Example
+///
+/// ```
+/// # use wasmer::{Pages, Store, StoreLimits};
+/// # let store = Store::default();
+/// store.set_limits(
+///     StoreLimits::new()
+///         .instances(4)
+///         .memories(4)
+///         .memory_pages(Pages(160)),
+/// );
+/// ```
+///
+/// [`Store::set_limits`]: crate::Store::set_limits
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StoreLimits {
+    instances: Option<usize>,
+    memories: Option<usize>,
+    tables: Option<usize>,
+    memory_pages: Option<Pages>,
+    table_elements: Option<u32>,
+}
+
+impl StoreLimits {
+    /// Creates limits leaving all the resources unlimited.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The maximum number of instances alive in the store.
+    pub fn instances(&mut self, limit: usize) -> &mut Self {
+        self.instances = Some(limit);
+        self
+    }
+
+    /// The maximum number of memories alive in the store, whether
+    /// they are defined by the instances or created by the host.
+    pub fn memories(&mut self, limit: usize) -> &mut Self {
+        self.memories = Some(limit);
+        self
+    }
+
+    /// The maximum number of tables alive in the store, whether they
+    /// are defined by the instances or created by the host.
+    pub fn tables(&mut self, limit: usize) -> &mut Self {
+        self.tables = Some(limit);
+        self
+    }
+
+    /// The maximum initial size of a memory of the store.
+    pub fn memory_pages(&mut self, limit: Pages) -> &mut Self {
+        self.memory_pages = Some(limit);
+        self
+    }
+
+    /// The maximum initial number of elements of a table of the store.
+    pub fn table_elements(&mut self, limit: u32) -> &mut Self {
+        self.table_elements = Some(limit);
+        self
+    }
+
+    /// Returns the maximum number of instances, if limited.
+    pub fn max_instances(&self) -> Option<usize> {
+        self.instances
+    }
+
+    /// Returns the maximum number of memories, if limited.
+    pub fn max_memories(&self) -> Option<usize> {
+        self.memories
+    }
+
+    /// Returns the maximum number of tables, if limited.
+    pub fn max_tables(&self) -> Option<usize> {
+        self.tables
+    }
+
+    /// Returns the maximum initial size of a memory, if limited.
+    pub fn max_memory_pages(&self) -> Option<Pages> {
+        self.memory_pages
+    }
+
+    /// Returns the maximum initial number of elements of a table, if
+    /// limited.
+    pub fn max_table_elements(&self) -> Option<u32> {
+        self.table_elements
+    }
+}
+
+#[derive(Default)]
+struct LimitsState {
+    limits: StoreLimits,
+    instances: Counted<Mutex<InstanceHandle>>,
+    memories: Counted<dyn Memory>,
+    tables: Counted<dyn Table>,
+}
+
+/// The resources of a kind alive in a store, and the number of the
+/// ones reserved while they are created.
+struct Counted<T: ?Sized> {
+    alive: Vec<Weak<T>>,
+    reserved: usize,
+}
+
+impl<T: ?Sized> Default for Counted<T> {
+    fn default() -> Self {
+        Self {
+            alive: Vec::new(),
+            reserved: 0,
+        }
+    }
+}
+
+impl<T: ?Sized> Counted<T> {
+    /// Reserves `requested` more resources of the kind `what`, if the
+    /// ones alive and reserved leave room for them under `limit`.
+    fn reserve(
+        &mut self,
+        what: &str,
+        requested: usize,
+        limit: Option<usize>,
+    ) -> Result<(), String> {
+        self.alive.retain(|resource| resource.strong_count() > 0);
+        let counted = self.alive.len() + self.reserved;
+        match limit {
+            Some(limit) if requested > 0 && counted + requested > limit => {
+                return Err(format!(
+                    "the store limits the number of {} to {}, and {} are alive",
+                    what, limit, counted
+                ))
+            }
+            _ => {}
+        }
+        self.reserved += requested;
+        Ok(())
+    }
+}
+
+/// The limits of a store and the instances, memories and tables they
+/// count, shared by the clones of the store.
+///
+/// A resource is reserved before it's created, and the reservation is
+/// filled with the resource once created, both under the lock of the
+/// limits, so the stores used from several threads can't exceed them.
+#[derive(Clone, Default)]
+pub(crate) struct LimitsSlot(Arc<Mutex<LimitsState>>);
+
+impl LimitsSlot {
+    pub(crate) fn set(&self, limits: StoreLimits) {
+        self.0.lock().unwrap().limits = limits;
+    }
+
+    pub(crate) fn get(&self) -> StoreLimits {
+        self.0.lock().unwrap().limits.clone()
+    }
+
+    /// Reserves an instance.
+    pub(crate) fn reserve_instance(&self) -> Result<Reservation<Mutex<InstanceHandle>>, String> {
+        let mut state = self.0.lock().unwrap();
+        let limit = state.limits.instances;
+        state.instances.reserve("instances", 1, limit)?;
+        Ok(Reservation::new(self, 1, |state| &mut state.instances))
+    }
+
+    /// Reserves memories of the types `types`.
+    pub(crate) fn reserve_memories<'a>(
+        &self,
+        types: impl ExactSizeIterator<Item = &'a MemoryType>,
+    ) -> Result<Reservation<dyn Memory>, MemoryError> {
+        let mut state = self.0.lock().unwrap();
+        let requested = types.len();
+        for ty in types {
+            match state.limits.memory_pages {
+                Some(limit) if ty.minimum > limit => {
+                    return Err(MemoryError::MinimumMemoryTooLarge {
+                        min_requested: ty.minimum,
+                        max_allowed: limit,
+                    })
+                }
+                _ => {}
+            }
+        }
+        let limit = state.limits.memories;
+        state
+            .memories
+            .reserve("memories", requested, limit)
+            .map_err(MemoryError::Generic)?;
+        Ok(Reservation::new(self, requested, |state| {
+            &mut state.memories
+        }))
+    }
+
+    /// Reserves tables of the types `types`.
+    pub(crate) fn reserve_tables<'a>(
+        &self,
+        types: impl ExactSizeIterator<Item = &'a TableType>,
+    ) -> Result<Reservation<dyn Table>, String> {
+        let mut state = self.0.lock().unwrap();
+        let requested = types.len();
+        for ty in types {
+            match state.limits.table_elements {
+                Some(limit) if ty.minimum > limit => {
+                    return Err(format!(
+                        "the store limits the initial size of a table to {} elements, \
+                         but a table of {} elements is requested",
+                        limit, ty.minimum
+                    ))
+                }
+                _ => {}
+            }
+        }
+        let limit = state.limits.tables;
+        state.tables.reserve("tables", requested, limit)?;
+        Ok(Reservation::new(self, requested, |state| &mut state.tables))
+    }
+}
+
+/// Resources reserved in the limits of a store while they are
+/// created. The reservations that aren't filled, because the creation
+/// failed, are released when it's dropped.
+#[must_use]
+pub(crate) struct Reservation<T: ?Sized> {
+    slot: LimitsSlot,
+    remaining: usize,
+    counted: fn(&mut LimitsState) -> &mut Counted<T>,
+}
+
+impl<T: ?Sized> Reservation<T> {
+    fn new(
+        slot: &LimitsSlot,
+        remaining: usize,
+        counted: fn(&mut LimitsState) -> &mut Counted<T>,
+    ) -> Self {
+        Self {
+            slot: slot.clone(),
+            remaining,
+            counted,
+        }
+    }
+
+    /// Counts `resource` in place of one of the reserved resources,
+    /// until it's dropped.
+    pub(crate) fn fill(&mut self, resource: &Arc<T>) {
+        assert!(self.remaining > 0, "no resource is left reserved");
+        let mut state = self.slot.0.lock().unwrap();
+        let counted = (self.counted)(&mut state);
+        counted.alive.push(Arc::downgrade(resource));
+        counted.reserved -= 1;
+        self.remaining -= 1;
+    }
+}
+
+impl<T: ?Sized> Drop for Reservation<T> {
+    fn drop(&mut self) {
+        if self.remaining > 0 {
+            let mut state = self.slot.0.lock().unwrap();
+            (self.counted)(&mut state).reserved -= self.remaining;
+        }
+    }
+}
@@ -0,0 +1,347 @@
+//! Linking core modules together by the names of their imports.
+
+use crate::sys::exports::Exportable;
+use crate::sys::externals::{Extern, Function};
+use crate::sys::externals::{Global, Memory, Table};
+use crate::sys::import_object::ImportObject;
+use crate::sys::instance::{Instance, InstantiationError};
+use crate::sys::module::Module;
+use crate::sys::store::Store;
+use crate::sys::types::{ExternRef, ExternType, ImportType, Val, ValType};
+use indexmap::IndexMap;
+use std::collections::{HashMap, HashSet};
+use thiserror::Error;
+use wasmer_engine::{Export, NamedResolver, Resolver, RuntimeError};
+
+/// An error while defining items in a [`ModuleLinker`], or while
+/// instantiating modules with it.
+#[derive(Error, Debug)]
+pub enum LinkerError {
+    /// The item is already defined, and shadowing isn't allowed.
+    #[error("`{module}`.`{name}` is already defined")]
+    AlreadyDefined {
+        /// The name of the module of the item.
+        module: String,
+        /// The name of the item.
+        name: String,
+    },
+
+    /// The modules to instantiate import each other in a cycle.
+    #[error("the modules {0:?} import each other in a cycle")]
+    Cycle(Vec<String>),
+
+    /// A module failed to be instantiated.
+    #[error(transparent)]
+    Instantiation(#[from] InstantiationError),
+
+    /// A stub of an unknown import failed to be created.
+    #[error("failed to create a stub for `{module}`.`{name}`: {message}")]
+    Stub {
+        /// The name of the module of the import.
+        module: String,
+        /// The name of the import.
+        name: String,
+        /// Why the stub couldn't be created.
+        message: String,
+    },
+}
+
+/// Defines the imports of core modules by module name and item name,
+/// and instantiates modules with them.
+///
+/// The exports of an instance registered under a name are the
+/// definitions of the imports from the module of this name, so the
+/// instances can be wired to each other.
+///
+/// ```
+/// # use wasmer::{ModuleLinker, Module, Store};
+/// # fn main() -> anyhow::Result<()> {
+/// let store = Store::default();
+/// let math = Module::new(&store, r#"
+///     (module
+///       (func (export "double") (param i32) (result i32)
+///         local.get 0
+///         i32.const 2
+///         i32.mul))
+/// "#)?;
+/// let app = Module::new(&store, r#"
+///     (module
+///       (import "math" "double" (func $double (param i32) (result i32)))
+///       (func (export "run") (result i32)
+///         i32.const 21
+///         call $double))
+/// "#)?;
+///
+/// let mut linker = ModuleLinker::new(&store);
+/// linker.module("math", &math)?;
+/// let instance = linker.instantiate(&app)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ModuleLinker {
+    store: Store,
+    definitions: IndexMap<(String, String), Extern>,
+    allow_shadowing: bool,
+}
+
+impl ModuleLinker {
+    /// Creates a linker without any definition.
+    pub fn new(store: &Store) -> Self {
+        Self {
+            store: store.clone(),
+            definitions: IndexMap::new(),
+            allow_shadowing: false,
+        }
+    }
+
+    /// Sets whether an item can be redefined, replacing the previous
+    /// definition. It's not allowed by default.
+    pub fn allow_shadowing(&mut self, allow: bool) -> &mut Self {
+        self.allow_shadowing = allow;
+        self
+    }
+
+    /// Defines the item `name` of the module `module`.
+    pub fn define(
+        &mut self,
+        module: &str,
+        name: &str,
+        item: impl Into<Extern>,
+    ) -> Result<&mut Self, LinkerError> {
+        let key = (module.to_string(), name.to_string());
+        if !self.allow_shadowing && self.definitions.contains_key(&key) {
+            return Err(LinkerError::AlreadyDefined {
+                module: key.0,
+                name: key.1,
+            });
+        }
+        self.definitions.insert(key, item.into());
+        Ok(self)
+    }
+
+    /// Defines every item of `import_object`, like the imports of
+    /// `wasmer-wasi` or `wasmer-emscripten`.
+    pub fn define_import_object(
+        &mut self,
+        import_object: &ImportObject,
+    ) -> Result<&mut Self, LinkerError> {
+        for ((module, name), export) in import_object.clone() {
+            self.define(&module, &name, Extern::from_vm_export(&self.store, export))?;
+        }
+        Ok(self)
+    }
+
+    /// Defines the exports of `instance` as the items of the module
+    /// `module`.
+    pub fn instance(
+        &mut self,
+        module: &str,
+        instance: &Instance,
+    ) -> Result<&mut Self, LinkerError> {
+        for (name, export) in instance.exports.iter() {
+            self.define(module, name, export.clone())?;
+        }
+        Ok(self)
+    }
+
+    /// Instantiates `wasm_module`, and defines its exports as the items
+    /// of the module `module`.
+    pub fn module(&mut self, module: &str, wasm_module: &Module) -> Result<&mut Self, LinkerError> {
+        let instance = self.instantiate(wasm_module)?;
+        self.instance(module, &instance)
+    }
+
+    /// Defines the items imported by `module` which aren't defined yet
+    /// with stubs: functions trapping when they're called, zero-valued
+    /// globals, tables of null references, and memories of the minimum
+    /// size.
+    ///
+    /// It allows to instantiate a module whose optional imports are
+    /// never called.
+    pub fn define_unknown_imports_as_traps(
+        &mut self,
+        module: &Module,
+    ) -> Result<&mut Self, LinkerError> {
+        for import in module.imports() {
+            let key = (import.module().to_string(), import.name().to_string());
+            if self.definitions.contains_key(&key) {
+                continue;
+            }
+            let stub = stub(&self.store, &import)?;
+            self.definitions.insert(key, stub);
+        }
+        Ok(self)
+    }
+
+    /// Returns the definition of the item `name` of the module `module`.
+    pub fn get(&self, module: &str, name: &str) -> Option<&Extern> {
+        self.definitions
+            .get(&(module.to_string(), name.to_string()))
+    }
+
+    /// Returns the module names, the item names and the definitions of
+    /// the items, in the order they were defined.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str, &Extern)> {
+        self.definitions
+            .iter()
+            .map(|((module, name), item)| (module.as_str(), name.as_str(), item))
+    }
+
+    /// Instantiates `module` with the definitions of the linker.
+    pub fn instantiate(&self, module: &Module) -> Result<Instance, InstantiationError> {
+        Instance::new(module, self)
+    }
+
+    /// Instantiates `modules` in the order of their dependencies, and
+    /// defines the exports of each one under its name.
+    ///
+    /// A module depends on the other modules of `modules` it imports
+    /// items from. The instances are returned in the order they were
+    /// instantiated.
+    pub fn instantiate_all<'a>(
+        &mut self,
+        modules: impl IntoIterator<Item = (&'a str, &'a Module)>,
+    ) -> Result<Vec<(String, Instance)>, LinkerError> {
+        let mut pending = modules
+            .into_iter()
+            .map(|(name, module)| (name.to_string(), module))
+            .collect::<IndexMap<_, _>>();
+        let dependencies = pending
+            .iter()
+            .map(|(name, module)| {
+                let dependencies = module
+                    .imports()
+                    .map(|import| import.module().to_string())
+                    .filter(|dependency| dependency != name && pending.contains_key(dependency))
+                    .collect::<HashSet<_>>();
+                (name.clone(), dependencies)
+            })
+            .collect::<HashMap<_, _>>();
+
+        let mut instances = Vec::with_capacity(pending.len());
+        while !pending.is_empty() {
+            let ready = pending
+                .keys()
+                .find(|name| {
+                    dependencies[*name]
+                        .iter()
+                        .all(|dependency| !pending.contains_key(dependency))
+                })
+                .cloned();
+            let name = match ready {
+                Some(name) => name,
+                None => return Err(LinkerError::Cycle(pending.keys().cloned().collect())),
+            };
+            let module = pending.shift_remove(&name).unwrap();
+            let instance = self.instantiate(module)?;
+            self.instance(&name, &instance)?;
+            instances.push((name, instance));
+        }
+        Ok(instances)
+    }
+}
+
+impl NamedResolver for ModuleLinker {
+    fn resolve_by_name(&self, module: &str, field: &str) -> Option<Export> {
+        self.get(module, field).map(Extern::to_export)
+    }
+}
+
+/// Returns the zero value of `ty`, the null reference for the
+/// reference types.
+fn zero(ty: ValType) -> Val {
+    match ty {
+        ValType::I32 => Val::I32(0),
+        ValType::I64 => Val::I64(0),
+        ValType::F32 => Val::F32(0.0),
+        ValType::F64 => Val::F64(0.0),
+        ValType::V128 => Val::V128(0),
+        ValType::ExternRef | ValType::FuncRef => Val::ExternRef(ExternRef::null()),
+    }
+}
+
+/// Creates a stub for the unknown import `import`.
+fn stub(store: &Store, import: &ImportType) -> Result<Extern, LinkerError> {
+    let error = |message: String| LinkerError::Stub {
+        module: import.module().to_string(),
+        name: import.name().to_string(),
+        message,
+    };
+    Ok(match import.ty() {
+        ExternType::Function(ty) => {
+            let message = format!(
+                "the unknown import `{}`.`{}` was called",
+                import.module(),
+                import.name()
+            );
+            Function::new(store, ty, move |_| Err(RuntimeError::new(message.clone()))).into()
+        }
+        ExternType::Global(ty) if ty.mutability.is_mutable() => {
+            Global::new_mut(store, zero(ty.ty)).into()
+        }
+        ExternType::Global(ty) => Global::new(store, zero(ty.ty)).into(),
+        ExternType::Table(ty) => Table::new(store, *ty, zero(ty.ty))
+            .map_err(|err| error(err.message()))?
+            .into(),
+        ExternType::Memory(ty) => Memory::new(store, *ty)
+            .map_err(|err| error(err.to_string()))?
+            .into(),
+    })
+}
+
+/// A resolver satisfying the imports `R` doesn't resolve with stubs:
+/// functions trapping with a descriptive message when they're called,
+/// zero-valued globals, tables of null references, and memories of the
+/// minimum size.
+///
+/// It allows to instantiate a module whose optional imports are never
+/// called.
+///
+/// ```
+/// # use wasmer::{ImportObject, Instance, Module, StubResolver, Store};
+/// # fn main() -> anyhow::Result<()> {
+/// let store = Store::default();
+/// let module = Module::new(&store, r#"
+///     (module
+///       (import "env" "optional" (func))
+///       (import "env" "flags" (global i32)))
+/// "#)?;
+/// let resolver = StubResolver::new(&module, ImportObject::new());
+/// let instance = Instance::new(&module, &resolver)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct StubResolver<R: Resolver> {
+    store: Store,
+    imports: Vec<ImportType>,
+    resolver: R,
+}
+
+impl<R: Resolver> StubResolver<R> {
+    /// Creates a resolver satisfying the imports of `module` which
+    /// `resolver` doesn't resolve with stubs.
+    pub fn new(module: &Module, resolver: R) -> Self {
+        Self {
+            store: module.store().clone(),
+            imports: module.imports().collect(),
+            resolver,
+        }
+    }
+}
+
+impl<R: Resolver> Resolver for StubResolver<R> {
+    fn resolve(&self, index: u32, module: &str, field: &str) -> Option<Export> {
+        if let Some(export) = self.resolver.resolve(index, module, field) {
+            return Some(export);
+        }
+        let import = match self.imports.get(index as usize) {
+            Some(import) if import.module() == module && import.name() == field => import,
+            _ => self
+                .imports
+                .iter()
+                .find(|import| import.module() == module && import.name() == field)?,
+        };
+        stub(&self.store, import).ok().map(|stub| stub.to_export())
+    }
+}
@@ -0,0 +1,214 @@
+//! Notifications of the growth of the memories.
+//!
+//! A memory may move when it grows, leaving the pointers into its
+//! bytes dangling, so the bindings caching views of a memory subscribe
+//! to its growth with [`Memory::subscribe_growth`] to invalidate them.
+//!
+//! [`Memory::subscribe_growth`]: crate::Memory::subscribe_growth
+
+use crate::{MemoryType, Pages};
+use std::collections::HashMap;
+use std::fmt;
+use std::ptr::NonNull;
+use std::sync::{Arc, Mutex, Weak};
+use wasmer_vm::{Memory, MemoryError, MemoryStyle, VMMemoryDefinition};
+
+/// The growth of a memory, passed to the callbacks subscribed with
+/// [`Memory::subscribe_growth`].
+///
+/// [`Memory::subscribe_growth`]: crate::Memory::subscribe_growth
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryGrowth {
+    /// The size of the memory before growing.
+    pub old_size: Pages,
+
+    /// The size of the memory after growing.
+    pub new_size: Pages,
+
+    /// The address of the bytes of the memory before growing.
+    pub old_base: *mut u8,
+
+    /// The address of the bytes of the memory after growing. The
+    /// pointers into the old bytes are dangling when it differs from
+    /// `old_base`.
+    pub new_base: *mut u8,
+}
+
+// The addresses are only compared or used by the owner of the memory,
+// so they can be sent to another thread.
+unsafe impl Send for MemoryGrowth {}
+unsafe impl Sync for MemoryGrowth {}
+
+type Callback = Arc<dyn Fn(&MemoryGrowth) + Send + Sync>;
+
+/// The callbacks subscribed to the growth of each memory of a store,
+/// by the address of the definition of the memory. They're shared by
+/// the clones of the store.
+#[derive(Clone, Default)]
+pub(crate) struct GrowthSubscribers(Arc<Mutex<Subscribers>>);
+
+#[derive(Default)]
+struct Subscribers {
+    next_id: u64,
+    callbacks: HashMap<usize, Vec<(u64, Callback)>>,
+}
+
+impl GrowthSubscribers {
+    pub(crate) fn subscribe(
+        &self,
+        memory: NonNull<VMMemoryDefinition>,
+        callback: Callback,
+    ) -> GrowthSubscription {
+        let mut subscribers = self.0.lock().unwrap();
+        let id = subscribers.next_id;
+        subscribers.next_id += 1;
+        let memory = memory.as_ptr() as usize;
+        subscribers
+            .callbacks
+            .entry(memory)
+            .or_default()
+            .push((id, callback));
+        GrowthSubscription {
+            subscribers: Arc::downgrade(&self.0),
+            memory,
+            id,
+        }
+    }
+
+    /// Calls the callbacks subscribed to the growth of `memory`.
+    pub(crate) fn notify(&self, memory: NonNull<VMMemoryDefinition>, growth: &MemoryGrowth) {
+        // The callbacks are called without the lock, so they can
+        // subscribe and unsubscribe.
+        let callbacks = match self
+            .0
+            .lock()
+            .unwrap()
+            .callbacks
+            .get(&(memory.as_ptr() as usize))
+        {
+            Some(callbacks) => callbacks
+                .iter()
+                .map(|(_, callback)| callback.clone())
+                .collect::<Vec<_>>(),
+            None => return,
+        };
+        for callback in callbacks {
+            callback(growth);
+        }
+    }
+
+    /// Forgets the callbacks subscribed to the growth of `memory`, as
+    /// it's dropped.
+    pub(crate) fn forget(&self, memory: NonNull<VMMemoryDefinition>) {
+        self.0
+            .lock()
+            .unwrap()
+            .callbacks
+            .remove(&(memory.as_ptr() as usize));
+    }
+}
+
+impl fmt::Debug for GrowthSubscribers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GrowthSubscribers").finish()
+    }
+}
+
+/// The subscription of a callback to the growth of a memory, returned
+/// by [`Memory::subscribe_growth`]. The callback is unsubscribed when
+/// it's dropped.
+///
+/// [`Memory::subscribe_growth`]: crate::Memory::subscribe_growth
+#[must_use = "the callback is unsubscribed when the subscription is dropped"]
+pub struct GrowthSubscription {
+    subscribers: Weak<Mutex<Subscribers>>,
+    memory: usize,
+    id: u64,
+}
+
+impl Drop for GrowthSubscription {
+    fn drop(&mut self) {
+        if let Some(subscribers) = self.subscribers.upgrade() {
+            let mut subscribers = subscribers.lock().unwrap();
+            if let Some(callbacks) = subscribers.callbacks.get_mut(&self.memory) {
+                callbacks.retain(|(id, _)| *id != self.id);
+                if callbacks.is_empty() {
+                    subscribers.callbacks.remove(&self.memory);
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Debug for GrowthSubscription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GrowthSubscription")
+            .field("id", &self.id)
+            .finish()
+    }
+}
+
+/// Wraps `memory` so its growth is notified to the callbacks
+/// subscribed in `growth`.
+///
+/// All the memories of a store are wrapped, as the callbacks are
+/// subscribed once the memories are created.
+pub(crate) fn notifying_memory(
+    growth: &GrowthSubscribers,
+    memory: Arc<dyn Memory>,
+) -> Arc<dyn Memory> {
+    Arc::new(NotifyingMemory {
+        inner: memory,
+        growth: growth.clone(),
+    })
+}
+
+/// A memory notifying its growth.
+#[derive(Debug)]
+struct NotifyingMemory {
+    inner: Arc<dyn Memory>,
+    growth: GrowthSubscribers,
+}
+
+impl NotifyingMemory {
+    fn base(&self) -> *mut u8 {
+        unsafe { self.inner.vmmemory().as_ref().base }
+    }
+}
+
+impl Memory for NotifyingMemory {
+    fn ty(&self) -> &MemoryType {
+        self.inner.ty()
+    }
+
+    fn style(&self) -> &MemoryStyle {
+        self.inner.style()
+    }
+
+    fn size(&self) -> Pages {
+        self.inner.size()
+    }
+
+    fn grow(&self, delta: Pages) -> Result<Pages, MemoryError> {
+        let old_base = self.base();
+        let old_size = self.inner.grow(delta)?;
+        let growth = MemoryGrowth {
+            old_size,
+            new_size: self.inner.size(),
+            old_base,
+            new_base: self.base(),
+        };
+        self.growth.notify(self.inner.vmmemory(), &growth);
+        Ok(old_size)
+    }
+
+    fn vmmemory(&self) -> NonNull<VMMemoryDefinition> {
+        self.inner.vmmemory()
+    }
+}
+
+impl Drop for NotifyingMemory {
+    fn drop(&mut self) {
+        self.growth.forget(self.inner.vmmemory());
+    }
+}
@@ -0,0 +1,151 @@
+//! The implementation of the `wasmer` API on top of the Wasmer
+//! runtime: the engines, the compilers and `wasmer-vm`. It's used on
+//! every target but `wasm32`, where the `js` feature delegates to the
+//! JavaScript `WebAssembly` API instead.
+
+mod call_hook;
+mod component;
+mod env;
+mod event_log;
+mod exports;
+mod externals;
+mod import_object;
+mod instance;
+mod inventory;
+mod labels;
+mod limits;
+mod linker;
+mod memory_growth;
+mod module;
+mod native;
+mod ptr;
+mod resources;
+mod snapshot;
+mod store;
+mod tunables;
+mod types;
+
+/// Implement [`WasmerEnv`] for your type with `#[derive(WasmerEnv)]`.
+///
+/// See the [`WasmerEnv`] trait for more information.
+pub use wasmer_derive::WasmerEnv;
+
+#[doc(hidden)]
+pub mod internals {
+    //! We use the internals module for exporting types that are only
+    //! intended to use in internal crates such as the compatibility crate
+    //! `wasmer-vm`. Please don't use any of this types directly, as
+    //! they might change frequently or be removed in the future.
+
+    #[cfg(feature = "deprecated")]
+    pub use crate::sys::externals::{UnsafeMutableEnv, WithUnsafeMutableEnv};
+    pub use crate::sys::externals::{WithEnv, WithoutEnv};
+}
+
+pub use crate::sys::call_hook::{CallHook, CallHookEvent, CallHookKind};
+pub use crate::sys::component::{
+    Component, ComponentError, ComponentFunc, ComponentInstance, ComponentVal, ComponentValue,
+    ComponentValues, Linker, TypedComponentFunc,
+};
+pub use crate::sys::env::{HostEnvInitError, LazyInit, WasmerEnv};
+pub use crate::sys::event_log::StoreEvent;
+pub use crate::sys::exports::{ExportError, Exportable, Exports, ExportsIterator};
+pub use crate::sys::externals::{
+    Extern, FromToNativeWasmType, Function, Global, HostFunction, IndirectCallAction, Memory,
+    Table, TableInterposer, TypedGlobal, WasmTypeList,
+};
+pub use crate::sys::import_object::{ImportObject, ImportObjectIterator, LikeNamespace};
+pub use crate::sys::instance::{ImportEnvError, Instance, InstantiationError};
+pub use crate::sys::inventory::{InstanceEntry, ModuleEntry};
+pub use crate::sys::labels::Labels;
+pub use crate::sys::limits::StoreLimits;
+pub use crate::sys::linker::{LinkerError, ModuleLinker, StubResolver};
+pub use crate::sys::memory_growth::{GrowthSubscription, MemoryGrowth};
+pub use crate::sys::module::{ConvertError, Module, ProducersField, ProducersFieldValue};
+pub use crate::sys::native::NativeFunc;
+pub use crate::sys::ptr::{Array, Item, WasmPtr};
+pub use crate::sys::resources::{ResourceError, Resources};
+pub use crate::sys::snapshot::{InstanceSnapshot, SnapshotError};
+pub use crate::sys::store::{Store, StoreObject};
+pub use crate::sys::tunables::BaseTunables;
+pub use crate::sys::types::{
+    ExportType, ExternRef, ExternType, FunctionType, GlobalType, HostInfo, HostRef, ImportType,
+    MemoryType, Mutability, TableType, Val, ValType,
+};
+pub use crate::sys::types::{Val as Value, ValType as Type};
+pub use target_lexicon::{Architecture, CallingConvention, OperatingSystem, Triple, HOST};
+pub use wasmer_compiler::component::{
+    ComponentExternType, ComponentFuncType, ComponentInstanceType, ComponentTypeDef,
+    ComponentValType, ResourceType,
+};
+#[cfg(feature = "compiler")]
+pub use wasmer_compiler::{
+    wasmparser, CompilerConfig, FunctionMiddleware, MiddlewareError, MiddlewareReaderState,
+    ModuleItemInjector, ModuleMiddleware,
+};
+pub use wasmer_compiler::{
+    CompileError, CpuFeature, Features, ParseCpuFeatureError, Target, UnwindInfoMode, WasmError,
+    WasmResult,
+};
+pub use wasmer_engine::{
+    Artifact, ArtifactMetadata, BatchCompileStats, ChainableNamedResolver, DeserializeError,
+    Engine, EngineId, Export, FrameInfo, LinkError, NamedResolver, NamedResolverChain, Resolver,
+    RuntimeError, SerializeError, SourceLocation, SourceMap, SourceMapError, Tunables,
+    TunablesConfig,
+};
+pub use wasmer_types::{
+    Atomically, Bytes, ExportIndex, GlobalIndex, GlobalInit, LocalFunctionIndex, MemoryAccessError,
+    MemoryIndex, MemoryView, Pages, ValueType, WASM_MAX_PAGES, WASM_MIN_PAGES, WASM_PAGE_SIZE,
+};
+
+// TODO: should those be moved into wasmer::vm as well?
+pub use wasmer_vm::{raise_user_trap, MemoryError, VMExport};
+pub mod vm {
+    //! The vm module re-exports wasmer-vm types.
+
+    pub use wasmer_vm::libcalls::Libcalls;
+    #[cfg(unix)]
+    pub use wasmer_vm::previous_signal_handler;
+    pub use wasmer_vm::{
+        catch_traps, defer_init_traps, raise_lib_trap, set_trap_dispatcher, BoundsCheck,
+        CustomMemory, FunctionBodyPtr, Memory, MemoryError, MemoryStorage, MemoryStyle, ModuleInfo,
+        SignatureRegistry, Table, TableStyle, Trap, TrapCode, TrapDispatch, TrapDispatcher,
+        VMContext, VMFunctionBody, VMFunctionEnvironment, VMMemoryDefinition,
+        VMSharedSignatureIndex, VMTableDefinition, VMTrampoline,
+    };
+}
+
+// The compilers are mutually exclusive
+#[cfg(any(
+    all(
+        feature = "default-llvm",
+        any(feature = "default-cranelift", feature = "default-singlepass")
+    ),
+    all(feature = "default-cranelift", feature = "default-singlepass")
+))]
+compile_error!(
+    r#"The `default-singlepass`, `default-cranelift` and `default-llvm` features are mutually exclusive.
+If you wish to use more than one compiler, you can simply create the own store. Eg.:
+
+```
+use wasmer::{Store, JIT, Singlepass};
+
+let engine = JIT::new(Singlepass::default()).engine();
+let store = Store::new(&engine);
+```"#
+);
+
+#[cfg(feature = "singlepass")]
+pub use wasmer_compiler_singlepass::Singlepass;
+
+#[cfg(feature = "cranelift")]
+pub use wasmer_compiler_cranelift::{Cranelift, CraneliftOptLevel};
+
+#[cfg(feature = "llvm")]
+pub use wasmer_compiler_llvm::{LLVMOptLevel, LLVM};
+
+#[cfg(feature = "jit")]
+pub use wasmer_engine_jit::{JITArtifact, JITEngine, ProfilingStrategy, JIT};
+
+#[cfg(feature = "native")]
+pub use wasmer_engine_native::{Native, NativeArtifact, NativeEngine};
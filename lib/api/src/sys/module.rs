@@ -0,0 +1,830 @@
+use crate::sys::inventory::ModuleRecord;
+use crate::sys::labels::InstanceLabels;
+use crate::sys::store::Store;
+use crate::sys::types::{ExportType, ImportType};
+use crate::InstantiationError;
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use thiserror::Error;
+use wasmer_compiler::CompileError;
+#[cfg(feature = "wat")]
+use wasmer_compiler::WasmError;
+use wasmer_engine::{
+    Artifact, ArtifactMetadata, BatchCompileStats, DeserializeError, Resolver, SerializeError,
+    SourceMap,
+};
+use wasmer_types::{Bytes, FunctionIndex};
+use wasmer_vm::{ExportsIterator, ImportsIterator, InstanceHandle, ModuleInfo};
+
+#[derive(Error, Debug)]
+pub enum IoCompileError {
+    /// An IO error
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    /// A compilation error
+    #[error(transparent)]
+    Compile(#[from] CompileError),
+}
+
+/// An error while converting a module with [`Module::convert`].
+#[derive(Error, Debug)]
+pub enum ConvertError {
+    /// The artifact of the module doesn't embed its original wasm, so
+    /// the module must be recompiled from its source.
+    #[error("the artifact doesn't embed the original wasm, the module must be recompiled from its source")]
+    WasmNotEmbedded,
+    /// A compilation error
+    #[error(transparent)]
+    Compile(#[from] CompileError),
+}
+
+/// A WebAssembly Module contains stateless WebAssembly
+/// code that has already been compiled and can be instantiated
+/// multiple times.
+///
+/// ## Cloning a module
+///
+/// Cloning a module is cheap: it does a shallow copy of the compiled
+/// contents rather than a deep copy.
+#[derive(Clone)]
+pub struct Module {
+    store: Store,
+    artifact: Arc<dyn Artifact>,
+    record: Arc<ModuleRecord>,
+}
+
+impl Module {
+    /// Creates a new WebAssembly Module given the configuration
+    /// in the store.
+    ///
+    /// If the provided bytes are not WebAssembly-like (start with `b"\0asm"`),
+    /// and the "wat" feature is enabled for this crate, this function will try to
+    /// to convert the bytes assuming they correspond to the WebAssembly text
+    /// format.
+    ///
+    /// ## Security
+    ///
+    /// Before the code is compiled, it will be validated using the store
+    /// features.
+    ///
+    /// ## Errors
+    ///
+    /// Creating a WebAssembly module from bytecode can result in a
+    /// [`CompileError`] since this operation requires to transorm the Wasm
+    /// bytecode into code the machine can easily execute (normally through a JIT).
+    ///
+    /// ## Example
+    ///
+    /// Reading from a WAT file.
+    ///
+    /// ```
+    /// use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let wat = "(module)";
+    /// let module = Module::new(&store, wat)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Reading from bytes:
+    ///
+    /// ```
+    /// use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// // The following is the same as:
+    /// // (module
+    /// //   (type $t0 (func (param i32) (result i32)))
+    /// //   (func $add_one (export "add_one") (type $t0) (param $p0 i32) (result i32)
+    /// //     get_local $p0
+    /// //     i32.const 1
+    /// //     i32.add)
+    /// // )
+    /// let bytes: Vec<u8> = vec![
+    ///     0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x06, 0x01, 0x60,
+    ///     0x01, 0x7f, 0x01, 0x7f, 0x03, 0x02, 0x01, 0x00, 0x07, 0x0b, 0x01, 0x07,
+    ///     0x61, 0x64, 0x64, 0x5f, 0x6f, 0x6e, 0x65, 0x00, 0x00, 0x0a, 0x09, 0x01,
+    ///     0x07, 0x00, 0x20, 0x00, 0x41, 0x01, 0x6a, 0x0b, 0x00, 0x1a, 0x04, 0x6e,
+    ///     0x61, 0x6d, 0x65, 0x01, 0x0a, 0x01, 0x00, 0x07, 0x61, 0x64, 0x64, 0x5f,
+    ///     0x6f, 0x6e, 0x65, 0x02, 0x07, 0x01, 0x00, 0x01, 0x00, 0x02, 0x70, 0x30,
+    /// ];
+    /// let module = Module::new(&store, bytes)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[allow(unreachable_code)]
+    pub fn new(store: &Store, bytes: impl AsRef<[u8]>) -> Result<Self, CompileError> {
+        #[cfg(feature = "wat")]
+        let bytes = wat::parse_bytes(bytes.as_ref()).map_err(|e| {
+            CompileError::Wasm(WasmError::Generic(format!(
+                "Error when converting wat: {}",
+                e
+            )))
+        })?;
+
+        Self::from_binary(store, bytes.as_ref())
+    }
+
+    /// Creates a new WebAssembly module from a file path.
+    pub fn from_file(store: &Store, file: impl AsRef<Path>) -> Result<Self, IoCompileError> {
+        let file_ref = file.as_ref();
+        let canonical = file_ref.canonicalize()?;
+        let wasm_bytes = std::fs::read(file_ref)?;
+        let mut module = Self::new(store, &wasm_bytes)?;
+        // Set the module name to the absolute path of the filename.
+        // This is useful for debugging the stack traces.
+        let filename = canonical.as_path().to_str().unwrap();
+        module.set_name(filename);
+        Ok(module)
+    }
+
+    /// Creates a new WebAssembly module from a binary.
+    ///
+    /// Opposed to [`Module::new`], this function is not compatible with
+    /// the WebAssembly text format (if the "wat" feature is enabled for
+    /// this crate).
+    pub fn from_binary(store: &Store, binary: &[u8]) -> Result<Self, CompileError> {
+        Self::validate(store, binary)?;
+        unsafe { Self::from_binary_unchecked(store, binary) }
+    }
+
+    /// Creates a new WebAssembly module skipping any kind of validation.
+    ///
+    /// # Safety
+    ///
+    /// This can speed up compilation time a bit, but it should be only used
+    /// in environments where the WebAssembly modules are trusted and validated
+    /// beforehand.
+    pub unsafe fn from_binary_unchecked(
+        store: &Store,
+        binary: &[u8],
+    ) -> Result<Self, CompileError> {
+        let module = Self::compile(store, binary)?;
+        Ok(module)
+    }
+
+    /// Validates and compiles many WebAssembly binaries at once.
+    ///
+    /// Identical binaries are compiled only once, and the compiler may
+    /// compile the binaries in parallel and reuse the code of their
+    /// identical function bodies. The modules are returned in the same
+    /// order as the binaries, along with the statistics of the
+    /// compilation, see [`Engine::compile_batch`][wasmer_engine::Engine::compile_batch].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let add_one = wat2wasm(br#"(module (func (param i32) (result i32) local.get 0 i32.const 1 i32.add))"#)?;
+    /// let empty = wat2wasm(b"(module)")?;
+    /// let (modules, stats) = Module::compile_batch(&store, &[&add_one, &empty, &add_one])?;
+    /// assert_eq!(modules.len(), 3);
+    /// assert_eq!(stats.compiled_modules, 2);
+    /// assert_eq!(stats.compiled_function_bodies(), 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn compile_batch(
+        store: &Store,
+        binaries: &[&[u8]],
+    ) -> Result<(Vec<Self>, BatchCompileStats), CompileError> {
+        Self::check_deterministic(store)?;
+        let compilation = store.engine().compile_batch(binaries, store.tunables())?;
+        let modules = compilation
+            .artifacts
+            .into_iter()
+            .map(|artifact| Self::from_artifact(store, artifact))
+            .collect();
+        Ok((modules, compilation.stats))
+    }
+
+    /// Validates a new WebAssembly Module given the configuration
+    /// in the Store.
+    ///
+    /// This validation is normally pretty fast and checks the enabled
+    /// WebAssembly features in the Store Engine to assure deterministic
+    /// validation of the Module.
+    pub fn validate(store: &Store, binary: &[u8]) -> Result<(), CompileError> {
+        store.engine().validate(binary)
+    }
+
+    fn compile(store: &Store, binary: &[u8]) -> Result<Self, CompileError> {
+        Self::check_deterministic(store)?;
+        let artifact = store.engine().compile(binary, store.tunables())?;
+        Ok(Self::from_artifact(store, artifact))
+    }
+
+    /// Checks that the engine of `store` compiles deterministic code,
+    /// if the store requires it, see [`Store::set_deterministic`].
+    fn check_deterministic(store: &Store) -> Result<(), CompileError> {
+        if store.is_deterministic() && !store.engine().deterministic() {
+            return Err(CompileError::Validate(
+                "the store requires a deterministic execution, but its engine doesn't compile \
+                 deterministic code"
+                    .to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Serializes a module into a binary representation that the `Engine`
+    /// can later process via [`Module::deserialize`].
+    ///
+    /// # Usage
+    ///
+    /// ```ignore
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// # let module = Module::from_file(&store, "path/to/foo.wasm")?;
+    /// let serialized = module.serialize()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn serialize(&self) -> Result<Vec<u8>, SerializeError> {
+        self.artifact.serialize()
+    }
+
+    /// Serializes a module into a file that the `Engine`
+    /// can later process via [`Module::deserialize_from_file`].
+    ///
+    /// # Usage
+    ///
+    /// ```ignore
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// # let module = Module::from_file(&store, "path/to/foo.wasm")?;
+    /// module.serialize_to_file("path/to/foo.so")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn serialize_to_file(&self, path: impl AsRef<Path>) -> Result<(), SerializeError> {
+        self.artifact.serialize_to_file(path.as_ref())
+    }
+
+    /// Serializes a module into bytes like [`Module::serialize`], with
+    /// the key/value `metadata` of the host (a build id, a tenant id,
+    /// the flags of the compiler…) embedded in it.
+    ///
+    /// The metadata can be read back with [`Module::read_metadata`]
+    /// without deserializing the module.
+    ///
+    /// # Usage
+    ///
+    /// ```ignore
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// # let module = Module::from_file(&store, "path/to/foo.wasm")?;
+    /// let mut metadata = ArtifactMetadata::new();
+    /// metadata.insert("build-id".to_string(), "1234".to_string());
+    /// let serialized = module.serialize_with_metadata(&metadata)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn serialize_with_metadata(
+        &self,
+        metadata: &ArtifactMetadata,
+    ) -> Result<Vec<u8>, SerializeError> {
+        self.artifact.serialize_with_metadata(metadata)
+    }
+
+    /// Serializes a module into a file like
+    /// [`Module::serialize_to_file`], with the key/value `metadata` of
+    /// the host embedded in it.
+    pub fn serialize_to_file_with_metadata(
+        &self,
+        path: impl AsRef<Path>,
+        metadata: &ArtifactMetadata,
+    ) -> Result<(), SerializeError> {
+        self.artifact
+            .serialize_to_file_with_metadata(path.as_ref(), metadata)
+    }
+
+    /// Reads the metadata embedded in the serialized module at `path`
+    /// by [`Module::serialize_to_file_with_metadata`].
+    ///
+    /// Only the end of the file is read: the module isn't loaded in
+    /// memory, so it's safe to call on any file. A module serialized
+    /// without metadata has no entries.
+    ///
+    /// # Usage
+    ///
+    /// ```ignore
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// let metadata = Module::read_metadata("path/to/foo.so")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read_metadata(path: impl AsRef<Path>) -> Result<ArtifactMetadata, DeserializeError> {
+        wasmer_engine::read_metadata(path.as_ref())
+    }
+
+    /// Deserializes a serialized Module binary into a `Module`.
+    /// > Note: the module has to be serialized before with the `serialize` method.
+    ///
+    /// # Safety
+    ///
+    /// This function is inherently **unsafe** as the provided bytes:
+    /// 1. Are going to be deserialized directly into Rust objects.
+    /// 2. Contains the function assembly bodies and, if intercepted,
+    ///    a malicious actor could inject code into executable
+    ///    memory.
+    ///
+    /// And as such, the `deserialize` method is unsafe.
+    ///
+    /// # Usage
+    ///
+    /// ```ignore
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let module = Module::deserialize(&store, serialized_data)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub unsafe fn deserialize(store: &Store, bytes: &[u8]) -> Result<Self, DeserializeError> {
+        let artifact = store.engine().deserialize(bytes)?;
+        Ok(Self::from_artifact(store, artifact))
+    }
+
+    /// Deserializes a a serialized Module located in a `Path` into a `Module`.
+    /// > Note: the module has to be serialized before with the `serialize` method.
+    ///
+    /// # Safety
+    ///
+    /// Please check [`Module::deserialize`].
+    ///
+    /// # Usage
+    ///
+    /// ```ignore
+    /// # use wasmer::*;
+    /// # let store = Store::default();
+    /// # fn main() -> anyhow::Result<()> {
+    /// let module = Module::deserialize_from_file(&store, path)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub unsafe fn deserialize_from_file(
+        store: &Store,
+        path: impl AsRef<Path>,
+    ) -> Result<Self, DeserializeError> {
+        let artifact = store.engine().deserialize_from_file(path.as_ref())?;
+        Ok(Self::from_artifact(store, artifact))
+    }
+
+    /// Deserializes a serialized Module that comes from an untrusted
+    /// source, like [`Module::deserialize`] but with all the checks
+    /// of the engine on.
+    ///
+    /// The metadata of the module is parsed without reading or
+    /// allocating more than the provided bytes, and all its indices
+    /// and offsets are checked to be in bounds before its code is
+    /// loaded, so that a malformed module is reported as a
+    /// [`DeserializeError`] instead of causing undefined behavior.
+    ///
+    /// Only the JIT engine supports it: the native engine loads the
+    /// shared object of the module before it can read its metadata,
+    /// so it returns an error.
+    ///
+    /// # Safety
+    ///
+    /// The machine code of the module isn't verified, so it must have
+    /// been generated by a trusted compiler (e.g. the artifact has
+    /// been signed by the party that compiled it).
+    ///
+    /// # Usage
+    ///
+    /// ```ignore
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let module = Module::deserialize_untrusted(&store, uploaded_bytes)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub unsafe fn deserialize_untrusted(
+        store: &Store,
+        bytes: &[u8],
+    ) -> Result<Self, DeserializeError> {
+        let artifact = store.engine().deserialize_untrusted(bytes)?;
+        Ok(Self::from_artifact(store, artifact))
+    }
+
+    /// Returns the original wasm of the module, if the engine that
+    /// compiled it embeds it in its artifacts (see `JIT::embed_wasm`
+    /// and `Native::embed_wasm`).
+    pub fn wasm(&self) -> Option<&[u8]> {
+        self.artifact.wasm()
+    }
+
+    /// Recompiles the module for the engine and the target of
+    /// `store`, from the original wasm embedded in its artifact.
+    ///
+    /// It re-targets a serialized module to another engine or target
+    /// once deserialized, e.g. to migrate from JIT to native
+    /// artifacts. The name of the module is kept.
+    ///
+    /// # Errors
+    ///
+    /// If the artifact doesn't embed the original wasm,
+    /// [`ConvertError::WasmNotEmbedded`] is returned: the module must
+    /// be recompiled from its source.
+    pub fn convert(&self, store: &Store) -> Result<Self, ConvertError> {
+        let wasm = self.wasm().ok_or(ConvertError::WasmNotEmbedded)?;
+        let mut module = Self::from_binary(store, wasm)?;
+        if let Some(name) = self.name() {
+            if module.name() != Some(name) {
+                module.set_name(name);
+            }
+        }
+        Ok(module)
+    }
+
+    fn from_artifact(store: &Store, artifact: Arc<dyn Artifact>) -> Self {
+        let data_size = artifact
+            .data_initializers()
+            .iter()
+            .map(|initializer| initializer.data.len())
+            .sum();
+        let record = store
+            .inventory()
+            .add_module(artifact.module_ref().name.as_deref(), Bytes(data_size));
+        Self {
+            store: store.clone(),
+            artifact,
+            record,
+        }
+    }
+
+    pub(crate) fn record(&self) -> &Arc<ModuleRecord> {
+        &self.record
+    }
+
+    pub(crate) fn instantiate(
+        &self,
+        resolver: &dyn Resolver,
+        labels: InstanceLabels,
+    ) -> Result<InstanceHandle, InstantiationError> {
+        unsafe {
+            let instance_handle =
+                self.artifact
+                    .instantiate(self.store.tunables(), resolver, Box::new(labels))?;
+
+            // After the instance handle is created, we need to initialize
+            // the data, call the start function and so. However, if any
+            // of this steps traps, we still need to keep the instance alive
+            // as some of the Instance elements may have placed in other
+            // instance tables.
+            self.artifact.finish_instantiation(&instance_handle)?;
+
+            Ok(instance_handle)
+        }
+    }
+
+    /// Returns the name of the current module.
+    ///
+    /// This name is normally set in the WebAssembly bytecode by some
+    /// compilers, but can be also overwritten using the [`Module::set_name`] method.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let wat = "(module $moduleName)";
+    /// let module = Module::new(&store, wat)?;
+    /// assert_eq!(module.name(), Some("moduleName"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn name(&self) -> Option<&str> {
+        self.artifact.module_ref().name.as_deref()
+    }
+
+    /// Sets the name of the current module.
+    /// This is normally useful for stacktraces and debugging.
+    ///
+    /// It will return `true` if the module name was changed successfully,
+    /// and return `false` otherwise (in case the module is already
+    /// instantiated).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let wat = "(module)";
+    /// let mut module = Module::new(&store, wat)?;
+    /// assert_eq!(module.name(), None);
+    /// module.set_name("foo");
+    /// assert_eq!(module.name(), Some("foo"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_name(&mut self, name: &str) -> bool {
+        let record = &self.record;
+        Arc::get_mut(&mut self.artifact)
+            .and_then(|artifact| artifact.module_mut())
+            .map(|mut module_info| {
+                module_info.name = Some(name.to_string());
+                record.set_name(name);
+                true
+            })
+            .unwrap_or(false)
+    }
+
+    /// Attaches a source map to the current module, so the frames of
+    /// the [`RuntimeError`]s resolve to the original source code (see
+    /// [`FrameInfo::source_location`]).
+    ///
+    /// It will return `true` if the source map was attached successfully,
+    /// and return `false` otherwise (in case the module is already
+    /// instantiated, or the engine doesn't support source maps).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let wat = "(module)";
+    /// let mut module = Module::new(&store, wat)?;
+    /// let source_map = SourceMap::from_json(
+    ///     r#"{"version": 3, "sources": ["main.ts"], "names": [], "mappings": "AAAA"}"#,
+    /// )?;
+    /// module.set_source_map(source_map);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`RuntimeError`]: crate::RuntimeError
+    /// [`FrameInfo::source_location`]: crate::FrameInfo::source_location
+    pub fn set_source_map(&mut self, source_map: SourceMap) -> bool {
+        Arc::get_mut(&mut self.artifact)
+            .map(|artifact| artifact.set_source_map(Arc::new(source_map)))
+            .unwrap_or(false)
+    }
+
+    /// Returns the source map attached to the current module, if any.
+    pub fn source_map(&self) -> Option<&SourceMap> {
+        self.artifact.source_map().map(|source_map| &**source_map)
+    }
+
+    /// Returns the URL of the source map of the current module, as
+    /// stored in its `sourceMappingURL` custom section.
+    ///
+    /// Loading the source map is left to the embedder, since the URL
+    /// is often relative to where the module was fetched from.
+    pub fn source_mapping_url(&self) -> Option<String> {
+        let section = self.custom_sections("sourceMappingURL").next()?;
+        // The section contains a single string, prefixed by its length.
+        let mut length = 0usize;
+        let mut offset = 0;
+        loop {
+            let byte = *section.get(offset)?;
+            length |= ((byte & 0x7f) as usize).checked_shl(7 * offset as u32)?;
+            offset += 1;
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+        let url = section.get(offset..offset.checked_add(length)?)?;
+        String::from_utf8(url.to_vec()).ok()
+    }
+
+    /// Returns an iterator over the imported types in the Module.
+    ///
+    /// The order of the imports is guaranteed to be the same as in the
+    /// WebAssembly bytecode.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let wat = r#"(module
+    ///     (import "host" "func1" (func))
+    ///     (import "host" "func2" (func))
+    /// )"#;
+    /// let module = Module::new(&store, wat)?;
+    /// for import in module.imports() {
+    ///     assert_eq!(import.module(), "host");
+    ///     assert!(import.name().contains("func"));
+    ///     import.ty();
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn imports<'a>(&'a self) -> ImportsIterator<impl Iterator<Item = ImportType> + 'a> {
+        self.artifact.module_ref().imports()
+    }
+
+    /// Returns an iterator over the exported types in the Module.
+    ///
+    /// The order of the exports is guaranteed to be the same as in the
+    /// WebAssembly bytecode.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let wat = r#"(module
+    ///     (func (export "namedfunc"))
+    ///     (memory (export "namedmemory") 1)
+    /// )"#;
+    /// let module = Module::new(&store, wat)?;
+    /// for export_ in module.exports() {
+    ///     assert!(export_.name().contains("named"));
+    ///     export_.ty();
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn exports<'a>(&'a self) -> ExportsIterator<impl Iterator<Item = ExportType> + 'a> {
+        self.artifact.module_ref().exports()
+    }
+
+    /// Get the custom sections of the module given a `name`.
+    ///
+    /// # Important
+    ///
+    /// Following the WebAssembly spec, one name can have multiple
+    /// custom sections. That's why an iterator (rather than one element)
+    /// is returned.
+    pub fn custom_sections<'a>(&'a self, name: &'a str) -> impl Iterator<Item = Arc<[u8]>> + 'a {
+        self.artifact.module_ref().custom_sections(name)
+    }
+
+    /// Returns the names and the contents of all the custom sections
+    /// of the module, in the order they appear, without copying them.
+    ///
+    /// The `name` section is parsed at compilation instead, see
+    /// [`Module::function_names`] and [`Module::local_names`].
+    pub fn all_custom_sections(&self) -> impl Iterator<Item = (&str, Arc<[u8]>)> + '_ {
+        self.artifact.module_ref().all_custom_sections()
+    }
+
+    /// Returns the name of the function `index` in the `name` section,
+    /// if any. The imported functions come first in the index space.
+    pub fn function_name(&self, index: u32) -> Option<&str> {
+        self.artifact
+            .module_ref()
+            .function_names
+            .get(&FunctionIndex::from_u32(index))
+            .map(String::as_str)
+    }
+
+    /// Returns the indices and the names of the functions named in the
+    /// `name` section, sorted by index.
+    pub fn function_names(&self) -> Vec<(u32, &str)> {
+        let mut names = self
+            .artifact
+            .module_ref()
+            .function_names
+            .iter()
+            .map(|(index, name)| (index.as_u32(), name.as_str()))
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+        names
+    }
+
+    /// Returns the indices and the names of the locals of the function
+    /// `function` named in the `name` section, sorted by index. The
+    /// parameters come first in the index space.
+    pub fn local_names(&self, function: u32) -> Vec<(u32, &str)> {
+        let mut names = self
+            .artifact
+            .module_ref()
+            .local_names
+            .get(&FunctionIndex::from_u32(function))
+            .into_iter()
+            .flatten()
+            .map(|(index, name)| (*index, name.as_str()))
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+        names
+    }
+
+    /// Returns the fields of the `producers` section of the module,
+    /// like the languages and the tools which produced it.
+    ///
+    /// Returns `None` if the module has no `producers` section, or if
+    /// it's malformed.
+    pub fn producers(&self) -> Option<Vec<ProducersField>> {
+        let section = self.custom_sections("producers").next()?;
+        let mut reader = SectionReader(&section);
+        let fields = (0..reader.u32()?)
+            .map(|_| {
+                let name = reader.string()?;
+                let values = (0..reader.u32()?)
+                    .map(|_| {
+                        Some(ProducersFieldValue {
+                            name: reader.string()?,
+                            version: reader.string()?,
+                        })
+                    })
+                    .collect::<Option<_>>()?;
+                Some(ProducersField { name, values })
+            })
+            .collect::<Option<_>>()?;
+        if reader.0.is_empty() {
+            Some(fields)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the [`Store`] where the `Instance` belongs.
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+
+    /// The ABI of the ModuleInfo is very unstable, we refactor it very often.
+    /// This function is public because in some cases it can be useful to get some
+    /// extra information from the module.
+    ///
+    /// However, the usage is highly discouraged.
+    #[doc(hidden)]
+    pub fn info(&self) -> &ModuleInfo {
+        &self.artifact.module_ref()
+    }
+
+    /// Gets the [`Artifact`] used internally by the Module.
+    ///
+    /// This API is hidden because it's not necessarily stable;
+    /// this functionality is required for some core functionality though, like
+    /// the object file engine.
+    #[doc(hidden)]
+    pub fn artifact(&self) -> &Arc<dyn Artifact> {
+        &self.artifact
+    }
+}
+
+/// A field of the `producers` section of a module, see
+/// [`Module::producers`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProducersField {
+    /// The name of the field, like `language`, `processed-by` or `sdk`.
+    pub name: String,
+    /// The values of the field.
+    pub values: Vec<ProducersFieldValue>,
+}
+
+/// A value of a field of the `producers` section of a module, like a
+/// language or a tool.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProducersFieldValue {
+    /// The name of the language or of the tool.
+    pub name: String,
+    /// Its version, possibly empty.
+    pub version: String,
+}
+
+/// Reads the integers and the strings of a custom section.
+struct SectionReader<'a>(&'a [u8]);
+
+impl<'a> SectionReader<'a> {
+    fn u32(&mut self) -> Option<u32> {
+        let mut result = 0u32;
+        for shift in (0..35).step_by(7) {
+            let (&byte, rest) = self.0.split_first()?;
+            self.0 = rest;
+            result |= u32::from(byte & 0x7f).checked_shl(shift)?;
+            if byte & 0x80 == 0 {
+                return Some(result);
+            }
+        }
+        None
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let length = self.u32()? as usize;
+        if self.0.len() < length {
+            return None;
+        }
+        let (string, rest) = self.0.split_at(length);
+        self.0 = rest;
+        String::from_utf8(string.to_vec()).ok()
+    }
+}
+
+impl fmt::Debug for Module {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Module")
+            .field("name", &self.name())
+            .finish()
+    }
+}
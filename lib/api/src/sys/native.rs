@@ -9,15 +9,22 @@
 //! ```
 use std::marker::PhantomData;
 
-use crate::externals::function::{
+use crate::sys::call_hook::{values_from_binaries, CallHookScope};
+use crate::sys::event_log::EventLogScope;
+use crate::sys::externals::function::{
     DynamicFunctionWithEnv, DynamicFunctionWithoutEnv, FunctionDefinition, HostFunctionDefinition,
     VMDynamicFunction, WasmFunctionDefinition,
 };
+use crate::sys::labels::InstanceLabels;
 use crate::{FromToNativeWasmType, Function, RuntimeError, Store, WasmTypeList};
 use std::panic::{catch_unwind, AssertUnwindSafe};
+use wasmer_compiler::CALL_DEPTH_GLOBAL;
 use wasmer_engine::ExportFunction;
 use wasmer_types::NativeWasmType;
-use wasmer_vm::{VMDynamicFunctionContext, VMFunctionBody, VMFunctionEnvironment, VMFunctionKind};
+use wasmer_vm::{
+    catch_traps_with_result, VMDynamicFunctionContext, VMFunctionBody, VMFunctionEnvironment,
+    VMFunctionKind,
+};
 
 /// A WebAssembly function that can be called natively
 /// (using the Native ABI).
@@ -153,15 +160,35 @@ macro_rules! impl_native_traits {
                             }
                             rets_list.as_mut()
                         };
+                        let instance_ref = self.exported.vm_function.instance_ref.as_ref();
+                        let _execution = instance_ref.map(|instance| self.store.enter_instance(instance)).transpose()?;
+                        let labels = || InstanceLabels::of_function(self.store.label_set(), instance_ref);
+                        let hook = self.store.call_hook().map(|hook| hook.labeled(labels()));
+                        let _hook_scope = CallHookScope::enter(hook.clone());
+                        let _log_scope = EventLogScope::enter(self.store.event_log());
+                        let wasm_call = hook.map(|hook| {
+                            hook.calling_wasm(self.address() as usize, || {
+                                values_from_binaries(args_rets, <( $( $x ),* ) as WasmTypeList>::wasm_types())
+                            })
+                        });
+                        let _call_depth = instance_ref.and_then(|instance| instance.save_middleware_globals(CALL_DEPTH_GLOBAL));
+                        let _stack_limit = self.store.stack_limit().enter();
                         if trampoline_checked {
-                            unsafe {
+                            if let Err(error) = unsafe {
                                 wasmer_vm::wasmer_call_trampoline(
                                     self.vmctx(),
                                     trampoline,
                                     self.address(),
                                     args_rets.as_mut_ptr() as *mut u8,
                                 )
-                            }?;
+                            } {
+                                if let Some(wasm_call) = wasm_call {
+                                    wasm_call.failed();
+                                }
+                                let error = RuntimeError::from_trap(error).with_labels(labels);
+                                self.store.event_log().trapped(&error);
+                                return Err(error);
+                            }
                         } else {
                             unsafe {
                                 wasmer_vm::wasmer_call_trampoline_unchecked(
@@ -184,6 +211,9 @@ macro_rules! impl_native_traits {
                                                               num_rets);
                             }
                         }
+                        if let Some(wasm_call) = wasm_call {
+                            wasm_call.returned(|| values_from_binaries(rets_list_array.as_mut(), Rets::wasm_types()));
+                        }
                         Ok(Rets::from_array(rets_list_array))
                         // TODO: When the Host ABI and Wasm ABI are the same, we could do this instead:
                         // but we can't currently detect whether that's safe.
@@ -203,11 +233,16 @@ macro_rules! impl_native_traits {
                     }) => {
                         match self.arg_kind() {
                             VMFunctionKind::Static => {
+                                // The errors of the host function are raised as
+                                // traps, caught to keep them typed.
                                 let results = catch_unwind(AssertUnwindSafe(|| unsafe {
-                                    let f = std::mem::transmute::<_, unsafe extern "C" fn( VMFunctionEnvironment, $( $x, )*) -> Rets::CStruct>(self.address());
-                                    // We always pass the vmctx
-                                    f( self.vmctx(), $( $x, )* )
-                                })).map_err(|e| RuntimeError::new(format!("{:?}", e)))?;
+                                    catch_traps_with_result(self.vmctx(), || {
+                                        let f = std::mem::transmute::<_, unsafe extern "C" fn( VMFunctionEnvironment, $( $x, )*) -> Rets::CStruct>(self.address());
+                                        // We always pass the vmctx
+                                        f( self.vmctx(), $( $x, )* )
+                                    })
+                                })).map_err(|e| RuntimeError::new(format!("{:?}", e)))?
+                                    .map_err(RuntimeError::from_trap)?;
                                 Ok(Rets::from_c_struct(results))
                             },
                             VMFunctionKind::Dynamic => {
@@ -242,14 +277,14 @@ macro_rules! impl_native_traits {
         }
 
         #[allow(unused_parens)]
-        impl<'a, $( $x, )* Rets> crate::exports::ExportableWithGenerics<'a, ($( $x ),*), Rets> for NativeFunc<( $( $x ),* ), Rets>
+        impl<'a, $( $x, )* Rets> crate::sys::exports::ExportableWithGenerics<'a, ($( $x ),*), Rets> for NativeFunc<( $( $x ),* ), Rets>
         where
             $( $x: FromToNativeWasmType, )*
             Rets: WasmTypeList,
         {
-            fn get_self_from_extern_with_generics(_extern: &crate::externals::Extern) -> Result<Self, crate::exports::ExportError> {
-                use crate::exports::Exportable;
-                crate::Function::get_self_from_extern(_extern)?.native().map_err(|_| crate::exports::ExportError::IncompatibleType)
+            fn get_self_from_extern_with_generics(_extern: &crate::sys::externals::Extern) -> Result<Self, crate::sys::exports::ExportError> {
+                use crate::sys::exports::Exportable;
+                crate::Function::get_self_from_extern(_extern)?.native().map_err(|_| crate::sys::exports::ExportError::IncompatibleType)
             }
         }
     };
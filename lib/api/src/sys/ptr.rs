@@ -6,7 +6,8 @@
 //! Therefore, you should use this abstraction whenever possible to avoid memory
 //! related bugs when implementing an ABI.
 
-use crate::{externals::Memory, FromToNativeWasmType};
+use crate::sys::externals::Memory;
+use crate::FromToNativeWasmType;
 use std::{cell::Cell, fmt, marker::PhantomData, mem};
 use wasmer_types::ValueType;
 
@@ -0,0 +1,331 @@
+//! Tables of host objects handed out to the guests as integer handles.
+
+use crate::{HostEnvInitError, Instance, WasmerEnv};
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Arc, Mutex, Weak};
+use thiserror::Error;
+
+/// The maximum number of resources in a table.
+const MAX_RESOURCES: u32 = 1 << 24;
+
+/// An error while using the handle of a resource, or creating one.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ResourceError {
+    /// The handle doesn't designate a resource of the table: it's
+    /// forged, it comes from another table or the resource was
+    /// removed.
+    #[error("invalid resource handle {0}")]
+    InvalidHandle(u32),
+
+    /// The instance already holds as many resources as allowed.
+    #[error("the quota of {0} resources per instance is exceeded")]
+    QuotaExceeded(usize),
+
+    /// The table holds as many resources as it can.
+    #[error("the resource table is full")]
+    TableFull,
+
+    /// The instance the environment was bound to was dropped.
+    #[error("the instance owning the resources was dropped")]
+    InstanceDropped,
+}
+
+/// A table of host objects of type `T` handed out to the guests as
+/// `u32` handles, to be passed back to the host functions.
+///
+/// The table is shared by its clones. Used as the environment of the
+/// host functions, or a part of it, each clone is bound to the
+/// instance importing the functions when it's initialized with
+/// [`WasmerEnv::init_with_instance`]:
+///
+/// * The resources are owned by the instance inserting them, and
+///   can't be used by another instance.
+/// * An instance can't hold more resources than the quota of the
+///   table, if any.
+/// * The resources of an instance are dropped with the last clone of
+///   the instance.
+///
+/// A clone that isn't bound to an instance, like the one kept by the
+/// host, can use all the resources.
+///
+/// The handles are scrambled with a key specific to each table, and
+/// a handle of a removed resource isn't valid anymore, even if its
+/// slot is reused, so a handle meant for another table or a stale one
+/// is rejected with [`ResourceError::InvalidHandle`] rather than
+/// designating an unrelated resource (until a slot has been reused 256
+/// times).
+///
+/// # Example
+///
+/// ```
+/// # use wasmer::{imports, Function, Instance, Module, Resources, ResourceError, Store};
+/// # fn main() -> anyhow::Result<()> {
+/// # let store = Store::default();
+/// struct Counter(std::sync::atomic::AtomicU32);
+///
+/// let counters = Resources::<Counter>::with_quota(16);
+/// let module = Module::new(&store, r#"
+///     (module
+///       (import "host" "new_counter" (func $new (result i32)))
+///       (import "host" "increment" (func $increment (param i32) (result i32)))
+///       (func (export "run") (result i32)
+///         (drop (call $increment (call $new)))
+///         (call $increment (call $new))))
+/// "#)?;
+/// let import_object = imports! {
+///     "host" => {
+///         "new_counter" => Function::new_native_with_env(&store, counters.clone(), |counters: &Resources<Counter>| {
+///             counters.insert(Counter(Default::default()))
+///         }),
+///         "increment" => Function::new_native_with_env(&store, counters.clone(), |counters: &Resources<Counter>, handle: u32| {
+///             let counter = counters.get(handle)?;
+///             Ok::<_, ResourceError>(counter.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1)
+///         }),
+///     },
+/// };
+/// let instance = Instance::new(&module, &import_object)?;
+/// let run = instance.exports.get_native_function::<(), u32>("run")?;
+/// assert_eq!(run.call()?, 1);
+/// assert_eq!(counters.len(), 2);
+///
+/// drop(run);
+/// drop(instance);
+/// assert_eq!(counters.len(), 0);
+/// # Ok(())
+/// # }
+/// ```
+pub struct Resources<T> {
+    table: Arc<Mutex<Table<T>>>,
+    /// The owner of the resources inserted with this clone, once it's
+    /// bound to an instance.
+    owner: Option<u64>,
+}
+
+struct Table<T> {
+    key: u32,
+    quota: Option<usize>,
+    slots: Vec<Slot<T>>,
+    free: Vec<u32>,
+    /// The number of resources of each owner alive.
+    owners: HashMap<u64, usize>,
+    /// The owners, by the id of their instance.
+    instances: HashMap<usize, u64>,
+    next_owner: u64,
+}
+
+struct Slot<T> {
+    generation: u8,
+    resource: Option<(Arc<T>, Option<u64>)>,
+}
+
+impl<T: Send + Sync + 'static> Resources<T> {
+    /// Creates an empty table, without quota.
+    pub fn new() -> Self {
+        Self::with_optional_quota(None)
+    }
+
+    /// Creates an empty table, where each instance can hold at most
+    /// `quota` resources.
+    pub fn with_quota(quota: usize) -> Self {
+        Self::with_optional_quota(Some(quota))
+    }
+
+    fn with_optional_quota(quota: Option<usize>) -> Self {
+        let key = RandomState::new().build_hasher().finish() as u32;
+        Self {
+            table: Arc::new(Mutex::new(Table {
+                key,
+                quota,
+                slots: Vec::new(),
+                free: Vec::new(),
+                owners: HashMap::new(),
+                instances: HashMap::new(),
+                next_owner: 0,
+            })),
+            owner: None,
+        }
+    }
+
+    /// Inserts `resource` in the table, owned by the instance this
+    /// clone is bound to, and returns its handle.
+    pub fn insert(&self, resource: T) -> Result<u32, ResourceError> {
+        let mut table = self.table.lock().unwrap();
+        if let Some(owner) = self.owner {
+            let quota = table.quota;
+            let count = table
+                .owners
+                .get_mut(&owner)
+                .ok_or(ResourceError::InstanceDropped)?;
+            if quota.map_or(false, |quota| *count >= quota) {
+                return Err(ResourceError::QuotaExceeded(quota.unwrap()));
+            }
+            *count += 1;
+        }
+        let index = match table.free.pop() {
+            Some(index) => index,
+            None if table.slots.len() < MAX_RESOURCES as usize => {
+                table.slots.push(Slot {
+                    generation: 0,
+                    resource: None,
+                });
+                table.slots.len() as u32 - 1
+            }
+            None => {
+                if let Some(owner) = self.owner {
+                    *table.owners.get_mut(&owner).unwrap() -= 1;
+                }
+                return Err(ResourceError::TableFull);
+            }
+        };
+        let slot = &mut table.slots[index as usize];
+        slot.resource = Some((Arc::new(resource), self.owner));
+        let generation = slot.generation;
+        Ok(table.handle(index, generation))
+    }
+
+    /// Returns the resource designated by `handle`.
+    pub fn get(&self, handle: u32) -> Result<Arc<T>, ResourceError> {
+        let table = self.table.lock().unwrap();
+        let index = self.index(&table, handle)?;
+        let (resource, _) = table.slots[index].resource.as_ref().unwrap();
+        Ok(resource.clone())
+    }
+
+    /// Removes the resource designated by `handle` from the table,
+    /// and returns it. Its handle isn't valid anymore.
+    pub fn remove(&self, handle: u32) -> Result<Arc<T>, ResourceError> {
+        let mut table = self.table.lock().unwrap();
+        let index = self.index(&table, handle)?;
+        Ok(table.remove(index))
+    }
+
+    /// Returns the number of resources this clone can use: the ones
+    /// of its instance if it's bound to one, or else all of them.
+    pub fn len(&self) -> usize {
+        let table = self.table.lock().unwrap();
+        match self.owner {
+            Some(owner) => table.owners.get(&owner).copied().unwrap_or(0),
+            None => table.slots.len() - table.free.len(),
+        }
+    }
+
+    /// Returns whether this clone can't use any resource.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Validates `handle`, returning the index of its slot.
+    fn index(&self, table: &Table<T>, handle: u32) -> Result<usize, ResourceError> {
+        let unscrambled = handle ^ table.key;
+        let index = (unscrambled % MAX_RESOURCES) as usize;
+        let generation = (unscrambled / MAX_RESOURCES) as u8;
+        match table.slots.get(index) {
+            Some(Slot {
+                generation: slot_generation,
+                resource: Some((_, owner)),
+            }) if *slot_generation == generation
+                && (self.owner.is_none() || *owner == self.owner) =>
+            {
+                Ok(index)
+            }
+            _ => Err(ResourceError::InvalidHandle(handle)),
+        }
+    }
+}
+
+impl<T> Table<T> {
+    fn handle(&self, index: u32, generation: u8) -> u32 {
+        (u32::from(generation) * MAX_RESOURCES + index) ^ self.key
+    }
+
+    fn remove(&mut self, index: usize) -> Arc<T> {
+        let slot = &mut self.slots[index];
+        let (resource, owner) = slot.resource.take().unwrap();
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(index as u32);
+        if let Some(count) = owner.and_then(|owner| self.owners.get_mut(&owner)) {
+            *count -= 1;
+        }
+        resource
+    }
+
+    /// Removes the resources of `owner`, returning them to be dropped
+    /// once the table is unlocked.
+    fn remove_owner(&mut self, owner: u64) -> Vec<Arc<T>> {
+        self.owners.remove(&owner);
+        let indices = self
+            .slots
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| matches!(slot.resource, Some((_, Some(o))) if o == owner))
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        indices
+            .into_iter()
+            .map(|index| self.remove(index))
+            .collect()
+    }
+}
+
+impl<T: Send + Sync + 'static> WasmerEnv for Resources<T> {
+    fn init_with_instance(&mut self, instance: &Instance) -> Result<(), HostEnvInitError> {
+        let mut table = self.table.lock().unwrap();
+        let owner = match table.instances.get(&instance.id()) {
+            Some(owner) => *owner,
+            None => {
+                let owner = table.next_owner;
+                table.next_owner += 1;
+                table.owners.insert(owner, 0);
+                table.instances.insert(instance.id(), owner);
+                let weak_table = Arc::downgrade(&self.table);
+                let instance_id = instance.id();
+                instance.on_teardown(move || drop_owner(&weak_table, instance_id));
+                owner
+            }
+        };
+        self.owner = Some(owner);
+        Ok(())
+    }
+}
+
+/// Drops the resources of the instance `instance_id`, as it's dropped.
+fn drop_owner<T>(table: &Weak<Mutex<Table<T>>>, instance_id: usize) {
+    if let Some(table) = table.upgrade() {
+        let resources = {
+            let mut table = table.lock().unwrap();
+            match table.instances.remove(&instance_id) {
+                Some(owner) => table.remove_owner(owner),
+                None => return,
+            }
+        };
+        // The resources are dropped once the table is unlocked, so
+        // their destructors can use it.
+        drop(resources);
+    }
+}
+
+impl<T: Send + Sync + 'static> Default for Resources<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for Resources<T> {
+    fn clone(&self) -> Self {
+        Self {
+            table: self.table.clone(),
+            owner: self.owner,
+        }
+    }
+}
+
+impl<T> fmt::Debug for Resources<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Resources")
+            .field("owner", &self.owner)
+            .finish()
+    }
+}
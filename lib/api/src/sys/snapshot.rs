@@ -0,0 +1,347 @@
+//! Snapshots of the state of the instances.
+//!
+//! An [`InstanceSnapshot`] holds the state an instance owns (its
+//! linear memories, mutable globals and tables), so it can be restored
+//! into another instance of the same module, possibly in another
+//! process: a warmed-up instance can be forked, or migrated.
+
+use crate::sys::externals::Extern;
+use crate::sys::instance::Instance;
+use crate::{ExternRef, Mutability, Pages, Val, WASM_PAGE_SIZE};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use thiserror::Error;
+use wasmer_types::entity::EntityRef;
+use wasmer_types::{
+    ExportIndex, FunctionIndex, LocalGlobalIndex, LocalMemoryIndex, LocalTableIndex,
+};
+use wasmer_vm::MemoryError;
+
+/// An error while taking or restoring a snapshot.
+#[derive(Error, Debug)]
+pub enum SnapshotError {
+    /// A value can't be part of a snapshot, like the `externref`s or
+    /// the functions coming from another instance.
+    #[error("{0} holds a reference which can't be snapshotted")]
+    UnsupportedReference(String),
+
+    /// The instance is executing, so its state may be changing.
+    #[error("the instance is executing")]
+    Executing,
+
+    /// The snapshot doesn't come from an instance of the same module.
+    #[error("the snapshot doesn't match the instance: {0}")]
+    Incompatible(String),
+
+    /// A memory failed to grow to the size of its snapshot.
+    #[error("failed to restore memory {index}: {error}")]
+    Memory {
+        /// The index of the local memory.
+        index: u32,
+        /// The error.
+        error: MemoryError,
+    },
+
+    /// The snapshot failed to be serialized or deserialized.
+    #[error("failed to (de)serialize the snapshot: {0}")]
+    Serialization(String),
+}
+
+/// A value of a global or an element of a table.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+enum SnapshotValue {
+    I32(i32),
+    I64(i64),
+    F32(u32),
+    F64(u64),
+    V128(u128),
+    Null,
+    /// A function of the instance, by index.
+    Function(u32),
+}
+
+/// A snapshot of the state of an instance, taken with
+/// [`Instance::snapshot`] and restored with [`Instance::restore`].
+///
+/// The memories, globals and tables imported by the instance aren't
+/// part of it, as the instance doesn't own them.
+///
+/// # Example
+///
+/// ```
+/// # use wasmer::{imports, Instance, InstanceSnapshot, Module, Store, Value};
+/// # fn main() -> anyhow::Result<()> {
+/// # let store = Store::default();
+/// let module = Module::new(&store, "(module (global (export \"g\") (mut i32) (i32.const 0)))")?;
+/// let instance = Instance::new(&module, &imports! {})?;
+/// instance.exports.get_global("g")?.set(Value::I32(42))?;
+///
+/// let bytes = instance.snapshot()?.serialize()?;
+/// let fork = Instance::new(&module, &imports! {})?;
+/// fork.restore(&InstanceSnapshot::deserialize(&bytes)?)?;
+/// assert_eq!(fork.exports.get_global("g")?.get(), Value::I32(42));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct InstanceSnapshot {
+    /// The contents of the local memories.
+    memories: Vec<serde_bytes::ByteBuf>,
+    /// The values of the mutable local globals, by local index.
+    globals: Vec<(u32, SnapshotValue)>,
+    /// The elements of the local tables.
+    tables: Vec<Vec<SnapshotValue>>,
+}
+
+impl InstanceSnapshot {
+    /// Serializes the snapshot into bytes.
+    pub fn serialize(&self) -> Result<Vec<u8>, SnapshotError> {
+        bincode::serialize(self).map_err(|error| SnapshotError::Serialization(error.to_string()))
+    }
+
+    /// Deserializes a snapshot from bytes made by
+    /// [`InstanceSnapshot::serialize`].
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        bincode::deserialize(bytes).map_err(|error| SnapshotError::Serialization(error.to_string()))
+    }
+
+    pub(crate) fn take(instance: &Instance) -> Result<Self, SnapshotError> {
+        if instance.is_executing() {
+            return Err(SnapshotError::Executing);
+        }
+        let module_info = instance.module().info();
+        let functions = FunctionIndices::new(instance);
+
+        let memories = (0..module_info.memories.len() - module_info.num_imported_memories)
+            .map(|index| {
+                let index = module_info.memory_index(LocalMemoryIndex::new(index));
+                match instance.lookup_by_declaration(ExportIndex::Memory(index)) {
+                    // Safety: the instance isn't executing, and the data is
+                    // copied right away.
+                    Extern::Memory(memory) => unsafe {
+                        serde_bytes::ByteBuf::from(memory.data_unchecked().to_vec())
+                    },
+                    _ => unreachable!("memory index"),
+                }
+            })
+            .collect();
+
+        let mut globals = vec![];
+        for index in 0..module_info.globals.len() - module_info.num_imported_globals {
+            let global_index = module_info.global_index(LocalGlobalIndex::new(index));
+            let global = match instance.lookup_by_declaration(ExportIndex::Global(global_index)) {
+                Extern::Global(global) => global,
+                _ => unreachable!("global index"),
+            };
+            if global.ty().mutability == Mutability::Var {
+                let what = || format!("global {}", global_index.index());
+                globals.push((index as u32, functions.to_snapshot(global.get(), what)?));
+            }
+        }
+
+        let mut tables = vec![];
+        for index in 0..module_info.tables.len() - module_info.num_imported_tables {
+            let table_index = module_info.table_index(LocalTableIndex::new(index));
+            let table = match instance.lookup_by_declaration(ExportIndex::Table(table_index)) {
+                Extern::Table(table) => table,
+                _ => unreachable!("table index"),
+            };
+            let elements = (0..table.size())
+                .map(|element| {
+                    let value = table.get(element).expect("element in bounds");
+                    let what = || format!("element {} of table {}", element, table_index.index());
+                    functions.to_snapshot(value, what)
+                })
+                .collect::<Result<_, _>>()?;
+            tables.push(elements);
+        }
+
+        Ok(Self {
+            memories,
+            globals,
+            tables,
+        })
+    }
+
+    pub(crate) fn restore(&self, instance: &Instance) -> Result<(), SnapshotError> {
+        if instance.is_executing() {
+            return Err(SnapshotError::Executing);
+        }
+        let module_info = instance.module().info();
+        let num_memories = module_info.memories.len() - module_info.num_imported_memories;
+        let num_tables = module_info.tables.len() - module_info.num_imported_tables;
+        if self.memories.len() != num_memories || self.tables.len() != num_tables {
+            return Err(SnapshotError::Incompatible(format!(
+                "the instance has {} memories and {} tables, the snapshot {} and {}",
+                num_memories,
+                num_tables,
+                self.memories.len(),
+                self.tables.len()
+            )));
+        }
+
+        // Check everything before changing the instance.
+        let mut memories = vec![];
+        for (index, data) in self.memories.iter().enumerate() {
+            let memory_index = module_info.memory_index(LocalMemoryIndex::new(index));
+            let memory = match instance.lookup_by_declaration(ExportIndex::Memory(memory_index)) {
+                Extern::Memory(memory) => memory,
+                _ => unreachable!("memory index"),
+            };
+            let pages = Pages((data.len() / WASM_PAGE_SIZE) as u32);
+            if memory.size() > pages {
+                return Err(SnapshotError::Incompatible(format!(
+                    "memory {} is bigger than its snapshot",
+                    index
+                )));
+            }
+            memories.push((memory, pages));
+        }
+        let num_globals = module_info.globals.len() - module_info.num_imported_globals;
+        let mut globals = vec![];
+        for (index, value) in &self.globals {
+            let global = if (*index as usize) < num_globals {
+                let index = module_info.global_index(LocalGlobalIndex::from_u32(*index));
+                match instance.lookup_by_declaration(ExportIndex::Global(index)) {
+                    Extern::Global(global) => Some(global),
+                    _ => unreachable!("global index"),
+                }
+            } else {
+                None
+            };
+            let global = global
+                .filter(|global| global.ty().mutability == Mutability::Var)
+                .ok_or_else(|| {
+                    SnapshotError::Incompatible(format!("no mutable local global {}", index))
+                })?;
+            globals.push((global, from_snapshot(instance, value)?));
+        }
+        let mut tables = vec![];
+        for (index, elements) in self.tables.iter().enumerate() {
+            let table_index = module_info.table_index(LocalTableIndex::new(index));
+            let table = match instance.lookup_by_declaration(ExportIndex::Table(table_index)) {
+                Extern::Table(table) => table,
+                _ => unreachable!("table index"),
+            };
+            if table.size() as usize > elements.len() {
+                return Err(SnapshotError::Incompatible(format!(
+                    "table {} is bigger than its snapshot",
+                    index
+                )));
+            }
+            let elements = elements
+                .iter()
+                .map(|value| from_snapshot(instance, value))
+                .collect::<Result<Vec<_>, _>>()?;
+            tables.push((table, elements));
+        }
+
+        for (index, ((memory, pages), data)) in memories.into_iter().zip(&self.memories).enumerate()
+        {
+            if memory.size() < pages {
+                memory
+                    .grow(pages - memory.size())
+                    .map_err(|error| SnapshotError::Memory {
+                        index: index as u32,
+                        error,
+                    })?;
+            }
+            // Safety: the instance isn't executing.
+            let memory_data = unsafe { memory.data_unchecked_mut() };
+            memory_data[..data.len()].copy_from_slice(data);
+        }
+        for (global, value) in globals {
+            global
+                .set(value)
+                .map_err(|error| SnapshotError::Incompatible(error.message()))?;
+        }
+        for (table, elements) in tables {
+            let size = table.size();
+            if (size as usize) < elements.len() {
+                table
+                    .grow(elements.len() as u32 - size, Val::null())
+                    .map_err(|error| SnapshotError::Incompatible(error.message()))?;
+            }
+            for (index, element) in elements.into_iter().enumerate() {
+                table
+                    .set(index as u32, element)
+                    .map_err(|error| SnapshotError::Incompatible(error.message()))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The indices of the functions of an instance, by the address of
+/// their code and their environment.
+struct FunctionIndices(HashMap<(usize, usize), u32>);
+
+impl FunctionIndices {
+    fn new(instance: &Instance) -> Self {
+        let module_info = instance.module().info();
+        Self(
+            (0..module_info.functions.len())
+                .filter_map(|index| {
+                    match instance
+                        .lookup_by_declaration(ExportIndex::Function(FunctionIndex::new(index)))
+                    {
+                        Extern::Function(function) => Some((function_key(&function), index as u32)),
+                        _ => None,
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    fn to_snapshot(
+        &self,
+        value: Val,
+        what: impl FnOnce() -> String,
+    ) -> Result<SnapshotValue, SnapshotError> {
+        Ok(match value {
+            Val::I32(value) => SnapshotValue::I32(value),
+            Val::I64(value) => SnapshotValue::I64(value),
+            Val::F32(value) => SnapshotValue::F32(value.to_bits()),
+            Val::F64(value) => SnapshotValue::F64(value.to_bits()),
+            Val::V128(value) => SnapshotValue::V128(value),
+            Val::ExternRef(ExternRef::Null) => SnapshotValue::Null,
+            Val::FuncRef(function) => match self.0.get(&function_key(&function)) {
+                Some(index) => SnapshotValue::Function(*index),
+                None => return Err(SnapshotError::UnsupportedReference(what())),
+            },
+            Val::ExternRef(_) => return Err(SnapshotError::UnsupportedReference(what())),
+        })
+    }
+}
+
+fn function_key(function: &crate::Function) -> (usize, usize) {
+    let vm_function = &function.exported.vm_function;
+    // Safety: both variants of the union are pointers.
+    let vmctx = unsafe { vm_function.vmctx.host_env };
+    (vm_function.address as usize, vmctx as usize)
+}
+
+fn from_snapshot(instance: &Instance, value: &SnapshotValue) -> Result<Val, SnapshotError> {
+    Ok(match *value {
+        SnapshotValue::I32(value) => Val::I32(value),
+        SnapshotValue::I64(value) => Val::I64(value),
+        SnapshotValue::F32(value) => Val::F32(f32::from_bits(value)),
+        SnapshotValue::F64(value) => Val::F64(f64::from_bits(value)),
+        SnapshotValue::V128(value) => Val::V128(value),
+        SnapshotValue::Null => Val::null(),
+        SnapshotValue::Function(index) => {
+            if index as usize >= instance.module().info().functions.len() {
+                return Err(SnapshotError::Incompatible(format!(
+                    "no function {}",
+                    index
+                )));
+            }
+            match instance
+                .lookup_by_declaration(ExportIndex::Function(FunctionIndex::from_u32(index)))
+            {
+                Extern::Function(function) => Val::FuncRef(function),
+                _ => unreachable!("function index"),
+            }
+        }
+    })
+}
@@ -0,0 +1,481 @@
+use crate::sys::call_hook::{CallHook, CallHookSlot};
+use crate::sys::event_log::{EventLogSlot, StoreEvent};
+use crate::sys::inventory::{InstanceEntry, Inventory, ModuleEntry};
+use crate::sys::labels::{LabelSet, Labels};
+use crate::sys::limits::{LimitsSlot, StoreLimits};
+use crate::sys::memory_growth::GrowthSubscribers;
+use crate::sys::tunables::{BaseTunables, StoreTunables};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+#[cfg(all(feature = "compiler", feature = "engine"))]
+use wasmer_compiler::CompilerConfig;
+use wasmer_engine::{Engine, RuntimeError, Tunables, TunablesConfig};
+use wasmer_vm::{ExecutionGuard, InstanceRef, StackLimit};
+
+/// The store represents all global state that can be manipulated by
+/// WebAssembly programs. It consists of the runtime representation
+/// of all instances of functions, tables, memories, and globals that
+/// have been allocated during the lifetime of the abstract machine.
+///
+/// The `Store` holds the engine (that is —amongst many things— used to compile
+/// the Wasm bytes into a valid module artifact), in addition to the
+/// [`Tunables`] (that are used to create the memories, tables and globals).
+///
+/// Spec: <https://webassembly.github.io/spec/core/exec/runtime.html#store>
+#[derive(Clone)]
+pub struct Store {
+    engine: Arc<dyn Engine + Send + Sync>,
+    tunables: Arc<dyn Tunables + Send + Sync>,
+    call_hook: CallHookSlot,
+    event_log: EventLogSlot,
+    labels: LabelSet,
+    growth_subscribers: GrowthSubscribers,
+    inventory: Inventory,
+    stack_limit: Arc<StackLimit>,
+    limits: LimitsSlot,
+    deterministic: Arc<AtomicBool>,
+}
+
+impl Store {
+    /// Creates a new `Store` with a specific [`Engine`], and the
+    /// [`BaseTunables`] configured by the [`TunablesConfig`] of its
+    /// builder.
+    pub fn new<E>(engine: &E) -> Self
+    where
+        E: Engine + ?Sized,
+    {
+        Self::new_with_tunables_config(engine, &engine.tunables_config())
+    }
+
+    /// Creates a new `Store` with a specific [`Engine`] and [`Tunables`].
+    pub fn new_with_tunables<E>(engine: &E, tunables: impl Tunables + Send + Sync + 'static) -> Self
+    where
+        E: Engine + ?Sized,
+    {
+        Self::from_parts(engine.cloned(), Arc::new(tunables))
+    }
+
+    /// Creates a new `Store` with a specific [`Engine`], and the
+    /// [`BaseTunables`] for its target configured by `config`, in
+    /// place of the configuration of the engine.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Pages, Store, TunablesConfig};
+    /// # let engine = Store::default().engine().clone();
+    /// let store = Store::new_with_tunables_config(
+    ///     &*engine,
+    ///     TunablesConfig::new()
+    ///         .static_memory_bound(Pages(0x100))
+    ///         .static_memory_offset_guard_size(0x1_0000),
+    /// );
+    /// ```
+    pub fn new_with_tunables_config<E>(engine: &E, config: &TunablesConfig) -> Self
+    where
+        E: Engine + ?Sized,
+    {
+        Self::new_with_tunables(
+            engine,
+            BaseTunables::for_target_with_config(engine.target(), config),
+        )
+    }
+
+    fn from_parts(
+        engine: Arc<dyn Engine + Send + Sync>,
+        tunables: Arc<dyn Tunables + Send + Sync>,
+    ) -> Self {
+        let labels = LabelSet::default();
+        let event_log = EventLogSlot::new(labels.clone());
+        let growth_subscribers = GrowthSubscribers::default();
+        let stack_limit = Arc::new(StackLimit::new());
+        let limits = LimitsSlot::default();
+        let deterministic = Arc::new(AtomicBool::new(engine.deterministic()));
+        Self {
+            engine,
+            tunables: Arc::new(StoreTunables::new(
+                tunables,
+                event_log.clone(),
+                growth_subscribers.clone(),
+                stack_limit.clone(),
+                limits.clone(),
+            )),
+            call_hook: Default::default(),
+            event_log,
+            labels,
+            growth_subscribers,
+            inventory: Default::default(),
+            stack_limit,
+            limits,
+            deterministic,
+        }
+    }
+
+    /// Returns the [`Tunables`].
+    pub fn tunables(&self) -> &dyn Tunables {
+        self.tunables.as_ref()
+    }
+
+    /// Returns the [`Engine`].
+    pub fn engine(&self) -> &Arc<dyn Engine + Send + Sync> {
+        &self.engine
+    }
+
+    /// Sets the hook reported the calls of the exported WebAssembly
+    /// functions made from the host, and the calls of the host
+    /// functions made from WebAssembly, replacing the previous one.
+    ///
+    /// The hook is shared by the clones of this store, and applies to
+    /// the functions already created.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{CallHook, CallHookKind, Store};
+    /// # let store = Store::default();
+    /// store.set_call_hook(CallHook::new(|event| {
+    ///     if event.kind() == CallHookKind::CallingHost {
+    ///         println!("calling {}", event.name().unwrap_or("<unknown>"));
+    ///     }
+    /// }));
+    /// ```
+    pub fn set_call_hook(&self, hook: CallHook) {
+        self.call_hook.set(Some(hook));
+    }
+
+    /// Removes the hook set with [`Store::set_call_hook`].
+    pub fn clear_call_hook(&self) {
+        self.call_hook.set(None);
+    }
+
+    pub(crate) fn call_hook(&self) -> Option<CallHook> {
+        self.call_hook.get()
+    }
+
+    /// Keeps the last `capacity` events of this store (instantiations,
+    /// traps, memory grows and host call failures) in memory, to be
+    /// dumped with [`Store::debug_dump`]. The events logged before are
+    /// discarded.
+    ///
+    /// The grows of the memories created while the log is disabled
+    /// aren't logged, so that they don't pay for it.
+    ///
+    /// The log is shared by the clones of this store.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::Store;
+    /// # let store = Store::default();
+    /// store.enable_event_log(64);
+    /// // ... if a call traps:
+    /// eprintln!("{}", store.debug_dump());
+    /// ```
+    pub fn enable_event_log(&self, capacity: usize) {
+        self.event_log.enable(capacity);
+    }
+
+    /// Stops logging the events of this store, and discards them.
+    pub fn disable_event_log(&self) {
+        self.event_log.disable();
+    }
+
+    /// Returns the events logged since [`Store::enable_event_log`],
+    /// from the oldest to the most recent.
+    pub fn recent_events(&self) -> Vec<StoreEvent> {
+        self.event_log.events()
+    }
+
+    /// Formats the events logged since [`Store::enable_event_log`],
+    /// with the time they happened at, to be included in a bug report
+    /// after a trap or a panic.
+    pub fn debug_dump(&self) -> String {
+        self.event_log.dump()
+    }
+
+    /// Sets the handler passed [`Store::debug_dump`] when a call from
+    /// the host into an instance of this store traps, or when a host
+    /// function called by one of its instances panics, replacing the
+    /// previous one. It's only called while the event log is enabled.
+    ///
+    /// The handler is shared by the clones of this store.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::Store;
+    /// # let store = Store::default();
+    /// store.enable_event_log(64);
+    /// store.set_debug_dump_handler(|dump| eprintln!("{}", dump));
+    /// ```
+    pub fn set_debug_dump_handler<F>(&self, handler: F)
+    where
+        F: Fn(&str) + Send + Sync + 'static,
+    {
+        self.event_log.set_dump_handler(Some(Arc::new(handler)));
+    }
+
+    /// Removes the handler set with [`Store::set_debug_dump_handler`].
+    pub fn clear_debug_dump_handler(&self) {
+        self.event_log.set_dump_handler(None);
+    }
+
+    pub(crate) fn event_log(&self) -> &EventLogSlot {
+        &self.event_log
+    }
+
+    /// Sets the label `key` of this store to `value`, to identify it
+    /// in the errors of the calls, the events of the call hook and
+    /// [`Store::debug_dump`]. The labels of the store are the ones of
+    /// its instances, unless an instance sets them itself with
+    /// [`Instance::set_label`].
+    ///
+    /// The labels are shared by the clones of this store.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::Store;
+    /// # let store = Store::default();
+    /// store.set_label("tenant", "acme");
+    /// assert_eq!(store.labels()["tenant"], "acme");
+    /// ```
+    ///
+    /// [`Instance::set_label`]: crate::Instance::set_label
+    pub fn set_label(&self, key: impl Into<String>, value: impl Into<String>) {
+        self.labels.set(key.into(), value.into());
+    }
+
+    /// Removes the label `key` of this store, returning its value.
+    pub fn remove_label(&self, key: &str) -> Option<String> {
+        self.labels.remove(key)
+    }
+
+    /// Returns the labels of this store.
+    pub fn labels(&self) -> Labels {
+        self.labels.get()
+    }
+
+    pub(crate) fn label_set(&self) -> &LabelSet {
+        &self.labels
+    }
+
+    pub(crate) fn growth_subscribers(&self) -> &GrowthSubscribers {
+        &self.growth_subscribers
+    }
+
+    /// Limits the native stack used by each call from the host into
+    /// the instances of this store to `size` bytes, including the
+    /// stack of the host functions they call. A call exceeding it
+    /// traps with a "call stack exhausted" [`RuntimeError`], like the
+    /// other traps. `None` removes the limit.
+    ///
+    /// The limit is checked by the code compiled by Cranelift and
+    /// Singlepass, but not by LLVM. It can't exceed the stack of the
+    /// calling thread: run the calls on a thread created with a larger
+    /// stack (see [`std::thread::Builder::stack_size`]) to allow deeper
+    /// recursions. Whatever the limit, overflowing the stack of the
+    /// thread in WebAssembly code traps as well, rather than crashing.
+    ///
+    /// The limit is shared by the clones of this store, and applies to
+    /// the instances created afterwards. It's only enforced while a
+    /// single thread calls into the instances of the store at a time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::Store;
+    /// # let store = Store::default();
+    /// store.set_stack_size(Some(256 * 1024));
+    /// assert_eq!(store.stack_size(), Some(256 * 1024));
+    /// ```
+    ///
+    /// [`RuntimeError`]: crate::RuntimeError
+    pub fn set_stack_size(&self, size: Option<usize>) {
+        self.stack_limit.set_size(size);
+    }
+
+    /// Returns the limit set with [`Store::set_stack_size`].
+    pub fn stack_size(&self) -> Option<usize> {
+        self.stack_limit.size()
+    }
+
+    pub(crate) fn stack_limit(&self) -> &StackLimit {
+        &self.stack_limit
+    }
+
+    /// Limits the resources created in this store from now on, replacing
+    /// the previous limits: the instances, memories and tables alive in
+    /// the store, and the initial size of the memories and tables.
+    ///
+    /// Creating a resource exceeding the limits fails: a memory or a
+    /// table created by the host returns an error, and a module fails
+    /// to instantiate with an [`InstantiationError::Link`] describing
+    /// the limit. The resources already created are kept.
+    ///
+    /// The limits are shared by the clones of this store. See
+    /// [`StoreLimits`] for an example.
+    ///
+    /// [`InstantiationError::Link`]: crate::InstantiationError::Link
+    pub fn set_limits(&self, limits: &StoreLimits) {
+        self.limits.set(limits.clone());
+    }
+
+    /// Returns the limits set with [`Store::set_limits`].
+    pub fn limits(&self) -> StoreLimits {
+        self.limits.get()
+    }
+
+    pub(crate) fn limits_slot(&self) -> &LimitsSlot {
+        &self.limits
+    }
+
+    /// Requires the modules compiled in this store from now on to
+    /// execute deterministically, or stops requiring it. It's required
+    /// by default when the engine compiles deterministic code, see
+    /// `CompilerConfig::enable_deterministic`.
+    ///
+    /// While it's required, compiling a module fails with a
+    /// [`CompileError::Validate`] if the engine doesn't compile
+    /// deterministic code, so a host relying on a deterministic
+    /// execution can't be given the wrong engine. The modules
+    /// deserialized from an artifact aren't checked. A call entering an
+    /// instance another thread is executing fails with a
+    /// [`RuntimeError`] too, as the depth of the calls is counted per
+    /// instance.
+    ///
+    /// The flag is shared by the clones of this store.
+    ///
+    /// [`CompileError::Validate`]: crate::CompileError::Validate
+    pub fn set_deterministic(&self, deterministic: bool) {
+        self.deterministic.store(deterministic, Ordering::SeqCst);
+    }
+
+    /// Whether the modules compiled in this store must execute
+    /// deterministically, see [`Store::set_deterministic`].
+    pub fn is_deterministic(&self) -> bool {
+        self.deterministic.load(Ordering::SeqCst)
+    }
+
+    /// Marks a call into the code of `instance` as in progress. While
+    /// the store must execute deterministically, a call can't enter
+    /// an instance another thread is executing, see
+    /// [`InstanceRef::enter_exclusive`].
+    pub(crate) fn enter_instance<'a>(
+        &self,
+        instance: &'a InstanceRef,
+    ) -> Result<ExecutionGuard<'a>, RuntimeError> {
+        if !self.is_deterministic() {
+            return Ok(instance.enter());
+        }
+        instance.enter_exclusive().ok_or_else(|| {
+            RuntimeError::new(
+                "the instance is executing on another thread, which a deterministic store \
+                 doesn't allow",
+            )
+        })
+    }
+
+    /// Returns the modules created in this store that are alive, from
+    /// the oldest to the most recent.
+    ///
+    /// The modules are shared by the clones of this store.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Module, Store};
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let module = Module::new(&store, "(module $answer)")?;
+    /// let names = store.modules().map(|module| module.name).collect::<Vec<_>>();
+    /// assert_eq!(names, [Some("answer".to_string())]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn modules(&self) -> impl Iterator<Item = ModuleEntry> {
+        self.inventory.modules().into_iter()
+    }
+
+    /// Returns the instances created in this store that are alive,
+    /// from the oldest to the most recent.
+    ///
+    /// The instances are shared by the clones of this store.
+    pub fn instances(&self) -> impl Iterator<Item = InstanceEntry> {
+        self.inventory.instances().into_iter()
+    }
+
+    pub(crate) fn inventory(&self) -> &Inventory {
+        &self.inventory
+    }
+
+    /// Checks whether two stores are identical. A store is considered
+    /// equal to another store if both have the same engine. The
+    /// tunables are excluded from the logic.
+    pub fn same(a: &Self, b: &Self) -> bool {
+        a.engine.id() == b.engine.id()
+    }
+}
+
+impl PartialEq for Store {
+    fn eq(&self, other: &Self) -> bool {
+        Self::same(self, other)
+    }
+}
+
+// We only implement default if we have assigned a default compiler and engine
+#[cfg(all(feature = "default-compiler", feature = "default-engine"))]
+impl Default for Store {
+    fn default() -> Self {
+        // We store them on a function that returns to make
+        // sure this function doesn't emit a compile error even if
+        // more than one compiler is enabled.
+        #[allow(unreachable_code)]
+        fn get_config() -> impl CompilerConfig + 'static {
+            cfg_if::cfg_if! {
+                if #[cfg(feature = "default-cranelift")] {
+                    wasmer_compiler_cranelift::Cranelift::default()
+                } else if #[cfg(feature = "default-llvm")] {
+                    wasmer_compiler_llvm::LLVM::default()
+                } else if #[cfg(feature = "default-singlepass")] {
+                    wasmer_compiler_singlepass::Singlepass::default()
+                } else {
+                    compile_error!("No default compiler chosen")
+                }
+            }
+        }
+
+        #[allow(unreachable_code, unused_mut)]
+        fn get_engine(mut config: impl CompilerConfig + 'static) -> impl Engine + Send + Sync {
+            cfg_if::cfg_if! {
+                if #[cfg(feature = "default-jit")] {
+                    wasmer_engine_jit::JIT::new(config)
+                        .engine()
+                } else if #[cfg(feature = "default-native")] {
+                    wasmer_engine_native::Native::new(config)
+                        .engine()
+                } else {
+                    compile_error!("No default engine chosen")
+                }
+            }
+        }
+
+        let config = get_config();
+        let engine = get_engine(config);
+        let tunables = BaseTunables::for_target(engine.target());
+        Store::from_parts(Arc::new(engine), Arc::new(tunables))
+    }
+}
+
+impl fmt::Debug for Store {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Store").finish()
+    }
+}
+
+/// A trait represinting any object that lives in the `Store`.
+pub trait StoreObject {
+    /// Return true if the object `Store` is the same as the provided `Store`.
+    fn comes_from_same_store(&self, store: &Store) -> bool;
+}
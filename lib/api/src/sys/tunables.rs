@@ -0,0 +1,466 @@
+use crate::sys::event_log::{self, EventLogSlot};
+use crate::sys::limits::LimitsSlot;
+use crate::sys::memory_growth::{self, GrowthSubscribers};
+use crate::{MemoryType, Pages, TableType};
+use std::cmp::min;
+use std::iter;
+use std::ptr::NonNull;
+use std::sync::Arc;
+use target_lexicon::{OperatingSystem, PointerWidth};
+use wasmer_compiler::Target;
+use wasmer_engine::{LinkError, Tunables, TunablesConfig};
+use wasmer_types::entity::PrimaryMap;
+use wasmer_types::{GlobalType, LocalGlobalIndex, LocalMemoryIndex, LocalTableIndex};
+use wasmer_types::{MemoryIndex, TableIndex};
+use wasmer_vm::libcalls::Libcalls;
+use wasmer_vm::MemoryError;
+use wasmer_vm::{
+    BoundsCheck, Global, LinearMemory, LinearTable, Memory, MemoryStyle, ModuleInfo, StackLimit,
+    Table, TableStyle, VMMemoryDefinition, VMTableDefinition,
+};
+
+/// Tunable parameters for WebAssembly compilation.
+/// This is the reference implementation of the `Tunables` trait,
+/// used by default.
+///
+/// You can use this as a template for creating a custom Tunables
+/// implementation or use composition to wrap your Tunables around
+/// this one. The later approach is demonstrated in the
+/// tunables-limit-memory example.
+#[derive(Clone)]
+pub struct BaseTunables {
+    /// For static heaps, the size in wasm pages of the heap protected by bounds checking.
+    pub static_memory_bound: Pages,
+
+    /// The size in bytes of the offset guard for static heaps.
+    pub static_memory_offset_guard_size: u64,
+
+    /// The size in bytes of the offset guard for dynamic heaps.
+    pub dynamic_memory_offset_guard_size: u64,
+
+    /// How the accesses to the memories are checked to be in bounds.
+    pub memory_bounds_check: BoundsCheck,
+}
+
+impl BaseTunables {
+    /// Get the `BaseTunables` for a specific Target
+    pub fn for_target(target: &Target) -> Self {
+        let triple = target.triple();
+        let pointer_width: PointerWidth = triple.pointer_width().unwrap();
+        let (mut static_memory_bound, mut static_memory_offset_guard_size): (Pages, u64) =
+            match pointer_width {
+                PointerWidth::U16 => (0x400.into(), 0x1000),
+                PointerWidth::U32 => (0x4000.into(), 0x1_0000),
+                // Static Memory Bound:
+                //   Allocating 4 GiB of address space let us avoid the
+                //   need for explicit bounds checks.
+                // Static Memory Guard size:
+                //   Allocating 2 GiB of address space lets us translate wasm
+                //   offsets into x86 offsets as aggressively as we can.
+                PointerWidth::U64 => (0x1_0000.into(), 0x8000_0000),
+            };
+
+        // Allocate a small guard to optimize common cases but without
+        // wasting too much memory.
+        let dynamic_memory_offset_guard_size: u64 = 0x1_0000;
+
+        if let OperatingSystem::Windows = triple.operating_system {
+            // For now, use a smaller footprint on Windows so that we don't
+            // outstrip the paging file.
+            static_memory_bound = min(static_memory_bound, 0x100.into());
+            static_memory_offset_guard_size = min(static_memory_offset_guard_size, 0x10000);
+        }
+
+        Self {
+            static_memory_bound,
+            static_memory_offset_guard_size,
+            dynamic_memory_offset_guard_size,
+            memory_bounds_check: BoundsCheck::GuardPages,
+        }
+    }
+
+    /// Get the `BaseTunables` for a specific Target, with the
+    /// parameters set by `config`.
+    pub fn for_target_with_config(target: &Target, config: &TunablesConfig) -> Self {
+        let defaults = Self::for_target(target);
+        Self {
+            static_memory_bound: config
+                .static_memory_bound
+                .unwrap_or(defaults.static_memory_bound),
+            static_memory_offset_guard_size: config
+                .static_memory_offset_guard_size
+                .unwrap_or(defaults.static_memory_offset_guard_size),
+            dynamic_memory_offset_guard_size: config
+                .dynamic_memory_offset_guard_size
+                .unwrap_or(defaults.dynamic_memory_offset_guard_size),
+            memory_bounds_check: config
+                .memory_bounds_check
+                .unwrap_or(defaults.memory_bounds_check),
+        }
+    }
+}
+
+/// The tunables of a store, wrapping the ones it's created with to
+/// add the features of the store: the memories are wrapped so their
+/// grows are notified and logged, the memories and tables are counted
+/// in the limits of the store, and the instances get the stack limit
+/// of the store.
+pub(crate) struct StoreTunables {
+    inner: Arc<dyn Tunables + Send + Sync>,
+    log: EventLogSlot,
+    growth: GrowthSubscribers,
+    stack_limit: Arc<StackLimit>,
+    limits: LimitsSlot,
+}
+
+impl StoreTunables {
+    pub(crate) fn new(
+        inner: Arc<dyn Tunables + Send + Sync>,
+        log: EventLogSlot,
+        growth: GrowthSubscribers,
+        stack_limit: Arc<StackLimit>,
+        limits: LimitsSlot,
+    ) -> Self {
+        Self {
+            inner,
+            log,
+            growth,
+            stack_limit,
+            limits,
+        }
+    }
+
+    fn wrap(&self, memory: Arc<dyn Memory>) -> Arc<dyn Memory> {
+        let memory = memory_growth::notifying_memory(&self.growth, memory);
+        event_log::logged_memory(&self.log, memory)
+    }
+}
+
+impl Tunables for StoreTunables {
+    fn memory_style(&self, memory: &MemoryType) -> MemoryStyle {
+        self.inner.memory_style(memory)
+    }
+
+    fn memory_bounds_check(&self, memory: &MemoryType, style: &MemoryStyle) -> BoundsCheck {
+        self.inner.memory_bounds_check(memory, style)
+    }
+
+    fn table_style(&self, table: &TableType) -> TableStyle {
+        self.inner.table_style(table)
+    }
+
+    fn create_host_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+    ) -> Result<Arc<dyn Memory>, MemoryError> {
+        let mut reservation = self.limits.reserve_memories(iter::once(ty))?;
+        let memory = self.wrap(self.inner.create_host_memory(ty, style)?);
+        reservation.fill(&memory);
+        Ok(memory)
+    }
+
+    unsafe fn create_vm_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+        vm_definition_location: NonNull<VMMemoryDefinition>,
+    ) -> Result<Arc<dyn Memory>, MemoryError> {
+        let mut reservation = self.limits.reserve_memories(iter::once(ty))?;
+        let memory = self.wrap(
+            self.inner
+                .create_vm_memory(ty, style, vm_definition_location)?,
+        );
+        reservation.fill(&memory);
+        Ok(memory)
+    }
+
+    fn create_host_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+    ) -> Result<Arc<dyn Table>, String> {
+        let mut reservation = self.limits.reserve_tables(iter::once(ty))?;
+        let table = self.inner.create_host_table(ty, style)?;
+        reservation.fill(&table);
+        Ok(table)
+    }
+
+    unsafe fn create_vm_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+        vm_definition_location: NonNull<VMTableDefinition>,
+    ) -> Result<Arc<dyn Table>, String> {
+        let mut reservation = self.limits.reserve_tables(iter::once(ty))?;
+        let table = self
+            .inner
+            .create_vm_table(ty, style, vm_definition_location)?;
+        reservation.fill(&table);
+        Ok(table)
+    }
+
+    fn create_global(&self, ty: GlobalType) -> Result<Arc<Global>, String> {
+        self.inner.create_global(ty)
+    }
+
+    unsafe fn create_memories(
+        &self,
+        module: &ModuleInfo,
+        memory_styles: &PrimaryMap<MemoryIndex, MemoryStyle>,
+        memory_definition_locations: &[NonNull<VMMemoryDefinition>],
+    ) -> Result<PrimaryMap<LocalMemoryIndex, Arc<dyn Memory>>, LinkError> {
+        let mut reservation = self
+            .limits
+            .reserve_memories(module.memories.values().skip(module.num_imported_memories))
+            .map_err(|error| LinkError::Resource(error.to_string()))?;
+        let memories =
+            self.inner
+                .create_memories(module, memory_styles, memory_definition_locations)?;
+        Ok(memories
+            .into_iter()
+            .map(|(_, memory)| {
+                let memory = self.wrap(memory);
+                reservation.fill(&memory);
+                memory
+            })
+            .collect())
+    }
+
+    unsafe fn create_tables(
+        &self,
+        module: &ModuleInfo,
+        table_styles: &PrimaryMap<TableIndex, TableStyle>,
+        table_definition_locations: &[NonNull<VMTableDefinition>],
+    ) -> Result<PrimaryMap<LocalTableIndex, Arc<dyn Table>>, LinkError> {
+        let mut reservation = self
+            .limits
+            .reserve_tables(module.tables.values().skip(module.num_imported_tables))
+            .map_err(LinkError::Resource)?;
+        let tables = self
+            .inner
+            .create_tables(module, table_styles, table_definition_locations)?;
+        for table in tables.values() {
+            reservation.fill(table);
+        }
+        Ok(tables)
+    }
+
+    fn create_globals(
+        &self,
+        module: &ModuleInfo,
+    ) -> Result<PrimaryMap<LocalGlobalIndex, Arc<Global>>, LinkError> {
+        self.inner.create_globals(module)
+    }
+
+    fn libcalls(&self) -> Libcalls {
+        self.inner.libcalls()
+    }
+
+    fn stack_limit(&self) -> Option<Arc<StackLimit>> {
+        Some(self.stack_limit.clone())
+    }
+}
+
+impl Tunables for BaseTunables {
+    /// Get a `MemoryStyle` for the provided `MemoryType`
+    fn memory_style(&self, memory: &MemoryType) -> MemoryStyle {
+        // A heap with a maximum that doesn't exceed the static memory bound specified by the
+        // tunables make it static.
+        //
+        // If the module doesn't declare an explicit maximum treat it as 4GiB.
+        let maximum = memory.maximum.unwrap_or_else(Pages::max_value);
+        if maximum <= self.static_memory_bound {
+            MemoryStyle::Static {
+                // Bound can be larger than the maximum for performance reasons
+                bound: self.static_memory_bound,
+                offset_guard_size: self.static_memory_offset_guard_size,
+            }
+        } else {
+            MemoryStyle::Dynamic {
+                offset_guard_size: self.dynamic_memory_offset_guard_size,
+            }
+        }
+    }
+
+    /// Get the [`BoundsCheck`] of the tunables for any memory.
+    fn memory_bounds_check(&self, _memory: &MemoryType, _style: &MemoryStyle) -> BoundsCheck {
+        self.memory_bounds_check
+    }
+
+    /// Get a [`TableStyle`] for the provided [`TableType`].
+    fn table_style(&self, _table: &TableType) -> TableStyle {
+        TableStyle::CallerChecksSignature
+    }
+
+    /// Create a memory owned by the host given a [`MemoryType`] and a [`MemoryStyle`].
+    fn create_host_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+    ) -> Result<Arc<dyn Memory>, MemoryError> {
+        Ok(Arc::new(LinearMemory::new(&ty, &style)?))
+    }
+
+    /// Create a memory owned by the VM given a [`MemoryType`] and a [`MemoryStyle`].
+    ///
+    /// # Safety
+    /// - `vm_definition_location` must point to a valid, owned `VMMemoryDefinition`,
+    ///   for example in `VMContext`.
+    unsafe fn create_vm_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+        vm_definition_location: NonNull<VMMemoryDefinition>,
+    ) -> Result<Arc<dyn Memory>, MemoryError> {
+        Ok(Arc::new(LinearMemory::from_definition(
+            &ty,
+            &style,
+            vm_definition_location,
+        )?))
+    }
+
+    /// Create a table owned by the host given a [`TableType`] and a [`TableStyle`].
+    fn create_host_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+    ) -> Result<Arc<dyn Table>, String> {
+        Ok(Arc::new(LinearTable::new(&ty, &style)?))
+    }
+
+    /// Create a table owned by the VM given a [`TableType`] and a [`TableStyle`].
+    ///
+    /// # Safety
+    /// - `vm_definition_location` must point to a valid, owned `VMTableDefinition`,
+    ///   for example in `VMContext`.
+    unsafe fn create_vm_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+        vm_definition_location: NonNull<VMTableDefinition>,
+    ) -> Result<Arc<dyn Table>, String> {
+        Ok(Arc::new(LinearTable::from_definition(
+            &ty,
+            &style,
+            vm_definition_location,
+        )?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::WASM_PAGE_SIZE;
+
+    #[test]
+    fn memory_style() {
+        let tunables = BaseTunables {
+            static_memory_bound: Pages(2048),
+            static_memory_offset_guard_size: 128,
+            dynamic_memory_offset_guard_size: 256,
+            memory_bounds_check: BoundsCheck::GuardPages,
+        };
+
+        // No maximum
+        let requested = MemoryType::new(3, None, true);
+        let style = tunables.memory_style(&requested);
+        match style {
+            MemoryStyle::Dynamic { offset_guard_size } => assert_eq!(offset_guard_size, 256),
+            s => panic!("Unexpected memory style: {:?}", s),
+        }
+
+        // Large maximum
+        let requested = MemoryType::new(3, Some(5_000_000), true);
+        let style = tunables.memory_style(&requested);
+        match style {
+            MemoryStyle::Dynamic { offset_guard_size } => assert_eq!(offset_guard_size, 256),
+            s => panic!("Unexpected memory style: {:?}", s),
+        }
+
+        // Small maximum
+        let requested = MemoryType::new(3, Some(16), true);
+        let style = tunables.memory_style(&requested);
+        match style {
+            MemoryStyle::Static {
+                bound,
+                offset_guard_size,
+            } => {
+                assert_eq!(bound, Pages(2048));
+                assert_eq!(offset_guard_size, 128);
+            }
+            s => panic!("Unexpected memory style: {:?}", s),
+        }
+    }
+
+    #[test]
+    fn config() {
+        let target = Target::default();
+        let defaults = BaseTunables::for_target(&target);
+
+        let tunables = BaseTunables::for_target_with_config(&target, &TunablesConfig::new());
+        assert_eq!(tunables.static_memory_bound, defaults.static_memory_bound);
+        assert_eq!(
+            tunables.dynamic_memory_offset_guard_size,
+            defaults.dynamic_memory_offset_guard_size
+        );
+
+        let tunables = BaseTunables::for_target_with_config(
+            &target,
+            TunablesConfig::new()
+                .static_memory_bound(Pages(0))
+                .dynamic_memory_offset_guard_size(1),
+        );
+        assert_eq!(
+            tunables.static_memory_offset_guard_size,
+            defaults.static_memory_offset_guard_size
+        );
+        assert_eq!(
+            tunables.dynamic_memory_offset_guard_size,
+            WASM_PAGE_SIZE as u64
+        );
+        match tunables.memory_style(&MemoryType::new(1, Some(2), false)) {
+            MemoryStyle::Dynamic { offset_guard_size } => {
+                assert_eq!(offset_guard_size, WASM_PAGE_SIZE as u64)
+            }
+            s => panic!("Unexpected memory style: {:?}", s),
+        }
+    }
+
+    #[test]
+    fn for_foreign_target() {
+        use std::str::FromStr;
+        use target_lexicon::Triple;
+        use wasmer_compiler::CpuFeature;
+
+        let requested = MemoryType::new(1, None, false);
+
+        let aarch64 = Target::new(
+            Triple::from_str("aarch64-unknown-linux-gnu").unwrap(),
+            CpuFeature::NEON.into(),
+        );
+        let tunables = BaseTunables::for_target(&aarch64);
+        match tunables.memory_style(&requested) {
+            MemoryStyle::Static {
+                bound,
+                offset_guard_size,
+            } => {
+                assert_eq!(bound, Pages(0x1_0000));
+                assert_eq!(offset_guard_size, 0x8000_0000);
+            }
+            s => panic!("Unexpected memory style: {:?}", s),
+        }
+
+        let armv7 = Target::new(
+            Triple::from_str("armv7-unknown-linux-gnueabihf").unwrap(),
+            CpuFeature::set(),
+        );
+        let tunables = BaseTunables::for_target(&armv7);
+        match tunables.memory_style(&requested) {
+            MemoryStyle::Dynamic { offset_guard_size } => {
+                assert_eq!(offset_guard_size, 0x1_0000)
+            }
+            s => panic!("Unexpected memory style: {:?}", s),
+        }
+    }
+}
@@ -1,5 +1,5 @@
-use crate::externals::Function;
-use crate::store::{Store, StoreObject};
+use crate::sys::externals::Function;
+use crate::sys::store::{Store, StoreObject};
 use crate::RuntimeError;
 use std::ptr;
 use wasmer_types::Value;
@@ -49,6 +49,11 @@ impl ValFuncRef for Val {
         &self,
         store: &Store,
     ) -> Result<wasmer_vm::VMCallerCheckedAnyfunc, RuntimeError> {
+        if let Self::ExternRef(ExternRef::Ref(_)) | Self::ExternRef(ExternRef::Other(_)) = self {
+            return Err(RuntimeError::new(
+                "tables only hold function references and null references",
+            ));
+        }
         if !self.comes_from_same_store(store) {
             return Err(RuntimeError::new("cross-`Store` values are not supported"));
         }
@@ -60,6 +65,14 @@ impl ValFuncRef for Val {
                     host_env: ptr::null_mut(),
                 },
             },
+            // The dynamic host functions can only be called from
+            // WebAssembly through the trampolines of the modules
+            // importing them.
+            Self::FuncRef(f) if f.exported.vm_function.kind == wasmer_vm::VMFunctionKind::Dynamic => {
+                return Err(RuntimeError::new(
+                    "dynamic host functions can't be stored in a table or a global, create them with `Function::new_native` instead",
+                ))
+            }
             Self::FuncRef(f) => f.checked_anyfunc(),
             _ => return Err(RuntimeError::new("val is not funcref")),
         })
@@ -84,7 +97,10 @@ impl ValFuncRef for Val {
                 // are converted to use the trampolines with static signatures).
                 kind: wasmer_vm::VMFunctionKind::Static,
                 vmctx: item.vmctx,
-                call_trampoline: None,
+                // The host calls the function through any trampoline of
+                // its signature, as the table doesn't tell the artifact
+                // it comes from.
+                call_trampoline: store.engine().lookup_call_trampoline(item.type_index),
                 instance_ref: None,
             },
         };
@@ -0,0 +1,172 @@
+#![cfg(not(feature = "js"))]
+
+use anyhow::Result;
+use wasmer::*;
+
+/// The core module of the component: `add` calls the imported
+/// `double`, and `greet` returns its string parameter.
+const CORE_MODULE: &str = r#"
+(module
+  (import "host" "double" (func $double (param i32) (result i32)))
+  (memory (export "memory") 1)
+  (global $heap (mut i32) (i32.const 1024))
+
+  (func (export "cabi_realloc") (param i32 i32 i32 i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $heap))
+    (global.set $heap (i32.add (global.get $heap) (local.get 3)))
+    (local.get $ptr))
+
+  (func (export "add") (param i32 i32) (result i32)
+    (call $double (i32.add (local.get 0) (local.get 1))))
+
+  (func (export "greet") (param i32 i32) (result i32)
+    (i32.store (i32.const 0) (local.get 0))
+    (i32.store (i32.const 4) (local.get 1))
+    (i32.const 0)))
+"#;
+
+fn leb(mut value: usize, out: &mut Vec<u8>) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn name(name: &str, out: &mut Vec<u8>) {
+    leb(name.len(), out);
+    out.extend(name.as_bytes());
+}
+
+fn section(id: u8, contents: &[u8], out: &mut Vec<u8>) {
+    out.push(id);
+    leb(contents.len(), out);
+    out.extend(contents);
+}
+
+/// Encodes the component:
+///
+/// ```text
+/// (component
+///   (type (func (param "x" u32) (result u32)))
+///   (type (func (param "a" u32) (param "b" u32) (result u32)))
+///   (type (func (param "name" string) (result string)))
+///   (import "double" (func $double (type 0)))
+///   (core func $double (canon lower (func $double)))
+///   (core instance $host (export "double" (func $double)))
+///   (core instance $main (instantiate 0 (with "host" (instance $host))))
+///   (func (export "add") (type 1) (canon lift (core func $main "add")))
+///   (func (export "greet") (type 2)
+///     (canon lift (core func $main "greet") (memory $main "memory")
+///       (realloc (func $main "cabi_realloc")))))
+/// ```
+fn component() -> Result<Vec<u8>> {
+    let mut bytes = vec![0x00, 0x61, 0x73, 0x6d, 0x0d, 0x00, 0x01, 0x00];
+    section(0x01, &wat2wasm(CORE_MODULE.as_bytes())?, &mut bytes);
+
+    let mut types = vec![0x03];
+    types.extend(&[0x40, 0x01]);
+    name("x", &mut types);
+    types.extend(&[0x79, 0x00, 0x79]);
+    types.extend(&[0x40, 0x02]);
+    name("a", &mut types);
+    types.push(0x79);
+    name("b", &mut types);
+    types.extend(&[0x79, 0x00, 0x79]);
+    types.extend(&[0x40, 0x01]);
+    name("name", &mut types);
+    types.extend(&[0x73, 0x00, 0x73]);
+    section(0x07, &types, &mut bytes);
+
+    let mut imports = vec![0x01, 0x00];
+    name("double", &mut imports);
+    imports.extend(&[0x01, 0x00]);
+    section(0x0a, &imports, &mut bytes);
+
+    // canon lower of the function 0, without options.
+    section(0x08, &[0x01, 0x01, 0x00, 0x00, 0x00], &mut bytes);
+
+    let mut instances = vec![0x02, 0x01, 0x01];
+    name("double", &mut instances);
+    instances.extend(&[0x00, 0x00]);
+    instances.extend(&[0x00, 0x00, 0x01]);
+    name("host", &mut instances);
+    instances.extend(&[0x12, 0x00]);
+    section(0x02, &instances, &mut bytes);
+
+    // The core functions 1, 2 and 3, and the core memory 0.
+    let mut aliases = vec![0x04];
+    for (sort, export) in &[
+        (0x00, "add"),
+        (0x00, "cabi_realloc"),
+        (0x00, "greet"),
+        (0x02, "memory"),
+    ] {
+        aliases.extend(&[0x00, *sort, 0x01, 0x01]);
+        name(export, &mut aliases);
+    }
+    section(0x06, &aliases, &mut bytes);
+
+    let mut canons = vec![0x02];
+    // The lift of the core function 1, of type 1.
+    canons.extend(&[0x00, 0x00, 0x01, 0x00, 0x01]);
+    // The lift of the core function 3, with the memory 0 and the
+    // `realloc` 2, of type 2.
+    canons.extend(&[0x00, 0x00, 0x03, 0x02, 0x03, 0x00, 0x04, 0x02, 0x02]);
+    section(0x08, &canons, &mut bytes);
+
+    let mut exports = vec![0x02];
+    for (export, func) in &[("add", 1), ("greet", 2)] {
+        exports.push(0x00);
+        name(export, &mut exports);
+        exports.extend(&[0x01, *func, 0x00]);
+    }
+    section(0x0b, &exports, &mut bytes);
+    Ok(bytes)
+}
+
+#[test]
+fn call_component_functions() -> Result<()> {
+    let store = Store::default();
+    let component = Component::new(&store, component()?)?;
+    assert_eq!(
+        component.exports().collect::<Vec<_>>(),
+        vec!["add", "greet"]
+    );
+
+    let mut linker = Linker::new(&store);
+    linker.func_wrap("", "double", |(x,): (u32,)| Ok((x * 2,)));
+    let instance = linker.instantiate(&component)?;
+
+    let add = instance.get_func("add")?;
+    assert_eq!(
+        add.call(&[ComponentVal::U32(1), ComponentVal::U32(2)])?,
+        vec![ComponentVal::U32(6)]
+    );
+
+    let greet = instance
+        .get_func("greet")?
+        .typed::<(String,), (String,)>()?;
+    assert_eq!(greet.call(("world".to_string(),))?, ("world".to_string(),));
+    Ok(())
+}
+
+#[test]
+fn unknown_import() -> Result<()> {
+    let store = Store::default();
+    let component = Component::new(&store, component()?)?;
+
+    match Linker::new(&store).instantiate(&component) {
+        Err(ComponentError::UnknownImport { interface, name }) => {
+            assert_eq!(interface, "");
+            assert_eq!(name, "double");
+        }
+        _ => panic!("the import should be unknown"),
+    }
+    Ok(())
+}
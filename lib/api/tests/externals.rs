@@ -1,4 +1,7 @@
+#![cfg(not(feature = "js"))]
+
 use anyhow::Result;
+use std::sync::{Arc, Mutex};
 use wasmer::*;
 
 #[test]
@@ -99,10 +102,89 @@ fn table_get() -> Result<()> {
     Ok(())
 }
 
+const CALL_INDIRECT_WAT: &str = r#"
+(module
+  (type $t (func (param i32) (result i32)))
+  (table (export "table") 4 funcref)
+  (elem (i32.const 0) $add_one)
+  (func $add_one (param i32) (result i32) (i32.add (local.get 0) (i32.const 1)))
+  (func (export "call") (param i32 i32) (result i32)
+    (call_indirect (type $t) (local.get 1) (local.get 0))))
+"#;
+
 #[test]
-#[ignore]
 fn table_set() -> Result<()> {
-    // Table set not yet tested
+    let store = Store::default();
+    let module = Module::new(&store, CALL_INDIRECT_WAT)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let table = instance.exports.get_table("table")?;
+    let call: NativeFunc<(i32, i32), i32> = instance.exports.get_native_function("call")?;
+
+    let double = Function::new_native(&store, |num: i32| num * 2);
+    table.set(1, Value::FuncRef(double))?;
+    assert_eq!(call.call(0, 10)?, 11);
+    assert_eq!(call.call(1, 10)?, 20);
+    assert!(call.call(2, 10).is_err());
+
+    let dynamic = Function::new(
+        &store,
+        FunctionType::new(vec![Type::I32], vec![Type::I32]),
+        |args| Ok(args.to_vec()),
+    );
+    assert!(table.set(2, Value::FuncRef(dynamic)).is_err());
+    assert!(table
+        .set(2, Value::ExternRef(ExternRef::new(Box::new(1u32))))
+        .is_err());
+    assert!(table.set(4, Value::null()).is_err());
+
+    table.set(1, Value::null())?;
+    assert!(call.call(1, 10).is_err());
+    Ok(())
+}
+
+#[test]
+fn table_elements_are_callable() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(&store, CALL_INDIRECT_WAT)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let table = instance.exports.get_table("table")?;
+    table.set(
+        1,
+        Value::FuncRef(Function::new_native(&store, |num: i32| num * 2)),
+    )?;
+
+    let element = |index| match table.get(index) {
+        Some(Value::FuncRef(function)) => function,
+        _ => panic!("the element {} isn't a function", index),
+    };
+    let add_one = element(0);
+    assert_eq!(
+        add_one.ty(),
+        &FunctionType::new(vec![Type::I32], vec![Type::I32])
+    );
+    assert_eq!(
+        add_one.call(&[Value::I32(10)])?.to_vec(),
+        vec![Value::I32(11)]
+    );
+    assert_eq!(add_one.native::<i32, i32>()?.call(20)?, 21);
+    assert!(add_one.native::<i64, i32>().is_err());
+
+    let double = element(1);
+    assert_eq!(
+        double.call(&[Value::I32(10)])?.to_vec(),
+        vec![Value::I32(20)]
+    );
+    assert_eq!(double.native::<i32, i32>()?.call(20)?, 40);
+
+    // The functions taken from a table can be stored back in one.
+    table.set(2, Value::FuncRef(double))?;
+    let call: NativeFunc<(i32, i32), i32> = instance.exports.get_native_function("call")?;
+    assert_eq!(call.call(2, 10)?, 20);
+
+    assert!(matches!(
+        table.get(3),
+        Some(Value::ExternRef(ExternRef::Null))
+    ));
     Ok(())
 }
 
@@ -128,9 +210,87 @@ fn table_grow() -> Result<()> {
 }
 
 #[test]
-#[ignore]
 fn table_copy() -> Result<()> {
-    // TODO: table copy test not yet implemented
+    let store = Store::default();
+    let module = Module::new(&store, CALL_INDIRECT_WAT)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let table = instance.exports.get_table("table")?;
+    let call: NativeFunc<(i32, i32), i32> = instance.exports.get_native_function("call")?;
+
+    let host_table = Table::new(
+        &store,
+        *table.ty(),
+        Value::FuncRef(Function::new_native(&store, |num: i32| num * 3)),
+    )?;
+    Table::copy(table, 2, &host_table, 0, 2)?;
+    Table::copy(table, 1, table, 0, 1)?;
+    assert_eq!(call.call(1, 10)?, 11);
+    assert_eq!(call.call(3, 10)?, 30);
+    assert!(Table::copy(table, 3, &host_table, 0, 2).is_err());
+    Ok(())
+}
+
+#[test]
+fn table_fill() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(&store, CALL_INDIRECT_WAT)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let table = instance.exports.get_table("table")?;
+    let call: NativeFunc<(i32, i32), i32> = instance.exports.get_native_function("call")?;
+
+    let negate = Function::new_native(&store, |num: i32| -num);
+    table.fill(1, Value::FuncRef(negate.clone()), 3)?;
+    assert_eq!(call.call(0, 10)?, 11);
+    for index in 1..4 {
+        assert_eq!(call.call(index, 10)?, -10);
+    }
+    assert!(table.fill(2, Value::FuncRef(negate), 3).is_err());
+
+    table.fill(0, Value::null(), 4)?;
+    assert!(call.call(0, 10).is_err());
+    Ok(())
+}
+
+#[test]
+fn table_interpose() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+        (module
+          (type $t (func (param i32) (result i32)))
+          (table (export "table") 3 funcref)
+          (elem (i32.const 0) $add_one $add_two $add_three)
+          (func $add_one (param i32) (result i32) (i32.add (local.get 0) (i32.const 1)))
+          (func $add_two (param i32) (result i32) (i32.add (local.get 0) (i32.const 2)))
+          (func $add_three (param i32) (result i32) (i32.add (local.get 0) (i32.const 3)))
+          (func (export "call") (param i32 i32) (result i32)
+            (call_indirect (type $t) (local.get 1) (local.get 0))))
+        "#,
+    )?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let table = instance.exports.get_table("table")?;
+    let call: NativeFunc<(i32, i32), i32> = instance.exports.get_native_function("call")?;
+
+    {
+        let _interposer = table.interpose(|index| match index {
+            0 => IndirectCallAction::Allow,
+            1 => IndirectCallAction::Redirect(2),
+            _ => IndirectCallAction::Deny,
+        })?;
+        assert_eq!(call.call(0, 10)?, 11);
+        assert_eq!(call.call(1, 10)?, 13);
+        let err = call.call(2, 10).unwrap_err();
+        assert_eq!(
+            err.message(),
+            "indirect call to table index 2 denied by host policy"
+        );
+    }
+
+    // Dropping the interposer restores the original entries.
+    assert_eq!(call.call(1, 10)?, 12);
+    assert_eq!(call.call(2, 10)?, 13);
+
     Ok(())
 }
 
@@ -148,6 +308,147 @@ fn memory_new() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn memory_read_write() -> Result<()> {
+    let store = Store::default();
+    let memory = Memory::new(&store, MemoryType::new(Pages(1), Some(Pages(2)), false))?;
+
+    memory.write(65532, &[1, 2, 3, 4])?;
+    let mut buf = [0; 4];
+    memory.read(65532, &mut buf)?;
+    assert_eq!(buf, [1, 2, 3, 4]);
+    assert_eq!(
+        memory.read(65533, &mut buf),
+        Err(MemoryAccessError::OutOfBounds)
+    );
+    assert_eq!(
+        memory.write(u64::MAX, &buf),
+        Err(MemoryAccessError::OutOfBounds)
+    );
+
+    // The pages added by growing the memory are accessible.
+    memory.grow(1)?;
+    memory.write(65533, &buf)?;
+    let words = memory.view::<u8>().slice::<u32>(65532, 2)?;
+    assert_eq!(words[0].get(), u32::from_le_bytes([1, 1, 2, 3]));
+    assert_eq!(words[1].get(), u32::from_le_bytes([4, 0, 0, 0]));
+    assert_eq!(
+        memory.view::<u8>().slice::<u32>(65533, 1).err(),
+        Some(MemoryAccessError::Misaligned)
+    );
+    Ok(())
+}
+
+#[test]
+fn shared_global_and_memory() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+    (module
+      (import "env" "counter" (global $counter (mut i32)))
+      (import "env" "memory" (memory 1))
+      (func (export "increment") (result i32)
+        (global.set $counter (i32.add (global.get $counter) (i32.const 1)))
+        (global.get $counter))
+      (func (export "store") (param i32 i32)
+        (i32.store (local.get 0) (local.get 1)))
+      (func (export "load") (param i32) (result i32)
+        (i32.load (local.get 0)))
+      (func (export "grow") (param i32) (result i32)
+        (memory.grow (local.get 0))))
+"#,
+    )?;
+    let counter = Global::new_mut(&store, Value::I32(0));
+    let memory = Memory::new(&store, MemoryType::new(1, None, false))?;
+    let import_object = imports! {
+        "env" => {
+            "counter" => counter.clone(),
+            "memory" => memory.clone(),
+        },
+    };
+    let first = Instance::new(&module, &import_object)?;
+    let second = Instance::new(&module, &import_object)?;
+    let increment = |instance: &Instance| -> Result<i32> {
+        Ok(instance
+            .exports
+            .get_native_function::<(), i32>("increment")?
+            .call()?)
+    };
+
+    let typed = counter.typed::<i32>()?;
+    assert_eq!(increment(&first)?, 1);
+    assert_eq!(increment(&second)?, 2);
+    assert_eq!(typed.get(), 2);
+    typed.set(10)?;
+    assert_eq!(increment(&first)?, 11);
+    assert_eq!(counter.get(), Value::I32(11));
+    assert!(counter.typed::<i64>().is_err());
+
+    let store_fn = first
+        .exports
+        .get_native_function::<(u32, u32), ()>("store")?;
+    let load = second.exports.get_native_function::<u32, u32>("load")?;
+    store_fn.call(8, 42)?;
+    assert_eq!(load.call(8)?, 42);
+    memory.write(16, &7u32.to_le_bytes())?;
+    assert_eq!(load.call(16)?, 7);
+
+    // The growth by an instance is seen by the other one.
+    let grow = first.exports.get_native_function::<u32, i32>("grow")?;
+    assert_eq!(grow.call(1)?, 1);
+    assert_eq!(memory.size(), Pages(2));
+    store_fn.call(WASM_PAGE_SIZE as u32, 5)?;
+    assert_eq!(load.call(WASM_PAGE_SIZE as u32)?, 5);
+    assert_eq!(load.call(8)?, 42);
+    Ok(())
+}
+
+#[test]
+fn memory_growth_subscription() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+    (module
+      (memory (export "memory") 1)
+      (func (export "grow") (param i32) (result i32)
+        (memory.grow (local.get 0))))
+"#,
+    )?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let memory = instance.exports.get_memory("memory")?;
+    let grow: NativeFunc<i32, i32> = instance.exports.get_native_function("grow")?;
+
+    let growths = Arc::new(Mutex::new(vec![]));
+    let growths_ = growths.clone();
+    let subscription = memory.subscribe_growth(move |growth| {
+        growths_.lock().unwrap().push(*growth);
+    });
+
+    let base = memory.data_ptr();
+    memory.grow(1)?;
+    assert_eq!(grow.call(2)?, 2);
+    {
+        let growths = growths.lock().unwrap();
+        assert_eq!(growths.len(), 2);
+        assert_eq!(growths[0].old_size, Pages(1));
+        assert_eq!(growths[0].new_size, Pages(2));
+        assert_eq!(growths[0].old_base, base);
+        assert_eq!(growths[1].old_size, Pages(2));
+        assert_eq!(growths[1].new_size, Pages(4));
+        assert_eq!(growths[1].old_base, growths[0].new_base);
+        assert_eq!(growths[1].new_base, memory.data_ptr());
+    }
+
+    // A failed growth isn't notified, nor a growth once unsubscribed.
+    assert!(memory.grow(WASM_MAX_PAGES).is_err());
+    drop(subscription);
+    memory.grow(1)?;
+    assert_eq!(growths.lock().unwrap().len(), 2);
+    Ok(())
+}
+
 #[test]
 fn memory_grow() -> Result<()> {
     let store = Store::default();
@@ -177,6 +478,65 @@ fn memory_grow() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn memory_from_custom() -> Result<()> {
+    let engine = Store::default().engine().clone();
+    let tunables = BaseTunables {
+        static_memory_bound: Pages(0),
+        static_memory_offset_guard_size: 0,
+        dynamic_memory_offset_guard_size: 0,
+        memory_bounds_check: vm::BoundsCheck::GuardPages,
+    };
+    let store = Store::new_with_tunables(&*engine, tunables);
+    let module = Module::new(
+        &store,
+        r#"
+    (module
+      (import "env" "memory" (memory 1))
+      (func (export "store") (param i32 i32)
+        (i32.store (local.get 0) (local.get 1))))
+"#,
+    )?;
+
+    // The bytes in the minimum size are kept, not the ones past it.
+    let mut storage = vec![0; 2 * 65536];
+    storage[0] = 7;
+    storage[65536] = 7;
+    let memory = Memory::from_custom(&store, MemoryType::new(1, Some(3), false), storage)?;
+    assert_eq!(memory.size(), Pages(1));
+    assert_eq!(memory.view::<u8>()[0].get(), 7);
+    assert_eq!(memory.grow(1)?, Pages(1));
+    assert_eq!(memory.view::<u8>()[65536].get(), 0);
+
+    let instance = Instance::new(
+        &module,
+        &imports! {
+            "env" => {
+                "memory" => memory.clone(),
+            },
+        },
+    )?;
+    let store_fn: NativeFunc<(i32, i32), ()> = instance.exports.get_native_function("store")?;
+    store_fn.call(65540, 42)?;
+    assert_eq!(memory.view::<u32>()[65540 / 4].get(), 42);
+    assert!(store_fn.call(2 * 65536, 42).is_err());
+
+    // The storage grows past its initial size.
+    assert_eq!(memory.grow(1)?, Pages(2));
+    store_fn.call(2 * 65536, 42)?;
+    assert!(memory.grow(1).is_err());
+
+    // The storage has no guard pages.
+    let bad_result = Memory::from_custom(
+        &Store::default(),
+        MemoryType::new(1, Some(3), false),
+        vec![],
+    );
+    assert!(matches!(bad_result, Err(MemoryError::InvalidMemory { .. })));
+
+    Ok(())
+}
+
 #[test]
 fn function_new() -> Result<()> {
     let store = Store::default();
@@ -374,6 +734,53 @@ fn native_function_works() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn native_function_with_many_arguments() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"(module
+  (import "env" "sum" (func $sum (param i32 i64 i32 i64 i32 i64 i32 i64 i32 f32 i64) (result i64)))
+  (func (export "run") (result i64)
+    (call $sum
+      (i32.const 1) (i64.const 2) (i32.const 3) (i64.const 4) (i32.const 5) (i64.const 6)
+      (i32.const 7) (i64.const 8) (i32.const 9) (f32.const 10) (i64.const 11))))
+"#,
+    )?;
+
+    #[derive(WasmerEnv, Clone)]
+    struct Env {
+        multiplier: i64,
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn sum(
+        env: &Env,
+        a: i32,
+        b: i64,
+        c: i32,
+        d: i64,
+        e: i32,
+        f: i64,
+        g: i32,
+        h: i64,
+        i: i32,
+        j: f32,
+        k: i64,
+    ) -> i64 {
+        let sum =
+            a as i64 + b + c as i64 + d + e as i64 + f + g as i64 + h + i as i64 + j as i64 + k;
+        sum * env.multiplier
+    }
+
+    let function = Function::new_native_with_env(&store, Env { multiplier: 2 }, sum);
+    let instance = Instance::new(&module, &imports! { "env" => { "sum" => function } })?;
+    let run: NativeFunc<(), i64> = instance.exports.get_native_function("run")?;
+    assert_eq!(run.call()?, 132);
+
+    Ok(())
+}
+
 #[test]
 fn function_outlives_instance() -> Result<()> {
     let store = Store::default();
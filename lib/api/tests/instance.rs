@@ -1,4 +1,10 @@
+#![cfg(not(feature = "js"))]
+
 use anyhow::Result;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Barrier};
+use std::thread;
+use std::time::Duration;
 use wasmer::*;
 
 #[test]
@@ -37,3 +43,318 @@ fn exports_work_after_multiple_instances_have_been_freed() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn wait_idle_until_calls_return() -> Result<()> {
+    #[derive(WasmerEnv, Clone)]
+    struct Env {
+        barrier: Arc<Barrier>,
+    }
+
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+    (module
+      (import "host" "block" (func $block))
+      (func (export "run") (call $block)))
+"#,
+    )?;
+    let barrier = Arc::new(Barrier::new(2));
+    let block = Function::new_native_with_env(
+        &store,
+        Env {
+            barrier: barrier.clone(),
+        },
+        |env: &Env| {
+            // Entered, then released.
+            env.barrier.wait();
+            env.barrier.wait();
+        },
+    );
+    let instance = Instance::new(&module, &imports! { "host" => { "block" => block } })?;
+    assert!(!instance.is_executing());
+    assert!(instance.wait_idle(Duration::from_millis(0)));
+
+    let run = instance.exports.get_native_function::<(), ()>("run")?;
+    let thread = thread::spawn(move || run.call());
+    barrier.wait();
+    assert!(instance.is_executing());
+    assert!(!instance.wait_idle(Duration::from_millis(10)));
+
+    barrier.wait();
+    assert!(instance.wait_idle(Duration::from_secs(60)));
+    assert!(!instance.is_executing());
+    thread.join().unwrap()?;
+
+    Ok(())
+}
+
+#[test]
+fn snapshot_and_restore() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+    (module
+      (memory (export "memory") 1)
+      (global $counter (mut i32) (i32.const 0))
+      (table (export "table") 2 funcref)
+      (func $one (export "one") (result i32) (i32.const 1))
+      (func $two (result i32) (i32.const 2))
+      (func (export "warm_up")
+        (global.set $counter (i32.add (global.get $counter) (i32.const 1)))
+        (drop (memory.grow (i32.const 1)))
+        (i32.store (i32.const 65536) (i32.const 42)))
+      (func (export "counter") (result i32) (global.get $counter))
+      (func (export "call") (param i32) (result i32)
+        (call_indirect (result i32) (local.get 0)))
+      (elem (i32.const 0) $one $two))
+"#,
+    )?;
+    let import_object = imports! {};
+
+    let instance = Instance::new(&module, &import_object)?;
+    let warm_up: NativeFunc<(), ()> = instance.exports.get_native_function("warm_up")?;
+    warm_up.call()?;
+    warm_up.call()?;
+    let one = instance.exports.get_function("one")?.clone();
+    instance
+        .exports
+        .get_table("table")?
+        .set(1, Val::FuncRef(one))?;
+    let bytes = instance.snapshot()?.serialize()?;
+
+    let fork = Instance::new(&module, &import_object)?;
+    fork.restore(&InstanceSnapshot::deserialize(&bytes)?)?;
+    let counter: NativeFunc<(), i32> = fork.exports.get_native_function("counter")?;
+    assert_eq!(counter.call()?, 2);
+    let memory = fork.exports.get_memory("memory")?;
+    assert_eq!(memory.size(), Pages(3));
+    assert_eq!(memory.view::<u32>()[65536 / 4].get(), 42);
+    let call: NativeFunc<i32, i32> = fork.exports.get_native_function("call")?;
+    assert_eq!(call.call(0)?, 1);
+    assert_eq!(call.call(1)?, 1);
+
+    // The state of the fork is its own.
+    warm_up.call()?;
+    assert_eq!(counter.call()?, 2);
+
+    // A snapshot doesn't fit in a bigger instance.
+    let snapshot = Instance::new(&module, &import_object)?.snapshot()?;
+    assert!(matches!(
+        instance.restore(&snapshot),
+        Err(SnapshotError::Incompatible(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn resources() -> Result<()> {
+    struct File(Arc<AtomicUsize>);
+    impl Drop for File {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+    (module
+      (import "host" "open" (func $open (result i32)))
+      (import "host" "close" (func $close (param i32) (result i32)))
+      (export "open" (func $open))
+      (export "close" (func $close)))
+"#,
+    )?;
+    #[derive(Clone)]
+    struct OpenEnv(Resources<File>, Arc<AtomicUsize>);
+    impl WasmerEnv for OpenEnv {
+        fn init_with_instance(&mut self, instance: &Instance) -> Result<(), HostEnvInitError> {
+            self.0.init_with_instance(instance)
+        }
+    }
+
+    let files = Resources::<File>::with_quota(2);
+    let dropped = Arc::new(AtomicUsize::new(0));
+    let import_object = imports! {
+        "host" => {
+            "open" => Function::new_native_with_env(&store, OpenEnv(files.clone(), dropped.clone()), |env: &OpenEnv| {
+                env.0.insert(File(env.1.clone()))
+            }),
+            "close" => Function::new_native_with_env(&store, files.clone(), |files: &Resources<File>, handle: u32| {
+                files.remove(handle).map(|_| 0)
+            }),
+        },
+    };
+
+    let first = Instance::new(&module, &import_object)?;
+    let second = Instance::new(&module, &import_object)?;
+    let open = |instance: &Instance| -> Result<u32, RuntimeError> {
+        instance
+            .exports
+            .get_native_function::<(), u32>("open")
+            .unwrap()
+            .call()
+    };
+    let close = |instance: &Instance, handle: u32| -> Result<u32, RuntimeError> {
+        instance
+            .exports
+            .get_native_function::<u32, u32>("close")
+            .unwrap()
+            .call(handle)
+    };
+
+    let a = open(&first)?;
+    let b = open(&first)?;
+    assert_ne!(a, b);
+    let error = open(&first).unwrap_err();
+    assert_eq!(
+        error.downcast::<ResourceError>().unwrap(),
+        ResourceError::QuotaExceeded(2)
+    );
+    // The rejected resource is dropped.
+    assert_eq!(dropped.load(Ordering::SeqCst), 1);
+    let c = open(&second)?;
+    assert_eq!(files.len(), 3);
+    assert!(files.get(c).is_ok());
+
+    // An instance can't use the resources of another one.
+    assert!(close(&second, a).is_err());
+    assert_eq!(close(&first, a)?, 0);
+    assert_eq!(dropped.load(Ordering::SeqCst), 2);
+    // The handle of a removed resource isn't valid anymore.
+    assert!(close(&first, a).is_err());
+    assert!(files.get(a).is_err());
+
+    // The resources of an instance are dropped with it.
+    drop(first);
+    assert_eq!(dropped.load(Ordering::SeqCst), 3);
+    assert_eq!(files.len(), 1);
+    assert!(files.get(b).is_err());
+    assert!(files.get(c).is_ok());
+
+    drop(second);
+    assert_eq!(dropped.load(Ordering::SeqCst), 4);
+    assert!(files.is_empty());
+    Ok(())
+}
+
+#[test]
+fn resolved_imports() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"(module (import "env" "a" (global i32)) (import "env" "b" (global i32)))"#,
+    )?;
+    let first = imports! {
+        "env" => {
+            "a" => Global::new(&store, Value::I32(1)),
+        },
+    };
+    let second = imports! {
+        "env" => {
+            "a" => Global::new(&store, Value::I32(2)),
+            "b" => Global::new(&store, Value::I32(3)),
+        },
+    };
+    let instance = Instance::new(&module, &first.chain_front(second))?;
+
+    let resolved = instance
+        .resolved_imports()
+        .map(|(import, extern_)| match extern_ {
+            Extern::Global(global) => (import.name().to_string(), global.get().unwrap_i32()),
+            _ => panic!("`{}` isn't resolved to a global", import.name()),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(resolved, vec![("a".to_string(), 2), ("b".to_string(), 3)]);
+    Ok(())
+}
+
+#[test]
+fn replace_import_env() -> Result<()> {
+    #[derive(WasmerEnv, Clone)]
+    struct Env {
+        value: i32,
+    }
+
+    fn value(env: &Env) -> i32 {
+        env.value
+    }
+
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"(module
+          (import "host" "value" (func $value (result i32)))
+          (import "host" "dynamic" (func $dynamic (result i32)))
+          (table (export "table") 1 funcref)
+          (elem (i32.const 0) $value)
+          (func (export "value") (result i32) (call $value))
+          (func (export "dynamic") (result i32) (call $dynamic)))"#,
+    )?;
+    let dynamic = |value| {
+        Function::new_with_env(
+            &store,
+            FunctionType::new(vec![], vec![Type::I32]),
+            Env { value },
+            |env, _| Ok(vec![Value::I32(env.value)]),
+        )
+    };
+    let instance = Instance::new(
+        &module,
+        &imports! {
+            "host" => {
+                "value" => Function::new_native_with_env(&store, Env { value: 1 }, value),
+                "dynamic" => dynamic(10),
+            },
+        },
+    )?;
+    let call = |name| -> Result<i32> {
+        let function = instance.exports.get_native_function::<(), i32>(name)?;
+        Ok(function.call()?)
+    };
+    assert_eq!(call("value")?, 1);
+    assert_eq!(call("dynamic")?, 10);
+
+    let element = instance.exports.get_table("table")?.get(0).unwrap();
+    instance.replace_import_env(
+        "host",
+        "value",
+        &Function::new_native_with_env(&store, Env { value: 2 }, value),
+    )?;
+    instance.replace_import_env("host", "dynamic", &dynamic(20))?;
+    assert_eq!(call("value")?, 2);
+    assert_eq!(call("dynamic")?, 20);
+    // The table element was taken before.
+    match element {
+        Value::FuncRef(function) => {
+            assert_eq!(function.call(&[])?.to_vec(), vec![Value::I32(1)])
+        }
+        _ => panic!("the table element isn't a function"),
+    }
+
+    fn other(env: &Env) -> i32 {
+        -env.value
+    }
+    assert!(matches!(
+        instance.replace_import_env(
+            "host",
+            "value",
+            &Function::new_native_with_env(&store, Env { value: 3 }, other),
+        ),
+        Err(ImportEnvError::IncompatibleFunction { .. })
+    ));
+    assert!(matches!(
+        instance.replace_import_env("host", "value", &Function::new_native(&store, || 3)),
+        Err(ImportEnvError::NoEnv { .. })
+    ));
+    assert!(matches!(
+        instance.replace_import_env("host", "missing", &dynamic(3)),
+        Err(ImportEnvError::UnknownImport { .. })
+    ));
+    assert_eq!(call("value")?, 2);
+    Ok(())
+}
@@ -0,0 +1,87 @@
+#![cfg(feature = "js")]
+
+use wasm_bindgen_test::*;
+use wasmer::*;
+
+#[wasm_bindgen_test]
+fn exported_function_is_called() {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+(module
+  (func (export "add") (param i32 i32) (result i32)
+    local.get 0
+    local.get 1
+    i32.add))
+"#,
+    )
+    .unwrap();
+    let instance = Instance::new(&module, &imports! {}).unwrap();
+
+    let add = instance.exports.get_function("add").unwrap();
+    assert_eq!(
+        add.call(&[Value::I32(1), Value::I32(2)]).unwrap().to_vec(),
+        vec![Value::I32(3)]
+    );
+    assert!(add.call(&[Value::I32(1)]).is_err());
+}
+
+#[wasm_bindgen_test]
+fn host_function_and_memory_are_imported() {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+(module
+  (import "env" "double" (func $double (param i64) (result i64)))
+  (import "env" "memory" (memory 1))
+  (func (export "run") (param i64) (result i64)
+    i32.const 0
+    i32.const 42
+    i32.store8
+    local.get 0
+    call $double))
+"#,
+    )
+    .unwrap();
+    assert_eq!(module.imports().len(), 2);
+    assert_eq!(module.exports().len(), 1);
+
+    let double = Function::new(
+        &store,
+        FunctionType::new(vec![Type::I64], vec![Type::I64]),
+        |args| Ok(vec![Value::I64(args[0].unwrap_i64() * 2)]),
+    );
+    let memory = Memory::new(&store, MemoryType::new(1, None, false)).unwrap();
+    let import_object = imports! {
+        "env" => {
+            "double" => double,
+            "memory" => memory.clone(),
+        },
+    };
+    let instance = Instance::new(&module, &import_object).unwrap();
+
+    let run = instance.exports.get_function("run").unwrap();
+    assert_eq!(
+        run.call(&[Value::I64(1 << 40)]).unwrap().to_vec(),
+        vec![Value::I64(1 << 41)]
+    );
+    let mut byte = [0];
+    memory.read(0, &mut byte).unwrap();
+    assert_eq!(byte, [42]);
+
+    assert_eq!(memory.grow(1).unwrap(), Pages(1));
+    assert_eq!(memory.size(), Pages(2));
+}
+
+#[wasm_bindgen_test]
+fn missing_import_is_a_link_error() {
+    let store = Store::default();
+    let module = Module::new(&store, r#"(module (import "env" "f" (func)))"#).unwrap();
+
+    match Instance::new(&module, &imports! {}) {
+        Err(InstantiationError::Link(_)) => {}
+        result => panic!("expected a link error, got {:?}", result),
+    }
+}
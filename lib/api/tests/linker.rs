@@ -0,0 +1,128 @@
+#![cfg(not(feature = "js"))]
+
+use anyhow::Result;
+use wasmer::*;
+
+const MATH: &str = r#"
+(module
+  (func (export "double") (param i32) (result i32)
+    local.get 0
+    i32.const 2
+    i32.mul))
+"#;
+
+const APP: &str = r#"
+(module
+  (import "math" "double" (func $double (param i32) (result i32)))
+  (import "env" "log" (func $log (param i32)))
+  (func (export "run") (result i32)
+    i32.const 21
+    call $double)
+  (func (export "log") (param i32)
+    local.get 0
+    call $log))
+"#;
+
+#[test]
+fn linker_wires_instances() -> Result<()> {
+    let store = Store::default();
+    let math = Module::new(&store, MATH)?;
+    let app = Module::new(&store, APP)?;
+
+    let mut linker = ModuleLinker::new(&store);
+    linker.module("math", &math)?;
+    assert!(linker.instantiate(&app).is_err());
+
+    linker.define_unknown_imports_as_traps(&app)?;
+    let instance = linker.instantiate(&app)?;
+    let run = instance.exports.get_native_function::<(), i32>("run")?;
+    assert_eq!(run.call()?, 42);
+    let log = instance.exports.get_native_function::<i32, ()>("log")?;
+    let err = log.call(1).unwrap_err();
+    assert!(err.message().contains("`env`.`log`"));
+
+    Ok(())
+}
+
+#[test]
+fn linker_shadowing() -> Result<()> {
+    let store = Store::default();
+    let mut linker = ModuleLinker::new(&store);
+    let one = Global::new(&store, Value::I32(1));
+    let two = Global::new(&store, Value::I32(2));
+
+    linker.define("env", "g", one)?;
+    assert!(matches!(
+        linker.define("env", "g", two.clone()),
+        Err(LinkerError::AlreadyDefined { .. })
+    ));
+    linker.allow_shadowing(true).define("env", "g", two)?;
+    match linker.get("env", "g") {
+        Some(Extern::Global(global)) => assert_eq!(global.get().unwrap_i32(), 2),
+        _ => panic!("`env`.`g` isn't a global"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn linker_instantiates_dependency_graph() -> Result<()> {
+    let store = Store::default();
+    let math = Module::new(&store, MATH)?;
+    let app = Module::new(&store, APP)?;
+    let mut linker = ModuleLinker::new(&store);
+    linker.define("env", "log", Function::new_native(&store, |_: i32| {}))?;
+
+    let instances = linker.instantiate_all(vec![("app", &app), ("math", &math)])?;
+    let names = instances
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["math", "app"]);
+    assert!(linker.get("app", "run").is_some());
+
+    let cyclic = Module::new(
+        &store,
+        r#"(module (import "cyclic" "f" (func)) (import "other" "f" (func)) (func (export "f")))"#,
+    )?;
+    let other = Module::new(
+        &store,
+        r#"(module (import "cyclic" "f" (func)) (func (export "f")))"#,
+    )?;
+    let mut linker = ModuleLinker::new(&store);
+    assert!(matches!(
+        linker.instantiate_all(vec![("cyclic", &cyclic), ("other", &other)]),
+        Err(LinkerError::Cycle(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn stub_resolver() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+(module
+  (import "env" "optional" (func $optional (param i32) (result i32)))
+  (import "env" "flags" (global $flags i32))
+  (import "env" "table" (table 2 funcref))
+  (func (export "flags") (result i32)
+    global.get $flags)
+  (func (export "call") (result i32)
+    i32.const 0
+    call $optional))
+"#,
+    )?;
+
+    assert!(Instance::new(&module, &ImportObject::new()).is_err());
+    let instance = Instance::new(&module, &StubResolver::new(&module, ImportObject::new()))?;
+    let flags = instance.exports.get_native_function::<(), i32>("flags")?;
+    assert_eq!(flags.call()?, 0);
+    let call = instance.exports.get_native_function::<(), i32>("call")?;
+    let err = call.call().unwrap_err();
+    assert!(err.message().contains("`env`.`optional`"));
+
+    Ok(())
+}
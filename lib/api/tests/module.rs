@@ -1,3 +1,5 @@
+#![cfg(not(feature = "js"))]
+
 use anyhow::Result;
 use wasmer::*;
 
@@ -246,3 +248,82 @@ fn calling_host_functions_with_negative_values_works() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn names_and_custom_sections() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+(module
+  (import "env" "log" (func $log (param i32)))
+  (func $add (param $a i32) (param $b i32) (result i32) (local $sum i32)
+    local.get $a
+    local.get $b
+    i32.add))
+"#,
+    )?;
+    assert_eq!(module.function_name(1), Some("add"));
+    assert_eq!(module.function_names(), vec![(0, "log"), (1, "add")]);
+    assert_eq!(module.local_names(1), vec![(0, "a"), (1, "b"), (2, "sum")]);
+    assert!(module.producers().is_none());
+
+    // A module with a `producers` section and another custom section.
+    let string = |bytes: &mut Vec<u8>, s: &str| {
+        bytes.push(s.len() as u8);
+        bytes.extend_from_slice(s.as_bytes());
+    };
+    let mut producers = vec![1];
+    string(&mut producers, "language");
+    producers.push(1);
+    string(&mut producers, "Rust");
+    string(&mut producers, "1.50");
+    let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+    for (name, payload) in [("producers", &producers[..]), ("extra", &b"data"[..])].iter() {
+        let mut section = vec![];
+        string(&mut section, name);
+        section.extend_from_slice(payload);
+        wasm.push(0);
+        wasm.push(section.len() as u8);
+        wasm.extend(section);
+    }
+    let module = Module::new(&store, wasm)?;
+    assert_eq!(
+        module.producers(),
+        Some(vec![ProducersField {
+            name: "language".to_string(),
+            values: vec![ProducersFieldValue {
+                name: "Rust".to_string(),
+                version: "1.50".to_string(),
+            }],
+        }])
+    );
+    let sections = module
+        .all_custom_sections()
+        .map(|(name, data)| (name.to_string(), data.to_vec()))
+        .collect::<Vec<_>>();
+    assert_eq!(sections[1], ("extra".to_string(), b"data".to_vec()));
+
+    Ok(())
+}
+
+#[test]
+fn serialize_with_metadata() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(&store, "(module (func (export \"f\")))")?;
+    let mut metadata = ArtifactMetadata::new();
+    metadata.insert("build-id".to_string(), "1234".to_string());
+    metadata.insert("tenant".to_string(), "acme".to_string());
+
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("module.bin");
+    module.serialize_to_file_with_metadata(&path, &metadata)?;
+    assert_eq!(Module::read_metadata(&path)?, metadata);
+    let deserialized = unsafe { Module::deserialize_from_file(&store, &path)? };
+    assert_eq!(deserialized.exports().count(), 1);
+
+    module.serialize_to_file(&path)?;
+    assert!(Module::read_metadata(&path)?.is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,459 @@
+#![cfg(not(feature = "js"))]
+
+use anyhow::Result;
+use std::sync::{Arc, Mutex};
+use wasmer::*;
+
+#[test]
+fn call_hook() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+    (module
+      (import "host" "double" (func $double (param i32) (result i32)))
+      (import "host" "log" (func $log (param i32)))
+      (func (export "run") (param i32) (result i32) (local i32)
+        (local.set 1 (call $double (local.get 0)))
+        (call $log (local.get 1))
+        (local.get 1)))
+"#,
+    )?;
+
+    fn double(x: i32) -> i32 {
+        x * 2
+    }
+    let log = Function::new(&store, FunctionType::new(vec![Type::I32], vec![]), |_| {
+        Ok(vec![])
+    });
+    let instance = Instance::new(
+        &module,
+        &imports! {
+            "host" => {
+                "double" => Function::new_native(&store, double),
+                "log" => log,
+            }
+        },
+    )?;
+
+    let events = Arc::new(Mutex::new(vec![]));
+    let recorded = events.clone();
+    store.set_call_hook(
+        CallHook::new(move |event| {
+            recorded.lock().unwrap().push((
+                event.kind(),
+                event.name().map(str::to_string),
+                event
+                    .values()
+                    .map(|values| values.iter().map(Value::unwrap_i32).collect::<Vec<_>>()),
+            ));
+        })
+        .capture_values(true),
+    );
+    let run = instance.exports.get_native_function::<i32, i32>("run")?;
+    assert_eq!(run.call(3)?, 6);
+
+    let events = events.lock().unwrap();
+    let double_name = events[1].1.clone();
+    assert!(double_name.as_ref().unwrap().ends_with("double"));
+    assert_eq!(
+        *events,
+        vec![
+            (
+                CallHookKind::CallingWasm,
+                Some("run".to_string()),
+                Some(vec![3])
+            ),
+            (
+                CallHookKind::CallingHost,
+                double_name.clone(),
+                Some(vec![3])
+            ),
+            (CallHookKind::ReturningFromHost, double_name, Some(vec![6])),
+            (CallHookKind::CallingHost, None, Some(vec![6])),
+            (CallHookKind::ReturningFromHost, None, Some(vec![])),
+            (
+                CallHookKind::ReturningFromWasm,
+                Some("run".to_string()),
+                Some(vec![6])
+            ),
+        ]
+    );
+    drop(events);
+
+    // The hook is shared by the clones of the store.
+    let count = Arc::new(Mutex::new(0));
+    let counted = count.clone();
+    instance
+        .store()
+        .set_call_hook(CallHook::new(move |_| *counted.lock().unwrap() += 1));
+    run.call(3)?;
+    assert_eq!(*count.lock().unwrap(), 6);
+
+    store.clear_call_hook();
+    run.call(3)?;
+    assert_eq!(*count.lock().unwrap(), 6);
+
+    Ok(())
+}
+
+#[test]
+fn event_log() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+    (module $test
+      (import "host" "fail" (func $fail))
+      (memory 1 2)
+      (func (export "grow") (param i32) (result i32)
+        (memory.grow (local.get 0)))
+      (func (export "fail")
+        (call $fail)))
+"#,
+    )?;
+
+    fn fail() -> Result<(), RuntimeError> {
+        Err(RuntimeError::new("oops"))
+    }
+    let import_object = imports! {
+        "host" => {
+            "fail" => Function::new_native(&store, fail),
+        },
+    };
+
+    // Nothing is logged until the log is enabled.
+    Instance::new(&module, &import_object)?;
+    assert!(store.recent_events().is_empty());
+
+    store.enable_event_log(4);
+    let instance = Instance::new(&module, &import_object)?;
+    let grow: NativeFunc<i32, i32> = instance.exports.get_native_function("grow")?;
+    assert_eq!(grow.call(1)?, 1);
+    assert_eq!(grow.call(1)?, -1);
+    let fail: NativeFunc<(), ()> = instance.exports.get_native_function("fail")?;
+    assert!(fail.call().is_err());
+
+    // The ring buffer only keeps the last 4 events.
+    let events = store.recent_events();
+    assert_eq!(events.len(), 4);
+    assert_eq!(
+        events[0],
+        StoreEvent::MemoryGrown {
+            from: Pages(1),
+            to: Pages(2),
+        }
+    );
+    assert!(matches!(
+        events[1],
+        StoreEvent::MemoryGrowFailed {
+            delta: Pages(1),
+            ..
+        }
+    ));
+    assert_eq!(
+        events[2],
+        StoreEvent::HostCallFailed {
+            message: "oops".to_string(),
+        }
+    );
+    assert_eq!(
+        events[3],
+        StoreEvent::Trapped {
+            message: "oops".to_string(),
+        }
+    );
+    assert!(store.debug_dump().contains("host function failed: oops"));
+
+    store.enable_event_log(4);
+    Instance::new(&module, &import_object)?;
+    assert_eq!(
+        store.recent_events(),
+        vec![StoreEvent::Instantiated {
+            module: Some("test".to_string()),
+        }]
+    );
+
+    store.disable_event_log();
+    assert!(store.recent_events().is_empty());
+    Ok(())
+}
+
+#[test]
+fn debug_dump_handler() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+    (module
+      (import "host" "panic" (func $panic))
+      (func (export "trap")
+        unreachable)
+      (func (export "panic")
+        (call $panic)))
+"#,
+    )?;
+    let import_object = imports! {
+        "host" => {
+            "panic" => Function::new_native(&store, || panic!("boom")),
+        },
+    };
+    let instance = Instance::new(&module, &import_object)?;
+    let trap: NativeFunc<(), ()> = instance.exports.get_native_function("trap")?;
+    let panic: NativeFunc<(), ()> = instance.exports.get_native_function("panic")?;
+
+    let dumps = Arc::new(Mutex::new(vec![]));
+    let handled = dumps.clone();
+    store.set_debug_dump_handler(move |dump| handled.lock().unwrap().push(dump.to_string()));
+
+    // The handler isn't called while the log is disabled.
+    assert!(trap.call().is_err());
+    assert!(dumps.lock().unwrap().is_empty());
+
+    store.enable_event_log(4);
+    assert!(trap.call().is_err());
+    assert!(std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| panic.call())).is_err());
+    {
+        let dumps = dumps.lock().unwrap();
+        assert_eq!(dumps.len(), 2);
+        assert!(dumps[0].contains("trapped: unreachable"));
+        assert!(dumps[1].contains("host function panicked: boom"));
+    }
+
+    store.clear_debug_dump_handler();
+    assert!(trap.call().is_err());
+    assert_eq!(dumps.lock().unwrap().len(), 2);
+    Ok(())
+}
+
+#[test]
+fn labels() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+    (module
+      (import "host" "log" (func $log))
+      (func (export "run")
+        (call $log))
+      (func (export "fail")
+        unreachable))
+"#,
+    )?;
+    let import_object = imports! {
+        "host" => {
+            "log" => Function::new_native(&store, || {}),
+        },
+    };
+    store.set_label("tenant", "acme");
+    store.set_label("region", "eu");
+    store.enable_event_log(4);
+    let first = Instance::new(&module, &import_object)?;
+    let second = Instance::new(&module, &import_object)?;
+    second.set_label("tenant", "initech");
+
+    assert_eq!(first.labels()["tenant"], "acme");
+    assert_eq!(second.labels()["tenant"], "initech");
+    assert_eq!(second.labels()["region"], "eu");
+
+    let labels = Arc::new(Mutex::new(vec![]));
+    let recorded = labels.clone();
+    store.set_call_hook(CallHook::new(move |event| {
+        recorded
+            .lock()
+            .unwrap()
+            .push((event.kind(), event.labels().get("tenant").cloned()));
+    }));
+    second.exports.get_function("run")?.call(&[])?;
+    assert_eq!(
+        *labels.lock().unwrap(),
+        vec![
+            (CallHookKind::CallingWasm, Some("initech".to_string())),
+            (CallHookKind::CallingHost, Some("initech".to_string())),
+            (CallHookKind::ReturningFromHost, Some("initech".to_string())),
+            (CallHookKind::ReturningFromWasm, Some("initech".to_string())),
+        ]
+    );
+    store.clear_call_hook();
+
+    let fail: NativeFunc<(), ()> = second.exports.get_native_function("fail")?;
+    let error = fail.call().unwrap_err();
+    assert_eq!(error.labels()["tenant"], "initech");
+    assert!(error
+        .to_string()
+        .starts_with("RuntimeError: unreachable {region=eu, tenant=initech}"));
+    let error = first.exports.get_function("fail")?.call(&[]).unwrap_err();
+    assert_eq!(error.labels()["tenant"], "acme");
+
+    let dump = store.debug_dump();
+    assert!(dump.starts_with("labels: region=eu, tenant=acme\n"));
+    assert!(dump.contains("{region=eu, tenant=initech}"));
+
+    assert_eq!(second.remove_label("tenant"), Some("initech".to_string()));
+    assert_eq!(second.labels()["tenant"], "acme");
+    assert_eq!(store.remove_label("region"), Some("eu".to_string()));
+    assert!(!second.labels().contains_key("region"));
+    Ok(())
+}
+
+#[test]
+fn modules_and_instances() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+    (module $counter
+      (memory 2)
+      (data (i32.const 0) "hello"))
+"#,
+    )?;
+    let mut unnamed = Module::new(&store, "(module)")?;
+    unnamed.set_name("renamed");
+    assert_eq!(store.modules().count(), 2);
+
+    let first = Instance::new(&module, &imports! {})?;
+    let second = Instance::new(&module, &imports! {})?;
+    second.set_label("tenant", "acme");
+
+    let modules = store.modules().collect::<Vec<_>>();
+    assert_eq!(modules[0].name.as_deref(), Some("counter"));
+    assert_eq!(modules[0].data_size, Bytes(5));
+    assert_eq!(modules[0].instances, 2);
+    assert_eq!(modules[1].name.as_deref(), Some("renamed"));
+    assert_eq!(modules[1].instances, 0);
+    assert!(modules[0].created <= modules[1].created);
+
+    let instances = store.instances().collect::<Vec<_>>();
+    assert_eq!(instances.len(), 2);
+    assert_eq!(instances[0].module.as_deref(), Some("counter"));
+    assert_eq!(instances[0].memory_size, Bytes::from(Pages(2)));
+    assert!(!instances[0].is_executing);
+    assert_eq!(instances[1].labels["tenant"], "acme");
+
+    drop(first);
+    drop(unnamed);
+    assert_eq!(store.instances().count(), 1);
+    let modules = store.modules().collect::<Vec<_>>();
+    assert_eq!(modules.len(), 1);
+    assert_eq!(modules[0].instances, 1);
+
+    drop(second);
+    drop(module);
+    assert_eq!(store.modules().count(), 0);
+    assert_eq!(store.instances().count(), 0);
+    Ok(())
+}
+
+#[test]
+fn limits() -> Result<()> {
+    let store = Store::default();
+    store.set_limits(
+        StoreLimits::new()
+            .instances(1)
+            .memories(1)
+            .memory_pages(Pages(2))
+            .table_elements(10),
+    );
+    assert_eq!(store.limits().max_instances(), Some(1));
+    assert_eq!(store.limits().max_tables(), None);
+
+    let module = Module::new(&store, "(module (memory (export \"memory\") 1))")?;
+    let instance = Instance::new(&module, &imports! {})?;
+
+    // The instance and its memory reach the limits.
+    match Instance::new(&module, &imports! {}) {
+        Err(InstantiationError::Link(LinkError::Resource(message))) => {
+            assert!(message.contains("instances"), "{}", message)
+        }
+        result => panic!("unexpected result: {:?}", result),
+    }
+    assert!(Memory::new(&store, MemoryType::new(1, None, false)).is_err());
+
+    // Dropping them frees their slots.
+    drop(instance);
+    assert!(Memory::new(&store, MemoryType::new(3, None, false)).is_err());
+    let _memory = Memory::new(&store, MemoryType::new(2, None, false))?;
+    assert!(Table::new(
+        &store,
+        TableType::new(ValType::FuncRef, 11, None),
+        Val::FuncRef(Function::new_native(&store, || {}))
+    )
+    .is_err());
+
+    let module = Module::new(&store, "(module (memory 1))")?;
+    match Instance::new(&module, &imports! {}) {
+        Err(InstantiationError::Link(LinkError::Resource(message))) => {
+            assert!(message.contains("memories"), "{}", message)
+        }
+        result => panic!("unexpected result: {:?}", result),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn limits_are_released_on_failure() -> Result<()> {
+    let store = Store::default();
+    store.set_limits(
+        StoreLimits::new()
+            .instances(1)
+            .memories(1)
+            .table_elements(10),
+    );
+
+    // The table fails once the instance and its memory are reserved.
+    let module = Module::new(&store, "(module (memory 1) (table 20 funcref))")?;
+    match Instance::new(&module, &imports! {}) {
+        Err(InstantiationError::Link(LinkError::Resource(message))) => {
+            assert!(message.contains("table"), "{}", message)
+        }
+        result => panic!("unexpected result: {:?}", result),
+    }
+
+    let module = Module::new(&store, "(module (memory 1))")?;
+    let _instance = Instance::new(&module, &imports! {})?;
+
+    Ok(())
+}
+
+#[test]
+fn limits_across_threads() -> Result<()> {
+    let store = Store::default();
+    store.set_limits(StoreLimits::new().memories(4));
+
+    let threads = (0..16)
+        .map(|_| {
+            let store = store.clone();
+            std::thread::spawn(move || Memory::new(&store, MemoryType::new(1, None, false)).ok())
+        })
+        .collect::<Vec<_>>();
+    let memories = threads
+        .into_iter()
+        .filter_map(|thread| thread.join().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(memories.len(), 4);
+
+    Ok(())
+}
+
+#[cfg(feature = "jit")]
+#[test]
+fn engine_tunables_config() -> Result<()> {
+    let engine = JIT::headless()
+        .tunables_config(TunablesConfig::new().static_memory_bound(Pages(0)).clone())
+        .engine();
+    let store = Store::new(&engine);
+    let memory = MemoryType::new(1, Some(2), false);
+    assert!(matches!(
+        store.tunables().memory_style(&memory),
+        vm::MemoryStyle::Dynamic { .. }
+    ));
+
+    // The configuration given to the store replaces the one of the engine.
+    let store = Store::new_with_tunables_config(&engine, &TunablesConfig::new());
+    assert!(matches!(
+        store.tunables().memory_style(&memory),
+        vm::MemoryStyle::Static { .. }
+    ));
+    Ok(())
+}
@@ -39,6 +39,9 @@ const WASI_FEATURE_AS_C_DEFINE: &'static str = "WASMER_WASI_ENABLED";
 #[allow(unused)]
 const EMSCRIPTEN_FEATURE_AS_C_DEFINE: &'static str = "WASMER_EMSCRIPTEN_ENABLED";
 
+#[allow(unused)]
+const MIDDLEWARES_FEATURE_AS_C_DEFINE: &'static str = "WASMER_MIDDLEWARES_ENABLED";
+
 macro_rules! map_feature_as_c_define {
     ($feature:expr, $c_define:ident, $accumulator:ident) => {
         #[cfg(feature = $feature)]
@@ -87,6 +90,7 @@ fn build_wasm_c_api_headers(crate_dir: &str, out_dir: &str) {
     map_feature_as_c_define!("compiler", COMPILER_FEATURE_AS_C_DEFINE, pre_header);
     map_feature_as_c_define!("wasi", WASI_FEATURE_AS_C_DEFINE, pre_header);
     map_feature_as_c_define!("emscripten", EMSCRIPTEN_FEATURE_AS_C_DEFINE, pre_header);
+    map_feature_as_c_define!("middlewares", MIDDLEWARES_FEATURE_AS_C_DEFINE, pre_header);
 
     add_wasmer_version(&mut pre_header);
 
@@ -237,6 +241,7 @@ fn new_builder(language: Language, crate_dir: &str, include_guard: &str, header:
         .with_define("feature", "compiler", COMPILER_FEATURE_AS_C_DEFINE)
         .with_define("feature", "wasi", WASI_FEATURE_AS_C_DEFINE)
         .with_define("feature", "emscripten", EMSCRIPTEN_FEATURE_AS_C_DEFINE)
+        .with_define("feature", "middlewares", MIDDLEWARES_FEATURE_AS_C_DEFINE)
 }
 
 /// Exclude types and functions from the `deprecated` API.
@@ -384,15 +389,24 @@ fn exclude_items_from_deprecated(builder: Builder) -> Builder {
 /// ones from the WASI API.
 fn exclude_items_from_wasm_c_api(builder: Builder) -> Builder {
     builder
+        .exclude_item("WASI_DIR_CREATE")
+        .exclude_item("WASI_DIR_DENY_DELETE")
+        .exclude_item("WASI_DIR_READ")
+        .exclude_item("WASI_DIR_WRITE")
         .exclude_item("wasi_config_arg")
+        .exclude_item("wasi_config_capture_stderr")
+        .exclude_item("wasi_config_capture_stdout")
         .exclude_item("wasi_config_env")
         .exclude_item("wasi_config_mapdir")
+        .exclude_item("wasi_config_mapdir_with_flags")
+        .exclude_item("wasi_config_stdin_bytes")
         .exclude_item("wasi_config_preopen_dir")
         .exclude_item("wasi_config_inherit_stderr")
         .exclude_item("wasi_config_inherit_stdin")
         .exclude_item("wasi_config_inherit_stdout")
         .exclude_item("wasi_config_new")
         .exclude_item("wasi_config_t")
+        .exclude_item("wasi_dir_flags_t")
         .exclude_item("wasi_env_delete")
         .exclude_item("wasi_env_new")
         .exclude_item("wasi_env_read_stderr")
@@ -400,17 +414,47 @@ fn exclude_items_from_wasm_c_api(builder: Builder) -> Builder {
         .exclude_item("wasi_env_set_instance")
         .exclude_item("wasi_env_set_memory")
         .exclude_item("wasi_env_t")
+        .exclude_item("wasi_env_write_stdin")
         .exclude_item("wasi_get_imports")
         .exclude_item("wasi_get_imports_inner")
         .exclude_item("wasi_get_start_function")
         .exclude_item("wasi_get_wasi_version")
+        .exclude_item("wasi_output_callback_t")
         .exclude_item("wasi_version_t")
+        .exclude_item("wasm_config_push_middleware")
         .exclude_item("wasm_config_set_compiler")
         .exclude_item("wasm_config_set_engine")
+        .exclude_item("wasm_config_set_headless")
+        .exclude_item("wasm_config_set_target")
+        .exclude_item("wasm_module_deserialize_from_file")
         .exclude_item("wasm_module_name")
+        .exclude_item("wasm_module_serialize_to_file")
         .exclude_item("wasm_module_set_name")
         .exclude_item("wasmer_compiler_t")
+        .exclude_item("wasmer_cpu_features_add")
+        .exclude_item("wasmer_cpu_features_delete")
+        .exclude_item("wasmer_cpu_features_new")
+        .exclude_item("wasmer_cpu_features_t")
         .exclude_item("wasmer_engine_t")
+        .exclude_item("wasmer_metering_as_middleware")
+        .exclude_item("wasmer_metering_cost_function_t")
+        .exclude_item("wasmer_metering_cost_table_default")
+        .exclude_item("wasmer_metering_cost_table_t")
+        .exclude_item("wasmer_metering_delete")
+        .exclude_item("wasmer_metering_get_remaining_points")
+        .exclude_item("wasmer_metering_new")
+        .exclude_item("wasmer_metering_new_with_cost_table")
+        .exclude_item("wasmer_metering_points_are_exhausted")
+        .exclude_item("wasmer_metering_set_remaining_points")
+        .exclude_item("wasmer_metering_t")
+        .exclude_item("wasmer_middleware_t")
+        .exclude_item("wasmer_target_delete")
+        .exclude_item("wasmer_target_new")
+        .exclude_item("wasmer_target_t")
+        .exclude_item("wasmer_triple_delete")
+        .exclude_item("wasmer_triple_new")
+        .exclude_item("wasmer_triple_new_from_host")
+        .exclude_item("wasmer_triple_t")
         .exclude_item("wat2wasm")
 }
 
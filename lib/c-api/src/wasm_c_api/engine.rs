@@ -1,7 +1,10 @@
+use super::target::wasmer_target_t;
 use crate::error::{update_last_error, CApiError};
 use cfg_if::cfg_if;
 use std::sync::Arc;
-use wasmer::Engine;
+use wasmer::{Engine, Target};
+#[cfg(feature = "middlewares")]
+use wasmer_compiler::ModuleMiddleware;
 #[cfg(feature = "jit")]
 use wasmer_engine_jit::JIT;
 #[cfg(feature = "native")]
@@ -93,6 +96,10 @@ pub struct wasm_config_t {
     engine: wasmer_engine_t,
     #[cfg(feature = "compiler")]
     compiler: wasmer_compiler_t,
+    headless: bool,
+    target: Option<Target>,
+    #[cfg(feature = "middlewares")]
+    pub(crate) middlewares: Vec<Arc<dyn ModuleMiddleware>>,
 }
 
 /// Create a new default Wasmer configuration.
@@ -213,6 +220,88 @@ pub extern "C" fn wasm_config_set_engine(config: &mut wasm_config_t, engine: was
     config.engine = engine;
 }
 
+/// Updates the configuration to create a headless engine: an engine
+/// without a compiler, which can only deserialize modules compiled
+/// and serialized beforehand.
+///
+/// A library built without the `compiler` feature only creates
+/// headless engines.
+///
+/// This is a Wasmer-specific function.
+///
+/// # Example
+///
+/// ```rust
+/// # use inline_c::assert_c;
+/// # fn main() {
+/// #    (assert_c! {
+/// # #include "tests/wasmer_wasm.h"
+/// #
+/// int main() {
+///     // Compile and serialize a module with the default engine.
+///     wasm_engine_t* engine = wasm_engine_new();
+///     wasm_store_t* store = wasm_store_new(engine);
+///
+///     wasm_byte_vec_t wat;
+///     wasmer_byte_vec_new_from_string(&wat, "(module (func (export \"function\")))");
+///     wasm_byte_vec_t wasm;
+///     wat2wasm(&wat, &wasm);
+///     wasm_module_t* module = wasm_module_new(store, &wasm);
+///     assert(module);
+///
+///     wasm_byte_vec_t serialized_module;
+///     wasm_module_serialize(module, &serialized_module);
+///     assert(serialized_module.size > 0);
+///
+///     // Create a headless engine.
+///     wasm_config_t* config = wasm_config_new();
+///     wasm_config_set_headless(config, true);
+///     wasm_engine_t* headless_engine = wasm_engine_new_with_config(config);
+///     assert(headless_engine);
+///     wasm_store_t* headless_store = wasm_store_new(headless_engine);
+///
+///     // It can't compile modules…
+///     assert(!wasm_module_new(headless_store, &wasm));
+///
+///     // … but it can deserialize them.
+///     wasm_module_t* deserialized_module = wasm_module_deserialize(headless_store, &serialized_module);
+///     assert(deserialized_module);
+///
+///     // Free everything.
+///     wasm_module_delete(deserialized_module);
+///     wasm_store_delete(headless_store);
+///     wasm_engine_delete(headless_engine);
+///     wasm_byte_vec_delete(&serialized_module);
+///     wasm_module_delete(module);
+///     wasm_byte_vec_delete(&wasm);
+///     wasm_byte_vec_delete(&wat);
+///     wasm_store_delete(store);
+///     wasm_engine_delete(engine);
+///
+///     return 0;
+/// }
+/// #    })
+/// #    .success();
+/// # }
+/// ```
+#[no_mangle]
+pub extern "C" fn wasm_config_set_headless(config: &mut wasm_config_t, headless: bool) {
+    config.headless = headless;
+}
+
+/// Updates the configuration to compile the modules for `target`
+/// instead of the host. It takes ownership of `target`.
+///
+/// This is a Wasmer-specific function.
+///
+/// # Example
+///
+/// See the [`target`][super::target] module's documentation.
+#[no_mangle]
+pub extern "C" fn wasm_config_set_target(config: &mut wasm_config_t, target: Box<wasmer_target_t>) {
+    config.target = Some(target.inner);
+}
+
 /// An engine is used by the store to drive the compilation and the
 /// execution of a WebAssembly module.
 ///
@@ -357,6 +446,55 @@ cfg_if! {
 #[no_mangle]
 pub unsafe extern "C" fn wasm_engine_delete(_engine: Option<Box<wasm_engine_t>>) {}
 
+/// Creates the engine of `config` without a compiler, or returns why
+/// it can't.
+#[allow(dead_code)]
+fn new_headless_engine(
+    config: &wasm_config_t,
+) -> Result<Arc<dyn Engine + Send + Sync>, &'static str> {
+    Ok(match config.engine {
+        wasmer_engine_t::JIT => {
+            cfg_if! {
+                if #[cfg(feature = "jit")] {
+                    let mut builder = JIT::headless();
+                    if let Some(target) = &config.target {
+                        builder = builder.target(target.clone());
+                    }
+                    Arc::new(builder.engine())
+                } else {
+                    return Err("Wasmer has not been compiled with the `jit` feature.");
+                }
+            }
+        }
+        wasmer_engine_t::NATIVE => {
+            cfg_if! {
+                if #[cfg(feature = "native")] {
+                    let mut builder = Native::headless();
+                    if let Some(target) = &config.target {
+                        builder = builder.target(target.clone());
+                    }
+                    Arc::new(builder.engine())
+                } else {
+                    return Err("Wasmer has not been compiled with the `native` feature.");
+                }
+            }
+        }
+        wasmer_engine_t::OBJECT_FILE => {
+            cfg_if! {
+                if #[cfg(feature = "object-file")] {
+                    let mut builder = ObjectFile::headless();
+                    if let Some(target) = &config.target {
+                        builder = builder.target(target.clone());
+                    }
+                    Arc::new(builder.engine())
+                } else {
+                    return Err("Wasmer has not been compiled with the `object-file` feature.");
+                }
+            }
+        }
+    })
+}
+
 /// Creates an engine with a particular configuration.
 ///
 /// # Example
@@ -382,6 +520,13 @@ pub extern "C" fn wasm_engine_new_with_config(
 
     cfg_if! {
         if #[cfg(feature = "compiler")] {
+            if config.headless {
+                return match new_headless_engine(&config) {
+                    Ok(inner) => Some(Box::new(wasm_engine_t { inner })),
+                    Err(msg) => return_with_error(msg),
+                };
+            }
+
             #[allow(unused_mut)]
             let mut compiler_config: Box<dyn CompilerConfig> = match config.compiler {
                 wasmer_compiler_t::CRANELIFT => {
@@ -413,11 +558,20 @@ pub extern "C" fn wasm_engine_new_with_config(
                 },
             };
 
+            #[cfg(feature = "middlewares")]
+            for middleware in config.middlewares.iter() {
+                compiler_config.push_middleware(middleware.clone());
+            }
+
             let inner: Arc<dyn Engine + Send + Sync> = match config.engine {
                 wasmer_engine_t::JIT => {
                     cfg_if! {
                         if #[cfg(feature = "jit")] {
-                            Arc::new(JIT::new(compiler_config).engine())
+                            let mut builder = JIT::new(compiler_config);
+                            if let Some(target) = config.target {
+                                builder = builder.target(target);
+                            }
+                            Arc::new(builder.engine())
                         } else {
                             return return_with_error("Wasmer has not been compiled with the `jit` feature.");
                         }
@@ -426,56 +580,31 @@ pub extern "C" fn wasm_engine_new_with_config(
                 wasmer_engine_t::NATIVE => {
                     cfg_if! {
                         if #[cfg(feature = "native")] {
-                            Arc::new(Native::new(compiler_config).engine())
+                            let mut builder = Native::new(compiler_config);
+                            if let Some(target) = config.target {
+                                builder = builder.target(target);
+                            }
+                            Arc::new(builder.engine())
                         } else {
                             return return_with_error("Wasmer has not been compiled with the `native` feature.");
                         }
                     }
                 },
                 wasmer_engine_t::OBJECT_FILE => {
-                    cfg_if! {
-                        // There are currently no uses of the object-file engine + compiler from the C API.
-                        // So we run in headless mode.
-                        if #[cfg(feature = "object-file")] {
-                            Arc::new(ObjectFile::headless().engine())
-                        } else {
-                            return return_with_error("Wasmer has not been compiled with the `object-file` feature.");
-                        }
+                    // There are currently no uses of the object-file engine + compiler from the C API.
+                    // So we run in headless mode.
+                    match new_headless_engine(&config) {
+                        Ok(inner) => inner,
+                        Err(msg) => return return_with_error(msg),
                     }
                 },
             };
             Some(Box::new(wasm_engine_t { inner }))
         } else {
-            let inner: Arc<dyn Engine + Send + Sync> = match config.engine {
-                wasmer_engine_t::JIT => {
-                    cfg_if! {
-                        if #[cfg(feature = "jit")] {
-                            Arc::new(JIT::headless().engine())
-                        } else {
-                            return return_with_error("Wasmer has not been compiled with the `jit` feature.");
-                        }
-                    }
-                },
-                wasmer_engine_t::NATIVE => {
-                    cfg_if! {
-                        if #[cfg(feature = "native")] {
-                            Arc::new(Native::headless().engine())
-                        } else {
-                            return return_with_error("Wasmer has not been compiled with the `native` feature.");
-                        }
-                    }
-                },
-                wasmer_engine_t::OBJECT_FILE => {
-                    cfg_if! {
-                        if #[cfg(feature = "object-file")] {
-                            Arc::new(ObjectFile::headless().engine())
-                        } else {
-                            return return_with_error("Wasmer has not been compiled with the `object-file` feature.");
-                        }
-                    }
-                },
-            };
-            Some(Box::new(wasm_engine_t { inner }))
+            match new_headless_engine(&config) {
+                Ok(inner) => Some(Box::new(wasm_engine_t { inner })),
+                Err(msg) => return_with_error(msg),
+            }
         }
     }
 }
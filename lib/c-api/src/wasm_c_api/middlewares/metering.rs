@@ -0,0 +1,361 @@
+//! Wasmer-specific API to meter the execution of the instances, and
+//! to put a limit on it, like gas.
+//!
+//! The cost of each operator is given either by a C callback, or by a
+//! [`wasmer_metering_cost_table_t`] with a cost per class of
+//! operators. When the points of an instance are exhausted, it traps.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use inline_c::assert_c;
+//! # fn main() {
+//! #    (assert_c! {
+//! # #include "tests/wasmer_wasm.h"
+//! #
+//! int main() {
+//!     // Each operator costs 1 point, and each instance has 10 points.
+//!     wasmer_metering_cost_table_t cost_table;
+//!     wasmer_metering_cost_table_default(&cost_table);
+//!     wasmer_metering_t* metering = wasmer_metering_new_with_cost_table(10, &cost_table);
+//!
+//!     wasm_config_t* config = wasm_config_new();
+//!     wasm_config_push_middleware(config, wasmer_metering_as_middleware(metering));
+//!     wasm_engine_t* engine = wasm_engine_new_with_config(config);
+//!     wasm_store_t* store = wasm_store_new(engine);
+//!
+//!     wasm_byte_vec_t wat;
+//!     wasmer_byte_vec_new_from_string(
+//!         &wat,
+//!         "(module\n"
+//!         "  (func (export \"spin\") (param i32)\n"
+//!         "    (loop\n"
+//!         "      local.get 0\n"
+//!         "      i32.const 1\n"
+//!         "      i32.sub\n"
+//!         "      local.tee 0\n"
+//!         "      br_if 0)))"
+//!     );
+//!     wasm_byte_vec_t wasm;
+//!     wat2wasm(&wat, &wasm);
+//!     wasm_module_t* module = wasm_module_new(store, &wasm);
+//!     assert(module);
+//!
+//!     wasm_extern_vec_t imports = WASM_EMPTY_VEC;
+//!     wasm_trap_t* traps = NULL;
+//!     wasm_instance_t* instance = wasm_instance_new(store, module, &imports, &traps);
+//!     assert(instance);
+//!
+//!     wasm_extern_vec_t exports;
+//!     wasm_instance_exports(instance, &exports);
+//!     const wasm_func_t* spin = wasm_extern_as_func(exports.data[0]);
+//!     assert(spin);
+//!
+//!     // One iteration fits in the limit.
+//!     wasm_val_t arguments[1] = { WASM_I32_VAL(1) };
+//!     wasm_val_vec_t arguments_as_array = WASM_ARRAY_VEC(arguments);
+//!     wasm_val_vec_t results = WASM_EMPTY_VEC;
+//!     assert(wasm_func_call(spin, &arguments_as_array, &results) == NULL);
+//!     assert(!wasmer_metering_points_are_exhausted(instance));
+//!     assert(wasmer_metering_get_remaining_points(instance) < 10);
+//!
+//!     // A hundred iterations don't.
+//!     arguments[0] = (wasm_val_t) WASM_I32_VAL(100);
+//!     wasm_trap_t* trap = wasm_func_call(spin, &arguments_as_array, &results);
+//!     assert(trap != NULL);
+//!     assert(wasmer_metering_points_are_exhausted(instance));
+//!
+//!     // Until more points are given.
+//!     wasmer_metering_set_remaining_points(instance, 1000);
+//!     assert(wasm_func_call(spin, &arguments_as_array, &results) == NULL);
+//!
+//!     wasm_trap_delete(trap);
+//!     wasm_extern_vec_delete(&exports);
+//!     wasm_instance_delete(instance);
+//!     wasm_module_delete(module);
+//!     wasm_byte_vec_delete(&wasm);
+//!     wasm_byte_vec_delete(&wat);
+//!     wasm_store_delete(store);
+//!     wasm_engine_delete(engine);
+//!
+//!     return 0;
+//! }
+//! #    })
+//! #    .success();
+//! # }
+//! ```
+
+use super::super::instance::wasm_instance_t;
+use super::wasmer_middleware_t;
+use crate::error::{update_last_error, CApiError};
+use std::ffi::CString;
+use std::os::raw::{c_char, c_void};
+use std::sync::Arc;
+use wasmer::wasmparser::Operator;
+use wasmer::Instance;
+use wasmer_middlewares::metering::{
+    get_remaining_points, set_remaining_points, CostTable, MeteringPoints, OperatorCost,
+};
+use wasmer_middlewares::Metering;
+
+/// The callback giving the cost of an operator in points, with the
+/// `data` given along with it.
+///
+/// The operator is named after its variant of the `Operator` enum of
+/// `wasmparser`, e.g. `I32Add` or `LocalGet`.
+#[allow(non_camel_case_types)]
+pub type wasmer_metering_cost_function_t =
+    unsafe extern "C" fn(operator_name: *const c_char, data: *mut c_void) -> u64;
+
+/// The costs of the operators by class, in points.
+///
+/// This is a Wasmer-specific type with Wasmer-specific functions for
+/// manipulating it.
+#[derive(Debug, Clone, Copy)]
+#[repr(C)]
+#[allow(non_camel_case_types)]
+pub struct wasmer_metering_cost_table_t {
+    /// Constants, e.g. `i32.const` or `ref.null`.
+    pub constant: u64,
+    /// Accesses to the locals and the globals.
+    pub variable: u64,
+    /// Blocks, branches, and the parametric operators `drop` and `select`.
+    pub control: u64,
+    /// Direct and indirect calls, including tail calls.
+    pub call: u64,
+    /// Loads and stores.
+    pub memory_access: u64,
+    /// `memory.size`, `memory.grow`, and the bulk memory and table
+    /// operators, e.g. `memory.copy` or `table.init`.
+    pub memory_management: u64,
+    /// Each page requested by a `memory.grow`.
+    pub memory_grow_page: u64,
+    /// Every other operator: the numeric, reference, SIMD and atomic
+    /// operators.
+    pub numeric: u64,
+}
+
+impl From<CostTable> for wasmer_metering_cost_table_t {
+    fn from(other: CostTable) -> Self {
+        Self {
+            constant: other.constant,
+            variable: other.variable,
+            control: other.control,
+            call: other.call,
+            memory_access: other.memory_access,
+            memory_management: other.memory_management,
+            memory_grow_page: other.memory_grow_page,
+            numeric: other.numeric,
+        }
+    }
+}
+
+impl From<&wasmer_metering_cost_table_t> for CostTable {
+    fn from(other: &wasmer_metering_cost_table_t) -> Self {
+        Self {
+            constant: other.constant,
+            variable: other.variable,
+            control: other.control,
+            call: other.call,
+            memory_access: other.memory_access,
+            memory_management: other.memory_management,
+            memory_grow_page: other.memory_grow_page,
+            numeric: other.numeric,
+        }
+    }
+}
+
+/// A cost function implemented in C.
+#[derive(Clone)]
+struct CostFunction {
+    function: wasmer_metering_cost_function_t,
+    data: *mut c_void,
+}
+
+// The caller of `wasmer_metering_new` guarantees the cost function can
+// be called with its data from any thread compiling a module.
+unsafe impl Send for CostFunction {}
+unsafe impl Sync for CostFunction {}
+
+impl OperatorCost for CostFunction {
+    fn cost(&self, operator: &Operator) -> u64 {
+        let name = format!("{:?}", operator)
+            .chars()
+            .take_while(char::is_ascii_alphanumeric)
+            .collect::<String>();
+        let name = CString::new(name).expect("an operator name has no NUL byte");
+        unsafe { (self.function)(name.as_ptr(), self.data) }
+    }
+}
+
+#[derive(Clone)]
+enum Cost {
+    Function(CostFunction),
+    Table(CostTable),
+}
+
+impl OperatorCost for Cost {
+    fn cost(&self, operator: &Operator) -> u64 {
+        match self {
+            Self::Function(function) => function.cost(operator),
+            Self::Table(table) => table.cost(operator),
+        }
+    }
+
+    fn memory_grow_page_cost(&self) -> u64 {
+        match self {
+            Self::Function(function) => function.memory_grow_page_cost(),
+            Self::Table(table) => table.memory_grow_page_cost(),
+        }
+    }
+}
+
+/// The metering middleware, to push onto a configuration with
+/// `wasmer_metering_as_middleware` and
+/// [`wasm_config_push_middleware`][super::wasm_config_push_middleware].
+///
+/// This is a Wasmer-specific type with Wasmer-specific functions for
+/// manipulating it.
+#[allow(non_camel_case_types)]
+pub struct wasmer_metering_t {
+    inner: Arc<Metering<Cost>>,
+}
+
+/// Creates a metering middleware giving `initial_limit` points to each
+/// instance, where each operator costs what `cost_function` returns
+/// when called with its name and `data`.
+///
+/// The cost function is called while the modules are compiled, from
+/// any thread compiling them.
+///
+/// This is a Wasmer-specific function.
+#[no_mangle]
+pub extern "C" fn wasmer_metering_new(
+    initial_limit: u64,
+    cost_function: wasmer_metering_cost_function_t,
+    data: *mut c_void,
+) -> Box<wasmer_metering_t> {
+    let cost = Cost::Function(CostFunction {
+        function: cost_function,
+        data,
+    });
+    Box::new(wasmer_metering_t {
+        inner: Arc::new(Metering::new(initial_limit, cost)),
+    })
+}
+
+/// Creates a metering middleware giving `initial_limit` points to each
+/// instance, where the operators cost what `cost_table` gives for their
+/// class.
+///
+/// This is a Wasmer-specific function.
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_metering_new_with_cost_table(
+    initial_limit: u64,
+    cost_table: &wasmer_metering_cost_table_t,
+) -> Box<wasmer_metering_t> {
+    let cost = Cost::Table(cost_table.into());
+    Box::new(wasmer_metering_t {
+        inner: Arc::new(Metering::new(initial_limit, cost)),
+    })
+}
+
+/// Writes the default cost table to `out`: 1 point per operator, and
+/// nothing per page grown.
+///
+/// This is a Wasmer-specific function.
+#[no_mangle]
+pub extern "C" fn wasmer_metering_cost_table_default(out: &mut wasmer_metering_cost_table_t) {
+    *out = CostTable::default().into();
+}
+
+/// Deletes a metering middleware which hasn't been turned into a
+/// `wasmer_middleware_t`.
+///
+/// This is a Wasmer-specific function.
+#[no_mangle]
+pub extern "C" fn wasmer_metering_delete(_metering: Option<Box<wasmer_metering_t>>) {}
+
+/// Turns the metering middleware into a `wasmer_middleware_t`, to push
+/// onto a configuration. It takes ownership of `metering`.
+///
+/// This is a Wasmer-specific function.
+#[no_mangle]
+pub extern "C" fn wasmer_metering_as_middleware(
+    metering: Box<wasmer_metering_t>,
+) -> Box<wasmer_middleware_t> {
+    Box::new(wasmer_middleware_t {
+        inner: metering.inner,
+    })
+}
+
+/// Whether the module of `instance` has been compiled with the metering
+/// middleware, or reports an error.
+fn is_metered(instance: &Instance) -> bool {
+    let exports = &instance.exports;
+    if exports
+        .get_global("wasmer_metering_remaining_points")
+        .is_ok()
+        && exports
+            .get_global("wasmer_metering_points_exhausted")
+            .is_ok()
+    {
+        return true;
+    }
+    update_last_error(CApiError {
+        msg: "the instance hasn't been compiled with the metering middleware".to_string(),
+    });
+    false
+}
+
+/// Returns the points remaining to `instance`, 0 if they're exhausted
+/// or if its module hasn't been compiled with the metering middleware.
+///
+/// This is a Wasmer-specific function.
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_metering_get_remaining_points(instance: &wasm_instance_t) -> u64 {
+    if !is_metered(&instance.inner) {
+        return 0;
+    }
+    match get_remaining_points(&instance.inner) {
+        MeteringPoints::Remaining(points) => points,
+        MeteringPoints::Exhausted => 0,
+    }
+}
+
+/// Returns whether the execution of `instance` has been stopped because
+/// it exhausted its points.
+///
+/// This is a Wasmer-specific function.
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_metering_points_are_exhausted(instance: &wasm_instance_t) -> bool {
+    is_metered(&instance.inner)
+        && get_remaining_points(&instance.inner) == MeteringPoints::Exhausted
+}
+
+/// Gives `points` remaining points to `instance`, which can run again
+/// if it had exhausted its points.
+///
+/// This is a Wasmer-specific function.
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_metering_set_remaining_points(instance: &wasm_instance_t, points: u64) {
+    if is_metered(&instance.inner) {
+        set_remaining_points(&instance.inner, points);
+    }
+}
@@ -0,0 +1,37 @@
+//! Wasmer-specific API to transform the modules while they're
+//! compiled, with middlewares like [`metering`].
+//!
+//! A middleware is pushed onto a [`wasm_config_t`] with
+//! [`wasm_config_push_middleware`], and applies to every module
+//! compiled by the engine created with this configuration.
+
+pub mod metering;
+
+use super::engine::wasm_config_t;
+use std::sync::Arc;
+use wasmer_compiler::ModuleMiddleware;
+
+/// A middleware transforming the modules while they're compiled.
+///
+/// This is a Wasmer-specific type with Wasmer-specific functions for
+/// manipulating it.
+#[allow(non_camel_case_types)]
+pub struct wasmer_middleware_t {
+    pub(crate) inner: Arc<dyn ModuleMiddleware>,
+}
+
+/// Pushes `middleware` onto the back of the middleware chain of the
+/// engine created with `config`. It takes ownership of `middleware`.
+///
+/// This is a Wasmer-specific function.
+///
+/// # Example
+///
+/// See [`metering::wasmer_metering_new`].
+#[no_mangle]
+pub extern "C" fn wasm_config_push_middleware(
+    config: &mut wasm_config_t,
+    middleware: Box<wasmer_middleware_t>,
+) {
+    config.middlewares.push(middleware.inner);
+}
@@ -128,6 +128,9 @@ pub mod externals;
 /// cbindgen:ignore
 pub mod instance;
 
+#[cfg(feature = "middlewares")]
+pub mod middlewares;
+
 /// A WebAssembly module contains stateless WebAssembly code that has
 /// already been compiled and can be instantiated multiple times.
 ///
@@ -224,6 +227,8 @@ pub mod module;
 /// cbindgen:ignore
 pub mod store;
 
+pub mod target;
+
 /// cbindgen:ignore
 pub mod trap;
 
@@ -0,0 +1,226 @@
+//! Wasmer-specific API to select the target the engine compiles for:
+//! a target triple and a set of CPU features.
+//!
+//! A module compiled for a target other than the host can't be
+//! instantiated, but it can be serialized with
+//! [`wasm_module_serialize`][super::module::wasm_module_serialize], and
+//! deserialized by a headless engine on the target machine.
+//!
+//! # Example
+//!
+//! ```rust
+//! # use inline_c::assert_c;
+//! # fn main() {
+//! #    (assert_c! {
+//! # #include "tests/wasmer_wasm.h"
+//! #
+//! int main() {
+//!     // Declare the target triple.
+//!     wasm_name_t triple_name;
+//!     wasmer_byte_vec_new_from_string(&triple_name, "x86_64-apple-darwin");
+//!     wasmer_triple_t* triple = wasmer_triple_new(&triple_name);
+//!     assert(triple);
+//!
+//!     // Declare the CPU features.
+//!     wasmer_cpu_features_t* cpu_features = wasmer_cpu_features_new();
+//!     wasm_name_t cpu_feature_name;
+//!     wasmer_byte_vec_new_from_string(&cpu_feature_name, "sse2");
+//!     assert(wasmer_cpu_features_add(cpu_features, &cpu_feature_name));
+//!
+//!     // An unknown CPU feature is an error.
+//!     wasm_name_t unknown_cpu_feature_name;
+//!     wasmer_byte_vec_new_from_string(&unknown_cpu_feature_name, "warp-drive");
+//!     assert(!wasmer_cpu_features_add(cpu_features, &unknown_cpu_feature_name));
+//!
+//!     // Create the target, and the engine compiling for it.
+//!     wasmer_target_t* target = wasmer_target_new(triple, cpu_features);
+//!     assert(target);
+//!
+//!     wasm_config_t* config = wasm_config_new();
+//!     wasm_config_set_target(config, target);
+//!     wasm_engine_t* engine = wasm_engine_new_with_config(config);
+//!     assert(engine);
+//!
+//!     // Free everything.
+//!     wasm_engine_delete(engine);
+//!     wasm_byte_vec_delete(&unknown_cpu_feature_name);
+//!     wasm_byte_vec_delete(&cpu_feature_name);
+//!     wasm_byte_vec_delete(&triple_name);
+//!
+//!     return 0;
+//! }
+//! #    })
+//! #    .success();
+//! # }
+//! ```
+
+use super::types::wasm_name_t;
+use crate::error::{update_last_error, CApiError};
+use enumset::EnumSet;
+use std::str::{self, FromStr};
+use wasmer::{CpuFeature, Target, Triple};
+
+/// Returns the UTF-8 string of `name`, or reports an error.
+unsafe fn name_to_str<'a>(name: Option<&'a wasm_name_t>, what: &str) -> Option<&'a str> {
+    let name = match name.and_then(|name| name.into_slice()) {
+        Some(name) => name,
+        None => {
+            update_last_error(CApiError {
+                msg: format!("the {} is null or empty", what),
+            });
+            return None;
+        }
+    };
+    match str::from_utf8(name) {
+        Ok(name) => Some(name),
+        Err(_) => {
+            update_last_error(CApiError {
+                msg: format!("the {} isn't valid UTF-8", what),
+            });
+            None
+        }
+    }
+}
+
+/// A target triple, like `x86_64-unknown-linux-gnu` or
+/// `aarch64-apple-darwin`.
+///
+/// This is a Wasmer-specific type with Wasmer-specific functions for
+/// manipulating it.
+#[allow(non_camel_case_types)]
+pub struct wasmer_triple_t {
+    inner: Triple,
+}
+
+/// Parses a target triple, or returns `NULL` and reports an error if
+/// it's unknown.
+///
+/// This is a Wasmer-specific function.
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_triple_new(
+    triple: Option<&wasm_name_t>,
+) -> Option<Box<wasmer_triple_t>> {
+    let triple = name_to_str(triple, "target triple")?;
+    match Triple::from_str(triple) {
+        Ok(inner) => Some(Box::new(wasmer_triple_t { inner })),
+        Err(error) => {
+            update_last_error(CApiError {
+                msg: format!("unknown target triple `{}`: {}", triple, error),
+            });
+            None
+        }
+    }
+}
+
+/// Returns the triple of the host.
+///
+/// This is a Wasmer-specific function.
+#[no_mangle]
+pub extern "C" fn wasmer_triple_new_from_host() -> Box<wasmer_triple_t> {
+    Box::new(wasmer_triple_t {
+        inner: Triple::host(),
+    })
+}
+
+/// Deletes a triple which hasn't been given to `wasmer_target_new`.
+///
+/// This is a Wasmer-specific function.
+#[no_mangle]
+pub extern "C" fn wasmer_triple_delete(_triple: Option<Box<wasmer_triple_t>>) {}
+
+/// A set of CPU features, like `sse4.2` or `avx2`.
+///
+/// This is a Wasmer-specific type with Wasmer-specific functions for
+/// manipulating it.
+#[allow(non_camel_case_types)]
+pub struct wasmer_cpu_features_t {
+    inner: EnumSet<CpuFeature>,
+}
+
+/// Creates an empty set of CPU features.
+///
+/// This is a Wasmer-specific function.
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_cpu_features_new() -> Box<wasmer_cpu_features_t> {
+    Box::new(wasmer_cpu_features_t {
+        inner: EnumSet::new(),
+    })
+}
+
+/// Adds the CPU feature named `feature` to the set, like `sse4.2`.
+/// Returns false and reports an error if the feature is unknown.
+///
+/// This is a Wasmer-specific function.
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub unsafe extern "C" fn wasmer_cpu_features_add(
+    cpu_features: &mut wasmer_cpu_features_t,
+    feature: Option<&wasm_name_t>,
+) -> bool {
+    let feature = match name_to_str(feature, "CPU feature") {
+        Some(feature) => feature,
+        None => return false,
+    };
+    match CpuFeature::from_str(feature) {
+        Ok(feature) => {
+            cpu_features.inner.insert(feature);
+            true
+        }
+        Err(error) => {
+            update_last_error(error);
+            false
+        }
+    }
+}
+
+/// Deletes a set of CPU features which hasn't been given to
+/// `wasmer_target_new`.
+///
+/// This is a Wasmer-specific function.
+#[no_mangle]
+pub extern "C" fn wasmer_cpu_features_delete(_cpu_features: Option<Box<wasmer_cpu_features_t>>) {}
+
+/// A target: a triple and a set of CPU features.
+///
+/// This is a Wasmer-specific type with Wasmer-specific functions for
+/// manipulating it.
+#[allow(non_camel_case_types)]
+pub struct wasmer_target_t {
+    pub(crate) inner: Target,
+}
+
+/// Creates a target. It takes ownership of `triple` and `cpu_features`.
+///
+/// This is a Wasmer-specific function.
+///
+/// # Example
+///
+/// See the module's documentation.
+#[no_mangle]
+pub extern "C" fn wasmer_target_new(
+    triple: Option<Box<wasmer_triple_t>>,
+    cpu_features: Option<Box<wasmer_cpu_features_t>>,
+) -> Option<Box<wasmer_target_t>> {
+    let triple = triple?;
+    let cpu_features = cpu_features?;
+    Some(Box::new(wasmer_target_t {
+        inner: Target::new(triple.inner, cpu_features.inner),
+    }))
+}
+
+/// Deletes a target which hasn't been given to `wasm_config_set_target`.
+///
+/// This is a Wasmer-specific function.
+#[no_mangle]
+pub extern "C" fn wasmer_target_delete(_target: Option<Box<wasmer_target_t>>) {}
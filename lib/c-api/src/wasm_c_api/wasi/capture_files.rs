@@ -3,6 +3,8 @@
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use std::io::{self, Read, Seek, Write};
+use std::os::raw::c_void;
+use std::ptr;
 use wasmer_wasi::{WasiFile, WasiFsError};
 
 /// For capturing stdout/stderr. Stores all output in a string.
@@ -93,3 +95,89 @@ impl Write for OutputCapturer {
         Ok(())
     }
 }
+
+/// The callback receiving the output of a WASI program, with the `data`
+/// given along with it.
+#[allow(non_camel_case_types)]
+pub type wasi_output_callback_t =
+    unsafe extern "C" fn(data: *mut c_void, bytes: *const u8, len: usize);
+
+/// For streaming stdout/stderr to a callback, as soon as the WASI program
+/// writes it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OutputCallback {
+    #[serde(skip)]
+    callback: Option<wasi_output_callback_t>,
+    #[serde(skip, default = "ptr::null_mut")]
+    data: *mut c_void,
+}
+
+// The caller of `wasi_config_capture_stdout` or
+// `wasi_config_capture_stderr` guarantees the callback can be called
+// with its data from any thread.
+unsafe impl Send for OutputCallback {}
+
+impl OutputCallback {
+    pub fn new(callback: wasi_output_callback_t, data: *mut c_void) -> Self {
+        Self {
+            callback: Some(callback),
+            data,
+        }
+    }
+}
+
+#[typetag::serde]
+impl WasiFile for OutputCallback {
+    fn last_accessed(&self) -> u64 {
+        0
+    }
+    fn last_modified(&self) -> u64 {
+        0
+    }
+    fn created_time(&self) -> u64 {
+        0
+    }
+    fn size(&self) -> u64 {
+        0
+    }
+    fn set_len(&mut self, _len: u64) -> Result<(), WasiFsError> {
+        Ok(())
+    }
+    fn unlink(&mut self) -> Result<(), WasiFsError> {
+        Ok(())
+    }
+    fn bytes_available(&self) -> Result<usize, WasiFsError> {
+        // return an arbitrary amount
+        Ok(1024)
+    }
+}
+
+impl Read for OutputCallback {
+    fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "can not read from a captured output",
+        ))
+    }
+}
+impl Seek for OutputCallback {
+    fn seek(&mut self, _pos: io::SeekFrom) -> io::Result<u64> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "can not seek a captured output",
+        ))
+    }
+}
+impl Write for OutputCallback {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // A callback deserialized with its `WasiState` is lost, and the
+        // output is discarded.
+        if let Some(callback) = self.callback {
+            unsafe { callback(self.data, buf.as_ptr(), buf.len()) };
+        }
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
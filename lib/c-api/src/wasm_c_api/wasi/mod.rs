@@ -4,6 +4,8 @@
 
 mod capture_files;
 
+pub use capture_files::wasi_output_callback_t;
+
 use super::{
     externals::{wasm_extern_t, wasm_extern_vec_t, wasm_func_t, wasm_memory_t},
     instance::wasm_instance_t,
@@ -15,11 +17,12 @@ use super::{
 use crate::error::{update_last_error, CApiError};
 use std::convert::TryFrom;
 use std::ffi::CStr;
-use std::os::raw::c_char;
+use std::io::Write;
+use std::os::raw::{c_char, c_void};
 use std::slice;
 use wasmer::{Extern, NamedResolver};
 use wasmer_wasi::{
-    generate_import_object_from_env, get_wasi_version, WasiEnv, WasiFile, WasiState,
+    generate_import_object_from_env, get_wasi_version, Pipe, WasiEnv, WasiFile, WasiState,
     WasiStateBuilder, WasiVersion,
 };
 
@@ -30,6 +33,12 @@ pub struct wasi_config_t {
     inherit_stderr: bool,
     inherit_stdin: bool,
     /// cbindgen:ignore
+    stdin: Option<Pipe>,
+    /// cbindgen:ignore
+    stdout_callback: Option<capture_files::OutputCallback>,
+    /// cbindgen:ignore
+    stderr_callback: Option<capture_files::OutputCallback>,
+    /// cbindgen:ignore
     state_builder: WasiStateBuilder,
 }
 
@@ -132,6 +141,111 @@ pub unsafe extern "C" fn wasi_config_mapdir(
     true
 }
 
+/// The capabilities of a directory mapped with
+/// `wasi_config_mapdir_with_flags`, combined with `|`.
+#[allow(non_camel_case_types)]
+pub type wasi_dir_flags_t = u32;
+
+/// The files of the directory can be read.
+pub const WASI_DIR_READ: wasi_dir_flags_t = 1;
+
+/// The files of the directory can be written.
+pub const WASI_DIR_WRITE: wasi_dir_flags_t = 1 << 1;
+
+/// Files can be created in the directory. It implies `WASI_DIR_WRITE`.
+pub const WASI_DIR_CREATE: wasi_dir_flags_t = 1 << 2;
+
+/// The files and the directories of the directory can't be removed or
+/// renamed, even when it can be written.
+pub const WASI_DIR_DENY_DELETE: wasi_dir_flags_t = 1 << 3;
+
+/// Maps the host directory `dir` to `alias` in the guest, with the
+/// capabilities `flags`, e.g. `WASI_DIR_READ` for a read-only
+/// directory.
+#[no_mangle]
+pub unsafe extern "C" fn wasi_config_mapdir_with_flags(
+    config: &mut wasi_config_t,
+    alias: *const c_char,
+    dir: *const c_char,
+    flags: wasi_dir_flags_t,
+) -> bool {
+    let alias_cstr = CStr::from_ptr(alias);
+    let alias_str = match alias_cstr.to_str() {
+        Ok(alias_str) => alias_str,
+        Err(e) => {
+            update_last_error(e);
+            return false;
+        }
+    };
+
+    let dir_cstr = CStr::from_ptr(dir);
+    let dir_str = match dir_cstr.to_str() {
+        Ok(dir_str) => dir_str,
+        Err(e) => {
+            update_last_error(e);
+            return false;
+        }
+    };
+
+    if let Err(e) = config.state_builder.preopen(|p| {
+        p.directory(dir_str)
+            .alias(alias_str)
+            .read(flags & WASI_DIR_READ != 0)
+            .write(flags & WASI_DIR_WRITE != 0)
+            .create(flags & WASI_DIR_CREATE != 0)
+            .deny_delete(flags & WASI_DIR_DENY_DELETE != 0)
+    }) {
+        update_last_error(e);
+        return false;
+    }
+
+    true
+}
+
+/// Appends `bytes` to the stdin of the WASI program, which becomes a
+/// pipe the program reads from. More bytes can be written to it later
+/// with `wasi_env_write_stdin`.
+#[no_mangle]
+pub unsafe extern "C" fn wasi_config_stdin_bytes(
+    config: &mut wasi_config_t,
+    bytes: *const u8,
+    len: usize,
+) {
+    let stdin = config.stdin.get_or_insert_with(Pipe::new);
+    if len > 0 {
+        debug_assert!(!bytes.is_null());
+        stdin
+            .write_all(slice::from_raw_parts(bytes, len))
+            .expect("writing to a pipe can't fail");
+    }
+}
+
+/// Calls `callback` with `data` and the bytes written by the WASI
+/// program to its stdout, as soon as it writes them.
+///
+/// The callback can be called from any thread running the program.
+#[no_mangle]
+pub extern "C" fn wasi_config_capture_stdout(
+    config: &mut wasi_config_t,
+    callback: wasi_output_callback_t,
+    data: *mut c_void,
+) {
+    config.stdout_callback = Some(capture_files::OutputCallback::new(callback, data));
+}
+
+/// Calls `callback` with `data` and the bytes written by the WASI
+/// program to its stderr, as soon as it writes them.
+///
+/// The callback can be called from any thread running the program.
+#[no_mangle]
+pub extern "C" fn wasi_config_capture_stderr(
+    config: &mut wasi_config_t,
+    callback: wasi_output_callback_t,
+    data: *mut c_void,
+) {
+    config.stderr_callback = Some(capture_files::OutputCallback::new(callback, data));
+}
+
 #[no_mangle]
 pub extern "C" fn wasi_config_inherit_stdout(config: &mut wasi_config_t) {
     config.inherit_stdout = true;
@@ -154,16 +268,23 @@ pub struct wasi_env_t {
 /// Takes ownership over the `wasi_config_t`.
 #[no_mangle]
 pub extern "C" fn wasi_env_new(mut config: Box<wasi_config_t>) -> Option<Box<wasi_env_t>> {
-    if config.inherit_stdout {
+    if let Some(callback) = config.stdout_callback.take() {
+        config.state_builder.stdout(Box::new(callback));
+    } else if config.inherit_stdout {
         config
             .state_builder
             .stdout(Box::new(capture_files::OutputCapturer::new()));
     }
-    if config.inherit_stderr {
+    if let Some(callback) = config.stderr_callback.take() {
+        config.state_builder.stderr(Box::new(callback));
+    } else if config.inherit_stderr {
         config
             .state_builder
             .stderr(Box::new(capture_files::OutputCapturer::new()));
     }
+    if let Some(stdin) = config.stdin.take() {
+        config.state_builder.stdin(Box::new(stdin));
+    }
     // TODO: impl capturer for stdin
     let wasi_state = c_try!(config.state_builder.build());
     Some(Box::new(wasi_env_t {
@@ -251,6 +372,43 @@ pub unsafe extern "C" fn wasi_env_read_stderr(
     read_inner(stderr, inner_buffer)
 }
 
+/// Writes `bytes` to the stdin of the WASI program, which must have been
+/// made a pipe with `wasi_config_stdin_bytes`.
+#[no_mangle]
+pub unsafe extern "C" fn wasi_env_write_stdin(
+    env: &mut wasi_env_t,
+    bytes: *const u8,
+    len: usize,
+) -> bool {
+    let mut state = env.inner.state();
+    let stdin = match state.fs.stdin_mut() {
+        Ok(Some(stdin)) => stdin,
+        _ => {
+            update_last_error(CApiError {
+                msg: "could not find a file handle for `stdin`".to_string(),
+            });
+            return false;
+        }
+    };
+    let pipe = match stdin.downcast_mut::<Pipe>() {
+        Some(pipe) => pipe,
+        None => {
+            update_last_error(CApiError {
+                msg: "`stdin` isn't a pipe, see `wasi_config_stdin_bytes`".to_string(),
+            });
+            return false;
+        }
+    };
+    if len > 0 {
+        debug_assert!(!bytes.is_null());
+        if let Err(e) = pipe.write_all(slice::from_raw_parts(bytes, len)) {
+            update_last_error(e);
+            return false;
+        }
+    }
+    true
+}
+
 fn read_inner(wasi_file: &mut Box<dyn WasiFile>, inner_buffer: &mut [u8]) -> isize {
     if let Some(oc) = wasi_file.downcast_mut::<capture_files::OutputCapturer>() {
         let mut num_bytes_written = 0;
@@ -374,3 +532,93 @@ pub unsafe extern "C" fn wasi_get_start_function(
 /// cbindgen:ignore
 #[no_mangle]
 pub unsafe extern "C" fn wasm_extern_delete(_item: Option<Box<wasm_extern_t>>) {}
+
+#[cfg(test)]
+mod tests {
+    use inline_c::assert_c;
+
+    #[test]
+    fn test_wasi_stdin_bytes_and_capture_stdout() {
+        (assert_c! {
+            #include "tests/wasmer_wasm.h"
+            #include <string.h>
+
+            typedef struct {
+                char bytes[64];
+                size_t len;
+            } output_t;
+
+            void on_stdout(void* data, const uint8_t* bytes, uintptr_t len) {
+                output_t* output = (output_t*) data;
+                memcpy(output->bytes + output->len, bytes, len);
+                output->len += len;
+            }
+
+            int main() {
+                wasm_engine_t* engine = wasm_engine_new();
+                wasm_store_t* store = wasm_store_new(engine);
+
+                // A program echoing the first 64 bytes of its stdin.
+                wasm_byte_vec_t wat;
+                wasmer_byte_vec_new_from_string(
+                    &wat,
+                    "(module\n"
+                    "  (import \"wasi_unstable\" \"fd_read\" (func $fd_read (param i32 i32 i32 i32) (result i32)))\n"
+                    "  (import \"wasi_unstable\" \"fd_write\" (func $fd_write (param i32 i32 i32 i32) (result i32)))\n"
+                    "  (memory (export \"memory\") 1)\n"
+                    "  (func (export \"_start\")\n"
+                    "    (i32.store (i32.const 0) (i32.const 16))\n"
+                    "    (i32.store (i32.const 4) (i32.const 64))\n"
+                    "    (drop (call $fd_read (i32.const 0) (i32.const 0) (i32.const 1) (i32.const 8)))\n"
+                    "    (i32.store (i32.const 4) (i32.load (i32.const 8)))\n"
+                    "    (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8)))))"
+                );
+                wasm_byte_vec_t wasm;
+                wat2wasm(&wat, &wasm);
+                wasm_module_t* module = wasm_module_new(store, &wasm);
+
+                assert(module);
+
+                output_t output = { .len = 0 };
+                wasi_config_t* config = wasi_config_new("echo");
+                wasi_config_stdin_bytes(config, (const uint8_t*) "hello", 5);
+                wasi_config_capture_stdout(config, on_stdout, &output);
+                wasi_env_t* wasi_env = wasi_env_new(config);
+
+                assert(wasi_env);
+                assert(wasi_env_write_stdin(wasi_env, (const uint8_t*) ", world", 7));
+
+                wasm_extern_vec_t imports;
+                wasm_extern_vec_new_uninitialized(&imports, 2);
+
+                assert(wasi_get_imports(store, module, wasi_env, &imports));
+
+                wasm_trap_t* traps = NULL;
+                wasm_instance_t* instance = wasm_instance_new(store, module, &imports, &traps);
+
+                assert(instance);
+
+                wasm_func_t* start = wasi_get_start_function(instance);
+                wasm_val_vec_t arguments = WASM_EMPTY_VEC;
+                wasm_val_vec_t results = WASM_EMPTY_VEC;
+
+                assert(wasm_func_call(start, &arguments, &results) == NULL);
+                assert(output.len == 12);
+                assert(memcmp(output.bytes, "hello, world", 12) == 0);
+
+                wasm_func_delete(start);
+                wasm_instance_delete(instance);
+                wasm_extern_vec_delete(&imports);
+                wasi_env_delete(wasi_env);
+                wasm_module_delete(module);
+                wasm_byte_vec_delete(&wasm);
+                wasm_byte_vec_delete(&wat);
+                wasm_store_delete(store);
+                wasm_engine_delete(engine);
+
+                return 0;
+            }
+        })
+        .success();
+    }
+}
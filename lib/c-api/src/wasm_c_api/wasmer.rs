@@ -1,10 +1,15 @@
 //! Non-standard Wasmer-specific extensions to the Wasm C API.
 
 use super::module::wasm_module_t;
+use super::store::wasm_store_t;
 use super::types::wasm_name_t;
+use crate::error::{update_last_error, CApiError};
+use std::ffi::CStr;
+use std::os::raw::c_char;
 use std::ptr;
 use std::str;
 use std::sync::Arc;
+use wasmer::Module;
 
 /// Non-standard Wasmer-specific API to get the module's name,
 /// otherwise `out->size` is set to `0` and `out->data` to `NULL`.
@@ -156,3 +161,125 @@ pub unsafe extern "C" fn wasm_module_set_name(
         None => false,
     }
 }
+
+/// Returns the UTF-8 path `path`, or reports an error.
+unsafe fn path_to_str<'a>(path: *const c_char) -> Option<&'a str> {
+    if path.is_null() {
+        update_last_error(CApiError {
+            msg: "`path` is null".to_string(),
+        });
+        return None;
+    }
+    match CStr::from_ptr(path).to_str() {
+        Ok(path) => Some(path),
+        Err(error) => {
+            update_last_error(error);
+            None
+        }
+    }
+}
+
+/// Non-standard Wasmer-specific API to serialize a module to the file
+/// at `path`, to deserialize it later with
+/// [`wasm_module_deserialize_from_file`], possibly with a headless
+/// engine. Returns false and reports an error if it fails.
+///
+/// # Example
+///
+/// ```rust
+/// # use inline_c::assert_c;
+/// # fn main() {
+/// #    (assert_c! {
+/// # #include "tests/wasmer_wasm.h"
+/// #
+/// int main() {
+///     // Create the engine and the store.
+///     wasm_engine_t* engine = wasm_engine_new();
+///     wasm_store_t* store = wasm_store_new(engine);
+///
+///     // Create the module.
+///     wasm_byte_vec_t wat;
+///     wasmer_byte_vec_new_from_string(&wat, "(module (func (export \"function\")))");
+///     wasm_byte_vec_t wasm;
+///     wat2wasm(&wat, &wasm);
+///     wasm_module_t* module = wasm_module_new(store, &wasm);
+///     assert(module);
+///
+///     // Serialize it to a file.
+///     assert(wasm_module_serialize_to_file(module, "module.wasmer"));
+///     wasm_module_delete(module);
+///
+///     // Deserialize it from the file.
+///     wasm_module_t* deserialized_module = wasm_module_deserialize_from_file(store, "module.wasmer");
+///     assert(deserialized_module);
+///
+///     wasm_exporttype_vec_t export_types;
+///     wasm_module_exports(deserialized_module, &export_types);
+///     assert(export_types.size == 1);
+///
+///     // A missing file is an error.
+///     assert(!wasm_module_deserialize_from_file(store, "missing.wasmer"));
+///     assert(wasmer_last_error_length() > 0);
+///
+///     // Free everything.
+///     remove("module.wasmer");
+///     wasm_exporttype_vec_delete(&export_types);
+///     wasm_module_delete(deserialized_module);
+///     wasm_byte_vec_delete(&wasm);
+///     wasm_byte_vec_delete(&wat);
+///     wasm_store_delete(store);
+///     wasm_engine_delete(engine);
+///
+///     return 0;
+/// }
+/// #    })
+/// #    .success();
+/// # }
+/// ```
+#[no_mangle]
+pub unsafe extern "C" fn wasm_module_serialize_to_file(
+    module: &wasm_module_t,
+    path: *const c_char,
+) -> bool {
+    let path = match path_to_str(path) {
+        Some(path) => path,
+        None => return false,
+    };
+    match module.inner.serialize_to_file(path) {
+        Ok(()) => true,
+        Err(error) => {
+            update_last_error(error);
+            false
+        }
+    }
+}
+
+/// Non-standard Wasmer-specific API to deserialize a module from the
+/// file at `path`, serialized with [`wasm_module_serialize_to_file`]
+/// by an engine of the same kind. Returns `NULL` and reports an error
+/// if it fails.
+///
+/// # Safety
+///
+/// Like `wasm_module_deserialize`, the content of the file is loaded
+/// into executable memory as is: it must come from a trusted source.
+///
+/// # Example
+///
+/// See [`wasm_module_serialize_to_file`].
+#[no_mangle]
+pub unsafe extern "C" fn wasm_module_deserialize_from_file(
+    store: &wasm_store_t,
+    path: *const c_char,
+) -> Option<Box<wasm_module_t>> {
+    let path = path_to_str(path)?;
+    match Module::deserialize_from_file(&store.inner, path) {
+        Ok(module) => Some(Box::new(wasm_module_t {
+            inner: Arc::new(module),
+        })),
+        Err(error) => {
+            update_last_error(error);
+            None
+        }
+    }
+}
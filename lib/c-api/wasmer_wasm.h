@@ -38,6 +38,9 @@
 // The `wasi` feature has been enabled for this build.
 #define WASMER_WASI_ENABLED
 
+// The `middlewares` feature has been enabled for this build.
+#define WASMER_MIDDLEWARES_ENABLED
+
 // This file corresponds to the following Wasmer version.
 #define WASMER_VERSION "1.0.0"
 #define WASMER_VERSION_MAJOR 1
@@ -62,6 +65,35 @@
 #include <stdlib.h>
 #include "wasm.h"
 
+#if defined(WASMER_WASI_ENABLED)
+/**
+ * Files can be created in the directory. It implies `WASI_DIR_WRITE`.
+ */
+#define WASI_DIR_CREATE (1 << 2)
+#endif
+
+#if defined(WASMER_WASI_ENABLED)
+/**
+ * The files and the directories of the directory can't be removed or
+ * renamed, even when it can be written.
+ */
+#define WASI_DIR_DENY_DELETE (1 << 3)
+#endif
+
+#if defined(WASMER_WASI_ENABLED)
+/**
+ * The files of the directory can be read.
+ */
+#define WASI_DIR_READ 1
+#endif
+
+#if defined(WASMER_WASI_ENABLED)
+/**
+ * The files of the directory can be written.
+ */
+#define WASI_DIR_WRITE (1 << 1)
+#endif
+
 #if defined(WASMER_COMPILER_ENABLED)
 /**
  * Kind of compilers that can be used by the engines.
@@ -124,10 +156,116 @@ typedef struct wasi_env_t wasi_env_t;
 typedef struct wasi_version_t wasi_version_t;
 #endif
 
+typedef struct wasmer_cpu_features_t wasmer_cpu_features_t;
+
+#if defined(WASMER_MIDDLEWARES_ENABLED)
+typedef struct wasmer_metering_t wasmer_metering_t;
+#endif
+
+#if defined(WASMER_MIDDLEWARES_ENABLED)
+typedef struct wasmer_middleware_t wasmer_middleware_t;
+#endif
+
+typedef struct wasmer_target_t wasmer_target_t;
+
+typedef struct wasmer_triple_t wasmer_triple_t;
+
+#if defined(WASMER_WASI_ENABLED)
+/**
+ * The capabilities of a directory mapped with
+ * `wasi_config_mapdir_with_flags`, combined with `|`.
+ */
+typedef uint32_t wasi_dir_flags_t;
+#endif
+
+#if defined(WASMER_WASI_ENABLED)
+/**
+ * The callback receiving the output of a WASI program, with the `data`
+ * given along with it.
+ */
+typedef void (*wasi_output_callback_t)(void *data, const uint8_t *bytes, uintptr_t len);
+#endif
+
+#if defined(WASMER_MIDDLEWARES_ENABLED)
+/**
+ * The costs of the operators by class, in points.
+ *
+ * This is a Wasmer-specific type with Wasmer-specific functions for
+ * manipulating it.
+ */
+typedef struct {
+  /**
+   * Constants, e.g. `i32.const` or `ref.null`.
+   */
+  uint64_t constant;
+  /**
+   * Accesses to the locals and the globals.
+   */
+  uint64_t variable;
+  /**
+   * Blocks, branches, and the parametric operators `drop` and `select`.
+   */
+  uint64_t control;
+  /**
+   * Direct and indirect calls, including tail calls.
+   */
+  uint64_t call;
+  /**
+   * Loads and stores.
+   */
+  uint64_t memory_access;
+  /**
+   * `memory.size`, `memory.grow`, and the bulk memory and table
+   * operators, e.g. `memory.copy` or `table.init`.
+   */
+  uint64_t memory_management;
+  /**
+   * Each page requested by a `memory.grow`.
+   */
+  uint64_t memory_grow_page;
+  /**
+   * Every other operator: the numeric, reference, SIMD and atomic
+   * operators.
+   */
+  uint64_t numeric;
+} wasmer_metering_cost_table_t;
+#endif
+
+#if defined(WASMER_MIDDLEWARES_ENABLED)
+/**
+ * The callback giving the cost of an operator in points, with the
+ * `data` given along with it.
+ *
+ * The operator is named after its variant of the `Operator` enum of
+ * `wasmparser`, e.g. `I32Add` or `LocalGet`.
+ */
+typedef uint64_t (*wasmer_metering_cost_function_t)(const char *operator_name, void *data);
+#endif
+
 #if defined(WASMER_WASI_ENABLED)
 void wasi_config_arg(wasi_config_t *config, const char *arg);
 #endif
 
+#if defined(WASMER_WASI_ENABLED)
+/**
+ * Calls `callback` with `data` and the bytes written by the WASI
+ * program to its stderr, as soon as it writes them.
+ *
+ * The callback can be called from any thread running the program.
+ */
+void wasi_config_capture_stderr(wasi_config_t *config, wasi_output_callback_t callback, void *data);
+#endif
+
+#if defined(WASMER_WASI_ENABLED)
+/**
+ * Calls `callback` with `data` and the bytes written by the WASI
+ * program to its stdout, as soon as it writes them.
+ *
+ * The callback can be called from any thread running the program.
+ */
+void wasi_config_capture_stdout(wasi_config_t *config, wasi_output_callback_t callback, void *data);
+#endif
+
 #if defined(WASMER_WASI_ENABLED)
 void wasi_config_env(wasi_config_t *config, const char *key, const char *value);
 #endif
@@ -148,6 +286,18 @@ void wasi_config_inherit_stdout(wasi_config_t *config);
 bool wasi_config_mapdir(wasi_config_t *config, const char *alias, const char *dir);
 #endif
 
+#if defined(WASMER_WASI_ENABLED)
+/**
+ * Maps the host directory `dir` to `alias` in the guest, with the
+ * capabilities `flags`, e.g. `WASI_DIR_READ` for a read-only
+ * directory.
+ */
+bool wasi_config_mapdir_with_flags(wasi_config_t *config,
+                                   const char *alias,
+                                   const char *dir,
+                                   wasi_dir_flags_t flags);
+#endif
+
 #if defined(WASMER_WASI_ENABLED)
 wasi_config_t *wasi_config_new(const char *program_name);
 #endif
@@ -156,6 +306,15 @@ wasi_config_t *wasi_config_new(const char *program_name);
 bool wasi_config_preopen_dir(wasi_config_t *config, const char *dir);
 #endif
 
+#if defined(WASMER_WASI_ENABLED)
+/**
+ * Appends `bytes` to the stdin of the WASI program, which becomes a
+ * pipe the program reads from. More bytes can be written to it later
+ * with `wasi_env_write_stdin`.
+ */
+void wasi_config_stdin_bytes(wasi_config_t *config, const uint8_t *bytes, uintptr_t len);
+#endif
+
 #if defined(WASMER_WASI_ENABLED)
 void wasi_env_delete(wasi_env_t *_state);
 #endif
@@ -191,6 +350,14 @@ bool wasi_env_set_instance(wasi_env_t *env, const wasm_instance_t *instance);
 void wasi_env_set_memory(wasi_env_t *env, const wasm_memory_t *memory);
 #endif
 
+#if defined(WASMER_WASI_ENABLED)
+/**
+ * Writes `bytes` to the stdin of the WASI program, which must have been
+ * made a pipe with `wasi_config_stdin_bytes`.
+ */
+bool wasi_env_write_stdin(wasi_env_t *env, const uint8_t *bytes, uintptr_t len);
+#endif
+
 #if defined(WASMER_WASI_ENABLED)
 /**
  * Takes ownership of `wasi_env_t`.
@@ -209,6 +376,20 @@ wasm_func_t *wasi_get_start_function(wasm_instance_t *instance);
 wasi_version_t wasi_get_wasi_version(const wasm_module_t *module);
 #endif
 
+#if defined(WASMER_MIDDLEWARES_ENABLED)
+/**
+ * Pushes `middleware` onto the back of the middleware chain of the
+ * engine created with `config`. It takes ownership of `middleware`.
+ *
+ * This is a Wasmer-specific function.
+ *
+ * # Example
+ *
+ * See [`metering::wasmer_metering_new`].
+ */
+void wasm_config_push_middleware(wasm_config_t *config, wasmer_middleware_t *middleware);
+#endif
+
 #if defined(WASMER_COMPILER_ENABLED)
 /**
  * Updates the configuration to specify a particular compiler to use.
@@ -287,6 +468,103 @@ void wasm_config_set_compiler(wasm_config_t *config, wasmer_compiler_t compiler)
  */
 void wasm_config_set_engine(wasm_config_t *config, wasmer_engine_t engine);
 
+/**
+ * Updates the configuration to create a headless engine: an engine
+ * without a compiler, which can only deserialize modules compiled
+ * and serialized beforehand.
+ *
+ * A library built without the `compiler` feature only creates
+ * headless engines.
+ *
+ * This is a Wasmer-specific function.
+ *
+ * # Example
+ *
+ * ```rust
+ * # use inline_c::assert_c;
+ * # fn main() {
+ * #    (assert_c! {
+ * # #include "tests/wasmer_wasm.h"
+ * #
+ * int main() {
+ *     // Compile and serialize a module with the default engine.
+ *     wasm_engine_t* engine = wasm_engine_new();
+ *     wasm_store_t* store = wasm_store_new(engine);
+ *
+ *     wasm_byte_vec_t wat;
+ *     wasmer_byte_vec_new_from_string(&wat, "(module (func (export \"function\")))");
+ *     wasm_byte_vec_t wasm;
+ *     wat2wasm(&wat, &wasm);
+ *     wasm_module_t* module = wasm_module_new(store, &wasm);
+ *     assert(module);
+ *
+ *     wasm_byte_vec_t serialized_module;
+ *     wasm_module_serialize(module, &serialized_module);
+ *     assert(serialized_module.size > 0);
+ *
+ *     // Create a headless engine.
+ *     wasm_config_t* config = wasm_config_new();
+ *     wasm_config_set_headless(config, true);
+ *     wasm_engine_t* headless_engine = wasm_engine_new_with_config(config);
+ *     assert(headless_engine);
+ *     wasm_store_t* headless_store = wasm_store_new(headless_engine);
+ *
+ *     // It can't compile modules…
+ *     assert(!wasm_module_new(headless_store, &wasm));
+ *
+ *     // … but it can deserialize them.
+ *     wasm_module_t* deserialized_module = wasm_module_deserialize(headless_store, &serialized_module);
+ *     assert(deserialized_module);
+ *
+ *     // Free everything.
+ *     wasm_module_delete(deserialized_module);
+ *     wasm_store_delete(headless_store);
+ *     wasm_engine_delete(headless_engine);
+ *     wasm_byte_vec_delete(&serialized_module);
+ *     wasm_module_delete(module);
+ *     wasm_byte_vec_delete(&wasm);
+ *     wasm_byte_vec_delete(&wat);
+ *     wasm_store_delete(store);
+ *     wasm_engine_delete(engine);
+ *
+ *     return 0;
+ * }
+ * #    })
+ * #    .success();
+ * # }
+ * ```
+ */
+void wasm_config_set_headless(wasm_config_t *config, bool headless);
+
+/**
+ * Updates the configuration to compile the modules for `target`
+ * instead of the host. It takes ownership of `target`.
+ *
+ * This is a Wasmer-specific function.
+ *
+ * # Example
+ *
+ * See the [`target`][super::target] module's documentation.
+ */
+void wasm_config_set_target(wasm_config_t *config, wasmer_target_t *target);
+
+/**
+ * Non-standard Wasmer-specific API to deserialize a module from the
+ * file at `path`, serialized with [`wasm_module_serialize_to_file`]
+ * by an engine of the same kind. Returns `NULL` and reports an error
+ * if it fails.
+ *
+ * # Safety
+ *
+ * Like `wasm_module_deserialize`, the content of the file is loaded
+ * into executable memory as is: it must come from a trusted source.
+ *
+ * # Example
+ *
+ * See [`wasm_module_serialize_to_file`].
+ */
+wasm_module_t *wasm_module_deserialize_from_file(const wasm_store_t *store, const char *path);
+
 /**
  * Non-standard Wasmer-specific API to get the module's name,
  * otherwise `out->size` is set to `0` and `out->data` to `NULL`.
@@ -338,6 +616,67 @@ void wasm_config_set_engine(wasm_config_t *config, wasmer_engine_t engine);
  */
 void wasm_module_name(const wasm_module_t *module, wasm_name_t *out);
 
+/**
+ * Non-standard Wasmer-specific API to serialize a module to the file
+ * at `path`, to deserialize it later with
+ * [`wasm_module_deserialize_from_file`], possibly with a headless
+ * engine. Returns false and reports an error if it fails.
+ *
+ * # Example
+ *
+ * ```rust
+ * # use inline_c::assert_c;
+ * # fn main() {
+ * #    (assert_c! {
+ * # #include "tests/wasmer_wasm.h"
+ * #
+ * int main() {
+ *     // Create the engine and the store.
+ *     wasm_engine_t* engine = wasm_engine_new();
+ *     wasm_store_t* store = wasm_store_new(engine);
+ *
+ *     // Create the module.
+ *     wasm_byte_vec_t wat;
+ *     wasmer_byte_vec_new_from_string(&wat, "(module (func (export \"function\")))");
+ *     wasm_byte_vec_t wasm;
+ *     wat2wasm(&wat, &wasm);
+ *     wasm_module_t* module = wasm_module_new(store, &wasm);
+ *     assert(module);
+ *
+ *     // Serialize it to a file.
+ *     assert(wasm_module_serialize_to_file(module, "module.wasmer"));
+ *     wasm_module_delete(module);
+ *
+ *     // Deserialize it from the file.
+ *     wasm_module_t* deserialized_module = wasm_module_deserialize_from_file(store, "module.wasmer");
+ *     assert(deserialized_module);
+ *
+ *     wasm_exporttype_vec_t export_types;
+ *     wasm_module_exports(deserialized_module, &export_types);
+ *     assert(export_types.size == 1);
+ *
+ *     // A missing file is an error.
+ *     assert(!wasm_module_deserialize_from_file(store, "missing.wasmer"));
+ *     assert(wasmer_last_error_length() > 0);
+ *
+ *     // Free everything.
+ *     remove("module.wasmer");
+ *     wasm_exporttype_vec_delete(&export_types);
+ *     wasm_module_delete(deserialized_module);
+ *     wasm_byte_vec_delete(&wasm);
+ *     wasm_byte_vec_delete(&wat);
+ *     wasm_store_delete(store);
+ *     wasm_engine_delete(engine);
+ *
+ *     return 0;
+ * }
+ * #    })
+ * #    .success();
+ * # }
+ * ```
+ */
+bool wasm_module_serialize_to_file(const wasm_module_t *module, const char *path);
+
 /**
  * Non-standard Wasmer-specific API to set the module's name. The
  * function returns `true` if the name has been updated, `false`
@@ -407,6 +746,37 @@ void wasm_module_name(const wasm_module_t *module, wasm_name_t *out);
  */
 bool wasm_module_set_name(wasm_module_t *module, const wasm_name_t *name);
 
+/**
+ * Adds the CPU feature named `feature` to the set, like `sse4.2`.
+ * Returns false and reports an error if the feature is unknown.
+ *
+ * This is a Wasmer-specific function.
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+bool wasmer_cpu_features_add(wasmer_cpu_features_t *cpu_features, const wasm_name_t *feature);
+
+/**
+ * Deletes a set of CPU features which hasn't been given to
+ * `wasmer_target_new`.
+ *
+ * This is a Wasmer-specific function.
+ */
+void wasmer_cpu_features_delete(wasmer_cpu_features_t *_cpu_features);
+
+/**
+ * Creates an empty set of CPU features.
+ *
+ * This is a Wasmer-specific function.
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+wasmer_cpu_features_t *wasmer_cpu_features_new(void);
+
 /**
  * Gets the length in bytes of the last error if any, zero otherwise.
  *
@@ -445,6 +815,154 @@ int wasmer_last_error_length(void);
  */
 int wasmer_last_error_message(char *buffer, int length);
 
+#if defined(WASMER_MIDDLEWARES_ENABLED)
+/**
+ * Turns the metering middleware into a `wasmer_middleware_t`, to push
+ * onto a configuration. It takes ownership of `metering`.
+ *
+ * This is a Wasmer-specific function.
+ */
+wasmer_middleware_t *wasmer_metering_as_middleware(wasmer_metering_t *metering);
+#endif
+
+#if defined(WASMER_MIDDLEWARES_ENABLED)
+/**
+ * Writes the default cost table to `out`: 1 point per operator, and
+ * nothing per page grown.
+ *
+ * This is a Wasmer-specific function.
+ */
+void wasmer_metering_cost_table_default(wasmer_metering_cost_table_t *out);
+#endif
+
+#if defined(WASMER_MIDDLEWARES_ENABLED)
+/**
+ * Deletes a metering middleware which hasn't been turned into a
+ * `wasmer_middleware_t`.
+ *
+ * This is a Wasmer-specific function.
+ */
+void wasmer_metering_delete(wasmer_metering_t *_metering);
+#endif
+
+#if defined(WASMER_MIDDLEWARES_ENABLED)
+/**
+ * Returns the points remaining to `instance`, 0 if they're exhausted
+ * or if its module hasn't been compiled with the metering middleware.
+ *
+ * This is a Wasmer-specific function.
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+uint64_t wasmer_metering_get_remaining_points(const wasm_instance_t *instance);
+#endif
+
+#if defined(WASMER_MIDDLEWARES_ENABLED)
+/**
+ * Creates a metering middleware giving `initial_limit` points to each
+ * instance, where each operator costs what `cost_function` returns
+ * when called with its name and `data`.
+ *
+ * The cost function is called while the modules are compiled, from
+ * any thread compiling them.
+ *
+ * This is a Wasmer-specific function.
+ */
+wasmer_metering_t *wasmer_metering_new(uint64_t initial_limit,
+                                       wasmer_metering_cost_function_t cost_function,
+                                       void *data);
+#endif
+
+#if defined(WASMER_MIDDLEWARES_ENABLED)
+/**
+ * Creates a metering middleware giving `initial_limit` points to each
+ * instance, where the operators cost what `cost_table` gives for their
+ * class.
+ *
+ * This is a Wasmer-specific function.
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+wasmer_metering_t *wasmer_metering_new_with_cost_table(uint64_t initial_limit,
+                                                       const wasmer_metering_cost_table_t *cost_table);
+#endif
+
+#if defined(WASMER_MIDDLEWARES_ENABLED)
+/**
+ * Returns whether the execution of `instance` has been stopped because
+ * it exhausted its points.
+ *
+ * This is a Wasmer-specific function.
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+bool wasmer_metering_points_are_exhausted(const wasm_instance_t *instance);
+#endif
+
+#if defined(WASMER_MIDDLEWARES_ENABLED)
+/**
+ * Gives `points` remaining points to `instance`, which can run again
+ * if it had exhausted its points.
+ *
+ * This is a Wasmer-specific function.
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+void wasmer_metering_set_remaining_points(const wasm_instance_t *instance, uint64_t points);
+#endif
+
+/**
+ * Deletes a target which hasn't been given to `wasm_config_set_target`.
+ *
+ * This is a Wasmer-specific function.
+ */
+void wasmer_target_delete(wasmer_target_t *_target);
+
+/**
+ * Creates a target. It takes ownership of `triple` and `cpu_features`.
+ *
+ * This is a Wasmer-specific function.
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+wasmer_target_t *wasmer_target_new(wasmer_triple_t *triple, wasmer_cpu_features_t *cpu_features);
+
+/**
+ * Deletes a triple which hasn't been given to `wasmer_target_new`.
+ *
+ * This is a Wasmer-specific function.
+ */
+void wasmer_triple_delete(wasmer_triple_t *_triple);
+
+/**
+ * Parses a target triple, or returns `NULL` and reports an error if
+ * it's unknown.
+ *
+ * This is a Wasmer-specific function.
+ *
+ * # Example
+ *
+ * See the module's documentation.
+ */
+wasmer_triple_t *wasmer_triple_new(const wasm_name_t *triple);
+
+/**
+ * Returns the triple of the host.
+ *
+ * This is a Wasmer-specific function.
+ */
+wasmer_triple_t *wasmer_triple_new_from_host(void);
+
 /**
  * Get the version of the Wasmer C API.
  *
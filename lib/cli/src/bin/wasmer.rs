@@ -1,9 +1,13 @@
 use anyhow::Result;
+#[cfg(all(feature = "engine", feature = "compiler"))]
+use wasmer_cli::commands::Bench;
 #[cfg(all(feature = "object-file", feature = "compiler"))]
 use wasmer_cli::commands::CreateExe;
 #[cfg(feature = "wast")]
 use wasmer_cli::commands::Wast;
-use wasmer_cli::commands::{Cache, Compile, Config, Inspect, Run, SelfUpdate, Validate};
+use wasmer_cli::commands::{
+    Cache, Compile, Config, Convert, Inspect, Repl, Run, SelfUpdate, Validate,
+};
 use wasmer_cli::error::PrettyError;
 
 use structopt::{clap::ErrorKind, StructOpt};
@@ -16,6 +20,10 @@ enum WasmerCLIOptions {
     #[structopt(name = "run")]
     Run(Run),
 
+    /// Call the exports of a WebAssembly file interactively
+    #[structopt(name = "repl")]
+    Repl(Repl),
+
     /// Wasmer cache
     #[structopt(name = "cache")]
     Cache(Cache),
@@ -28,6 +36,10 @@ enum WasmerCLIOptions {
     #[structopt(name = "compile")]
     Compile(Compile),
 
+    /// Convert a compiled artifact to another engine or target
+    #[structopt(name = "convert")]
+    Convert(Convert),
+
     /// Compile a WebAssembly binary into a native executable
     #[cfg(all(feature = "object-file", feature = "compiler"))]
     #[structopt(name = "create-exe")]
@@ -46,6 +58,12 @@ enum WasmerCLIOptions {
     #[structopt(name = "inspect")]
     Inspect(Inspect),
 
+    /// Benchmark the compilation, the instantiation and the calls of a
+    /// WebAssembly file
+    #[cfg(all(feature = "engine", feature = "compiler"))]
+    #[structopt(name = "bench")]
+    Bench(Bench),
+
     /// Run spec testsuite
     #[cfg(feature = "wast")]
     #[structopt(name = "wast")]
@@ -56,14 +74,18 @@ impl WasmerCLIOptions {
     fn execute(&self) -> Result<()> {
         match self {
             Self::Run(options) => options.execute(),
+            Self::Repl(repl) => repl.execute(),
             Self::SelfUpdate(options) => options.execute(),
             Self::Cache(cache) => cache.execute(),
             Self::Validate(validate) => validate.execute(),
             Self::Compile(compile) => compile.execute(),
+            Self::Convert(convert) => convert.execute(),
             #[cfg(all(feature = "object-file", feature = "compiler"))]
             Self::CreateExe(create_exe) => create_exe.execute(),
             Self::Config(config) => config.execute(),
             Self::Inspect(inspect) => inspect.execute(),
+            #[cfg(all(feature = "engine", feature = "compiler"))]
+            Self::Bench(bench) => bench.execute(),
             #[cfg(feature = "wast")]
             Self::Wast(wast) => wast.execute(),
         }
@@ -82,8 +104,10 @@ fn main() {
     let args = std::env::args().collect::<Vec<_>>();
     let command = args.get(1);
     let options = match command.unwrap_or(&"".to_string()).as_ref() {
-        "cache" | "compile" | "config" | "create-exe" | "help" | "inspect" | "run"
-        | "self-update" | "validate" | "wast" => WasmerCLIOptions::from_args(),
+        "bench" | "cache" | "compile" | "config" | "convert" | "create-exe" | "help"
+        | "inspect" | "repl" | "run" | "self-update" | "validate" | "wast" => {
+            WasmerCLIOptions::from_args()
+        }
         _ => {
             WasmerCLIOptions::from_iter_safe(args.iter()).unwrap_or_else(|e| {
                 match e.kind {
@@ -1,18 +1,27 @@
 //! The commands available in the Wasmer binary.
+#[cfg(all(feature = "engine", feature = "compiler"))]
+mod bench;
 mod cache;
 mod compile;
 mod config;
+mod convert;
 #[cfg(all(feature = "object-file", feature = "compiler"))]
 mod create_exe;
 mod inspect;
+mod repl;
 mod run;
 mod self_update;
 mod validate;
 #[cfg(feature = "wast")]
 mod wast;
 
+#[cfg(all(feature = "engine", feature = "compiler"))]
+pub use bench::*;
 #[cfg(all(feature = "object-file", feature = "compiler"))]
 pub use create_exe::*;
 #[cfg(feature = "wast")]
 pub use wast::*;
-pub use {cache::*, compile::*, config::*, inspect::*, run::*, self_update::*, validate::*};
+pub use {
+    cache::*, compile::*, config::*, convert::*, inspect::*, repl::*, run::*, self_update::*,
+    validate::*,
+};
@@ -0,0 +1,155 @@
+use crate::store::StoreOptions;
+use crate::utils::parse_val;
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use structopt::StructOpt;
+use wasmer::*;
+
+#[derive(Debug, StructOpt)]
+/// The options for the `wasmer bench` subcommand
+///
+/// Compare compilers with a comma-separated list like `--backend
+/// singlepass,cranelift,llvm`.
+pub struct Bench {
+    /// File to benchmark
+    #[structopt(name = "FILE", parse(from_os_str))]
+    path: PathBuf,
+
+    /// Function to call, with the arguments after the file
+    #[structopt(long = "invoke", short = "i")]
+    invoke: Option<String>,
+
+    /// Number of calls of the function
+    #[structopt(long = "iterations", short = "n", default_value = "1000")]
+    iterations: usize,
+
+    #[structopt(flatten)]
+    store: StoreOptions,
+
+    /// Arguments of the function
+    #[structopt(name = "--", multiple = true)]
+    args: Vec<String>,
+}
+
+/// The measurements of one compiler.
+struct Measurements {
+    compiler: String,
+    compile: Duration,
+    instantiate: Duration,
+    calls: Vec<Duration>,
+}
+
+impl Measurements {
+    /// The latency of the calls at `percentile`, the calls being sorted.
+    fn percentile(&self, percentile: usize) -> Duration {
+        let index = (self.calls.len() * percentile / 100).min(self.calls.len() - 1);
+        self.calls[index]
+    }
+}
+
+impl Bench {
+    /// Runs logic for the `bench` subcommand
+    pub fn execute(&self) -> Result<()> {
+        self.inner_execute()
+            .context(format!("failed to benchmark `{}`", self.path.display()))
+    }
+
+    fn inner_execute(&self) -> Result<()> {
+        let contents = std::fs::read(&self.path)?;
+        let mut measurements = vec![];
+        for (store, _engine_type, compiler_type) in self.store.get_stores_for_backends()? {
+            let compiler = compiler_type.to_string();
+            measurements.push(
+                self.measure(&store, &contents, compiler.clone())
+                    .with_context(|| format!("failed to benchmark with {}", compiler))?,
+            );
+        }
+        self.print(&measurements);
+        Ok(())
+    }
+
+    fn measure(&self, store: &Store, contents: &[u8], compiler: String) -> Result<Measurements> {
+        let start = Instant::now();
+        let module = Module::new(store, contents)?;
+        let compile = start.elapsed();
+
+        // The imports of the module are stubbed, so only the functions
+        // which don't call them can be benchmarked.
+        let resolver = StubResolver::new(&module, imports! {});
+        let start = Instant::now();
+        let instance = Instance::new(&module, &resolver)?;
+        let instantiate = start.elapsed();
+
+        let mut calls = vec![];
+        if let Some(invoke) = &self.invoke {
+            let function = instance.exports.get_function(invoke)?;
+            let params = function.ty().params();
+            if params.len() != self.args.len() {
+                bail!(
+                    "Function expected {} arguments, but received {}: \"{}\"",
+                    params.len(),
+                    self.args.len(),
+                    self.args.join(" ")
+                );
+            }
+            let args = self
+                .args
+                .iter()
+                .zip(params.iter())
+                .map(|(arg, ty)| parse_val(arg, *ty))
+                .collect::<Result<Vec<_>>>()?;
+            // Warm up the caches before measuring.
+            function.call(&args)?;
+            calls.reserve(self.iterations);
+            for _ in 0..self.iterations {
+                let start = Instant::now();
+                function.call(&args)?;
+                calls.push(start.elapsed());
+            }
+            calls.sort();
+        }
+
+        Ok(Measurements {
+            compiler,
+            compile,
+            instantiate,
+            calls,
+        })
+    }
+
+    fn print(&self, measurements: &[Measurements]) {
+        let duration = |duration: Duration| format!("{:?}", duration);
+        print!("{:<12}{:>14}{:>14}", "Compiler", "Compile", "Instantiate");
+        if self.invoke.is_some() {
+            print!(
+                "{:>14}{:>14}{:>14}{:>14}{:>14}",
+                "Call p50", "p90", "p99", "max", "mean"
+            );
+        }
+        println!();
+        for measurement in measurements {
+            print!(
+                "{:<12}{:>14}{:>14}",
+                measurement.compiler,
+                duration(measurement.compile),
+                duration(measurement.instantiate)
+            );
+            if !measurement.calls.is_empty() {
+                let total = measurement.calls.iter().sum::<Duration>();
+                print!(
+                    "{:>14}{:>14}{:>14}{:>14}{:>14}",
+                    duration(measurement.percentile(50)),
+                    duration(measurement.percentile(90)),
+                    duration(measurement.percentile(99)),
+                    duration(*measurement.calls.last().unwrap()),
+                    duration(total / measurement.calls.len() as u32)
+                );
+            }
+            println!();
+        }
+        if let Some(invoke) = &self.invoke {
+            println!("{} calls of `{}` per compiler", self.iterations, invoke);
+        }
+    }
+}
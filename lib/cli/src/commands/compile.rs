@@ -1,7 +1,8 @@
 use crate::store::{EngineType, StoreOptions};
+use crate::utils::target_from_options;
 use crate::warning;
 use anyhow::{Context, Result};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use structopt::StructOpt;
 use wasmer::*;
 
@@ -12,7 +13,11 @@ pub struct Compile {
     #[structopt(name = "FILE", parse(from_os_str))]
     path: PathBuf,
 
-    /// Output file
+    /// Output file.
+    ///
+    /// With the object file engine, an output path with the static
+    /// library extension of the target (`.a` or `.lib`) produces a
+    /// static library instead of a bare object file.
     #[structopt(name = "OUTPUT PATH", short = "o", parse(from_os_str))]
     output: PathBuf,
 
@@ -27,7 +32,14 @@ pub struct Compile {
     #[structopt(flatten)]
     store: StoreOptions,
 
-    #[structopt(short = "m", multiple = true)]
+    /// CPU features to enable for the target, e.g. `-m sse4.2` or
+    /// `--cpu-features neon`
+    #[structopt(
+        short = "m",
+        long = "cpu-features",
+        multiple = true,
+        use_delimiter = true
+    )]
     cpu_features: Vec<CpuFeature>,
 }
 
@@ -58,22 +70,29 @@ impl Compile {
         }
     }
 
+    /// Whether the output path asks for a static library: only the
+    /// object file engine can produce one.
+    fn is_static_library(&self, engine_type: &EngineType, target_triple: &Triple) -> bool {
+        match engine_type {
+            #[cfg(feature = "object-file")]
+            EngineType::ObjectFile => {
+                let extension =
+                    wasmer_engine_object_file::ObjectFileArtifact::get_default_static_library_extension(
+                        target_triple,
+                    );
+                self.output
+                    .extension()
+                    .map_or(false, |ext| ext == extension)
+            }
+            _ => {
+                let _ = target_triple;
+                false
+            }
+        }
+    }
+
     fn inner_execute(&self) -> Result<()> {
-        let target = self
-            .target_triple
-            .as_ref()
-            .map(|target_triple| {
-                let mut features = self
-                    .cpu_features
-                    .clone()
-                    .into_iter()
-                    .fold(CpuFeature::set(), |a, b| a | b);
-                // Cranelift requires SSE2, so we have this "hack" for now to facilitate
-                // usage
-                features |= CpuFeature::SSE2;
-                Target::new(target_triple.clone(), features)
-            })
-            .unwrap_or_default();
+        let target = target_from_options(self.target_triple.as_ref(), &self.cpu_features);
         let (store, engine_type, compiler_type) =
             self.store.get_store_for_target(target.clone())?;
         let output_filename = self
@@ -82,9 +101,10 @@ impl Compile {
             .map(|osstr| osstr.to_string_lossy().to_string())
             .unwrap_or_default();
         let recommended_extension = Self::get_recommend_extension(&engine_type, target.triple());
+        let static_library = self.is_static_library(&engine_type, target.triple());
         match self.output.extension() {
             Some(ext) => {
-                if ext != recommended_extension {
+                if ext != recommended_extension && !static_library {
                     warning!("the output file has a wrong extension. We recommend using `{}.{}` for the chosen target", &output_filename, &recommended_extension)
                 }
             }
@@ -97,11 +117,26 @@ impl Compile {
         println!("Target: {}", target.triple());
 
         let module = Module::from_file(&store, &self.path)?;
-        let _ = module.serialize_to_file(&self.output)?;
-        eprintln!(
-            "✔ File compiled successfully to `{}`.",
-            self.output.display(),
-        );
+        if static_library {
+            let working_dir = tempfile::tempdir()?;
+            let object_path = working_dir
+                .path()
+                .join(&output_filename)
+                .with_extension(recommended_extension);
+            let _ = module.serialize_to_file(&object_path)?;
+            create_static_library(&object_path, &self.output)
+                .context("failed to create the static library")?;
+            eprintln!(
+                "✔ Static library created successfully at `{}`.",
+                self.output.display(),
+            );
+        } else {
+            let _ = module.serialize_to_file(&self.output)?;
+            eprintln!(
+                "✔ File compiled successfully to `{}`.",
+                self.output.display(),
+            );
+        }
 
         #[cfg(feature = "object-file")]
         if engine_type == EngineType::ObjectFile {
@@ -143,3 +178,43 @@ impl Compile {
         Ok(())
     }
 }
+
+/// Archive the object file at `object_path` into a static library at
+/// `output_path`.
+///
+/// The archiver defaults to `ar` (`llvm-lib` on Windows) and can be
+/// overridden with the `AR` environment variable.
+fn create_static_library(object_path: &Path, output_path: &Path) -> Result<()> {
+    use std::process::Command;
+
+    // `ar` appends to existing archives, so we always start from scratch.
+    if output_path.exists() {
+        std::fs::remove_file(output_path)?;
+    }
+
+    #[cfg(not(windows))]
+    let archiver = "ar";
+    #[cfg(windows)]
+    let archiver = "llvm-lib";
+    let archiver = std::env::var("AR").unwrap_or_else(|_| archiver.to_string());
+
+    let mut command = Command::new(&archiver);
+    #[cfg(not(windows))]
+    let command = command.arg("crs").arg(output_path).arg(object_path);
+    #[cfg(windows)]
+    let command = command
+        .arg(format!("/OUT:{}", output_path.display()))
+        .arg(object_path);
+    let output = command
+        .output()
+        .with_context(|| format!("failed to run the archiver `{}`", archiver))?;
+
+    if !output.status.success() {
+        bail!(
+            "archiving failed with: stdout: {}\n\nstderr: {}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr),
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,135 @@
+use crate::commands::Compile;
+use crate::store::{EngineType, StoreOptions};
+use crate::utils::target_from_options;
+use crate::warning;
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+use structopt::StructOpt;
+use wasmer::*;
+
+#[derive(Debug, StructOpt)]
+/// The options for the `wasmer convert` subcommand
+pub struct Convert {
+    /// Serialized artifact to convert
+    #[structopt(name = "FILE", parse(from_os_str))]
+    path: PathBuf,
+
+    /// Output file
+    #[structopt(name = "OUTPUT PATH", short = "o", parse(from_os_str))]
+    output: PathBuf,
+
+    /// The original wasm of the artifact, to recompile it from when
+    /// the artifact doesn't embed it
+    #[structopt(long = "wasm", parse(from_os_str))]
+    wasm: Option<PathBuf>,
+
+    /// Compilation Target triple
+    #[structopt(long = "target")]
+    target_triple: Option<Triple>,
+
+    #[structopt(flatten)]
+    store: StoreOptions,
+
+    /// CPU features to enable for the target, e.g. `-m sse4.2` or
+    /// `--cpu-features neon`
+    #[structopt(
+        short = "m",
+        long = "cpu-features",
+        multiple = true,
+        use_delimiter = true
+    )]
+    cpu_features: Vec<CpuFeature>,
+}
+
+impl Convert {
+    /// Runs logic for the `convert` subcommand
+    pub fn execute(&self) -> Result<()> {
+        self.inner_execute()
+            .context(format!("failed to convert `{}`", self.path.display()))
+    }
+
+    /// Deserializes the artifact to convert, with the engine that
+    /// produced it.
+    fn get_module(&self) -> Result<Module> {
+        let contents = std::fs::read(&self.path)?;
+        #[cfg(feature = "native")]
+        {
+            if wasmer_engine_native::NativeArtifact::is_deserializable(&contents) {
+                let engine = wasmer_engine_native::Native::headless().engine();
+                let store = Store::new(&engine);
+                let module = unsafe { Module::deserialize_from_file(&store, &self.path) }
+                    .context("only the native artifacts for the host can be converted")?;
+                return Ok(module);
+            }
+        }
+        #[cfg(feature = "jit")]
+        {
+            if wasmer_engine_jit::JITArtifact::is_deserializable(&contents) {
+                let engine = wasmer_engine_jit::JIT::headless().engine();
+                let store = Store::new(&engine);
+                let module = unsafe { Module::deserialize(&store, &contents)? };
+                return Ok(module);
+            }
+        }
+        let _ = contents;
+        bail!("the file isn't an artifact of an engine included in this binary")
+    }
+
+    fn inner_execute(&self) -> Result<()> {
+        let target = target_from_options(self.target_triple.as_ref(), &self.cpu_features);
+        let (store, engine_type, compiler_type) =
+            self.store.get_store_for_target(target.clone())?;
+        #[cfg(feature = "object-file")]
+        if engine_type == EngineType::ObjectFile {
+            bail!("artifacts can't be converted to the object-file engine, compile the original wasm with `wasmer compile` instead");
+        }
+        let recommended_extension = Compile::get_recommend_extension(&engine_type, target.triple());
+        if self
+            .output
+            .extension()
+            .map_or(true, |ext| ext != recommended_extension)
+        {
+            let output_filename = self
+                .output
+                .file_stem()
+                .map(|osstr| osstr.to_string_lossy().to_string())
+                .unwrap_or_default();
+            warning!("the output file has a wrong extension. We recommend using `{}.{}` for the chosen target", &output_filename, &recommended_extension)
+        }
+        println!("Engine: {}", engine_type.to_string());
+        println!("Compiler: {}", compiler_type.to_string());
+        println!("Target: {}", target.triple());
+
+        let module = match &self.wasm {
+            Some(wasm) => {
+                let source = self.get_module()?;
+                let mut module = Module::from_file(&store, wasm)?;
+                if module.exports().collect::<Vec<_>>() != source.exports().collect::<Vec<_>>()
+                    || module.imports().collect::<Vec<_>>()
+                        != source.imports().collect::<Vec<_>>()
+                {
+                    bail!(
+                        "`{}` isn't the original wasm of the artifact: their imports or exports differ",
+                        wasm.display()
+                    );
+                }
+                if let Some(name) = source.name() {
+                    module.set_name(name);
+                }
+                module
+            }
+            None => match self.get_module()?.convert(&store) {
+                Err(ConvertError::WasmNotEmbedded) => bail!(
+                    "the artifact doesn't embed its original wasm: recompile it from source with `wasmer compile`, or pass the source with `--wasm`"
+                ),
+                result => result?,
+            },
+        };
+        module.serialize_to_file(&self.output)?;
+        eprintln!(
+            "✔ File converted successfully to `{}`.",
+            self.output.display(),
+        );
+        Ok(())
+    }
+}
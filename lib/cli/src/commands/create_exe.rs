@@ -1,6 +1,7 @@
 //! Create a standalone native executable for a given Wasm file.
 
 use crate::store::{CompilerOptions, EngineType};
+use crate::utils::target_from_options;
 use anyhow::{Context, Result};
 use std::env;
 use std::fs;
@@ -29,7 +30,14 @@ pub struct CreateExe {
     #[structopt(flatten)]
     compiler: CompilerOptions,
 
-    #[structopt(short = "m", multiple = true)]
+    /// CPU features to enable for the target, e.g. `-m sse4.2` or
+    /// `--cpu-features neon`
+    #[structopt(
+        short = "m",
+        long = "cpu-features",
+        multiple = true,
+        use_delimiter = true
+    )]
     cpu_features: Vec<CpuFeature>,
 
     /// Additional libraries to link against.
@@ -41,21 +49,7 @@ pub struct CreateExe {
 impl CreateExe {
     /// Runs logic for the `compile` subcommand
     pub fn execute(&self) -> Result<()> {
-        let target = self
-            .target_triple
-            .as_ref()
-            .map(|target_triple| {
-                let mut features = self
-                    .cpu_features
-                    .clone()
-                    .into_iter()
-                    .fold(CpuFeature::set(), |a, b| a | b);
-                // Cranelift requires SSE2, so we have this "hack" for now to facilitate
-                // usage
-                features |= CpuFeature::SSE2;
-                Target::new(target_triple.clone(), features)
-            })
-            .unwrap_or_default();
+        let target = target_from_options(self.target_triple.as_ref(), &self.cpu_features);
         let engine_type = EngineType::ObjectFile;
         let (store, compiler_type) = self
             .compiler
@@ -1,6 +1,7 @@
 use crate::store::StoreOptions;
 use anyhow::{Context, Result};
 use bytesize::ByteSize;
+use serde::Serialize;
 use std::path::PathBuf;
 use structopt::StructOpt;
 use wasmer::*;
@@ -12,63 +13,304 @@ pub struct Inspect {
     #[structopt(name = "FILE", parse(from_os_str))]
     path: PathBuf,
 
+    /// Print the report as JSON, for scripting
+    #[structopt(long = "json")]
+    json: bool,
+
     #[structopt(flatten)]
     store: StoreOptions,
 }
 
+/// The report of `wasmer inspect`.
+#[derive(Debug, Serialize)]
+struct Report {
+    #[serde(rename = "type")]
+    ty: &'static str,
+    size: usize,
+    imports: Vec<ImportReport>,
+    exports: Vec<ExportReport>,
+    memories: Vec<MemoryReport>,
+    tables: Vec<TableReport>,
+    features: Vec<&'static str>,
+    start_function: Option<FunctionReport>,
+    custom_sections: Vec<CustomSectionReport>,
+}
+
+#[derive(Debug, Serialize)]
+struct ImportReport {
+    module: String,
+    name: String,
+    kind: &'static str,
+    #[serde(rename = "type")]
+    ty: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ExportReport {
+    name: String,
+    kind: &'static str,
+    #[serde(rename = "type")]
+    ty: String,
+}
+
+#[derive(Debug, Serialize)]
+struct MemoryReport {
+    index: u32,
+    imported: bool,
+    minimum_pages: u32,
+    maximum_pages: Option<u32>,
+    shared: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct TableReport {
+    index: u32,
+    imported: bool,
+    element_type: String,
+    minimum: u32,
+    maximum: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+struct FunctionReport {
+    index: u32,
+    name: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct CustomSectionReport {
+    name: String,
+    size: usize,
+}
+
+/// Returns the kind of item of `ty`, and `ty` in a readable form.
+fn describe(ty: &ExternType) -> (&'static str, String) {
+    match ty {
+        ExternType::Function(ty) => ("function", ty.to_string()),
+        ExternType::Global(ty) => ("global", ty.to_string()),
+        ExternType::Table(ty) => ("table", ty.to_string()),
+        ExternType::Memory(ty) => ("memory", ty.to_string()),
+    }
+}
+
+/// Returns the proposals `wasm` requires: the ones without which it
+/// doesn't validate.
+#[cfg(feature = "compiler")]
+fn required_features(wasm: &[u8]) -> Vec<&'static str> {
+    use wasmer_compiler::wasmparser::{Validator, WasmFeatures};
+
+    let all = || WasmFeatures {
+        reference_types: true,
+        multi_value: true,
+        bulk_memory: true,
+        module_linking: true,
+        simd: true,
+        threads: true,
+        tail_call: true,
+        deterministic_only: false,
+        multi_memory: true,
+        memory64: true,
+    };
+    let proposals: [(&'static str, fn(&mut WasmFeatures)); 9] = [
+        ("threads", |f| f.threads = false),
+        ("reference-types", |f| f.reference_types = false),
+        ("simd", |f| f.simd = false),
+        ("bulk-memory", |f| f.bulk_memory = false),
+        ("multi-value", |f| f.multi_value = false),
+        ("tail-call", |f| f.tail_call = false),
+        ("module-linking", |f| f.module_linking = false),
+        ("multi-memory", |f| f.multi_memory = false),
+        ("memory64", |f| f.memory64 = false),
+    ];
+    proposals
+        .iter()
+        .filter(|(_, disable)| {
+            let mut features = all();
+            disable(&mut features);
+            let mut validator = Validator::new();
+            validator.wasm_features(features);
+            validator.validate_all(wasm).is_err()
+        })
+        .map(|(name, _)| *name)
+        .collect()
+}
+
+#[cfg(not(feature = "compiler"))]
+fn required_features(_wasm: &[u8]) -> Vec<&'static str> {
+    vec![]
+}
+
 impl Inspect {
     /// Runs logic for the `validate` subcommand
     pub fn execute(&self) -> Result<()> {
         self.inner_execute()
             .context(format!("failed to inspect `{}`", self.path.display()))
     }
+
     fn inner_execute(&self) -> Result<()> {
         let (store, _engine_type, _compiler_type) = self.store.get_store()?;
         let module_contents = std::fs::read(&self.path)?;
         let module = Module::new(&store, &module_contents)?;
-        println!(
-            "Type: {}",
-            if !is_wasm(&module_contents) {
-                "wat"
-            } else {
+        let report = self.report(&module, &module_contents)?;
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            Self::print(&report);
+        }
+        Ok(())
+    }
+
+    fn report(&self, module: &Module, module_contents: &[u8]) -> Result<Report> {
+        let info = module.info();
+        #[cfg(feature = "wat")]
+        let wasm = wat2wasm(module_contents)?;
+        #[cfg(not(feature = "wat"))]
+        let wasm = module_contents;
+        let imports = module
+            .imports()
+            .map(|import| {
+                let (kind, ty) = describe(import.ty());
+                ImportReport {
+                    module: import.module().to_string(),
+                    name: import.name().to_string(),
+                    kind,
+                    ty,
+                }
+            })
+            .collect();
+        let exports = module
+            .exports()
+            .map(|export| {
+                let (kind, ty) = describe(export.ty());
+                ExportReport {
+                    name: export.name().to_string(),
+                    kind,
+                    ty,
+                }
+            })
+            .collect();
+        let memories = info
+            .memories
+            .iter()
+            .map(|(index, memory)| MemoryReport {
+                index: index.as_u32(),
+                imported: (index.as_u32() as usize) < info.num_imported_memories,
+                minimum_pages: memory.minimum.0,
+                maximum_pages: memory.maximum.map(|pages| pages.0),
+                shared: memory.shared,
+            })
+            .collect();
+        let tables = info
+            .tables
+            .iter()
+            .map(|(index, table)| TableReport {
+                index: index.as_u32(),
+                imported: (index.as_u32() as usize) < info.num_imported_tables,
+                element_type: table.ty.to_string(),
+                minimum: table.minimum,
+                maximum: table.maximum,
+            })
+            .collect();
+        let start_function = info.start_function.map(|index| FunctionReport {
+            index: index.as_u32(),
+            name: module.function_name(index.as_u32()).map(str::to_string),
+        });
+        let custom_sections = module
+            .all_custom_sections()
+            .map(|(name, data)| CustomSectionReport {
+                name: name.to_string(),
+                size: data.len(),
+            })
+            .collect();
+        Ok(Report {
+            ty: if is_wasm(module_contents) {
                 "wasm"
-            }
-        );
-        println!("Size: {}", ByteSize(module_contents.len() as _));
+            } else {
+                "wat"
+            },
+            size: module_contents.len(),
+            imports,
+            exports,
+            memories,
+            tables,
+            features: required_features(&wasm),
+            start_function,
+            custom_sections,
+        })
+    }
+
+    fn print(report: &Report) {
+        println!("Type: {}", report.ty);
+        println!("Size: {}", ByteSize(report.size as _));
         println!("Imports:");
-        println!("  Functions:");
-        for f in module.imports().functions() {
-            println!("    \"{}\".\"{}\": {}", f.module(), f.name(), f.ty());
+        for kind in &["function", "memory", "table", "global"] {
+            println!("  {}:", plural(kind));
+            for import in report.imports.iter().filter(|import| import.kind == *kind) {
+                println!(
+                    "    \"{}\".\"{}\": {}",
+                    import.module, import.name, import.ty
+                );
+            }
         }
-        println!("  Memories:");
-        for f in module.imports().memories() {
-            println!("    \"{}\".\"{}\": {}", f.module(), f.name(), f.ty());
+        println!("Exports:");
+        for kind in &["function", "memory", "table", "global"] {
+            println!("  {}:", plural(kind));
+            for export in report.exports.iter().filter(|export| export.kind == *kind) {
+                println!("    \"{}\": {}", export.name, export.ty);
+            }
         }
-        println!("  Tables:");
-        for f in module.imports().tables() {
-            println!("    \"{}\".\"{}\": {}", f.module(), f.name(), f.ty());
+        println!("Memories:");
+        for memory in &report.memories {
+            println!(
+                "  {}: {} pages minimum, {}{}{}",
+                memory.index,
+                memory.minimum_pages,
+                match memory.maximum_pages {
+                    Some(maximum) => format!("{} pages maximum", maximum),
+                    None => "no maximum".to_string(),
+                },
+                if memory.shared { ", shared" } else { "" },
+                if memory.imported { " (imported)" } else { "" },
+            );
         }
-        println!("  Globals:");
-        for f in module.imports().globals() {
-            println!("    \"{}\".\"{}\": {}", f.module(), f.name(), f.ty());
+        println!("Tables:");
+        for table in &report.tables {
+            println!(
+                "  {}: {}, {} elements minimum, {}{}",
+                table.index,
+                table.element_type,
+                table.minimum,
+                match table.maximum {
+                    Some(maximum) => format!("{} elements maximum", maximum),
+                    None => "no maximum".to_string(),
+                },
+                if table.imported { " (imported)" } else { "" },
+            );
         }
-        println!("Exports:");
-        println!("  Functions:");
-        for f in module.exports().functions() {
-            println!("    \"{}\": {}", f.name(), f.ty());
+        println!("Required features:");
+        for feature in &report.features {
+            println!("  {}", feature);
         }
-        println!("  Memories:");
-        for f in module.exports().memories() {
-            println!("    \"{}\": {}", f.name(), f.ty());
+        match &report.start_function {
+            Some(FunctionReport {
+                index,
+                name: Some(name),
+            }) => println!("Start function: {} ({})", index, name),
+            Some(FunctionReport { index, name: None }) => println!("Start function: {}", index),
+            None => println!("Start function: none"),
         }
-        println!("  Tables:");
-        for f in module.exports().tables() {
-            println!("    \"{}\": {}", f.name(), f.ty());
+        println!("Custom sections:");
+        for section in &report.custom_sections {
+            println!("  \"{}\": {}", section.name, ByteSize(section.size as _));
         }
-        println!("  Globals:");
-        for f in module.exports().globals() {
-            println!("    \"{}\": {}", f.name(), f.ty());
-        }
-        Ok(())
+    }
+}
+
+fn plural(kind: &str) -> &'static str {
+    match kind {
+        "function" => "Functions",
+        "memory" => "Memories",
+        "table" => "Tables",
+        _ => "Globals",
     }
 }
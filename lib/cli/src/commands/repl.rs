@@ -0,0 +1,116 @@
+use crate::commands::Run;
+use crate::utils::{format_typed_val, val_type_name};
+use anyhow::{Context, Result};
+use std::io::{self, BufRead, Write};
+use structopt::StructOpt;
+use wasmer::*;
+
+#[derive(Debug, StructOpt, Clone)]
+/// The options for the `wasmer repl` subcommand
+pub struct Repl {
+    #[structopt(flatten)]
+    run: Run,
+}
+
+const HELP: &str = "\
+Commands:
+  <function> [args...]  call an exported function, e.g. `add 1 2`
+  <global>              print the value of an exported global
+  :exports              list the exports of the module
+  :help                 print this help
+  :quit                 exit the REPL";
+
+impl Repl {
+    /// Runs the REPL, calling the exports of one instance of the module
+    /// until the input ends or `:quit` is entered.
+    pub fn execute(&self) -> Result<()> {
+        let module = self.run.get_module()?;
+        let instance = self
+            .run
+            .instantiate(&module)
+            .with_context(|| "failed to instantiate the module")?;
+        print_exports(&instance);
+        println!("Type `:help` for the list of commands.");
+
+        let stdin = io::stdin();
+        let mut lines = stdin.lock().lines();
+        loop {
+            print!("> ");
+            io::stdout().flush()?;
+            let line = match lines.next() {
+                Some(line) => line?,
+                None => break,
+            };
+            let mut words = line.split_whitespace();
+            let command = match words.next() {
+                Some(command) => command,
+                None => continue,
+            };
+            let args = words.map(|arg| arg.to_string()).collect::<Vec<_>>();
+            match command {
+                ":quit" | ":q" => break,
+                ":help" | ":h" => println!("{}", HELP),
+                ":exports" => print_exports(&instance),
+                name => {
+                    if let Err(err) = self.evaluate(&instance, name, &args) {
+                        eprintln!("error: {:#}", err);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Calls the exported function `name`, or prints the exported
+    /// global `name`.
+    fn evaluate(&self, instance: &Instance, name: &str, args: &[String]) -> Result<()> {
+        if let Ok(global) = instance.exports.get_global(name) {
+            if !args.is_empty() {
+                bail!("`{}` is a global, it can't be called", name);
+            }
+            println!("{}", format_typed_val(&global.get()));
+            return Ok(());
+        }
+        let results = self.run.invoke_function(instance, name, args)?;
+        for result in results.iter() {
+            println!("{}", format_typed_val(result));
+        }
+        Ok(())
+    }
+}
+
+/// Prints the exports of `instance` with their types.
+fn print_exports(instance: &Instance) {
+    println!("Exports:");
+    for (name, export) in instance.exports.iter() {
+        match export.ty() {
+            ExternType::Function(ty) => {
+                let names = |types: &[ValType]| {
+                    types
+                        .iter()
+                        .map(|ty| val_type_name(*ty))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                };
+                println!(
+                    "  {}: func ({}) -> ({})",
+                    name,
+                    names(ty.params()),
+                    names(ty.results())
+                );
+            }
+            ExternType::Global(ty) => println!(
+                "  {}: global {}{}",
+                name,
+                if ty.mutability.is_mutable() {
+                    "mut "
+                } else {
+                    ""
+                },
+                val_type_name(ty.ty)
+            ),
+            ExternType::Memory(ty) => println!("  {}: memory {}", name, ty),
+            ExternType::Table(ty) => println!("  {}: table {}", name, ty),
+        }
+    }
+}
@@ -3,6 +3,7 @@ use crate::common::get_cache_dir;
 use crate::logging;
 use crate::store::{CompilerType, EngineType, StoreOptions};
 use crate::suggestions::suggest_function_exports;
+use crate::utils::parse_val;
 use crate::warning;
 use anyhow::{anyhow, Context, Result};
 use std::path::PathBuf;
@@ -40,6 +41,11 @@ pub struct Run {
     #[structopt(long = "command-name", hidden = true)]
     command_name: Option<String>,
 
+    /// Override the first argument (`argv[0]`) passed to the wasm program,
+    /// e.g. to pick the applet of a multicall binary
+    #[structopt(long = "argv0", name = "ARGV0")]
+    argv0: Option<String>,
+
     /// A prehashed string, used to speed up start times by avoiding hashing the
     /// wasm module. If the specified hash is not found, Wasmer will hash the module
     /// as if no `cache-key` argument was passed.
@@ -93,8 +99,7 @@ impl Run {
         let module = self.get_module()?;
         // Do we want to invoke a function?
         if let Some(ref invoke) = self.invoke {
-            let imports = imports! {};
-            let instance = Instance::new(&module, &imports)?;
+            let instance = self.instantiate(&module)?;
             let result = self.invoke_function(&instance, &invoke, &self.args)?;
             println!(
                 "{}",
@@ -126,7 +131,7 @@ impl Run {
                     &mut instance,
                     &mut em_env,
                     &mut emscripten_globals,
-                    if let Some(cn) = &self.command_name {
+                    if let Some(cn) = self.argv0.as_ref().or(self.command_name.as_ref()) {
                         cn
                     } else {
                         self.path.to_str().unwrap()
@@ -143,18 +148,9 @@ impl Run {
         {
             let wasi_version = Wasi::get_version(&module);
             if wasi_version.is_some() {
-                let program_name = self
-                    .command_name
-                    .clone()
-                    .or_else(|| {
-                        self.path
-                            .file_name()
-                            .map(|f| f.to_string_lossy().to_string())
-                    })
-                    .unwrap_or_default();
                 return self
                     .wasi
-                    .execute(module, program_name, self.args.clone())
+                    .execute(module, self.program_name(), self.args.clone())
                     .with_context(|| "WASI execution failed");
             }
         }
@@ -168,7 +164,37 @@ impl Run {
         Ok(())
     }
 
-    fn get_module(&self) -> Result<Module> {
+    /// The name of the program passed as the first argument to a WASI
+    /// program.
+    #[cfg(feature = "wasi")]
+    fn program_name(&self) -> String {
+        self.argv0
+            .clone()
+            .or_else(|| self.command_name.clone())
+            .or_else(|| {
+                self.path
+                    .file_name()
+                    .map(|f| f.to_string_lossy().to_string())
+            })
+            .unwrap_or_default()
+    }
+
+    /// Instantiates `module` without running it, with the WASI imports
+    /// if it's a WASI module, or without imports otherwise.
+    pub(crate) fn instantiate(&self, module: &Module) -> Result<Instance> {
+        #[cfg(feature = "wasi")]
+        {
+            if Wasi::get_version(module).is_some() {
+                return self
+                    .wasi
+                    .instantiate(module, self.program_name(), self.args.clone());
+            }
+        }
+        let imports = imports! {};
+        Ok(Instance::new(module, &imports)?)
+    }
+
+    pub(crate) fn get_module(&self) -> Result<Module> {
         let contents = std::fs::read(self.path.clone())?;
         #[cfg(feature = "native")]
         {
@@ -326,7 +352,9 @@ impl Run {
             .clone())
     }
 
-    fn invoke_function(
+    /// Calls the exported function `invoke` of `instance` with `args`,
+    /// parsed according to the types of its parameters.
+    pub(crate) fn invoke_function(
         &self,
         instance: &Instance,
         invoke: &str,
@@ -341,39 +369,13 @@ impl Run {
                 "Function expected {} arguments, but received {}: \"{}\"",
                 required_arguments,
                 provided_arguments,
-                self.args.join(" ")
+                args.join(" ")
             );
         }
         let invoke_args = args
             .iter()
             .zip(func_ty.params().iter())
-            .map(|(arg, param_type)| match param_type {
-                ValType::I32 => {
-                    Ok(Val::I32(arg.parse().map_err(|_| {
-                        anyhow!("Can't convert `{}` into a i32", arg)
-                    })?))
-                }
-                ValType::I64 => {
-                    Ok(Val::I64(arg.parse().map_err(|_| {
-                        anyhow!("Can't convert `{}` into a i64", arg)
-                    })?))
-                }
-                ValType::F32 => {
-                    Ok(Val::F32(arg.parse().map_err(|_| {
-                        anyhow!("Can't convert `{}` into a f32", arg)
-                    })?))
-                }
-                ValType::F64 => {
-                    Ok(Val::F64(arg.parse().map_err(|_| {
-                        anyhow!("Can't convert `{}` into a f64", arg)
-                    })?))
-                }
-                _ => Err(anyhow!(
-                    "Don't know how to convert {} into {:?}",
-                    arg,
-                    param_type
-                )),
-            })
+            .map(|(arg, param_type)| parse_val(arg, *param_type))
             .collect::<Result<Vec<_>>>()?;
         Ok(func.call(&invoke_args)?)
     }
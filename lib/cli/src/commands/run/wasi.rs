@@ -1,4 +1,4 @@
-use crate::utils::{parse_envvar, parse_mapdir};
+use crate::utils::{parse_envvar, parse_mapdir, parse_umask};
 use anyhow::{Context, Result};
 use std::path::PathBuf;
 use wasmer::{Instance, Module};
@@ -17,10 +17,20 @@ pub struct Wasi {
     #[structopt(long = "mapdir", name = "GUEST_DIR:HOST_DIR", multiple = true, parse(try_from_str = parse_mapdir))]
     mapped_dirs: Vec<(String, PathBuf)>,
 
+    /// Map a host directory to a different location for the wasm module,
+    /// which can only read it
+    #[structopt(long = "mapdir-ro", name = "RO_GUEST_DIR:HOST_DIR", multiple = true, parse(try_from_str = parse_mapdir))]
+    read_only_mapped_dirs: Vec<(String, PathBuf)>,
+
     /// Pass custom environment variables
     #[structopt(long = "env", name = "KEY=VALUE", multiple = true, parse(try_from_str = parse_envvar))]
     env_vars: Vec<(String, String)>,
 
+    /// Mask out the permissions of the files and directories created by the
+    /// wasm program, as an octal number like the `umask` of a process
+    #[structopt(long = "umask", name = "UMASK", parse(try_from_str = parse_umask))]
+    umask: Option<u32>,
+
     /// Enable experimental IO devices
     #[cfg(feature = "experimental-io-devices")]
     #[structopt(long = "enable-experimental-io-devices")]
@@ -35,8 +45,13 @@ impl Wasi {
         get_wasi_version(&module, true)
     }
 
-    /// Helper function for executing Wasi from the `Run` command.
-    pub fn execute(&self, module: Module, program_name: String, args: Vec<String>) -> Result<()> {
+    /// Instantiates `module` with the WASI imports, without running it.
+    pub fn instantiate(
+        &self,
+        module: &Module,
+        program_name: String,
+        args: Vec<String>,
+    ) -> Result<Instance> {
         let args = args.iter().cloned().map(|arg| arg.into_bytes());
 
         let mut wasi_state_builder = WasiState::new(program_name);
@@ -45,6 +60,12 @@ impl Wasi {
             .envs(self.env_vars.clone())
             .preopen_dirs(self.pre_opened_directories.clone())?
             .map_dirs(self.mapped_dirs.clone())?;
+        for (alias, host_dir) in &self.read_only_mapped_dirs {
+            wasi_state_builder.preopen(|p| p.directory(host_dir).alias(alias).read_only())?;
+        }
+        if let Some(umask) = self.umask {
+            wasi_state_builder.umask(umask);
+        }
 
         #[cfg(feature = "experimental-io-devices")]
         {
@@ -56,8 +77,12 @@ impl Wasi {
 
         let mut wasi_env = wasi_state_builder.finalize()?;
         let import_object = wasi_env.import_object(&module)?;
-        let instance = Instance::new(&module, &import_object)?;
+        Ok(Instance::new(&module, &import_object)?)
+    }
 
+    /// Helper function for executing Wasi from the `Run` command.
+    pub fn execute(&self, module: Module, program_name: String, args: Vec<String>) -> Result<()> {
+        let instance = self.instantiate(&module, program_name, args)?;
         let start = instance.exports.get_function("_start")?;
         let result = start.call(&[]);
 
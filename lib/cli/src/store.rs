@@ -51,11 +51,46 @@ pub struct CompilerOptions {
     #[structopt(long)]
     enable_verifier: bool,
 
+    /// Register the compiled code with the debuggers (GDB or LLDB),
+    /// translating the DWARF of the module if any. Only supported by
+    /// the JIT engine.
+    #[structopt(long)]
+    debug_info: bool,
+
+    /// Describe the compiled code to a profiler (`perfmap` or `jitdump`),
+    /// so the samples are attributed to the WebAssembly functions. Only
+    /// supported by the JIT engine.
+    #[structopt(long)]
+    profiler: Option<String>,
+
+    /// Embed the original wasm in the compiled artifacts, so `wasmer
+    /// convert` can re-target them to another engine or target. Only
+    /// supported by the JIT and Native engines.
+    #[structopt(long)]
+    embed_wasm: bool,
+
     /// LLVM debug directory, where IR and object files will be written to.
     #[structopt(long, parse(from_os_str))]
     llvm_debug_dir: Option<PathBuf>,
 
-    /// The deprecated backend flag - Please do not use
+    /// Directory where the optimized LLVM IR of each function is
+    /// written to, as `<function>.ll`. Only supported by LLVM.
+    #[structopt(long = "llvm-ir", parse(from_os_str))]
+    llvm_ir_dir: Option<PathBuf>,
+
+    /// Directory where the Cranelift IR of each function is written
+    /// to, as `<function>.clif`. Only supported by Cranelift.
+    #[structopt(long = "clif", parse(from_os_str))]
+    clif_dir: Option<PathBuf>,
+
+    /// Directory where the assembly of each compiled function is
+    /// written to, as `<function>.s`. Only supported by Cranelift and
+    /// LLVM.
+    #[structopt(long = "asm", parse(from_os_str))]
+    asm_dir: Option<PathBuf>,
+
+    /// The deprecated backend flag - Please do not use, except for a
+    /// comma-separated list of compilers to compare in `wasmer bench`
     #[structopt(long = "backend", hidden = true, conflicts_with_all = &["singlepass", "cranelift", "llvm"])]
     backend: Option<String>,
 
@@ -141,6 +176,12 @@ impl CompilerOptions {
                 wasmer_engine_jit::JIT::new(compiler_config)
                     .features(features)
                     .target(target)
+                    .debug_info(self.debug_info)
+                    .profiler(match &self.profiler {
+                        Some(profiler) => profiler.parse().map_err(Error::msg)?,
+                        None => Default::default(),
+                    })
+                    .embed_wasm(self.embed_wasm)
                     .engine(),
             ),
             #[cfg(feature = "native")]
@@ -148,9 +189,14 @@ impl CompilerOptions {
                 wasmer_engine_native::Native::new(compiler_config)
                     .target(target)
                     .features(features)
+                    .embed_wasm(self.embed_wasm)
                     .engine(),
             ),
             #[cfg(feature = "object-file")]
+            EngineType::ObjectFile if self.embed_wasm => {
+                bail!("The object-file engine can't embed the original wasm")
+            }
+            #[cfg(feature = "object-file")]
             EngineType::ObjectFile => Box::new(
                 wasmer_engine_object_file::ObjectFile::new(compiler_config)
                     .target(target)
@@ -168,9 +214,36 @@ impl CompilerOptions {
     }
 
     /// Get the Compiler Config for the current options
-    #[allow(unused_variables)]
     pub(crate) fn get_compiler_config(&self) -> Result<(Box<dyn CompilerConfig>, CompilerType)> {
         let compiler = self.get_compiler()?;
+        let compiler_config = self.get_compiler_config_for(&compiler)?;
+        Ok((compiler_config, compiler))
+    }
+
+    /// The compilers of a comma-separated `--backend` list, if any.
+    fn get_backends(&self) -> Result<Option<Vec<CompilerType>>> {
+        match &self.backend {
+            Some(backends) if backends.contains(',') => backends
+                .split(',')
+                .map(|backend| CompilerType::from_str(backend.trim()))
+                .collect::<Result<Vec<_>>>()
+                .map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Get the Compiler Config of `compiler` for the current options
+    #[allow(unused_variables)]
+    fn get_compiler_config_for(&self, compiler: &CompilerType) -> Result<Box<dyn CompilerConfig>> {
+        if self.llvm_ir_dir.is_some() && *compiler != CompilerType::LLVM {
+            bail!("`--llvm-ir` is only supported by the LLVM compiler");
+        }
+        if self.clif_dir.is_some() && *compiler != CompilerType::Cranelift {
+            bail!("`--clif` is only supported by the Cranelift compiler");
+        }
+        if self.asm_dir.is_some() && *compiler == CompilerType::Singlepass {
+            bail!("`--asm` is not supported by the Singlepass compiler");
+        }
         let compiler_config: Box<dyn CompilerConfig> = match compiler {
             CompilerType::Headless => bail!("The headless engine can't be chosen"),
             #[cfg(feature = "singlepass")]
@@ -183,7 +256,40 @@ impl CompilerOptions {
             }
             #[cfg(feature = "cranelift")]
             CompilerType::Cranelift => {
-                let mut config = wasmer_compiler_cranelift::Cranelift::new();
+                use std::path::Path;
+                use wasmer_compiler_cranelift::{Cranelift, CraneliftCallbacks};
+                let mut config = Cranelift::new();
+                #[derive(Debug)]
+                struct Callbacks {
+                    clif_dir: Option<PathBuf>,
+                    asm_dir: Option<PathBuf>,
+                }
+                fn write_dump(dir: &Option<PathBuf>, filename: String, contents: &str) {
+                    if let Some(dir) = dir {
+                        std::fs::write(Path::new(dir).join(filename), contents)
+                            .expect("Error while dumping the Cranelift compilation");
+                    }
+                }
+                impl CraneliftCallbacks for Callbacks {
+                    fn clif(&self, function: LocalFunctionIndex, name: Option<&str>, clif: &str) {
+                        let filename = format!("{}.clif", function_filename(function, name));
+                        write_dump(&self.clif_dir, filename, clif);
+                    }
+                    fn asm(&self, function: LocalFunctionIndex, name: Option<&str>, asm: &str) {
+                        let filename = format!("{}.s", function_filename(function, name));
+                        write_dump(&self.asm_dir, filename, asm);
+                    }
+                }
+
+                if self.clif_dir.is_some() || self.asm_dir.is_some() {
+                    for dir in self.clif_dir.iter().chain(self.asm_dir.iter()) {
+                        std::fs::create_dir_all(dir)?;
+                    }
+                    config.callbacks(Some(Arc::new(Callbacks {
+                        clif_dir: self.clif_dir.clone(),
+                        asm_dir: self.asm_dir.clone(),
+                    })));
+                }
                 if self.enable_verifier {
                     config.enable_verifier();
                 }
@@ -197,16 +303,36 @@ impl CompilerOptions {
                 use wasmer_compiler_llvm::{
                     CompiledKind, InkwellMemoryBuffer, InkwellModule, LLVMCallbacks, LLVM,
                 };
-                use wasmer_types::entity::EntityRef;
                 let mut config = LLVM::new();
                 struct Callbacks {
-                    debug_dir: PathBuf,
+                    debug_dir: Option<PathBuf>,
+                    ir_dir: Option<PathBuf>,
+                    asm_dir: Option<PathBuf>,
                 }
                 impl Callbacks {
-                    fn new(debug_dir: PathBuf) -> Result<Self> {
-                        // Create the debug dir in case it doesn't exist
-                        std::fs::create_dir_all(&debug_dir)?;
-                        Ok(Self { debug_dir })
+                    fn new(
+                        debug_dir: Option<PathBuf>,
+                        ir_dir: Option<PathBuf>,
+                        asm_dir: Option<PathBuf>,
+                    ) -> Result<Self> {
+                        // Create the dirs in case they don't exist
+                        for dir in debug_dir.iter().chain(ir_dir.iter()).chain(asm_dir.iter()) {
+                            std::fs::create_dir_all(dir)?;
+                        }
+                        Ok(Self {
+                            debug_dir,
+                            ir_dir,
+                            asm_dir,
+                        })
+                    }
+                }
+                fn write_memory_buffer(path: PathBuf, memory_buffer: &InkwellMemoryBuffer) {
+                    let mem_buf_slice = memory_buffer.as_slice();
+                    let mut file = File::create(path)
+                        .expect("Error while creating debug object file from LLVM IR");
+                    let mut pos = 0;
+                    while pos < mem_buf_slice.len() {
+                        pos += file.write(&mem_buf_slice[pos..]).unwrap();
                     }
                 }
                 // Converts a kind into a filename, that we will use to dump
@@ -229,8 +355,8 @@ impl CompilerOptions {
                 // the contents of the IR object file to.
                 fn function_kind_to_filename(kind: &CompiledKind) -> String {
                     match kind {
-                        CompiledKind::Local(local_index) => {
-                            format!("function_{}", local_index.index())
+                        CompiledKind::Local(local_index, name) => {
+                            function_filename(*local_index, name.as_deref())
                         }
                         CompiledKind::FunctionCallTrampoline(func_type) => format!(
                             "trampoline_call_{}_{}",
@@ -247,34 +373,55 @@ impl CompilerOptions {
                 }
                 impl LLVMCallbacks for Callbacks {
                     fn preopt_ir(&self, kind: &CompiledKind, module: &InkwellModule) {
-                        let mut path = self.debug_dir.clone();
-                        path.push(format!("{}.preopt.ll", function_kind_to_filename(kind)));
-                        module
-                            .print_to_file(&path)
-                            .expect("Error while dumping pre optimized LLVM IR");
+                        if let Some(debug_dir) = &self.debug_dir {
+                            let mut path = debug_dir.clone();
+                            path.push(format!("{}.preopt.ll", function_kind_to_filename(kind)));
+                            module
+                                .print_to_file(&path)
+                                .expect("Error while dumping pre optimized LLVM IR");
+                        }
                     }
                     fn postopt_ir(&self, kind: &CompiledKind, module: &InkwellModule) {
-                        let mut path = self.debug_dir.clone();
-                        path.push(format!("{}.postopt.ll", function_kind_to_filename(kind)));
-                        module
-                            .print_to_file(&path)
-                            .expect("Error while dumping post optimized LLVM IR");
+                        if let Some(debug_dir) = &self.debug_dir {
+                            let mut path = debug_dir.clone();
+                            path.push(format!("{}.postopt.ll", function_kind_to_filename(kind)));
+                            module
+                                .print_to_file(&path)
+                                .expect("Error while dumping post optimized LLVM IR");
+                        }
+                        if let (Some(ir_dir), CompiledKind::Local(..)) = (&self.ir_dir, kind) {
+                            let mut path = ir_dir.clone();
+                            path.push(format!("{}.ll", function_kind_to_filename(kind)));
+                            module
+                                .print_to_file(&path)
+                                .expect("Error while dumping LLVM IR");
+                        }
                     }
                     fn obj_memory_buffer(
                         &self,
                         kind: &CompiledKind,
                         memory_buffer: &InkwellMemoryBuffer,
                     ) {
-                        let mut path = self.debug_dir.clone();
-                        path.push(format!("{}.o", function_kind_to_filename(kind)));
-                        let mem_buf_slice = memory_buffer.as_slice();
-                        let mut file = File::create(path)
-                            .expect("Error while creating debug object file from LLVM IR");
-                        let mut pos = 0;
-                        while pos < mem_buf_slice.len() {
-                            pos += file.write(&mem_buf_slice[pos..]).unwrap();
+                        if let Some(debug_dir) = &self.debug_dir {
+                            let mut path = debug_dir.clone();
+                            path.push(format!("{}.o", function_kind_to_filename(kind)));
+                            write_memory_buffer(path, memory_buffer);
+                        }
+                    }
+                    fn asm_memory_buffer(
+                        &self,
+                        kind: &CompiledKind,
+                        memory_buffer: &InkwellMemoryBuffer,
+                    ) {
+                        if let (Some(asm_dir), CompiledKind::Local(..)) = (&self.asm_dir, kind) {
+                            let mut path = asm_dir.clone();
+                            path.push(format!("{}.s", function_kind_to_filename(kind)));
+                            write_memory_buffer(path, memory_buffer);
                         }
                     }
+                    fn wants_asm(&self) -> bool {
+                        self.asm_dir.is_some()
+                    }
                 }
 
                 impl fmt::Debug for Callbacks {
@@ -283,8 +430,15 @@ impl CompilerOptions {
                     }
                 }
 
-                if let Some(ref llvm_debug_dir) = self.llvm_debug_dir {
-                    config.callbacks(Some(Arc::new(Callbacks::new(llvm_debug_dir.clone())?)));
+                if self.llvm_debug_dir.is_some()
+                    || self.llvm_ir_dir.is_some()
+                    || self.asm_dir.is_some()
+                {
+                    config.callbacks(Some(Arc::new(Callbacks::new(
+                        self.llvm_debug_dir.clone(),
+                        self.llvm_ir_dir.clone(),
+                        self.asm_dir.clone(),
+                    )?)));
                 }
                 if self.enable_verifier {
                     config.enable_verifier();
@@ -299,7 +453,27 @@ impl CompilerOptions {
         };
 
         #[allow(unreachable_code)]
-        Ok((compiler_config, compiler))
+        Ok(compiler_config)
+    }
+}
+
+/// The name of the files the compilation of a function is dumped to:
+/// its index, followed by its name in the name section if any.
+#[cfg(any(feature = "cranelift", feature = "llvm"))]
+fn function_filename(index: LocalFunctionIndex, name: Option<&str>) -> String {
+    use wasmer_types::entity::EntityRef;
+    match name {
+        Some(name) => {
+            let name = name
+                .chars()
+                .map(|c| match c {
+                    'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '-' | '.' => c,
+                    _ => '_',
+                })
+                .collect::<String>();
+            format!("function_{}_{}", index.index(), name)
+        }
+        None => format!("function_{}", index.index()),
     }
 }
 
@@ -394,6 +568,26 @@ impl StoreOptions {
         Ok((store, engine_type, compiler_type))
     }
 
+    /// Gets a store for the host target for each compiler of the
+    /// comma-separated `--backend` list, e.g. `--backend
+    /// singlepass,cranelift`, or the store of the selected compiler
+    /// without a list.
+    pub fn get_stores_for_backends(&self) -> Result<Vec<(Store, EngineType, CompilerType)>> {
+        let compilers = match self.compiler.get_backends()? {
+            Some(compilers) => compilers,
+            None => return Ok(vec![self.get_store()?]),
+        };
+        compilers
+            .into_iter()
+            .map(|compiler| {
+                let compiler_config = self.compiler.get_compiler_config_for(&compiler)?;
+                let (engine, engine_type) =
+                    self.get_engine_with_compiler(Target::default(), compiler_config)?;
+                Ok((Store::new(&*engine), engine_type, compiler))
+            })
+            .collect()
+    }
+
     fn get_engine_with_compiler(
         &self,
         target: Target,
@@ -2,6 +2,7 @@
 use anyhow::{bail, Result};
 use std::env;
 use std::path::PathBuf;
+use wasmer::{Architecture, CpuFeature, Target, Triple, Val, ValType};
 
 /// Whether or not Wasmer should print with color
 pub fn wasmer_should_print_color() -> bool {
@@ -51,3 +52,92 @@ pub fn parse_envvar(entry: &str) -> Result<(String, String)> {
         );
     }
 }
+
+/// Parses an octal umask, e.g. `022`
+pub fn parse_umask(entry: &str) -> Result<u32> {
+    match u32::from_str_radix(entry, 8) {
+        Ok(umask) if umask <= 0o777 => Ok(umask),
+        _ => bail!(
+            "The umask must be an octal number up to 777. Found {}",
+            &entry
+        ),
+    }
+}
+
+/// Builds the compilation target from the `--target` triple and CPU
+/// features passed on the command line.
+///
+/// Without either of them, the host target is used. The CPU features
+/// apply to the host triple when no triple is provided.
+pub fn target_from_options(triple: Option<&Triple>, cpu_features: &[CpuFeature]) -> Target {
+    if triple.is_none() && cpu_features.is_empty() {
+        return Target::default();
+    }
+    let triple = triple.cloned().unwrap_or_else(Triple::host);
+    let mut features = cpu_features.iter().fold(CpuFeature::set(), |a, b| a | *b);
+    // Cranelift requires SSE2, so we have this "hack" for now to facilitate
+    // usage
+    if let Architecture::X86_32(_) | Architecture::X86_64 = triple.architecture {
+        features |= CpuFeature::SSE2;
+    }
+    Target::new(triple, features)
+}
+
+/// Returns the name of `ty` in the text format, e.g. `i32`.
+pub fn val_type_name(ty: ValType) -> &'static str {
+    match ty {
+        ValType::I32 => "i32",
+        ValType::I64 => "i64",
+        ValType::F32 => "f32",
+        ValType::F64 => "f64",
+        ValType::V128 => "v128",
+        ValType::ExternRef => "externref",
+        ValType::FuncRef => "funcref",
+    }
+}
+
+/// Parses a value of type `ty` passed on the command line, e.g. `-1`,
+/// `0xff` or `1.5`.
+///
+/// The integers can be written in hexadecimal with a `0x` prefix, and
+/// as unsigned numbers.
+pub fn parse_val(arg: &str, ty: ValType) -> Result<Val> {
+    fn parse_int<T: std::str::FromStr>(arg: &str, from_hex: fn(&str) -> Option<T>) -> Option<T> {
+        match arg.strip_prefix("0x") {
+            Some(hex) => from_hex(hex),
+            None => arg.parse().ok(),
+        }
+    }
+    let val = match ty {
+        ValType::I32 => parse_int(arg, |hex| {
+            u32::from_str_radix(hex, 16).ok().map(|v| v as i32)
+        })
+        .or_else(|| arg.parse::<u32>().ok().map(|v| v as i32))
+        .map(Val::I32),
+        ValType::I64 => parse_int(arg, |hex| {
+            u64::from_str_radix(hex, 16).ok().map(|v| v as i64)
+        })
+        .or_else(|| arg.parse::<u64>().ok().map(|v| v as i64))
+        .map(Val::I64),
+        ValType::F32 => arg.parse().ok().map(Val::F32),
+        ValType::F64 => arg.parse().ok().map(Val::F64),
+        ValType::V128 => parse_int(arg, |hex| u128::from_str_radix(hex, 16).ok()).map(Val::V128),
+        ValType::ExternRef | ValType::FuncRef => bail!(
+            "Don't know how to convert `{}` into a {}",
+            arg,
+            val_type_name(ty)
+        ),
+    };
+    match val {
+        Some(val) => Ok(val),
+        None => bail!("Can't convert `{}` into a {}", arg, val_type_name(ty)),
+    }
+}
+
+/// Formats `val` followed by its type, e.g. `42: i32`.
+pub fn format_typed_val(val: &Val) -> String {
+    match val {
+        Val::V128(v) => format!("0x{:032x}: v128", v),
+        _ => format!("{}: {}", val.to_string(), val_type_name(val.ty())),
+    }
+}
@@ -13,22 +13,25 @@ use crate::translator::{
     compiled_function_unwind_info, signature_to_cranelift_ir, transform_jump_table,
     CraneliftUnwindInfo, FuncTranslator,
 };
+use crate::HashMap;
 use cranelift_codegen::ir;
+use cranelift_codegen::isa::unwind::systemv::UnwindInfo as DwarfFDE;
+use cranelift_codegen::isa::TargetIsa;
 use cranelift_codegen::print_errors::pretty_error;
 use cranelift_codegen::{binemit, Context};
 #[cfg(feature = "unwind")]
 use gimli::write::{Address, EhFrame, FrameTable};
-use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
-use std::sync::Arc;
+use rayon::prelude::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
 use wasmer_compiler::CompileError;
 use wasmer_compiler::{CallingConvention, ModuleTranslationState, Target};
 use wasmer_compiler::{
     Compilation, CompileModuleInfo, CompiledFunction, CompiledFunctionFrameInfo,
-    CompiledFunctionUnwindInfo, Compiler, Dwarf, FunctionBody, FunctionBodyData,
-    ModuleMiddlewareChain, SectionIndex,
+    CompiledFunctionUnwindInfo, Compiler, CompilerConfig, Dwarf, FunctionBody, FunctionBodyData,
+    ModuleMiddlewareChain, ModuleToCompile, SectionIndex,
 };
 use wasmer_types::entity::{EntityRef, PrimaryMap};
-use wasmer_types::{FunctionIndex, LocalFunctionIndex, SignatureIndex};
+use wasmer_types::{FunctionIndex, LocalFunctionIndex, OwnedDataInitializer, SignatureIndex};
+use wasmer_vm::ModuleInfo;
 
 /// A compiler that compiles a WebAssembly module with Cranelift, translating the Wasm to Cranelift IR,
 /// optimizing it and then translating to assembly.
@@ -46,174 +49,166 @@ impl CraneliftCompiler {
     pub fn config(&self) -> &Cranelift {
         &self.config
     }
-}
 
-impl Compiler for CraneliftCompiler {
-    /// Compile the module using Cranelift, producing a compilation result with
-    /// associated relocations.
-    fn compile_module(
+    /// Compiles a function of a module.
+    ///
+    /// When the module has a frame table, the SystemV unwind information
+    /// of the function is returned apart, to be inserted in the frame
+    /// table of the module by [`Self::finish_compilation`].
+    #[allow(clippy::too_many_arguments)]
+    fn compile_function(
         &self,
-        target: &Target,
-        compile_info: &mut CompileModuleInfo,
+        isa: &dyn TargetIsa,
+        compile_info: &CompileModuleInfo,
+        signatures: &PrimaryMap<SignatureIndex, ir::Signature>,
         module_translation_state: &ModuleTranslationState,
-        function_body_inputs: PrimaryMap<LocalFunctionIndex, FunctionBodyData<'_>>,
-    ) -> Result<Compilation, CompileError> {
-        let isa = self.config().isa(target);
-        let frontend_config = isa.frontend_config();
-        let memory_styles = &compile_info.memory_styles;
-        let table_styles = &compile_info.table_styles;
-        let mut module = (*compile_info.module).clone();
-        self.config.middlewares.apply_on_module_info(&mut module);
-        compile_info.module = Arc::new(module);
+        func_translator: &mut FuncTranslator,
+        i: LocalFunctionIndex,
+        input: &FunctionBodyData<'_>,
+        frame_table: bool,
+    ) -> Result<(CompiledFunction, Option<DwarfFDE>), CompileError> {
         let module = &compile_info.module;
-        let signatures = module
-            .signatures
-            .iter()
-            .map(|(_sig_index, func_type)| signature_to_cranelift_ir(func_type, frontend_config))
-            .collect::<PrimaryMap<SignatureIndex, ir::Signature>>();
+        let func_index = module.func_index(i);
+        let mut context = Context::new();
+        let mut func_env = FuncEnvironment::new(
+            isa.frontend_config(),
+            module,
+            signatures,
+            &compile_info.memory_styles,
+            &compile_info.memory_bounds_checks,
+            &compile_info.table_styles,
+        );
+        context.func.name = get_function_name(func_index);
+        context.func.signature = signatures[module.functions[func_index]].clone();
+        // if generate_debug_info {
+        //     context.func.collect_debug_info();
+        // }
 
-        // Generate the frametable
-        #[cfg(feature = "unwind")]
-        let dwarf_frametable = if function_body_inputs.is_empty() {
-            // If we have no function body inputs, we don't need to
-            // construct the `FrameTable`. Constructing it, with empty
-            // FDEs will cause some issues in Linux.
-            None
-        } else {
-            use std::sync::Mutex;
-            match target.triple().default_calling_convention() {
-                Ok(CallingConvention::SystemV) => {
-                    match isa.create_systemv_cie() {
-                        Some(cie) => {
-                            let mut dwarf_frametable = FrameTable::default();
-                            let cie_id = dwarf_frametable.add_cie(cie);
-                            Some((Arc::new(Mutex::new(dwarf_frametable)), cie_id))
-                        }
-                        // Even though we are in a SystemV system, Cranelift doesn't support it
-                        None => None,
-                    }
-                }
-                _ => None,
+        func_translator.translate(
+            module_translation_state,
+            input.data,
+            input.module_offset,
+            &mut context.func,
+            &mut func_env,
+            i,
+            &self.config,
+        )?;
+        context.func.stack_limit = Some(func_env.stack_limit(&mut context.func));
+
+        let name = module.function_names.get(&func_index).map(String::as_str);
+        if let Some(callbacks) = &self.config.callbacks {
+            callbacks.clif(i, name, &context.func.display(isa).to_string());
+            context.set_disasm(true);
+        }
+
+        let mut code_buf: Vec<u8> = Vec::new();
+        let mut reloc_sink = RelocSink::new(&module, func_index);
+        let mut trap_sink = TrapSink::new();
+        let mut stackmap_sink = binemit::NullStackMapSink {};
+        context
+            .compile_and_emit(
+                isa,
+                &mut code_buf,
+                &mut reloc_sink,
+                &mut trap_sink,
+                &mut stackmap_sink,
+            )
+            .map_err(|error| {
+                CompileError::Codegen(pretty_error(&context.func, Some(isa), error))
+            })?;
+
+        if let Some(callbacks) = &self.config.callbacks {
+            let disasm = context
+                .mach_compile_result
+                .as_ref()
+                .and_then(|result| result.disasm.clone());
+            let asm = disasm.unwrap_or_else(|| context.func.display(isa).to_string());
+            callbacks.asm(i, name, &asm);
+        }
+
+        let (unwind_info, fde) = match compiled_function_unwind_info(isa, &context)? {
+            // The unwind information is inserted into the dwarf section
+            CraneliftUnwindInfo::FDE(fde) if frame_table => {
+                (Some(CompiledFunctionUnwindInfo::Dwarf), Some(fde))
             }
+            other => (other.maybe_into_to_windows_unwind(), None),
         };
 
-        let functions = function_body_inputs
-            .iter()
-            .collect::<Vec<(LocalFunctionIndex, &FunctionBodyData<'_>)>>()
-            .par_iter()
-            .map_init(FuncTranslator::new, |func_translator, (i, input)| {
-                let func_index = module.func_index(*i);
-                let mut context = Context::new();
-                let mut func_env = FuncEnvironment::new(
-                    isa.frontend_config(),
-                    module,
-                    &signatures,
-                    &memory_styles,
-                    &table_styles,
-                );
-                context.func.name = get_function_name(func_index);
-                context.func.signature = signatures[module.functions[func_index]].clone();
-                // if generate_debug_info {
-                //     context.func.collect_debug_info();
-                // }
-
-                func_translator.translate(
-                    module_translation_state,
-                    input.data,
-                    input.module_offset,
-                    &mut context.func,
-                    &mut func_env,
-                    *i,
-                    &self.config,
-                )?;
-
-                let mut code_buf: Vec<u8> = Vec::new();
-                let mut reloc_sink = RelocSink::new(&module, func_index);
-                let mut trap_sink = TrapSink::new();
-                let mut stackmap_sink = binemit::NullStackMapSink {};
-                context
-                    .compile_and_emit(
-                        &*isa,
-                        &mut code_buf,
-                        &mut reloc_sink,
-                        &mut trap_sink,
-                        &mut stackmap_sink,
-                    )
-                    .map_err(|error| {
-                        CompileError::Codegen(pretty_error(&context.func, Some(&*isa), error))
-                    })?;
-
-                let unwind_info = match compiled_function_unwind_info(&*isa, &context)? {
-                    #[cfg(feature = "unwind")]
-                    CraneliftUnwindInfo::FDE(fde) => {
-                        if let Some((dwarf_frametable, cie_id)) = &dwarf_frametable {
-                            dwarf_frametable
-                                .lock()
-                                .expect("Can't write into DWARF frametable")
-                                .add_fde(
-                                    *cie_id,
-                                    fde.to_fde(Address::Symbol {
-                                        // The symbol is the kind of relocation.
-                                        // "0" is used for functions
-                                        symbol: WriterRelocate::FUNCTION_SYMBOL,
-                                        // We use the addend as a way to specify the
-                                        // function index
-                                        addend: i.index() as _,
-                                    }),
-                                );
-                            // The unwind information is inserted into the dwarf section
-                            Some(CompiledFunctionUnwindInfo::Dwarf)
-                        } else {
-                            None
-                        }
-                    }
-                    other => other.maybe_into_to_windows_unwind(),
-                };
-
-                let address_map = get_function_address_map(&context, input, code_buf.len(), &*isa);
-
-                // We transform the Cranelift JumpTable's into compiler JumpTables
-                let func_jt_offsets = transform_jump_table(context.func.jt_offsets);
-
-                Ok(CompiledFunction {
-                    body: FunctionBody {
-                        body: code_buf,
-                        unwind_info,
-                    },
-                    jt_offsets: func_jt_offsets,
-                    relocations: reloc_sink.func_relocs,
-                    frame_info: CompiledFunctionFrameInfo {
-                        address_map,
-                        traps: trap_sink.traps,
-                    },
-                })
-            })
-            .collect::<Result<Vec<_>, CompileError>>()?
-            .into_iter()
-            .collect::<PrimaryMap<LocalFunctionIndex, _>>();
+        let address_map = get_function_address_map(&context, input, code_buf.len(), isa);
+
+        // We transform the Cranelift JumpTable's into compiler JumpTables
+        let func_jt_offsets = transform_jump_table(context.func.jt_offsets);
+
+        let function = CompiledFunction {
+            body: FunctionBody {
+                body: code_buf,
+                unwind_info,
+            },
+            jt_offsets: func_jt_offsets,
+            relocations: reloc_sink.func_relocs,
+            frame_info: CompiledFunctionFrameInfo {
+                address_map,
+                traps: trap_sink.traps,
+            },
+        };
+        Ok((function, fde))
+    }
 
+    /// Finishes the compilation of a module from its compiled functions:
+    /// generates the frame table of their SystemV unwind information,
+    /// and compiles the trampolines.
+    fn finish_compilation(
+        &self,
+        isa: &dyn TargetIsa,
+        target: &Target,
+        module: &ModuleInfo,
+        functions: Vec<(CompiledFunction, Option<DwarfFDE>)>,
+    ) -> Result<Compilation, CompileError> {
+        let frontend_config = isa.frontend_config();
+
+        // Generate the frametable
         #[cfg(feature = "unwind")]
         let (custom_sections, dwarf) = {
             let mut custom_sections = PrimaryMap::new();
-            let dwarf = if let Some((dwarf_frametable, _cie_id)) = dwarf_frametable {
-                let mut eh_frame = EhFrame(WriterRelocate::new(target.triple().endianness().ok()));
-                dwarf_frametable
-                    .lock()
-                    .unwrap()
-                    .write_eh_frame(&mut eh_frame)
-                    .unwrap();
-
-                let eh_frame_section = eh_frame.0.into_section();
-                custom_sections.push(eh_frame_section);
-                Some(Dwarf::new(SectionIndex::new(0)))
-            } else {
-                None
+            let dwarf = match isa.create_systemv_cie() {
+                Some(cie) if functions.iter().any(|(_, fde)| fde.is_some()) => {
+                    let mut dwarf_frametable = FrameTable::default();
+                    let cie_id = dwarf_frametable.add_cie(cie);
+                    for (i, (_, fde)) in functions.iter().enumerate() {
+                        if let Some(fde) = fde {
+                            dwarf_frametable.add_fde(
+                                cie_id,
+                                fde.to_fde(Address::Symbol {
+                                    // The symbol is the kind of relocation.
+                                    // "0" is used for functions
+                                    symbol: WriterRelocate::FUNCTION_SYMBOL,
+                                    // We use the addend as a way to specify the
+                                    // function index
+                                    addend: i as _,
+                                }),
+                            );
+                        }
+                    }
+                    let mut eh_frame =
+                        EhFrame(WriterRelocate::new(target.triple().endianness().ok()));
+                    dwarf_frametable.write_eh_frame(&mut eh_frame).unwrap();
+
+                    let eh_frame_section = eh_frame.0.into_section();
+                    custom_sections.push(eh_frame_section);
+                    Some(Dwarf::new(SectionIndex::new(0)))
+                }
+                _ => None,
             };
             (custom_sections, dwarf)
         };
         #[cfg(not(feature = "unwind"))]
         let (custom_sections, dwarf) = (PrimaryMap::new(), None);
 
+        let functions = functions
+            .into_iter()
+            .map(|(function, _)| function)
+            .collect::<PrimaryMap<LocalFunctionIndex, _>>();
+
         // function call trampolines (only for local functions, by signature)
         let function_call_trampolines = module
             .signatures
@@ -221,7 +216,7 @@ impl Compiler for CraneliftCompiler {
             .collect::<Vec<_>>()
             .par_iter()
             .map_init(FunctionBuilderContext::new, |mut cx, sig| {
-                make_trampoline_function_call(&*isa, &mut cx, sig)
+                make_trampoline_function_call(isa, &mut cx, sig)
             })
             .collect::<Result<Vec<FunctionBody>, CompileError>>()?
             .into_iter()
@@ -235,7 +230,7 @@ impl Compiler for CraneliftCompiler {
             .collect::<Vec<_>>()
             .par_iter()
             .map_init(FunctionBuilderContext::new, |mut cx, func_type| {
-                make_trampoline_dynamic_function(&*isa, &offsets, &mut cx, &func_type)
+                make_trampoline_dynamic_function(isa, &offsets, &mut cx, &func_type)
             })
             .collect::<Result<Vec<_>, CompileError>>()?
             .into_iter()
@@ -250,3 +245,191 @@ impl Compiler for CraneliftCompiler {
         ))
     }
 }
+
+/// Whether the unwind information of the functions goes in a frame
+/// table, with the SystemV calling convention.
+fn uses_frame_table(isa: &dyn TargetIsa, target: &Target) -> bool {
+    cfg!(feature = "unwind")
+        && matches!(
+            target.triple().default_calling_convention(),
+            Ok(CallingConvention::SystemV)
+        )
+        // Even though we are in a SystemV system, Cranelift doesn't support it
+        && isa.create_systemv_cie().is_some()
+}
+
+impl Compiler for CraneliftCompiler {
+    fn transform_data_initializers(&self, data_initializers: &mut Vec<OwnedDataInitializer>) {
+        self.config
+            .middlewares
+            .apply_on_data_initializers(data_initializers);
+    }
+
+    fn deterministic(&self) -> bool {
+        self.config.deterministic()
+    }
+
+    /// Compile the module using Cranelift, producing a compilation result with
+    /// associated relocations.
+    fn compile_module(
+        &self,
+        target: &Target,
+        compile_info: &mut CompileModuleInfo,
+        module_translation_state: &ModuleTranslationState,
+        function_body_inputs: PrimaryMap<LocalFunctionIndex, FunctionBodyData<'_>>,
+    ) -> Result<Compilation, CompileError> {
+        let isa = self.config().isa(target);
+        let frontend_config = isa.frontend_config();
+        self.config
+            .middlewares
+            .apply_on_compile_info(compile_info, &function_body_inputs);
+        let compile_info = &*compile_info;
+        let signatures = compile_info
+            .module
+            .signatures
+            .iter()
+            .map(|(_sig_index, func_type)| signature_to_cranelift_ir(func_type, frontend_config))
+            .collect::<PrimaryMap<SignatureIndex, ir::Signature>>();
+
+        // If we have no function body inputs, we don't need to
+        // construct the `FrameTable`. Constructing it, with empty
+        // FDEs will cause some issues in Linux.
+        let frame_table = !function_body_inputs.is_empty() && uses_frame_table(&*isa, target);
+
+        let functions = function_body_inputs
+            .iter()
+            .collect::<Vec<(LocalFunctionIndex, &FunctionBodyData<'_>)>>()
+            .par_iter()
+            .map_init(FuncTranslator::new, |func_translator, (i, input)| {
+                self.compile_function(
+                    &*isa,
+                    compile_info,
+                    &signatures,
+                    module_translation_state,
+                    func_translator,
+                    *i,
+                    input,
+                    frame_table,
+                )
+            })
+            .collect::<Result<Vec<_>, CompileError>>()?;
+
+        self.finish_compilation(&*isa, target, &compile_info.module, functions)
+    }
+
+    /// Compiles the modules in parallel. The identical function bodies
+    /// with the same signature, in modules with the same [`CodeLayout`],
+    /// are compiled only once.
+    ///
+    /// [`CodeLayout`]: wasmer_compiler::CodeLayout
+    fn compile_modules<'data, 'module>(
+        &self,
+        target: &Target,
+        modules: Vec<ModuleToCompile<'data, 'module>>,
+    ) -> Result<(Vec<Compilation>, usize), CompileError> {
+        // The module middlewares keep the state of the module being
+        // compiled, so the modules are compiled one after the other.
+        if !self.config.middlewares.is_empty() {
+            let compilations = modules
+                .into_iter()
+                .map(|module| {
+                    self.compile_module(
+                        target,
+                        module.compile_info,
+                        module.module_translation,
+                        module.function_body_inputs,
+                    )
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            return Ok((compilations, 0));
+        }
+
+        let isa = self.config().isa(target);
+        let frontend_config = isa.frontend_config();
+        let frame_table = uses_frame_table(&*isa, target);
+        let signatures = modules
+            .iter()
+            .map(|module| {
+                module
+                    .compile_info
+                    .module
+                    .signatures
+                    .values()
+                    .map(|func_type| signature_to_cranelift_ir(func_type, frontend_config))
+                    .collect::<PrimaryMap<SignatureIndex, ir::Signature>>()
+            })
+            .collect::<Vec<_>>();
+
+        // The functions to compile, by module and local index, and for
+        // every function of every module, the index of the function
+        // compiled for it.
+        let mut to_compile = vec![];
+        let mut sources = Vec::with_capacity(modules.len());
+        let mut layouts = HashMap::new();
+        let mut bodies = HashMap::new();
+        for (m, module) in modules.iter().enumerate() {
+            let next_layout = layouts.len();
+            let layout = *layouts
+                .entry(module.compile_info.code_layout())
+                .or_insert(next_layout);
+            let module_info = &module.compile_info.module;
+            let module_sources = module
+                .function_body_inputs
+                .iter()
+                .map(|(i, input)| {
+                    let signature = module_info.functions[module_info.func_index(i)];
+                    let next = to_compile.len();
+                    let source = *bodies
+                        .entry((layout, signature, input.data))
+                        .or_insert(next);
+                    if source == next {
+                        to_compile.push((m, i));
+                    }
+                    source
+                })
+                .collect::<Vec<_>>();
+            sources.push(module_sources);
+        }
+
+        let compiled = to_compile
+            .par_iter()
+            .map_init(FuncTranslator::new, |func_translator, (m, i)| {
+                let module = &modules[*m];
+                self.compile_function(
+                    &*isa,
+                    module.compile_info,
+                    &signatures[*m],
+                    module.module_translation,
+                    func_translator,
+                    *i,
+                    &module.function_body_inputs[*i],
+                    frame_table,
+                )
+            })
+            .collect::<Result<Vec<_>, CompileError>>()?;
+
+        let compilations = modules
+            .par_iter()
+            .zip(sources.par_iter())
+            .map(|(module, module_sources)| {
+                let functions = module
+                    .function_body_inputs
+                    .iter()
+                    .zip(module_sources)
+                    .map(|((i, input), source)| {
+                        let (function, fde) = &compiled[*source];
+                        let (original_m, original_i) = to_compile[*source];
+                        let original_offset =
+                            modules[original_m].function_body_inputs[original_i].module_offset;
+                        let function = function.reuse_at(i, original_offset, input.module_offset);
+                        (function, fde.clone())
+                    })
+                    .collect();
+                self.finish_compilation(&*isa, target, &module.compile_info.module, functions)
+            })
+            .collect::<Result<Vec<_>, CompileError>>()?;
+
+        let functions = sources.iter().map(Vec::len).sum::<usize>();
+        Ok((compilations, functions - to_compile.len()))
+    }
+}
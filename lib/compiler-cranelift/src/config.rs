@@ -1,10 +1,12 @@
 use crate::compiler::CraneliftCompiler;
 use cranelift_codegen::isa::{lookup, TargetIsa};
 use cranelift_codegen::settings::{self, Configurable};
+use std::fmt::Debug;
 use std::sync::Arc;
 use wasmer_compiler::{
-    Architecture, Compiler, CompilerConfig, CpuFeature, ModuleMiddleware, Target,
+    Architecture, CallDepthLimit, Compiler, CompilerConfig, CpuFeature, ModuleMiddleware, Target,
 };
+use wasmer_types::LocalFunctionIndex;
 
 // Runtime Environment
 
@@ -22,6 +24,19 @@ pub enum CraneliftOptLevel {
     SpeedAndSize,
 }
 
+/// Callbacks to the different Cranelift compilation phases of the
+/// functions of a module, used for debugging. `name` is the name of
+/// the function in the name section of the module, if any.
+pub trait CraneliftCallbacks: Debug + Send + Sync {
+    /// The IR of the function, before it's optimized and compiled.
+    fn clif(&self, function: LocalFunctionIndex, name: Option<&str>, clif: &str);
+
+    /// The disassembly of the compiled function when the backend of
+    /// the target provides one, or else its compiled IR, annotated
+    /// with the encodings and registers of the instructions.
+    fn asm(&self, function: LocalFunctionIndex, name: Option<&str>, asm: &str);
+}
+
 /// Global configuration options used to create an
 /// `wasmer_engine::Engine` and customize its behavior.
 ///
@@ -33,7 +48,9 @@ pub struct Cranelift {
     enable_verifier: bool,
     enable_simd: bool,
     enable_pic: bool,
+    deterministic: bool,
     opt_level: CraneliftOptLevel,
+    pub(crate) callbacks: Option<Arc<dyn CraneliftCallbacks>>,
     /// The middleware chain.
     pub(crate) middlewares: Vec<Arc<dyn ModuleMiddleware>>,
 }
@@ -48,6 +65,8 @@ impl Cranelift {
             opt_level: CraneliftOptLevel::Speed,
             enable_pic: false,
             enable_simd: true,
+            deterministic: false,
+            callbacks: None,
             middlewares: vec![],
         }
     }
@@ -73,6 +92,13 @@ impl Cranelift {
         self
     }
 
+    /// Callbacks that will triggered in the different compilation
+    /// phases in Cranelift.
+    pub fn callbacks(&mut self, callbacks: Option<Arc<dyn CraneliftCallbacks>>) -> &mut Self {
+        self.callbacks = callbacks;
+        self
+    }
+
     /// Generates the ISA for the provided target
     pub fn isa(&self, target: &Target) -> Box<dyn TargetIsa> {
         let mut builder =
@@ -84,6 +110,14 @@ impl Cranelift {
         {
             panic!("x86 support requires SSE2");
         }
+        // The remaining features are x86 flags: Cranelift doesn't expose
+        // any for the other architectures.
+        if !matches!(
+            target.triple().architecture,
+            Architecture::X86_32(_) | Architecture::X86_64
+        ) {
+            return builder.finish(self.flags());
+        }
         if cpu_features.contains(CpuFeature::SSE3) {
             builder.enable("has_sse3").expect("should be valid flag");
         }
@@ -193,6 +227,25 @@ impl CompilerConfig for Cranelift {
         self.enable_verifier = true;
     }
 
+    fn enable_frame_pointers(&mut self) {
+        // Do nothing, since cranelift already keeps the
+        // frame pointer in every function.
+    }
+
+    /// Guarantee a bit-for-bit deterministic execution.
+    fn enable_deterministic(&mut self) {
+        self.enable_nan_canonicalization = true;
+        if !self.deterministic {
+            self.deterministic = true;
+            self.middlewares.push(Arc::new(CallDepthLimit::default()));
+        }
+    }
+
+    /// Whether the execution is deterministic.
+    fn deterministic(&self) -> bool {
+        self.deterministic
+    }
+
     /// Transform it into the compiler
     fn compiler(self: Box<Self>) -> Box<dyn Compiler> {
         Box::new(CraneliftCompiler::new(*self))
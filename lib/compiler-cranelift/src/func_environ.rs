@@ -20,7 +20,7 @@ use wasmer_types::entity::PrimaryMap;
 use wasmer_types::{FunctionIndex, GlobalIndex, MemoryIndex, SignatureIndex, TableIndex};
 use wasmer_vm::VMBuiltinFunctionIndex;
 use wasmer_vm::VMOffsets;
-use wasmer_vm::{MemoryStyle, ModuleInfo, TableStyle};
+use wasmer_vm::{BoundsCheck, MemoryStyle, ModuleInfo, TableStyle};
 
 /// Compute an `ir::ExternalName` for a given wasm function index.
 pub fn get_function_name(func_index: FunctionIndex) -> ir::ExternalName {
@@ -89,6 +89,9 @@ pub struct FuncEnvironment<'module_environment> {
     /// The memory styles
     memory_styles: &'module_environment PrimaryMap<MemoryIndex, MemoryStyle>,
 
+    /// The bounds checks of the memories
+    memory_bounds_checks: &'module_environment PrimaryMap<MemoryIndex, BoundsCheck>,
+
     /// The table styles
     table_styles: &'module_environment PrimaryMap<TableIndex, TableStyle>,
 }
@@ -99,6 +102,7 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
         module: &'module_environment ModuleInfo,
         signatures: &'module_environment PrimaryMap<SignatureIndex, ir::Signature>,
         memory_styles: &'module_environment PrimaryMap<MemoryIndex, MemoryStyle>,
+        memory_bounds_checks: &'module_environment PrimaryMap<MemoryIndex, BoundsCheck>,
         table_styles: &'module_environment PrimaryMap<TableIndex, TableStyle>,
     ) -> Self {
         Self {
@@ -117,6 +121,7 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
             data_drop_sig: None,
             offsets: VMOffsets::new(target_config.pointer_bytes(), module),
             memory_styles,
+            memory_bounds_checks,
             table_styles,
         }
     }
@@ -133,6 +138,25 @@ impl<'module_environment> FuncEnvironment<'module_environment> {
         })
     }
 
+    /// Returns the stack limit of the function, which the `vmctx`
+    /// points to.
+    pub(crate) fn stack_limit(&mut self, func: &mut Function) -> ir::GlobalValue {
+        let pointer_type = self.pointer_type();
+        let vmctx = self.vmctx(func);
+        let stack_limit = func.create_global_value(ir::GlobalValueData::Load {
+            base: vmctx,
+            offset: Offset32::new(i32::try_from(self.offsets.vmctx_stack_limit()).unwrap()),
+            global_type: pointer_type,
+            readonly: true,
+        });
+        func.create_global_value(ir::GlobalValueData::Load {
+            base: stack_limit,
+            offset: Offset32::new(0),
+            global_type: pointer_type,
+            readonly: false,
+        })
+    }
+
     fn get_memory_grow_sig(&mut self, func: &mut Function) -> ir::SigRef {
         let sig = self.memory_grow_sig.unwrap_or_else(|| {
             func.import_signature(Signature {
@@ -681,9 +705,19 @@ impl<'module_environment> BaseFuncEnvironment for FuncEnvironment<'module_enviro
         };
 
         // If we have a declared maximum, we can make this a "static" heap, which is
-        // allocated up front and never moved.
-        let (offset_guard_size, heap_style, readonly_base) = match self.memory_styles[index] {
-            MemoryStyle::Dynamic { offset_guard_size } => {
+        // allocated up front and never moved. Its accesses are still checked
+        // against its current length when they must not rely on guard pages.
+        let memory_style = &self.memory_styles[index];
+        let bounds_check = self.memory_bounds_checks[index];
+        let offset_guard_size = Uimm64::new(bounds_check.offset_guard_size(memory_style));
+        let (heap_style, readonly_base) = match memory_style {
+            MemoryStyle::Static { bound, .. } if bounds_check != BoundsCheck::Explicit => (
+                ir::HeapStyle::Static {
+                    bound: Uimm64::new(bound.bytes().0 as u64),
+                },
+                true,
+            ),
+            _ => {
                 let heap_bound = func.create_global_value(ir::GlobalValueData::Load {
                     base: ptr,
                     offset: Offset32::new(current_length_offset),
@@ -691,23 +725,12 @@ impl<'module_environment> BaseFuncEnvironment for FuncEnvironment<'module_enviro
                     readonly: false,
                 });
                 (
-                    Uimm64::new(offset_guard_size),
                     ir::HeapStyle::Dynamic {
                         bound_gv: heap_bound,
                     },
-                    false,
+                    matches!(memory_style, MemoryStyle::Static { .. }),
                 )
             }
-            MemoryStyle::Static {
-                bound,
-                offset_guard_size,
-            } => (
-                Uimm64::new(offset_guard_size),
-                ir::HeapStyle::Static {
-                    bound: Uimm64::new(bound.bytes().0 as u64),
-                },
-                true,
-            ),
         };
 
         let heap_base = func.create_global_value(ir::GlobalValueData::Load {
@@ -7,15 +7,15 @@ use inkwell::memory_buffer::MemoryBuffer;
 use inkwell::module::{Linkage, Module};
 use inkwell::targets::FileType;
 use inkwell::DLLStorageClass;
-use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
-use std::sync::Arc;
+use rayon::prelude::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
 use wasmer_compiler::{
-    Compilation, CompileError, CompileModuleInfo, Compiler, CustomSection, CustomSectionProtection,
-    Dwarf, FunctionBodyData, ModuleMiddlewareChain, ModuleTranslationState, RelocationTarget,
-    SectionBody, SectionIndex, Symbol, SymbolRegistry, Target,
+    Compilation, CompileError, CompileModuleInfo, Compiler, CompilerConfig, CustomSection,
+    CustomSectionProtection, Dwarf, FunctionBodyData, ModuleMiddlewareChain, ModuleToCompile,
+    ModuleTranslationState, RelocationTarget, SectionBody, SectionIndex, Symbol, SymbolRegistry,
+    Target,
 };
 use wasmer_types::entity::{EntityRef, PrimaryMap};
-use wasmer_types::{FunctionIndex, LocalFunctionIndex, SignatureIndex};
+use wasmer_types::{FunctionIndex, LocalFunctionIndex, OwnedDataInitializer, SignatureIndex};
 
 //use std::sync::Mutex;
 
@@ -106,6 +106,7 @@ impl LLVMCompiler {
                         input,
                         self.config(),
                         &compile_info.memory_styles,
+                        &compile_info.memory_bounds_checks,
                         &compile_info.table_styles,
                         symbol_registry,
                     )?;
@@ -199,6 +200,12 @@ impl LLVMCompiler {
             .unwrap();
         if let Some(ref callbacks) = self.config.callbacks {
             callbacks.obj_memory_buffer(&CompiledKind::Module, &memory_buffer);
+            if callbacks.wants_asm() {
+                let asm_buffer = target_machine
+                    .write_to_memory_buffer(&merged_module, FileType::Assembly)
+                    .unwrap();
+                callbacks.asm_memory_buffer(&CompiledKind::Module, &asm_buffer);
+            }
         }
 
         Ok(memory_buffer.as_slice().to_vec())
@@ -206,6 +213,16 @@ impl LLVMCompiler {
 }
 
 impl Compiler for LLVMCompiler {
+    fn transform_data_initializers(&self, data_initializers: &mut Vec<OwnedDataInitializer>) {
+        self.config
+            .middlewares
+            .apply_on_data_initializers(data_initializers);
+    }
+
+    fn deterministic(&self) -> bool {
+        self.config.deterministic()
+    }
+
     fn experimental_native_compile_module<'data, 'module>(
         &self,
         target: &Target,
@@ -217,9 +234,9 @@ impl Compiler for LLVMCompiler {
         // The metadata to inject into the wasmer_metadata section of the object file.
         wasmer_metadata: &[u8],
     ) -> Option<Result<Vec<u8>, CompileError>> {
-        let mut module = (*compile_info.module).clone();
-        self.config.middlewares.apply_on_module_info(&mut module);
-        compile_info.module = Arc::new(module);
+        self.config
+            .middlewares
+            .apply_on_compile_info(compile_info, function_body_inputs);
 
         Some(self.compile_native_object(
             target,
@@ -241,12 +258,12 @@ impl Compiler for LLVMCompiler {
         function_body_inputs: PrimaryMap<LocalFunctionIndex, FunctionBodyData<'data>>,
     ) -> Result<Compilation, CompileError> {
         //let data = Arc::new(Mutex::new(0));
+        self.config
+            .middlewares
+            .apply_on_compile_info(compile_info, &function_body_inputs);
         let memory_styles = &compile_info.memory_styles;
+        let memory_bounds_checks = &compile_info.memory_bounds_checks;
         let table_styles = &compile_info.table_styles;
-
-        let mut module = (*compile_info.module).clone();
-        self.config.middlewares.apply_on_module_info(&mut module);
-        compile_info.module = Arc::new(module);
         let module = &compile_info.module;
 
         // TODO: merge constants in sections.
@@ -273,6 +290,7 @@ impl Compiler for LLVMCompiler {
                         input,
                         self.config(),
                         memory_styles,
+                        memory_bounds_checks,
                         &table_styles,
                         &ShortNames {},
                     )
@@ -389,4 +407,33 @@ impl Compiler for LLVMCompiler {
             dwarf,
         ))
     }
+
+    /// Compiles the modules in parallel, unless a module middleware
+    /// keeps the state of the module being compiled.
+    fn compile_modules<'data, 'module>(
+        &self,
+        target: &Target,
+        modules: Vec<ModuleToCompile<'data, 'module>>,
+    ) -> Result<(Vec<Compilation>, usize), CompileError> {
+        let compile = |module: ModuleToCompile<'data, 'module>| {
+            self.compile_module(
+                target,
+                module.compile_info,
+                module.module_translation,
+                module.function_body_inputs,
+            )
+        };
+        let compilations = if self.config.middlewares.is_empty() {
+            modules
+                .into_par_iter()
+                .map(compile)
+                .collect::<Result<Vec<_>, _>>()?
+        } else {
+            modules
+                .into_iter()
+                .map(compile)
+                .collect::<Result<Vec<_>, _>>()?
+        };
+        Ok((compilations, 0))
+    }
 }
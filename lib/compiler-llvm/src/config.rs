@@ -8,7 +8,7 @@ use itertools::Itertools;
 use std::fmt::Debug;
 use std::sync::Arc;
 use target_lexicon::Architecture;
-use wasmer_compiler::{Compiler, CompilerConfig, ModuleMiddleware, Target, Triple};
+use wasmer_compiler::{CallDepthLimit, Compiler, CompilerConfig, ModuleMiddleware, Target, Triple};
 use wasmer_types::{FunctionType, LocalFunctionIndex};
 
 /// The InkWell ModuleInfo type
@@ -20,8 +20,9 @@ pub type InkwellMemoryBuffer = inkwell::memory_buffer::MemoryBuffer;
 /// The compiled function kind, used for debugging in the `LLVMCallbacks`.
 #[derive(Debug, Clone)]
 pub enum CompiledKind {
-    // A locally-defined function in the Wasm file.
-    Local(LocalFunctionIndex),
+    // A locally-defined function in the Wasm file, with its name in
+    // the name section if any.
+    Local(LocalFunctionIndex, Option<String>),
     // A function call trampoline for a given signature.
     FunctionCallTrampoline(FunctionType),
     // A dynamic function trampoline for a given signature.
@@ -35,6 +36,13 @@ pub trait LLVMCallbacks: Debug + Send + Sync {
     fn preopt_ir(&self, function: &CompiledKind, module: &InkwellModule);
     fn postopt_ir(&self, function: &CompiledKind, module: &InkwellModule);
     fn obj_memory_buffer(&self, function: &CompiledKind, memory_buffer: &InkwellMemoryBuffer);
+    /// The assembly of the compiled code. Emitting it is an additional
+    /// codegen of each function, so it's skipped unless it's wanted.
+    fn asm_memory_buffer(&self, _function: &CompiledKind, _memory_buffer: &InkwellMemoryBuffer) {}
+    /// Whether `asm_memory_buffer` should be called.
+    fn wants_asm(&self) -> bool {
+        false
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -43,6 +51,8 @@ pub struct LLVM {
     pub(crate) enable_verifier: bool,
     pub(crate) opt_level: LLVMOptLevel,
     is_pic: bool,
+    pub(crate) enable_frame_pointers: bool,
+    deterministic: bool,
     pub(crate) callbacks: Option<Arc<dyn LLVMCallbacks>>,
     /// The middleware chain.
     pub(crate) middlewares: Vec<Arc<dyn ModuleMiddleware>>,
@@ -57,6 +67,8 @@ impl LLVM {
             enable_verifier: false,
             opt_level: LLVMOptLevel::Aggressive,
             is_pic: false,
+            enable_frame_pointers: false,
+            deterministic: false,
             callbacks: None,
             middlewares: vec![],
         }
@@ -206,6 +218,25 @@ impl CompilerConfig for LLVM {
         self.enable_verifier = true;
     }
 
+    /// Keep the frame pointer in every function.
+    fn enable_frame_pointers(&mut self) {
+        self.enable_frame_pointers = true;
+    }
+
+    /// Guarantee a bit-for-bit deterministic execution.
+    fn enable_deterministic(&mut self) {
+        self.enable_nan_canonicalization = true;
+        if !self.deterministic {
+            self.deterministic = true;
+            self.middlewares.push(Arc::new(CallDepthLimit::default()));
+        }
+    }
+
+    /// Whether the execution is deterministic.
+    fn deterministic(&self) -> bool {
+        self.deterministic
+    }
+
     /// Transform it into the compiler.
     fn compiler(self: Box<Self>) -> Box<dyn Compiler> {
         Box::new(LLVMCompiler::new(*self))
@@ -111,6 +111,12 @@ impl FuncTrampoline {
 
         if let Some(ref callbacks) = config.callbacks {
             callbacks.obj_memory_buffer(&function, &memory_buffer);
+            if callbacks.wants_asm() {
+                let asm_buffer = target_machine
+                    .write_to_memory_buffer(&module, FileType::Assembly)
+                    .unwrap();
+                callbacks.asm_memory_buffer(&function, &asm_buffer);
+            }
         }
 
         let mem_buf_slice = memory_buffer.as_slice();
@@ -234,6 +240,12 @@ impl FuncTrampoline {
 
         if let Some(ref callbacks) = config.callbacks {
             callbacks.obj_memory_buffer(&function, &memory_buffer);
+            if callbacks.wants_asm() {
+                let asm_buffer = target_machine
+                    .write_to_memory_buffer(&module, FileType::Assembly)
+                    .unwrap();
+                callbacks.asm_memory_buffer(&function, &asm_buffer);
+            }
         }
 
         let mem_buf_slice = memory_buffer.as_slice();
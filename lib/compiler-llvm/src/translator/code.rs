@@ -34,7 +34,7 @@ use wasmer_types::{
     FunctionIndex, FunctionType, GlobalIndex, LocalFunctionIndex, MemoryIndex, SignatureIndex,
     TableIndex, Type,
 };
-use wasmer_vm::{MemoryStyle, ModuleInfo, TableStyle};
+use wasmer_vm::{BoundsCheck, MemoryStyle, ModuleInfo, TableStyle};
 
 const FUNCTION_SECTION: &str = "__TEXT,wasmer_function";
 
@@ -78,12 +78,16 @@ impl FuncTranslator {
         function_body: &FunctionBodyData,
         config: &LLVM,
         memory_styles: &PrimaryMap<MemoryIndex, MemoryStyle>,
+        memory_bounds_checks: &PrimaryMap<MemoryIndex, BoundsCheck>,
         _table_styles: &PrimaryMap<TableIndex, TableStyle>,
         symbol_registry: &dyn SymbolRegistry,
     ) -> Result<Module, CompileError> {
-        // The function type, used for the callbacks.
-        let function = CompiledKind::Local(*local_func_index);
         let func_index = wasm_module.func_index(*local_func_index);
+        // The function type, used for the callbacks.
+        let function = CompiledKind::Local(
+            *local_func_index,
+            wasm_module.function_names.get(&func_index).cloned(),
+        );
         let function_name =
             symbol_registry.symbol_to_name(Symbol::LocalFunction(*local_func_index));
         let module_name = match wasm_module.name.as_ref() {
@@ -115,6 +119,12 @@ impl FuncTranslator {
         // TODO: figure out how many bytes long vmctx is, and mark it dereferenceable. (no need to mark it nonnull once we do this.)
         // TODO: mark vmctx nofree
         func.add_attribute(AttributeLoc::Function, intrinsics.stack_probe);
+        if config.enable_frame_pointers {
+            func.add_attribute(
+                AttributeLoc::Function,
+                self.ctx.create_string_attribute("frame-pointer", "all"),
+            );
+        }
         func.set_personality_function(intrinsics.personality);
         func.as_global_value().set_section(FUNCTION_SECTION);
         func.set_linkage(Linkage::DLLExport);
@@ -208,6 +218,7 @@ impl FuncTranslator {
             ctx: CtxType::new(wasm_module, &func, &cache_builder, &*self.abi),
             unreachable_depth: 0,
             memory_styles,
+            memory_bounds_checks,
             _table_styles,
             module: &module,
             module_translation,
@@ -287,6 +298,7 @@ impl FuncTranslator {
         function_body: &FunctionBodyData,
         config: &LLVM,
         memory_styles: &PrimaryMap<MemoryIndex, MemoryStyle>,
+        memory_bounds_checks: &PrimaryMap<MemoryIndex, BoundsCheck>,
         table_styles: &PrimaryMap<TableIndex, TableStyle>,
         symbol_registry: &dyn SymbolRegistry,
     ) -> Result<CompiledFunction, CompileError> {
@@ -297,10 +309,17 @@ impl FuncTranslator {
             function_body,
             config,
             memory_styles,
+            memory_bounds_checks,
             table_styles,
             symbol_registry,
         )?;
-        let function = CompiledKind::Local(*local_func_index);
+        let function = CompiledKind::Local(
+            *local_func_index,
+            wasm_module
+                .function_names
+                .get(&wasm_module.func_index(*local_func_index))
+                .cloned(),
+        );
         let target_machine = &self.target_machine;
         let memory_buffer = target_machine
             .write_to_memory_buffer(&module, FileType::Object)
@@ -308,6 +327,12 @@ impl FuncTranslator {
 
         if let Some(ref callbacks) = config.callbacks {
             callbacks.obj_memory_buffer(&function, &memory_buffer);
+            if callbacks.wants_asm() {
+                let asm_buffer = target_machine
+                    .write_to_memory_buffer(&module, FileType::Assembly)
+                    .unwrap();
+                callbacks.asm_memory_buffer(&function, &asm_buffer);
+            }
         }
 
         let mem_buf_slice = memory_buffer.as_slice();
@@ -937,18 +962,14 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
 
     // If this memory access must trap when out of bounds (i.e. it is a memory
     // access written in the user program as opposed to one used by our VM)
-    // then mark that it can't be delete.
+    // and it isn't checked explicitly, then mark that it can't be delete.
     fn mark_memaccess_nodelete(
         &mut self,
         memory_index: MemoryIndex,
+        memarg: &MemoryImmediate,
         memaccess: InstructionValue<'ctx>,
     ) -> Result<(), CompileError> {
-        if let MemoryCache::Static { base_ptr: _ } = self.ctx.memory(
-            memory_index,
-            self.intrinsics,
-            self.module,
-            self.memory_styles,
-        ) {
+        if !self.is_explicitly_checked(memory_index, memarg) {
             // The best we've got is `volatile`.
             // TODO: convert unwrap fail to CompileError
             memaccess.set_volatile(true).unwrap();
@@ -959,7 +980,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
     fn annotate_user_memaccess(
         &mut self,
         memory_index: MemoryIndex,
-        _memarg: &MemoryImmediate,
+        memarg: &MemoryImmediate,
         alignment: u32,
         memaccess: InstructionValue<'ctx>,
     ) -> Result<(), CompileError> {
@@ -969,7 +990,7 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
             }
             _ => {}
         };
-        self.mark_memaccess_nodelete(memory_index, memaccess)?;
+        self.mark_memaccess_nodelete(memory_index, memarg, memaccess)?;
         tbaa_label(
             &self.module,
             self.intrinsics,
@@ -979,6 +1000,13 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
         Ok(())
     }
 
+    // Whether the accesses to the memory with the offset of `memarg` are
+    // checked explicitly rather than relying on the guard pages.
+    fn is_explicitly_checked(&self, memory_index: MemoryIndex, memarg: &MemoryImmediate) -> bool {
+        self.memory_bounds_checks[memory_index]
+            .is_explicit(&self.memory_styles[memory_index], memarg.offset as u64)
+    }
+
     fn resolve_memory_ptr(
         &mut self,
         memory_index: MemoryIndex,
@@ -998,107 +1026,122 @@ impl<'ctx, 'a> LLVMFunctionCodeGenerator<'ctx, 'a> {
         let offset = builder.build_int_add(var_offset, imm_offset, "");
 
         // Look up the memory base (as pointer) and bounds (as unsigned integer).
-        let base_ptr =
-            match self
-                .ctx
-                .memory(memory_index, intrinsics, self.module, self.memory_styles)
-            {
-                MemoryCache::Dynamic {
-                    ptr_to_base_ptr,
-                    ptr_to_current_length,
-                } => {
-                    // Bounds check it.
-                    let minimum = self.wasm_module.memories[memory_index].minimum;
-                    let value_size_v = intrinsics.i64_ty.const_int(value_size as u64, false);
-                    let ptr_in_bounds = if offset.is_const() {
-                        // When the offset is constant, if it's below the minimum
-                        // memory size, we've statically shown that it's safe.
-                        let load_offset_end = offset.const_add(value_size_v);
-                        let ptr_in_bounds = load_offset_end.const_int_compare(
-                            IntPredicate::ULE,
-                            intrinsics.i64_ty.const_int(minimum.bytes().0 as u64, false),
-                        );
-                        if ptr_in_bounds.get_zero_extended_constant() == Some(1) {
-                            Some(ptr_in_bounds)
-                        } else {
-                            None
-                        }
+        let explicitly_checked = self.is_explicitly_checked(memory_index, memarg);
+        let base_ptr = match self.ctx.memory(
+            memory_index,
+            intrinsics,
+            self.module,
+            self.memory_styles,
+            self.memory_bounds_checks,
+        ) {
+            MemoryCache::Dynamic {
+                ptr_to_base_ptr,
+                ptr_to_current_length,
+            } if explicitly_checked => {
+                // Bounds check it.
+                let minimum = self.wasm_module.memories[memory_index].minimum;
+                let value_size_v = intrinsics.i64_ty.const_int(value_size as u64, false);
+                let ptr_in_bounds = if offset.is_const() {
+                    // When the offset is constant, if it's below the minimum
+                    // memory size, we've statically shown that it's safe.
+                    let load_offset_end = offset.const_add(value_size_v);
+                    let ptr_in_bounds = load_offset_end.const_int_compare(
+                        IntPredicate::ULE,
+                        intrinsics.i64_ty.const_int(minimum.bytes().0 as u64, false),
+                    );
+                    if ptr_in_bounds.get_zero_extended_constant() == Some(1) {
+                        Some(ptr_in_bounds)
                     } else {
                         None
                     }
-                    .unwrap_or_else(|| {
-                        let load_offset_end = builder.build_int_add(offset, value_size_v, "");
-
-                        let current_length = builder
-                            .build_load(ptr_to_current_length, "")
-                            .into_int_value();
-                        tbaa_label(
-                            self.module,
-                            self.intrinsics,
-                            format!("memory {} length", memory_index.as_u32()),
-                            current_length.as_instruction_value().unwrap(),
-                        );
-                        let current_length =
-                            builder.build_int_z_extend(current_length, intrinsics.i64_ty, "");
+                } else {
+                    None
+                }
+                .unwrap_or_else(|| {
+                    let load_offset_end = builder.build_int_add(offset, value_size_v, "");
 
-                        builder.build_int_compare(
-                            IntPredicate::ULE,
-                            load_offset_end,
-                            current_length,
-                            "",
-                        )
-                    });
-                    if !ptr_in_bounds.is_constant_int()
-                        || ptr_in_bounds.get_zero_extended_constant().unwrap() != 1
-                    {
-                        // LLVM may have folded this into 'i1 true' in which case we know
-                        // the pointer is in bounds. LLVM may also have folded it into a
-                        // constant expression, not known to be either true or false yet.
-                        // If it's false, unknown-but-constant, or not-a-constant, emit a
-                        // runtime bounds check. LLVM may yet succeed at optimizing it away.
-                        let ptr_in_bounds = builder
-                            .build_call(
-                                intrinsics.expect_i1,
-                                &[
-                                    ptr_in_bounds.as_basic_value_enum(),
-                                    intrinsics.i1_ty.const_int(1, true).as_basic_value_enum(),
-                                ],
-                                "ptr_in_bounds_expect",
-                            )
-                            .try_as_basic_value()
-                            .left()
-                            .unwrap()
-                            .into_int_value();
-
-                        let in_bounds_continue_block =
-                            context.append_basic_block(*function, "in_bounds_continue_block");
-                        let not_in_bounds_block =
-                            context.append_basic_block(*function, "not_in_bounds_block");
-                        builder.build_conditional_branch(
-                            ptr_in_bounds,
-                            in_bounds_continue_block,
-                            not_in_bounds_block,
-                        );
-                        builder.position_at_end(not_in_bounds_block);
-                        builder.build_call(
-                            intrinsics.throw_trap,
-                            &[intrinsics.trap_memory_oob],
-                            "throw",
-                        );
-                        builder.build_unreachable();
-                        builder.position_at_end(in_bounds_continue_block);
-                    }
-                    let ptr_to_base = builder.build_load(ptr_to_base_ptr, "").into_pointer_value();
+                    let current_length = builder
+                        .build_load(ptr_to_current_length, "")
+                        .into_int_value();
                     tbaa_label(
                         self.module,
                         self.intrinsics,
-                        format!("memory base_ptr {}", memory_index.as_u32()),
-                        ptr_to_base.as_instruction_value().unwrap(),
+                        format!("memory {} length", memory_index.as_u32()),
+                        current_length.as_instruction_value().unwrap(),
+                    );
+                    let current_length =
+                        builder.build_int_z_extend(current_length, intrinsics.i64_ty, "");
+
+                    builder.build_int_compare(
+                        IntPredicate::ULE,
+                        load_offset_end,
+                        current_length,
+                        "",
+                    )
+                });
+                if !ptr_in_bounds.is_constant_int()
+                    || ptr_in_bounds.get_zero_extended_constant().unwrap() != 1
+                {
+                    // LLVM may have folded this into 'i1 true' in which case we know
+                    // the pointer is in bounds. LLVM may also have folded it into a
+                    // constant expression, not known to be either true or false yet.
+                    // If it's false, unknown-but-constant, or not-a-constant, emit a
+                    // runtime bounds check. LLVM may yet succeed at optimizing it away.
+                    let ptr_in_bounds = builder
+                        .build_call(
+                            intrinsics.expect_i1,
+                            &[
+                                ptr_in_bounds.as_basic_value_enum(),
+                                intrinsics.i1_ty.const_int(1, true).as_basic_value_enum(),
+                            ],
+                            "ptr_in_bounds_expect",
+                        )
+                        .try_as_basic_value()
+                        .left()
+                        .unwrap()
+                        .into_int_value();
+
+                    let in_bounds_continue_block =
+                        context.append_basic_block(*function, "in_bounds_continue_block");
+                    let not_in_bounds_block =
+                        context.append_basic_block(*function, "not_in_bounds_block");
+                    builder.build_conditional_branch(
+                        ptr_in_bounds,
+                        in_bounds_continue_block,
+                        not_in_bounds_block,
+                    );
+                    builder.position_at_end(not_in_bounds_block);
+                    builder.build_call(
+                        intrinsics.throw_trap,
+                        &[intrinsics.trap_memory_oob],
+                        "throw",
                     );
-                    ptr_to_base
+                    builder.build_unreachable();
+                    builder.position_at_end(in_bounds_continue_block);
                 }
-                MemoryCache::Static { base_ptr } => base_ptr,
-            };
+                let ptr_to_base = builder.build_load(ptr_to_base_ptr, "").into_pointer_value();
+                tbaa_label(
+                    self.module,
+                    self.intrinsics,
+                    format!("memory base_ptr {}", memory_index.as_u32()),
+                    ptr_to_base.as_instruction_value().unwrap(),
+                );
+                ptr_to_base
+            }
+            MemoryCache::Dynamic {
+                ptr_to_base_ptr, ..
+            } => {
+                let ptr_to_base = builder.build_load(ptr_to_base_ptr, "").into_pointer_value();
+                tbaa_label(
+                    self.module,
+                    self.intrinsics,
+                    format!("memory base_ptr {}", memory_index.as_u32()),
+                    ptr_to_base.as_instruction_value().unwrap(),
+                );
+                ptr_to_base
+            }
+            MemoryCache::Static { base_ptr } => base_ptr,
+        };
         let value_ptr = unsafe { builder.build_gep(base_ptr, &[offset], "") };
         Ok(builder
             .build_bitcast(value_ptr, ptr_ty, "")
@@ -1299,6 +1342,7 @@ pub struct LLVMFunctionCodeGenerator<'ctx, 'a> {
     ctx: CtxType<'ctx, 'a>,
     unreachable_depth: usize,
     memory_styles: &'a PrimaryMap<MemoryIndex, MemoryStyle>,
+    memory_bounds_checks: &'a PrimaryMap<MemoryIndex, BoundsCheck>,
     _table_styles: &'a PrimaryMap<TableIndex, TableStyle>,
 
     // This is support for stackmaps:
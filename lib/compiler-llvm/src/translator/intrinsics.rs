@@ -27,7 +27,7 @@ use wasmer_types::{
     Mutability, SignatureIndex, TableIndex, Type,
 };
 use wasmer_vm::ModuleInfo as WasmerCompilerModule;
-use wasmer_vm::{MemoryStyle, TrapCode, VMBuiltinFunctionIndex, VMOffsets};
+use wasmer_vm::{BoundsCheck, MemoryStyle, TrapCode, VMBuiltinFunctionIndex, VMOffsets};
 
 pub fn type_to_llvm_ptr<'ctx>(
     intrinsics: &Intrinsics<'ctx>,
@@ -586,6 +586,7 @@ impl<'ctx, 'a> CtxType<'ctx, 'a> {
         intrinsics: &Intrinsics<'ctx>,
         module: &Module<'ctx>,
         memory_styles: &PrimaryMap<MemoryIndex, MemoryStyle>,
+        memory_bounds_checks: &PrimaryMap<MemoryIndex, BoundsCheck>,
     ) -> MemoryCache<'ctx> {
         let (cached_memories, wasm_module, ctx_ptr_value, cache_builder, offsets) = (
             &mut self.cached_memories,
@@ -595,6 +596,7 @@ impl<'ctx, 'a> CtxType<'ctx, 'a> {
             &self.offsets,
         );
         let memory_style = &memory_styles[index];
+        let bounds_check = memory_bounds_checks[index];
         *cached_memories.entry(index).or_insert_with(|| {
             let memory_definition_ptr =
                 if let Some(local_memory_index) = wasm_module.local_memory_index(index) {
@@ -638,7 +640,11 @@ impl<'ctx, 'a> CtxType<'ctx, 'a> {
                     "",
                 )
                 .unwrap();
-            if let MemoryStyle::Dynamic { .. } = memory_style {
+            // The accesses to a static memory may still be checked
+            // against its current length, depending on the bounds check.
+            let static_only = matches!(memory_style, MemoryStyle::Static { .. })
+                && bounds_check == BoundsCheck::GuardPages;
+            if !static_only {
                 let current_length_ptr = cache_builder
                     .build_struct_gep(
                         memory_definition_ptr,
@@ -20,7 +20,9 @@ use wasmer_types::{
     FunctionIndex, GlobalIndex, LocalFunctionIndex, LocalMemoryIndex, MemoryIndex, SignatureIndex,
     TableIndex, Type,
 };
-use wasmer_vm::{MemoryStyle, ModuleInfo, TableStyle, TrapCode, VMBuiltinFunctionIndex, VMOffsets};
+use wasmer_vm::{
+    BoundsCheck, MemoryStyle, ModuleInfo, TableStyle, TrapCode, VMBuiltinFunctionIndex, VMOffsets,
+};
 
 /// The singlepass per-function code generator.
 pub struct FuncGen<'a> {
@@ -37,6 +39,9 @@ pub struct FuncGen<'a> {
     // // Memory plans.
     memory_styles: &'a PrimaryMap<MemoryIndex, MemoryStyle>,
 
+    /// Bounds checks of the memories.
+    memory_bounds_checks: &'a PrimaryMap<MemoryIndex, BoundsCheck>,
+
     // // Table plans.
     // table_styles: &'a PrimaryMap<TableIndex, TableStyle>,
     /// Function signature.
@@ -97,6 +102,7 @@ struct SpecialLabelSet {
     table_access_oob: DynamicLabel,
     indirect_call_null: DynamicLabel,
     bad_signature: DynamicLabel,
+    stack_overflow: DynamicLabel,
 }
 
 /// A trap table for a `RunnableModuleInfo`.
@@ -1244,10 +1250,10 @@ impl<'a> FuncGen<'a> {
         value_size: usize,
         cb: F,
     ) -> Result<(), CodegenError> {
-        let need_check = match self.memory_styles[MemoryIndex::new(0)] {
-            MemoryStyle::Static { .. } => false,
-            MemoryStyle::Dynamic { .. } => true,
-        };
+        let need_check = self.memory_bounds_checks[MemoryIndex::new(0)].is_explicit(
+            &self.memory_styles[MemoryIndex::new(0)],
+            memarg.offset as u64,
+        );
         let tmp_addr = self.machine.acquire_temp_gpr().unwrap();
 
         // Reusing `tmp_addr` for temporary indirection here, since it's not used before the last reference to `{base,bound}_loc`.
@@ -1736,6 +1742,28 @@ impl<'a> FuncGen<'a> {
         id
     }
 
+    /// Traps if the stack pointer is below the stack limit, see
+    /// `wasmer_vm::StackLimit`. It's checked before the calls, so the
+    /// leaf functions don't access the `vmctx`.
+    fn emit_stack_check(&mut self) {
+        let tmp = self.machine.acquire_temp_gpr().unwrap();
+        self.assembler.emit_mov(
+            Size::S64,
+            Location::Memory(
+                Machine::get_vmctx_reg(),
+                self.vmoffsets.vmctx_stack_limit() as i32,
+            ),
+            Location::GPR(tmp),
+        );
+        self.assembler
+            .emit_mov(Size::S64, Location::Memory(tmp, 0), Location::GPR(tmp));
+        self.assembler
+            .emit_cmp(Size::S64, Location::GPR(tmp), Location::GPR(GPR::RSP));
+        self.assembler
+            .emit_jmp(Condition::BelowEqual, self.special_labels.stack_overflow);
+        self.machine.release_temp_gpr(tmp);
+    }
+
     fn emit_head(&mut self) -> Result<(), CodegenError> {
         // TODO: Patchpoint is not emitted for now, and ARM trampoline is not prepended.
 
@@ -1812,6 +1840,7 @@ impl<'a> FuncGen<'a> {
         config: &'a Singlepass,
         vmoffsets: &'a VMOffsets,
         memory_styles: &'a PrimaryMap<MemoryIndex, MemoryStyle>,
+        memory_bounds_checks: &'a PrimaryMap<MemoryIndex, BoundsCheck>,
         _table_styles: &'a PrimaryMap<TableIndex, TableStyle>,
         local_func_index: LocalFunctionIndex,
         local_types_excluding_arguments: &[WpType],
@@ -1843,6 +1872,7 @@ impl<'a> FuncGen<'a> {
             table_access_oob: assembler.get_label(),
             indirect_call_null: assembler.get_label(),
             bad_signature: assembler.get_label(),
+            stack_overflow: assembler.get_label(),
         };
 
         let mut fg = FuncGen {
@@ -1850,6 +1880,7 @@ impl<'a> FuncGen<'a> {
             config,
             vmoffsets,
             memory_styles,
+            memory_bounds_checks,
             // table_styles,
             signature,
             assembler,
@@ -5144,6 +5175,7 @@ impl<'a> FuncGen<'a> {
             }
 
             Operator::Call { function_index } => {
+                self.emit_stack_check();
                 let function_index = function_index as usize;
 
                 let sig_index = *self
@@ -5247,6 +5279,7 @@ impl<'a> FuncGen<'a> {
                 }
             }
             Operator::CallIndirect { index, table_index } => {
+                self.emit_stack_check();
                 if table_index != 0 {
                     return Err(CodegenError {
                         message: "CallIndirect: table_index is not 0".to_string(),
@@ -8209,6 +8242,11 @@ impl<'a> FuncGen<'a> {
         self.mark_address_with_trap_code(TrapCode::BadSignature);
         self.assembler.emit_ud2();
 
+        self.assembler
+            .emit_label(self.special_labels.stack_overflow);
+        self.mark_address_with_trap_code(TrapCode::StackOverflow);
+        self.assembler.emit_ud2();
+
         // Notify the assembler backend to generate necessary code at end of function.
         self.assembler.finalize_function();
 
@@ -8312,11 +8350,15 @@ pub fn gen_std_trampoline(sig: &FunctionType) -> FunctionBody {
         }
     }
 
-    // Align to 16 bytes. We push two 8-byte registers below, so here we need to ensure stack_offset % 16 == 8.
-    if stack_offset % 16 != 8 {
+    // Align to 16 bytes. We push three 8-byte registers below, so here we need to ensure stack_offset % 16 == 0.
+    if stack_offset % 16 != 0 {
         stack_offset += 8;
     }
 
+    // Keep the frame pointer, so that native stack walkers can go through the trampoline.
+    a.emit_push(Size::S64, Location::GPR(GPR::RBP));
+    a.emit_mov(Size::S64, Location::GPR(GPR::RSP), Location::GPR(GPR::RBP));
+
     // Used callee-saved registers
     a.emit_push(Size::S64, Location::GPR(GPR::R15));
     a.emit_push(Size::S64, Location::GPR(GPR::R14));
@@ -8390,6 +8432,7 @@ pub fn gen_std_trampoline(sig: &FunctionType) -> FunctionBody {
     // Restore callee-saved registers.
     a.emit_pop(Size::S64, Location::GPR(GPR::R14));
     a.emit_pop(Size::S64, Location::GPR(GPR::R15));
+    a.emit_pop(Size::S64, Location::GPR(GPR::RBP));
 
     a.emit_ret();
 
@@ -8406,8 +8449,12 @@ pub fn gen_std_dynamic_import_trampoline(
 ) -> FunctionBody {
     let mut a = Assembler::new().unwrap();
 
+    // Keep the frame pointer, so that native stack walkers can go through the trampoline.
+    a.emit_push(Size::S64, Location::GPR(GPR::RBP));
+    a.emit_mov(Size::S64, Location::GPR(GPR::RSP), Location::GPR(GPR::RBP));
+
     // Allocate argument array.
-    let stack_offset: usize = 16 * std::cmp::max(sig.params().len(), sig.results().len()) + 8; // 16 bytes each + 8 bytes sysv call padding
+    let stack_offset: usize = 16 * std::cmp::max(sig.params().len(), sig.results().len()); // 16 bytes each
     a.emit_sub(
         Size::S64,
         Location::Imm32(stack_offset as _),
@@ -8428,7 +8475,10 @@ pub fn gen_std_dynamic_import_trampoline(
                 None => {
                     a.emit_mov(
                         Size::S64,
-                        Location::Memory(GPR::RSP, (stack_offset + 8 + stack_param_count * 8) as _),
+                        Location::Memory(
+                            GPR::RSP,
+                            (stack_offset + 16 + stack_param_count * 8) as _,
+                        ),
                         Location::GPR(GPR::RAX),
                     );
                     stack_param_count += 1;
@@ -8482,6 +8532,7 @@ pub fn gen_std_dynamic_import_trampoline(
         Location::Imm32(stack_offset as _),
         Location::GPR(GPR::RSP),
     );
+    a.emit_pop(Size::S64, Location::GPR(GPR::RBP));
 
     // Return.
     a.emit_ret();
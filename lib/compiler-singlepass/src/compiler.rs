@@ -12,12 +12,14 @@ use std::sync::Arc;
 use wasmer_compiler::TrapInformation;
 use wasmer_compiler::{
     Architecture, CompileModuleInfo, CompilerConfig, MiddlewareBinaryReader, ModuleMiddlewareChain,
-    ModuleTranslationState, OperatingSystem, Target,
+    ModuleToCompile, ModuleTranslationState, OperatingSystem, Target,
 };
 use wasmer_compiler::{Compilation, CompileError, CompiledFunction, Compiler, SectionIndex};
 use wasmer_compiler::{FunctionBody, FunctionBodyData};
 use wasmer_types::entity::{EntityRef, PrimaryMap};
-use wasmer_types::{FunctionIndex, FunctionType, LocalFunctionIndex, MemoryIndex, TableIndex};
+use wasmer_types::{
+    FunctionIndex, FunctionType, LocalFunctionIndex, MemoryIndex, OwnedDataInitializer, TableIndex,
+};
 use wasmer_vm::{ModuleInfo, TrapCode, VMOffsets};
 
 /// A compiler that compiles a WebAssembly module with Singlepass.
@@ -39,6 +41,16 @@ impl SinglepassCompiler {
 }
 
 impl Compiler for SinglepassCompiler {
+    fn transform_data_initializers(&self, data_initializers: &mut Vec<OwnedDataInitializer>) {
+        self.config
+            .middlewares
+            .apply_on_data_initializers(data_initializers);
+    }
+
+    fn deterministic(&self) -> bool {
+        self.config.deterministic()
+    }
+
     /// Compile the module using Singlepass, producing a compilation result with
     /// associated relocations.
     fn compile_module(
@@ -59,11 +71,12 @@ impl Compiler for SinglepassCompiler {
         if compile_info.features.multi_value {
             return Err(CompileError::UnsupportedFeature("multivalue".to_string()));
         }
+        self.config
+            .middlewares
+            .apply_on_compile_info(compile_info, &function_body_inputs);
         let memory_styles = &compile_info.memory_styles;
+        let memory_bounds_checks = &compile_info.memory_bounds_checks;
         let table_styles = &compile_info.table_styles;
-        let mut module = (*compile_info.module).clone();
-        self.config.middlewares.apply_on_module_info(&mut module);
-        compile_info.module = Arc::new(module);
         let vmoffsets = VMOffsets::new(8, &compile_info.module);
         let module = &compile_info.module;
         let import_trampolines: PrimaryMap<SectionIndex, _> = (0..module.num_imported_functions)
@@ -104,6 +117,7 @@ impl Compiler for SinglepassCompiler {
                     &self.config,
                     &vmoffsets,
                     &memory_styles,
+                    memory_bounds_checks,
                     &table_styles,
                     *i,
                     &locals,
@@ -150,6 +164,35 @@ impl Compiler for SinglepassCompiler {
             None,
         ))
     }
+
+    /// Compiles the modules in parallel, unless a module middleware
+    /// keeps the state of the module being compiled.
+    fn compile_modules<'data, 'module>(
+        &self,
+        target: &Target,
+        modules: Vec<ModuleToCompile<'data, 'module>>,
+    ) -> Result<(Vec<Compilation>, usize), CompileError> {
+        let compile = |module: ModuleToCompile<'data, 'module>| {
+            self.compile_module(
+                target,
+                module.compile_info,
+                module.module_translation,
+                module.function_body_inputs,
+            )
+        };
+        let compilations = if self.config.middlewares.is_empty() {
+            modules
+                .into_par_iter()
+                .map(compile)
+                .collect::<Result<Vec<_>, _>>()?
+        } else {
+            modules
+                .into_iter()
+                .map(compile)
+                .collect::<Result<Vec<_>, _>>()?
+        };
+        Ok((compilations, 0))
+    }
 }
 
 trait ToCompileError {
@@ -172,7 +215,7 @@ mod tests {
     use std::str::FromStr;
     use target_lexicon::triple;
     use wasmer_compiler::{CpuFeature, Features, Triple};
-    use wasmer_vm::{MemoryStyle, TableStyle};
+    use wasmer_vm::{BoundsCheck, MemoryStyle, TableStyle};
 
     fn dummy_compilation_ingredients<'a>() -> (
         CompileModuleInfo,
@@ -183,6 +226,7 @@ mod tests {
             features: Features::new(),
             module: Arc::new(ModuleInfo::new()),
             memory_styles: PrimaryMap::<MemoryIndex, MemoryStyle>::new(),
+            memory_bounds_checks: PrimaryMap::<MemoryIndex, BoundsCheck>::new(),
             table_styles: PrimaryMap::<TableIndex, TableStyle>::new(),
         };
         let module_translation = ModuleTranslationState::new();
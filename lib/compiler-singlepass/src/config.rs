@@ -3,13 +3,16 @@
 
 use crate::compiler::SinglepassCompiler;
 use std::sync::Arc;
-use wasmer_compiler::{Compiler, CompilerConfig, CpuFeature, ModuleMiddleware, Target};
+use wasmer_compiler::{
+    CallDepthLimit, Compiler, CompilerConfig, CpuFeature, ModuleMiddleware, Target,
+};
 use wasmer_types::Features;
 
 #[derive(Debug, Clone)]
 pub struct Singlepass {
     pub(crate) enable_nan_canonicalization: bool,
     pub(crate) enable_stack_check: bool,
+    deterministic: bool,
     /// The middleware chain.
     pub(crate) middlewares: Vec<Arc<dyn ModuleMiddleware>>,
 }
@@ -21,6 +24,7 @@ impl Singlepass {
         Self {
             enable_nan_canonicalization: true,
             enable_stack_check: false,
+            deterministic: false,
             middlewares: vec![],
         }
     }
@@ -53,6 +57,25 @@ impl CompilerConfig for Singlepass {
         // PIC code.
     }
 
+    fn enable_frame_pointers(&mut self) {
+        // Do nothing, since singlepass already keeps the
+        // frame pointer in every function.
+    }
+
+    /// Guarantee a bit-for-bit deterministic execution.
+    fn enable_deterministic(&mut self) {
+        self.enable_nan_canonicalization = true;
+        if !self.deterministic {
+            self.deterministic = true;
+            self.middlewares.push(Arc::new(CallDepthLimit::default()));
+        }
+    }
+
+    /// Whether the execution is deterministic.
+    fn deterministic(&self) -> bool {
+        self.deterministic
+    }
+
     /// Transform it into the compiler
     fn compiler(self: Box<Self>) -> Box<dyn Compiler> {
         Box::new(SinglepassCompiler::new(*self))
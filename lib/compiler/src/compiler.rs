@@ -5,6 +5,7 @@ use crate::error::CompileError;
 use crate::function::Compilation;
 use crate::lib::std::boxed::Box;
 use crate::lib::std::sync::Arc;
+use crate::lib::std::vec::Vec;
 use crate::module::CompileModuleInfo;
 use crate::target::Target;
 use crate::translator::ModuleMiddleware;
@@ -12,9 +13,21 @@ use crate::FunctionBodyData;
 use crate::ModuleTranslationState;
 use crate::SectionIndex;
 use wasmer_types::entity::PrimaryMap;
-use wasmer_types::{Features, FunctionIndex, LocalFunctionIndex, SignatureIndex};
+use wasmer_types::{
+    Features, FunctionIndex, LocalFunctionIndex, OwnedDataInitializer, SignatureIndex,
+};
 use wasmparser::{Validator, WasmFeatures};
 
+/// A parsed module to compile with [`Compiler::compile_modules`].
+pub struct ModuleToCompile<'data, 'module> {
+    /// The module information.
+    pub compile_info: &'module mut CompileModuleInfo,
+    /// The translation state of the module.
+    pub module_translation: &'module ModuleTranslationState,
+    /// The function bodies of the module.
+    pub function_body_inputs: PrimaryMap<LocalFunctionIndex, FunctionBodyData<'data>>,
+}
+
 /// The compiler configuration options.
 pub trait CompilerConfig {
     /// Enable Position Independent Code (PIC).
@@ -36,6 +49,42 @@ pub trait CompilerConfig {
         // in case they create an IR that they can verify.
     }
 
+    /// Keep the frame pointer in every function.
+    ///
+    /// This lets native stack walkers go through the compiled functions
+    /// by following the chain of frame pointers.
+    fn enable_frame_pointers(&mut self) {
+        // By default we do nothing, each backend will need to customize this
+        // in case it can omit the frame pointer.
+    }
+
+    /// Guarantee a bit-for-bit deterministic execution of the compiled
+    /// code, e.g. for blockchains or to replay an execution:
+    /// - the NaNs are canonicalized,
+    /// - the non-deterministic proposals (threads) are disabled,
+    /// - the depth of the calls is limited with a [`CallDepthLimit`], so
+    ///   the stack is exhausted at the same call everywhere.
+    ///
+    /// The depth of the calls is counted in a global of each instance,
+    /// rather than per call, so the calls into an instance must run on
+    /// one thread at a time: a store requiring a deterministic
+    /// execution fails a call entering an instance another thread is
+    /// executing with a `RuntimeError`.
+    ///
+    /// Enabling it again has no effect.
+    ///
+    /// [`CallDepthLimit`]: crate::CallDepthLimit
+    fn enable_deterministic(&mut self) {
+        // By default we do nothing, each backend will need to customize this
+        // since the NaN canonicalization is done by the backends.
+    }
+
+    /// Whether the execution of the compiled code is deterministic, see
+    /// [`CompilerConfig::enable_deterministic`].
+    fn deterministic(&self) -> bool {
+        false
+    }
+
     /// Gets the custom compiler config
     fn compiler(self: Box<Self>) -> Box<dyn Compiler>;
 
@@ -99,6 +148,46 @@ pub trait Compiler: Send {
         function_body_inputs: PrimaryMap<LocalFunctionIndex, FunctionBodyData<'data>>,
     ) -> Result<Compilation, CompileError>;
 
+    /// Compiles many parsed modules at once.
+    ///
+    /// Returns their [`Compilation`]s, in the same order, and the
+    /// number of function bodies whose code was reused from an
+    /// identical function body rather than compiled.
+    ///
+    /// By default, the modules are compiled one after the other with
+    /// [`Compiler::compile_module`]. The compilers may compile them in
+    /// parallel, and reuse the code of the identical function bodies,
+    /// when no module middleware keeps the state of the module being
+    /// compiled, see [the state of the module middlewares](ModuleMiddleware#state).
+    fn compile_modules<'data, 'module>(
+        &self,
+        target: &Target,
+        modules: Vec<ModuleToCompile<'data, 'module>>,
+    ) -> Result<(Vec<Compilation>, usize), CompileError> {
+        let compilations = modules
+            .into_iter()
+            .map(|module| {
+                self.compile_module(
+                    target,
+                    module.compile_info,
+                    module.module_translation,
+                    module.function_body_inputs,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok((compilations, 0))
+    }
+
+    /// Transforms the data initializers of a module with the
+    /// middlewares, see [`ModuleMiddleware::transform_data_initializers`].
+    fn transform_data_initializers(&self, _data_initializers: &mut Vec<OwnedDataInitializer>) {}
+
+    /// Whether the execution of the compiled code is deterministic, see
+    /// [`CompilerConfig::enable_deterministic`].
+    fn deterministic(&self) -> bool {
+        false
+    }
+
     /// Compiles a module into a native object file.
     ///
     /// It returns the bytes as a `&[u8]` or a [`CompileError`].
@@ -0,0 +1,21 @@
+//! The component model: the types of the components, and the parser
+//! of their binary format.
+//!
+//! A component is made of core modules, which are instantiated and
+//! linked together by the definitions of the component. Its functions
+//! take and return the values of the component model, like strings,
+//! records and lists, which are lifted from and lowered to the core
+//! values and the memories of the core instances with the canonical
+//! ABI.
+
+mod translate;
+mod types;
+
+pub use self::translate::{
+    translate_component, CanonicalOptions, ComponentItem, ComponentTranslation, CoreExport,
+    CoreFuncDef, CoreInstanceDef, CoreItem, FuncDef, InstanceDef, StringEncoding,
+};
+pub use self::types::{
+    ComponentExternType, ComponentFuncType, ComponentInstanceType, ComponentTypeDef,
+    ComponentValType, ResourceType,
+};
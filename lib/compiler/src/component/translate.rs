@@ -0,0 +1,922 @@
+//! Parses the binary format of the components into the index spaces
+//! of their definitions.
+
+use super::types::{
+    ComponentExternType, ComponentFuncType, ComponentInstanceType, ComponentTypeDef,
+    ComponentValType, ResourceType,
+};
+use crate::lib::std::boxed::Box;
+use crate::lib::std::string::{String, ToString};
+use crate::lib::std::vec::Vec;
+use crate::{WasmError, WasmResult};
+
+/// The preamble of a component: the magic number, the version and
+/// the layer of the binary format.
+const COMPONENT_PREAMBLE: [u8; 8] = [0x00, 0x61, 0x73, 0x6d, 0x0d, 0x00, 0x01, 0x00];
+
+/// An export of a core instance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoreExport {
+    /// The index of the core instance.
+    pub instance: u32,
+    /// The name of the export.
+    pub name: String,
+}
+
+/// A core item, referred to by its index in its index space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoreItem {
+    /// A core function.
+    Func(u32),
+    /// A core table.
+    Table(u32),
+    /// A core memory.
+    Memory(u32),
+    /// A core global.
+    Global(u32),
+}
+
+/// The definition of a core instance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CoreInstanceDef {
+    /// The instance of a core module, and the core instances providing
+    /// its imports by module name.
+    Instantiate {
+        /// The index of the core module.
+        module: u32,
+        /// The names of the imported modules, and their core instances.
+        args: Vec<(String, u32)>,
+    },
+    /// An instance made of the given core items.
+    FromExports(Vec<(String, CoreItem)>),
+}
+
+/// How the strings are encoded in the memory of a core instance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StringEncoding {
+    /// UTF-8
+    Utf8,
+    /// UTF-16
+    Utf16,
+    /// Latin-1 or UTF-16
+    CompactUtf16,
+}
+
+impl Default for StringEncoding {
+    fn default() -> Self {
+        Self::Utf8
+    }
+}
+
+/// The options of a `canon lift` or a `canon lower`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CanonicalOptions {
+    /// The encoding of the strings.
+    pub string_encoding: StringEncoding,
+    /// The index of the core memory storing the values.
+    pub memory: Option<u32>,
+    /// The index of the core function allocating the values.
+    pub realloc: Option<u32>,
+    /// The index of the core function called after a lifted function
+    /// returned, to release its results.
+    pub post_return: Option<u32>,
+}
+
+/// The definition of a core function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CoreFuncDef {
+    /// A function exported by a core instance.
+    Export(CoreExport),
+    /// A function of the component, lowered to a core function.
+    Lower {
+        /// The index of the function of the component.
+        func: u32,
+        /// The canonical options of the lowering.
+        options: CanonicalOptions,
+    },
+    /// The `resource.drop` of an imported resource.
+    ResourceDrop(ResourceType),
+}
+
+/// The definition of a function of the component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FuncDef {
+    /// A function imported by the component.
+    Import {
+        /// The name of the import.
+        name: String,
+        /// The type of the function.
+        ty: ComponentFuncType,
+    },
+    /// A function exported by an imported instance.
+    InstanceExport {
+        /// The index of the instance.
+        instance: u32,
+        /// The name of the export.
+        name: String,
+        /// The type of the function.
+        ty: ComponentFuncType,
+    },
+    /// A core function, lifted to a function of the component.
+    Lift {
+        /// The index of the core function.
+        core_func: u32,
+        /// The type of the function.
+        ty: ComponentFuncType,
+        /// The canonical options of the lifting.
+        options: CanonicalOptions,
+    },
+}
+
+impl FuncDef {
+    /// Returns the type of the function.
+    pub fn ty(&self) -> &ComponentFuncType {
+        match self {
+            Self::Import { ty, .. } | Self::InstanceExport { ty, .. } | Self::Lift { ty, .. } => ty,
+        }
+    }
+}
+
+/// An item of the component, referred to by its index in its index
+/// space.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ComponentItem {
+    /// A function.
+    Func(u32),
+    /// An instance.
+    Instance(u32),
+    /// A type, with its definition.
+    Type(ComponentTypeDef),
+}
+
+/// The definition of an instance of the component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstanceDef {
+    /// An instance imported by the component.
+    Import {
+        /// The name of the import.
+        name: String,
+        /// The type of the instance.
+        ty: ComponentInstanceType,
+    },
+    /// An instance made of the given items.
+    FromExports(Vec<(String, ComponentItem)>),
+}
+
+/// The definitions of a component, by index space.
+///
+/// The definitions only refer to the definitions which precede them
+/// in their index spaces, so the core instances can be instantiated
+/// in order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ComponentTranslation {
+    /// The binaries of the core modules.
+    pub modules: Vec<Vec<u8>>,
+    /// The core instances.
+    pub core_instances: Vec<CoreInstanceDef>,
+    /// The core functions.
+    pub core_funcs: Vec<CoreFuncDef>,
+    /// The core tables.
+    pub core_tables: Vec<CoreExport>,
+    /// The core memories.
+    pub core_memories: Vec<CoreExport>,
+    /// The core globals.
+    pub core_globals: Vec<CoreExport>,
+    /// The functions.
+    pub funcs: Vec<FuncDef>,
+    /// The instances.
+    pub instances: Vec<InstanceDef>,
+    /// The names and the types of the imports.
+    pub imports: Vec<(String, ComponentExternType)>,
+    /// The names of the exports, and their items.
+    pub exports: Vec<(String, ComponentItem)>,
+}
+
+/// Parses the component `data`.
+///
+/// The nested components, the core module types, the resources
+/// defined by the component and the `start` functions aren't
+/// supported yet.
+pub fn translate_component(data: &[u8]) -> WasmResult<ComponentTranslation> {
+    let mut reader = Reader { data, offset: 0 };
+    if reader.bytes(COMPONENT_PREAMBLE.len())? != COMPONENT_PREAMBLE {
+        return Err(reader.invalid("expected the preamble of a component"));
+    }
+    let mut translator = Translator {
+        translation: ComponentTranslation::default(),
+        scopes: [Vec::new()].to_vec(),
+    };
+    while !reader.is_empty() {
+        let id = reader.u8()?;
+        let size = reader.u32()? as usize;
+        let section_offset = reader.offset;
+        let mut section = Reader {
+            data: reader.bytes(size)?,
+            offset: 0,
+        };
+        translator
+            .section(id, &mut section)
+            .and_then(|()| {
+                if id != 0 && !section.is_empty() {
+                    return Err(section.invalid("unexpected data at the end of the section"));
+                }
+                Ok(())
+            })
+            .map_err(|error| match error {
+                WasmError::InvalidWebAssembly { message, offset } => {
+                    WasmError::InvalidWebAssembly {
+                        message,
+                        offset: section_offset + offset,
+                    }
+                }
+                error => error,
+            })?;
+    }
+    Ok(translator.translation)
+}
+
+fn unsupported(feature: &str) -> WasmError {
+    WasmError::Unsupported(feature.to_string() + " in components")
+}
+
+struct Reader<'data> {
+    data: &'data [u8],
+    offset: usize,
+}
+
+impl<'data> Reader<'data> {
+    fn invalid(&self, message: &str) -> WasmError {
+        WasmError::InvalidWebAssembly {
+            message: message.to_string(),
+            offset: self.offset,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.offset >= self.data.len()
+    }
+
+    fn bytes(&mut self, len: usize) -> WasmResult<&'data [u8]> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|end| *end <= self.data.len())
+            .ok_or_else(|| self.invalid("unexpected end of the component"))?;
+        let bytes = &self.data[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> WasmResult<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn peek(&self) -> WasmResult<u8> {
+        self.data
+            .get(self.offset)
+            .copied()
+            .ok_or_else(|| self.invalid("unexpected end of the component"))
+    }
+
+    /// Reads an unsigned LEB128 integer.
+    fn u32(&mut self) -> WasmResult<u32> {
+        let mut result = 0u32;
+        for shift in (0..35).step_by(7) {
+            let byte = self.u8()?;
+            if shift == 28 && byte >> 4 != 0 {
+                return Err(self.invalid("integer too large"));
+            }
+            result |= u32::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(result);
+            }
+        }
+        Err(self.invalid("integer representation too long"))
+    }
+
+    fn string(&mut self) -> WasmResult<String> {
+        let len = self.u32()? as usize;
+        let bytes = self.bytes(len)?;
+        crate::lib::std::str::from_utf8(bytes)
+            .map(ToString::to_string)
+            .map_err(|_| self.invalid("invalid UTF-8 string"))
+    }
+
+    /// Reads an `importname'` or an `exportname'`.
+    fn extern_name(&mut self) -> WasmResult<String> {
+        match self.u8()? {
+            0x00 => self.string(),
+            _ => Err(unsupported("versioned names")),
+        }
+    }
+
+    fn vec<T>(&mut self, mut item: impl FnMut(&mut Self) -> WasmResult<T>) -> WasmResult<Vec<T>> {
+        let len = self.u32()?;
+        (0..len).map(|_| item(self)).collect()
+    }
+
+    fn option<T>(
+        &mut self,
+        item: impl FnOnce(&mut Self) -> WasmResult<T>,
+    ) -> WasmResult<Option<T>> {
+        match self.u8()? {
+            0x00 => Ok(None),
+            0x01 => item(self).map(Some),
+            _ => Err(self.invalid("invalid option")),
+        }
+    }
+}
+
+/// A sort of item of a component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Sort {
+    CoreFunc,
+    CoreTable,
+    CoreMemory,
+    CoreGlobal,
+    CoreType,
+    CoreModule,
+    CoreInstance,
+    Func,
+    Value,
+    Type,
+    Component,
+    Instance,
+}
+
+impl Sort {
+    fn core(reader: &mut Reader) -> WasmResult<Self> {
+        Ok(match reader.u8()? {
+            0x00 => Self::CoreFunc,
+            0x01 => Self::CoreTable,
+            0x02 => Self::CoreMemory,
+            0x03 => Self::CoreGlobal,
+            0x10 => Self::CoreType,
+            0x11 => Self::CoreModule,
+            0x12 => Self::CoreInstance,
+            _ => return Err(reader.invalid("invalid core sort")),
+        })
+    }
+
+    fn read(reader: &mut Reader) -> WasmResult<Self> {
+        Ok(match reader.u8()? {
+            0x00 => Self::core(reader)?,
+            0x01 => Self::Func,
+            0x02 => Self::Value,
+            0x03 => Self::Type,
+            0x04 => Self::Component,
+            0x05 => Self::Instance,
+            _ => return Err(reader.invalid("invalid sort")),
+        })
+    }
+}
+
+struct Translator {
+    translation: ComponentTranslation,
+    /// The type index spaces of the component and of the instance
+    /// types being parsed, from the outermost.
+    scopes: Vec<Vec<ComponentTypeDef>>,
+}
+
+impl Translator {
+    fn types(&mut self) -> &mut Vec<ComponentTypeDef> {
+        self.scopes.last_mut().unwrap()
+    }
+
+    fn type_def(&self, reader: &Reader, outer: u32, index: u32) -> WasmResult<ComponentTypeDef> {
+        self.scopes
+            .len()
+            .checked_sub(outer as usize + 1)
+            .and_then(|scope| self.scopes[scope].get(index as usize))
+            .cloned()
+            .ok_or_else(|| reader.invalid("unknown type"))
+    }
+
+    /// Reads a type index, and returns its definition.
+    fn type_index(&self, reader: &mut Reader) -> WasmResult<ComponentTypeDef> {
+        let index = reader.u32()?;
+        self.type_def(reader, 0, index)
+    }
+
+    fn section(&mut self, id: u8, reader: &mut Reader) -> WasmResult<()> {
+        match id {
+            0 => {}
+            1 => self.translation.modules.push(reader.data.to_vec()),
+            2 => {
+                let instances = reader.vec(Self::core_instance)?;
+                self.translation.core_instances.extend(instances);
+            }
+            3 => return Err(unsupported("core type sections")),
+            4 => return Err(unsupported("nested components")),
+            5 => {
+                for _ in 0..reader.u32()? {
+                    self.instance(reader)?;
+                }
+            }
+            6 => {
+                for _ in 0..reader.u32()? {
+                    self.alias(reader)?;
+                }
+            }
+            7 => {
+                for _ in 0..reader.u32()? {
+                    let ty = self.def_type(reader)?;
+                    self.types().push(ty);
+                }
+            }
+            8 => {
+                for _ in 0..reader.u32()? {
+                    self.canon(reader)?;
+                }
+            }
+            9 => return Err(unsupported("start functions")),
+            10 => {
+                for _ in 0..reader.u32()? {
+                    self.import(reader)?;
+                }
+            }
+            11 => {
+                for _ in 0..reader.u32()? {
+                    self.export(reader)?;
+                }
+            }
+            _ => return Err(reader.invalid("unknown section")),
+        }
+        if id == 1 {
+            reader.offset = reader.data.len();
+        }
+        Ok(())
+    }
+
+    fn core_instance(reader: &mut Reader) -> WasmResult<CoreInstanceDef> {
+        match reader.u8()? {
+            0x00 => {
+                let module = reader.u32()?;
+                let args = reader.vec(|reader| {
+                    let name = reader.string()?;
+                    if reader.u8()? != 0x12 {
+                        return Err(reader.invalid("expected a core instance"));
+                    }
+                    Ok((name, reader.u32()?))
+                })?;
+                Ok(CoreInstanceDef::Instantiate { module, args })
+            }
+            0x01 => {
+                let exports = reader.vec(|reader| {
+                    let name = reader.string()?;
+                    let sort = Sort::core(reader)?;
+                    let index = reader.u32()?;
+                    let item = match sort {
+                        Sort::CoreFunc => CoreItem::Func(index),
+                        Sort::CoreTable => CoreItem::Table(index),
+                        Sort::CoreMemory => CoreItem::Memory(index),
+                        Sort::CoreGlobal => CoreItem::Global(index),
+                        _ => return Err(unsupported("core instances exporting types")),
+                    };
+                    Ok((name, item))
+                })?;
+                Ok(CoreInstanceDef::FromExports(exports))
+            }
+            _ => Err(reader.invalid("invalid core instance")),
+        }
+    }
+
+    fn instance(&mut self, reader: &mut Reader) -> WasmResult<()> {
+        match reader.u8()? {
+            0x00 => Err(unsupported("instances of nested components")),
+            0x01 => {
+                let exports = reader.vec(|reader| {
+                    let name = reader.extern_name()?;
+                    let sort = Sort::read(reader)?;
+                    let index = reader.u32()?;
+                    Ok((name, sort, index))
+                })?;
+                let exports = exports
+                    .into_iter()
+                    .map(|(name, sort, index)| Ok((name, self.item(reader, sort, index)?)))
+                    .collect::<WasmResult<_>>()?;
+                self.translation
+                    .instances
+                    .push(InstanceDef::FromExports(exports));
+                Ok(())
+            }
+            _ => Err(reader.invalid("invalid instance")),
+        }
+    }
+
+    fn item(&self, reader: &Reader, sort: Sort, index: u32) -> WasmResult<ComponentItem> {
+        match sort {
+            Sort::Func if (index as usize) < self.translation.funcs.len() => {
+                Ok(ComponentItem::Func(index))
+            }
+            Sort::Instance if (index as usize) < self.translation.instances.len() => {
+                Ok(ComponentItem::Instance(index))
+            }
+            Sort::Type => Ok(ComponentItem::Type(self.type_def(reader, 0, index)?)),
+            Sort::Func | Sort::Instance => Err(reader.invalid("unknown item")),
+            _ => Err(unsupported("exports of this sort")),
+        }
+    }
+
+    fn alias(&mut self, reader: &mut Reader) -> WasmResult<()> {
+        let sort = Sort::read(reader)?;
+        match reader.u8()? {
+            0x00 => {
+                let instance = reader.u32()?;
+                let name = reader.string()?;
+                self.alias_instance_export(reader, sort, instance, name)
+            }
+            0x01 => {
+                let export = CoreExport {
+                    instance: reader.u32()?,
+                    name: reader.string()?,
+                };
+                if export.instance as usize >= self.translation.core_instances.len() {
+                    return Err(reader.invalid("unknown core instance"));
+                }
+                let translation = &mut self.translation;
+                match sort {
+                    Sort::CoreFunc => translation.core_funcs.push(CoreFuncDef::Export(export)),
+                    Sort::CoreTable => translation.core_tables.push(export),
+                    Sort::CoreMemory => translation.core_memories.push(export),
+                    Sort::CoreGlobal => translation.core_globals.push(export),
+                    _ => return Err(unsupported("aliases of core exports of this sort")),
+                }
+                Ok(())
+            }
+            0x02 => {
+                let outer = reader.u32()?;
+                let index = reader.u32()?;
+                if sort != Sort::Type {
+                    return Err(unsupported("outer aliases of this sort"));
+                }
+                let ty = self.type_def(reader, outer, index)?;
+                self.types().push(ty);
+                Ok(())
+            }
+            _ => Err(reader.invalid("invalid alias")),
+        }
+    }
+
+    fn alias_instance_export(
+        &mut self,
+        reader: &Reader,
+        sort: Sort,
+        instance: u32,
+        name: String,
+    ) -> WasmResult<()> {
+        let unknown = || reader.invalid("unknown instance export");
+        let def = self
+            .translation
+            .instances
+            .get(instance as usize)
+            .ok_or_else(|| reader.invalid("unknown instance"))?;
+        match def {
+            InstanceDef::Import {
+                name: interface,
+                ty,
+            } => match (sort, ty.export(&name).ok_or_else(unknown)?) {
+                (Sort::Func, ComponentExternType::Func(ty)) => {
+                    let ty = ty.clone();
+                    self.translation
+                        .funcs
+                        .push(FuncDef::InstanceExport { instance, name, ty });
+                }
+                (Sort::Type, ComponentExternType::Type(ty)) => {
+                    let mut ty = ty.clone();
+                    if let ComponentTypeDef::Resource(resource) = &mut ty {
+                        resource.interface = Some(interface.clone());
+                    }
+                    self.types().push(ty);
+                }
+                (Sort::Instance, _) => return Err(unsupported("nested instances")),
+                _ => return Err(reader.invalid("the instance export has another sort")),
+            },
+            InstanceDef::FromExports(exports) => {
+                let item = exports
+                    .iter()
+                    .find(|(export, _)| *export == name)
+                    .map(|(_, item)| item.clone())
+                    .ok_or_else(unknown)?;
+                match (sort, item) {
+                    (Sort::Func, ComponentItem::Func(func)) => {
+                        let func = self.translation.funcs[func as usize].clone();
+                        self.translation.funcs.push(func);
+                    }
+                    (Sort::Instance, ComponentItem::Instance(instance)) => {
+                        let instance = self.translation.instances[instance as usize].clone();
+                        self.translation.instances.push(instance);
+                    }
+                    (Sort::Type, ComponentItem::Type(ty)) => self.types().push(ty),
+                    _ => return Err(reader.invalid("the instance export has another sort")),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn val_type(&self, reader: &mut Reader) -> WasmResult<ComponentValType> {
+        if let Some(ty) = Self::primitive_val_type(reader.peek()?) {
+            reader.u8()?;
+            return Ok(ty);
+        }
+        match self.type_index(reader)? {
+            ComponentTypeDef::Val(ty) => Ok(ty),
+            _ => Err(reader.invalid("expected a value type")),
+        }
+    }
+
+    fn primitive_val_type(byte: u8) -> Option<ComponentValType> {
+        Some(match byte {
+            0x7f => ComponentValType::Bool,
+            0x7e => ComponentValType::S8,
+            0x7d => ComponentValType::U8,
+            0x7c => ComponentValType::S16,
+            0x7b => ComponentValType::U16,
+            0x7a => ComponentValType::S32,
+            0x79 => ComponentValType::U32,
+            0x78 => ComponentValType::S64,
+            0x77 => ComponentValType::U64,
+            0x76 => ComponentValType::Float32,
+            0x75 => ComponentValType::Float64,
+            0x74 => ComponentValType::Char,
+            0x73 => ComponentValType::String,
+            _ => return None,
+        })
+    }
+
+    fn resource(&self, reader: &mut Reader) -> WasmResult<ResourceType> {
+        match self.type_index(reader)? {
+            ComponentTypeDef::Resource(resource) => Ok(resource),
+            _ => Err(reader.invalid("expected a resource type")),
+        }
+    }
+
+    fn def_type(&mut self, reader: &mut Reader) -> WasmResult<ComponentTypeDef> {
+        let byte = reader.u8()?;
+        if let Some(ty) = Self::primitive_val_type(byte) {
+            return Ok(ComponentTypeDef::Val(ty));
+        }
+        let ty = match byte {
+            0x72 => ComponentValType::Record(
+                reader.vec(|reader| Ok((reader.string()?, self.val_type(reader)?)))?,
+            ),
+            0x71 => ComponentValType::Variant(reader.vec(|reader| {
+                let name = reader.string()?;
+                let ty = reader.option(|reader| self.val_type(reader))?;
+                // The `refines` clause is ignored.
+                reader.option(Reader::u32)?;
+                Ok((name, ty))
+            })?),
+            0x70 => ComponentValType::List(Box::new(self.val_type(reader)?)),
+            0x6f => ComponentValType::Tuple(reader.vec(|reader| self.val_type(reader))?),
+            0x6e => ComponentValType::Flags(reader.vec(Reader::string)?),
+            0x6d => ComponentValType::Enum(reader.vec(Reader::string)?),
+            0x6b => ComponentValType::Option(Box::new(self.val_type(reader)?)),
+            0x6a => ComponentValType::Result {
+                ok: reader.option(|reader| self.val_type(reader))?.map(Box::new),
+                err: reader.option(|reader| self.val_type(reader))?.map(Box::new),
+            },
+            0x69 => ComponentValType::Own(self.resource(reader)?),
+            0x68 => ComponentValType::Borrow(self.resource(reader)?),
+            0x40 => return Ok(ComponentTypeDef::Func(self.func_type(reader)?)),
+            0x42 => return Ok(ComponentTypeDef::Instance(self.instance_type(reader)?)),
+            0x41 => return Err(unsupported("component types")),
+            0x3f => return Err(unsupported("resources defined")),
+            _ => return Err(reader.invalid("invalid type")),
+        };
+        Ok(ComponentTypeDef::Val(ty))
+    }
+
+    fn func_type(&mut self, reader: &mut Reader) -> WasmResult<ComponentFuncType> {
+        let params = reader.vec(|reader| Ok((reader.string()?, self.val_type(reader)?)))?;
+        let results = match reader.u8()? {
+            0x00 => [self.val_type(reader)?].to_vec(),
+            0x01 => reader.vec(|reader| {
+                reader.string()?;
+                self.val_type(reader)
+            })?,
+            _ => return Err(reader.invalid("invalid function results")),
+        };
+        Ok(ComponentFuncType { params, results })
+    }
+
+    fn instance_type(&mut self, reader: &mut Reader) -> WasmResult<ComponentInstanceType> {
+        self.scopes.push(Vec::new());
+        let result = self.instance_decls(reader);
+        self.scopes.pop();
+        result
+    }
+
+    fn instance_decls(&mut self, reader: &mut Reader) -> WasmResult<ComponentInstanceType> {
+        let mut instance = ComponentInstanceType::default();
+        for _ in 0..reader.u32()? {
+            match reader.u8()? {
+                0x00 => return Err(unsupported("core types in instance types")),
+                0x01 => {
+                    let ty = self.def_type(reader)?;
+                    self.types().push(ty);
+                }
+                0x02 => match (Sort::read(reader)?, reader.u8()?) {
+                    (Sort::Type, 0x02) => {
+                        let outer = reader.u32()?;
+                        let index = reader.u32()?;
+                        let ty = self.type_def(reader, outer, index)?;
+                        self.types().push(ty);
+                    }
+                    _ => return Err(unsupported("aliases in instance types")),
+                },
+                0x04 => {
+                    let name = reader.extern_name()?;
+                    let ty = self.extern_desc(reader, &name)?;
+                    if let ComponentExternType::Type(ty) = &ty {
+                        self.types().push(ty.clone());
+                    }
+                    instance.exports.push((name, ty));
+                }
+                _ => return Err(reader.invalid("invalid instance type declaration")),
+            }
+        }
+        Ok(instance)
+    }
+
+    /// Reads the type of the import or the export `name`.
+    fn extern_desc(&mut self, reader: &mut Reader, name: &str) -> WasmResult<ComponentExternType> {
+        match reader.u8()? {
+            0x01 => match self.type_index(reader)? {
+                ComponentTypeDef::Func(ty) => Ok(ComponentExternType::Func(ty)),
+                _ => Err(reader.invalid("expected a function type")),
+            },
+            0x03 => match reader.u8()? {
+                0x00 => Ok(ComponentExternType::Type(self.type_index(reader)?)),
+                0x01 => Ok(ComponentExternType::Type(ComponentTypeDef::Resource(
+                    ResourceType {
+                        interface: None,
+                        name: name.to_string(),
+                    },
+                ))),
+                _ => Err(reader.invalid("invalid type bound")),
+            },
+            0x05 => match self.type_index(reader)? {
+                ComponentTypeDef::Instance(ty) => Ok(ComponentExternType::Instance(ty)),
+                _ => Err(reader.invalid("expected an instance type")),
+            },
+            0x00 => Err(unsupported("imports and exports of core modules")),
+            0x02 => Err(unsupported("imports and exports of values")),
+            0x04 => Err(unsupported("imports and exports of components")),
+            _ => Err(reader.invalid("invalid import or export type")),
+        }
+    }
+
+    fn canon_options(reader: &mut Reader) -> WasmResult<CanonicalOptions> {
+        let mut options = CanonicalOptions::default();
+        for _ in 0..reader.u32()? {
+            match reader.u8()? {
+                0x00 => options.string_encoding = StringEncoding::Utf8,
+                0x01 => options.string_encoding = StringEncoding::Utf16,
+                0x02 => options.string_encoding = StringEncoding::CompactUtf16,
+                0x03 => options.memory = Some(reader.u32()?),
+                0x04 => options.realloc = Some(reader.u32()?),
+                0x05 => options.post_return = Some(reader.u32()?),
+                _ => return Err(unsupported("this canonical option")),
+            }
+        }
+        Ok(options)
+    }
+
+    fn canon(&mut self, reader: &mut Reader) -> WasmResult<()> {
+        match reader.u8()? {
+            0x00 => {
+                if reader.u8()? != 0x00 {
+                    return Err(reader.invalid("invalid canon lift"));
+                }
+                let core_func = reader.u32()?;
+                let options = Self::canon_options(reader)?;
+                let ty = match self.type_index(reader)? {
+                    ComponentTypeDef::Func(ty) => ty,
+                    _ => return Err(reader.invalid("expected a function type")),
+                };
+                self.translation.funcs.push(FuncDef::Lift {
+                    core_func,
+                    ty,
+                    options,
+                });
+            }
+            0x01 => {
+                if reader.u8()? != 0x00 {
+                    return Err(reader.invalid("invalid canon lower"));
+                }
+                let func = reader.u32()?;
+                if func as usize >= self.translation.funcs.len() {
+                    return Err(reader.invalid("unknown function"));
+                }
+                let options = Self::canon_options(reader)?;
+                self.translation
+                    .core_funcs
+                    .push(CoreFuncDef::Lower { func, options });
+            }
+            0x03 => {
+                let resource = self.resource(reader)?;
+                if resource.interface.is_none() {
+                    return Err(unsupported(
+                        "drops of resources not imported by an instance",
+                    ));
+                }
+                self.translation
+                    .core_funcs
+                    .push(CoreFuncDef::ResourceDrop(resource));
+            }
+            _ => return Err(unsupported("this canonical built-in")),
+        }
+        Ok(())
+    }
+
+    fn import(&mut self, reader: &mut Reader) -> WasmResult<()> {
+        let name = reader.extern_name()?;
+        let ty = self.extern_desc(reader, &name)?;
+        match &ty {
+            ComponentExternType::Func(ty) => self.translation.funcs.push(FuncDef::Import {
+                name: name.clone(),
+                ty: ty.clone(),
+            }),
+            ComponentExternType::Instance(ty) => {
+                self.translation.instances.push(InstanceDef::Import {
+                    name: name.clone(),
+                    ty: ty.clone(),
+                })
+            }
+            ComponentExternType::Type(ty) => self.types().push(ty.clone()),
+        }
+        self.translation.imports.push((name, ty));
+        Ok(())
+    }
+
+    fn export(&mut self, reader: &mut Reader) -> WasmResult<()> {
+        let name = reader.extern_name()?;
+        let sort = Sort::read(reader)?;
+        let index = reader.u32()?;
+        // The type ascribed to the export is ignored.
+        if reader.u8()? == 0x01 {
+            self.extern_desc(reader, &name)?;
+        }
+        let item = self.item(reader, sort, index)?;
+        // An export adds a new item to its index space.
+        match &item {
+            ComponentItem::Func(func) => {
+                let func = self.translation.funcs[*func as usize].clone();
+                self.translation.funcs.push(func);
+            }
+            ComponentItem::Instance(instance) => {
+                let instance = self.translation.instances[*instance as usize].clone();
+                self.translation.instances.push(instance);
+            }
+            ComponentItem::Type(ty) => self.types().push(ty.clone()),
+        }
+        self.translation.exports.push((name, item));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `(component (core module) (import "f" (func (param "s" string) (result u32))))`
+    #[test]
+    fn parse_imports() {
+        let mut bytes = COMPONENT_PREAMBLE.to_vec();
+        // A core module.
+        bytes.extend(&[0x01, 0x08, 0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00]);
+        // (type (func (param "s" string) (result u32)))
+        bytes.extend(&[0x07, 0x08, 0x01, 0x40, 0x01, 0x01, b's', 0x73, 0x00, 0x79]);
+        // (import "f" (func (type 0)))
+        bytes.extend(&[0x0a, 0x06, 0x01, 0x00, 0x01, b'f', 0x01, 0x00]);
+
+        let translation = translate_component(&bytes).unwrap();
+        assert_eq!(translation.modules.len(), 1);
+        let ty = ComponentFuncType {
+            params: vec![("s".to_string(), ComponentValType::String)],
+            results: vec![ComponentValType::U32],
+        };
+        assert_eq!(
+            translation.imports,
+            vec![("f".to_string(), ComponentExternType::Func(ty.clone()))]
+        );
+        assert_eq!(
+            translation.funcs,
+            vec![FuncDef::Import {
+                name: "f".to_string(),
+                ty
+            }]
+        );
+    }
+
+    #[test]
+    fn reject_core_modules() {
+        let bytes = [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+        assert!(matches!(
+            translate_component(&bytes),
+            Err(WasmError::InvalidWebAssembly { offset: 8, .. })
+        ));
+    }
+}
@@ -0,0 +1,158 @@
+//! The types of the component model.
+
+use crate::lib::std::boxed::Box;
+use crate::lib::std::fmt;
+use crate::lib::std::string::String;
+use crate::lib::std::vec::Vec;
+
+/// A value type of the component model.
+///
+/// The named types are resolved: a type referring to another type of
+/// the component contains its definition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ComponentValType {
+    /// `bool`
+    Bool,
+    /// `s8`
+    S8,
+    /// `u8`
+    U8,
+    /// `s16`
+    S16,
+    /// `u16`
+    U16,
+    /// `s32`
+    S32,
+    /// `u32`
+    U32,
+    /// `s64`
+    S64,
+    /// `u64`
+    U64,
+    /// `f32`
+    Float32,
+    /// `f64`
+    Float64,
+    /// `char`
+    Char,
+    /// `string`
+    String,
+    /// `record { name: type, ... }`
+    Record(Vec<(String, ComponentValType)>),
+    /// `variant { case(type), case, ... }`
+    Variant(Vec<(String, Option<ComponentValType>)>),
+    /// `list<type>`
+    List(Box<ComponentValType>),
+    /// `tuple<type, ...>`
+    Tuple(Vec<ComponentValType>),
+    /// `flags { name, ... }`
+    Flags(Vec<String>),
+    /// `enum { name, ... }`
+    Enum(Vec<String>),
+    /// `option<type>`
+    Option(Box<ComponentValType>),
+    /// `result<ok, err>`, where both types are optional.
+    Result {
+        /// The type of the `ok` case, if it has a payload.
+        ok: Option<Box<ComponentValType>>,
+        /// The type of the `err` case, if it has a payload.
+        err: Option<Box<ComponentValType>>,
+    },
+    /// `own<resource>`: a handle to a resource, whose ownership is
+    /// transferred.
+    Own(ResourceType),
+    /// `borrow<resource>`: a handle to a resource, borrowed for the
+    /// duration of a call.
+    Borrow(ResourceType),
+}
+
+impl fmt::Display for ComponentValType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bool => write!(f, "bool"),
+            Self::S8 => write!(f, "s8"),
+            Self::U8 => write!(f, "u8"),
+            Self::S16 => write!(f, "s16"),
+            Self::U16 => write!(f, "u16"),
+            Self::S32 => write!(f, "s32"),
+            Self::U32 => write!(f, "u32"),
+            Self::S64 => write!(f, "s64"),
+            Self::U64 => write!(f, "u64"),
+            Self::Float32 => write!(f, "f32"),
+            Self::Float64 => write!(f, "f64"),
+            Self::Char => write!(f, "char"),
+            Self::String => write!(f, "string"),
+            Self::Record(_) => write!(f, "record"),
+            Self::Variant(_) => write!(f, "variant"),
+            Self::List(ty) => write!(f, "list<{}>", ty),
+            Self::Tuple(_) => write!(f, "tuple"),
+            Self::Flags(_) => write!(f, "flags"),
+            Self::Enum(_) => write!(f, "enum"),
+            Self::Option(ty) => write!(f, "option<{}>", ty),
+            Self::Result { .. } => write!(f, "result"),
+            Self::Own(resource) => write!(f, "own<{}>", resource.name),
+            Self::Borrow(resource) => write!(f, "borrow<{}>", resource.name),
+        }
+    }
+}
+
+/// A resource type, imported by the component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResourceType {
+    /// The name of the imported instance exporting the resource, or
+    /// `None` if the component imports the resource itself, or if it's
+    /// referred to in the type of an instance.
+    pub interface: Option<String>,
+    /// The name of the resource.
+    pub name: String,
+}
+
+/// The type of a function of the component model.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ComponentFuncType {
+    /// The names and the types of the parameters.
+    pub params: Vec<(String, ComponentValType)>,
+    /// The types of the results.
+    pub results: Vec<ComponentValType>,
+}
+
+/// The type of an instance of the component model.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ComponentInstanceType {
+    /// The names and the types of the exports.
+    pub exports: Vec<(String, ComponentExternType)>,
+}
+
+impl ComponentInstanceType {
+    /// Returns the type of the export `name`.
+    pub fn export(&self, name: &str) -> Option<&ComponentExternType> {
+        self.exports
+            .iter()
+            .find(|(export, _)| export == name)
+            .map(|(_, ty)| ty)
+    }
+}
+
+/// A type definition of the component model.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ComponentTypeDef {
+    /// A value type.
+    Val(ComponentValType),
+    /// A function type.
+    Func(ComponentFuncType),
+    /// An instance type.
+    Instance(ComponentInstanceType),
+    /// A resource type.
+    Resource(ResourceType),
+}
+
+/// The type of an import or an export of the component model.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ComponentExternType {
+    /// A function.
+    Func(ComponentFuncType),
+    /// An instance.
+    Instance(ComponentInstanceType),
+    /// A type.
+    Type(ComponentTypeDef),
+}
@@ -40,6 +40,15 @@ pub enum CompileError {
     /// Insufficient resources available for execution.
     #[cfg_attr(feature = "std", error("Insufficient resources: {0}"))]
     Resource(String),
+
+    /// The compilation needed more work than its fuel allowed.
+    ///
+    /// See [`CompileFuel`](crate::CompileFuel).
+    #[cfg_attr(
+        feature = "std",
+        error("The compilation ran out of fuel (limit of {0} units)")
+    )]
+    FuelExhausted(u64),
 }
 
 impl From<WasmError> for CompileError {
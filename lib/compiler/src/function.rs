@@ -11,7 +11,10 @@
 use crate::lib::std::vec::Vec;
 use crate::section::{CustomSection, SectionIndex};
 use crate::trap::TrapInformation;
-use crate::{CompiledFunctionUnwindInfo, FunctionAddressMap, JumpTableOffsets, Relocation};
+use crate::{
+    CompiledFunctionUnwindInfo, FunctionAddressMap, JumpTableOffsets, Relocation, RelocationTarget,
+    SourceLoc,
+};
 #[cfg(feature = "enable-serde")]
 use serde::{Deserialize, Serialize};
 use wasmer_types::entity::PrimaryMap;
@@ -66,6 +69,42 @@ pub struct CompiledFunction {
     pub frame_info: CompiledFunctionFrameInfo,
 }
 
+impl CompiledFunction {
+    /// Returns the code of this function for an identical function
+    /// body compiled at `index`, whose body starts at `module_offset`
+    /// in its module, rather than at `original_module_offset`.
+    ///
+    /// The jump tables are relocated to the new function, and its
+    /// source locations are moved to the new body. The function must
+    /// not refer to the custom sections of its module.
+    pub fn reuse_at(
+        &self,
+        index: LocalFunctionIndex,
+        original_module_offset: usize,
+        module_offset: usize,
+    ) -> Self {
+        let mut function = self.clone();
+        for relocation in function.relocations.iter_mut() {
+            if let RelocationTarget::JumpTable(_, jt) = relocation.reloc_target {
+                relocation.reloc_target = RelocationTarget::JumpTable(index, jt);
+            }
+        }
+        let move_srcloc = |srcloc: &mut SourceLoc| {
+            if !srcloc.is_default() {
+                let bits = srcloc.bits() as usize - original_module_offset + module_offset;
+                *srcloc = SourceLoc::new(bits as u32);
+            }
+        };
+        let address_map = &mut function.frame_info.address_map;
+        move_srcloc(&mut address_map.start_srcloc);
+        move_srcloc(&mut address_map.end_srcloc);
+        for instruction in address_map.instructions.iter_mut() {
+            move_srcloc(&mut instruction.srcloc);
+        }
+        function
+    }
+}
+
 /// The compiled functions map (index in the Wasm -> function)
 pub type Functions = PrimaryMap<LocalFunctionIndex, CompiledFunction>;
 
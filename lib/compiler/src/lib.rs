@@ -52,6 +52,7 @@ mod lib {
 mod address_map;
 #[cfg(feature = "translator")]
 mod compiler;
+pub mod component;
 mod error;
 mod function;
 mod jump_table;
@@ -68,7 +69,7 @@ mod sourceloc;
 
 pub use crate::address_map::{FunctionAddressMap, InstructionAddressMap};
 #[cfg(feature = "translator")]
-pub use crate::compiler::{Compiler, CompilerConfig, Symbol, SymbolRegistry};
+pub use crate::compiler::{Compiler, CompilerConfig, ModuleToCompile, Symbol, SymbolRegistry};
 pub use crate::error::{
     CompileError, MiddlewareError, ParseCpuFeatureError, WasmError, WasmResult,
 };
@@ -77,7 +78,7 @@ pub use crate::function::{
     Functions,
 };
 pub use crate::jump_table::{JumpTable, JumpTableOffsets};
-pub use crate::module::CompileModuleInfo;
+pub use crate::module::{CodeLayout, CompileModuleInfo};
 pub use crate::relocation::{Relocation, RelocationKind, RelocationTarget, Relocations};
 pub use crate::section::{CustomSection, CustomSectionProtection, SectionBody, SectionIndex};
 pub use crate::sourceloc::SourceLoc;
@@ -87,12 +88,14 @@ pub use crate::target::{
 };
 #[cfg(feature = "translator")]
 pub use crate::translator::{
-    translate_module, wptype_to_type, FunctionBodyData, FunctionMiddleware, MiddlewareBinaryReader,
-    MiddlewareReaderState, ModuleEnvironment, ModuleInfoTranslation, ModuleMiddleware,
-    ModuleMiddlewareChain, ModuleTranslationState,
+    translate_module, wptype_to_type, CallDepthLimit, CompileFuel, FunctionBodyData,
+    FunctionCounters, FunctionMiddleware, MiddlewareBinaryReader, MiddlewareReaderState,
+    ModuleEnvironment, ModuleInfoTranslation, ModuleItemInjector, ModuleMiddleware,
+    ModuleMiddlewareChain, ModuleTranslationState, Prepass, CALL_DEPTH_GLOBAL,
+    DEFAULT_MAX_CALL_DEPTH, FUNCTION_COUNTERS,
 };
 pub use crate::trap::TrapInformation;
-pub use crate::unwind::CompiledFunctionUnwindInfo;
+pub use crate::unwind::{CompiledFunctionUnwindInfo, UnwindInfoMode};
 
 pub use wasmer_types::Features;
 
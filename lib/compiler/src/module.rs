@@ -1,9 +1,13 @@
 use crate::lib::std::sync::Arc;
+use crate::lib::std::vec::Vec;
 #[cfg(feature = "enable-serde")]
 use serde::{Deserialize, Serialize};
 use wasmer_types::entity::PrimaryMap;
-use wasmer_types::{Features, MemoryIndex, TableIndex};
-use wasmer_vm::{MemoryStyle, ModuleInfo, TableStyle};
+use wasmer_types::{
+    Features, FunctionType, GlobalType, MemoryIndex, MemoryType, SignatureIndex, TableIndex,
+    TableType,
+};
+use wasmer_vm::{BoundsCheck, MemoryStyle, ModuleInfo, TableStyle};
 
 /// The required info for compiling a module.
 ///
@@ -22,6 +26,55 @@ pub struct CompileModuleInfo {
     /// The compiler will emit the most optimal code based
     /// on the memory style (static or dynamic) chosen.
     pub memory_styles: PrimaryMap<MemoryIndex, MemoryStyle>,
+    /// How the accesses to the memories are checked to be in bounds,
+    /// given their memory style.
+    pub memory_bounds_checks: PrimaryMap<MemoryIndex, BoundsCheck>,
     /// The table plans used for compiling.
     pub table_styles: PrimaryMap<TableIndex, TableStyle>,
 }
+
+impl CompileModuleInfo {
+    /// Returns the parts of the module the code of its functions
+    /// depends on, see [`CodeLayout`].
+    pub fn code_layout(&self) -> CodeLayout {
+        let module = &self.module;
+        CodeLayout {
+            signatures: module.signatures.values().cloned().collect(),
+            functions: module.functions.values().cloned().collect(),
+            tables: module.tables.values().cloned().collect(),
+            memories: module.memories.values().cloned().collect(),
+            globals: module.globals.values().cloned().collect(),
+            num_imported_functions: module.num_imported_functions,
+            num_imported_tables: module.num_imported_tables,
+            num_imported_memories: module.num_imported_memories,
+            num_imported_globals: module.num_imported_globals,
+            memory_styles: self.memory_styles.values().cloned().collect(),
+            memory_bounds_checks: self.memory_bounds_checks.values().cloned().collect(),
+            table_styles: self.table_styles.values().cloned().collect(),
+        }
+    }
+}
+
+/// The parts of a module the code of its functions depends on: the
+/// types, the functions, tables, memories and globals, whether they
+/// are imported, and how the memories and tables are accessed.
+///
+/// Without module middlewares, identical function bodies with the
+/// same signature in modules with the same `CodeLayout` compile to the
+/// same code, up to the index of the function and its position in the
+/// module, see [`CompiledFunction::reuse_at`][crate::CompiledFunction::reuse_at].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CodeLayout {
+    signatures: Vec<FunctionType>,
+    functions: Vec<SignatureIndex>,
+    tables: Vec<TableType>,
+    memories: Vec<MemoryType>,
+    globals: Vec<GlobalType>,
+    num_imported_functions: usize,
+    num_imported_tables: usize,
+    num_imported_memories: usize,
+    num_imported_globals: usize,
+    memory_styles: Vec<MemoryStyle>,
+    memory_bounds_checks: Vec<BoundsCheck>,
+    table_styles: Vec<TableStyle>,
+}
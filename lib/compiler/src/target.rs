@@ -41,6 +41,7 @@ pub enum CpuFeature {
     AVX512VL,
     LZCNT,
     // ARM features
+    NEON,
     // Risc-V features
 }
 
@@ -98,7 +99,13 @@ impl CpuFeature {
         }
         features
     }
-    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+    #[cfg(target_arch = "aarch64")]
+    /// Retrieves the features for the current Host
+    pub fn for_host() -> EnumSet<Self> {
+        // Advanced SIMD is mandatory on AArch64
+        EnumSet::only(Self::NEON)
+    }
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
     /// Retrieves the features for the current Host
     pub fn for_host() -> EnumSet<Self> {
         // We default to an empty hash set
@@ -136,6 +143,7 @@ impl FromStr for CpuFeature {
             "avx512dq" => Ok(Self::AVX512DQ),
             "avx512vl" => Ok(Self::AVX512VL),
             "lzcnt" => Ok(Self::LZCNT),
+            "neon" => Ok(Self::NEON),
             _ => Err(ParseCpuFeatureError::Missing(s.to_string())),
         }
     }
@@ -157,6 +165,7 @@ impl ToString for CpuFeature {
             Self::AVX512DQ => "avx512dq",
             Self::AVX512VL => "avx512vl",
             Self::LZCNT => "lzcnt",
+            Self::NEON => "neon",
         }
         .to_string()
     }
@@ -0,0 +1,157 @@
+//! A middleware limiting the depth of the calls between WebAssembly
+//! functions, so the stack is exhausted deterministically.
+
+use std::sync::Mutex;
+use wasmer_types::{GlobalIndex, GlobalInit, GlobalType, LocalFunctionIndex, Mutability, Type};
+use wasmparser::{Operator, Type as WpType, TypeOrFuncType as WpTypeOrFuncType};
+
+use super::middleware::{
+    FunctionMiddleware, MiddlewareReaderState, ModuleItemInjector, ModuleMiddleware,
+};
+use crate::error::MiddlewareError;
+
+/// The default maximum depth of the calls of [`CallDepthLimit`].
+pub const DEFAULT_MAX_CALL_DEPTH: u32 = 10_000;
+
+/// The name the global holding the depth of the calls is registered
+/// under, see [`ModuleItemInjector::register_globals`].
+pub const CALL_DEPTH_GLOBAL: &str = "wasmer_call_depth";
+
+/// The module-level middleware limiting the depth of the calls.
+///
+/// Whether a native stack overflow happens depends on the compiler, the
+/// platform and the stack of the host thread. With this middleware, a
+/// function called at a depth of `max_depth` calls traps with
+/// `unreachable` instead, the same way everywhere, as long as
+/// `max_depth` calls fit in the native stack.
+///
+/// The depth is held by a global of the instance, incremented around
+/// each call. The host saves it when it calls a function of the
+/// instance, and restores it when the call returns or traps, so a trap
+/// doesn't leave the frames it unwound counted.
+///
+/// It keeps the global of the module being compiled, see
+/// [the state of the module middlewares](ModuleMiddleware#state).
+#[derive(Debug)]
+pub struct CallDepthLimit {
+    /// The maximum depth of the calls.
+    max_depth: u32,
+
+    /// The global holding the depth in the module being compiled.
+    depth_global: Mutex<Option<GlobalIndex>>,
+}
+
+/// The function-level middleware limiting the depth of the calls.
+#[derive(Debug)]
+struct FunctionCallDepthLimit {
+    /// The maximum depth of the calls.
+    max_depth: u32,
+
+    /// The global holding the depth.
+    depth_global: u32,
+
+    /// Whether the depth is checked at the entry of the function yet.
+    checked: bool,
+}
+
+impl CallDepthLimit {
+    /// Creates a `CallDepthLimit` middleware trapping at a depth of
+    /// `max_depth` calls.
+    pub fn new(max_depth: u32) -> Self {
+        Self {
+            max_depth,
+            depth_global: Mutex::new(None),
+        }
+    }
+
+    /// The maximum depth of the calls.
+    pub fn max_depth(&self) -> u32 {
+        self.max_depth
+    }
+}
+
+impl Default for CallDepthLimit {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_CALL_DEPTH)
+    }
+}
+
+impl ModuleMiddleware for CallDepthLimit {
+    fn generate_function_middleware(&self, _: LocalFunctionIndex) -> Box<dyn FunctionMiddleware> {
+        Box::new(FunctionCallDepthLimit {
+            max_depth: self.max_depth,
+            depth_global: self
+                .depth_global
+                .lock()
+                .unwrap()
+                .expect("the depth global is injected before compiling the functions")
+                .as_u32(),
+            checked: false,
+        })
+    }
+
+    fn inject_module_items(&self, injector: &mut ModuleItemInjector) {
+        let depth_global = injector.add_global(
+            GlobalType::new(Type::I32, Mutability::Var),
+            GlobalInit::I32Const(0),
+        );
+        injector.register_globals(CALL_DEPTH_GLOBAL, vec![depth_global]);
+        *self.depth_global.lock().unwrap() = Some(depth_global);
+    }
+}
+
+impl FunctionCallDepthLimit {
+    /// Adds `delta` to the depth.
+    fn add_to_depth(&self, delta: i32, state: &mut MiddlewareReaderState) {
+        state.extend(&[
+            Operator::GlobalGet {
+                global_index: self.depth_global,
+            },
+            Operator::I32Const { value: delta },
+            Operator::I32Add,
+            Operator::GlobalSet {
+                global_index: self.depth_global,
+            },
+        ]);
+    }
+}
+
+impl FunctionMiddleware for FunctionCallDepthLimit {
+    fn feed<'a>(
+        &mut self,
+        operator: Operator<'a>,
+        state: &mut MiddlewareReaderState<'a>,
+    ) -> Result<(), MiddlewareError> {
+        if !self.checked {
+            // if unsigned(globals[depth_global]) >= max_depth { throw(); }
+            state.extend(&[
+                Operator::GlobalGet {
+                    global_index: self.depth_global,
+                },
+                Operator::I32Const {
+                    value: self.max_depth as i32,
+                },
+                Operator::I32GeU,
+                Operator::If {
+                    ty: WpTypeOrFuncType::Type(WpType::EmptyBlockType),
+                },
+                Operator::Unreachable,
+                Operator::End,
+            ]);
+            self.checked = true;
+        }
+
+        match operator {
+            Operator::Call { .. } | Operator::CallIndirect { .. } => {
+                self.add_to_depth(1, state);
+                state.push_operator(operator);
+                self.add_to_depth(-1, state);
+            }
+            // The tail calls replace the frame of the caller, so they
+            // keep the depth.
+            _ => state.push_operator(operator),
+        }
+
+        Ok(())
+    }
+}
@@ -368,6 +368,11 @@ impl<'data> ModuleEnvironment<'data> {
         Ok(())
     }
 
+    pub(crate) fn declare_code_section_offset(&mut self, offset: usize) -> WasmResult<()> {
+        self.result.module.code_section_offset = offset;
+        Ok(())
+    }
+
     pub(crate) fn define_function_body(
         &mut self,
         _module_translation_state: &ModuleTranslationState,
@@ -445,6 +450,21 @@ impl<'data> ModuleEnvironment<'data> {
         Ok(())
     }
 
+    pub(crate) fn declare_local_name(
+        &mut self,
+        func_index: FunctionIndex,
+        local_index: u32,
+        name: &'data str,
+    ) -> WasmResult<()> {
+        self.result
+            .module
+            .local_names
+            .entry(func_index)
+            .or_default()
+            .insert(local_index, name.to_string());
+        Ok(())
+    }
+
     /// Provides the number of imports up front. By default this does nothing, but
     /// implementations can use this to preallocate memory if desired.
     pub(crate) fn reserve_imports(&mut self, _num: u32) -> WasmResult<()> {
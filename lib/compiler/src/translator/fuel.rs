@@ -0,0 +1,97 @@
+//! A budget of compilation work, so a module can't make the engines
+//! spend an unbounded amount of time compiling it.
+
+use super::environ::FunctionBodyData;
+use crate::CompileError;
+use wasmer_types::entity::PrimaryMap;
+use wasmer_types::LocalFunctionIndex;
+use wasmparser::FunctionBody;
+
+/// The fuel of a compilation.
+///
+/// The work is measured from the module alone, so it's the same on
+/// every machine and with every compiler: parsing and validating the
+/// module costs one unit per byte, and generating the code of the
+/// functions costs one unit per operator of their bodies. The fuel is
+/// consumed before each step, so a module exceeding it is rejected
+/// before the work is done.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompileFuel {
+    limit: u64,
+    consumed: u64,
+}
+
+impl CompileFuel {
+    /// Creates the fuel of a compilation doing at most `limit` units
+    /// of work.
+    pub fn new(limit: u64) -> Self {
+        Self { limit, consumed: 0 }
+    }
+
+    /// The units of work allowed.
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// The units of work consumed so far.
+    pub fn consumed(&self) -> u64 {
+        self.consumed
+    }
+
+    /// Consumes `units` of work, or returns
+    /// [`CompileError::FuelExhausted`] if they exceed the remaining
+    /// fuel.
+    pub fn consume(&mut self, units: u64) -> Result<(), CompileError> {
+        match self.consumed.checked_add(units) {
+            Some(consumed) if consumed <= self.limit => {
+                self.consumed = consumed;
+                Ok(())
+            }
+            _ => {
+                self.consumed = self.limit;
+                Err(CompileError::FuelExhausted(self.limit))
+            }
+        }
+    }
+
+    /// Consumes the parsing and the validation of the module `data`.
+    pub fn consume_module(&mut self, data: &[u8]) -> Result<(), CompileError> {
+        self.consume(data.len() as u64)
+    }
+
+    /// Consumes the code generation of the function bodies.
+    pub fn consume_function_bodies(
+        &mut self,
+        function_body_inputs: &PrimaryMap<LocalFunctionIndex, FunctionBodyData<'_>>,
+    ) -> Result<(), CompileError> {
+        for body in function_body_inputs.values() {
+            let body = FunctionBody::new(body.module_offset, body.data);
+            let mut operators = body.get_operators_reader()?;
+            let mut count = 0;
+            while !operators.eof() {
+                operators.read()?;
+                count += 1;
+            }
+            self.consume(count)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn consume() {
+        let mut fuel = CompileFuel::new(10);
+        fuel.consume(4).unwrap();
+        fuel.consume(6).unwrap();
+        assert_eq!(fuel.consumed(), 10);
+        match fuel.consume(1) {
+            Err(CompileError::FuelExhausted(10)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(CompileFuel::new(10).consume(u64::MAX).is_err());
+    }
+}
@@ -0,0 +1,81 @@
+//! A middleware counting the calls of each function, enabled with the
+//! `function_counters` option of the engines.
+
+use std::sync::Mutex;
+use wasmer_types::entity::EntityRef;
+use wasmer_types::{GlobalIndex, LocalFunctionIndex};
+use wasmparser::Operator;
+
+use super::middleware::{
+    FunctionMiddleware, MiddlewareReaderState, ModuleItemInjector, ModuleMiddleware,
+};
+use crate::error::MiddlewareError;
+
+/// The name the counters of [`FunctionCounters`] are registered under,
+/// see [`ModuleItemInjector::add_counters`].
+pub const FUNCTION_COUNTERS: &str = "wasmer_function_counters";
+
+/// The module-level middleware counting the calls of each function.
+///
+/// Each local function gets a counter, incremented on its entry, and
+/// registered under [`FUNCTION_COUNTERS`] in the order of the local
+/// functions.
+///
+/// It keeps the counters of the module being compiled, see
+/// [the state of the module middlewares](ModuleMiddleware#state).
+#[derive(Debug, Default)]
+pub struct FunctionCounters {
+    /// The counters of the local functions of the module being compiled.
+    counters: Mutex<Vec<GlobalIndex>>,
+}
+
+/// The function-level middleware counting the calls of a function.
+#[derive(Debug)]
+struct FunctionCounter {
+    /// The counter of the function.
+    counter: GlobalIndex,
+
+    /// Whether the counter is incremented yet.
+    counted: bool,
+}
+
+impl FunctionCounters {
+    /// Creates a `FunctionCounters` middleware.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ModuleMiddleware for FunctionCounters {
+    fn generate_function_middleware(
+        &self,
+        local_function_index: LocalFunctionIndex,
+    ) -> Box<dyn FunctionMiddleware> {
+        Box::new(FunctionCounter {
+            counter: self.counters.lock().unwrap()[local_function_index.index()],
+            counted: false,
+        })
+    }
+
+    fn inject_module_items(&self, injector: &mut ModuleItemInjector) {
+        let module_info = injector.module_info();
+        let num_local_functions = module_info.functions.len() - module_info.num_imported_functions;
+        *self.counters.lock().unwrap() =
+            injector.add_counters(FUNCTION_COUNTERS, num_local_functions);
+    }
+}
+
+impl FunctionMiddleware for FunctionCounter {
+    fn feed<'a>(
+        &mut self,
+        operator: Operator<'a>,
+        state: &mut MiddlewareReaderState<'a>,
+    ) -> Result<(), MiddlewareError> {
+        if !self.counted {
+            state.increment_counter(self.counter);
+            self.counted = true;
+        }
+        state.push_operator(operator);
+        Ok(())
+    }
+}
@@ -5,13 +5,30 @@ use smallvec::SmallVec;
 use std::collections::VecDeque;
 use std::fmt::Debug;
 use std::ops::Deref;
-use wasmer_types::LocalFunctionIndex;
-use wasmer_vm::ModuleInfo;
+use std::sync::Arc;
+use wasmer_types::entity::PrimaryMap;
+use wasmer_types::{
+    ExportIndex, GlobalIndex, GlobalInit, GlobalType, LocalFunctionIndex, MemoryIndex, MemoryType,
+    Mutability, OwnedDataInitializer,
+};
+use wasmer_vm::{BoundsCheck, MemoryStyle, ModuleInfo};
 use wasmparser::{BinaryReader, Operator, Type};
 
+use super::environ::FunctionBodyData;
 use crate::error::{MiddlewareError, WasmResult};
+use crate::module::CompileModuleInfo;
 
 /// A shared builder for function middlewares.
+///
+/// # State
+///
+/// A module middleware is shared by the modules compiled by an engine,
+/// and may keep the state of the module being compiled, from
+/// [`ModuleMiddleware::inject_module_items`] or
+/// [`ModuleMiddleware::transform_module_info`] to the generation of the
+/// middlewares of its functions, like the items it injected. This
+/// requires the modules to be compiled one after the other, which the
+/// engines do, holding their lock while compiling a module.
 pub trait ModuleMiddleware: Debug + Send + Sync {
     /// Generates a `FunctionMiddleware` for a given function.
     ///
@@ -25,6 +42,107 @@ pub trait ModuleMiddleware: Debug + Send + Sync {
 
     /// Transforms a `ModuleInfo` struct in-place. This is called before application on functions begins.
     fn transform_module_info(&self, _: &mut ModuleInfo) {}
+
+    /// Injects module-level items in the module, like the globals or
+    /// the memories keeping the state shared by the instrumented
+    /// functions. Registering the globals, or exporting the items,
+    /// lets the host read the results after the execution.
+    ///
+    /// This is called before `transform_module_info`, and before
+    /// application on functions begins.
+    fn inject_module_items(&self, _: &mut ModuleItemInjector) {}
+
+    /// Transforms the data initializers of the module in-place. This
+    /// is called by the engines, independently of the compilation of
+    /// the module.
+    fn transform_data_initializers(&self, _: &mut Vec<OwnedDataInitializer>) {}
+}
+
+/// Injects module-level items in a module being compiled, see
+/// [`ModuleMiddleware::inject_module_items`].
+///
+/// The items are appended after the ones of the module, so they don't
+/// change the indexes the function bodies refer to.
+pub struct ModuleItemInjector<'a> {
+    module_info: &'a mut ModuleInfo,
+    memory_styles: &'a mut PrimaryMap<MemoryIndex, MemoryStyle>,
+    memory_bounds_checks: &'a mut PrimaryMap<MemoryIndex, BoundsCheck>,
+    function_bodies: &'a PrimaryMap<LocalFunctionIndex, FunctionBodyData<'a>>,
+}
+
+impl<'a> Debug for ModuleItemInjector<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ModuleItemInjector")
+            .field("module_info", &self.module_info)
+            .field("memory_styles", &self.memory_styles)
+            .field("memory_bounds_checks", &self.memory_bounds_checks)
+            .finish()
+    }
+}
+
+impl<'a> ModuleItemInjector<'a> {
+    /// The module the items are injected in.
+    pub fn module_info(&self) -> &ModuleInfo {
+        self.module_info
+    }
+
+    /// The bodies of the functions of the module, before they're
+    /// transformed, to size the injected items.
+    pub fn function_bodies(&self) -> &PrimaryMap<LocalFunctionIndex, FunctionBodyData<'a>> {
+        self.function_bodies
+    }
+
+    /// Injects a global initialized with `init`.
+    pub fn add_global(&mut self, ty: GlobalType, init: GlobalInit) -> GlobalIndex {
+        let index = self.module_info.globals.push(ty);
+        self.module_info.global_initializers.push(init);
+        index
+    }
+
+    /// Injects a zero-initialized memory.
+    ///
+    /// Its accesses are bounds-checked explicitly, whatever the
+    /// tunables, as they aren't known by the middlewares.
+    pub fn add_memory(&mut self, ty: MemoryType) -> MemoryIndex {
+        let index = self.module_info.memories.push(ty);
+        let style_index = self.memory_styles.push(MemoryStyle::Dynamic {
+            offset_guard_size: 0,
+        });
+        debug_assert_eq!(index, style_index);
+        self.memory_bounds_checks.push(BoundsCheck::Explicit);
+        index
+    }
+
+    /// Registers injected globals under `name`, so the host can find
+    /// them in the instances without them being exported, see
+    /// [`ModuleInfo::middleware_globals`]. The globals registered
+    /// under the same name before are replaced.
+    pub fn register_globals(&mut self, name: impl Into<String>, globals: Vec<GlobalIndex>) {
+        self.module_info
+            .middleware_globals
+            .insert(name.into(), globals);
+    }
+
+    /// Injects `count` `i64` counters initialized to 0, registered
+    /// under `name` with [`ModuleItemInjector::register_globals`].
+    ///
+    /// The instrumented functions increment them with
+    /// [`MiddlewareReaderState::increment_counter`], and the host reads
+    /// them with `Instance::counters`.
+    pub fn add_counters(&mut self, name: impl Into<String>, count: usize) -> Vec<GlobalIndex> {
+        let ty = GlobalType::new(wasmer_types::Type::I64, Mutability::Var);
+        let counters = (0..count)
+            .map(|_| self.add_global(ty, GlobalInit::I64Const(0)))
+            .collect::<Vec<_>>();
+        self.register_globals(name, counters.clone());
+        counters
+    }
+
+    /// Exports an item of the module as `name`, replacing the export
+    /// with the same name, if any.
+    pub fn export(&mut self, name: impl Into<String>, index: ExportIndex) {
+        self.module_info.exports.insert(name.into(), index);
+    }
 }
 
 /// A function middleware specialized for a single function.
@@ -70,6 +188,18 @@ pub trait ModuleMiddlewareChain {
 
     /// Applies the chain on a `ModuleInfo` struct.
     fn apply_on_module_info(&self, module_info: &mut ModuleInfo);
+
+    /// Applies the chain on a `CompileModuleInfo` struct: injects the
+    /// module-level items of the middlewares, then transforms its
+    /// `ModuleInfo`.
+    fn apply_on_compile_info(
+        &self,
+        compile_info: &mut CompileModuleInfo,
+        function_bodies: &PrimaryMap<LocalFunctionIndex, FunctionBodyData<'_>>,
+    );
+
+    /// Applies the chain on the data initializers of a module.
+    fn apply_on_data_initializers(&self, data_initializers: &mut Vec<OwnedDataInitializer>);
 }
 
 impl<T: Deref<Target = dyn ModuleMiddleware>> ModuleMiddlewareChain for [T] {
@@ -89,6 +219,33 @@ impl<T: Deref<Target = dyn ModuleMiddleware>> ModuleMiddlewareChain for [T] {
             item.transform_module_info(module_info);
         }
     }
+
+    /// Applies the chain on a `CompileModuleInfo` struct.
+    fn apply_on_compile_info(
+        &self,
+        compile_info: &mut CompileModuleInfo,
+        function_bodies: &PrimaryMap<LocalFunctionIndex, FunctionBodyData<'_>>,
+    ) {
+        let mut module = (*compile_info.module).clone();
+        let mut injector = ModuleItemInjector {
+            module_info: &mut module,
+            memory_styles: &mut compile_info.memory_styles,
+            memory_bounds_checks: &mut compile_info.memory_bounds_checks,
+            function_bodies,
+        };
+        for item in self {
+            item.inject_module_items(&mut injector);
+        }
+        self.apply_on_module_info(&mut module);
+        compile_info.module = Arc::new(module);
+    }
+
+    /// Applies the chain on the data initializers of a module.
+    fn apply_on_data_initializers(&self, data_initializers: &mut Vec<OwnedDataInitializer>) {
+        for item in self {
+            item.transform_data_initializers(data_initializers);
+        }
+    }
 }
 
 impl<'a> MiddlewareReaderState<'a> {
@@ -96,6 +253,18 @@ impl<'a> MiddlewareReaderState<'a> {
     pub fn push_operator(&mut self, operator: Operator<'a>) {
         self.pending_operations.push_back(operator);
     }
+
+    /// Pushes the operators incrementing `counter`, injected with
+    /// [`ModuleItemInjector::add_counters`].
+    pub fn increment_counter(&mut self, counter: GlobalIndex) {
+        let global_index = counter.as_u32();
+        self.extend(&[
+            Operator::GlobalGet { global_index },
+            Operator::I64Const { value: 1 },
+            Operator::I64Add,
+            Operator::GlobalSet { global_index },
+        ]);
+    }
 }
 
 impl<'a> Extend<Operator<'a>> for MiddlewareReaderState<'a> {
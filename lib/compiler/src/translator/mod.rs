@@ -5,19 +5,27 @@
 //! compilers rather than just Cranelift.
 //!
 //! [cranelift-wasm]: https://crates.io/crates/cranelift-wasm/
+mod call_depth;
 mod environ;
+mod fuel;
+mod function_counters;
 mod middleware;
 mod module;
+mod prepass;
 mod state;
 #[macro_use]
 mod error;
 mod sections;
 
+pub use self::call_depth::{CallDepthLimit, CALL_DEPTH_GLOBAL, DEFAULT_MAX_CALL_DEPTH};
 pub use self::environ::{FunctionBodyData, ModuleEnvironment, ModuleInfoTranslation};
+pub use self::fuel::CompileFuel;
+pub use self::function_counters::{FunctionCounters, FUNCTION_COUNTERS};
 pub use self::middleware::{
-    FunctionMiddleware, MiddlewareBinaryReader, MiddlewareReaderState, ModuleMiddleware,
-    ModuleMiddlewareChain,
+    FunctionMiddleware, MiddlewareBinaryReader, MiddlewareReaderState, ModuleItemInjector,
+    ModuleMiddleware, ModuleMiddlewareChain,
 };
 pub use self::module::translate_module;
+pub use self::prepass::Prepass;
 pub use self::sections::wptype_to_type;
 pub use self::state::ModuleTranslationState;
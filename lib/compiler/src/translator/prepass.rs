@@ -0,0 +1,326 @@
+//! A whole-module optimization pre-pass, run by the middleware chain
+//! before the functions are compiled.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use wasmer_types::entity::PrimaryMap;
+use wasmer_types::{
+    ExportIndex, FunctionIndex, GlobalInit, LocalFunctionIndex, Mutability, OwnedDataInitializer,
+};
+use wasmer_vm::ModuleInfo;
+use wasmparser::{FunctionBody, Operator};
+
+use super::environ::FunctionBodyData;
+use super::middleware::{
+    FunctionMiddleware, MiddlewareReaderState, ModuleItemInjector, ModuleMiddleware,
+};
+use crate::error::MiddlewareError;
+
+/// The module-level middleware optimizing the module as a whole before
+/// its functions are compiled, for the bloated output of some
+/// toolchains:
+///
+/// - The `global.get`s of the immutable globals initialized with a
+///   numeric constant are replaced with the constant.
+/// - The functions which can't be called from the roots (the exports,
+///   the start function and the elements of the tables) are compiled
+///   as a single `unreachable`. With [`Prepass::with_roots`], only the
+///   given exports are kept, and the others are removed.
+/// - The data segments written next to each other are merged, so the
+///   memories are initialized with fewer copies.
+///
+/// The functions and the globals keep their indexes, so the dead ones
+/// are left in the module, without code.
+///
+/// It keeps the analysis of the module being compiled, see
+/// [the state of the module middlewares](ModuleMiddleware#state).
+#[derive(Debug, Default)]
+pub struct Prepass {
+    /// The names of the exports to keep, all of them if `None`.
+    roots: Option<HashSet<String>>,
+
+    /// The analysis of the module being compiled.
+    analysis: Mutex<Option<Arc<Analysis>>>,
+}
+
+/// The results of the analysis of a module.
+#[derive(Debug, Default)]
+struct Analysis {
+    /// The constant values of the globals.
+    constants: HashMap<u32, GlobalInit>,
+
+    /// The local functions which can't be called.
+    dead_functions: HashSet<LocalFunctionIndex>,
+}
+
+/// The function-level middleware optimizing a function.
+#[derive(Debug)]
+struct FunctionPrepass {
+    /// The analysis of the module.
+    analysis: Arc<Analysis>,
+
+    /// Whether the function can't be called, so its body is dropped.
+    dead: bool,
+
+    /// The depth of the blocks, to find the end of a dropped body.
+    depth: u32,
+}
+
+impl Prepass {
+    /// Creates a `Prepass` middleware keeping all the exports.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a `Prepass` middleware keeping only the exports named
+    /// in `roots`, and the functions they can call.
+    pub fn with_roots<I, S>(roots: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            roots: Some(roots.into_iter().map(Into::into).collect()),
+            analysis: Mutex::new(None),
+        }
+    }
+
+    /// Whether the export `name` is kept.
+    fn is_root(&self, name: &str) -> bool {
+        self.roots
+            .as_ref()
+            .map_or(true, |roots| roots.contains(name))
+    }
+
+    fn analyze(
+        &self,
+        module_info: &ModuleInfo,
+        function_bodies: &PrimaryMap<LocalFunctionIndex, FunctionBodyData<'_>>,
+    ) -> Option<Analysis> {
+        let constants = module_info
+            .global_initializers
+            .iter()
+            .filter(|(index, init)| {
+                module_info.globals[module_info.global_index(*index)].mutability
+                    == Mutability::Const
+                    && matches!(
+                        init,
+                        GlobalInit::I32Const(_)
+                            | GlobalInit::I64Const(_)
+                            | GlobalInit::F32Const(_)
+                            | GlobalInit::F64Const(_)
+                    )
+            })
+            .map(|(index, init)| (module_info.global_index(index).as_u32(), *init))
+            .collect();
+
+        // The functions called, or referenced, by each function.
+        let mut callees = HashMap::new();
+        for (index, body) in function_bodies.iter() {
+            let body = FunctionBody::new(body.module_offset, body.data);
+            let mut operators = body.get_operators_reader().ok()?;
+            let mut referenced = vec![];
+            while !operators.eof() {
+                match operators.read().ok()? {
+                    Operator::Call { function_index } | Operator::RefFunc { function_index } => {
+                        referenced.push(FunctionIndex::from_u32(function_index))
+                    }
+                    _ => {}
+                }
+            }
+            callees.insert(index, referenced);
+        }
+
+        let mut live = module_info
+            .exports
+            .iter()
+            .filter(|(name, _)| self.is_root(name))
+            .filter_map(|(_, export)| match export {
+                ExportIndex::Function(index) => Some(*index),
+                _ => None,
+            })
+            .chain(module_info.start_function)
+            .chain(
+                module_info
+                    .table_initializers
+                    .iter()
+                    .flat_map(|initializer| initializer.elements.iter().copied()),
+            )
+            .chain(
+                module_info
+                    .passive_elements
+                    .values()
+                    .flat_map(|elements| elements.iter().copied()),
+            )
+            .chain(
+                module_info
+                    .global_initializers
+                    .values()
+                    .filter_map(|init| match init {
+                        GlobalInit::RefFunc(index) => Some(*index),
+                        _ => None,
+                    }),
+            )
+            .collect::<Vec<_>>();
+        let mut reached = HashSet::new();
+        while let Some(index) = live.pop() {
+            if let Some(local_index) = module_info.local_func_index(index) {
+                if reached.insert(local_index) {
+                    live.extend(callees.get(&local_index).into_iter().flatten());
+                }
+            }
+        }
+        let dead_functions = function_bodies
+            .keys()
+            .filter(|index| !reached.contains(index))
+            .collect();
+
+        Some(Analysis {
+            constants,
+            dead_functions,
+        })
+    }
+}
+
+impl ModuleMiddleware for Prepass {
+    fn generate_function_middleware(
+        &self,
+        local_function_index: LocalFunctionIndex,
+    ) -> Box<dyn FunctionMiddleware> {
+        let analysis = self
+            .analysis
+            .lock()
+            .unwrap()
+            .clone()
+            .expect("the module is analyzed before compiling the functions");
+        Box::new(FunctionPrepass {
+            dead: analysis.dead_functions.contains(&local_function_index),
+            analysis,
+            depth: 0,
+        })
+    }
+
+    fn inject_module_items(&self, injector: &mut ModuleItemInjector) {
+        // Leave a module which can't be read to the compiler, so it
+        // reports the error.
+        let analysis = self
+            .analyze(injector.module_info(), injector.function_bodies())
+            .unwrap_or_default();
+        *self.analysis.lock().unwrap() = Some(Arc::new(analysis));
+    }
+
+    fn transform_module_info(&self, module_info: &mut ModuleInfo) {
+        if self.roots.is_some() {
+            module_info.exports.retain(|name, _| self.is_root(name));
+        }
+    }
+
+    fn transform_data_initializers(&self, data_initializers: &mut Vec<OwnedDataInitializer>) {
+        let mut merged: Vec<OwnedDataInitializer> = Vec::with_capacity(data_initializers.len());
+        for initializer in data_initializers.drain(..) {
+            if let Some(last) = merged.last_mut() {
+                let location = &initializer.location;
+                if location.memory_index == last.location.memory_index
+                    && location.base.is_none()
+                    && last.location.base.is_none()
+                    && last.location.offset.checked_add(last.data.len()) == Some(location.offset)
+                {
+                    let mut data = std::mem::take(&mut last.data).into_vec();
+                    data.extend_from_slice(&initializer.data);
+                    last.data = data.into_boxed_slice();
+                    continue;
+                }
+            }
+            merged.push(initializer);
+        }
+        *data_initializers = merged;
+    }
+}
+
+impl FunctionMiddleware for FunctionPrepass {
+    fn feed<'a>(
+        &mut self,
+        operator: Operator<'a>,
+        state: &mut MiddlewareReaderState<'a>,
+    ) -> Result<(), MiddlewareError> {
+        if self.dead {
+            match operator {
+                Operator::Block { .. } | Operator::Loop { .. } | Operator::If { .. } => {
+                    self.depth += 1
+                }
+                Operator::End if self.depth == 0 => {
+                    state.extend(&[Operator::Unreachable, Operator::End]);
+                }
+                Operator::End => self.depth -= 1,
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        match operator {
+            Operator::GlobalGet { global_index } => {
+                match self.analysis.constants.get(&global_index) {
+                    Some(GlobalInit::I32Const(value)) => {
+                        state.push_operator(Operator::I32Const { value: *value })
+                    }
+                    Some(GlobalInit::I64Const(value)) => {
+                        state.push_operator(Operator::I64Const { value: *value })
+                    }
+                    Some(GlobalInit::F32Const(value)) => state.extend(&[
+                        Operator::I32Const {
+                            value: value.to_bits() as i32,
+                        },
+                        Operator::F32ReinterpretI32,
+                    ]),
+                    Some(GlobalInit::F64Const(value)) => state.extend(&[
+                        Operator::I64Const {
+                            value: value.to_bits() as i64,
+                        },
+                        Operator::F64ReinterpretI64,
+                    ]),
+                    _ => state.push_operator(operator),
+                }
+            }
+            _ => state.push_operator(operator),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasmer_types::entity::EntityRef;
+    use wasmer_types::{DataInitializerLocation, MemoryIndex};
+
+    fn initializer(offset: usize, data: &[u8]) -> OwnedDataInitializer {
+        OwnedDataInitializer {
+            location: DataInitializerLocation {
+                memory_index: MemoryIndex::new(0),
+                base: None,
+                offset,
+            },
+            data: data.into(),
+        }
+    }
+
+    #[test]
+    fn merges_adjacent_data_segments() {
+        let mut initializers = vec![
+            initializer(0, b"ab"),
+            initializer(2, b"cd"),
+            initializer(8, b"ef"),
+            initializer(10, b"g"),
+            initializer(4, b"h"),
+        ];
+        Prepass::new().transform_data_initializers(&mut initializers);
+        let merged = initializers
+            .iter()
+            .map(|initializer| (initializer.location.offset, &*initializer.data))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            merged,
+            vec![(0, &b"abcd"[..]), (8, &b"efg"[..]), (4, &b"h"[..])]
+        );
+    }
+}
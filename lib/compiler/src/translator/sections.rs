@@ -439,7 +439,31 @@ pub fn parse_name_section<'data>(
                     environ.declare_module_name(name)?;
                 }
             }
-            wasmparser::Name::Local(_) => {}
+            wasmparser::Name::Local(local_subsection) => {
+                if let Ok(mut functions) = local_subsection.get_function_local_reader() {
+                    for _ in 0..functions.get_count() {
+                        let function = match functions.read() {
+                            Ok(function) => function,
+                            Err(_) => break,
+                        };
+                        let mut naming_reader = match function.get_map() {
+                            Ok(naming_reader) => naming_reader,
+                            Err(_) => continue,
+                        };
+                        for _ in 0..naming_reader.get_count() {
+                            let Naming { index, name } = match naming_reader.read() {
+                                Ok(naming) => naming,
+                                Err(_) => break,
+                            };
+                            environ.declare_local_name(
+                                FunctionIndex::from_u32(function.func_index),
+                                index,
+                                name,
+                            )?;
+                        }
+                    }
+                }
+            }
         };
     }
     Ok(())
@@ -25,3 +25,25 @@ pub enum CompiledFunctionUnwindInfo {
     /// The unwind info is added to the Dwarf section in `Compilation`.
     Dwarf,
 }
+
+/// The unwind information that an engine registers for the compiled code.
+///
+/// Host crash reporters and profilers walk the native stack with the
+/// registered unwind information, or by following the chain of frame
+/// pointers when there is none.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnwindInfoMode {
+    /// Register the unwind information emitted by the compiler, if any.
+    Default,
+    /// Keep the frame pointer in every function, and register unwind
+    /// information based on it for the functions the compiler didn't
+    /// emit any for, so the whole stack can be walked through the
+    /// WebAssembly frames.
+    FullWithFP,
+}
+
+impl Default for UnwindInfoMode {
+    fn default() -> Self {
+        Self::Default
+    }
+}
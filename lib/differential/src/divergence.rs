@@ -0,0 +1,198 @@
+use std::fmt;
+use wasmer::{ExternRef, Pages, Val};
+
+/// The outcome of an invocation on one backend.
+#[derive(Debug, Clone)]
+pub enum Outcome {
+    /// The function returned these results.
+    Returned(Vec<Val>),
+    /// The function trapped with this message.
+    Trapped(String),
+}
+
+impl Outcome {
+    /// Whether the two outcomes agree.
+    ///
+    /// Two traps agree whatever their messages unless
+    /// `compare_trap_messages` is set.
+    pub(crate) fn agrees_with(&self, other: &Self, compare_trap_messages: bool) -> bool {
+        match (self, other) {
+            (Self::Returned(left), Self::Returned(right)) => {
+                left.len() == right.len()
+                    && left
+                        .iter()
+                        .zip(right)
+                        .all(|(left, right)| same_value(left, right))
+            }
+            (Self::Trapped(left), Self::Trapped(right)) => !compare_trap_messages || left == right,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Returned(results) => write!(f, "returned {:?}", results),
+            Self::Trapped(message) => write!(f, "trapped: {}", message),
+        }
+    }
+}
+
+/// Whether two values of different backends are the same.
+///
+/// The floats are compared bit for bit, except that any two NaNs are
+/// the same: WebAssembly lets each backend pick the bits of the NaNs
+/// it produces, unless the compilers canonicalize them. The references
+/// can't be compared across stores, so only their nullness is.
+pub(crate) fn same_value(left: &Val, right: &Val) -> bool {
+    match (left, right) {
+        (Val::I32(left), Val::I32(right)) => left == right,
+        (Val::I64(left), Val::I64(right)) => left == right,
+        (Val::F32(left), Val::F32(right)) => {
+            (left.is_nan() && right.is_nan()) || left.to_bits() == right.to_bits()
+        }
+        (Val::F64(left), Val::F64(right)) => {
+            (left.is_nan() && right.is_nan()) || left.to_bits() == right.to_bits()
+        }
+        (Val::V128(left), Val::V128(right)) => left == right,
+        (Val::FuncRef(_), Val::FuncRef(_)) => true,
+        (Val::ExternRef(left), Val::ExternRef(right)) => {
+            matches!(left, ExternRef::Null) == matches!(right, ExternRef::Null)
+        }
+        _ => false,
+    }
+}
+
+/// The first place where the two backends diverge.
+#[derive(Debug, Clone)]
+pub enum Divergence {
+    /// Only one backend compiled the module. The error of the other
+    /// one is reported.
+    Compilation {
+        /// The error of the left backend, if it failed.
+        left: Option<String>,
+        /// The error of the right backend, if it failed.
+        right: Option<String>,
+    },
+    /// Only one backend instantiated the module. The error of the
+    /// other one is reported.
+    Instantiation {
+        /// The error of the left backend, if it failed.
+        left: Option<String>,
+        /// The error of the right backend, if it failed.
+        right: Option<String>,
+    },
+    /// An invocation had different outcomes.
+    Invocation {
+        /// The index of the invocation.
+        index: usize,
+        /// The function called by the invocation.
+        function: String,
+        /// The outcome on the left backend.
+        left: Outcome,
+        /// The outcome on the right backend.
+        right: Outcome,
+    },
+    /// An exported memory has different sizes.
+    MemorySize {
+        /// The name of the memory.
+        name: String,
+        /// The size on the left backend.
+        left: Pages,
+        /// The size on the right backend.
+        right: Pages,
+    },
+    /// An exported memory has different bytes. The first different
+    /// byte is reported.
+    MemoryContents {
+        /// The name of the memory.
+        name: String,
+        /// The offset of the byte.
+        offset: u64,
+        /// The byte on the left backend.
+        left: u8,
+        /// The byte on the right backend.
+        right: u8,
+    },
+    /// An exported global has different values.
+    Global {
+        /// The name of the global.
+        name: String,
+        /// The value on the left backend.
+        left: Val,
+        /// The value on the right backend.
+        right: Val,
+    },
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let error = |error: &Option<String>| match error {
+            Some(error) => format!("failed: {}", error),
+            None => "succeeded".to_string(),
+        };
+        match self {
+            Self::Compilation { left, right } => write!(
+                f,
+                "the compilation diverges: left {}, right {}",
+                error(left),
+                error(right)
+            ),
+            Self::Instantiation { left, right } => write!(
+                f,
+                "the instantiation diverges: left {}, right {}",
+                error(left),
+                error(right)
+            ),
+            Self::Invocation {
+                index,
+                function,
+                left,
+                right,
+            } => write!(
+                f,
+                "the invocation #{} of `{}` diverges: left {}, right {}",
+                index, function, left, right
+            ),
+            Self::MemorySize { name, left, right } => write!(
+                f,
+                "the size of the memory `{}` diverges: left {:?}, right {:?}",
+                name, left, right
+            ),
+            Self::MemoryContents {
+                name,
+                offset,
+                left,
+                right,
+            } => write!(
+                f,
+                "the byte {:#x} of the memory `{}` diverges: left {:#04x}, right {:#04x}",
+                offset, name, left, right
+            ),
+            Self::Global { name, left, right } => write!(
+                f,
+                "the global `{}` diverges: left {:?}, right {:?}",
+                name, left, right
+            ),
+        }
+    }
+}
+
+/// The report of a differential run.
+#[derive(Debug, Clone, Default)]
+pub struct Report {
+    /// The outcomes the backends agreed on, one per invocation, up to
+    /// the divergence if any. Those are the outcomes of the left
+    /// backend.
+    pub outcomes: Vec<Outcome>,
+    /// The first divergence of the backends, if any.
+    pub divergence: Option<Divergence>,
+}
+
+impl Report {
+    /// Whether the backends agreed on everything.
+    pub fn is_consistent(&self) -> bool {
+        self.divergence.is_none()
+    }
+}
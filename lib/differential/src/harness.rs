@@ -0,0 +1,286 @@
+use crate::divergence::{same_value, Divergence, Outcome, Report};
+use thiserror::Error;
+use wasmer::{
+    CompileError, ExportError, ImportObject, Instance, InstantiationError, Memory, Module,
+    RuntimeError, Store, Val,
+};
+
+/// The size of the chunks in which the memories are compared.
+const CHUNK_SIZE: u64 = 0x10000;
+
+/// An error of a differential run, which both backends hit the same
+/// way, so that there's nothing to compare.
+#[derive(Error, Debug)]
+pub enum DifferentialError {
+    /// Both backends failed to compile the module. The error of the
+    /// left backend is reported.
+    #[error("both backends failed to compile the module: {0}")]
+    Compile(CompileError),
+    /// Both backends failed to instantiate the module. The error of
+    /// the left backend is reported.
+    #[error("both backends failed to instantiate the module: {0}")]
+    Instantiation(InstantiationError),
+    /// An invocation calls a function which the module doesn't export.
+    #[error("the invocation #{index} calls an unknown function: {error}")]
+    UnknownFunction {
+        /// The index of the invocation.
+        index: usize,
+        /// The error getting the function.
+        error: ExportError,
+    },
+}
+
+/// A backend to run the module on: a store, configured with any
+/// engine, compiler and middlewares.
+#[derive(Clone)]
+pub struct Backend {
+    name: String,
+    store: Store,
+}
+
+impl Backend {
+    /// Creates a backend named `name`, which runs the module in
+    /// `store`.
+    pub fn new(name: impl Into<String>, store: Store) -> Self {
+        Self {
+            name: name.into(),
+            store,
+        }
+    }
+
+    /// The name of the backend.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The store of the backend.
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+}
+
+/// A call of an exported function with the given parameters.
+#[derive(Debug, Clone)]
+pub struct Invocation {
+    /// The name of the exported function.
+    pub function: String,
+    /// The parameters of the call.
+    pub params: Vec<Val>,
+}
+
+impl Invocation {
+    /// Creates an invocation of the exported function `function`.
+    pub fn new(function: impl Into<String>, params: Vec<Val>) -> Self {
+        Self {
+            function: function.into(),
+            params,
+        }
+    }
+}
+
+/// The differential harness, comparing two backends.
+pub struct Differential {
+    left: Backend,
+    right: Backend,
+    compare_trap_messages: bool,
+    compare_memories: bool,
+    compare_globals: bool,
+}
+
+impl Differential {
+    /// Creates a harness comparing the `left` and `right` backends.
+    ///
+    /// By default, the harness compares the trap messages, the
+    /// exported memories and the exported globals.
+    pub fn new(left: Backend, right: Backend) -> Self {
+        Self {
+            left,
+            right,
+            compare_trap_messages: true,
+            compare_memories: true,
+            compare_globals: true,
+        }
+    }
+
+    /// The left backend.
+    pub fn left(&self) -> &Backend {
+        &self.left
+    }
+
+    /// The right backend.
+    pub fn right(&self) -> &Backend {
+        &self.right
+    }
+
+    /// Whether two traps must have the same message to agree, rather
+    /// than only both trap.
+    ///
+    /// The backends which detect a trap differently, like a stack
+    /// overflow, can report it with a different message.
+    pub fn compare_trap_messages(&mut self, enable: bool) -> &mut Self {
+        self.compare_trap_messages = enable;
+        self
+    }
+
+    /// Whether to compare the exported memories once the invocations
+    /// are done.
+    pub fn compare_memories(&mut self, enable: bool) -> &mut Self {
+        self.compare_memories = enable;
+        self
+    }
+
+    /// Whether to compare the exported globals once the invocations
+    /// are done.
+    pub fn compare_globals(&mut self, enable: bool) -> &mut Self {
+        self.compare_globals = enable;
+        self
+    }
+
+    /// Compiles and instantiates `wasm` on both backends, calls the
+    /// `invocations` on both instances, and compares them.
+    ///
+    /// The `imports` of each instance are created by calling `imports`
+    /// with the store of its backend. The host functions must behave
+    /// the same for both backends, or the divergence is theirs.
+    ///
+    /// The invocations keep running after the backends agree on a
+    /// trap, and the run stops at the first divergence.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when both backends fail to compile or to
+    /// instantiate the module, or when an invocation calls a function
+    /// the module doesn't export.
+    pub fn run<F>(
+        &self,
+        wasm: &[u8],
+        imports: F,
+        invocations: &[Invocation],
+    ) -> Result<Report, DifferentialError>
+    where
+        F: Fn(&Store) -> ImportObject,
+    {
+        let diverge = |divergence| {
+            Ok(Report {
+                outcomes: vec![],
+                divergence: Some(divergence),
+            })
+        };
+
+        let (left, right) = match (
+            Module::new(self.left.store(), wasm),
+            Module::new(self.right.store(), wasm),
+        ) {
+            (Ok(left), Ok(right)) => (left, right),
+            (Err(error), Err(_)) => return Err(DifferentialError::Compile(error)),
+            (left, right) => {
+                return diverge(Divergence::Compilation {
+                    left: left.err().map(|error| error.to_string()),
+                    right: right.err().map(|error| error.to_string()),
+                })
+            }
+        };
+
+        let (left, right) = match (
+            Instance::new(&left, &imports(self.left.store())),
+            Instance::new(&right, &imports(self.right.store())),
+        ) {
+            (Ok(left), Ok(right)) => (left, right),
+            (Err(error), Err(_)) => return Err(DifferentialError::Instantiation(error)),
+            (left, right) => {
+                return diverge(Divergence::Instantiation {
+                    left: left.err().map(|error| error.to_string()),
+                    right: right.err().map(|error| error.to_string()),
+                })
+            }
+        };
+
+        let mut report = Report::default();
+        for (index, invocation) in invocations.iter().enumerate() {
+            let unknown_function = |error| DifferentialError::UnknownFunction { index, error };
+            let left_function = left
+                .exports
+                .get_function(&invocation.function)
+                .map_err(unknown_function)?;
+            let right_function = right
+                .exports
+                .get_function(&invocation.function)
+                .map_err(unknown_function)?;
+            let outcome = |result: Result<Box<[Val]>, RuntimeError>| match result {
+                Ok(results) => Outcome::Returned(results.into_vec()),
+                Err(error) => Outcome::Trapped(error.message()),
+            };
+            let left_outcome = outcome(left_function.call(&invocation.params));
+            let right_outcome = outcome(right_function.call(&invocation.params));
+            if !left_outcome.agrees_with(&right_outcome, self.compare_trap_messages) {
+                report.divergence = Some(Divergence::Invocation {
+                    index,
+                    function: invocation.function.clone(),
+                    left: left_outcome,
+                    right: right_outcome,
+                });
+                return Ok(report);
+            }
+            report.outcomes.push(left_outcome);
+        }
+
+        if self.compare_memories {
+            for (name, left_memory) in left.exports.iter().memories() {
+                if let Ok(right_memory) = right.exports.get_memory(name) {
+                    report.divergence = compare_memories(name, left_memory, right_memory);
+                    if report.divergence.is_some() {
+                        return Ok(report);
+                    }
+                }
+            }
+        }
+
+        if self.compare_globals {
+            for (name, left_global) in left.exports.iter().globals() {
+                if let Ok(right_global) = right.exports.get_global(name) {
+                    let (left_value, right_value) = (left_global.get(), right_global.get());
+                    if !same_value(&left_value, &right_value) {
+                        report.divergence = Some(Divergence::Global {
+                            name: name.clone(),
+                            left: left_value,
+                            right: right_value,
+                        });
+                        return Ok(report);
+                    }
+                }
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+/// Compares the sizes, then the bytes, of two memories.
+fn compare_memories(name: &str, left: &Memory, right: &Memory) -> Option<Divergence> {
+    if left.size() != right.size() {
+        return Some(Divergence::MemorySize {
+            name: name.to_string(),
+            left: left.size(),
+            right: right.size(),
+        });
+    }
+    let size = left.data_size();
+    let mut left_chunk = vec![0; CHUNK_SIZE as usize];
+    let mut right_chunk = vec![0; CHUNK_SIZE as usize];
+    let mut offset = 0;
+    while offset < size {
+        let len = (size - offset).min(CHUNK_SIZE) as usize;
+        left.read(offset, &mut left_chunk[..len]).ok()?;
+        right.read(offset, &mut right_chunk[..len]).ok()?;
+        if let Some(index) = (0..len).find(|&index| left_chunk[index] != right_chunk[index]) {
+            return Some(Divergence::MemoryContents {
+                name: name.to_string(),
+                offset: offset + index as u64,
+                left: left_chunk[index],
+                right: right_chunk[index],
+            });
+        }
+        offset += len as u64;
+    }
+    None
+}
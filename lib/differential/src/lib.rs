@@ -0,0 +1,62 @@
+//! The `wasmer-differential` crate runs the same WebAssembly module on
+//! two backends, e.g. Singlepass and Cranelift, or the same compiler
+//! with and without a middleware, and reports the first place where
+//! they diverge.
+//!
+//! A backend is a [`Store`][wasmer::Store], configured with any engine,
+//! compiler, features and middlewares. The [`Differential`] harness
+//! compiles and instantiates the module on each backend, calls the
+//! same [`Invocation`]s on both instances, and compares their results
+//! and traps. Once the invocations are done, it compares the exported
+//! memories and globals of the instances.
+//!
+//! # Example
+//!
+//! ```
+//! use wasmer::{imports, wat2wasm, Store, Val};
+//! use wasmer_differential::{Backend, Differential, Invocation};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let wasm = wat2wasm(
+//!     br#"(module
+//!       (func (export "div") (param i32 i32) (result i32)
+//!         (i32.div_s (local.get 0) (local.get 1))))"#,
+//! )?;
+//! let differential = Differential::new(
+//!     Backend::new("left", Store::default()),
+//!     Backend::new("right", Store::default()),
+//! );
+//! let report = differential.run(
+//!     &wasm,
+//!     |_store| imports! {},
+//!     &[
+//!         Invocation::new("div", vec![Val::I32(7), Val::I32(2)]),
+//!         Invocation::new("div", vec![Val::I32(1), Val::I32(0)]),
+//!     ],
+//! )?;
+//! assert!(report.divergence.is_none());
+//! # Ok(())
+//! # }
+//! ```
+
+#![deny(missing_docs, trivial_numeric_casts, unused_extern_crates)]
+#![warn(unused_import_braces)]
+#![cfg_attr(
+    feature = "cargo-clippy",
+    warn(
+        clippy::float_arithmetic,
+        clippy::mut_mut,
+        clippy::nonminimal_bool,
+        clippy::option_map_unwrap_or,
+        clippy::option_map_unwrap_or_else,
+        clippy::print_stdout,
+        clippy::unicode_not_nfc,
+        clippy::use_self
+    )
+)]
+
+mod divergence;
+mod harness;
+
+pub use crate::divergence::{Divergence, Outcome, Report};
+pub use crate::harness::{Backend, Differential, DifferentialError, Invocation};
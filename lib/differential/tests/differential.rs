@@ -0,0 +1,136 @@
+use anyhow::Result;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::Arc;
+use wasmer::{imports, wat2wasm, Function, FunctionType, Store, Type, Val};
+use wasmer_differential::{Backend, Differential, Divergence, Invocation, Outcome};
+
+const WAT: &str = r#"
+    (module
+        (import "host" "seed" (func $seed (result i32)))
+        (memory (export "memory") 1)
+        (global $last (export "last") (mut i32) (i32.const 0))
+        (func (export "div") (param i32 i32) (result i32)
+            (i32.div_s (local.get 0) (local.get 1)))
+        (func (export "store_seed") (param i32)
+            (i32.store (local.get 0) (call $seed)))
+        (func (export "set_last") (param i32)
+            (global.set $last (local.get 0))))
+"#;
+
+fn differential() -> Differential {
+    Differential::new(
+        Backend::new("left", Store::default()),
+        Backend::new("right", Store::default()),
+    )
+}
+
+/// Imports a `seed` function returning a different value for each
+/// instance, so that the backends diverge once it's stored.
+fn diverging_imports(store: &Store, seeds: &Arc<AtomicI32>) -> wasmer::ImportObject {
+    let seed = seeds.fetch_add(1, Ordering::SeqCst);
+    imports! {
+        "host" => {
+            "seed" => Function::new(store, FunctionType::new(vec![], vec![Type::I32]), move |_| {
+                Ok(vec![Val::I32(seed)])
+            }),
+        },
+    }
+}
+
+fn constant_imports(store: &Store) -> wasmer::ImportObject {
+    imports! {
+        "host" => {
+            "seed" => Function::new_native(store, || 7),
+        },
+    }
+}
+
+#[test]
+fn consistent_backends_agree() -> Result<()> {
+    let wasm = wat2wasm(WAT.as_bytes())?;
+    let report = differential().run(
+        &wasm,
+        constant_imports,
+        &[
+            Invocation::new("div", vec![Val::I32(7), Val::I32(2)]),
+            Invocation::new("div", vec![Val::I32(1), Val::I32(0)]),
+            Invocation::new("store_seed", vec![Val::I32(8)]),
+        ],
+    )?;
+
+    assert!(report.is_consistent());
+    assert_eq!(report.outcomes.len(), 3);
+    match &report.outcomes[0] {
+        Outcome::Returned(results) => assert_eq!(results, &vec![Val::I32(3)]),
+        outcome => panic!("unexpected outcome: {}", outcome),
+    }
+    assert!(matches!(report.outcomes[1], Outcome::Trapped(_)));
+    Ok(())
+}
+
+#[test]
+fn memory_divergence_is_reported() -> Result<()> {
+    let wasm = wat2wasm(WAT.as_bytes())?;
+    let seeds = Arc::new(AtomicI32::new(1));
+    let report = differential().run(
+        &wasm,
+        |store| diverging_imports(store, &seeds),
+        &[Invocation::new("store_seed", vec![Val::I32(16)])],
+    )?;
+
+    match report.divergence {
+        Some(Divergence::MemoryContents {
+            name,
+            offset,
+            left,
+            right,
+        }) => {
+            assert_eq!(name, "memory");
+            assert_eq!(offset, 16);
+            assert_eq!((left, right), (1, 2));
+        }
+        divergence => panic!("unexpected divergence: {:?}", divergence),
+    }
+    Ok(())
+}
+
+#[test]
+fn memory_comparison_can_be_disabled() -> Result<()> {
+    let wasm = wat2wasm(WAT.as_bytes())?;
+    let seeds = Arc::new(AtomicI32::new(1));
+    let mut differential = differential();
+    differential.compare_memories(false);
+    let report = differential.run(
+        &wasm,
+        |store| diverging_imports(store, &seeds),
+        &[
+            Invocation::new("store_seed", vec![Val::I32(16)]),
+            Invocation::new("set_last", vec![Val::I32(5)]),
+        ],
+    )?;
+
+    assert!(report.is_consistent());
+    Ok(())
+}
+
+#[test]
+fn unknown_function_is_an_error() -> Result<()> {
+    let wasm = wat2wasm(WAT.as_bytes())?;
+    let result = differential().run(
+        &wasm,
+        constant_imports,
+        &[Invocation::new("missing", vec![])],
+    );
+
+    assert!(result.is_err());
+    Ok(())
+}
+
+#[test]
+fn failed_instantiation_on_both_backends_is_an_error() -> Result<()> {
+    let wasm = wat2wasm(WAT.as_bytes())?;
+    let result = differential().run(&wasm, |_| imports! {}, &[]);
+
+    assert!(result.is_err());
+    Ok(())
+}
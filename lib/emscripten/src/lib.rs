@@ -62,6 +62,7 @@ mod unistd;
 mod utils;
 mod varargs;
 
+pub use self::linking::DynamicLibraries;
 pub use self::storage::{align_memory, static_alloc};
 pub use self::utils::{
     allocate_cstr_on_stack, allocate_on_stack, get_emscripten_memory_size, get_emscripten_metadata,
@@ -79,6 +80,7 @@ impl WasmerEnv for EmEnv {
     fn init_with_instance(&mut self, instance: &Instance) -> Result<(), wasmer::HostEnvInitError> {
         let mut ed = self.data.lock().unwrap();
         ed.init_with_instance(instance)?;
+        ed.dynamic_libraries.set_main(instance.exports.clone());
         Ok(())
     }
 }
@@ -275,6 +277,7 @@ pub struct EmscriptenData {
     #[wasmer(export)]
     pub set_threw: LazyInit<NativeFunc<(i32, i32)>>,
     pub mapped_dirs: HashMap<String, PathBuf>,
+    pub dynamic_libraries: DynamicLibraries,
 }
 
 impl EmscriptenData {
@@ -1021,6 +1024,12 @@ pub fn generate_emscripten_env(
         );
     }
 
+    env.data
+        .lock()
+        .unwrap()
+        .dynamic_libraries
+        .set_env(globals.table.clone(), env_ns.clone());
+
     let import_object: ImportObject = imports! {
         "env" => env_ns,
         "global" => {
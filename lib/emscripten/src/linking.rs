@@ -1,27 +1,701 @@
+//! Emscripten's dynamic linking.
+//!
+//! A program built with `MAIN_MODULE` loads the programs built with
+//! `SIDE_MODULE` with `dlopen`. A side module shares the memory and the
+//! table of the main module: it's given a block of memory allocated with
+//! `memalign` and a range of new table slots, and relocates itself with
+//! the `__memory_base` and `__table_base` globals it imports. Its other
+//! imports are resolved against the exports of the main module and of the
+//! side modules already loaded, then against the Emscripten host functions.
+
+use crate::env::get_emscripten_data;
+use crate::utils::{get_cstr_path, read_string_from_wasm};
 use crate::EmEnv;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use wasmer::{
+    Exports, Extern, ExternRef, ExternType, Function, Global, ImportObject, Instance, Memory,
+    Module, NativeFunc, Table, Val,
+};
+
+/// The `dlsym` handle searching the symbols of every loaded module.
+const RTLD_DEFAULT: u32 = 0;
+/// The handle returned by `dlopen(NULL)`, searching the symbols of every
+/// loaded module too.
+const MAIN_MODULE: u32 = 1;
+
+/// The libraries loaded with `dlopen`.
+#[derive(Clone, Default)]
+pub struct DynamicLibraries {
+    table: Option<Table>,
+    env: Exports,
+    main: Exports,
+    libraries: HashMap<u32, Library>,
+    next_handle: u32,
+    /// The table slots of the functions returned by `dlsym`.
+    function_indices: HashMap<(u32, String), u32>,
+    error: Option<String>,
+    error_buffer: u32,
+}
+
+#[derive(Clone)]
+struct Library {
+    path: PathBuf,
+    exports: Exports,
+    memory_base: u32,
+    references: u32,
+}
+
+impl DynamicLibraries {
+    /// Sets the table and the Emscripten host functions shared with the
+    /// side modules.
+    pub(crate) fn set_env(&mut self, table: Table, env: Exports) {
+        self.table = Some(table);
+        self.env = env;
+    }
+
+    /// Sets the exports of the main module.
+    pub(crate) fn set_main(&mut self, main: Exports) {
+        self.main = main;
+    }
+
+    fn find(&self, path: &Path) -> Option<u32> {
+        self.libraries
+            .iter()
+            .find(|(_, library)| library.path == path)
+            .map(|(handle, _)| *handle)
+    }
+
+    /// Returns the modules to search for symbols, the main module first,
+    /// with their memory bases.
+    fn modules(&self) -> Vec<(Exports, u32)> {
+        let mut handles = self.libraries.keys().copied().collect::<Vec<_>>();
+        handles.sort_unstable();
+        let mut modules = vec![(self.main.clone(), 0)];
+        modules.extend(handles.into_iter().map(|handle| {
+            let library = &self.libraries[&handle];
+            (library.exports.clone(), library.memory_base)
+        }));
+        modules
+    }
+
+    fn symbol(&mut self, handle: u32, symbol: &str) -> Result<u32, String> {
+        let table = self.table.clone().ok_or("the table isn't set")?;
+        let modules = match handle {
+            RTLD_DEFAULT | MAIN_MODULE => self.modules(),
+            _ => match self.libraries.get(&handle) {
+                Some(library) => vec![(library.exports.clone(), library.memory_base)],
+                None => return Err(format!("invalid handle {}", handle)),
+            },
+        };
+        // the C symbols are exported with a leading underscore
+        let names = [format!("_{}", symbol), symbol.to_string()];
+        for (exports, memory_base) in modules {
+            for name in names.iter() {
+                match exports.get_extern(name) {
+                    Some(Extern::Function(function)) => {
+                        let key = (handle, name.clone());
+                        if let Some(index) = self.function_indices.get(&key) {
+                            return Ok(*index);
+                        }
+                        let index = add_function(&table, function)?;
+                        self.function_indices.insert(key, index);
+                        return Ok(index);
+                    }
+                    Some(Extern::Global(global)) => {
+                        return data_address(global, memory_base);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Err(format!("undefined symbol `{}`", symbol))
+    }
+}
+
+fn add_function(table: &Table, function: &Function) -> Result<u32, String> {
+    table
+        .grow(1, Val::FuncRef(function.clone()))
+        .map_err(|err| format!("failed to grow the table: {}", err.message()))
+}
+
+fn data_address(global: &Global, memory_base: u32) -> Result<u32, String> {
+    match global.get().i32() {
+        Some(offset) => Ok(memory_base.wrapping_add(offset as u32)),
+        None => Err("a data symbol isn't an `i32` global".to_string()),
+    }
+}
+
+/// The sizes and alignments a side module needs in the memory and in the
+/// table, from its `dylink` custom section.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct DylinkInfo {
+    memory_size: u32,
+    memory_align: u32,
+    table_size: u32,
+    table_align: u32,
+    needed: Vec<String>,
+}
+
+fn read_leb(bytes: &mut &[u8]) -> Result<u32, String> {
+    let mut result = 0u32;
+    let mut shift = 0;
+    loop {
+        let (&byte, rest) = bytes
+            .split_first()
+            .ok_or("the `dylink` section is truncated")?;
+        *bytes = rest;
+        if shift >= 32 {
+            return Err("the `dylink` section has an invalid integer".to_string());
+        }
+        result |= u32::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(result);
+        }
+        shift += 7;
+    }
+}
+
+fn read_needed(bytes: &mut &[u8]) -> Result<Vec<String>, String> {
+    let count = read_leb(bytes)?;
+    let mut needed = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let len = read_leb(bytes)? as usize;
+        if bytes.len() < len {
+            return Err("the `dylink` section is truncated".to_string());
+        }
+        needed.push(String::from_utf8_lossy(&bytes[..len]).into_owned());
+        *bytes = &bytes[len..];
+    }
+    Ok(needed)
+}
+
+impl DylinkInfo {
+    /// Reads the `dylink.0` section of the LLVM backend, or the `dylink`
+    /// section of fastcomp.
+    fn from_module(module: &Module) -> Result<Self, String> {
+        if let Some(section) = module.custom_sections("dylink.0").next() {
+            let mut bytes = &section[..];
+            let mut info = Self::default();
+            while !bytes.is_empty() {
+                let kind = bytes[0];
+                bytes = &bytes[1..];
+                let len = read_leb(&mut bytes)? as usize;
+                if bytes.len() < len {
+                    return Err("the `dylink.0` section is truncated".to_string());
+                }
+                let mut payload = &bytes[..len];
+                bytes = &bytes[len..];
+                match kind {
+                    // WASM_DYLINK_MEM_INFO
+                    1 => {
+                        info.memory_size = read_leb(&mut payload)?;
+                        info.memory_align = read_leb(&mut payload)?;
+                        info.table_size = read_leb(&mut payload)?;
+                        info.table_align = read_leb(&mut payload)?;
+                    }
+                    // WASM_DYLINK_NEEDED
+                    2 => info.needed = read_needed(&mut payload)?,
+                    _ => {}
+                }
+            }
+            return Ok(info);
+        }
+        let section = module
+            .custom_sections("dylink")
+            .next()
+            .ok_or("not a side module, it has no `dylink` section")?;
+        let mut bytes = &section[..];
+        Ok(Self {
+            memory_size: read_leb(&mut bytes)?,
+            memory_align: read_leb(&mut bytes)?,
+            table_size: read_leb(&mut bytes)?,
+            table_align: read_leb(&mut bytes)?,
+            needed: read_needed(&mut bytes)?,
+        })
+    }
+}
+
+/// An import of a side module which is the address of a symbol: a
+/// `GOT.mem` or `GOT.func` global, or a `g$` or `fp$` getter of fastcomp.
+///
+/// The symbol may be defined by the side module itself, so it's resolved
+/// once the side module is instantiated.
+struct Relocation {
+    symbol: String,
+    function: bool,
+    target: RelocationTarget,
+}
+
+enum RelocationTarget {
+    Global(Global),
+    Getter(Arc<AtomicU32>),
+}
+
+/// What's needed to load a side module, copied out of the Emscripten data
+/// so that the side module can run while it isn't locked.
+struct Linker {
+    memory: Memory,
+    table: Table,
+    env: Exports,
+    modules: Vec<(Exports, u32)>,
+    memalign: NativeFunc<(u32, u32), u32>,
+}
 
-// TODO: Need to implement.
+impl Linker {
+    fn new(ctx: &EmEnv) -> Result<Self, String> {
+        let data = get_emscripten_data(ctx);
+        let libraries = &data.dynamic_libraries;
+        Ok(Self {
+            memory: ctx.memory(0).clone(),
+            table: libraries
+                .table
+                .clone()
+                .ok_or("the main module has no table")?,
+            env: libraries.env.clone(),
+            modules: libraries.modules(),
+            memalign: data
+                .memalign_ref()
+                .cloned()
+                .ok_or("the main module doesn't export `memalign`")?,
+        })
+    }
+
+    fn import(&self, name: &str) -> Option<Extern> {
+        self.modules
+            .iter()
+            .find_map(|(exports, _)| match exports.get_extern(name) {
+                Some(export @ Extern::Function(_)) => Some(export.clone()),
+                _ => None,
+            })
+            .or_else(|| self.env.get_extern(name).cloned())
+    }
+
+    fn address(&self, own: &(Exports, u32), symbol: &str, function: bool) -> Result<u32, String> {
+        for (exports, memory_base) in std::iter::once(own).chain(self.modules.iter()) {
+            match exports.get_extern(symbol) {
+                Some(Extern::Function(f)) if function => return add_function(&self.table, f),
+                Some(Extern::Global(global)) if !function => {
+                    return data_address(global, *memory_base)
+                }
+                _ => {}
+            }
+        }
+        match self.env.get_extern(symbol) {
+            Some(Extern::Function(f)) if function => add_function(&self.table, f),
+            _ => Err(format!("undefined symbol `{}`", symbol)),
+        }
+    }
+
+    /// Reserves the memory and the table slots of a side module, and
+    /// returns their bases.
+    fn reserve(&self, info: &DylinkInfo) -> Result<(u32, u32), String> {
+        let memory_base = if info.memory_size > 0 {
+            let align = 1u32.checked_shl(info.memory_align).unwrap_or(0).max(16);
+            let memory_base = self
+                .memalign
+                .call(align, info.memory_size)
+                .map_err(|err| err.message())?;
+            if memory_base == 0 {
+                return Err("out of memory".to_string());
+            }
+            let view = self.memory.view::<u8>();
+            let memory_end = memory_base
+                .checked_add(info.memory_size)
+                .filter(|end| *end as usize <= view.len())
+                .ok_or("the memory of the side module is out of bounds")?;
+            for cell in &view[memory_base as usize..memory_end as usize] {
+                cell.set(0);
+            }
+            memory_base
+        } else {
+            0
+        };
+        let null = Val::ExternRef(ExternRef::null());
+        let table_align = 1u32.checked_shl(info.table_align).unwrap_or(1).max(1);
+        let padding = (table_align - self.table.size() % table_align) % table_align;
+        let grow = |delta| {
+            self.table
+                .grow(delta, null.clone())
+                .map_err(|err| format!("failed to grow the table: {}", err.message()))
+        };
+        grow(padding)?;
+        let table_base = grow(info.table_size)?;
+        Ok((memory_base, table_base))
+    }
+
+    fn load(&self, module: &Module, info: &DylinkInfo) -> Result<(Exports, u32), String> {
+        let store = module.store();
+        let (memory_base, table_base) = self.reserve(info)?;
+
+        let mut env = Exports::new();
+        let mut got_mem = Exports::new();
+        let mut got_func = Exports::new();
+        let mut relocations = vec![];
+        for import in module.imports() {
+            let name = import.name();
+            let extern_ = match (import.module(), name) {
+                ("env", "memory") => self.memory.clone().into(),
+                ("env", "table") | ("env", "__indirect_function_table") => {
+                    self.table.clone().into()
+                }
+                ("env", "__memory_base") | ("env", "memoryBase") | ("env", "gb") => {
+                    Global::new(store, Val::I32(memory_base as i32)).into()
+                }
+                ("env", "__table_base") | ("env", "tableBase") | ("env", "fb") => {
+                    Global::new(store, Val::I32(table_base as i32)).into()
+                }
+                ("GOT.mem", _) | ("GOT.func", _) => {
+                    let global = Global::new_mut(store, Val::I32(0));
+                    relocations.push(Relocation {
+                        symbol: name.to_string(),
+                        function: import.module() == "GOT.func",
+                        target: RelocationTarget::Global(global.clone()),
+                    });
+                    global.into()
+                }
+                ("env", _) if name.starts_with("g$") || name.starts_with("fp$") => {
+                    let function = name.starts_with("fp$");
+                    let symbol = if function {
+                        name[3..].rsplitn(2, '$').last().unwrap_or_default()
+                    } else {
+                        &name[2..]
+                    };
+                    let ty = match import.ty() {
+                        ExternType::Function(ty) => ty.clone(),
+                        _ => return Err(format!("the import `{}` isn't a function", name)),
+                    };
+                    let address = Arc::new(AtomicU32::new(0));
+                    relocations.push(Relocation {
+                        symbol: symbol.to_string(),
+                        function,
+                        target: RelocationTarget::Getter(address.clone()),
+                    });
+                    Function::new(store, ty, move |_| {
+                        Ok(vec![Val::I32(address.load(Ordering::SeqCst) as i32)])
+                    })
+                    .into()
+                }
+                ("env", _) => self
+                    .import(name)
+                    .ok_or_else(|| format!("undefined symbol `{}`", name))?,
+                (namespace, _) => {
+                    return Err(format!(
+                        "unsupported import `{}` from `{}`",
+                        name, namespace
+                    ))
+                }
+            };
+            match import.module() {
+                "GOT.mem" => got_mem.insert(name, extern_),
+                "GOT.func" => got_func.insert(name, extern_),
+                _ => env.insert(name, extern_),
+            }
+        }
+
+        let mut import_object = ImportObject::new();
+        import_object.register("env", env);
+        import_object.register("GOT.mem", got_mem);
+        import_object.register("GOT.func", got_func);
+        let instance = Instance::new(module, &import_object).map_err(|err| err.to_string())?;
+
+        let own = (instance.exports.clone(), memory_base);
+        for relocation in relocations {
+            let address = self.address(&own, &relocation.symbol, relocation.function)?;
+            match relocation.target {
+                RelocationTarget::Global(global) => global
+                    .set(Val::I32(address as i32))
+                    .map_err(|err| err.message())?,
+                RelocationTarget::Getter(getter) => getter.store(address, Ordering::SeqCst),
+            }
+        }
+
+        let initializers: &[&str] = if instance.exports.contains("__post_instantiate") {
+            &["__post_instantiate"]
+        } else {
+            &["__wasm_apply_relocs", "__wasm_call_ctors"]
+        };
+        for initializer in initializers {
+            if let Ok(function) = instance.exports.get_function(initializer) {
+                function.call(&[]).map_err(|err| err.message())?;
+            }
+        }
+        Ok((instance.exports.clone(), memory_base))
+    }
+}
+
+fn open(ctx: &EmEnv, path: PathBuf) -> Result<u32, String> {
+    {
+        let mut data = get_emscripten_data(ctx);
+        let libraries = &mut data.dynamic_libraries;
+        if let Some(handle) = libraries.find(&path) {
+            libraries.libraries.get_mut(&handle).unwrap().references += 1;
+            return Ok(handle);
+        }
+    }
+
+    let bytes = std::fs::read(&path).map_err(|err| format!("{}: {}", path.display(), err))?;
+    let module = Module::new(ctx.memory(0).store(), bytes).map_err(|err| err.to_string())?;
+    let info = DylinkInfo::from_module(&module)?;
+    for needed in info.needed.iter() {
+        let needed = match path.parent() {
+            Some(directory) if Path::new(needed).is_relative() => directory.join(needed),
+            _ => PathBuf::from(needed),
+        };
+        open(ctx, needed)?;
+    }
+    let (exports, memory_base) = Linker::new(ctx)?.load(&module, &info)?;
+
+    let mut data = get_emscripten_data(ctx);
+    let libraries = &mut data.dynamic_libraries;
+    let handle = libraries.next_handle.max(MAIN_MODULE) + 1;
+    libraries.next_handle = handle;
+    libraries.libraries.insert(
+        handle,
+        Library {
+            path,
+            exports,
+            memory_base,
+            references: 1,
+        },
+    );
+    Ok(handle)
+}
+
+fn set_error(ctx: &EmEnv, error: String) {
+    debug!("emscripten::dlerror: {}", error);
+    get_emscripten_data(ctx).dynamic_libraries.error = Some(error);
+}
 
 /// emscripten: dlopen(filename: *const c_char, flag: c_int) -> *mut c_void
-pub fn _dlopen(_ctx: &EmEnv, _filename: u32, _flag: u32) -> i32 {
+pub fn _dlopen(ctx: &EmEnv, filename: u32, _flag: u32) -> i32 {
     debug!("emscripten::_dlopen");
-    -1
+    if filename == 0 {
+        return MAIN_MODULE as i32;
+    }
+    let filename_ptr = emscripten_memory_pointer!(ctx.memory(0), filename) as *const i8;
+    let path = match get_cstr_path(ctx, filename_ptr) {
+        Some(path) => PathBuf::from(path.to_string_lossy().into_owned()),
+        None => PathBuf::from(read_string_from_wasm(ctx.memory(0), filename)),
+    };
+    match open(ctx, path) {
+        Ok(handle) => handle as i32,
+        Err(error) => {
+            set_error(ctx, error);
+            0
+        }
+    }
 }
 
 /// emscripten: dlclose(handle: *mut c_void) -> c_int
-pub fn _dlclose(_ctx: &EmEnv, _filename: u32) -> i32 {
+pub fn _dlclose(ctx: &EmEnv, handle: u32) -> i32 {
     debug!("emscripten::_dlclose");
-    -1
+    let mut data = get_emscripten_data(ctx);
+    let libraries = &mut data.dynamic_libraries;
+    if handle == MAIN_MODULE {
+        return 0;
+    }
+    match libraries.libraries.get_mut(&handle) {
+        Some(library) => {
+            library.references -= 1;
+            if library.references == 0 {
+                // the memory and the table slots of the library are leaked,
+                // the pointers to them may still be used
+                libraries.libraries.remove(&handle);
+                libraries
+                    .function_indices
+                    .retain(|(owner, _), _| *owner != handle);
+            }
+            0
+        }
+        None => {
+            libraries.error = Some(format!("invalid handle {}", handle));
+            -1
+        }
+    }
 }
 
 /// emscripten: dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void
-pub fn _dlsym(_ctx: &EmEnv, _filepath: u32, _symbol: u32) -> i32 {
+pub fn _dlsym(ctx: &EmEnv, handle: u32, symbol: u32) -> i32 {
     debug!("emscripten::_dlsym");
-    -1
+    let symbol = read_string_from_wasm(ctx.memory(0), symbol);
+    let mut data = get_emscripten_data(ctx);
+    let libraries = &mut data.dynamic_libraries;
+    match libraries.symbol(handle, &symbol) {
+        Ok(address) => address as i32,
+        Err(error) => {
+            libraries.error = Some(error);
+            0
+        }
+    }
 }
 
 /// emscripten: dlerror() -> *mut c_char
-pub fn _dlerror(_ctx: &EmEnv) -> i32 {
+pub fn _dlerror(ctx: &EmEnv) -> i32 {
     debug!("emscripten::_dlerror");
-    -1
+    let (error, old_buffer, free) = {
+        let mut data = get_emscripten_data(ctx);
+        let free = data.free_ref().cloned();
+        let libraries = &mut data.dynamic_libraries;
+        match libraries.error.take() {
+            Some(error) => (error, libraries.error_buffer, free),
+            None => return 0,
+        }
+    };
+    if let (Some(free), true) = (free, old_buffer != 0) {
+        let _ = free.call(old_buffer);
+    }
+    let buffer = crate::env::call_malloc(ctx, error.len() as u32 + 1);
+    let view = ctx.memory(0).view::<u8>();
+    let cells = &view[buffer as usize..buffer as usize + error.len() + 1];
+    for (cell, byte) in cells.iter().zip(error.bytes().chain(std::iter::once(0))) {
+        cell.set(byte);
+    }
+    get_emscripten_data(ctx).dynamic_libraries.error_buffer = buffer;
+    buffer as i32
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::EmscriptenGlobalsData;
+    use wasmer::{imports, Store};
+
+    /// A main module exporting its table, and `memalign` as a bump
+    /// allocator.
+    const MAIN: &str = r#"
+(module
+  (memory (export "memory") 1)
+  (table (export "table") 0 funcref)
+  (global $heap (mut i32) (i32.const 1024))
+  (func (export "memalign") (param i32 i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $heap))
+    (global.set $heap (i32.add (global.get $heap) (local.get 1)))
+    (local.get $ptr)))
+"#;
+
+    /// A side module exporting the function `answer`, and the data
+    /// symbol `value` initialized by its constructor.
+    const SIDE: &str = r#"
+(module
+  (import "env" "memory" (memory 0))
+  (import "env" "__memory_base" (global $memory_base i32))
+  (import "env" "__indirect_function_table" (table 0 funcref))
+  (global (export "value") i32 (i32.const 0))
+  (func (export "answer") (result i32) (i32.const 42))
+  (func (export "__wasm_call_ctors")
+    (i32.store (global.get $memory_base) (i32.const 7))))
+"#;
+
+    /// Returns the side module, with its `dylink.0` section.
+    fn side_module() -> Vec<u8> {
+        let mut bytes = wasmer::wat2wasm(SIDE.as_bytes()).unwrap().into_owned();
+        // WASM_DYLINK_MEM_INFO: 4 bytes aligned to 4, and no table slots
+        let payload = [1, 4, 4, 2, 0, 0];
+        let name = b"dylink.0";
+        bytes.push(0);
+        bytes.push((1 + name.len() + payload.len()) as u8);
+        bytes.push(name.len() as u8);
+        bytes.extend_from_slice(name);
+        bytes.extend_from_slice(&payload);
+        bytes
+    }
+
+    fn write_cstr(memory: &Memory, offset: u32, string: &str) {
+        let view = memory.view::<u8>();
+        let bytes = string.bytes().chain(std::iter::once(0));
+        for (cell, byte) in view[offset as usize..].iter().zip(bytes) {
+            cell.set(byte);
+        }
+    }
+
+    #[test]
+    fn leb() {
+        let mut bytes = &[0xe5, 0x8e, 0x26, 0x01][..];
+        assert_eq!(read_leb(&mut bytes), Ok(624_485));
+        assert_eq!(bytes, [0x01]);
+        assert!(read_leb(&mut &[0x80][..]).is_err());
+        assert!(read_leb(&mut &[0xff, 0xff, 0xff, 0xff, 0xff, 0x01][..]).is_err());
+    }
+
+    #[test]
+    fn needed() {
+        let mut bytes = &[2, 4, b'a', b'.', b's', b'o', 4, b'b', b'.', b's', b'o'][..];
+        assert_eq!(
+            read_needed(&mut bytes),
+            Ok(vec!["a.so".into(), "b.so".into()])
+        );
+        assert!(bytes.is_empty());
+        assert!(read_needed(&mut &[1, 5, b'a'][..]).is_err());
+    }
+
+    #[test]
+    fn dlopen_side_module() {
+        let store = Store::default();
+        let main = Instance::new(&Module::new(&store, MAIN).unwrap(), &imports! {}).unwrap();
+        let memory = main.exports.get_memory("memory").unwrap().clone();
+        let table = main.exports.get_table("table").unwrap().clone();
+        let mut env = EmEnv::new(&EmscriptenGlobalsData::default(), HashMap::new());
+        env.set_memory(memory.clone());
+        {
+            let mut data = get_emscripten_data(&env);
+            data.memalign
+                .initialize(main.exports.get_native_function("memalign").unwrap());
+            data.dynamic_libraries
+                .set_env(table.clone(), Exports::new());
+            data.dynamic_libraries.set_main(main.exports.clone());
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("side.wasm");
+        std::fs::write(&path, side_module()).unwrap();
+        write_cstr(&memory, 2048, path.to_str().unwrap());
+        let handle = _dlopen(&env, 2048, 0);
+        assert!(handle > MAIN_MODULE as i32);
+
+        write_cstr(&memory, 16, "answer");
+        let index = _dlsym(&env, handle as u32, 16);
+        let answer = match table.get(index as u32) {
+            Some(Val::FuncRef(function)) => function.call(&[]).unwrap(),
+            _ => panic!("`answer` isn't in the table"),
+        };
+        assert_eq!(answer[0].i32(), Some(42));
+
+        write_cstr(&memory, 32, "value");
+        let address = _dlsym(&env, handle as u32, 32);
+        assert_eq!(address, 1024);
+        assert_eq!(memory.view::<u32>()[address as usize / 4].get(), 7);
+
+        write_cstr(&memory, 48, "missing");
+        assert_eq!(_dlsym(&env, handle as u32, 48), 0);
+    }
+
+    #[test]
+    fn side_module_out_of_bounds() {
+        let store = Store::default();
+        let main = Instance::new(&Module::new(&store, MAIN).unwrap(), &imports! {}).unwrap();
+        let mut env = EmEnv::new(&EmscriptenGlobalsData::default(), HashMap::new());
+        env.set_memory(main.exports.get_memory("memory").unwrap().clone());
+        {
+            let mut data = get_emscripten_data(&env);
+            data.memalign
+                .initialize(main.exports.get_native_function("memalign").unwrap());
+            data.dynamic_libraries.set_env(
+                main.exports.get_table("table").unwrap().clone(),
+                Exports::new(),
+            );
+        }
+
+        let linker = Linker::new(&env).unwrap();
+        for memory_size in [0x1_0000, u32::MAX].iter() {
+            let info = DylinkInfo {
+                memory_size: *memory_size,
+                ..Default::default()
+            };
+            assert!(linker.reserve(&info).is_err());
+        }
+    }
 }
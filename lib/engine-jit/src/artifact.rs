@@ -1,28 +1,41 @@
 //! Define `JITArtifact` to allow compiling and instantiating to be
 //! done as separate steps.
 
+use crate::debug::{register_debug_info, DebugFunction, GdbJitImageRegistration};
 use crate::engine::{JITEngine, JITEngineInner};
 use crate::link::link_module;
 #[cfg(feature = "compiler")]
 use crate::serialize::SerializableCompilation;
 use crate::serialize::SerializableModule;
+use crate::validate::validate;
+use bincode::Options;
+#[cfg(feature = "compiler")]
+use std::mem;
 use std::sync::{Arc, Mutex};
-use wasmer_compiler::{CompileError, Features, Triple};
 #[cfg(feature = "compiler")]
-use wasmer_compiler::{CompileModuleInfo, ModuleEnvironment};
+use wasmer_compiler::{
+    Compilation, CompileFuel, CompileModuleInfo, FunctionBodyData, ModuleEnvironment,
+    ModuleToCompile, ModuleTranslationState,
+};
+use wasmer_compiler::{CompileError, Features, Triple, UnwindInfoMode};
 use wasmer_engine::{
     register_frame_info, Artifact, DeserializeError, FunctionExtent, GlobalFrameInfoRegistration,
-    SerializeError,
+    SerializableFunctionFrameInfo, SerializeError, SourceMap,
 };
 #[cfg(feature = "compiler")]
-use wasmer_engine::{Engine, SerializableFunctionFrameInfo, Tunables};
-use wasmer_types::entity::{BoxedSlice, PrimaryMap};
+use wasmer_engine::{Engine, Tunables};
+use wasmer_types::entity::{BoxedSlice, EntityRef, PrimaryMap};
+#[cfg(feature = "compiler")]
+use wasmer_types::DataInitializer;
 use wasmer_types::{
     FunctionIndex, LocalFunctionIndex, MemoryIndex, OwnedDataInitializer, SignatureIndex,
     TableIndex,
 };
+#[cfg(feature = "compiler")]
+use wasmer_vm::BoundsCheck;
 use wasmer_vm::{
-    FunctionBodyPtr, MemoryStyle, ModuleInfo, TableStyle, VMSharedSignatureIndex, VMTrampoline,
+    FunctionBodyPtr, MemoryStyle, ModuleInfo, TableStyle, VMFunctionBody, VMSharedSignatureIndex,
+    VMTrampoline,
 };
 
 /// A compiled wasm module, ready to be instantiated.
@@ -34,6 +47,18 @@ pub struct JITArtifact {
     signatures: BoxedSlice<SignatureIndex, VMSharedSignatureIndex>,
     frame_info_registration: Mutex<Option<GlobalFrameInfoRegistration>>,
     finished_function_lengths: BoxedSlice<LocalFunctionIndex, usize>,
+    // Keeps the code registered with the debuggers until the artifact is dropped.
+    _debug_registration: Option<GdbJitImageRegistration>,
+    source_map: Option<Arc<SourceMap>>,
+}
+
+/// A translated module, ready to be compiled.
+#[cfg(feature = "compiler")]
+struct TranslatedModule<'data> {
+    compile_info: CompileModuleInfo,
+    module_translation: ModuleTranslationState,
+    function_body_inputs: PrimaryMap<LocalFunctionIndex, FunctionBodyData<'data>>,
+    data_initializers: Vec<DataInitializer<'data>>,
 }
 
 impl JITArtifact {
@@ -51,11 +76,99 @@ impl JITArtifact {
         data: &[u8],
         tunables: &dyn Tunables,
     ) -> Result<Self, CompileError> {
-        let environ = ModuleEnvironment::new();
         let mut inner_jit = jit.inner_mut();
+        let mut module = Self::translate(&inner_jit, data, tunables)?;
+
+        // Compile the Module
+        let compilation = inner_jit.compiler()?.compile_module(
+            &jit.target(),
+            &mut module.compile_info,
+            &module.module_translation,
+            module.function_body_inputs,
+        )?;
+        Self::from_compilation(
+            &mut inner_jit,
+            data,
+            module.compile_info,
+            &module.data_initializers,
+            compilation,
+        )
+    }
+
+    /// Validate and compile many data buffers into `JITArtifact`s,
+    /// with [`Compiler::compile_modules`].
+    ///
+    /// Returns the artifacts, in the same order as the data buffers,
+    /// and the number of function bodies whose compiled code was
+    /// reused rather than compiled.
+    ///
+    /// [`Compiler::compile_modules`]: wasmer_compiler::Compiler::compile_modules
+    #[cfg(feature = "compiler")]
+    pub fn new_batch(
+        jit: &JITEngine,
+        binaries: &[&[u8]],
+        tunables: &dyn Tunables,
+    ) -> Result<(Vec<Self>, usize), CompileError> {
+        let mut inner_jit = jit.inner_mut();
+        let mut modules = binaries
+            .iter()
+            .map(|data| {
+                inner_jit.validate(data)?;
+                Self::translate(&inner_jit, data, tunables)
+            })
+            .collect::<Result<Vec<_>, CompileError>>()?;
+
+        let modules_to_compile = modules
+            .iter_mut()
+            .map(|module| ModuleToCompile {
+                compile_info: &mut module.compile_info,
+                module_translation: &module.module_translation,
+                function_body_inputs: mem::replace(
+                    &mut module.function_body_inputs,
+                    PrimaryMap::new(),
+                ),
+            })
+            .collect();
+        let (compilations, reused_function_bodies) = inner_jit
+            .compiler()?
+            .compile_modules(&jit.target(), modules_to_compile)?;
+
+        let artifacts = modules
+            .into_iter()
+            .zip(compilations)
+            .zip(binaries)
+            .map(|((module, compilation), data)| {
+                Self::from_compilation(
+                    &mut inner_jit,
+                    data,
+                    module.compile_info,
+                    &module.data_initializers,
+                    compilation,
+                )
+            })
+            .collect::<Result<Vec<_>, CompileError>>()?;
+        Ok((artifacts, reused_function_bodies))
+    }
+
+    /// Translate a data buffer into a module to compile, with the
+    /// memory and table styles of the tunables.
+    #[cfg(feature = "compiler")]
+    fn translate<'data>(
+        inner_jit: &JITEngineInner,
+        data: &'data [u8],
+        tunables: &dyn Tunables,
+    ) -> Result<TranslatedModule<'data>, CompileError> {
+        let environ = ModuleEnvironment::new();
         let features = inner_jit.features();
+        let mut fuel = inner_jit.compile_fuel().map(CompileFuel::new);
+        if let Some(fuel) = fuel.as_mut() {
+            fuel.consume_module(data)?;
+        }
 
         let translation = environ.translate(data).map_err(CompileError::Wasm)?;
+        if let Some(fuel) = fuel.as_mut() {
+            fuel.consume_function_bodies(&translation.function_body_inputs)?;
+        }
 
         let memory_styles: PrimaryMap<MemoryIndex, MemoryStyle> = translation
             .module
@@ -63,6 +176,13 @@ impl JITArtifact {
             .values()
             .map(|memory_type| tunables.memory_style(memory_type))
             .collect();
+        let memory_bounds_checks: PrimaryMap<MemoryIndex, BoundsCheck> = translation
+            .module
+            .memories
+            .values()
+            .zip(memory_styles.values())
+            .map(|(memory_type, style)| tunables.memory_bounds_check(memory_type, style))
+            .collect();
         let table_styles: PrimaryMap<TableIndex, TableStyle> = translation
             .module
             .tables
@@ -70,34 +190,45 @@ impl JITArtifact {
             .map(|table_type| tunables.table_style(table_type))
             .collect();
 
-        let mut compile_info = CompileModuleInfo {
+        let compile_info = CompileModuleInfo {
             module: Arc::new(translation.module),
             features: features.clone(),
             memory_styles,
+            memory_bounds_checks,
             table_styles,
         };
 
-        let compiler = inner_jit.compiler()?;
-
-        // Compile the Module
-        let compilation = compiler.compile_module(
-            &jit.target(),
-            &mut compile_info,
+        Ok(TranslatedModule {
+            compile_info,
             // SAFETY: Calling `unwrap` is correct since
             // `environ.translate()` above will write some data into
             // `module_translation_state`.
-            translation.module_translation_state.as_ref().unwrap(),
-            translation.function_body_inputs,
-        )?;
+            module_translation: translation.module_translation_state.unwrap(),
+            function_body_inputs: translation.function_body_inputs,
+            data_initializers: translation.data_initializers,
+        })
+    }
+
+    /// Construct a `JITArtifact` from the compilation of a module.
+    #[cfg(feature = "compiler")]
+    fn from_compilation(
+        inner_jit: &mut JITEngineInner,
+        data: &[u8],
+        compile_info: CompileModuleInfo,
+        data_initializers: &[DataInitializer<'_>],
+        compilation: Compilation,
+    ) -> Result<Self, CompileError> {
         let function_call_trampolines = compilation.get_function_call_trampolines();
         let dynamic_function_trampolines = compilation.get_dynamic_function_trampolines();
 
-        let data_initializers = translation
-            .data_initializers
+        let mut data_initializers = data_initializers
             .iter()
             .map(OwnedDataInitializer::new)
-            .collect::<Vec<_>>()
-            .into_boxed_slice();
+            .collect::<Vec<_>>();
+        inner_jit
+            .compiler()?
+            .transform_data_initializers(&mut data_initializers);
+        let data_initializers = data_initializers.into_boxed_slice();
 
         let frame_infos = compilation
             .get_frame_info()
@@ -120,8 +251,13 @@ impl JITArtifact {
             compilation: serializable_compilation,
             compile_info,
             data_initializers,
+            wasm: if inner_jit.embed_wasm() {
+                Some(data.to_vec())
+            } else {
+                None
+            },
         };
-        Self::from_parts(&mut inner_jit, serializable)
+        Self::from_parts(inner_jit, serializable)
     }
 
     /// Compile a data buffer into a `JITArtifact`, which may then be instantiated.
@@ -134,6 +270,35 @@ impl JITArtifact {
 
     /// Deserialize a JITArtifact
     pub fn deserialize(jit: &JITEngine, bytes: &[u8]) -> Result<Self, DeserializeError> {
+        let serializable = Self::deserialize_serializable(bytes)?;
+        Self::from_parts(&mut jit.inner_mut(), serializable).map_err(DeserializeError::Compiler)
+    }
+
+    /// Deserialize a JITArtifact that comes from an untrusted source.
+    ///
+    /// All the indices and offsets of the artifact are checked to be
+    /// in bounds before its code is loaded, and its frame info is
+    /// processed upfront, so that a malformed artifact is reported as
+    /// a `DeserializeError::CorruptedBinary`. Its machine code isn't
+    /// verified though.
+    pub fn deserialize_untrusted(jit: &JITEngine, bytes: &[u8]) -> Result<Self, DeserializeError> {
+        let mut serializable = Self::deserialize_serializable(bytes)?;
+        validate(&serializable).map_err(DeserializeError::CorruptedBinary)?;
+        for frame_info in serializable.compilation.function_frame_info.values_mut() {
+            if let SerializableFunctionFrameInfo::Unprocessed(unprocessed) = frame_info {
+                let processed = unprocessed
+                    .try_deserialize()
+                    .map_err(DeserializeError::CorruptedBinary)?;
+                *frame_info = SerializableFunctionFrameInfo::Processed(processed);
+            }
+        }
+        Self::from_parts(&mut jit.inner_mut(), serializable).map_err(DeserializeError::Compiler)
+    }
+
+    /// Deserialize the `SerializableModule` of a serialized
+    /// JITArtifact, reading no more than the provided bytes so that a
+    /// corrupted length can't make it allocate more memory.
+    fn deserialize_serializable(bytes: &[u8]) -> Result<SerializableModule, DeserializeError> {
         if !Self::is_deserializable(bytes) {
             return Err(DeserializeError::Incompatible(
                 "The provided bytes are not wasmer-jit".to_string(),
@@ -145,10 +310,12 @@ impl JITArtifact {
         // let r = flexbuffers::Reader::get_root(bytes).map_err(|e| DeserializeError::CorruptedBinary(format!("{:?}", e)))?;
         // let serializable = SerializableModule::deserialize(r).map_err(|e| DeserializeError::CorruptedBinary(format!("{:?}", e)))?;
 
-        let serializable: SerializableModule = bincode::deserialize(inner_bytes)
-            .map_err(|e| DeserializeError::CorruptedBinary(format!("{:?}", e)))?;
-
-        Self::from_parts(&mut jit.inner_mut(), serializable).map_err(DeserializeError::Compiler)
+        bincode::options()
+            .with_fixint_encoding()
+            .allow_trailing_bytes()
+            .with_limit(inner_bytes.len() as u64)
+            .deserialize(inner_bytes)
+            .map_err(|e| DeserializeError::CorruptedBinary(format!("{:?}", e)))
     }
 
     /// Construct a `JITArtifact` from component parts.
@@ -181,13 +348,18 @@ impl JITArtifact {
         // Compute indices into the shared signature table.
         let signatures = {
             let signature_registry = inner_jit.signatures();
-            serializable
+            let signatures = serializable
                 .compile_info
                 .module
                 .signatures
                 .values()
                 .map(|sig| signature_registry.register(sig))
-                .collect::<PrimaryMap<_, _>>()
+                .collect::<PrimaryMap<_, _>>();
+            // The code memory lives as long as the engine.
+            for (index, trampoline) in finished_function_call_trampolines.iter() {
+                unsafe { signature_registry.register_trampoline(signatures[index], *trampoline) };
+            }
+            signatures
         };
 
         let eh_frame = match &serializable.compilation.debug {
@@ -206,7 +378,97 @@ impl JITArtifact {
         // Make all code compiled thus far executable.
         inner_jit.publish_compiled_code();
 
-        inner_jit.publish_eh_frame(eh_frame)?;
+        // When the compiler didn't emit any unwind information for the
+        // module, the functions and trampolines are unwound by following
+        // the frame pointer.
+        let frame_pointer_functions =
+            if inner_jit.unwind_info() == UnwindInfoMode::FullWithFP && eh_frame.is_none() {
+                let compilation = &serializable.compilation;
+                let functions = finished_functions
+                    .iter()
+                    .filter(|(index, _)| compilation.function_bodies[*index].unwind_info.is_none())
+                    .map(|(_, extent)| (*extent.ptr as *const u8 as usize, extent.length));
+                let call_trampolines = finished_function_call_trampolines
+                    .iter()
+                    .filter(|(index, _)| {
+                        compilation.function_call_trampolines[*index]
+                            .unwind_info
+                            .is_none()
+                    })
+                    .map(|(index, trampoline)| {
+                        (
+                            *trampoline as usize,
+                            compilation.function_call_trampolines[index].body.len(),
+                        )
+                    });
+                let dynamic_trampolines = finished_dynamic_function_trampolines
+                    .iter()
+                    .filter(|(index, _)| {
+                        compilation.dynamic_function_trampolines[*index]
+                            .unwind_info
+                            .is_none()
+                    })
+                    .map(|(index, trampoline)| {
+                        (
+                            **trampoline as *const u8 as usize,
+                            compilation.dynamic_function_trampolines[index].body.len(),
+                        )
+                    });
+                functions
+                    .chain(call_trampolines)
+                    .chain(dynamic_trampolines)
+                    .map(|(address, length)| FunctionExtent {
+                        ptr: FunctionBodyPtr(address as *const VMFunctionBody),
+                        length,
+                    })
+                    .collect::<Vec<_>>()
+            } else {
+                vec![]
+            };
+        inner_jit.publish_eh_frame(eh_frame, &frame_pointer_functions)?;
+
+        let module = &serializable.compile_info.module;
+        let module_name = module.name.as_deref().unwrap_or("<module>");
+        let function_name = |index: LocalFunctionIndex| {
+            let func_index = module.func_index(index);
+            module
+                .function_name(func_index)
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("{}[{}]", module_name, func_index.index()))
+        };
+
+        let debug_registration = if inner_jit.debug_info() {
+            let functions = finished_functions
+                .iter()
+                .map(|(index, extent)| {
+                    let address_map = match &serializable.compilation.function_frame_info[index] {
+                        SerializableFunctionFrameInfo::Processed(info) => info.address_map.clone(),
+                        SerializableFunctionFrameInfo::Unprocessed(info) => {
+                            info.deserialize().address_map
+                        }
+                    };
+                    DebugFunction {
+                        name: function_name(index),
+                        address: *extent.ptr as *const u8 as u64,
+                        size: extent.length as u64,
+                        address_map,
+                    }
+                })
+                .collect::<Vec<_>>();
+            register_debug_info(module, &functions)
+        } else {
+            None
+        };
+
+        if let Some(agent) = inner_jit.profiling_agent() {
+            for (index, extent) in finished_functions.iter() {
+                agent.register_function(
+                    &function_name(index),
+                    *extent.ptr as *const u8,
+                    extent.length,
+                );
+            }
+        }
 
         let finished_function_lengths = finished_functions
             .values()
@@ -232,6 +494,8 @@ impl JITArtifact {
             signatures,
             frame_info_registration: Mutex::new(None),
             finished_function_lengths,
+            _debug_registration: debug_registration,
+            source_map: None,
         })
     }
 
@@ -276,9 +540,26 @@ impl Artifact for JITArtifact {
             self.serializable.compile_info.module.clone(),
             &finished_function_extents,
             frame_infos.clone(),
+            self.source_map.clone(),
         );
     }
 
+    fn source_map(&self) -> Option<&Arc<SourceMap>> {
+        self.source_map.as_ref()
+    }
+
+    fn set_source_map(&mut self, source_map: Arc<SourceMap>) -> bool {
+        self.source_map = Some(source_map);
+        // The frame info is registered again with the source map on
+        // the next instantiation.
+        *self.frame_info_registration.get_mut().unwrap() = None;
+        true
+    }
+
+    fn wasm(&self) -> Option<&[u8]> {
+        self.serializable.wasm.as_deref()
+    }
+
     fn features(&self) -> &Features {
         &self.serializable.compile_info.features
     }
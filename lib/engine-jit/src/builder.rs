@@ -1,5 +1,7 @@
-use crate::JITEngine;
-use wasmer_compiler::{CompilerConfig, Features, Target};
+use crate::{JITEngine, ProfilingStrategy};
+use std::sync::Arc;
+use wasmer_compiler::{CompilerConfig, Features, FunctionCounters, Target, UnwindInfoMode};
+use wasmer_engine::TunablesConfig;
 
 /// The JIT builder
 pub struct JIT {
@@ -7,6 +9,13 @@ pub struct JIT {
     compiler_config: Option<Box<dyn CompilerConfig>>,
     target: Option<Target>,
     features: Option<Features>,
+    debug_info: bool,
+    unwind_info: UnwindInfoMode,
+    profiler: ProfilingStrategy,
+    compile_fuel: Option<u64>,
+    tunables_config: TunablesConfig,
+    function_counters: bool,
+    embed_wasm: bool,
 }
 
 impl JIT {
@@ -19,6 +28,13 @@ impl JIT {
             compiler_config: Some(compiler_config.into()),
             target: None,
             features: None,
+            debug_info: false,
+            unwind_info: UnwindInfoMode::Default,
+            profiler: ProfilingStrategy::None,
+            compile_fuel: None,
+            tunables_config: TunablesConfig::default(),
+            function_counters: false,
+            embed_wasm: false,
         }
     }
 
@@ -28,6 +44,13 @@ impl JIT {
             compiler_config: None,
             target: None,
             features: None,
+            debug_info: false,
+            unwind_info: UnwindInfoMode::Default,
+            profiler: ProfilingStrategy::None,
+            compile_fuel: None,
+            tunables_config: TunablesConfig::default(),
+            function_counters: false,
+            embed_wasm: false,
         }
     }
 
@@ -43,24 +66,122 @@ impl JIT {
         self
     }
 
+    /// Register the compiled code with the debuggers (GDB or LLDB),
+    /// so they can show the name of the functions and, when the modules
+    /// have DWARF sections, set breakpoints and step through the
+    /// original source code.
+    ///
+    /// The DWARF is translated by this engine from the address maps of
+    /// the compiled functions, not by the compilers, so the artifacts of
+    /// the native and object-file engines don't carry it.
+    pub fn debug_info(mut self, enable: bool) -> Self {
+        self.debug_info = enable;
+        self
+    }
+
+    /// Set the unwind information registered for the compiled code.
+    ///
+    /// With [`UnwindInfoMode::FullWithFP`], host crash reporters and
+    /// profilers can walk the native stack through the WebAssembly
+    /// frames.
+    pub fn unwind_info(mut self, mode: UnwindInfoMode) -> Self {
+        self.unwind_info = mode;
+        self
+    }
+
+    /// Set the profiler the compiled code is described to, so the
+    /// samples are attributed to the WebAssembly functions.
+    pub fn profiler(mut self, strategy: ProfilingStrategy) -> Self {
+        self.profiler = strategy;
+        self
+    }
+
+    /// Limit the work of compiling a module to `limit` units, so a
+    /// hostile module can't make the compilation take an unbounded
+    /// amount of time. Compiling a module exceeding it fails with
+    /// [`CompileError::FuelExhausted`].
+    ///
+    /// See [`CompileFuel`] for how the work is measured.
+    ///
+    /// [`CompileError::FuelExhausted`]: wasmer_compiler::CompileError::FuelExhausted
+    /// [`CompileFuel`]: wasmer_compiler::CompileFuel
+    pub fn compile_fuel(mut self, limit: u64) -> Self {
+        self.compile_fuel = Some(limit);
+        self
+    }
+
+    /// Configure the [`TunablesConfig`] of the stores created for the
+    /// engine with `Store::new`, to tune the layout of their memories.
+    pub fn tunables_config(mut self, config: TunablesConfig) -> Self {
+        self.tunables_config = config;
+        self
+    }
+
+    /// Count the calls of each function of the compiled modules, read
+    /// with `Instance::function_counts`. It's cheap enough to find the
+    /// dead code and the hot paths in production, where no profiler can
+    /// be deployed.
+    pub fn function_counters(mut self, enable: bool) -> Self {
+        self.function_counters = enable;
+        self
+    }
+
+    /// Embed the original wasm in the compiled artifacts, so they can
+    /// be recompiled for another engine or target, like `wasmer
+    /// convert` does, at the cost of their size.
+    pub fn embed_wasm(mut self, enable: bool) -> Self {
+        self.embed_wasm = enable;
+        self
+    }
+
     /// Build the `JITEngine` for this configuration
     #[cfg(feature = "compiler")]
     pub fn engine(self) -> JITEngine {
         let target = self.target.unwrap_or_default();
-        if let Some(compiler_config) = self.compiler_config {
-            let features = self
+        let engine = if let Some(mut compiler_config) = self.compiler_config {
+            if self.unwind_info == UnwindInfoMode::FullWithFP {
+                compiler_config.enable_frame_pointers();
+            }
+            let mut features = self
                 .features
                 .unwrap_or_else(|| compiler_config.default_features_for_target(&target));
+            if compiler_config.deterministic() {
+                // The threads make the execution non-deterministic.
+                features.threads(false);
+            }
+            if self.function_counters {
+                compiler_config.push_middleware(Arc::new(FunctionCounters::new()));
+            }
             let compiler = compiler_config.compiler();
             JITEngine::new(compiler, target, features)
         } else {
             JITEngine::headless()
+        };
+        {
+            let mut inner = engine.inner_mut();
+            inner.set_debug_info(self.debug_info);
+            inner.set_unwind_info(self.unwind_info);
+            inner.set_profiler(self.profiler);
+            inner.set_compile_fuel(self.compile_fuel);
+            inner.set_embed_wasm(self.embed_wasm);
+            inner.set_tunables_config(self.tunables_config);
         }
+        engine
     }
 
     /// Build the `JITEngine` for this configuration
     #[cfg(not(feature = "compiler"))]
     pub fn engine(self) -> JITEngine {
-        JITEngine::headless()
+        let engine = JITEngine::headless();
+        {
+            let mut inner = engine.inner_mut();
+            inner.set_debug_info(self.debug_info);
+            inner.set_unwind_info(self.unwind_info);
+            inner.set_profiler(self.profiler);
+            inner.set_compile_fuel(self.compile_fuel);
+            inner.set_embed_wasm(self.embed_wasm);
+            inner.set_tunables_config(self.tunables_config);
+        }
+        engine
     }
 }
@@ -0,0 +1,376 @@
+//! Translation of the DWARF of a WebAssembly module to the native code.
+//!
+//! The line tables of the module, which map code section offsets to
+//! source lines, are combined with the address maps produced by the
+//! compiler, which map the native instructions to the wasm ones. Only
+//! the line tables are translated: the variables and types described
+//! in the module aren't available to the debugger.
+
+use gimli::write::{
+    self, Address, AttributeValue, DwarfUnit, EndianVec, LineProgram, LineString, Sections,
+};
+use gimli::{Encoding, EndianSlice, Format, LittleEndian, SectionId};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use wasmer_compiler::FunctionAddressMap;
+use wasmer_vm::ModuleInfo;
+
+/// A compiled function, as loaded in memory.
+pub struct DebugFunction {
+    pub name: String,
+    pub address: u64,
+    pub size: u64,
+    pub address_map: FunctionAddressMap,
+}
+
+/// A row of the line tables of the module, covering `start..end` in
+/// the code section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct WasmLine {
+    start: u64,
+    end: u64,
+    file: usize,
+    line: u64,
+    column: u64,
+}
+
+/// The line tables of a module, sorted by address.
+#[derive(Debug, Default)]
+struct WasmLines {
+    files: Vec<PathBuf>,
+    rows: Vec<WasmLine>,
+}
+
+impl WasmLines {
+    fn parse(module: &ModuleInfo) -> gimli::Result<Self> {
+        let load = |id: SectionId| -> gimli::Result<EndianSlice<LittleEndian>> {
+            let data = module
+                .custom_sections
+                .get(id.name())
+                .map(|index| &*module.custom_sections_data[*index])
+                .unwrap_or(&[]);
+            Ok(EndianSlice::new(data, LittleEndian))
+        };
+        let load_sup = |_| Ok(EndianSlice::new(&[], LittleEndian));
+        let dwarf = gimli::read::Dwarf::load(load, load_sup)?;
+
+        let mut lines = Self::default();
+        let mut file_indices = HashMap::new();
+        let mut units = dwarf.units();
+        while let Some(header) = units.next()? {
+            let unit = dwarf.unit(header)?;
+            let program = match unit.line_program.clone() {
+                Some(program) => program,
+                None => continue,
+            };
+            let mut rows = program.rows();
+            let mut previous: Option<WasmLine> = None;
+            while let Some((header, row)) = rows.next_row()? {
+                if let Some(mut line) = previous.take() {
+                    line.end = row.address();
+                    if line.start < line.end {
+                        lines.rows.push(line);
+                    }
+                }
+                if row.end_sequence() {
+                    continue;
+                }
+                let line = match row.line() {
+                    Some(line) => line,
+                    None => continue,
+                };
+                let file = match row.file(header) {
+                    Some(file) => file,
+                    None => continue,
+                };
+                let mut path = PathBuf::new();
+                if let Some(comp_dir) = &unit.comp_dir {
+                    path.push(&*comp_dir.to_string_lossy());
+                }
+                if file.directory_index() != 0 {
+                    if let Some(directory) = file.directory(header) {
+                        path.push(&*dwarf.attr_string(&unit, directory)?.to_string_lossy());
+                    }
+                }
+                path.push(
+                    &*dwarf
+                        .attr_string(&unit, file.path_name())?
+                        .to_string_lossy(),
+                );
+                let files = &mut lines.files;
+                let file = *file_indices.entry(path.clone()).or_insert_with(|| {
+                    files.push(path);
+                    files.len() - 1
+                });
+                let column = match row.column() {
+                    gimli::ColumnType::LeftEdge => 0,
+                    gimli::ColumnType::Column(column) => column,
+                };
+                previous = Some(WasmLine {
+                    start: row.address(),
+                    end: row.address(),
+                    file,
+                    line,
+                    column,
+                });
+            }
+        }
+        lines.rows.sort_by_key(|row| row.start);
+        Ok(lines)
+    }
+
+    /// Finds the row covering the given code section offset.
+    fn find(&self, address: u64) -> Option<&WasmLine> {
+        let index = match self.rows.binary_search_by_key(&address, |row| row.start) {
+            Ok(index) => index,
+            Err(0) => return None,
+            Err(index) => index - 1,
+        };
+        let row = &self.rows[index];
+        if address < row.end {
+            Some(row)
+        } else {
+            None
+        }
+    }
+}
+
+/// Generates the DWARF sections describing the given functions.
+pub fn translate(
+    module: &ModuleInfo,
+    functions: &[DebugFunction],
+) -> Result<Vec<(&'static str, Vec<u8>)>, String> {
+    let wasm_lines = WasmLines::parse(module)
+        .map_err(|e| format!("failed to read the DWARF of the module: {}", e))?;
+    let encoding = Encoding {
+        format: Format::Dwarf32,
+        version: 4,
+        address_size: std::mem::size_of::<usize>() as u8,
+    };
+    let name = module.name.as_deref().unwrap_or("<module>").to_string();
+    let mut dwarf = DwarfUnit::new(encoding);
+
+    if !wasm_lines.rows.is_empty() {
+        let mut program = LineProgram::new(
+            encoding,
+            Default::default(),
+            LineString::String(b"/".to_vec()),
+            LineString::String(name.clone().into_bytes()),
+            None,
+        );
+        let file_ids = wasm_lines
+            .files
+            .iter()
+            .map(|path| {
+                let directory = match path.parent().map(Path::to_string_lossy) {
+                    Some(directory) if !directory.is_empty() => program
+                        .add_directory(LineString::String(directory.into_owned().into_bytes())),
+                    _ => program.default_directory(),
+                };
+                let file = path
+                    .file_name()
+                    .map(|file| file.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| "<unknown>".to_string());
+                program.add_file(LineString::String(file.into_bytes()), directory, None)
+            })
+            .collect::<Vec<_>>();
+
+        for function in functions {
+            program.begin_sequence(Some(Address::Constant(function.address)));
+            let mut last = None;
+            for instruction in &function.address_map.instructions {
+                if instruction.srcloc.is_default() {
+                    continue;
+                }
+                let wasm_address = (instruction.srcloc.bits() as u64)
+                    .wrapping_sub(module.code_section_offset as u64);
+                let line = match wasm_lines.find(wasm_address) {
+                    Some(line) => line,
+                    None => continue,
+                };
+                let location = (line.file, line.line, line.column);
+                if last == Some(location) {
+                    continue;
+                }
+                last = Some(location);
+                let row = program.row();
+                row.address_offset = instruction.code_offset as u64;
+                row.file = file_ids[line.file];
+                row.line = line.line;
+                row.column = line.column;
+                program.generate_row();
+            }
+            program.end_sequence(function.size);
+        }
+        dwarf.unit.line_program = program;
+    }
+
+    let low_pc = functions.iter().map(|f| f.address).min().unwrap_or(0);
+    let high_pc = functions
+        .iter()
+        .map(|f| f.address + f.size)
+        .max()
+        .unwrap_or(0);
+    let root = dwarf.unit.root();
+    let entry = dwarf.unit.get_mut(root);
+    entry.set(gimli::DW_AT_name, AttributeValue::String(name.into_bytes()));
+    entry.set(
+        gimli::DW_AT_producer,
+        AttributeValue::String(format!("wasmer {}", crate::VERSION).into_bytes()),
+    );
+    entry.set(
+        gimli::DW_AT_low_pc,
+        AttributeValue::Address(Address::Constant(low_pc)),
+    );
+    entry.set(
+        gimli::DW_AT_high_pc,
+        AttributeValue::Udata(high_pc - low_pc),
+    );
+    for function in functions {
+        let id = dwarf.unit.add(root, gimli::DW_TAG_subprogram);
+        let entry = dwarf.unit.get_mut(id);
+        entry.set(
+            gimli::DW_AT_name,
+            AttributeValue::String(function.name.clone().into_bytes()),
+        );
+        entry.set(
+            gimli::DW_AT_low_pc,
+            AttributeValue::Address(Address::Constant(function.address)),
+        );
+        entry.set(gimli::DW_AT_high_pc, AttributeValue::Udata(function.size));
+    }
+
+    let mut sections = Sections::new(EndianVec::new(LittleEndian));
+    dwarf
+        .write(&mut sections)
+        .map_err(|e| format!("failed to write the DWARF: {}", e))?;
+    let mut result = vec![];
+    sections
+        .for_each(|id, data| {
+            if !data.slice().is_empty() {
+                result.push((id.name(), data.slice().to_vec()));
+            }
+            Ok::<(), write::Error>(())
+        })
+        .map_err(|e| format!("failed to write the DWARF: {}", e))?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use wasmer_compiler::{InstructionAddressMap, SourceLoc};
+
+    /// Builds the DWARF sections of a module whose code section
+    /// offsets `0x10..0x20` come from `src/lib.c:7` and `0x20..0x30`
+    /// from `src/lib.c:8`.
+    fn wasm_dwarf() -> Vec<(&'static str, Vec<u8>)> {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 4,
+        };
+        let mut dwarf = DwarfUnit::new(encoding);
+        let mut program = LineProgram::new(
+            encoding,
+            Default::default(),
+            LineString::String(b"/build".to_vec()),
+            LineString::String(b"lib.c".to_vec()),
+            None,
+        );
+        let directory = program.add_directory(LineString::String(b"src".to_vec()));
+        let file = program.add_file(LineString::String(b"lib.c".to_vec()), directory, None);
+        program.begin_sequence(Some(Address::Constant(0x10)));
+        for (offset, line) in &[(0, 7), (0x10, 8)] {
+            let row = program.row();
+            row.address_offset = *offset;
+            row.file = file;
+            row.line = *line;
+            program.generate_row();
+        }
+        program.end_sequence(0x20);
+        dwarf.unit.line_program = program;
+        let root = dwarf.unit.root();
+        dwarf.unit.get_mut(root).set(
+            gimli::DW_AT_comp_dir,
+            AttributeValue::String(b"/build".to_vec()),
+        );
+
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+        let mut result = vec![];
+        sections
+            .for_each(|id, data| {
+                result.push((id.name(), data.slice().to_vec()));
+                Ok::<(), write::Error>(())
+            })
+            .unwrap();
+        result
+    }
+
+    #[test]
+    fn translate_line_tables() {
+        let mut module = ModuleInfo::new();
+        module.code_section_offset = 0x100;
+        for (name, data) in wasm_dwarf() {
+            let index = module.custom_sections_data.push(Arc::from(data));
+            module.custom_sections.insert(name.to_string(), index);
+        }
+        let instruction = |srcloc, code_offset| InstructionAddressMap {
+            srcloc: SourceLoc::new(srcloc),
+            code_offset,
+            code_len: 4,
+        };
+        let functions = [DebugFunction {
+            name: "f".to_string(),
+            address: 0x1000,
+            size: 0x40,
+            address_map: FunctionAddressMap {
+                instructions: vec![
+                    instruction(0x112, 0),
+                    instruction(0x118, 8),
+                    instruction(0x124, 0x20),
+                    instruction(0x140, 0x30),
+                ],
+                ..Default::default()
+            },
+        }];
+        let sections = translate(&module, &functions).unwrap();
+
+        let load = |id: SectionId| -> gimli::Result<EndianSlice<LittleEndian>> {
+            let data = sections
+                .iter()
+                .find(|(name, _)| *name == id.name())
+                .map(|(_, data)| &data[..])
+                .unwrap_or(&[]);
+            Ok(EndianSlice::new(data, LittleEndian))
+        };
+        let load_sup = |_| Ok(EndianSlice::new(&[], LittleEndian));
+        let dwarf = gimli::read::Dwarf::load(load, load_sup).unwrap();
+        let unit = dwarf.unit(dwarf.units().next().unwrap().unwrap()).unwrap();
+        let mut rows = unit.line_program.clone().unwrap().rows();
+        let mut lines = vec![];
+        while let Some((header, row)) = rows.next_row().unwrap() {
+            if row.end_sequence() {
+                lines.push((row.address(), None));
+                continue;
+            }
+            let file = row.file(header).unwrap();
+            let directory = dwarf
+                .attr_string(&unit, file.directory(header).unwrap())
+                .unwrap();
+            let name = dwarf.attr_string(&unit, file.path_name()).unwrap();
+            let path = format!("{}/{}", directory.to_string_lossy(), name.to_string_lossy());
+            lines.push((row.address(), Some((path, row.line().unwrap()))));
+        }
+        assert_eq!(
+            lines,
+            vec![
+                (0x1000, Some(("/build/src/lib.c".to_string(), 7))),
+                (0x1020, Some(("/build/src/lib.c".to_string(), 8))),
+                (0x1040, None),
+            ]
+        );
+    }
+}
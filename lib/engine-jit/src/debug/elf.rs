@@ -0,0 +1,277 @@
+//! A minimal writer of 64-bit little-endian ELF images describing code
+//! that is already loaded in memory.
+//!
+//! The image doesn't contain the code itself: its `.text` section has
+//! no bits and is placed at the address where the code lives, so the
+//! symbols and the DWARF sections can refer to absolute addresses.
+
+const ELF_HEADER_SIZE: usize = 64;
+const PROGRAM_HEADER_SIZE: usize = 56;
+const SECTION_HEADER_SIZE: usize = 64;
+const SYMBOL_SIZE: usize = 24;
+
+const ET_DYN: u16 = 3;
+const PT_LOAD: u32 = 1;
+const PF_X: u32 = 1;
+const PF_R: u32 = 4;
+const SHT_PROGBITS: u32 = 1;
+const SHT_SYMTAB: u32 = 2;
+const SHT_STRTAB: u32 = 3;
+const SHT_NOBITS: u32 = 8;
+const SHF_ALLOC: u64 = 2;
+const SHF_EXECINSTR: u64 = 4;
+const STB_GLOBAL: u8 = 1;
+const STT_FUNC: u8 = 2;
+
+/// The ELF machine of the host, if it's supported.
+pub fn host_machine() -> Option<u16> {
+    if cfg!(not(all(
+        target_pointer_width = "64",
+        target_endian = "little"
+    ))) {
+        None
+    } else if cfg!(target_arch = "x86_64") {
+        Some(62)
+    } else if cfg!(target_arch = "aarch64") {
+        Some(183)
+    } else {
+        None
+    }
+}
+
+/// A function symbol of the image.
+pub struct ElfSymbol {
+    pub name: String,
+    pub address: u64,
+    pub size: u64,
+}
+
+struct SectionHeader {
+    name: u32,
+    kind: u32,
+    flags: u64,
+    address: u64,
+    offset: u64,
+    size: u64,
+    link: u32,
+    info: u32,
+    align: u64,
+    entry_size: u64,
+}
+
+/// Builds an image whose code spans `text_size` bytes from
+/// `text_address`, with the given symbols and extra (debug) sections.
+pub fn build_image(
+    machine: u16,
+    text_address: u64,
+    text_size: u64,
+    symbols: &[ElfSymbol],
+    sections: &[(&str, Vec<u8>)],
+) -> Vec<u8> {
+    let mut shstrtab = vec![0];
+    let mut add_name = |name: &str| {
+        let offset = shstrtab.len() as u32;
+        shstrtab.extend(name.as_bytes());
+        shstrtab.push(0);
+        offset
+    };
+
+    let mut headers = vec![SectionHeader {
+        name: 0,
+        kind: 0,
+        flags: 0,
+        address: 0,
+        offset: 0,
+        size: 0,
+        link: 0,
+        info: 0,
+        align: 0,
+        entry_size: 0,
+    }];
+    let text_index = headers.len() as u16;
+    headers.push(SectionHeader {
+        name: add_name(".text"),
+        kind: SHT_NOBITS,
+        flags: SHF_ALLOC | SHF_EXECINSTR,
+        address: text_address,
+        offset: 0,
+        size: text_size,
+        link: 0,
+        info: 0,
+        align: 1,
+        entry_size: 0,
+    });
+
+    let mut image = vec![0; ELF_HEADER_SIZE + PROGRAM_HEADER_SIZE];
+
+    for (name, data) in sections {
+        headers.push(SectionHeader {
+            name: add_name(name),
+            kind: SHT_PROGBITS,
+            flags: 0,
+            address: 0,
+            offset: add_data(&mut image, data),
+            size: data.len() as u64,
+            link: 0,
+            info: 0,
+            align: 1,
+            entry_size: 0,
+        });
+    }
+
+    let mut strtab = vec![0];
+    let mut symtab = vec![0; SYMBOL_SIZE];
+    for symbol in symbols {
+        let name = strtab.len() as u32;
+        strtab.extend(symbol.name.as_bytes());
+        strtab.push(0);
+        symtab.extend(&name.to_le_bytes());
+        symtab.push(STB_GLOBAL << 4 | STT_FUNC);
+        symtab.push(0);
+        symtab.extend(&text_index.to_le_bytes());
+        symtab.extend(&symbol.address.to_le_bytes());
+        symtab.extend(&symbol.size.to_le_bytes());
+    }
+    let symtab_index = headers.len();
+    headers.push(SectionHeader {
+        name: add_name(".symtab"),
+        kind: SHT_SYMTAB,
+        flags: 0,
+        address: 0,
+        offset: add_data(&mut image, &symtab),
+        size: symtab.len() as u64,
+        link: symtab_index as u32 + 1,
+        // The index of the first global symbol.
+        info: 1,
+        align: 8,
+        entry_size: SYMBOL_SIZE as u64,
+    });
+    headers.push(SectionHeader {
+        name: add_name(".strtab"),
+        kind: SHT_STRTAB,
+        flags: 0,
+        address: 0,
+        offset: add_data(&mut image, &strtab),
+        size: strtab.len() as u64,
+        link: 0,
+        info: 0,
+        align: 1,
+        entry_size: 0,
+    });
+    let shstrtab_index = headers.len() as u16;
+    let shstrtab_name = add_name(".shstrtab");
+    headers.push(SectionHeader {
+        name: shstrtab_name,
+        kind: SHT_STRTAB,
+        flags: 0,
+        address: 0,
+        offset: add_data(&mut image, &shstrtab),
+        size: shstrtab.len() as u64,
+        link: 0,
+        info: 0,
+        align: 1,
+        entry_size: 0,
+    });
+
+    let section_headers_offset = add_data(&mut image, &[]);
+    for header in &headers {
+        image.extend(&header.name.to_le_bytes());
+        image.extend(&header.kind.to_le_bytes());
+        image.extend(&header.flags.to_le_bytes());
+        image.extend(&header.address.to_le_bytes());
+        image.extend(&header.offset.to_le_bytes());
+        image.extend(&header.size.to_le_bytes());
+        image.extend(&header.link.to_le_bytes());
+        image.extend(&header.info.to_le_bytes());
+        image.extend(&header.align.to_le_bytes());
+        image.extend(&header.entry_size.to_le_bytes());
+    }
+
+    let mut header = Vec::with_capacity(ELF_HEADER_SIZE + PROGRAM_HEADER_SIZE);
+    // ELFCLASS64, ELFDATA2LSB, EV_CURRENT, ELFOSABI_NONE.
+    header.extend(b"\x7fELF\x02\x01\x01\x00");
+    header.extend(&[0; 8]);
+    header.extend(&ET_DYN.to_le_bytes());
+    header.extend(&machine.to_le_bytes());
+    header.extend(&1u32.to_le_bytes());
+    // The entry point.
+    header.extend(&0u64.to_le_bytes());
+    header.extend(&(ELF_HEADER_SIZE as u64).to_le_bytes());
+    header.extend(&section_headers_offset.to_le_bytes());
+    header.extend(&0u32.to_le_bytes());
+    header.extend(&(ELF_HEADER_SIZE as u16).to_le_bytes());
+    header.extend(&(PROGRAM_HEADER_SIZE as u16).to_le_bytes());
+    header.extend(&1u16.to_le_bytes());
+    header.extend(&(SECTION_HEADER_SIZE as u16).to_le_bytes());
+    header.extend(&(headers.len() as u16).to_le_bytes());
+    header.extend(&shstrtab_index.to_le_bytes());
+
+    // A single segment, covering the code.
+    header.extend(&PT_LOAD.to_le_bytes());
+    header.extend(&(PF_R | PF_X).to_le_bytes());
+    header.extend(&0u64.to_le_bytes());
+    header.extend(&text_address.to_le_bytes());
+    header.extend(&text_address.to_le_bytes());
+    header.extend(&0u64.to_le_bytes());
+    header.extend(&text_size.to_le_bytes());
+    header.extend(&1u64.to_le_bytes());
+
+    image[..header.len()].copy_from_slice(&header);
+    image
+}
+
+/// Appends 8-byte aligned data to the image, returning its offset.
+fn add_data(image: &mut Vec<u8>, data: &[u8]) -> u64 {
+    while image.len() % 8 != 0 {
+        image.push(0);
+    }
+    let offset = image.len() as u64;
+    image.extend(data);
+    offset
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_u16(image: &[u8], offset: usize) -> u16 {
+        u16::from_le_bytes([image[offset], image[offset + 1]])
+    }
+
+    fn read_u64(image: &[u8], offset: usize) -> u64 {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(&image[offset..offset + 8]);
+        u64::from_le_bytes(bytes)
+    }
+
+    #[test]
+    fn image_layout() {
+        let symbols = [ElfSymbol {
+            name: "f".to_string(),
+            address: 0x7f00_0000_1000,
+            size: 0x20,
+        }];
+        let image = build_image(
+            62,
+            0x7f00_0000_1000,
+            0x40,
+            &symbols,
+            &[(".debug_line", vec![1, 2, 3])],
+        );
+        assert_eq!(&image[..4], b"\x7fELF");
+        assert_eq!(read_u16(&image, 18), 62);
+
+        // null, .text, .debug_line, .symtab, .strtab, .shstrtab
+        let section_headers = read_u64(&image, 40) as usize;
+        assert_eq!(read_u16(&image, 60), 6);
+        assert_eq!(image.len(), section_headers + 6 * SECTION_HEADER_SIZE);
+        let text = section_headers + SECTION_HEADER_SIZE;
+        assert_eq!(read_u64(&image, text + 16), 0x7f00_0000_1000);
+        assert_eq!(read_u64(&image, text + 32), 0x40);
+
+        let symtab = section_headers + 3 * SECTION_HEADER_SIZE;
+        let symbol = read_u64(&image, symtab + 24) as usize + SYMBOL_SIZE;
+        assert_eq!(read_u64(&image, symbol + 8), 0x7f00_0000_1000);
+        assert_eq!(read_u64(&image, symbol + 16), 0x20);
+    }
+}
@@ -0,0 +1,116 @@
+//! Registration of in-memory object files with the GDB JIT interface.
+//!
+//! Debuggers put a breakpoint in `__jit_debug_register_code` and read
+//! `__jit_debug_descriptor` when it's hit to learn about the object
+//! files describing the code generated at runtime.
+//!
+//! See <https://sourceware.org/gdb/current/onlinedocs/gdb/JIT-Interface.html>.
+#![allow(non_upper_case_globals)]
+
+use std::ptr;
+use std::sync::Mutex;
+
+#[repr(C)]
+struct JITCodeEntry {
+    next_entry: *mut JITCodeEntry,
+    prev_entry: *mut JITCodeEntry,
+    symfile_addr: *const u8,
+    symfile_size: u64,
+}
+
+const JIT_NOACTION: u32 = 0;
+const JIT_REGISTER_FN: u32 = 1;
+const JIT_UNREGISTER_FN: u32 = 2;
+
+#[repr(C)]
+struct JITDescriptor {
+    version: u32,
+    action_flag: u32,
+    relevant_entry: *mut JITCodeEntry,
+    first_entry: *mut JITCodeEntry,
+}
+
+#[no_mangle]
+static mut __jit_debug_descriptor: JITDescriptor = JITDescriptor {
+    version: 1,
+    action_flag: JIT_NOACTION,
+    relevant_entry: ptr::null_mut(),
+    first_entry: ptr::null_mut(),
+};
+
+#[no_mangle]
+#[inline(never)]
+extern "C" fn __jit_debug_register_code() {
+    // The debugger sets a breakpoint here: make sure the call isn't
+    // optimized away.
+    unsafe {
+        ptr::read_volatile(&__jit_debug_descriptor.action_flag);
+    }
+}
+
+lazy_static::lazy_static! {
+    // The descriptor is global: serialize every access to it.
+    static ref GDB_REGISTRATION: Mutex<()> = Mutex::new(());
+}
+
+/// An object file registered with the GDB JIT interface, unregistered
+/// when dropped.
+pub struct GdbJitImageRegistration {
+    entry: Box<JITCodeEntry>,
+    // Keeps alive the ELF image the entry points to.
+    _file: Box<[u8]>,
+}
+
+impl GdbJitImageRegistration {
+    /// Registers the given object file.
+    pub fn register(file: Vec<u8>) -> Self {
+        let file = file.into_boxed_slice();
+        let mut entry = Box::new(JITCodeEntry {
+            next_entry: ptr::null_mut(),
+            prev_entry: ptr::null_mut(),
+            symfile_addr: file.as_ptr(),
+            symfile_size: file.len() as u64,
+        });
+        let _guard = GDB_REGISTRATION.lock().unwrap();
+        unsafe {
+            let entry = &mut *entry as *mut JITCodeEntry;
+            (*entry).next_entry = __jit_debug_descriptor.first_entry;
+            if !(*entry).next_entry.is_null() {
+                (*(*entry).next_entry).prev_entry = entry;
+            }
+            __jit_debug_descriptor.first_entry = entry;
+            __jit_debug_descriptor.relevant_entry = entry;
+            __jit_debug_descriptor.action_flag = JIT_REGISTER_FN;
+            __jit_debug_register_code();
+            __jit_debug_descriptor.action_flag = JIT_NOACTION;
+            __jit_debug_descriptor.relevant_entry = ptr::null_mut();
+        }
+        Self { entry, _file: file }
+    }
+}
+
+impl Drop for GdbJitImageRegistration {
+    fn drop(&mut self) {
+        let _guard = GDB_REGISTRATION.lock().unwrap();
+        unsafe {
+            let entry = &mut *self.entry as *mut JITCodeEntry;
+            let (prev, next) = ((*entry).prev_entry, (*entry).next_entry);
+            if prev.is_null() {
+                __jit_debug_descriptor.first_entry = next;
+            } else {
+                (*prev).next_entry = next;
+            }
+            if !next.is_null() {
+                (*next).prev_entry = prev;
+            }
+            __jit_debug_descriptor.relevant_entry = entry;
+            __jit_debug_descriptor.action_flag = JIT_UNREGISTER_FN;
+            __jit_debug_register_code();
+            __jit_debug_descriptor.action_flag = JIT_NOACTION;
+            __jit_debug_descriptor.relevant_entry = ptr::null_mut();
+        }
+    }
+}
+
+unsafe impl Send for GdbJitImageRegistration {}
+unsafe impl Sync for GdbJitImageRegistration {}
@@ -0,0 +1,54 @@
+//! Debug information for the JIT-compiled code.
+//!
+//! Every artifact gets an in-memory ELF image with a symbol per
+//! function and, when the module has DWARF sections, a line table
+//! translated to the native code. The image is registered with the
+//! GDB JIT interface, which both GDB and LLDB use to load the symbols
+//! of code generated at runtime.
+//!
+//! Only the line table is translated, here rather than in the
+//! compilers: it is built from the address maps the compilers record
+//! for the frame info, so the other engines don't get it.
+
+mod dwarf;
+mod elf;
+mod gdb_jit;
+
+pub use self::dwarf::DebugFunction;
+pub use self::elf::host_machine;
+pub use self::gdb_jit::GdbJitImageRegistration;
+use wasmer_vm::ModuleInfo;
+
+/// Registers the debug information of the given functions.
+///
+/// Returns `None` if the host isn't supported.
+pub fn register_debug_info(
+    module: &ModuleInfo,
+    functions: &[DebugFunction],
+) -> Option<GdbJitImageRegistration> {
+    let machine = elf::host_machine()?;
+    if functions.is_empty() {
+        return None;
+    }
+    // A malformed DWARF shouldn't prevent running the module: the
+    // function symbols are still registered.
+    let sections = dwarf::translate(module, functions).unwrap_or_default();
+    let text_address = functions.iter().map(|f| f.address).min().unwrap();
+    let text_end = functions.iter().map(|f| f.address + f.size).max().unwrap();
+    let symbols = functions
+        .iter()
+        .map(|function| elf::ElfSymbol {
+            name: function.name.clone(),
+            address: function.address,
+            size: function.size,
+        })
+        .collect::<Vec<_>>();
+    let image = elf::build_image(
+        machine,
+        text_address,
+        text_end - text_address,
+        &symbols,
+        &sections,
+    );
+    Some(GdbJitImageRegistration::register(image))
+}
@@ -1,13 +1,17 @@
 //! JIT compilation.
 
+use crate::profiling::{self, ProfilingAgent, ProfilingStrategy};
 use crate::{CodeMemory, JITArtifact};
 use std::sync::{Arc, Mutex};
-#[cfg(feature = "compiler")]
-use wasmer_compiler::Compiler;
 use wasmer_compiler::{
     CompileError, CustomSection, CustomSectionProtection, FunctionBody, SectionIndex, Target,
+    UnwindInfoMode,
+};
+#[cfg(feature = "compiler")]
+use wasmer_compiler::{CompileFuel, Compiler};
+use wasmer_engine::{
+    Artifact, DeserializeError, Engine, EngineId, FunctionExtent, Tunables, TunablesConfig,
 };
-use wasmer_engine::{Artifact, DeserializeError, Engine, EngineId, FunctionExtent, Tunables};
 use wasmer_types::entity::PrimaryMap;
 use wasmer_types::Features;
 use wasmer_types::{FunctionIndex, FunctionType, LocalFunctionIndex, SignatureIndex};
@@ -35,6 +39,13 @@ impl JITEngine {
                 code_memory: vec![],
                 signatures: SignatureRegistry::new(),
                 features,
+                debug_info: false,
+                unwind_info: UnwindInfoMode::Default,
+                profiler: ProfilingStrategy::None,
+                profiling_agent: None,
+                compile_fuel: None,
+                tunables_config: TunablesConfig::default(),
+                embed_wasm: false,
             })),
             target: Arc::new(target),
             engine_id: EngineId::default(),
@@ -62,6 +73,13 @@ impl JITEngine {
                 code_memory: vec![],
                 signatures: SignatureRegistry::new(),
                 features: Features::default(),
+                debug_info: false,
+                unwind_info: UnwindInfoMode::Default,
+                profiler: ProfilingStrategy::None,
+                profiling_agent: None,
+                compile_fuel: None,
+                tunables_config: TunablesConfig::default(),
+                embed_wasm: false,
             })),
             target: Arc::new(Target::default()),
             engine_id: EngineId::default(),
@@ -95,6 +113,25 @@ impl Engine for JITEngine {
         compiler.signatures().lookup(sig)
     }
 
+    /// Lookup a call trampoline
+    fn lookup_call_trampoline(&self, sig: VMSharedSignatureIndex) -> Option<VMTrampoline> {
+        let compiler = self.inner();
+        compiler.signatures().lookup_trampoline(sig)
+    }
+
+    /// Whether the compiled code is deterministic
+    #[cfg(feature = "compiler")]
+    fn deterministic(&self) -> bool {
+        self.inner()
+            .compiler()
+            .map_or(false, |compiler| compiler.deterministic())
+    }
+
+    /// The configuration of the default tunables of the stores
+    fn tunables_config(&self) -> TunablesConfig {
+        self.inner().tunables_config().clone()
+    }
+
     /// Validates a WebAssembly module
     fn validate(&self, binary: &[u8]) -> Result<(), CompileError> {
         self.inner().validate(binary)
@@ -110,6 +147,22 @@ impl Engine for JITEngine {
         Ok(Arc::new(JITArtifact::new(&self, binary, tunables)?))
     }
 
+    /// Compile many distinct WebAssembly binaries together
+    #[cfg(feature = "compiler")]
+    fn compile_distinct(
+        &self,
+        binaries: &[&[u8]],
+        tunables: &dyn Tunables,
+    ) -> Result<(Vec<Arc<dyn Artifact>>, usize), CompileError> {
+        let (artifacts, reused_function_bodies) =
+            JITArtifact::new_batch(&self, binaries, tunables)?;
+        let artifacts = artifacts
+            .into_iter()
+            .map(|artifact| Arc::new(artifact) as Arc<dyn Artifact>)
+            .collect();
+        Ok((artifacts, reused_function_bodies))
+    }
+
     /// Compile a WebAssembly binary
     #[cfg(not(feature = "compiler"))]
     fn compile(
@@ -128,6 +181,14 @@ impl Engine for JITEngine {
         Ok(Arc::new(JITArtifact::deserialize(&self, &bytes)?))
     }
 
+    /// Deserializes a WebAssembly module from an untrusted source
+    unsafe fn deserialize_untrusted(
+        &self,
+        bytes: &[u8],
+    ) -> Result<Arc<dyn Artifact>, DeserializeError> {
+        Ok(Arc::new(JITArtifact::deserialize_untrusted(self, bytes)?))
+    }
+
     fn id(&self) -> &EngineId {
         &self.engine_id
     }
@@ -150,6 +211,19 @@ pub struct JITEngineInner {
     /// The signature registry is used mainly to operate with trampolines
     /// performantly.
     signatures: SignatureRegistry,
+    /// Whether the compiled code is registered with the debuggers.
+    debug_info: bool,
+    /// The unwind information registered for the compiled code.
+    unwind_info: UnwindInfoMode,
+    /// The profiler the compiled code is described to.
+    profiler: ProfilingStrategy,
+    profiling_agent: Option<Box<dyn ProfilingAgent>>,
+    /// The units of work a module compilation can do.
+    compile_fuel: Option<u64>,
+    /// The configuration of the default tunables of the stores.
+    tunables_config: TunablesConfig,
+    /// Whether the original wasm is embedded in the artifacts.
+    embed_wasm: bool,
 }
 
 impl JITEngineInner {
@@ -165,6 +239,9 @@ impl JITEngineInner {
     /// Validate the module
     #[cfg(feature = "compiler")]
     pub fn validate<'data>(&self, data: &'data [u8]) -> Result<(), CompileError> {
+        if let Some(limit) = self.compile_fuel {
+            CompileFuel::new(limit).consume_module(data)?;
+        }
         self.compiler()?.validate_module(self.features(), data)
     }
 
@@ -182,6 +259,66 @@ impl JITEngineInner {
         &self.features
     }
 
+    /// Whether the compiled code is registered with the debuggers.
+    pub fn debug_info(&self) -> bool {
+        self.debug_info
+    }
+
+    pub(crate) fn set_debug_info(&mut self, enable: bool) {
+        self.debug_info = enable;
+    }
+
+    /// The unwind information registered for the compiled code.
+    pub fn unwind_info(&self) -> UnwindInfoMode {
+        self.unwind_info
+    }
+
+    pub(crate) fn set_unwind_info(&mut self, mode: UnwindInfoMode) {
+        self.unwind_info = mode;
+    }
+
+    /// The profiler the compiled code is described to.
+    pub fn profiler(&self) -> ProfilingStrategy {
+        self.profiler
+    }
+
+    pub(crate) fn set_profiler(&mut self, strategy: ProfilingStrategy) {
+        self.profiler = strategy;
+        self.profiling_agent = profiling::agent(strategy);
+    }
+
+    pub(crate) fn profiling_agent(&self) -> Option<&dyn ProfilingAgent> {
+        self.profiling_agent.as_deref()
+    }
+
+    /// The units of work a module compilation can do, see
+    /// [`CompileFuel`](wasmer_compiler::CompileFuel).
+    pub fn compile_fuel(&self) -> Option<u64> {
+        self.compile_fuel
+    }
+
+    pub(crate) fn set_compile_fuel(&mut self, limit: Option<u64>) {
+        self.compile_fuel = limit;
+    }
+
+    /// The configuration of the default tunables of the stores.
+    pub fn tunables_config(&self) -> &TunablesConfig {
+        &self.tunables_config
+    }
+
+    pub(crate) fn set_tunables_config(&mut self, config: TunablesConfig) {
+        self.tunables_config = config;
+    }
+
+    /// Whether the original wasm is embedded in the artifacts.
+    pub fn embed_wasm(&self) -> bool {
+        self.embed_wasm
+    }
+
+    pub(crate) fn set_embed_wasm(&mut self, enable: bool) {
+        self.embed_wasm = enable;
+    }
+
     /// Allocate compiled functions into memory
     #[allow(clippy::type_complexity)]
     pub(crate) fn allocate(
@@ -281,12 +418,20 @@ impl JITEngineInner {
     }
 
     /// Register DWARF-type exception handling information associated with the code.
-    pub(crate) fn publish_eh_frame(&mut self, eh_frame: Option<&[u8]>) -> Result<(), CompileError> {
+    ///
+    /// The `frame_pointer_functions` are the extents of the functions
+    /// without unwind information, to be unwound by following the
+    /// frame pointer.
+    pub(crate) fn publish_eh_frame(
+        &mut self,
+        eh_frame: Option<&[u8]>,
+        frame_pointer_functions: &[FunctionExtent],
+    ) -> Result<(), CompileError> {
         self.code_memory
             .last_mut()
             .unwrap()
             .unwind_registry_mut()
-            .publish(eh_frame)
+            .publish(eh_frame, frame_pointer_functions)
             .map_err(|e| {
                 CompileError::Resource(format!("Error while publishing the unwind code: {}", e))
             })?;
@@ -27,16 +27,20 @@
 mod artifact;
 mod builder;
 mod code_memory;
+mod debug;
 mod engine;
 mod link;
+mod profiling;
 mod serialize;
 mod unwind;
+mod validate;
 
 pub use crate::artifact::JITArtifact;
 pub use crate::builder::JIT;
 pub use crate::code_memory::CodeMemory;
 pub use crate::engine::JITEngine;
 pub use crate::link::link_module;
+pub use crate::profiling::ProfilingStrategy;
 
 /// Version number of this crate.
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
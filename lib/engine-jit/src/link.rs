@@ -62,6 +62,20 @@ fn apply_relocation(
     }
 }
 
+/// Returns the number of bytes `apply_relocation` patches for a
+/// relocation of `kind`, or `None` if the kind isn't supported in the
+/// current architecture.
+pub(crate) fn relocation_size(kind: RelocationKind) -> Option<usize> {
+    match kind {
+        #[cfg(target_pointer_width = "64")]
+        RelocationKind::Abs8 | RelocationKind::X86PCRel8 => Some(8),
+        #[cfg(target_pointer_width = "32")]
+        RelocationKind::X86PCRel4 | RelocationKind::X86CallPCRel4 => Some(4),
+        RelocationKind::X86PCRelRodata4 => Some(0),
+        _ => None,
+    }
+}
+
 /// Links a module, patching the allocated functions with the
 /// required relocations and jump tables.
 pub fn link_module(
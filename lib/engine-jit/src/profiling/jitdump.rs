@@ -0,0 +1,121 @@
+//! The jitdump: a binary file with the functions generated at runtime
+//! and their code, that `perf inject --jit` merges into a profile.
+//!
+//! See <https://github.com/torvalds/linux/blob/master/tools/perf/Documentation/jitdump-specification.txt>.
+
+use super::ProfilingAgent;
+use crate::debug::host_machine;
+use memmap2::{Mmap, MmapOptions};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::Mutex;
+
+const JITDUMP_MAGIC: u32 = 0x4A69_5444;
+const JITDUMP_VERSION: u32 = 1;
+const HEADER_SIZE: u32 = 40;
+const RECORD_HEADER_SIZE: u32 = 16;
+const JIT_CODE_LOAD: u32 = 0;
+
+struct JitDumpFile {
+    file: File,
+    /// `perf record` learns about the file through this mapping.
+    _marker: Mmap,
+    code_index: u64,
+}
+
+lazy_static::lazy_static! {
+    // The dump is per process, shared by all the engines.
+    static ref JITDUMP: Mutex<Option<JitDumpFile>> = Mutex::new(None);
+}
+
+/// The timestamp of the records: `perf record -k mono` uses the same clock.
+fn timestamp() -> u64 {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    unsafe {
+        libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut ts);
+    }
+    ts.tv_sec as u64 * 1_000_000_000 + ts.tv_nsec as u64
+}
+
+fn thread_id() -> u32 {
+    unsafe { libc::syscall(libc::SYS_gettid) as u32 }
+}
+
+pub struct JitDumpAgent;
+
+impl JitDumpAgent {
+    pub fn new() -> Result<Self, String> {
+        let mut jitdump = JITDUMP.lock().unwrap();
+        if jitdump.is_some() {
+            return Ok(Self);
+        }
+        let machine = host_machine().ok_or("the architecture is not supported")?;
+        let path = std::env::temp_dir().join(format!("jit-{}.dump", std::process::id()));
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)
+            .map_err(|e| format!("failed to open `{}`: {}", path.display(), e))?;
+
+        let mut header = Vec::with_capacity(HEADER_SIZE as usize);
+        header.extend(&JITDUMP_MAGIC.to_ne_bytes());
+        header.extend(&JITDUMP_VERSION.to_ne_bytes());
+        header.extend(&HEADER_SIZE.to_ne_bytes());
+        header.extend(&(machine as u32).to_ne_bytes());
+        // Padding.
+        header.extend(&0u32.to_ne_bytes());
+        header.extend(&std::process::id().to_ne_bytes());
+        header.extend(&timestamp().to_ne_bytes());
+        // Flags.
+        header.extend(&0u64.to_ne_bytes());
+        file.write_all(&header)
+            .map_err(|e| format!("failed to write `{}`: {}", path.display(), e))?;
+
+        let marker = unsafe { MmapOptions::new().len(header.len()).map_exec(&file) }
+            .map_err(|e| format!("failed to map `{}`: {}", path.display(), e))?;
+        *jitdump = Some(JitDumpFile {
+            file,
+            _marker: marker,
+            code_index: 0,
+        });
+        Ok(Self)
+    }
+}
+
+impl ProfilingAgent for JitDumpAgent {
+    fn register_function(&self, name: &str, address: *const u8, size: usize) {
+        let mut jitdump = JITDUMP.lock().unwrap();
+        let jitdump = match jitdump.as_mut() {
+            Some(jitdump) => jitdump,
+            None => return,
+        };
+        let code = unsafe { std::slice::from_raw_parts(address, size) };
+        let total_size = RECORD_HEADER_SIZE as usize + 40 + name.len() + 1 + size;
+
+        let mut record = Vec::with_capacity(total_size);
+        record.extend(&JIT_CODE_LOAD.to_ne_bytes());
+        record.extend(&(total_size as u32).to_ne_bytes());
+        record.extend(&timestamp().to_ne_bytes());
+        record.extend(&std::process::id().to_ne_bytes());
+        record.extend(&thread_id().to_ne_bytes());
+        // The virtual address and the address of the code are the same.
+        record.extend(&(address as u64).to_ne_bytes());
+        record.extend(&(address as u64).to_ne_bytes());
+        record.extend(&(size as u64).to_ne_bytes());
+        record.extend(&jitdump.code_index.to_ne_bytes());
+        record.extend(name.as_bytes());
+        record.push(0);
+        record.extend(code);
+        debug_assert_eq!(record.len(), total_size);
+
+        jitdump.code_index += 1;
+        if let Err(e) = jitdump.file.write_all(&record) {
+            tracing::warn!("Failed to write to the jitdump: {}", e);
+        }
+    }
+}
@@ -0,0 +1,101 @@
+//! Integration of the compiled code with the native profilers.
+//!
+//! Profilers like `perf` don't know about the code generated at
+//! runtime: the profiling agents tell them which function lives at
+//! which address, so the samples are attributed to the WebAssembly
+//! functions.
+
+#[cfg(target_os = "linux")]
+mod jitdump;
+#[cfg(unix)]
+mod perfmap;
+
+use std::fmt;
+use std::str::FromStr;
+
+/// The profiler the compiled code is described to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfilingStrategy {
+    /// No profiler.
+    None,
+    /// Write the address and the name of the functions to
+    /// `/tmp/perf-<pid>.map`, as read by `perf report`.
+    PerfMap,
+    /// Write the functions and their code to a `jit-<pid>.dump` file
+    /// in the temporary directory, to be merged into the profile with
+    /// `perf inject --jit` after recording with `perf record -k mono`.
+    JitDump,
+}
+
+impl Default for ProfilingStrategy {
+    fn default() -> Self {
+        Self::None
+    }
+}
+
+impl FromStr for ProfilingStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Self::None),
+            "perfmap" => Ok(Self::PerfMap),
+            "jitdump" => Ok(Self::JitDump),
+            _ => Err(format!("The profiling strategy `{}` is not recognized", s)),
+        }
+    }
+}
+
+impl fmt::Display for ProfilingStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::None => "none",
+            Self::PerfMap => "perfmap",
+            Self::JitDump => "jitdump",
+        })
+    }
+}
+
+/// Describes the compiled functions to a profiler.
+pub(crate) trait ProfilingAgent: Send + Sync {
+    /// Registers a function whose code spans `size` bytes from `address`.
+    fn register_function(&self, name: &str, address: *const u8, size: usize);
+}
+
+/// Creates the agent of the given strategy, if it's supported on
+/// this platform and its files could be created.
+pub(crate) fn agent(strategy: ProfilingStrategy) -> Option<Box<dyn ProfilingAgent>> {
+    let agent: Result<Box<dyn ProfilingAgent>, String> = match strategy {
+        ProfilingStrategy::None => return None,
+        #[cfg(unix)]
+        ProfilingStrategy::PerfMap => perfmap::PerfMapAgent::new().map(|a| Box::new(a) as _),
+        #[cfg(target_os = "linux")]
+        ProfilingStrategy::JitDump => jitdump::JitDumpAgent::new().map(|a| Box::new(a) as _),
+        #[allow(unreachable_patterns)]
+        _ => Err("it is not supported on this platform".to_string()),
+    };
+    match agent {
+        Ok(agent) => Some(agent),
+        Err(e) => {
+            tracing::warn!("Disabling the `{}` profiling: {}", strategy, e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_strategy() {
+        for strategy in &[
+            ProfilingStrategy::None,
+            ProfilingStrategy::PerfMap,
+            ProfilingStrategy::JitDump,
+        ] {
+            assert_eq!(strategy.to_string().parse(), Ok(*strategy));
+        }
+        assert!("vtune".parse::<ProfilingStrategy>().is_err());
+    }
+}
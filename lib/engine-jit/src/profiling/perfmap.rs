@@ -0,0 +1,44 @@
+//! The perf map: a text file listing the address, size and name of
+//! the functions generated at runtime.
+//!
+//! See <https://github.com/torvalds/linux/blob/master/tools/perf/Documentation/jit-interface.txt>.
+
+use super::ProfilingAgent;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::Mutex;
+
+lazy_static::lazy_static! {
+    // The map is per process, shared by all the engines.
+    static ref PERF_MAP: Mutex<Option<File>> = Mutex::new(None);
+}
+
+pub struct PerfMapAgent;
+
+impl PerfMapAgent {
+    pub fn new() -> Result<Self, String> {
+        let mut perf_map = PERF_MAP.lock().unwrap();
+        if perf_map.is_none() {
+            let path = format!("/tmp/perf-{}.map", std::process::id());
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .map_err(|e| format!("failed to open `{}`: {}", path, e))?;
+            *perf_map = Some(file);
+        }
+        Ok(Self)
+    }
+}
+
+impl ProfilingAgent for PerfMapAgent {
+    fn register_function(&self, name: &str, address: *const u8, size: usize) {
+        if let Some(file) = PERF_MAP.lock().unwrap().as_mut() {
+            // A line is written at once, so `perf` never reads half of it.
+            let line = format!("{:x} {:x} {}\n", address as usize, size, name);
+            if let Err(e) = file.write_all(line.as_bytes()) {
+                tracing::warn!("Failed to write to the perf map: {}", e);
+            }
+        }
+    }
+}
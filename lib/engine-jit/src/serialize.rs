@@ -42,4 +42,7 @@ pub struct SerializableModule {
     pub compilation: SerializableCompilation,
     pub compile_info: CompileModuleInfo,
     pub data_initializers: Box<[OwnedDataInitializer]>,
+    // The original wasm, if the engine embeds it.
+    #[serde(with = "serde_bytes")]
+    pub wasm: Option<Vec<u8>>,
 }
@@ -1,6 +1,7 @@
 //! Module for Dummy unwind registry.
 
 use wasmer_compiler::CompiledFunctionUnwindInfo;
+use wasmer_engine::FunctionExtent;
 
 /// Represents a registry of function unwind information when the host system
 /// support any one in specific.
@@ -25,7 +26,11 @@ impl DummyUnwindRegistry {
     }
 
     /// Publishes all registered functions.
-    pub fn publish(&mut self, eh_frame: Option<&[u8]>) -> Result<(), String> {
+    pub fn publish(
+        &mut self,
+        _eh_frame: Option<&[u8]>,
+        _frame_pointer_functions: &[FunctionExtent],
+    ) -> Result<(), String> {
         // Do nothing
         Ok(())
     }
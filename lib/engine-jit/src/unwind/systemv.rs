@@ -2,12 +2,21 @@
 // Attributions: https://github.com/wasmerio/wasmer/blob/master/ATTRIBUTIONS.md
 
 //! Module for System V ABI unwind registry.
+use gimli::write::{
+    Address, CallFrameInstruction, CommonInformationEntry, EhFrame, EndianVec,
+    FrameDescriptionEntry, FrameTable,
+};
+use gimli::{Encoding, Format, LittleEndian, Register};
 use wasmer_compiler::CompiledFunctionUnwindInfo;
+use wasmer_engine::FunctionExtent;
 
 /// Represents a registry of function unwind information for System V ABI.
 pub struct UnwindRegistry {
     registrations: Vec<usize>,
     published: bool,
+    /// The `eh_frame` generated for the functions without unwind
+    /// information, kept alive while it's registered.
+    frame_pointer_eh_frame: Option<Vec<u8>>,
 }
 
 extern "C" {
@@ -22,6 +31,7 @@ impl UnwindRegistry {
         Self {
             registrations: Vec::new(),
             published: false,
+            frame_pointer_eh_frame: None,
         }
     }
 
@@ -41,7 +51,14 @@ impl UnwindRegistry {
     }
 
     /// Publishes all registered functions.
-    pub fn publish(&mut self, eh_frame: Option<&[u8]>) -> Result<(), String> {
+    ///
+    /// The `frame_pointer_functions` don't have unwind information:
+    /// they are unwound by following the frame pointer.
+    pub fn publish(
+        &mut self,
+        eh_frame: Option<&[u8]>,
+        frame_pointer_functions: &[FunctionExtent],
+    ) -> Result<(), String> {
         if self.published {
             return Err("unwind registry has already been published".to_string());
         }
@@ -52,6 +69,16 @@ impl UnwindRegistry {
             }
         }
 
+        if !frame_pointer_functions.is_empty() {
+            if let Some(eh_frame) = frame_pointer_eh_frame(frame_pointer_functions)? {
+                unsafe {
+                    self.register_frames(&eh_frame);
+                }
+                // The registered entries point into the buffer of the `eh_frame`.
+                self.frame_pointer_eh_frame = Some(eh_frame);
+            }
+        }
+
         self.published = true;
 
         Ok(())
@@ -95,6 +122,58 @@ impl UnwindRegistry {
     }
 }
 
+/// Generates an `eh_frame` describing functions whose frame is set
+/// up by the usual prologue, which saves the frame pointer and the
+/// return address on top of the frame. Nothing is generated if the
+/// architecture isn't supported.
+///
+/// The unwind rules hold after the prologue and before the epilogue,
+/// which covers every call site and trap of the functions.
+fn frame_pointer_eh_frame(functions: &[FunctionExtent]) -> Result<Option<Vec<u8>>, String> {
+    let (code_alignment_factor, frame_pointer, return_address) = if cfg!(target_arch = "x86_64") {
+        (1, gimli::X86_64::RBP, gimli::X86_64::RA)
+    } else if cfg!(target_arch = "aarch64") {
+        // `x29` and `x30` (the link register).
+        (4, Register(29), Register(30))
+    } else {
+        return Ok(None);
+    };
+
+    let encoding = Encoding {
+        format: Format::Dwarf32,
+        version: 1,
+        address_size: std::mem::size_of::<usize>() as u8,
+    };
+    let mut cie = CommonInformationEntry::new(encoding, code_alignment_factor, -8, return_address);
+    cie.add_instruction(CallFrameInstruction::Cfa(frame_pointer, 16));
+    cie.add_instruction(CallFrameInstruction::Offset(frame_pointer, -16));
+    cie.add_instruction(CallFrameInstruction::Offset(return_address, -8));
+
+    let mut table = FrameTable::default();
+    let cie_id = table.add_cie(cie);
+    for function in functions {
+        table.add_fde(
+            cie_id,
+            FrameDescriptionEntry::new(
+                Address::Constant(*function.ptr as *const u8 as u64),
+                function.length as u32,
+            ),
+        );
+    }
+
+    let mut eh_frame = EhFrame(EndianVec::new(LittleEndian));
+    table.write_eh_frame(&mut eh_frame).map_err(|e| {
+        format!(
+            "failed to write the frame pointer unwind information: {}",
+            e
+        )
+    })?;
+    let mut eh_frame = eh_frame.0.into_vec();
+    // libgcc walks the entries until one of length 0.
+    eh_frame.extend(&[0, 0, 0, 0]);
+    Ok(Some(eh_frame))
+}
+
 impl Drop for UnwindRegistry {
     fn drop(&mut self) {
         if self.published {
@@ -114,3 +193,47 @@ impl Drop for UnwindRegistry {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gimli::{BaseAddresses, CfaRule, RegisterRule, UninitializedUnwindContext, UnwindSection};
+    use wasmer_vm::{FunctionBodyPtr, VMFunctionBody};
+
+    #[test]
+    #[cfg(target_arch = "x86_64")]
+    fn frame_pointer_unwind_rules() {
+        let functions = [(0x1000, 0x20), (0x1040, 0x10)]
+            .iter()
+            .map(|(address, length)| FunctionExtent {
+                ptr: FunctionBodyPtr(*address as *const VMFunctionBody),
+                length: *length,
+            })
+            .collect::<Vec<_>>();
+        let bytes = frame_pointer_eh_frame(&functions).unwrap().unwrap();
+        let eh_frame = gimli::EhFrame::new(&bytes, LittleEndian);
+        let bases = BaseAddresses::default();
+        let mut ctx = UninitializedUnwindContext::new();
+
+        for address in &[0x1000, 0x101f, 0x1048] {
+            let fde = eh_frame
+                .fde_for_address(&bases, *address, gimli::EhFrame::cie_from_offset)
+                .unwrap();
+            let row = fde
+                .unwind_info_for_address(&eh_frame, &bases, &mut ctx, *address)
+                .unwrap();
+            assert_eq!(
+                row.cfa(),
+                &CfaRule::RegisterAndOffset {
+                    register: gimli::X86_64::RBP,
+                    offset: 16
+                }
+            );
+            assert_eq!(row.register(gimli::X86_64::RBP), RegisterRule::Offset(-16));
+            assert_eq!(row.register(gimli::X86_64::RA), RegisterRule::Offset(-8));
+        }
+        assert!(eh_frame
+            .fde_for_address(&bases, 0x1030, gimli::EhFrame::cie_from_offset)
+            .is_err());
+    }
+}
@@ -4,6 +4,7 @@
 //! Module for Windows x64 ABI unwind registry.
 use std::collections::HashMap;
 use wasmer_compiler::CompiledFunctionUnwindInfo;
+use wasmer_engine::FunctionExtent;
 use winapi::um::winnt;
 
 /// Represents a registry of function unwind information for Windows x64 ABI.
@@ -60,7 +61,15 @@ impl UnwindRegistry {
     }
 
     /// Publishes all registered functions.
-    pub fn publish(&mut self, _eh_frame: Option<&[u8]>) -> Result<(), String> {
+    ///
+    /// The functions without unwind information can't be unwound by
+    /// following the frame pointer on Windows, so the
+    /// `frame_pointer_functions` are ignored.
+    pub fn publish(
+        &mut self,
+        _eh_frame: Option<&[u8]>,
+        _frame_pointer_functions: &[FunctionExtent],
+    ) -> Result<(), String> {
         if self.published {
             return Err("unwind registry has already been published".to_string());
         }
@@ -0,0 +1,452 @@
+//! Checks of the artifacts deserialized from untrusted sources, see
+//! [`JITArtifact::deserialize_untrusted`].
+//!
+//! The engine links and instantiates an artifact by indexing into its
+//! metadata and by patching its code at the offsets of its
+//! relocations, so all of them are checked to be in bounds first.
+//!
+//! [`JITArtifact::deserialize_untrusted`]: crate::JITArtifact::deserialize_untrusted
+
+use crate::link::relocation_size;
+use crate::serialize::SerializableModule;
+use gimli::UnwindSection;
+use std::collections::HashSet;
+use std::convert::TryInto;
+use wasmer_compiler::{
+    CompileModuleInfo, FunctionBody, JumpTable, JumpTableOffsets, Relocation, RelocationTarget,
+};
+use wasmer_types::entity::{EntityRef, PrimaryMap};
+use wasmer_types::{ExportIndex, GlobalInit, ImportIndex, LocalFunctionIndex};
+
+/// Checks that `index` is lower than `len`.
+fn check_index<I: EntityRef>(index: I, len: usize, kind: &str) -> Result<(), String> {
+    if index.index() < len {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} index {} is out of bounds (there are {})",
+            kind,
+            index.index(),
+            len
+        ))
+    }
+}
+
+/// Checks that there is one `kind` for each of the `expected` entities.
+fn check_len(len: usize, expected: usize, kind: &str) -> Result<(), String> {
+    if len == expected {
+        Ok(())
+    } else {
+        Err(format!("expected {} {}, found {}", expected, kind, len))
+    }
+}
+
+/// Checks that all the indices and offsets of `serializable` are in
+/// bounds.
+pub fn validate(serializable: &SerializableModule) -> Result<(), String> {
+    validate_module(&serializable.compile_info)?;
+    let module = &serializable.compile_info.module;
+    for initializer in serializable.data_initializers.iter() {
+        let location = &initializer.location;
+        check_index(location.memory_index, module.memories.len(), "memory")?;
+        if let Some(base) = location.base {
+            check_index(base, module.globals.len(), "global")?;
+        }
+    }
+    validate_compilation(serializable)
+}
+
+fn validate_module(compile_info: &CompileModuleInfo) -> Result<(), String> {
+    let module = &compile_info.module;
+    let functions = module.functions.len();
+    let tables = module.tables.len();
+    let memories = module.memories.len();
+    let globals = module.globals.len();
+    if module.num_imported_functions > functions
+        || module.num_imported_tables > tables
+        || module.num_imported_memories > memories
+        || module.num_imported_globals > globals
+    {
+        return Err("there are more imported entities than entities".to_string());
+    }
+    check_len(compile_info.memory_styles.len(), memories, "memory styles")?;
+    check_len(
+        compile_info.memory_bounds_checks.len(),
+        memories,
+        "memory bounds checks",
+    )?;
+    check_len(compile_info.table_styles.len(), tables, "table styles")?;
+
+    for signature in module.functions.values() {
+        check_index(*signature, module.signatures.len(), "signature")?;
+    }
+    for import in module.imports.values() {
+        match *import {
+            ImportIndex::Function(index) => {
+                check_index(index, module.num_imported_functions, "imported function")?
+            }
+            ImportIndex::Table(index) => {
+                check_index(index, module.num_imported_tables, "imported table")?
+            }
+            ImportIndex::Memory(index) => {
+                check_index(index, module.num_imported_memories, "imported memory")?
+            }
+            ImportIndex::Global(index) => {
+                check_index(index, module.num_imported_globals, "imported global")?
+            }
+        }
+    }
+    for export in module.exports.values() {
+        match *export {
+            ExportIndex::Function(index) => check_index(index, functions, "function")?,
+            ExportIndex::Table(index) => check_index(index, tables, "table")?,
+            ExportIndex::Memory(index) => check_index(index, memories, "memory")?,
+            ExportIndex::Global(index) => check_index(index, globals, "global")?,
+        }
+    }
+    if let Some(start) = module.start_function {
+        check_index(start, functions, "function")?;
+    }
+    for initializer in &module.table_initializers {
+        check_index(initializer.table_index, tables, "table")?;
+        if let Some(base) = initializer.base {
+            check_index(base, globals, "global")?;
+        }
+        for function in initializer.elements.iter() {
+            check_index(*function, functions, "function")?;
+        }
+    }
+    for elements in module.passive_elements.values() {
+        for function in elements.iter() {
+            check_index(*function, functions, "function")?;
+        }
+    }
+    check_len(
+        module.global_initializers.len(),
+        globals - module.num_imported_globals,
+        "global initializers",
+    )?;
+    for initializer in module.global_initializers.values() {
+        match *initializer {
+            GlobalInit::GetGlobal(index) => check_index(index, globals, "global")?,
+            GlobalInit::RefFunc(index) => check_index(index, functions, "function")?,
+            _ => {}
+        }
+    }
+    for section in module.custom_sections.values() {
+        check_index(
+            *section,
+            module.custom_sections_data.len(),
+            "custom section",
+        )?;
+    }
+    Ok(())
+}
+
+fn validate_compilation(serializable: &SerializableModule) -> Result<(), String> {
+    let module = &serializable.compile_info.module;
+    let compilation = &serializable.compilation;
+    let local_functions = module.functions.len() - module.num_imported_functions;
+    check_len(
+        compilation.function_bodies.len(),
+        local_functions,
+        "function bodies",
+    )?;
+    check_len(
+        compilation.function_relocations.len(),
+        local_functions,
+        "function relocations",
+    )?;
+    check_len(
+        compilation.function_jt_offsets.len(),
+        local_functions,
+        "function jump tables",
+    )?;
+    check_len(
+        compilation.function_frame_info.len(),
+        local_functions,
+        "function frame infos",
+    )?;
+    check_len(
+        compilation.function_call_trampolines.len(),
+        module.signatures.len(),
+        "function call trampolines",
+    )?;
+    check_len(
+        compilation.dynamic_function_trampolines.len(),
+        module.num_imported_functions,
+        "dynamic function trampolines",
+    )?;
+    check_len(
+        compilation.custom_section_relocations.len(),
+        compilation.custom_sections.len(),
+        "custom section relocations",
+    )?;
+
+    for (index, offsets) in compilation.function_jt_offsets.iter() {
+        let body_len = compilation.function_bodies[index].body.len();
+        if offsets.values().any(|offset| *offset as usize > body_len) {
+            return Err(format!(
+                "a jump table of the function {} is out of its body",
+                index.index()
+            ));
+        }
+    }
+    let check_relocations = |relocations: &[Relocation], body_len: usize, kind: &str| {
+        for relocation in relocations {
+            validate_relocation(
+                relocation,
+                body_len,
+                &compilation.function_bodies,
+                &compilation.function_jt_offsets,
+                compilation.custom_sections.len(),
+            )
+            .map_err(|message| format!("invalid relocation of a {}: {}", kind, message))?;
+        }
+        Ok::<_, String>(())
+    };
+    for (index, relocations) in compilation.function_relocations.iter() {
+        let body_len = compilation.function_bodies[index].body.len();
+        check_relocations(relocations, body_len, "function")?;
+    }
+    for (index, relocations) in compilation.custom_section_relocations.iter() {
+        let body_len = compilation.custom_sections[index].bytes.len();
+        check_relocations(relocations, body_len, "custom section")?;
+    }
+
+    if let Some(debug) = &compilation.debug {
+        check_index(
+            debug.eh_frame,
+            compilation.custom_sections.len(),
+            "custom section",
+        )?;
+        validate_eh_frame(
+            compilation.custom_sections[debug.eh_frame].bytes.as_slice(),
+            &compilation.custom_section_relocations[debug.eh_frame],
+        )
+        .map_err(|message| format!("invalid `eh_frame`: {}", message))?;
+    }
+    Ok(())
+}
+
+fn validate_relocation(
+    relocation: &Relocation,
+    body_len: usize,
+    function_bodies: &PrimaryMap<LocalFunctionIndex, FunctionBody>,
+    jt_offsets: &PrimaryMap<LocalFunctionIndex, JumpTableOffsets>,
+    custom_sections: usize,
+) -> Result<(), String> {
+    let size = relocation_size(relocation.kind).ok_or_else(|| {
+        format!(
+            "the relocation kind {} isn't supported in this architecture",
+            relocation.kind
+        )
+    })?;
+    if relocation.offset as usize + size > body_len {
+        return Err(format!(
+            "the offset {} is out of the body",
+            relocation.offset
+        ));
+    }
+    match relocation.reloc_target {
+        RelocationTarget::LocalFunc(index) => {
+            check_index(index, function_bodies.len(), "local function")
+        }
+        RelocationTarget::LibCall(_) => Ok(()),
+        RelocationTarget::CustomSection(index) => {
+            check_index(index, custom_sections, "custom section")
+        }
+        RelocationTarget::JumpTable(index, jt) => {
+            check_index(index, function_bodies.len(), "local function")?;
+            match jt_offsets[index].get(JumpTable::new(jt.index())) {
+                Some(_) => Ok(()),
+                None => Err(format!(
+                    "the jump table {} of the function {} doesn't exist",
+                    jt.index(),
+                    index.index()
+                )),
+            }
+        }
+    }
+}
+
+/// Checks that `eh_frame` is a sequence of well-formed CIEs and FDEs
+/// terminated by a zero length, as the unwinders walk it, and that the
+/// relocations only patch the initial locations of its FDEs.
+fn validate_eh_frame(eh_frame: &[u8], relocations: &[Relocation]) -> Result<(), String> {
+    let read_u32 = |offset: usize| {
+        eh_frame
+            .get(offset..offset + 4)
+            .map(|bytes| u32::from_ne_bytes(bytes.try_into().unwrap()))
+    };
+    let mut fde_initial_locations = HashSet::new();
+    let mut offset = 0;
+    loop {
+        let length = read_u32(offset).ok_or("it isn't terminated by a zero length")?;
+        if length == 0 {
+            break;
+        }
+        // A length of `0xffffffff` introduces a 64-bit length, which
+        // the compilers don't emit.
+        if length == u32::MAX {
+            return Err("64-bit lengths aren't supported".to_string());
+        }
+        // The CIE pointer of the FDEs is never 0, unlike the id of the CIEs.
+        if read_u32(offset + 4).ok_or("an entry is truncated")? != 0 {
+            fde_initial_locations.insert(offset + 8);
+        }
+        offset = offset.saturating_add(4 + length as usize);
+    }
+
+    let section = gimli::EhFrame::new(&eh_frame[..offset], gimli::NativeEndian);
+    let bases = gimli::BaseAddresses::default().set_eh_frame(0).set_text(0);
+    let mut entries = section.entries(&bases);
+    while let Some(entry) = entries.next().map_err(|error| error.to_string())? {
+        if let gimli::CieOrFde::Fde(partial) = entry {
+            partial
+                .parse(gimli::EhFrame::cie_from_offset)
+                .map_err(|error| error.to_string())?;
+        }
+    }
+
+    match relocations
+        .iter()
+        .find(|relocation| !fde_initial_locations.contains(&(relocation.offset as usize)))
+    {
+        Some(relocation) => Err(format!(
+            "the relocation at {} doesn't patch the initial location of an FDE",
+            relocation.offset
+        )),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serialize::SerializableCompilation;
+    use std::sync::Arc;
+    use wasmer_compiler::{Features, RelocationKind};
+    use wasmer_engine::SerializableFunctionFrameInfo;
+    use wasmer_types::{FunctionIndex, FunctionType, SignatureIndex};
+    use wasmer_vm::ModuleInfo;
+
+    /// A module with a local function whose body is 16 bytes long.
+    fn module(relocations: Vec<Relocation>) -> SerializableModule {
+        let mut module = ModuleInfo::new();
+        let signature = module.signatures.push(FunctionType::new(vec![], vec![]));
+        module.functions.push(signature);
+        module.exports.insert(
+            "f".to_string(),
+            ExportIndex::Function(FunctionIndex::new(0)),
+        );
+        let body = FunctionBody {
+            body: vec![0; 16],
+            unwind_info: None,
+        };
+        SerializableModule {
+            compilation: SerializableCompilation {
+                function_bodies: vec![body.clone()].into_iter().collect(),
+                function_relocations: vec![relocations].into_iter().collect(),
+                function_jt_offsets: vec![JumpTableOffsets::new()].into_iter().collect(),
+                function_frame_info: vec![SerializableFunctionFrameInfo::Processed(
+                    Default::default(),
+                )]
+                .into_iter()
+                .collect(),
+                function_call_trampolines: vec![body].into_iter().collect(),
+                dynamic_function_trampolines: PrimaryMap::new(),
+                custom_sections: PrimaryMap::new(),
+                custom_section_relocations: PrimaryMap::new(),
+                debug: None,
+            },
+            compile_info: CompileModuleInfo {
+                features: Features::default(),
+                module: Arc::new(module),
+                memory_styles: PrimaryMap::new(),
+                memory_bounds_checks: PrimaryMap::new(),
+                table_styles: PrimaryMap::new(),
+            },
+            data_initializers: Box::new([]),
+            wasm: None,
+        }
+    }
+
+    #[test]
+    fn check_indices() {
+        assert!(validate(&module(vec![])).is_ok());
+
+        let mut serializable = module(vec![]);
+        Arc::get_mut(&mut serializable.compile_info.module)
+            .unwrap()
+            .start_function = Some(FunctionIndex::new(1));
+        assert!(validate(&serializable).is_err());
+
+        let mut serializable = module(vec![]);
+        Arc::get_mut(&mut serializable.compile_info.module)
+            .unwrap()
+            .functions[FunctionIndex::new(0)] = SignatureIndex::new(1);
+        assert!(validate(&serializable).is_err());
+
+        let mut serializable = module(vec![]);
+        serializable
+            .compilation
+            .function_call_trampolines
+            .push(FunctionBody {
+                body: vec![],
+                unwind_info: None,
+            });
+        assert!(validate(&serializable).is_err());
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn check_relocations() {
+        let relocation = |offset, reloc_target| Relocation {
+            kind: RelocationKind::Abs8,
+            reloc_target,
+            offset,
+            addend: 0,
+        };
+        let function = RelocationTarget::LocalFunc(LocalFunctionIndex::new(0));
+        assert!(validate(&module(vec![relocation(8, function)])).is_ok());
+        assert!(validate(&module(vec![relocation(9, function)])).is_err());
+        let missing_function = RelocationTarget::LocalFunc(LocalFunctionIndex::new(1));
+        assert!(validate(&module(vec![relocation(0, missing_function)])).is_err());
+        let missing_jump_table =
+            RelocationTarget::JumpTable(LocalFunctionIndex::new(0), JumpTable::new(0));
+        assert!(validate(&module(vec![relocation(0, missing_jump_table)])).is_err());
+    }
+
+    #[test]
+    fn check_eh_frame() {
+        // A CIE with the "zR" augmentation and an FDE with absolute
+        // pointers, followed by the terminator.
+        let mut eh_frame = vec![];
+        eh_frame.extend(&13u32.to_ne_bytes());
+        eh_frame.extend(&[0, 0, 0, 0, 1, b'z', b'R', 0, 1, 0x78, 16, 1, 0]);
+        let fde = eh_frame.len();
+        eh_frame.extend(&21u32.to_ne_bytes());
+        eh_frame.extend(&(fde as u32 + 4).to_ne_bytes());
+        eh_frame.extend(&[0; 17]);
+        eh_frame.extend(&[0; 4]);
+
+        let relocation = |offset: usize| Relocation {
+            kind: RelocationKind::X86PCRelRodata4,
+            reloc_target: RelocationTarget::LocalFunc(LocalFunctionIndex::new(0)),
+            offset: offset as u32,
+            addend: 0,
+        };
+        assert!(validate_eh_frame(&eh_frame, &[relocation(fde + 8)]).is_ok());
+        assert!(validate_eh_frame(&eh_frame, &[relocation(fde)]).is_err());
+        assert!(validate_eh_frame(&eh_frame[..eh_frame.len() - 4], &[]).is_err());
+
+        let mut bad_cie_pointer = eh_frame.clone();
+        bad_cie_pointer[fde + 4..fde + 8].copy_from_slice(&3u32.to_ne_bytes());
+        assert!(validate_eh_frame(&bad_cie_pointer, &[]).is_err());
+
+        let mut overrun = eh_frame;
+        overrun[fde..fde + 4].copy_from_slice(&1000u32.to_ne_bytes());
+        assert!(validate_eh_frame(&overrun, &[]).is_err());
+    }
+}
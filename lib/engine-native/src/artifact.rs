@@ -17,7 +17,7 @@ use tracing::trace;
 use wasmer_compiler::{CompileError, Features, OperatingSystem, Symbol, SymbolRegistry, Triple};
 #[cfg(feature = "compiler")]
 use wasmer_compiler::{
-    CompileModuleInfo, FunctionBodyData, ModuleEnvironment, ModuleTranslationState,
+    CompileFuel, CompileModuleInfo, FunctionBodyData, ModuleEnvironment, ModuleTranslationState,
 };
 use wasmer_engine::{Artifact, DeserializeError, InstantiationError, SerializeError};
 #[cfg(feature = "compiler")]
@@ -31,6 +31,8 @@ use wasmer_types::{
     FunctionIndex, LocalFunctionIndex, MemoryIndex, OwnedDataInitializer, SignatureIndex,
     TableIndex,
 };
+#[cfg(feature = "compiler")]
+use wasmer_vm::BoundsCheck;
 use wasmer_vm::{
     FunctionBodyPtr, MemoryStyle, ModuleInfo, TableStyle, VMFunctionBody, VMSharedSignatureIndex,
     VMTrampoline,
@@ -98,6 +100,7 @@ impl NativeArtifact {
     fn generate_metadata<'data>(
         data: &'data [u8],
         features: &Features,
+        compile_fuel: Option<u64>,
         tunables: &dyn Tunables,
     ) -> Result<
         (
@@ -108,14 +111,28 @@ impl NativeArtifact {
         ),
         CompileError,
     > {
+        let mut fuel = compile_fuel.map(CompileFuel::new);
+        if let Some(fuel) = fuel.as_mut() {
+            fuel.consume_module(data)?;
+        }
         let environ = ModuleEnvironment::new();
         let translation = environ.translate(data).map_err(CompileError::Wasm)?;
+        if let Some(fuel) = fuel.as_mut() {
+            fuel.consume_function_bodies(&translation.function_body_inputs)?;
+        }
         let memory_styles: PrimaryMap<MemoryIndex, MemoryStyle> = translation
             .module
             .memories
             .values()
             .map(|memory_type| tunables.memory_style(memory_type))
             .collect();
+        let memory_bounds_checks: PrimaryMap<MemoryIndex, BoundsCheck> = translation
+            .module
+            .memories
+            .values()
+            .zip(memory_styles.values())
+            .map(|(memory_type, style)| tunables.memory_bounds_check(memory_type, style))
+            .collect();
         let table_styles: PrimaryMap<TableIndex, TableStyle> = translation
             .module
             .tables
@@ -127,6 +144,7 @@ impl NativeArtifact {
             module: Arc::new(translation.module),
             features: features.clone(),
             memory_styles,
+            memory_bounds_checks,
             table_styles,
         };
         Ok((
@@ -148,13 +166,19 @@ impl NativeArtifact {
         let target = engine.target();
         let compiler = engine_inner.compiler()?;
         let (compile_info, function_body_inputs, data_initializers, module_translation) =
-            Self::generate_metadata(data, engine_inner.features(), tunables)?;
-
-        let data_initializers = data_initializers
+            Self::generate_metadata(
+                data,
+                engine_inner.features(),
+                engine_inner.compile_fuel(),
+                tunables,
+            )?;
+
+        let mut data_initializers = data_initializers
             .iter()
             .map(OwnedDataInitializer::new)
-            .collect::<Vec<_>>()
-            .into_boxed_slice();
+            .collect::<Vec<_>>();
+        compiler.transform_data_initializers(&mut data_initializers);
+        let data_initializers = data_initializers.into_boxed_slice();
 
         let target_triple = target.triple();
 
@@ -181,6 +205,11 @@ impl NativeArtifact {
             prefix: engine_inner.get_prefix(&data),
             data_initializers,
             function_body_lengths,
+            wasm: if engine_inner.embed_wasm() {
+                Some(data.to_vec())
+            } else {
+                None
+            },
         };
 
         let serialized_data = bincode::serialize(&metadata).map_err(to_compile_error)?;
@@ -444,6 +473,14 @@ impl NativeArtifact {
                 .map(|sig| engine_inner.signatures().register(sig))
                 .collect::<PrimaryMap<_, _>>()
         };
+        // The library is loaded as long as the engine.
+        for (index, trampoline) in finished_function_call_trampolines.iter() {
+            unsafe {
+                engine_inner
+                    .signatures()
+                    .register_trampoline(signatures[index], *trampoline)
+            };
+        }
 
         engine_inner.add_library(lib);
 
@@ -571,6 +608,10 @@ impl Artifact for NativeArtifact {
         // Do nothing for now
     }
 
+    fn wasm(&self) -> Option<&[u8]> {
+        self.metadata.wasm.as_deref()
+    }
+
     fn features(&self) -> &Features {
         &self.metadata.compile_info.features
     }
@@ -1,11 +1,20 @@
 use crate::NativeEngine;
+#[cfg(feature = "compiler")]
+use std::sync::Arc;
+#[cfg(feature = "compiler")]
+use wasmer_compiler::FunctionCounters;
 use wasmer_compiler::{CompilerConfig, Features, Target};
+use wasmer_engine::TunablesConfig;
 
 /// The Native builder
 pub struct Native {
     compiler_config: Option<Box<dyn CompilerConfig>>,
     target: Option<Target>,
     features: Option<Features>,
+    compile_fuel: Option<u64>,
+    tunables_config: TunablesConfig,
+    function_counters: bool,
+    embed_wasm: bool,
 }
 
 impl Native {
@@ -22,6 +31,10 @@ impl Native {
             compiler_config: Some(compiler_config),
             target: None,
             features: None,
+            compile_fuel: None,
+            tunables_config: TunablesConfig::default(),
+            function_counters: false,
+            embed_wasm: false,
         }
     }
 
@@ -31,6 +44,10 @@ impl Native {
             compiler_config: None,
             target: None,
             features: None,
+            compile_fuel: None,
+            tunables_config: TunablesConfig::default(),
+            function_counters: false,
+            embed_wasm: false,
         }
     }
 
@@ -46,18 +63,70 @@ impl Native {
         self
     }
 
+    /// Limit the work of compiling a module to `limit` units, so a
+    /// hostile module can't make the compilation take an unbounded
+    /// amount of time. Compiling a module exceeding it fails with
+    /// [`CompileError::FuelExhausted`].
+    ///
+    /// See [`CompileFuel`] for how the work is measured.
+    ///
+    /// [`CompileError::FuelExhausted`]: wasmer_compiler::CompileError::FuelExhausted
+    /// [`CompileFuel`]: wasmer_compiler::CompileFuel
+    pub fn compile_fuel(mut self, limit: u64) -> Self {
+        self.compile_fuel = Some(limit);
+        self
+    }
+
+    /// Configure the [`TunablesConfig`] of the stores created for the
+    /// engine with `Store::new`, to tune the layout of their memories.
+    pub fn tunables_config(mut self, config: TunablesConfig) -> Self {
+        self.tunables_config = config;
+        self
+    }
+
+    /// Count the calls of each function of the compiled modules, read
+    /// with `Instance::function_counts`. It's cheap enough to find the
+    /// dead code and the hot paths in production, where no profiler can
+    /// be deployed.
+    pub fn function_counters(mut self, enable: bool) -> Self {
+        self.function_counters = enable;
+        self
+    }
+
+    /// Embed the original wasm in the compiled artifacts, so they can
+    /// be recompiled for another engine or target, like `wasmer
+    /// convert` does, at the cost of their size.
+    pub fn embed_wasm(mut self, enable: bool) -> Self {
+        self.embed_wasm = enable;
+        self
+    }
+
     /// Build the `NativeEngine` for this configuration
     pub fn engine(self) -> NativeEngine {
         if let Some(_compiler_config) = self.compiler_config {
             #[cfg(feature = "compiler")]
             {
-                let compiler_config = _compiler_config;
+                let mut compiler_config = _compiler_config;
                 let target = self.target.unwrap_or_default();
-                let features = self
+                let mut features = self
                     .features
                     .unwrap_or_else(|| compiler_config.default_features_for_target(&target));
+                if compiler_config.deterministic() {
+                    // The threads make the execution non-deterministic.
+                    features.threads(false);
+                }
+                if self.function_counters {
+                    compiler_config.push_middleware(Arc::new(FunctionCounters::new()));
+                }
                 let compiler = compiler_config.compiler();
-                NativeEngine::new(compiler, target, features)
+                let engine = NativeEngine::new(compiler, target, features);
+                {
+                    let mut inner = engine.inner_mut();
+                    inner.set_compile_fuel(self.compile_fuel);
+                    inner.set_embed_wasm(self.embed_wasm);
+                    inner.set_tunables_config(self.tunables_config);
+                }
+                engine
             }
 
             #[cfg(not(feature = "compiler"))]
@@ -65,7 +134,9 @@ impl Native {
                 unreachable!("Cannot call `NativeEngine::new` without the `compiler` feature")
             }
         } else {
-            NativeEngine::headless()
+            let engine = NativeEngine::headless();
+            engine.inner_mut().set_tunables_config(self.tunables_config);
+            engine
         }
     }
 }
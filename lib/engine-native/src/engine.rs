@@ -7,12 +7,12 @@ use std::sync::Arc;
 use std::sync::Mutex;
 use wasmer_compiler::{CompileError, Target};
 #[cfg(feature = "compiler")]
-use wasmer_compiler::{Compiler, Triple};
-use wasmer_engine::{Artifact, DeserializeError, Engine, EngineId, Tunables};
+use wasmer_compiler::{CompileFuel, Compiler, Triple};
+use wasmer_engine::{Artifact, DeserializeError, Engine, EngineId, Tunables, TunablesConfig};
 #[cfg(feature = "compiler")]
 use wasmer_types::Features;
 use wasmer_types::FunctionType;
-use wasmer_vm::{SignatureRegistry, VMSharedSignatureIndex};
+use wasmer_vm::{SignatureRegistry, VMSharedSignatureIndex, VMTrampoline};
 #[cfg(feature = "compiler")]
 use which::which;
 
@@ -52,6 +52,9 @@ impl NativeEngine {
                 signatures: SignatureRegistry::new(),
                 prefixer: None,
                 features,
+                compile_fuel: None,
+                tunables_config: TunablesConfig::default(),
+                embed_wasm: false,
                 is_cross_compiling,
                 linker,
                 libraries: vec![],
@@ -81,6 +84,11 @@ impl NativeEngine {
                 compiler: None,
                 #[cfg(feature = "compiler")]
                 features: Features::default(),
+                #[cfg(feature = "compiler")]
+                compile_fuel: None,
+                tunables_config: TunablesConfig::default(),
+                #[cfg(feature = "compiler")]
+                embed_wasm: false,
                 signatures: SignatureRegistry::new(),
                 prefixer: None,
                 is_cross_compiling: false,
@@ -137,6 +145,25 @@ impl Engine for NativeEngine {
         compiler.signatures().lookup(sig)
     }
 
+    /// Lookup a call trampoline
+    fn lookup_call_trampoline(&self, sig: VMSharedSignatureIndex) -> Option<VMTrampoline> {
+        let compiler = self.inner();
+        compiler.signatures().lookup_trampoline(sig)
+    }
+
+    /// Whether the compiled code is deterministic
+    #[cfg(feature = "compiler")]
+    fn deterministic(&self) -> bool {
+        self.inner()
+            .compiler()
+            .map_or(false, |compiler| compiler.deterministic())
+    }
+
+    /// The configuration of the default tunables of the stores
+    fn tunables_config(&self) -> TunablesConfig {
+        self.inner().tunables_config().clone()
+    }
+
     /// Validates a WebAssembly module
     fn validate(&self, binary: &[u8]) -> Result<(), CompileError> {
         self.inner().validate(binary)
@@ -217,6 +244,14 @@ pub struct NativeEngineInner {
     /// The WebAssembly features to use
     #[cfg(feature = "compiler")]
     features: Features,
+    /// The units of work a module compilation can do.
+    #[cfg(feature = "compiler")]
+    compile_fuel: Option<u64>,
+    /// The configuration of the default tunables of the stores.
+    tunables_config: TunablesConfig,
+    /// Whether the original wasm is embedded in the artifacts.
+    #[cfg(feature = "compiler")]
+    embed_wasm: bool,
     /// The signature registry is used mainly to operate with trampolines
     /// performantly.
     signatures: SignatureRegistry,
@@ -259,9 +294,44 @@ impl NativeEngineInner {
         &self.features
     }
 
+    /// The units of work a module compilation can do, see
+    /// [`CompileFuel`](wasmer_compiler::CompileFuel).
+    #[cfg(feature = "compiler")]
+    pub fn compile_fuel(&self) -> Option<u64> {
+        self.compile_fuel
+    }
+
+    #[cfg(feature = "compiler")]
+    pub(crate) fn set_compile_fuel(&mut self, limit: Option<u64>) {
+        self.compile_fuel = limit;
+    }
+
+    /// The configuration of the default tunables of the stores.
+    pub fn tunables_config(&self) -> &TunablesConfig {
+        &self.tunables_config
+    }
+
+    pub(crate) fn set_tunables_config(&mut self, config: TunablesConfig) {
+        self.tunables_config = config;
+    }
+
+    /// Whether the original wasm is embedded in the artifacts.
+    #[cfg(feature = "compiler")]
+    pub fn embed_wasm(&self) -> bool {
+        self.embed_wasm
+    }
+
+    #[cfg(feature = "compiler")]
+    pub(crate) fn set_embed_wasm(&mut self, enable: bool) {
+        self.embed_wasm = enable;
+    }
+
     /// Validate the module
     #[cfg(feature = "compiler")]
     pub fn validate<'data>(&self, data: &'data [u8]) -> Result<(), CompileError> {
+        if let Some(limit) = self.compile_fuel {
+            CompileFuel::new(limit).consume_module(data)?;
+        }
         self.compiler()?.validate_module(self.features(), data)
     }
 
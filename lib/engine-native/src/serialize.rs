@@ -11,6 +11,8 @@ pub struct ModuleMetadata {
     pub data_initializers: Box<[OwnedDataInitializer]>,
     // The function body lengths (used to find function by address)
     pub function_body_lengths: PrimaryMap<LocalFunctionIndex, u64>,
+    // The original wasm, if the engine embeds it.
+    pub wasm: Option<Vec<u8>>,
 }
 
 pub struct ModuleMetadataSymbolRegistry<'a> {
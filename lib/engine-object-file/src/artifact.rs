@@ -10,7 +10,7 @@ use std::sync::Arc;
 use wasmer_compiler::{CompileError, Features, OperatingSystem, SymbolRegistry, Triple};
 #[cfg(feature = "compiler")]
 use wasmer_compiler::{
-    CompileModuleInfo, FunctionBodyData, ModuleEnvironment, ModuleTranslationState,
+    CompileFuel, CompileModuleInfo, FunctionBodyData, ModuleEnvironment, ModuleTranslationState,
 };
 use wasmer_engine::{Artifact, DeserializeError, InstantiationError, SerializeError};
 #[cfg(feature = "compiler")]
@@ -25,6 +25,8 @@ use wasmer_types::{
     FunctionIndex, LocalFunctionIndex, MemoryIndex, OwnedDataInitializer, SignatureIndex,
     TableIndex,
 };
+#[cfg(feature = "compiler")]
+use wasmer_vm::BoundsCheck;
 use wasmer_vm::{
     FunctionBodyPtr, MemoryStyle, ModuleInfo, TableStyle, VMSharedSignatureIndex, VMTrampoline,
 };
@@ -96,6 +98,7 @@ impl ObjectFileArtifact {
     fn generate_metadata<'data>(
         data: &'data [u8],
         features: &Features,
+        compile_fuel: Option<u64>,
         tunables: &dyn Tunables,
     ) -> Result<
         (
@@ -106,14 +109,28 @@ impl ObjectFileArtifact {
         ),
         CompileError,
     > {
+        let mut fuel = compile_fuel.map(CompileFuel::new);
+        if let Some(fuel) = fuel.as_mut() {
+            fuel.consume_module(data)?;
+        }
         let environ = ModuleEnvironment::new();
         let translation = environ.translate(data).map_err(CompileError::Wasm)?;
+        if let Some(fuel) = fuel.as_mut() {
+            fuel.consume_function_bodies(&translation.function_body_inputs)?;
+        }
         let memory_styles: PrimaryMap<MemoryIndex, MemoryStyle> = translation
             .module
             .memories
             .values()
             .map(|memory_type| tunables.memory_style(memory_type))
             .collect();
+        let memory_bounds_checks: PrimaryMap<MemoryIndex, BoundsCheck> = translation
+            .module
+            .memories
+            .values()
+            .zip(memory_styles.values())
+            .map(|(memory_type, style)| tunables.memory_bounds_check(memory_type, style))
+            .collect();
         let table_styles: PrimaryMap<TableIndex, TableStyle> = translation
             .module
             .tables
@@ -124,6 +141,7 @@ impl ObjectFileArtifact {
             module: Arc::new(translation.module),
             features: features.clone(),
             memory_styles,
+            memory_bounds_checks,
             table_styles,
         };
 
@@ -147,13 +165,19 @@ impl ObjectFileArtifact {
         let target = engine.target();
         let compiler = engine_inner.compiler()?;
         let (compile_info, function_body_inputs, data_initializers, module_translation) =
-            Self::generate_metadata(data, engine_inner.features(), tunables)?;
+            Self::generate_metadata(
+                data,
+                engine_inner.features(),
+                engine_inner.compile_fuel(),
+                tunables,
+            )?;
 
-        let data_initializers = data_initializers
+        let mut data_initializers = data_initializers
             .iter()
             .map(OwnedDataInitializer::new)
-            .collect::<Vec<_>>()
-            .into_boxed_slice();
+            .collect::<Vec<_>>();
+        compiler.transform_data_initializers(&mut data_initializers);
+        let data_initializers = data_initializers.into_boxed_slice();
 
         let target_triple = target.triple();
 
@@ -244,6 +268,15 @@ impl ObjectFileArtifact {
         }
     }
 
+    /// Get the default extension of a static library containing this
+    /// artifact.
+    pub fn get_default_static_library_extension(triple: &Triple) -> &'static str {
+        match triple.operating_system {
+            OperatingSystem::Windows => "lib",
+            _ => "a",
+        }
+    }
+
     /// Construct a `ObjectFileArtifact` from component parts.
     pub fn from_parts_crosscompiled(
         engine_inner: &mut ObjectFileEngineInner,
@@ -356,6 +389,13 @@ impl ObjectFileArtifact {
             finished_function_call_trampolines.push(trampoline);
             // TODO: we can read back the length here if we serialize it. This will improve debug output.
         }
+        // The object file is linked in the executable, so the trampolines
+        // live as long as the engine.
+        for (index, trampoline) in finished_function_call_trampolines.iter() {
+            let func_type = &metadata.compile_info.module.signatures[index];
+            signature_registry
+                .register_trampoline(signature_registry.register(func_type), *trampoline);
+        }
 
         // read dynamic function trampolines in order now...
         let mut finished_dynamic_function_trampolines = PrimaryMap::new();
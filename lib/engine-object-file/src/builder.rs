@@ -1,11 +1,19 @@
 use crate::ObjectFileEngine;
+#[cfg(feature = "compiler")]
+use std::sync::Arc;
+#[cfg(feature = "compiler")]
+use wasmer_compiler::FunctionCounters;
 use wasmer_compiler::{CompilerConfig, Features, Target};
+use wasmer_engine::TunablesConfig;
 
 /// The ObjectFile builder
 pub struct ObjectFile {
     compiler_config: Option<Box<dyn CompilerConfig>>,
     target: Option<Target>,
     features: Option<Features>,
+    compile_fuel: Option<u64>,
+    tunables_config: TunablesConfig,
+    function_counters: bool,
 }
 
 impl ObjectFile {
@@ -22,6 +30,9 @@ impl ObjectFile {
             compiler_config: Some(compiler_config),
             target: None,
             features: None,
+            compile_fuel: None,
+            tunables_config: TunablesConfig::default(),
+            function_counters: false,
         }
     }
 
@@ -31,6 +42,9 @@ impl ObjectFile {
             compiler_config: None,
             target: None,
             features: None,
+            compile_fuel: None,
+            tunables_config: TunablesConfig::default(),
+            function_counters: false,
         }
     }
 
@@ -46,18 +60,61 @@ impl ObjectFile {
         self
     }
 
+    /// Limit the work of compiling a module to `limit` units, so a
+    /// hostile module can't make the compilation take an unbounded
+    /// amount of time. Compiling a module exceeding it fails with
+    /// [`CompileError::FuelExhausted`].
+    ///
+    /// See [`CompileFuel`] for how the work is measured.
+    ///
+    /// [`CompileError::FuelExhausted`]: wasmer_compiler::CompileError::FuelExhausted
+    /// [`CompileFuel`]: wasmer_compiler::CompileFuel
+    pub fn compile_fuel(mut self, limit: u64) -> Self {
+        self.compile_fuel = Some(limit);
+        self
+    }
+
+    /// Configure the [`TunablesConfig`] of the stores created for the
+    /// engine with `Store::new`, to tune the layout of their memories.
+    pub fn tunables_config(mut self, config: TunablesConfig) -> Self {
+        self.tunables_config = config;
+        self
+    }
+
+    /// Count the calls of each function of the compiled modules, read
+    /// with `Instance::function_counts`. It's cheap enough to find the
+    /// dead code and the hot paths in production, where no profiler can
+    /// be deployed.
+    pub fn function_counters(mut self, enable: bool) -> Self {
+        self.function_counters = enable;
+        self
+    }
+
     /// Build the `ObjectFileEngine` for this configuration
     pub fn engine(self) -> ObjectFileEngine {
         if let Some(_compiler_config) = self.compiler_config {
             #[cfg(feature = "compiler")]
             {
-                let compiler_config = _compiler_config;
+                let mut compiler_config = _compiler_config;
                 let target = self.target.unwrap_or_default();
-                let features = self
+                let mut features = self
                     .features
                     .unwrap_or_else(|| compiler_config.default_features_for_target(&target));
+                if compiler_config.deterministic() {
+                    // The threads make the execution non-deterministic.
+                    features.threads(false);
+                }
+                if self.function_counters {
+                    compiler_config.push_middleware(Arc::new(FunctionCounters::new()));
+                }
                 let compiler = compiler_config.compiler();
-                ObjectFileEngine::new(compiler, target, features)
+                let engine = ObjectFileEngine::new(compiler, target, features);
+                {
+                    let mut inner = engine.inner_mut();
+                    inner.set_compile_fuel(self.compile_fuel);
+                    inner.set_tunables_config(self.tunables_config);
+                }
+                engine
             }
 
             #[cfg(not(feature = "compiler"))]
@@ -65,7 +122,9 @@ impl ObjectFile {
                 unreachable!("Cannot call `ObjectFileEngine::new` without the `compiler` feature")
             }
         } else {
-            ObjectFileEngine::headless()
+            let engine = ObjectFileEngine::headless();
+            engine.inner_mut().set_tunables_config(self.tunables_config);
+            engine
         }
     }
 }
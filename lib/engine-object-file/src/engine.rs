@@ -2,14 +2,14 @@ use crate::ObjectFileArtifact;
 use std::io::Read;
 use std::path::Path;
 use std::sync::{Arc, Mutex};
-#[cfg(feature = "compiler")]
-use wasmer_compiler::Compiler;
 use wasmer_compiler::{CompileError, Target};
-use wasmer_engine::{Artifact, DeserializeError, Engine, EngineId, Tunables};
+#[cfg(feature = "compiler")]
+use wasmer_compiler::{CompileFuel, Compiler};
+use wasmer_engine::{Artifact, DeserializeError, Engine, EngineId, Tunables, TunablesConfig};
 #[cfg(feature = "compiler")]
 use wasmer_types::Features;
 use wasmer_types::FunctionType;
-use wasmer_vm::{SignatureRegistry, VMSharedSignatureIndex};
+use wasmer_vm::{SignatureRegistry, VMSharedSignatureIndex, VMTrampoline};
 
 /// A WebAssembly `ObjectFile` Engine.
 #[derive(Clone)]
@@ -30,6 +30,8 @@ impl ObjectFileEngine {
                 signatures: SignatureRegistry::new(),
                 prefixer: None,
                 features,
+                compile_fuel: None,
+                tunables_config: TunablesConfig::default(),
             })),
             target: Arc::new(target),
             engine_id: EngineId::default(),
@@ -56,6 +58,9 @@ impl ObjectFileEngine {
                 compiler: None,
                 #[cfg(feature = "compiler")]
                 features: Features::default(),
+                #[cfg(feature = "compiler")]
+                compile_fuel: None,
+                tunables_config: TunablesConfig::default(),
                 signatures: SignatureRegistry::new(),
                 prefixer: None,
             })),
@@ -109,6 +114,25 @@ impl Engine for ObjectFileEngine {
         compiler.signatures().lookup(sig)
     }
 
+    /// Lookup a call trampoline
+    fn lookup_call_trampoline(&self, sig: VMSharedSignatureIndex) -> Option<VMTrampoline> {
+        let compiler = self.inner();
+        compiler.signatures().lookup_trampoline(sig)
+    }
+
+    /// Whether the compiled code is deterministic
+    #[cfg(feature = "compiler")]
+    fn deterministic(&self) -> bool {
+        self.inner()
+            .compiler()
+            .map_or(false, |compiler| compiler.deterministic())
+    }
+
+    /// The configuration of the default tunables of the stores
+    fn tunables_config(&self) -> TunablesConfig {
+        self.inner().tunables_config().clone()
+    }
+
     /// Validates a WebAssembly module
     fn validate(&self, binary: &[u8]) -> Result<(), CompileError> {
         self.inner().validate(binary)
@@ -172,6 +196,11 @@ pub struct ObjectFileEngineInner {
     /// The WebAssembly features to use
     #[cfg(feature = "compiler")]
     features: Features,
+    /// The units of work a module compilation can do.
+    #[cfg(feature = "compiler")]
+    compile_fuel: Option<u64>,
+    /// The configuration of the default tunables of the stores.
+    tunables_config: TunablesConfig,
     /// The signature registry is used mainly to operate with trampolines
     /// performantly.
     signatures: SignatureRegistry,
@@ -208,9 +237,33 @@ impl ObjectFileEngineInner {
         &self.features
     }
 
+    /// The units of work a module compilation can do, see
+    /// [`CompileFuel`](wasmer_compiler::CompileFuel).
+    #[cfg(feature = "compiler")]
+    pub fn compile_fuel(&self) -> Option<u64> {
+        self.compile_fuel
+    }
+
+    #[cfg(feature = "compiler")]
+    pub(crate) fn set_compile_fuel(&mut self, limit: Option<u64>) {
+        self.compile_fuel = limit;
+    }
+
+    /// The configuration of the default tunables of the stores.
+    pub fn tunables_config(&self) -> &TunablesConfig {
+        &self.tunables_config
+    }
+
+    pub(crate) fn set_tunables_config(&mut self, config: TunablesConfig) {
+        self.tunables_config = config;
+    }
+
     /// Validate the module
     #[cfg(feature = "compiler")]
     pub fn validate<'data>(&self, data: &'data [u8]) -> Result<(), CompileError> {
+        if let Some(limit) = self.compile_fuel {
+            CompileFuel::new(limit).consume_module(data)?;
+        }
         self.compiler()?.validate_module(self.features(), data)
     }
 
@@ -1,5 +1,7 @@
+use crate::metadata::{append_metadata, ArtifactMetadata};
 use crate::{
-    resolve_imports, InstantiationError, Resolver, RuntimeError, SerializeError, Tunables,
+    resolve_imports, DeserializeError, InstantiationError, Resolver, RuntimeError, SerializeError,
+    SourceMap, Tunables,
 };
 use std::any::Any;
 use std::fs;
@@ -39,6 +41,26 @@ pub trait Artifact: Send + Sync + Upcastable {
     /// This is required to ensure that any traps can be properly symbolicated.
     fn register_frame_info(&self);
 
+    /// Returns the source map attached to this `Artifact`, if any.
+    fn source_map(&self) -> Option<&Arc<SourceMap>> {
+        None
+    }
+
+    /// Attaches a source map to this `Artifact`, so the frames of the
+    /// traps can be resolved to the original source code.
+    ///
+    /// Returns `false` if the `Artifact` doesn't support source maps.
+    fn set_source_map(&mut self, _source_map: Arc<SourceMap>) -> bool {
+        false
+    }
+
+    /// Returns the original wasm of the module, if the engine embeds
+    /// it in its artifacts, so they can be recompiled for another
+    /// engine or target.
+    fn wasm(&self) -> Option<&[u8]> {
+        None
+    }
+
     /// Returns the features for this Artifact
     fn features(&self) -> &Features;
 
@@ -76,6 +98,43 @@ pub trait Artifact: Send + Sync + Upcastable {
         Ok(())
     }
 
+    /// Serializes an artifact into bytes, with the key/value
+    /// `metadata` of the host embedded in it.
+    ///
+    /// The metadata can be read back with [`Artifact::read_metadata`]
+    /// without deserializing the artifact.
+    fn serialize_with_metadata(
+        &self,
+        metadata: &ArtifactMetadata,
+    ) -> Result<Vec<u8>, SerializeError> {
+        let mut serialized = self.serialize()?;
+        append_metadata(&mut serialized, metadata)?;
+        Ok(serialized)
+    }
+
+    /// Serializes an artifact into a file path, with the key/value
+    /// `metadata` of the host embedded in it.
+    fn serialize_to_file_with_metadata(
+        &self,
+        path: &Path,
+        metadata: &ArtifactMetadata,
+    ) -> Result<(), SerializeError> {
+        let serialized = self.serialize_with_metadata(metadata)?;
+        fs::write(&path, serialized)?;
+        Ok(())
+    }
+
+    /// Reads the metadata embedded in the serialized artifact at
+    /// `path`, without loading the artifact.
+    ///
+    /// An artifact serialized without metadata has no entries.
+    fn read_metadata(path: &Path) -> Result<ArtifactMetadata, DeserializeError>
+    where
+        Self: Sized,
+    {
+        crate::metadata::read_metadata(path)
+    }
+
     /// Do preinstantiation logic that is executed before instantiating
     fn preinstantiate(&self) -> Result<(), InstantiationError> {
         Ok(())
@@ -144,6 +203,8 @@ pub trait Artifact: Send + Sync + Upcastable {
             self.signatures().clone(),
             host_state,
             import_function_envs,
+            &tunables.libcalls(),
+            tunables.stack_limit(),
         )
         .map_err(|trap| InstantiationError::Start(RuntimeError::from_trap(trap)))?;
         Ok(handle)
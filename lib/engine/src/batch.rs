@@ -0,0 +1,113 @@
+//! Compilation of many WebAssembly modules at once.
+
+use crate::Artifact;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Statistics collected while compiling modules with
+/// [`Engine::compile_batch`][crate::Engine::compile_batch].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BatchCompileStats {
+    /// The number of modules provided.
+    pub modules: usize,
+    /// The number of modules that were actually compiled.
+    ///
+    /// Identical modules are compiled only once and share the same
+    /// [`Artifact`].
+    pub compiled_modules: usize,
+    /// The number of local function bodies of the modules.
+    pub function_bodies: usize,
+    /// The number of function bodies that were not compiled: the ones
+    /// of the modules identical to a module provided before, and the
+    /// ones whose code was reused from an identical function body by
+    /// the compiler.
+    pub reused_function_bodies: usize,
+}
+
+impl BatchCompileStats {
+    /// The number of modules that were not compiled because an
+    /// identical one was provided before.
+    pub fn deduplicated_modules(&self) -> usize {
+        self.modules - self.compiled_modules
+    }
+
+    /// The number of function bodies that were actually compiled.
+    pub fn compiled_function_bodies(&self) -> usize {
+        self.function_bodies - self.reused_function_bodies
+    }
+}
+
+/// The result of [`Engine::compile_batch`][crate::Engine::compile_batch].
+pub struct BatchCompilation {
+    /// The artifacts, in the same order as the provided binaries.
+    pub artifacts: Vec<Arc<dyn Artifact>>,
+    /// The statistics of the compilation.
+    pub stats: BatchCompileStats,
+}
+
+impl BatchCompilation {
+    /// Gathers the artifacts of the binaries from the artifacts of the
+    /// distinct ones, given for every binary the index of the first
+    /// binary identical to it.
+    pub(crate) fn new(
+        first_seen: &[usize],
+        compiled: Vec<Arc<dyn Artifact>>,
+        reused_function_bodies: usize,
+    ) -> Self {
+        let compiled_modules = compiled.len();
+        let compiled_function_bodies = compiled
+            .iter()
+            .map(|artifact| artifact.finished_functions().len())
+            .sum::<usize>()
+            - reused_function_bodies;
+
+        let mut compiled = compiled.into_iter();
+        let mut artifacts: Vec<Arc<dyn Artifact>> = Vec::with_capacity(first_seen.len());
+        for (index, first) in first_seen.iter().enumerate() {
+            let artifact = if *first == index {
+                compiled.next().expect("every distinct binary is compiled")
+            } else {
+                artifacts[*first].clone()
+            };
+            artifacts.push(artifact);
+        }
+
+        let function_bodies = artifacts
+            .iter()
+            .map(|artifact| artifact.finished_functions().len())
+            .sum();
+        Self {
+            artifacts,
+            stats: BatchCompileStats {
+                modules: first_seen.len(),
+                compiled_modules,
+                function_bodies,
+                reused_function_bodies: function_bodies - compiled_function_bodies,
+            },
+        }
+    }
+}
+
+/// Groups identical binaries together.
+///
+/// Returns, for every binary, the index of the first binary identical to it.
+pub(crate) fn deduplicate(binaries: &[&[u8]]) -> Vec<usize> {
+    let mut first_seen: HashMap<&[u8], usize> = HashMap::with_capacity(binaries.len());
+    binaries
+        .iter()
+        .enumerate()
+        .map(|(index, binary)| *first_seen.entry(*binary).or_insert(index))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deduplicate_binaries() {
+        let binaries: [&[u8]; 4] = [b"first", b"second", b"first", b"second"];
+        assert_eq!(deduplicate(&binaries), vec![0, 1, 0, 1]);
+        assert_eq!(deduplicate(&[]), Vec::<usize>::new());
+    }
+}
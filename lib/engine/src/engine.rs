@@ -1,6 +1,7 @@
 //! JIT compilation.
 
-use crate::tunables::Tunables;
+use crate::batch::{deduplicate, BatchCompilation};
+use crate::tunables::{Tunables, TunablesConfig};
 use crate::{Artifact, DeserializeError};
 use memmap2::Mmap;
 use std::path::Path;
@@ -8,7 +9,7 @@ use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
 use std::sync::Arc;
 use wasmer_compiler::{CompileError, Target};
 use wasmer_types::FunctionType;
-use wasmer_vm::VMSharedSignatureIndex;
+use wasmer_vm::{VMSharedSignatureIndex, VMTrampoline};
 
 /// A unimplemented Wasmer `Engine`.
 ///
@@ -26,6 +27,27 @@ pub trait Engine {
     /// Lookup a signature
     fn lookup_signature(&self, sig: VMSharedSignatureIndex) -> Option<FunctionType>;
 
+    /// Lookup a trampoline calling the functions of the signature `sig`
+    /// from the host, taken from the artifacts of this engine.
+    ///
+    /// It lets the host call the functions it gets from the tables,
+    /// which don't tell the artifact they come from.
+    fn lookup_call_trampoline(&self, _sig: VMSharedSignatureIndex) -> Option<VMTrampoline> {
+        None
+    }
+
+    /// Whether the code compiled by this engine executes
+    /// deterministically, see `CompilerConfig::enable_deterministic`.
+    fn deterministic(&self) -> bool {
+        false
+    }
+
+    /// The configuration of the default tunables of the stores created
+    /// for this engine, set with its builder.
+    fn tunables_config(&self) -> TunablesConfig {
+        TunablesConfig::default()
+    }
+
     /// Validates a WebAssembly module
     fn validate(&self, binary: &[u8]) -> Result<(), CompileError>;
 
@@ -36,6 +58,63 @@ pub trait Engine {
         tunables: &dyn Tunables,
     ) -> Result<Arc<dyn Artifact>, CompileError>;
 
+    /// Validates and compiles many WebAssembly binaries at once.
+    ///
+    /// Identical binaries are compiled only once and share the same
+    /// [`Artifact`]. The distinct binaries are compiled with
+    /// [`Engine::compile_distinct`]. The returned artifacts are in the
+    /// same order as the provided binaries, along with the
+    /// [`BatchCompileStats`][crate::BatchCompileStats] of the work that
+    /// was actually done.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error found while validating or compiling
+    /// the binaries.
+    fn compile_batch(
+        &self,
+        binaries: &[&[u8]],
+        tunables: &dyn Tunables,
+    ) -> Result<BatchCompilation, CompileError> {
+        let first_seen = deduplicate(binaries);
+        let distinct = binaries
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| first_seen[*index] == *index)
+            .map(|(_, binary)| *binary)
+            .collect::<Vec<_>>();
+        let (compiled, reused_function_bodies) = self.compile_distinct(&distinct, tunables)?;
+        Ok(BatchCompilation::new(
+            &first_seen,
+            compiled,
+            reused_function_bodies,
+        ))
+    }
+
+    /// Validates and compiles the distinct binaries of
+    /// [`Engine::compile_batch`].
+    ///
+    /// Returns their artifacts, in the same order, and the number of
+    /// function bodies whose code was reused rather than compiled.
+    ///
+    /// By default, the binaries are compiled one after the other with
+    /// [`Engine::compile`], and no code is reused. The engines may
+    /// compile them together, see `Compiler::compile_modules`.
+    fn compile_distinct(
+        &self,
+        binaries: &[&[u8]],
+        tunables: &dyn Tunables,
+    ) -> Result<(Vec<Arc<dyn Artifact>>, usize), CompileError> {
+        let artifacts = binaries
+            .iter()
+            .map(|binary| {
+                self.validate(binary)?;
+                self.compile(binary, tunables)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok((artifacts, 0))
+    }
+
     /// Deserializes a WebAssembly module
     ///
     /// # Safety
@@ -57,6 +136,24 @@ pub trait Engine {
         self.deserialize(&mmap)
     }
 
+    /// Deserializes a WebAssembly module that comes from an untrusted
+    /// source, checking its metadata before loading its code.
+    ///
+    /// The engines that can't check their artifacts return an error.
+    ///
+    /// # Safety
+    ///
+    /// The machine code of the module isn't verified, so it must have
+    /// been generated by a trusted compiler.
+    unsafe fn deserialize_untrusted(
+        &self,
+        _bytes: &[u8],
+    ) -> Result<Arc<dyn Artifact>, DeserializeError> {
+        Err(DeserializeError::Incompatible(
+            "the engine can't deserialize untrusted modules".to_string(),
+        ))
+    }
+
     /// A unique identifier for this object.
     ///
     /// This exists to allow us to compare two Engines for equality. Otherwise,
@@ -1,6 +1,6 @@
 use wasmer_vm::{
-    ImportInitializerFuncPtr, VMExport, VMExportFunction, VMExportGlobal, VMExportMemory,
-    VMExportTable,
+    ImportFunctionEnv, ImportInitializerFuncPtr, VMExport, VMExportFunction, VMExportGlobal,
+    VMExportMemory, VMExportTable,
 };
 
 use std::sync::Arc;
@@ -142,6 +142,52 @@ pub struct ExportFunction {
     pub metadata: Option<Arc<ExportFunctionMetadata>>,
 }
 
+impl ExportFunction {
+    /// Clones the host env of the function for an `Instance` importing
+    /// it, or returns `ImportFunctionEnv::NoEnv` if the function has no
+    /// host env.
+    ///
+    /// The clone isn't initialized yet: its initializer must be called
+    /// with the `Instance` before the function is called.
+    pub fn import_env(&self) -> ImportFunctionEnv {
+        match self.metadata.as_ref().map(|m| &**m) {
+            Some(ExportFunctionMetadata {
+                import_init_function_ptr,
+                host_env_clone_fn,
+                host_env_drop_fn,
+                ..
+            }) => {
+                // TODO: maybe start adding asserts in all these
+                // unsafe blocks to prevent future changes from
+                // horribly breaking things.
+                let env = unsafe {
+                    assert!(!self.vm_function.vmctx.host_env.is_null());
+                    (host_env_clone_fn)(self.vm_function.vmctx.host_env)
+                };
+                ImportFunctionEnv::Env {
+                    env,
+                    clone: *host_env_clone_fn,
+                    initializer: *import_init_function_ptr,
+                    destructor: *host_env_drop_fn,
+                }
+            }
+            None => ImportFunctionEnv::NoEnv,
+        }
+    }
+
+    /// Returns whether both functions have a host env of the same
+    /// type, so that the host env of one can be used by the other.
+    pub fn has_same_host_env_type(&self, other: &Self) -> bool {
+        match (&self.metadata, &other.metadata) {
+            (Some(this), Some(other)) => {
+                this.host_env_clone_fn as usize == other.host_env_clone_fn as usize
+                    && this.host_env_drop_fn as usize == other.host_env_drop_fn as usize
+            }
+            _ => false,
+        }
+    }
+}
+
 impl From<ExportFunction> for Export {
     fn from(func: ExportFunction) -> Self {
         Self::Function(func)
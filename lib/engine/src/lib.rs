@@ -1,4 +1,39 @@
 //! Generic Engine abstraction for Wasmer Engines.
+//!
+//! # Implementing an engine
+//!
+//! The `wasmer` API only uses engines through the traits of this
+//! crate, so an engine can be implemented outside of Wasmer:
+//!
+//! * [`Engine`] validates and compiles the modules into artifacts,
+//!   and registers the signatures of their functions, which must be
+//!   shared by all the artifacts of the engine.
+//! * [`Artifact`] is a compiled module. It gives the [`ModuleInfo`]
+//!   of the module, the pointers to its functions and trampolines, and
+//!   the styles of its memories and tables, from which
+//!   [`Artifact::instantiate`] creates the instances: an engine
+//!   doesn't need to implement the instantiation.
+//! * [`Tunables`] are given by the store to the compilation and the
+//!   instantiation, to pick the styles of the memories and the tables,
+//!   and to create them.
+//!
+//! The engine owns the code it generates, and must keep it executable
+//! as long as its artifacts are alive. The JIT engine publishes its
+//! code with the `CodeMemory` of `wasmer-engine-jit`, which other
+//! engines can use as well.
+//!
+//! For the traps raised in the generated code to be caught, the code
+//! must run within [`wasmer_vm::catch_traps`], which the `wasmer` API
+//! does, and their trap code must be known: [`register_frame_info`]
+//! registers the functions of an artifact, with their trap codes and
+//! the information to build the backtraces of the traps. Code
+//! implemented in Rust, like an interpreter, raises its traps with
+//! [`wasmer_vm::raise_lib_trap`] instead.
+//!
+//! The `engine-null` example of the Wasmer repository is a minimal
+//! engine, instantiating the modules without executing their code.
+//!
+//! [`ModuleInfo`]: wasmer_vm::ModuleInfo
 
 #![deny(missing_docs, trivial_numeric_casts, unused_extern_crates)]
 #![warn(unused_import_braces)]
@@ -21,15 +56,18 @@
 )]
 
 mod artifact;
+mod batch;
 mod engine;
 mod error;
 mod export;
+mod metadata;
 mod resolver;
 mod serialize;
 mod trap;
 mod tunables;
 
 pub use crate::artifact::Artifact;
+pub use crate::batch::{BatchCompilation, BatchCompileStats};
 pub use crate::engine::{Engine, EngineId};
 pub use crate::error::{
     DeserializeError, ImportError, InstantiationError, LinkError, SerializeError,
@@ -37,13 +75,16 @@ pub use crate::error::{
 pub use crate::export::{
     Export, ExportFunction, ExportFunctionMetadata, ExportGlobal, ExportMemory, ExportTable,
 };
+pub use crate::metadata::{
+    append_metadata, read_metadata, read_metadata_from_bytes, ArtifactMetadata,
+};
 pub use crate::resolver::{
     resolve_imports, ChainableNamedResolver, NamedResolver, NamedResolverChain, NullResolver,
     Resolver,
 };
 pub use crate::serialize::SerializableFunctionFrameInfo;
 pub use crate::trap::*;
-pub use crate::tunables::Tunables;
+pub use crate::tunables::{Tunables, TunablesConfig};
 
 /// Version number of this crate.
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
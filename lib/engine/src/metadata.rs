@@ -0,0 +1,122 @@
+//! Key/value metadata of the host embedded in serialized artifacts.
+//!
+//! The metadata (a build id, a tenant id, the flags of the compiler…)
+//! is appended to the serialized artifact as a trailer:
+//!
+//! ```text
+//! [artifact][metadata: bincode][metadata length: u64 LE][MAGIC_TRAILER]
+//! ```
+//!
+//! The engines ignore the bytes after their artifact, so an artifact
+//! with metadata is deserialized like any other one, and the metadata
+//! can be read from the end of a file without loading the artifact.
+
+use crate::{DeserializeError, SerializeError};
+use bincode::Options;
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// The key/value metadata embedded in a serialized artifact.
+pub type ArtifactMetadata = BTreeMap<String, String>;
+
+const MAGIC_TRAILER: &[u8] = b"\0wasmer-metadata";
+const TRAILER_LEN: usize = 8 + MAGIC_TRAILER.len();
+
+/// Appends `metadata` to the serialized artifact `serialized`.
+pub fn append_metadata(
+    serialized: &mut Vec<u8>,
+    metadata: &ArtifactMetadata,
+) -> Result<(), SerializeError> {
+    let bytes =
+        bincode::serialize(metadata).map_err(|e| SerializeError::Generic(format!("{:?}", e)))?;
+    serialized.extend_from_slice(&bytes);
+    serialized.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+    serialized.extend_from_slice(MAGIC_TRAILER);
+    Ok(())
+}
+
+/// Returns the length of the metadata ending `len` bytes in, given the
+/// trailer of the metadata, or `None` if there's no metadata.
+fn metadata_len(trailer: &[u8], len: usize) -> Result<Option<usize>, DeserializeError> {
+    if !trailer.ends_with(MAGIC_TRAILER) {
+        return Ok(None);
+    }
+    let metadata_len = u64::from_le_bytes(trailer[..8].try_into().unwrap());
+    if metadata_len > (len - TRAILER_LEN) as u64 {
+        return Err(DeserializeError::CorruptedBinary(
+            "The length of the artifact metadata is out of bounds".to_string(),
+        ));
+    }
+    Ok(Some(metadata_len as usize))
+}
+
+fn deserialize_metadata(bytes: &[u8]) -> Result<ArtifactMetadata, DeserializeError> {
+    bincode::options()
+        .with_fixint_encoding()
+        .with_limit(bytes.len() as u64)
+        .deserialize(bytes)
+        .map_err(|e| DeserializeError::CorruptedBinary(format!("{:?}", e)))
+}
+
+/// Reads the metadata embedded in the serialized artifact `bytes`.
+///
+/// An artifact serialized without metadata has no entries.
+pub fn read_metadata_from_bytes(bytes: &[u8]) -> Result<ArtifactMetadata, DeserializeError> {
+    if bytes.len() < TRAILER_LEN {
+        return Ok(ArtifactMetadata::new());
+    }
+    let end = bytes.len() - TRAILER_LEN;
+    match metadata_len(&bytes[end..], bytes.len())? {
+        Some(len) => deserialize_metadata(&bytes[end - len..end]),
+        None => Ok(ArtifactMetadata::new()),
+    }
+}
+
+/// Reads the metadata embedded in the serialized artifact at `path`.
+///
+/// Only the end of the file is read: the artifact isn't loaded.
+/// An artifact serialized without metadata has no entries.
+pub fn read_metadata(path: &Path) -> Result<ArtifactMetadata, DeserializeError> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len() as usize;
+    if len < TRAILER_LEN {
+        return Ok(ArtifactMetadata::new());
+    }
+    let mut trailer = [0; TRAILER_LEN];
+    file.seek(SeekFrom::End(-(TRAILER_LEN as i64)))?;
+    file.read_exact(&mut trailer)?;
+    match metadata_len(&trailer, len)? {
+        Some(metadata_len) => {
+            let mut bytes = vec![0; metadata_len];
+            file.seek(SeekFrom::End(-((TRAILER_LEN + metadata_len) as i64)))?;
+            file.read_exact(&mut bytes)?;
+            deserialize_metadata(&bytes)
+        }
+        None => Ok(ArtifactMetadata::new()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metadata_roundtrip() {
+        let mut metadata = ArtifactMetadata::new();
+        metadata.insert("build-id".to_string(), "1234".to_string());
+        metadata.insert("tenant".to_string(), "acme".to_string());
+
+        let mut serialized = b"\0wasmer-jit artifact".to_vec();
+        assert!(read_metadata_from_bytes(&serialized).unwrap().is_empty());
+        append_metadata(&mut serialized, &metadata).unwrap();
+        assert!(serialized.starts_with(b"\0wasmer-jit artifact"));
+        assert_eq!(read_metadata_from_bytes(&serialized).unwrap(), metadata);
+
+        let len = serialized.len();
+        serialized[len - TRAILER_LEN] = 0xff;
+        assert!(read_metadata_from_bytes(&serialized).is_err());
+    }
+}
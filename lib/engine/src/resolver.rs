@@ -1,7 +1,7 @@
 //! Define the `Resolver` trait, allowing custom resolution for external
 //! references.
 
-use crate::{Export, ExportFunctionMetadata, ImportError, LinkError};
+use crate::{Export, ImportError, LinkError};
 use more_asserts::assert_ge;
 use wasmer_types::entity::{BoxedSlice, EntityRef, PrimaryMap};
 use wasmer_types::{ExternType, FunctionIndex, ImportIndex, MemoryIndex, TableIndex};
@@ -167,36 +167,20 @@ pub fn resolve_imports(
                         // the shape of `VMDynamicFunctionImportContext`
                     }
                     VMFunctionKind::Static => {
-                        // The native ABI for functions fails when defining a function natively in
-                        // macos (Darwin) with the Apple Silicon ARM chip, for functions with more than 10 args
-                        // TODO: Cranelift should have a good ABI for the ABI
-                        if cfg!(all(target_os = "macos", target_arch = "aarch64")) {
-                            let num_params = f.vm_function.signature.params().len();
-                            assert!(num_params < 9, "Only native functions with less than 9 arguments are allowed in Apple Silicon (for now). Received {} in the import {}.{}", num_params, module_name, field);
-                        }
-
+                        // On Apple Silicon, the `wasmer` API makes the host
+                        // functions which take arguments on the stack dynamic,
+                        // as Cranelift doesn't pack them the way Apple does.
                         f.vm_function.address
                     }
                 };
 
                 // Clone the host env for this `Instance`.
-                let env = if let Some(ExportFunctionMetadata {
-                    host_env_clone_fn: clone,
-                    ..
-                }) = f.metadata.as_ref().map(|x| &**x)
-                {
-                    // TODO: maybe start adding asserts in all these
-                    // unsafe blocks to prevent future changes from
-                    // horribly breaking things.
-                    unsafe {
-                        assert!(!f.vm_function.vmctx.host_env.is_null());
-                        (clone)(f.vm_function.vmctx.host_env)
-                    }
-                } else {
-                    // No `clone` function means we're dealing with some
-                    // other kind of `vmctx`, not a host env of any
-                    // kind.
-                    unsafe { f.vm_function.vmctx.host_env }
+                let import_function_env = f.import_env();
+                let env = match import_function_env {
+                    ImportFunctionEnv::Env { env, .. } => env,
+                    // No host env means we're dealing with some other
+                    // kind of `vmctx`, not a host env of any kind.
+                    ImportFunctionEnv::NoEnv => unsafe { f.vm_function.vmctx.host_env },
                 };
 
                 function_imports.push(VMFunctionImport {
@@ -204,21 +188,6 @@ pub fn resolve_imports(
                     environment: VMFunctionEnvironment { host_env: env },
                 });
 
-                let initializer = f.metadata.as_ref().and_then(|m| m.import_init_function_ptr);
-                let clone = f.metadata.as_ref().map(|m| m.host_env_clone_fn);
-                let destructor = f.metadata.as_ref().map(|m| m.host_env_drop_fn);
-                let import_function_env =
-                    if let (Some(clone), Some(destructor)) = (clone, destructor) {
-                        ImportFunctionEnv::Env {
-                            env,
-                            clone,
-                            initializer,
-                            destructor,
-                        }
-                    } else {
-                        ImportFunctionEnv::NoEnv
-                    };
-
                 host_function_env_initializers.push(import_function_env);
             }
             Export::Table(ref t) => {
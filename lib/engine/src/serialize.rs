@@ -1,3 +1,4 @@
+use bincode::Options;
 use serde::de::{Deserializer, Visitor};
 use serde::ser::Serializer;
 use serde::{Deserialize, Serialize};
@@ -21,6 +22,17 @@ impl UnprocessedFunctionFrameInfo {
         bincode::deserialize(&self.bytes).expect("Can't deserialize the info")
     }
 
+    /// Converts the `UnprocessedFunctionFrameInfo` to a `CompiledFunctionFrameInfo`,
+    /// failing instead of panicking if it's corrupted.
+    pub fn try_deserialize(&self) -> Result<CompiledFunctionFrameInfo, String> {
+        bincode::options()
+            .with_fixint_encoding()
+            .allow_trailing_bytes()
+            .with_limit(self.bytes.len() as u64)
+            .deserialize(&self.bytes)
+            .map_err(|e| format!("{:?}", e))
+    }
+
     /// Converts the `CompiledFunctionFrameInfo` to a `UnprocessedFunctionFrameInfo`
     pub fn serialize(processed: &CompiledFunctionFrameInfo) -> Self {
         // let mut s = flexbuffers::FlexbufferSerializer::new();
@@ -1,5 +1,6 @@
 use super::frame_info::{FrameInfo, GlobalFrameInfo, FRAME_INFO};
 use backtrace::Backtrace;
+use std::collections::BTreeMap;
 use std::error::Error;
 use std::fmt;
 use std::sync::Arc;
@@ -11,6 +12,8 @@ use wasmer_vm::{raise_user_trap, Trap, TrapCode};
 #[derive(Clone)]
 pub struct RuntimeError {
     inner: Arc<RuntimeErrorInner>,
+    /// The labels of the instance that trapped.
+    labels: BTreeMap<String, String>,
 }
 
 /// The source of the `RuntimeError`.
@@ -68,16 +71,8 @@ impl RuntimeError {
         let info = FRAME_INFO.read().unwrap();
         match trap {
             Trap::User(error) => {
-                match error.downcast::<Self>() {
-                    // The error is already a RuntimeError, we return it directly
-                    Ok(runtime_error) => *runtime_error,
-                    Err(e) => Self::new_with_trace(
-                        info,
-                        None,
-                        RuntimeErrorSource::User(e),
-                        Backtrace::new_unresolved(),
-                    ),
-                }
+                drop(info);
+                Self::user(error)
             }
             // A trap caused by an error on the generated machine code for a Wasm function
             Trap::Wasm {
@@ -109,6 +104,47 @@ impl RuntimeError {
         }
     }
 
+    /// Creates a `RuntimeError` from a custom user error.
+    ///
+    /// Returned by a host function, the error goes through the
+    /// WebAssembly frames up to the caller of the WebAssembly function,
+    /// which gets it back with [`RuntimeError::downcast`] or
+    /// [`RuntimeError::downcast_ref`].
+    ///
+    /// # Example
+    /// ```
+    /// # use wasmer_engine::RuntimeError;
+    /// #[derive(Debug, PartialEq)]
+    /// struct Denied(u32);
+    ///
+    /// impl std::fmt::Display for Denied {
+    ///     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    ///         write!(f, "access {} denied", self.0)
+    ///     }
+    /// }
+    ///
+    /// impl std::error::Error for Denied {}
+    ///
+    /// let trap = RuntimeError::user(Box::new(Denied(7)));
+    /// assert_eq!("access 7 denied", trap.message());
+    /// assert_eq!(Some(&Denied(7)), trap.downcast_ref::<Denied>());
+    /// ```
+    pub fn user(error: Box<dyn Error + Send + Sync>) -> Self {
+        match error.downcast::<Self>() {
+            // The error is already a RuntimeError, we return it directly
+            Ok(runtime_error) => *runtime_error,
+            Err(error) => {
+                let info = FRAME_INFO.read().unwrap();
+                Self::new_with_trace(
+                    info,
+                    None,
+                    RuntimeErrorSource::User(error),
+                    Backtrace::new_unresolved(),
+                )
+            }
+        }
+    }
+
     /// Raises a custom user Error
     pub fn raise(error: Box<dyn Error + Send + Sync>) -> ! {
         unsafe { raise_user_trap(error) }
@@ -178,6 +214,7 @@ impl RuntimeError {
                 wasm_trace,
                 native_trace,
             }),
+            labels: BTreeMap::new(),
         }
     }
 
@@ -192,8 +229,27 @@ impl RuntimeError {
         &self.inner.wasm_trace
     }
 
+    /// Returns the labels of the instance, or of the store, the error
+    /// comes from, if any.
+    pub fn labels(&self) -> &BTreeMap<String, String> {
+        &self.labels
+    }
+
+    /// Sets the labels of the instance the error comes from, unless it
+    /// has some already, from an instance called deeper.
+    pub fn with_labels(mut self, labels: impl FnOnce() -> BTreeMap<String, String>) -> Self {
+        if self.labels.is_empty() {
+            self.labels = labels();
+        }
+        self
+    }
+
     /// Attempts to downcast the `RuntimeError` to a concrete type.
+    ///
+    /// It fails if the `RuntimeError` was cloned, as the clones share
+    /// the error: [`RuntimeError::downcast_ref`] works in any case.
     pub fn downcast<T: Error + 'static>(self) -> Result<T, Self> {
+        let labels = self.labels;
         match Arc::try_unwrap(self.inner) {
             // We only try to downcast user errors
             Ok(RuntimeErrorInner {
@@ -202,8 +258,18 @@ impl RuntimeError {
             }) if err.is::<T>() => Ok(*err.downcast::<T>().unwrap()),
             Ok(inner) => Err(Self {
                 inner: Arc::new(inner),
+                labels,
             }),
-            Err(inner) => Err(Self { inner }),
+            Err(inner) => Err(Self { inner, labels }),
+        }
+    }
+
+    /// Returns a reference to the custom user error, if it's of type
+    /// `T`.
+    pub fn downcast_ref<T: Error + 'static>(&self) -> Option<&T> {
+        match &self.inner.source {
+            RuntimeErrorSource::User(err) => err.downcast_ref::<T>(),
+            _ => None,
         }
     }
 
@@ -222,6 +288,7 @@ impl fmt::Debug for RuntimeError {
             .field("source", &self.inner.source)
             .field("wasm_trace", &self.inner.wasm_trace)
             .field("native_trace", &self.inner.native_trace)
+            .field("labels", &self.labels)
             .finish()
     }
 }
@@ -229,6 +296,14 @@ impl fmt::Debug for RuntimeError {
 impl fmt::Display for RuntimeError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "RuntimeError: {}", self.message())?;
+        if !self.labels.is_empty() {
+            let labels = self
+                .labels
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect::<Vec<_>>();
+            write!(f, " {{{}}}", labels.join(", "))?;
+        }
         let trace = self.trace();
         if trace.is_empty() {
             return Ok(());
@@ -252,6 +327,15 @@ impl fmt::Display for RuntimeError {
                 func_index,
                 frame.module_offset()
             )?;
+            if let Some(location) = frame.source_location() {
+                write!(
+                    f,
+                    " [{}:{}:{}]",
+                    location.source(),
+                    location.line(),
+                    location.column()
+                )?;
+            }
         }
         Ok(())
     }
@@ -10,6 +10,7 @@
 //! let module: ModuleInfo = ...;
 //! FRAME_INFO.register(module, compiled_functions);
 //! ```
+use super::source_map::{SourceLocation, SourceMap};
 use crate::serialize::SerializableFunctionFrameInfo;
 use std::cmp;
 use std::collections::BTreeMap;
@@ -55,6 +56,7 @@ struct ModuleInfoFrameInfo {
     functions: BTreeMap<usize, FunctionInfo>,
     module: Arc<ModuleInfo>,
     frame_infos: PrimaryMap<LocalFunctionIndex, SerializableFunctionFrameInfo>,
+    source_map: Option<Arc<SourceMap>>,
 }
 
 impl ModuleInfoFrameInfo {
@@ -159,12 +161,25 @@ impl GlobalFrameInfo {
         Some(FrameInfo {
             module_name: module.module.name(),
             func_index: func_index.index() as u32,
-            function_name: module.module.function_names.get(&func_index).cloned(),
+            function_name: module.module.function_name(func_index).map(str::to_string),
             instr,
             func_start: instr_map.start_srcloc,
+            source_location: module
+                .source_map
+                .as_ref()
+                .and_then(|source_map| source_map.lookup(instr.bits() as usize)),
         })
     }
 
+    /// Fetches the name of the function containing a program counter,
+    /// see [`FrameInfo::function_name`].
+    pub fn lookup_function_name(&self, pc: usize) -> Option<String> {
+        let module = self.module_info(pc)?;
+        let func = module.function_info(pc)?;
+        let func_index = module.module.func_index(func.local_index);
+        module.module.function_name(func_index).map(str::to_string)
+    }
+
     /// Fetches trap information about a program counter in a backtrace.
     pub fn lookup_trap_info(&self, pc: usize) -> Option<&TrapInformation> {
         let module = self.module_info(pc)?;
@@ -238,10 +253,14 @@ pub struct FunctionExtent {
 /// compiled functions within `module`. If the `module` has no functions
 /// then `None` will be returned. Otherwise the returned object, when
 /// dropped, will be used to unregister all name information from this map.
+///
+/// The frames are resolved to the original source code with the
+/// `source_map`, if provided.
 pub fn register(
     module: Arc<ModuleInfo>,
     finished_functions: &BoxedSlice<LocalFunctionIndex, FunctionExtent>,
     frame_infos: PrimaryMap<LocalFunctionIndex, SerializableFunctionFrameInfo>,
+    source_map: Option<Arc<SourceMap>>,
 ) -> Option<GlobalFrameInfoRegistration> {
     let mut min = usize::max_value();
     let mut max = 0;
@@ -286,6 +305,7 @@ pub fn register(
             functions,
             module,
             frame_infos,
+            source_map,
         },
     );
     assert!(prev.is_none());
@@ -307,6 +327,7 @@ pub struct FrameInfo {
     function_name: Option<String>,
     func_start: SourceLoc,
     instr: SourceLoc,
+    source_location: Option<SourceLocation>,
 }
 
 impl FrameInfo {
@@ -334,9 +355,8 @@ impl FrameInfo {
     /// Returns a descriptive name of the function for this frame, if one is
     /// available.
     ///
-    /// The name of this function may come from the `name` section of the
-    /// WebAssembly binary, or wasmer may try to infer a better name for it if
-    /// not available, for example the name of the export if it's exported.
+    /// The name of this function comes from the `name` section of the
+    /// WebAssembly binary, or from the name of the export if it's exported.
     ///
     /// This return value is primarily used for debugging and human-readable
     /// purposes for things like traps. Note that the exact return value may be
@@ -367,4 +387,13 @@ impl FrameInfo {
     pub fn func_offset(&self) -> usize {
         (self.instr.bits() - self.func_start.bits()) as usize
     }
+
+    /// Returns the location in the original source code of this
+    /// frame's program counter, if a source map was attached to the
+    /// module.
+    ///
+    /// See [`SourceMap`] for more information.
+    pub fn source_location(&self) -> Option<&SourceLocation> {
+        self.source_location.as_ref()
+    }
 }
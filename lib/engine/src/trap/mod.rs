@@ -1,7 +1,9 @@
 mod error;
 mod frame_info;
+mod source_map;
 pub use error::RuntimeError;
 pub use frame_info::{
     register as register_frame_info, FrameInfo, FunctionExtent, GlobalFrameInfoRegistration,
     FRAME_INFO,
 };
+pub use source_map::{SourceLocation, SourceMap, SourceMapError};
@@ -0,0 +1,340 @@
+//! Source maps of WebAssembly modules.
+//!
+//! Toolchains like AssemblyScript or Emscripten can emit a [source map]
+//! along with the module, where the generated columns are the offsets
+//! of the instructions in the WebAssembly binary. Attaching it to a
+//! module lets the frames of a [`RuntimeError`] point to the original
+//! source code.
+//!
+//! [source map]: https://sourcemaps.info/spec.html
+//! [`RuntimeError`]: crate::RuntimeError
+
+use serde::Deserialize;
+use thiserror::Error;
+
+/// An error while parsing a source map.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SourceMapError {
+    /// The source map isn't valid JSON or doesn't have the expected fields.
+    #[error("invalid source map: {0}")]
+    Json(String),
+    /// Only the version 3 of source maps is supported.
+    #[error("unsupported source map version {0}")]
+    UnsupportedVersion(u32),
+    /// The `mappings` field is malformed.
+    #[error("invalid source map mappings: {0}")]
+    Mappings(String),
+}
+
+/// A location in the original source code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    source: String,
+    line: u32,
+    column: u32,
+    name: Option<String>,
+}
+
+impl SourceLocation {
+    /// The path or URL of the original source file, prefixed with the
+    /// `sourceRoot` of the source map.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The line in the original source file, starting at 1.
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    /// The column in the original source file, starting at 1.
+    pub fn column(&self) -> u32 {
+        self.column
+    }
+
+    /// The original name of the symbol at this location, if any.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawSourceMap {
+    version: u32,
+    #[serde(default)]
+    source_root: Option<String>,
+    #[serde(default)]
+    sources: Vec<Option<String>>,
+    #[serde(default)]
+    names: Vec<String>,
+    mappings: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Mapping {
+    offset: u32,
+    original: Option<OriginalPosition>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct OriginalPosition {
+    source: u32,
+    line: u32,
+    column: u32,
+    name: Option<u32>,
+}
+
+/// A source map of a WebAssembly module, mapping offsets in the
+/// binary to locations in the original source code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceMap {
+    sources: Vec<String>,
+    names: Vec<String>,
+    /// Sorted by offset.
+    mappings: Vec<Mapping>,
+}
+
+impl SourceMap {
+    /// Parses a source map in the JSON format (version 3).
+    pub fn from_json(json: &str) -> Result<Self, SourceMapError> {
+        let raw: RawSourceMap =
+            serde_json::from_str(json).map_err(|e| SourceMapError::Json(e.to_string()))?;
+        if raw.version != 3 {
+            return Err(SourceMapError::UnsupportedVersion(raw.version));
+        }
+        let names = raw.names;
+        let source_root = raw.source_root.unwrap_or_default();
+        let sources = raw
+            .sources
+            .into_iter()
+            .map(|source| {
+                let source = source.unwrap_or_default();
+                if source_root.is_empty() {
+                    source
+                } else if source_root.ends_with('/') {
+                    format!("{}{}", source_root, source)
+                } else {
+                    format!("{}/{}", source_root, source)
+                }
+            })
+            .collect::<Vec<_>>();
+        let mut mappings = parse_mappings(&raw.mappings)?;
+        for mapping in &mappings {
+            if let Some(original) = mapping.original {
+                if original.source as usize >= sources.len() {
+                    return Err(SourceMapError::Mappings(format!(
+                        "unknown source index {}",
+                        original.source
+                    )));
+                }
+                if original
+                    .name
+                    .map_or(false, |name| name as usize >= names.len())
+                {
+                    return Err(SourceMapError::Mappings("unknown name index".to_string()));
+                }
+            }
+        }
+        mappings.sort_by_key(|mapping| mapping.offset);
+        Ok(Self {
+            sources,
+            names,
+            mappings,
+        })
+    }
+
+    /// The original source files.
+    pub fn sources(&self) -> &[String] {
+        &self.sources
+    }
+
+    /// Finds the original location of the instruction at the given
+    /// offset in the WebAssembly binary.
+    pub fn lookup(&self, module_offset: usize) -> Option<SourceLocation> {
+        let index = match self
+            .mappings
+            .binary_search_by_key(&module_offset, |mapping| mapping.offset as usize)
+        {
+            Ok(index) => index,
+            Err(0) => return None,
+            Err(index) => index - 1,
+        };
+        let original = self.mappings[index].original?;
+        Some(SourceLocation {
+            source: self.sources[original.source as usize].clone(),
+            line: original.line + 1,
+            column: original.column + 1,
+            name: original.name.map(|name| self.names[name as usize].clone()),
+        })
+    }
+}
+
+/// Parses the `mappings` of a source map of a WebAssembly module: all
+/// the segments are in the first generated line, the generated column
+/// being the offset in the binary.
+fn parse_mappings(mappings: &str) -> Result<Vec<Mapping>, SourceMapError> {
+    let mut lines = mappings.split(';');
+    let first_line = lines.next().unwrap_or_default();
+    if lines.any(|line| !line.is_empty()) {
+        return Err(SourceMapError::Mappings(
+            "the mappings of a WebAssembly module must be in a single line".to_string(),
+        ));
+    }
+
+    let mut result = vec![];
+    // The fields are relative to the previous segment.
+    let (mut offset, mut source, mut line, mut column, mut name) = (0i64, 0i64, 0i64, 0i64, 0i64);
+    for segment in first_line.split(',').filter(|segment| !segment.is_empty()) {
+        let fields = decode_vlq(segment)?;
+        offset += fields[0];
+        let original = match fields.len() {
+            1 => None,
+            4 | 5 => {
+                source += fields[1];
+                line += fields[2];
+                column += fields[3];
+                let has_name = fields.len() == 5;
+                if has_name {
+                    name += fields[4];
+                }
+                Some(OriginalPosition {
+                    source: to_u32(source)?,
+                    line: to_u32(line)?,
+                    column: to_u32(column)?,
+                    name: if has_name { Some(to_u32(name)?) } else { None },
+                })
+            }
+            n => {
+                return Err(SourceMapError::Mappings(format!(
+                    "a segment can't have {} fields",
+                    n
+                )))
+            }
+        };
+        result.push(Mapping {
+            offset: to_u32(offset)?,
+            original,
+        });
+    }
+    Ok(result)
+}
+
+fn to_u32(value: i64) -> Result<u32, SourceMapError> {
+    if value < 0 || value > u32::max_value() as i64 {
+        return Err(SourceMapError::Mappings(format!(
+            "value {} out of bounds",
+            value
+        )));
+    }
+    Ok(value as u32)
+}
+
+/// Decodes the Base64 VLQ values of a segment.
+fn decode_vlq(segment: &str) -> Result<Vec<i64>, SourceMapError> {
+    let mut values = vec![];
+    let mut value = 0i64;
+    let mut shift = 0;
+    for byte in segment.bytes() {
+        let digit = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => {
+                return Err(SourceMapError::Mappings(format!(
+                    "invalid character `{}`",
+                    byte as char
+                )))
+            }
+        } as i64;
+        if shift > 32 {
+            return Err(SourceMapError::Mappings("value too large".to_string()));
+        }
+        value |= (digit & 0x1f) << shift;
+        if digit & 0x20 != 0 {
+            shift += 5;
+            continue;
+        }
+        values.push(if value & 1 != 0 {
+            -(value >> 1)
+        } else {
+            value >> 1
+        });
+        value = 0;
+        shift = 0;
+    }
+    if shift != 0 {
+        return Err(SourceMapError::Mappings("truncated value".to_string()));
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vlq() {
+        assert_eq!(decode_vlq("AAAA").unwrap(), vec![0, 0, 0, 0]);
+        assert_eq!(decode_vlq("gBCD").unwrap(), vec![16, 1, -1]);
+        assert_eq!(decode_vlq("2HwjB").unwrap(), vec![123, 568]);
+        assert!(decode_vlq("g").is_err());
+        assert!(decode_vlq("A!").is_err());
+    }
+
+    #[test]
+    fn lookup() {
+        let source_map = SourceMap::from_json(
+            r#"{
+                "version": 3,
+                "sourceRoot": "src",
+                "sources": ["index.ts", "lib.ts"],
+                "names": ["main"],
+                "mappings": "kBAAA,IACEA,ECAA,M"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(source_map.sources(), &["src/index.ts", "src/lib.ts"]);
+
+        assert_eq!(source_map.lookup(0x11), None);
+        let location = source_map.lookup(0x12).unwrap();
+        assert_eq!(
+            (location.source(), location.line(), location.column()),
+            ("src/index.ts", 1, 1)
+        );
+        assert_eq!(location.name(), None);
+
+        let location = source_map.lookup(0x17).unwrap();
+        assert_eq!(
+            (location.source(), location.line(), location.column()),
+            ("src/index.ts", 2, 3)
+        );
+        assert_eq!(location.name(), Some("main"));
+
+        let location = source_map.lookup(0x1a).unwrap();
+        assert_eq!(
+            (location.source(), location.line(), location.column()),
+            ("src/lib.ts", 2, 3)
+        );
+
+        // An unmapped segment.
+        assert_eq!(source_map.lookup(0x1e), None);
+        assert_eq!(source_map.lookup(0x1000), None);
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(
+            SourceMap::from_json(r#"{"version": 2, "mappings": ""}"#),
+            Err(SourceMapError::UnsupportedVersion(2))
+        );
+        assert!(SourceMap::from_json(r#"{"version": 3}"#).is_err());
+        assert!(SourceMap::from_json(
+            r#"{"version": 3, "sources": ["a"], "mappings": "AAAA;AAAA"}"#
+        )
+        .is_err());
+        assert!(SourceMap::from_json(r#"{"version": 3, "mappings": "AAAA"}"#).is_err());
+    }
+}
@@ -4,11 +4,12 @@ use std::sync::Arc;
 use wasmer_types::entity::{EntityRef, PrimaryMap};
 use wasmer_types::{
     GlobalType, LocalGlobalIndex, LocalMemoryIndex, LocalTableIndex, MemoryIndex, MemoryType,
-    TableIndex, TableType,
+    Pages, TableIndex, TableType, WASM_PAGE_SIZE,
 };
-use wasmer_vm::MemoryError;
+use wasmer_vm::libcalls::Libcalls;
+use wasmer_vm::{BoundsCheck, MemoryStyle, TableStyle};
 use wasmer_vm::{Global, Memory, ModuleInfo, Table};
-use wasmer_vm::{MemoryStyle, TableStyle};
+use wasmer_vm::{MemoryError, StackLimit};
 use wasmer_vm::{VMMemoryDefinition, VMTableDefinition};
 
 /// An engine delegates the creation of memories, tables, and globals
@@ -17,6 +18,14 @@ pub trait Tunables {
     /// Construct a `MemoryStyle` for the provided `MemoryType`
     fn memory_style(&self, memory: &MemoryType) -> MemoryStyle;
 
+    /// Choose how the compiled code checks the accesses to a memory of
+    /// the provided `MemoryType` and `MemoryStyle` are in bounds.
+    ///
+    /// The choice is recorded in the artifacts along with the style.
+    fn memory_bounds_check(&self, _memory: &MemoryType, _style: &MemoryStyle) -> BoundsCheck {
+        BoundsCheck::GuardPages
+    }
+
     /// Construct a `TableStyle` for the provided `TableType`
     fn table_style(&self, table: &TableType) -> TableStyle;
 
@@ -125,4 +134,80 @@ pub trait Tunables {
 
         Ok(vmctx_globals)
     }
+
+    /// The implementations of the libcalls (`memory.grow`, `memory.copy`,
+    /// `table.init`...) called by the compiled code of the instances.
+    fn libcalls(&self) -> Libcalls {
+        Libcalls::default()
+    }
+
+    /// The stack limit checked by the compiled code of the instances,
+    /// if the stack of their calls is limited.
+    fn stack_limit(&self) -> Option<Arc<StackLimit>> {
+        None
+    }
+}
+
+/// A configuration of the default tunables of the `wasmer` API, to
+/// tune the layout of the memories without implementing the
+/// [`Tunables`] trait.
+///
+/// It's given to the builder of an engine, for the stores created
+/// with `Store::new`, or to `Store::new_with_tunables_config`. The
+/// parameters which aren't set keep the defaults of
+/// `BaseTunables::for_target`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TunablesConfig {
+    /// The size in wasm pages of the static memories.
+    pub static_memory_bound: Option<Pages>,
+    /// The size in bytes of the guard after the static memories.
+    pub static_memory_offset_guard_size: Option<u64>,
+    /// The size in bytes of the guard after the dynamic memories.
+    pub dynamic_memory_offset_guard_size: Option<u64>,
+    /// How the accesses to the memories are checked to be in bounds.
+    pub memory_bounds_check: Option<BoundsCheck>,
+}
+
+impl TunablesConfig {
+    /// Creates a configuration keeping all the defaults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The size in wasm pages of the static memories: the memories
+    /// whose maximum fits in it are reserved once at this size, and
+    /// the others are dynamic, and moved when they grow.
+    ///
+    /// A bound of 0 makes all the memories which can grow dynamic.
+    pub fn static_memory_bound(&mut self, bound: Pages) -> &mut Self {
+        self.static_memory_bound = Some(bound);
+        self
+    }
+
+    /// The size in bytes of the guard after the static memories,
+    /// rounded up to a multiple of the wasm page size.
+    pub fn static_memory_offset_guard_size(&mut self, size: u64) -> &mut Self {
+        self.static_memory_offset_guard_size = Some(round_up_to_wasm_page(size));
+        self
+    }
+
+    /// The size in bytes of the guard after the dynamic memories,
+    /// rounded up to a multiple of the wasm page size.
+    pub fn dynamic_memory_offset_guard_size(&mut self, size: u64) -> &mut Self {
+        self.dynamic_memory_offset_guard_size = Some(round_up_to_wasm_page(size));
+        self
+    }
+
+    /// How the accesses to the memories are checked to be in bounds.
+    pub fn memory_bounds_check(&mut self, bounds_check: BoundsCheck) -> &mut Self {
+        self.memory_bounds_check = Some(bounds_check);
+        self
+    }
+}
+
+/// Rounds `size` up to a multiple of the wasm page size, which is a
+/// multiple of the page size of the hosts, as the guards are mapped.
+fn round_up_to_wasm_page(size: u64) -> u64 {
+    let page_size = WASM_PAGE_SIZE as u64;
+    (size + page_size - 1) / page_size * page_size
 }
@@ -0,0 +1,9 @@
+//! The worker program of `wasmer-isolate`, which runs an instance on
+//! behalf of the host that spawned it.
+
+fn main() {
+    if let Err(error) = wasmer_isolate::run_worker() {
+        eprintln!("wasmer-isolate worker: {}", error);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,319 @@
+//! The host side: the instances running in worker processes.
+
+use crate::protocol::{from_values, read_message, to_values, write_message, Request, Response};
+use crate::worker::WORKER_ENV;
+use std::ffi::OsString;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, ChildStdout, Command, ExitStatus, Stdio};
+use thiserror::Error;
+use wasmer::{Module, SerializeError, Val};
+
+/// An error raised by an isolated instance.
+#[derive(Error, Debug)]
+pub enum IsolateError {
+    /// The worker couldn't be spawned, or the communication with it
+    /// failed.
+    #[error("failed to communicate with the worker: {0}")]
+    Io(#[from] io::Error),
+    /// The worker exited, e.g. because it crashed or was killed. The
+    /// instance can't be used anymore.
+    #[error("the worker exited: {0}")]
+    WorkerExited(ExitStatus),
+    /// The module couldn't be serialized to be sent to the worker.
+    #[error(transparent)]
+    Serialize(#[from] SerializeError),
+    /// The worker couldn't instantiate the module.
+    #[error("instantiation failed: {0}")]
+    Instantiation(String),
+    /// The call trapped, or a host function returned an error.
+    #[error("{0}")]
+    Trap(String),
+    /// The worker couldn't handle the request, e.g. because the
+    /// export doesn't exist or the memory access is out of bounds.
+    #[error("{0}")]
+    Request(String),
+}
+
+/// A host function that an isolated instance imports.
+///
+/// It runs in the host process, and only gets the parameters of the
+/// call: the memory of the instance can't be accessed while it runs.
+pub type HostFunction = Box<dyn FnMut(&[Val]) -> Result<Vec<Val>, String> + Send>;
+
+/// The host functions that an isolated instance imports.
+///
+/// Only functions can be imported, and only the values of the
+/// numeric types can be passed to and returned from them.
+#[derive(Default)]
+pub struct IsolatedImports {
+    functions: Vec<(String, String, HostFunction)>,
+}
+
+impl IsolatedImports {
+    /// Creates an empty set of imports.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Defines the host function imported as `module`.`name`.
+    pub fn define(
+        &mut self,
+        module: &str,
+        name: &str,
+        function: impl FnMut(&[Val]) -> Result<Vec<Val>, String> + Send + 'static,
+    ) -> &mut Self {
+        self.functions
+            .push((module.to_string(), name.to_string(), Box::new(function)));
+        self
+    }
+}
+
+impl fmt::Debug for IsolatedImports {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(
+                self.functions
+                    .iter()
+                    .map(|(module, name, _)| format!("{}.{}", module, name)),
+            )
+            .finish()
+    }
+}
+
+/// The program that the workers run.
+#[derive(Debug, Clone)]
+pub struct Isolate {
+    program: PathBuf,
+    args: Vec<OsString>,
+    current_exe: bool,
+}
+
+impl Isolate {
+    /// Runs the workers with the `wasmer-isolate-worker` program at
+    /// `program`, or with another program that calls
+    /// [`run_worker`](crate::run_worker).
+    pub fn new(program: impl Into<PathBuf>) -> Self {
+        Self {
+            program: program.into(),
+            args: vec![],
+            current_exe: false,
+        }
+    }
+
+    /// Runs the workers with the current executable, which must call
+    /// [`run_worker_if_requested`](crate::run_worker_if_requested) at
+    /// the beginning of its `main`.
+    pub fn current_exe() -> io::Result<Self> {
+        Ok(Self {
+            program: std::env::current_exe()?,
+            args: vec![],
+            current_exe: true,
+        })
+    }
+
+    /// Adds an argument passed to the workers.
+    pub fn arg(&mut self, arg: impl Into<OsString>) -> &mut Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    /// Instantiates `module` in a new worker.
+    ///
+    /// The module is serialized and sent to the worker, which
+    /// deserializes it with a headless engine of the same kind, so it
+    /// must be compiled by a JIT or native engine enabled in the
+    /// worker.
+    pub fn instantiate(
+        &self,
+        module: &Module,
+        imports: IsolatedImports,
+    ) -> Result<IsolatedInstance, IsolateError> {
+        let artifact = module.serialize()?;
+        let mut command = Command::new(&self.program);
+        command
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit());
+        if self.current_exe {
+            command.env(WORKER_ENV, "1");
+        }
+        let mut child = command.spawn()?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let mut instance = IsolatedInstance {
+            child,
+            stdin,
+            stdout,
+            functions: vec![],
+        };
+
+        let (names, functions) = imports
+            .functions
+            .into_iter()
+            .map(|(module, name, function)| ((module, name), function))
+            .unzip();
+        instance.functions = functions;
+        match instance.request(&Request::Instantiate {
+            artifact,
+            imports: names,
+        })? {
+            Response::Instantiated => Ok(instance),
+            Response::Error(message) | Response::Trap(message) => {
+                Err(IsolateError::Instantiation(message))
+            }
+            _ => Err(unexpected_response()),
+        }
+    }
+}
+
+/// A WebAssembly instance running in a worker process.
+///
+/// It's isolated from the host by the operating system: a crash of
+/// the instance (or of the code generated for it) only terminates the
+/// worker, and the instance can only access the memory of the host
+/// through the host functions it imports.
+///
+/// The worker is killed when the instance is dropped.
+pub struct IsolatedInstance {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: ChildStdout,
+    functions: Vec<HostFunction>,
+}
+
+impl IsolatedInstance {
+    /// Calls the exported function `name`.
+    pub fn call(&mut self, name: &str, params: &[Val]) -> Result<Box<[Val]>, IsolateError> {
+        let params = to_values(params).map_err(IsolateError::Request)?;
+        match self.request(&Request::Call {
+            function: name.to_string(),
+            params,
+        })? {
+            Response::Returned(results) => Ok(from_values(results).into_boxed_slice()),
+            Response::Trap(message) => Err(IsolateError::Trap(message)),
+            Response::Error(message) => Err(IsolateError::Request(message)),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    /// Reads `buffer.len()` bytes at `offset` in the exported memory
+    /// `name`.
+    pub fn read_memory(
+        &mut self,
+        name: &str,
+        offset: u64,
+        buffer: &mut [u8],
+    ) -> Result<(), IsolateError> {
+        match self.request(&Request::ReadMemory {
+            memory: name.to_string(),
+            offset,
+            len: buffer.len() as u64,
+        })? {
+            Response::Memory(data) if data.len() == buffer.len() => {
+                buffer.copy_from_slice(&data);
+                Ok(())
+            }
+            Response::Error(message) => Err(IsolateError::Request(message)),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    /// Writes `data` at `offset` in the exported memory `name`.
+    pub fn write_memory(
+        &mut self,
+        name: &str,
+        offset: u64,
+        data: &[u8],
+    ) -> Result<(), IsolateError> {
+        match self.request(&Request::WriteMemory {
+            memory: name.to_string(),
+            offset,
+            data: data.to_vec(),
+        })? {
+            Response::Written => Ok(()),
+            Response::Error(message) => Err(IsolateError::Request(message)),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    /// Returns the size of the exported memory `name`, in bytes.
+    pub fn memory_size(&mut self, name: &str) -> Result<u64, IsolateError> {
+        match self.request(&Request::MemorySize {
+            memory: name.to_string(),
+        })? {
+            Response::MemorySize(size) => Ok(size),
+            Response::Error(message) => Err(IsolateError::Request(message)),
+            _ => Err(unexpected_response()),
+        }
+    }
+
+    /// Returns the process id of the worker, e.g. to kill it from
+    /// another thread when a call takes too long.
+    pub fn worker_id(&self) -> u32 {
+        self.child.id()
+    }
+
+    /// Sends a request to the worker and waits for its response,
+    /// calling the host functions it asks for in the meantime.
+    fn request(&mut self, request: &Request) -> Result<Response, IsolateError> {
+        self.send(request)?;
+        loop {
+            match self.receive()? {
+                Response::HostCall { import, params } => {
+                    let result = match self.functions.get_mut(import as usize) {
+                        Some(function) => {
+                            function(&from_values(params)).and_then(|results| to_values(&results))
+                        }
+                        None => Err(format!("the host function {} doesn't exist", import)),
+                    };
+                    self.send(&Request::HostReturn(result))?;
+                }
+                response => return Ok(response),
+            }
+        }
+    }
+
+    fn send(&mut self, request: &Request) -> Result<(), IsolateError> {
+        write_message(&mut self.stdin, request).map_err(|error| self.exited_or(error))
+    }
+
+    fn receive(&mut self) -> Result<Response, IsolateError> {
+        read_message(&mut self.stdout).map_err(|error| self.exited_or(error))
+    }
+
+    /// Turns an error of communication with the worker into
+    /// `IsolateError::WorkerExited` if the worker exited.
+    fn exited_or(&mut self, error: io::Error) -> IsolateError {
+        if let io::ErrorKind::UnexpectedEof | io::ErrorKind::BrokenPipe = error.kind() {
+            if let Ok(status) = self.child.wait() {
+                return IsolateError::WorkerExited(status);
+            }
+        }
+        IsolateError::Io(error)
+    }
+}
+
+impl fmt::Debug for IsolatedInstance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IsolatedInstance")
+            .field("worker_id", &self.child.id())
+            .finish()
+    }
+}
+
+impl Drop for IsolatedInstance {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn unexpected_response() -> IsolateError {
+    IsolateError::Io(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "unexpected response from the worker",
+    ))
+}
@@ -0,0 +1,36 @@
+//! The `wasmer-isolate` crate runs WebAssembly instances in worker
+//! processes, one instance per worker, to isolate the host from the
+//! modules it doesn't trust with the operating system.
+//!
+//! The host compiles the module as usual, and [`Isolate::instantiate`]
+//! sends it to a new worker, which instantiates it. The exported
+//! functions and memories of the instance are then accessed through
+//! [`IsolatedInstance`], and the host functions it imports are called
+//! back in the host.
+//!
+//! The workers run the `wasmer-isolate-worker` program of this crate,
+//! or the executable of the host itself with
+//! [`Isolate::current_exe`] and [`run_worker_if_requested`].
+
+#![deny(missing_docs, trivial_numeric_casts, unused_extern_crates)]
+#![warn(unused_import_braces)]
+#![cfg_attr(
+    feature = "cargo-clippy",
+    warn(
+        clippy::float_arithmetic,
+        clippy::mut_mut,
+        clippy::nonminimal_bool,
+        clippy::option_map_unwrap_or,
+        clippy::option_map_unwrap_or_else,
+        clippy::print_stdout,
+        clippy::unicode_not_nfc,
+        clippy::use_self
+    )
+)]
+
+mod instance;
+mod protocol;
+mod worker;
+
+pub use crate::instance::{HostFunction, Isolate, IsolateError, IsolatedImports, IsolatedInstance};
+pub use crate::worker::{run_worker, run_worker_if_requested};
@@ -0,0 +1,147 @@
+//! The messages exchanged between the host and a worker, over the
+//! standard input and output of the worker.
+//!
+//! Each message is its length, as a little-endian `u32`, followed by
+//! the message serialized with bincode.
+
+use bincode::Options;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+use wasmer::Val;
+
+/// The maximum size of a message, so that a compromised worker can't
+/// make the host allocate an unbounded amount of memory.
+const MAX_MESSAGE_SIZE: u32 = 1 << 30;
+
+/// A message sent by the host to a worker.
+#[derive(Serialize, Deserialize)]
+pub(crate) enum Request {
+    /// Instantiates a serialized module, with the host functions
+    /// defined for its imports.
+    Instantiate {
+        artifact: Vec<u8>,
+        imports: Vec<(String, String)>,
+    },
+    /// Calls an exported function.
+    Call {
+        function: String,
+        params: Vec<Value>,
+    },
+    /// Reads from an exported memory.
+    ReadMemory {
+        memory: String,
+        offset: u64,
+        len: u64,
+    },
+    /// Writes to an exported memory.
+    WriteMemory {
+        memory: String,
+        offset: u64,
+        data: Vec<u8>,
+    },
+    /// Gets the size of an exported memory, in bytes.
+    MemorySize { memory: String },
+    /// The result of a `Response::HostCall`.
+    HostReturn(Result<Vec<Value>, String>),
+}
+
+/// A message sent by a worker to the host.
+#[derive(Serialize, Deserialize)]
+pub(crate) enum Response {
+    /// The module is instantiated.
+    Instantiated,
+    /// The values returned by a function.
+    Returned(Vec<Value>),
+    /// The bytes read from a memory.
+    Memory(Vec<u8>),
+    /// The bytes are written to a memory.
+    Written,
+    /// The size of a memory, in bytes.
+    MemorySize(u64),
+    /// The instance calls the host function defined for its
+    /// `import`-th import, and waits for a `Request::HostReturn`.
+    HostCall { import: u32, params: Vec<Value> },
+    /// The instance trapped.
+    Trap(String),
+    /// The request failed.
+    Error(String),
+}
+
+/// A WebAssembly value, as sent between the host and a worker.
+#[derive(Serialize, Deserialize)]
+pub(crate) enum Value {
+    I32(i32),
+    I64(i64),
+    F32(f32),
+    F64(f64),
+}
+
+impl TryFrom<&Val> for Value {
+    type Error = String;
+
+    fn try_from(value: &Val) -> Result<Self, Self::Error> {
+        Ok(match *value {
+            Val::I32(value) => Self::I32(value),
+            Val::I64(value) => Self::I64(value),
+            Val::F32(value) => Self::F32(value),
+            Val::F64(value) => Self::F64(value),
+            _ => return Err(format!("the {:?} values can't be isolated", value.ty())),
+        })
+    }
+}
+
+impl From<Value> for Val {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::I32(value) => Self::I32(value),
+            Value::I64(value) => Self::I64(value),
+            Value::F32(value) => Self::F32(value),
+            Value::F64(value) => Self::F64(value),
+        }
+    }
+}
+
+/// Converts values to send them.
+pub(crate) fn to_values(values: &[Val]) -> Result<Vec<Value>, String> {
+    values.iter().map(Value::try_from).collect()
+}
+
+/// Converts the values received.
+pub(crate) fn from_values(values: Vec<Value>) -> Vec<Val> {
+    values.into_iter().map(Val::from).collect()
+}
+
+/// Writes a message and flushes it.
+pub(crate) fn write_message(writer: &mut impl Write, message: &impl Serialize) -> io::Result<()> {
+    let bytes =
+        bincode::serialize(message).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let len = u32::try_from(bytes.len())
+        .ok()
+        .filter(|len| *len <= MAX_MESSAGE_SIZE)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "the message is too large"))?;
+    writer.write_all(&len.to_le_bytes())?;
+    writer.write_all(&bytes)?;
+    writer.flush()
+}
+
+/// Reads a message. It fails with `io::ErrorKind::UnexpectedEof` if
+/// the other side closed the pipe.
+pub(crate) fn read_message<T: DeserializeOwned>(reader: &mut impl Read) -> io::Result<T> {
+    let mut len = [0; 4];
+    reader.read_exact(&mut len)?;
+    let len = u32::from_le_bytes(len);
+    if len > MAX_MESSAGE_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "the message is too large",
+        ));
+    }
+    let mut bytes = vec![0; len as usize];
+    reader.read_exact(&mut bytes)?;
+    bincode::options()
+        .with_fixint_encoding()
+        .with_limit(u64::from(len))
+        .deserialize(&bytes)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
@@ -0,0 +1,184 @@
+//! The worker process, which runs a single instance on behalf of the
+//! host.
+
+use crate::protocol::{from_values, read_message, to_values, write_message, Request, Response};
+use std::collections::HashMap;
+use std::io;
+use wasmer::{
+    Engine, Exports, Function, ImportObject, Instance, Memory, Module, RuntimeError, Store,
+};
+
+/// The environment variable set for the workers spawned from the
+/// current executable, see [`run_worker_if_requested`].
+pub(crate) const WORKER_ENV: &str = "WASMER_ISOLATE_WORKER";
+
+/// Runs the worker if the current process has been spawned as one
+/// by [`Isolate::current_exe`], and then exits.
+///
+/// It must be called at the very beginning of `main`, so the worker
+/// doesn't run the code of the embedder.
+///
+/// [`Isolate::current_exe`]: crate::Isolate::current_exe
+pub fn run_worker_if_requested() {
+    if std::env::var_os(WORKER_ENV).is_some() {
+        let code = match run_worker() {
+            Ok(()) => 0,
+            Err(error) => {
+                eprintln!("wasmer-isolate worker: {}", error);
+                1
+            }
+        };
+        std::process::exit(code);
+    }
+}
+
+/// Runs the worker until the host closes its standard input.
+///
+/// This is the `main` of the `wasmer-isolate-worker` program.
+pub fn run_worker() -> io::Result<()> {
+    let mut instance = None;
+    loop {
+        let request = match read_message(&mut io::stdin()) {
+            Ok(request) => request,
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+            Err(error) => return Err(error),
+        };
+        let response = handle(&mut instance, request);
+        write_message(&mut io::stdout(), &response)?;
+    }
+}
+
+fn handle(instance: &mut Option<Instance>, request: Request) -> Response {
+    match request {
+        Request::Instantiate { artifact, imports } => {
+            if instance.is_some() {
+                return Response::Error("the worker already runs an instance".to_string());
+            }
+            match instantiate(&artifact, imports) {
+                Ok(new_instance) => {
+                    *instance = Some(new_instance);
+                    Response::Instantiated
+                }
+                Err(error) => Response::Error(error),
+            }
+        }
+        Request::HostReturn(_) => Response::Error("no host function is being called".to_string()),
+        request => match instance {
+            Some(instance) => {
+                handle_instance_request(&instance.exports, request).unwrap_or_else(Response::Error)
+            }
+            None => Response::Error("the worker doesn't run an instance yet".to_string()),
+        },
+    }
+}
+
+fn handle_instance_request(exports: &Exports, request: Request) -> Result<Response, String> {
+    let memory = |name: &str| exports.get_memory(name).map_err(|e| e.to_string());
+    match request {
+        Request::Call { function, params } => {
+            let function = exports.get_function(&function).map_err(|e| e.to_string())?;
+            Ok(match function.call(&from_values(params)) {
+                Ok(results) => Response::Returned(to_values(&results)?),
+                Err(error) => Response::Trap(error.message()),
+            })
+        }
+        Request::ReadMemory {
+            memory: name,
+            offset,
+            len,
+        } => {
+            let memory = memory(&name)?;
+            let range = memory_range(memory, offset, len)?;
+            let data = unsafe { memory.data_unchecked() };
+            Ok(Response::Memory(data[range].to_vec()))
+        }
+        Request::WriteMemory {
+            memory: name,
+            offset,
+            data,
+        } => {
+            let memory = memory(&name)?;
+            let range = memory_range(memory, offset, data.len() as u64)?;
+            unsafe { memory.data_unchecked_mut()[range].copy_from_slice(&data) };
+            Ok(Response::Written)
+        }
+        Request::MemorySize { memory: name } => {
+            Ok(Response::MemorySize(memory(&name)?.data_size()))
+        }
+        Request::Instantiate { .. } | Request::HostReturn(_) => unreachable!(),
+    }
+}
+
+/// Returns the range of the bytes of `memory` at `offset`, if it's in
+/// bounds.
+fn memory_range(memory: &Memory, offset: u64, len: u64) -> Result<std::ops::Range<usize>, String> {
+    match offset.checked_add(len) {
+        Some(end) if end <= memory.data_size() => Ok(offset as usize..end as usize),
+        _ => Err(format!(
+            "the {} bytes at {} are out of the bounds of the memory",
+            len, offset
+        )),
+    }
+}
+
+fn instantiate(artifact: &[u8], imports: Vec<(String, String)>) -> Result<Instance, String> {
+    let store = Store::new(&*headless_engine(artifact)?);
+    let module = unsafe { Module::deserialize(&store, artifact) }.map_err(|e| e.to_string())?;
+
+    let mut namespaces = HashMap::new();
+    for import in module.imports().functions() {
+        let index = match imports
+            .iter()
+            .position(|(module, name)| module == import.module() && name == import.name())
+        {
+            Some(index) => index as u32,
+            None => continue,
+        };
+        let function = Function::new(&store, import.ty(), move |params| {
+            let params = to_values(params).map_err(RuntimeError::new)?;
+            write_message(
+                &mut io::stdout(),
+                &Response::HostCall {
+                    import: index,
+                    params,
+                },
+            )
+            .map_err(|e| RuntimeError::new(e.to_string()))?;
+            match read_message(&mut io::stdin()).map_err(|e| RuntimeError::new(e.to_string()))? {
+                Request::HostReturn(Ok(results)) => Ok(from_values(results)),
+                Request::HostReturn(Err(message)) => Err(RuntimeError::new(message)),
+                _ => Err(RuntimeError::new(
+                    "the host didn't return from the host function",
+                )),
+            }
+        });
+        namespaces
+            .entry(import.module().to_string())
+            .or_insert_with(Exports::new)
+            .insert(import.name(), function);
+    }
+    let mut import_object = ImportObject::new();
+    for (name, namespace) in namespaces {
+        import_object.register(name, namespace);
+    }
+
+    Instance::new(&module, &import_object).map_err(|e| e.to_string())
+}
+
+/// Returns a headless engine of the kind that produced `artifact`.
+fn headless_engine(artifact: &[u8]) -> Result<Box<dyn Engine + Send + Sync>, String> {
+    #[cfg(feature = "jit")]
+    {
+        if wasmer::JITArtifact::is_deserializable(artifact) {
+            return Ok(Box::new(wasmer::JIT::headless().engine()));
+        }
+    }
+    #[cfg(feature = "native")]
+    {
+        if wasmer::NativeArtifact::is_deserializable(artifact) {
+            return Ok(Box::new(wasmer::Native::headless().engine()));
+        }
+    }
+    let _ = artifact;
+    Err("the module isn't an artifact of an engine the worker supports".to_string())
+}
@@ -0,0 +1,111 @@
+use anyhow::Result;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::Arc;
+use wasmer::{Module, Store, Val};
+use wasmer_isolate::{Isolate, IsolateError, IsolatedImports};
+
+fn isolate() -> Isolate {
+    Isolate::new(env!("CARGO_BIN_EXE_wasmer-isolate-worker"))
+}
+
+const WAT: &str = r#"
+    (module
+        (import "host" "double" (func $double (param i32) (result i32)))
+        (memory (export "memory") 1)
+        (data (i32.const 0) "hello")
+        (func (export "double_twice") (param i32) (result i32)
+            (call $double (call $double (local.get 0))))
+        (func (export "load") (param i32) (result i32)
+            (i32.load8_u (local.get 0)))
+        (func (export "trap") unreachable))
+"#;
+
+#[test]
+fn call_exports_and_imports() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(&store, WAT)?;
+    let calls = Arc::new(AtomicI32::new(0));
+    let mut imports = IsolatedImports::new();
+    let host_calls = calls.clone();
+    imports.define("host", "double", move |params| {
+        host_calls.fetch_add(1, Ordering::SeqCst);
+        Ok(vec![Val::I32(params[0].unwrap_i32() * 2)])
+    });
+    let mut instance = isolate().instantiate(&module, imports)?;
+
+    let results = instance.call("double_twice", &[Val::I32(3)])?;
+    assert_eq!(results.to_vec(), vec![Val::I32(12)]);
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+    assert_eq!(instance.memory_size("memory")?, 65536);
+    let mut hello = [0; 5];
+    instance.read_memory("memory", 0, &mut hello)?;
+    assert_eq!(&hello, b"hello");
+    instance.write_memory("memory", 1, &[42])?;
+    let results = instance.call("load", &[Val::I32(1)])?;
+    assert_eq!(results.to_vec(), vec![Val::I32(42)]);
+
+    assert!(matches!(
+        instance.read_memory("memory", 65535, &mut hello),
+        Err(IsolateError::Request(_))
+    ));
+    assert!(matches!(
+        instance.call("missing", &[]),
+        Err(IsolateError::Request(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn traps_and_host_errors() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(&store, WAT)?;
+    let mut imports = IsolatedImports::new();
+    imports.define("host", "double", |_| Err("denied".to_string()));
+    let mut instance = isolate().instantiate(&module, imports)?;
+
+    match instance.call("trap", &[]) {
+        Err(IsolateError::Trap(message)) => assert!(message.contains("unreachable")),
+        result => panic!("unexpected result: {:?}", result),
+    }
+    match instance.call("double_twice", &[Val::I32(1)]) {
+        Err(IsolateError::Trap(message)) => assert_eq!(message, "denied"),
+        result => panic!("unexpected result: {:?}", result),
+    }
+    // The instance can still be used after a trap.
+    let results = instance.call("load", &[Val::I32(0)])?;
+    assert_eq!(results.to_vec(), vec![Val::I32(i32::from(b'h'))]);
+    Ok(())
+}
+
+#[test]
+fn missing_imports() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(&store, WAT)?;
+    assert!(matches!(
+        isolate().instantiate(&module, IsolatedImports::new()),
+        Err(IsolateError::Instantiation(_))
+    ));
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn worker_exit() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(&store, WAT)?;
+    let mut imports = IsolatedImports::new();
+    imports.define("host", "double", |params| Ok(params.to_vec()));
+    let mut instance = isolate().instantiate(&module, imports)?;
+
+    let status = std::process::Command::new("kill")
+        .arg("-9")
+        .arg(instance.worker_id().to_string())
+        .status()?;
+    assert!(status.success());
+    assert!(matches!(
+        instance.call("load", &[Val::I32(0)]),
+        Err(IsolateError::WorkerExited(_))
+    ));
+    Ok(())
+}
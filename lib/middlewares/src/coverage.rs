@@ -0,0 +1,287 @@
+//! `coverage` is a middleware counting how many times each basic block of the functions
+//! is executed, for fuzzers and test-coverage tools.
+
+use std::fmt;
+use std::sync::Mutex;
+use wasmer::wasmparser::{FunctionBody, Operator};
+use wasmer::{
+    FunctionMiddleware, GlobalIndex, Instance, LocalFunctionIndex, MiddlewareError,
+    MiddlewareReaderState, ModuleItemInjector, ModuleMiddleware,
+};
+use wasmer_types::entity::PrimaryMap;
+
+/// The name the counters of the blocks are registered under, see
+/// [`ModuleItemInjector::add_counters`].
+pub const COVERAGE_COUNTERS: &str = "wasmer_coverage_counters";
+
+/// A basic block of a function, counted by [`Coverage`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoverageBlock {
+    /// The function of the block.
+    pub function: LocalFunctionIndex,
+    /// The offset of the first operator of the block in the module.
+    pub offset: usize,
+}
+
+/// Splits the operators of a function into basic blocks.
+///
+/// A block starts at the entry of the function, and after every
+/// operator that can be jumped to or branch conditionally. The code
+/// following an unconditional branch is unreachable, so it's part of
+/// the block of the branch.
+#[derive(Default)]
+struct BlockSplitter {
+    depth: u32,
+}
+
+impl BlockSplitter {
+    /// Whether a block starts after `operator`.
+    fn ends_block(&mut self, operator: &Operator) -> bool {
+        match operator {
+            Operator::Block { .. } => {
+                self.depth += 1;
+                false
+            }
+            Operator::Loop { .. } | Operator::If { .. } => {
+                self.depth += 1;
+                true
+            }
+            Operator::End => {
+                // The `end` of the function body ends the code.
+                if self.depth == 0 {
+                    return false;
+                }
+                self.depth -= 1;
+                true
+            }
+            Operator::Else | Operator::BrIf { .. } => true,
+            _ => false,
+        }
+    }
+}
+
+/// The module-level coverage middleware.
+///
+/// It must be the first middleware of the chain, so it instruments the
+/// blocks of the original code.
+///
+/// Each basic block of the functions gets a counter, registered under
+/// [`COVERAGE_COUNTERS`], and read with [`get_counters`].
+///
+/// # Panic
+///
+/// An instance of `Coverage` should not be shared among different modules, since it tracks
+/// module-specific information like the blocks and their counters. Attempts to use a
+/// `Coverage` instance from multiple modules will result in a panic.
+#[derive(Default)]
+pub struct Coverage {
+    /// The counters of the blocks of each function, in order.
+    counters: Mutex<Option<PrimaryMap<LocalFunctionIndex, Vec<GlobalIndex>>>>,
+
+    /// The blocks of the module.
+    blocks: Mutex<Vec<CoverageBlock>>,
+}
+
+/// The function-level coverage middleware.
+pub struct FunctionCoverage {
+    /// The counters of the blocks of the function, in order.
+    counters: Vec<GlobalIndex>,
+
+    /// The next block of the function.
+    next_block: usize,
+
+    /// The splitter of the operators of the function.
+    splitter: BlockSplitter,
+}
+
+impl Coverage {
+    /// Creates a `Coverage` middleware.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The blocks of the module, in the order of the counters of
+    /// [`get_counters`]. It's empty until the module is compiled.
+    pub fn blocks(&self) -> Vec<CoverageBlock> {
+        self.blocks.lock().unwrap().clone()
+    }
+}
+
+impl fmt::Debug for Coverage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Coverage")
+            .field("blocks", &self.blocks.lock().unwrap().len())
+            .finish()
+    }
+}
+
+impl ModuleMiddleware for Coverage {
+    /// Generates a `FunctionMiddleware` for a given function.
+    fn generate_function_middleware(
+        &self,
+        local_function_index: LocalFunctionIndex,
+    ) -> Box<dyn FunctionMiddleware> {
+        Box::new(FunctionCoverage {
+            counters: self.counters.lock().unwrap().as_ref().unwrap()[local_function_index].clone(),
+            next_block: 0,
+            splitter: BlockSplitter::default(),
+        })
+    }
+
+    /// Injects a counter for each block of the functions.
+    fn inject_module_items(&self, injector: &mut ModuleItemInjector) {
+        let mut counters = self.counters.lock().unwrap();
+
+        if counters.is_some() {
+            panic!("Coverage::inject_module_items: Attempting to use a `Coverage` middleware from multiple modules.");
+        }
+
+        let mut blocks = vec![];
+        let mut function_blocks = vec![];
+        for (function, body) in injector.function_bodies().iter() {
+            let start = blocks.len();
+            let body = FunctionBody::new(body.module_offset, body.data);
+            let mut operators = body
+                .get_operators_reader()
+                .expect("the function bodies are validated");
+            let mut splitter = BlockSplitter::default();
+            let mut block_start = true;
+            while !operators.eof() {
+                let offset = operators.original_position();
+                let operator = operators.read().expect("the function bodies are validated");
+                if block_start {
+                    blocks.push(CoverageBlock { function, offset });
+                }
+                block_start = splitter.ends_block(&operator);
+            }
+            function_blocks.push(start..blocks.len());
+        }
+
+        let globals = injector.add_counters(COVERAGE_COUNTERS, blocks.len());
+
+        *counters = Some(
+            function_blocks
+                .into_iter()
+                .map(|range| globals[range].to_vec())
+                .collect(),
+        );
+        *self.blocks.lock().unwrap() = blocks;
+    }
+}
+
+impl fmt::Debug for FunctionCoverage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FunctionCoverage")
+            .field("counters", &self.counters)
+            .field("next_block", &self.next_block)
+            .finish()
+    }
+}
+
+impl FunctionCoverage {
+    /// Increments the counter of the next block.
+    fn count_next_block(
+        &mut self,
+        state: &mut MiddlewareReaderState,
+    ) -> Result<(), MiddlewareError> {
+        let counter = *self.counters.get(self.next_block).ok_or_else(|| {
+            MiddlewareError::new(
+                "Coverage",
+                "the function has more blocks than counted, `Coverage` must be the first middleware",
+            )
+        })?;
+        self.next_block += 1;
+        state.increment_counter(counter);
+        Ok(())
+    }
+}
+
+impl FunctionMiddleware for FunctionCoverage {
+    fn feed<'a>(
+        &mut self,
+        operator: Operator<'a>,
+        state: &mut MiddlewareReaderState<'a>,
+    ) -> Result<(), MiddlewareError> {
+        // The entry of the function.
+        if self.next_block == 0 {
+            self.count_next_block(state)?;
+        }
+        let ends_block = self.splitter.ends_block(&operator);
+        state.push_operator(operator);
+        if ends_block {
+            self.count_next_block(state)?;
+        }
+        Ok(())
+    }
+}
+
+/// Get the execution counts of the blocks in an `Instance`, in the order of
+/// [`Coverage::blocks`].
+///
+/// This can be used in a headless engine after an ahead-of-time compilation
+/// as all required state lives in the instance.
+///
+/// The instance Module must have been processed with the [`Coverage`] middleware
+/// at compile time, otherwise no counters are returned.
+pub fn get_counters(instance: &Instance) -> Vec<u64> {
+    instance.counters(COVERAGE_COUNTERS)
+}
+
+/// Reset the execution counts of the blocks in an `Instance` to zero.
+///
+/// The instance Module must have been processed with the [`Coverage`] middleware
+/// at compile time, otherwise this does nothing.
+pub fn reset_counters(instance: &Instance) {
+    instance.reset_counters(COVERAGE_COUNTERS);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+    use wasmer::{imports, wat2wasm, CompilerConfig, Cranelift, Module, NativeFunc, Store, JIT};
+
+    fn bytecode() -> Vec<u8> {
+        wat2wasm(
+            br#"
+            (module
+            (func $abs (export "abs") (param $value i32) (result i32)
+                (if (result i32) (i32.lt_s (local.get $value) (i32.const 0))
+                    (then (i32.sub (i32.const 0) (local.get $value)))
+                    (else (local.get $value)))))
+            "#,
+        )
+        .unwrap()
+        .into()
+    }
+
+    #[test]
+    fn counters_work() {
+        let coverage = Arc::new(Coverage::new());
+        let mut compiler_config = Cranelift::default();
+        compiler_config.push_middleware(coverage.clone());
+        let store = Store::new(&JIT::new(compiler_config).engine());
+        let module = Module::new(&store, bytecode()).unwrap();
+
+        // The entry, the `then` and `else` branches, and after the `if`.
+        let blocks = coverage.blocks();
+        assert_eq!(blocks.len(), 4);
+        assert!(blocks
+            .iter()
+            .all(|block| block.function == LocalFunctionIndex::from_u32(0)));
+
+        // Instantiate
+        let instance = Instance::new(&module, &imports! {}).unwrap();
+        assert_eq!(get_counters(&instance), vec![0, 0, 0, 0]);
+
+        let abs: NativeFunc<i32, i32> = instance.exports.get_native_function("abs").unwrap();
+        assert_eq!(abs.call(-2).unwrap(), 2);
+        assert_eq!(abs.call(-3).unwrap(), 3);
+        assert_eq!(abs.call(4).unwrap(), 4);
+        assert_eq!(get_counters(&instance), vec![3, 2, 1, 3]);
+
+        reset_counters(&instance);
+        assert_eq!(get_counters(&instance), vec![0, 0, 0, 0]);
+    }
+}
@@ -1,5 +1,7 @@
+pub mod coverage;
 pub mod metering;
 
 // The most commonly used symbol are exported at top level of the module. Others are available
 // via modules, e.g. `wasmer_middlewares::metering::get_remaining_points`
+pub use coverage::Coverage;
 pub use metering::Metering;
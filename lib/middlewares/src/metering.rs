@@ -1,5 +1,8 @@
 //! `metering` is a middleware for tracking how many operators are executed in total
 //! and putting a limit on the total number of operators executed.
+//!
+//! The cost of the operators is given either by a function, or by a
+//! [`CostTable`] with a cost per class of operators.
 
 use std::convert::TryInto;
 use std::fmt;
@@ -7,13 +10,12 @@ use std::sync::Mutex;
 use wasmer::wasmparser::{Operator, Type as WpType, TypeOrFuncType as WpTypeOrFuncType};
 use wasmer::{
     ExportIndex, FunctionMiddleware, GlobalInit, GlobalType, Instance, LocalFunctionIndex,
-    MiddlewareError, MiddlewareReaderState, ModuleMiddleware, Mutability, Type,
+    MiddlewareError, MiddlewareReaderState, ModuleItemInjector, ModuleMiddleware, Mutability, Type,
 };
 use wasmer_types::GlobalIndex;
-use wasmer_vm::ModuleInfo;
 
 #[derive(Clone)]
-struct MeteringGlobalIndexes(GlobalIndex, GlobalIndex);
+struct MeteringGlobalIndexes(GlobalIndex, GlobalIndex, GlobalIndex);
 
 impl MeteringGlobalIndexes {
     /// The global index in the current module for remaining points.
@@ -29,6 +31,12 @@ impl MeteringGlobalIndexes {
     fn points_exhausted(&self) -> GlobalIndex {
         self.1
     }
+
+    /// The global index in the current module holding the number of pages
+    /// requested by a `memory.grow` while its cost is charged.
+    fn memory_grow_delta(&self) -> GlobalIndex {
+        self.2
+    }
 }
 
 impl fmt::Debug for MeteringGlobalIndexes {
@@ -36,10 +44,160 @@ impl fmt::Debug for MeteringGlobalIndexes {
         f.debug_struct("MeteringGlobalIndexes")
             .field("remaining_points", &self.remaining_points())
             .field("points_exhausted", &self.points_exhausted())
+            .field("memory_grow_delta", &self.memory_grow_delta())
             .finish()
     }
 }
 
+/// The cost of the operators in "points", charged by [`Metering`].
+///
+/// It's implemented by the functions mapping each operator to a cost,
+/// and by [`CostTable`].
+pub trait OperatorCost: Clone + Send + Sync {
+    /// The cost of `operator`.
+    fn cost(&self, operator: &Operator) -> u64;
+
+    /// The cost of each page requested by a `memory.grow`, charged when
+    /// it executes, in addition to the cost of the operator.
+    fn memory_grow_page_cost(&self) -> u64 {
+        0
+    }
+}
+
+impl<F: Fn(&Operator) -> u64 + Copy + Clone + Send + Sync> OperatorCost for F {
+    fn cost(&self, operator: &Operator) -> u64 {
+        self(operator)
+    }
+}
+
+/// The costs of the operators by class, in "points".
+///
+/// The default table costs 1 point per operator, and nothing per page
+/// grown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CostTable {
+    /// Constants, e.g. `i32.const` or `ref.null`.
+    pub constant: u64,
+    /// Accesses to the locals and the globals.
+    pub variable: u64,
+    /// Blocks, branches, and the parametric operators `drop` and `select`.
+    pub control: u64,
+    /// Direct and indirect calls, including tail calls.
+    pub call: u64,
+    /// Loads and stores.
+    pub memory_access: u64,
+    /// `memory.size`, `memory.grow`, and the bulk memory and table
+    /// operators, e.g. `memory.copy` or `table.init`.
+    pub memory_management: u64,
+    /// Each page requested by a `memory.grow`.
+    pub memory_grow_page: u64,
+    /// Every other operator: the numeric, reference, SIMD and atomic
+    /// operators.
+    pub numeric: u64,
+}
+
+impl Default for CostTable {
+    fn default() -> Self {
+        Self {
+            constant: 1,
+            variable: 1,
+            control: 1,
+            call: 1,
+            memory_access: 1,
+            memory_management: 1,
+            memory_grow_page: 0,
+            numeric: 1,
+        }
+    }
+}
+
+impl OperatorCost for CostTable {
+    fn cost(&self, operator: &Operator) -> u64 {
+        match operator {
+            Operator::I32Const { .. }
+            | Operator::I64Const { .. }
+            | Operator::F32Const { .. }
+            | Operator::F64Const { .. }
+            | Operator::V128Const { .. }
+            | Operator::RefNull { .. }
+            | Operator::RefFunc { .. } => self.constant,
+
+            Operator::LocalGet { .. }
+            | Operator::LocalSet { .. }
+            | Operator::LocalTee { .. }
+            | Operator::GlobalGet { .. }
+            | Operator::GlobalSet { .. } => self.variable,
+
+            Operator::Unreachable
+            | Operator::Nop
+            | Operator::Block { .. }
+            | Operator::Loop { .. }
+            | Operator::If { .. }
+            | Operator::Else
+            | Operator::End
+            | Operator::Br { .. }
+            | Operator::BrIf { .. }
+            | Operator::BrTable { .. }
+            | Operator::Return
+            | Operator::Drop
+            | Operator::Select
+            | Operator::TypedSelect { .. } => self.control,
+
+            Operator::Call { .. }
+            | Operator::CallIndirect { .. }
+            | Operator::ReturnCall { .. }
+            | Operator::ReturnCallIndirect { .. } => self.call,
+
+            Operator::I32Load { .. }
+            | Operator::I64Load { .. }
+            | Operator::F32Load { .. }
+            | Operator::F64Load { .. }
+            | Operator::I32Load8S { .. }
+            | Operator::I32Load8U { .. }
+            | Operator::I32Load16S { .. }
+            | Operator::I32Load16U { .. }
+            | Operator::I64Load8S { .. }
+            | Operator::I64Load8U { .. }
+            | Operator::I64Load16S { .. }
+            | Operator::I64Load16U { .. }
+            | Operator::I64Load32S { .. }
+            | Operator::I64Load32U { .. }
+            | Operator::I32Store { .. }
+            | Operator::I64Store { .. }
+            | Operator::F32Store { .. }
+            | Operator::F64Store { .. }
+            | Operator::I32Store8 { .. }
+            | Operator::I32Store16 { .. }
+            | Operator::I64Store8 { .. }
+            | Operator::I64Store16 { .. }
+            | Operator::I64Store32 { .. }
+            | Operator::V128Load { .. }
+            | Operator::V128Store { .. } => self.memory_access,
+
+            Operator::MemorySize { .. }
+            | Operator::MemoryGrow { .. }
+            | Operator::MemoryInit { .. }
+            | Operator::DataDrop { .. }
+            | Operator::MemoryCopy { .. }
+            | Operator::MemoryFill { .. }
+            | Operator::TableInit { .. }
+            | Operator::ElemDrop { .. }
+            | Operator::TableCopy { .. }
+            | Operator::TableFill { .. }
+            | Operator::TableGet { .. }
+            | Operator::TableSet { .. }
+            | Operator::TableGrow { .. }
+            | Operator::TableSize { .. } => self.memory_management,
+
+            _ => self.numeric,
+        }
+    }
+
+    fn memory_grow_page_cost(&self) -> u64 {
+        self.memory_grow_page
+    }
+}
+
 /// The module-level metering middleware.
 ///
 /// # Panic
@@ -47,21 +205,21 @@ impl fmt::Debug for MeteringGlobalIndexes {
 /// An instance of `Metering` should not be shared among different modules, since it tracks
 /// module-specific information like the global index to store metering state. Attempts to use
 /// a `Metering` instance from multiple modules will result in a panic.
-pub struct Metering<F: Fn(&Operator) -> u64 + Copy + Clone + Send + Sync> {
+pub struct Metering<C: OperatorCost> {
     /// Initial limit of points.
     initial_limit: u64,
 
-    /// Function that maps each operator to a cost in "points".
-    cost_function: F,
+    /// The cost of the operators.
+    cost: C,
 
     /// The global indexes for metering points.
     global_indexes: Mutex<Option<MeteringGlobalIndexes>>,
 }
 
 /// The function-level metering middleware.
-pub struct FunctionMetering<F: Fn(&Operator) -> u64 + Copy + Clone + Send + Sync> {
-    /// Function that maps each operator to a cost in "points".
-    cost_function: F,
+pub struct FunctionMetering<C: OperatorCost> {
+    /// The cost of the operators.
+    cost: C,
 
     /// The global indexes for metering points.
     global_indexes: MeteringGlobalIndexes,
@@ -80,94 +238,92 @@ pub enum MeteringPoints {
     Exhausted,
 }
 
-impl<F: Fn(&Operator) -> u64 + Copy + Clone + Send + Sync> Metering<F> {
-    /// Creates a `Metering` middleware.
-    pub fn new(initial_limit: u64, cost_function: F) -> Self {
+impl<C: OperatorCost> Metering<C> {
+    /// Creates a `Metering` middleware, charging the cost of the operators
+    /// given by `cost`, e.g. a function or a [`CostTable`].
+    pub fn new(initial_limit: u64, cost: C) -> Self {
         Self {
             initial_limit,
-            cost_function,
+            cost,
             global_indexes: Mutex::new(None),
         }
     }
 }
 
-impl<F: Fn(&Operator) -> u64 + Copy + Clone + Send + Sync> fmt::Debug for Metering<F> {
+impl<C: OperatorCost> fmt::Debug for Metering<C> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Metering")
             .field("initial_limit", &self.initial_limit)
-            .field("cost_function", &"<function>")
+            .field("cost", &"<cost>")
             .field("global_indexes", &self.global_indexes)
             .finish()
     }
 }
 
-impl<F: Fn(&Operator) -> u64 + Copy + Clone + Send + Sync + 'static> ModuleMiddleware
-    for Metering<F>
-{
+impl<C: OperatorCost + 'static> ModuleMiddleware for Metering<C> {
     /// Generates a `FunctionMiddleware` for a given function.
     fn generate_function_middleware(&self, _: LocalFunctionIndex) -> Box<dyn FunctionMiddleware> {
         Box::new(FunctionMetering {
-            cost_function: self.cost_function,
+            cost: self.cost.clone(),
             global_indexes: self.global_indexes.lock().unwrap().clone().unwrap(),
             accumulated_cost: 0,
         })
     }
 
-    /// Transforms a `ModuleInfo` struct in-place. This is called before application on functions begins.
-    fn transform_module_info(&self, module_info: &mut ModuleInfo) {
+    /// Injects the globals of the metering state, exported so the host can read them.
+    fn inject_module_items(&self, injector: &mut ModuleItemInjector) {
         let mut global_indexes = self.global_indexes.lock().unwrap();
 
         if global_indexes.is_some() {
-            panic!("Metering::transform_module_info: Attempting to use a `Metering` middleware from multiple modules.");
+            panic!("Metering::inject_module_items: Attempting to use a `Metering` middleware from multiple modules.");
         }
 
         // Append a global for remaining points and initialize it.
-        let remaining_points_global_index = module_info
-            .globals
-            .push(GlobalType::new(Type::I64, Mutability::Var));
-
-        module_info
-            .global_initializers
-            .push(GlobalInit::I64Const(self.initial_limit as i64));
+        let remaining_points_global_index = injector.add_global(
+            GlobalType::new(Type::I64, Mutability::Var),
+            GlobalInit::I64Const(self.initial_limit as i64),
+        );
 
-        module_info.exports.insert(
-            "wasmer_metering_remaining_points".to_string(),
+        injector.export(
+            "wasmer_metering_remaining_points",
             ExportIndex::Global(remaining_points_global_index),
         );
 
         // Append a global for the exhausted points boolean and initialize it.
-        let points_exhausted_global_index = module_info
-            .globals
-            .push(GlobalType::new(Type::I32, Mutability::Var));
-
-        module_info
-            .global_initializers
-            .push(GlobalInit::I32Const(0));
+        let points_exhausted_global_index = injector.add_global(
+            GlobalType::new(Type::I32, Mutability::Var),
+            GlobalInit::I32Const(0),
+        );
 
-        module_info.exports.insert(
-            "wasmer_metering_points_exhausted".to_string(),
+        injector.export(
+            "wasmer_metering_points_exhausted",
             ExportIndex::Global(points_exhausted_global_index),
         );
 
+        // Append a global for the pages requested by a `memory.grow`.
+        let memory_grow_delta_global_index = injector.add_global(
+            GlobalType::new(Type::I32, Mutability::Var),
+            GlobalInit::I32Const(0),
+        );
+
         *global_indexes = Some(MeteringGlobalIndexes(
             remaining_points_global_index,
             points_exhausted_global_index,
+            memory_grow_delta_global_index,
         ))
     }
 }
 
-impl<F: Fn(&Operator) -> u64 + Copy + Clone + Send + Sync> fmt::Debug for FunctionMetering<F> {
+impl<C: OperatorCost> fmt::Debug for FunctionMetering<C> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("FunctionMetering")
-            .field("cost_function", &"<function>")
+            .field("cost", &"<cost>")
             .field("global_indexes", &self.global_indexes)
             .finish()
     }
 }
 
-impl<F: Fn(&Operator) -> u64 + Copy + Clone + Send + Sync> FunctionMiddleware
-    for FunctionMetering<F>
-{
+impl<C: OperatorCost> FunctionMiddleware for FunctionMetering<C> {
     fn feed<'a>(
         &mut self,
         operator: Operator<'a>,
@@ -176,7 +332,7 @@ impl<F: Fn(&Operator) -> u64 + Copy + Clone + Send + Sync> FunctionMiddleware
         // Get the cost of the current operator, and add it to the accumulator.
         // This needs to be done before the metering logic, to prevent operators like `Call` from escaping metering in some
         // corner cases.
-        self.accumulated_cost += (self.cost_function)(&operator);
+        self.accumulated_cost += self.cost.cost(&operator);
 
         // Possible sources and targets of a branch. Finalize the cost of the previous basic block and perform necessary checks.
         match operator {
@@ -212,6 +368,43 @@ impl<F: Fn(&Operator) -> u64 + Copy + Clone + Send + Sync> FunctionMiddleware
                     self.accumulated_cost = 0;
                 }
             }
+            Operator::MemoryGrow { .. } => {
+                let page_cost = self.cost.memory_grow_page_cost();
+                if page_cost > 0 {
+                    let remaining_points = self.global_indexes.remaining_points().as_u32();
+                    let delta = self.global_indexes.memory_grow_delta().as_u32();
+                    state.extend(&[
+                        // The requested pages are on top of the stack.
+                        Operator::GlobalSet { global_index: delta },
+
+                        // if unsigned(globals[remaining_points_index]) / page_cost < delta { throw(); }
+                        // (checked this way so the cost of the pages can't overflow)
+                        Operator::GlobalGet { global_index: remaining_points },
+                        Operator::I64Const { value: page_cost as i64 },
+                        Operator::I64DivU,
+                        Operator::GlobalGet { global_index: delta },
+                        Operator::I64ExtendI32U,
+                        Operator::I64LtU,
+                        Operator::If { ty: WpTypeOrFuncType::Type(WpType::EmptyBlockType) },
+                        Operator::I32Const { value: 1 },
+                        Operator::GlobalSet { global_index: self.global_indexes.points_exhausted().as_u32() },
+                        Operator::Unreachable,
+                        Operator::End,
+
+                        // globals[remaining_points_index] -= delta * page_cost;
+                        Operator::GlobalGet { global_index: remaining_points },
+                        Operator::GlobalGet { global_index: delta },
+                        Operator::I64ExtendI32U,
+                        Operator::I64Const { value: page_cost as i64 },
+                        Operator::I64Mul,
+                        Operator::I64Sub,
+                        Operator::GlobalSet { global_index: remaining_points },
+
+                        // Put the requested pages back for `memory.grow`.
+                        Operator::GlobalGet { global_index: delta },
+                    ]);
+                }
+            }
             _ => {}
         }
         state.push_operator(operator);
@@ -407,4 +600,53 @@ mod tests {
             MeteringPoints::Remaining(4)
         );
     }
+
+    #[test]
+    fn cost_table_works() {
+        let cost_table = CostTable {
+            variable: 2,
+            memory_grow_page: 10,
+            ..CostTable::default()
+        };
+        assert_eq!(cost_table.cost(&Operator::LocalGet { local_index: 0 }), 2);
+        assert_eq!(cost_table.cost(&Operator::I32Add), 1);
+
+        let metering = Arc::new(Metering::new(100, cost_table));
+        let mut compiler_config = Cranelift::default();
+        compiler_config.push_middleware(metering);
+        let store = Store::new(&JIT::new(compiler_config).engine());
+        let wasm = wat2wasm(
+            br#"
+            (module
+            (memory (export "memory") 1)
+            (func (export "grow") (param $pages i32) (result i32)
+                (memory.grow (local.get $pages))))
+            "#,
+        )
+        .unwrap();
+        let module = Module::new(&store, wasm).unwrap();
+
+        // Instantiate
+        let instance = Instance::new(&module, &imports! {}).unwrap();
+        let grow = instance
+            .exports
+            .get_function("grow")
+            .unwrap()
+            .native::<i32, i32>()
+            .unwrap();
+
+        // Growing by 2 pages costs 24 points: 20 points for the pages, 2 points
+        // for `local.get $pages`, 1 point for `memory.grow` and 1 point for `end`.
+        assert_eq!(grow.call(2).unwrap(), 1);
+        assert_eq!(
+            get_remaining_points(&instance),
+            MeteringPoints::Remaining(76)
+        );
+
+        // Growing by 8 pages fails due to limit, before growing the memory
+        assert!(grow.call(8).is_err());
+        assert_eq!(get_remaining_points(&instance), MeteringPoints::Exhausted);
+        let memory = instance.exports.get_memory("memory").unwrap();
+        assert_eq!(memory.size().0, 3);
+    }
 }
@@ -1,9 +1,13 @@
 use crate::error::ObjectError;
-use object::write::{Object, Relocation, StandardSection, Symbol as ObjSymbol, SymbolSection};
-use object::{RelocationEncoding, RelocationKind, SymbolFlags, SymbolKind, SymbolScope};
+use object::write::{
+    Object, Relocation, StandardSection, Symbol as ObjSymbol, SymbolId, SymbolSection,
+};
+use object::{
+    RelocationEncoding, RelocationKind, SectionKind, SymbolFlags, SymbolKind, SymbolScope,
+};
 use wasmer_compiler::{
-    Architecture, BinaryFormat, Compilation, CustomSectionProtection, Endianness, RelocationTarget,
-    Symbol, SymbolRegistry, Triple,
+    Architecture, BinaryFormat, Compilation, CompiledFunctionUnwindInfo, CustomSectionProtection,
+    Endianness, FunctionBody, RelocationTarget, Symbol, SymbolRegistry, Triple,
 };
 
 /// Create an object for a given target `Triple`.
@@ -147,6 +151,10 @@ pub fn emit_compilation(
         obj.add_symbol_data(symbol_id, section_id, custom_section.bytes.as_slice(), 1);
     }
 
+    // The Windows unwind information of the functions and trampolines,
+    // emitted in the `.pdata` and `.xdata` sections.
+    let mut windows_unwind_infos = Vec::new();
+
     // Add functions
     for (function_local_index, function) in function_bodies.into_iter() {
         let function_name =
@@ -164,6 +172,7 @@ pub fn emit_compilation(
 
         let section_id = obj.section_id(StandardSection::Text);
         obj.add_symbol_data(symbol_id, section_id, &function.body, 1);
+        windows_unwind_infos.extend(windows_unwind_info(symbol_id, function));
     }
 
     // Add function call trampolines
@@ -182,6 +191,7 @@ pub fn emit_compilation(
         });
         let section_id = obj.section_id(StandardSection::Text);
         obj.add_symbol_data(symbol_id, section_id, &function.body, 1);
+        windows_unwind_infos.extend(windows_unwind_info(symbol_id, function));
     }
 
     // Add dynamic function trampolines
@@ -200,6 +210,11 @@ pub fn emit_compilation(
         });
         let section_id = obj.section_id(StandardSection::Text);
         obj.add_symbol_data(symbol_id, section_id, &function.body, 1);
+        windows_unwind_infos.extend(windows_unwind_info(symbol_id, function));
+    }
+
+    if triple.binary_format == BinaryFormat::Coff {
+        emit_windows_unwind_infos(obj, windows_unwind_infos)?;
     }
 
     // Add relocations (function and sections)
@@ -314,3 +329,63 @@ pub fn emit_compilation(
 
     Ok(())
 }
+
+/// The Windows unwind information of a function: the symbol of the
+/// function, its length, and its `UNWIND_INFO`.
+type WindowsUnwindInfo = (SymbolId, u32, Vec<u8>);
+
+/// Returns the Windows unwind information of `function`, whose symbol
+/// is `symbol_id`, if it has any.
+fn windows_unwind_info(symbol_id: SymbolId, function: FunctionBody) -> Option<WindowsUnwindInfo> {
+    match function.unwind_info {
+        Some(CompiledFunctionUnwindInfo::WindowsX64(info)) => {
+            Some((symbol_id, function.body.len() as u32, info))
+        }
+        _ => None,
+    }
+}
+
+/// Emits the Windows unwind information of the functions: their
+/// `UNWIND_INFO` in the `.xdata` section, and a `RUNTIME_FUNCTION`
+/// referring to it in the `.pdata` section.
+///
+/// The linker gathers the `.pdata` sections in the exception
+/// directory of the image, which the loader registers, so that traps
+/// and backtraces can unwind through the functions.
+fn emit_windows_unwind_infos(
+    obj: &mut Object,
+    unwind_infos: Vec<WindowsUnwindInfo>,
+) -> Result<(), ObjectError> {
+    if unwind_infos.is_empty() {
+        return Ok(());
+    }
+    let pdata = obj.add_section(vec![], b".pdata".to_vec(), SectionKind::ReadOnlyData);
+    let xdata = obj.add_section(vec![], b".xdata".to_vec(), SectionKind::ReadOnlyData);
+    let xdata_symbol = obj.section_symbol(xdata);
+    for (symbol_id, length, info) in unwind_infos {
+        let info_offset = obj.append_section_data(xdata, &info, 4);
+        // A `RUNTIME_FUNCTION` holds the begin and end addresses of the
+        // function, and the address of its `UNWIND_INFO`, all relative
+        // to the image base.
+        let offset = obj.append_section_data(pdata, &[0; 12], 4);
+        for (field_offset, symbol, addend) in &[
+            (0, symbol_id, 0),
+            (4, symbol_id, i64::from(length)),
+            (8, xdata_symbol, info_offset as i64),
+        ] {
+            obj.add_relocation(
+                pdata,
+                Relocation {
+                    offset: offset + field_offset,
+                    size: 32,
+                    kind: RelocationKind::ImageOffset,
+                    encoding: RelocationEncoding::Generic,
+                    symbol: *symbol,
+                    addend: *addend,
+                },
+            )
+            .map_err(ObjectError::Write)?;
+        }
+    }
+    Ok(())
+}
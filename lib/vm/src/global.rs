@@ -1,4 +1,4 @@
-use crate::vmcontext::VMGlobalDefinition;
+use crate::vmcontext::{VMCallerCheckedAnyfunc, VMGlobalDefinition};
 use std::cell::UnsafeCell;
 use std::ptr::NonNull;
 use std::sync::Mutex;
@@ -13,6 +13,9 @@ pub struct Global {
     vm_global_definition: Box<UnsafeCell<VMGlobalDefinition>>,
     // used to synchronize gets/sets
     lock: Mutex<()>,
+    // the functions referenced by a `funcref` global, kept while the
+    // code may still hold pointers to them
+    anyfuncs: Mutex<Vec<Box<VMCallerCheckedAnyfunc>>>,
 }
 
 /// # Safety
@@ -48,6 +51,7 @@ impl Global {
             ty: global_type,
             vm_global_definition: Box::new(UnsafeCell::new(VMGlobalDefinition::new())),
             lock: Mutex::new(()),
+            anyfuncs: Mutex::new(Vec::new()),
         }
     }
 
@@ -116,4 +120,65 @@ impl Global {
         }
         Ok(())
     }
+
+    /// Get the function referenced by a `funcref` global, `None` for a
+    /// null reference.
+    pub fn get_anyfunc(&self) -> Result<Option<VMCallerCheckedAnyfunc>, GlobalError> {
+        let _global_guard = self.lock.lock().unwrap();
+        if self.ty().ty != Type::FuncRef {
+            return Err(GlobalError::IncorrectType {
+                expected: self.ty.ty,
+                found: Type::FuncRef,
+            });
+        }
+        unsafe {
+            let definition = &*self.vm_global_definition.get();
+            Ok(definition.to_anyfunc().as_ref().cloned())
+        }
+    }
+
+    /// Set the function referenced by a `funcref` global, `None` for a
+    /// null reference.
+    ///
+    /// # Safety
+    /// The caller should check that the function comes from the same store as this global.
+    pub unsafe fn set_anyfunc(
+        &self,
+        anyfunc: Option<VMCallerCheckedAnyfunc>,
+    ) -> Result<(), GlobalError> {
+        let _global_guard = self.lock.lock().unwrap();
+        if self.ty().mutability != Mutability::Var {
+            return Err(GlobalError::ImmutableGlobalCannotBeSet);
+        }
+        self.set_anyfunc_unchecked(anyfunc)
+    }
+
+    /// Set the function referenced by a `funcref` global (unchecked)
+    ///
+    /// # Safety
+    /// The caller should check that the function comes from the same store as this global.
+    /// The caller should also ensure that this global is synchronized. Otherwise, use
+    /// `set_anyfunc` instead.
+    pub unsafe fn set_anyfunc_unchecked(
+        &self,
+        anyfunc: Option<VMCallerCheckedAnyfunc>,
+    ) -> Result<(), GlobalError> {
+        if self.ty().ty != Type::FuncRef {
+            return Err(GlobalError::IncorrectType {
+                expected: self.ty.ty,
+                found: Type::FuncRef,
+            });
+        }
+        let definition = &mut *self.vm_global_definition.get();
+        *definition.as_anyfunc_mut() = match anyfunc {
+            Some(anyfunc) => {
+                let anyfunc = Box::new(anyfunc);
+                let ptr: *const VMCallerCheckedAnyfunc = &*anyfunc;
+                self.anyfuncs.lock().unwrap().push(anyfunc);
+                ptr
+            }
+            None => std::ptr::null(),
+        };
+        Ok(())
+    }
 }
@@ -0,0 +1,209 @@
+//! Tracking of the threads executing the code of an instance, so it
+//! can be torn down once none of them is running.
+
+use crate::vmcontext::VMGlobalDefinition;
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::thread::{self, ThreadId};
+use std::time::{Duration, Instant};
+
+/// The number of calls in progress in an instance.
+#[derive(Debug, Default)]
+pub(crate) struct ExecutionState {
+    active_calls: AtomicUsize,
+    /// Locked when the last call returns, and while waiting for it,
+    /// so the notification can't be missed.
+    lock: Mutex<()>,
+    idle: Condvar,
+    /// The thread running the exclusive calls in progress, and their
+    /// number.
+    owner: Mutex<Option<(ThreadId, usize)>>,
+}
+
+impl ExecutionState {
+    pub(crate) fn enter(&self) {
+        self.active_calls.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub(crate) fn exit(&self) {
+        if self.active_calls.fetch_sub(1, Ordering::SeqCst) == 1 {
+            let _lock = self.lock.lock().unwrap();
+            self.idle.notify_all();
+        }
+    }
+
+    /// Enters an exclusive call, unless another thread is running
+    /// exclusive calls. The calls of the thread running them can
+    /// re-enter the instance.
+    pub(crate) fn enter_exclusive(&self) -> bool {
+        let current = thread::current().id();
+        let mut owner = self.owner.lock().unwrap();
+        match &mut *owner {
+            Some((thread, calls)) if *thread == current => *calls += 1,
+            Some(_) => return false,
+            None => *owner = Some((current, 1)),
+        }
+        self.enter();
+        true
+    }
+
+    pub(crate) fn exit_exclusive(&self) {
+        {
+            let mut owner = self.owner.lock().unwrap();
+            if let Some((_, calls)) = &mut *owner {
+                *calls -= 1;
+                if *calls == 0 {
+                    *owner = None;
+                }
+            }
+        }
+        self.exit();
+    }
+
+    pub(crate) fn is_executing(&self) -> bool {
+        self.active_calls.load(Ordering::SeqCst) != 0
+    }
+
+    pub(crate) fn wait_idle(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut lock = self.lock.lock().unwrap();
+        while self.is_executing() {
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            lock = self.idle.wait_timeout(lock, deadline - now).unwrap().0;
+        }
+        true
+    }
+}
+
+/// Marks a call in progress in an instance, until it's dropped.
+///
+/// See [`InstanceRef::enter`].
+///
+/// [`InstanceRef::enter`]: super::InstanceRef::enter
+#[derive(Debug)]
+pub struct ExecutionGuard<'a> {
+    state: &'a ExecutionState,
+    exclusive: bool,
+}
+
+impl<'a> ExecutionGuard<'a> {
+    pub(super) fn new(state: &'a ExecutionState) -> Self {
+        state.enter();
+        Self {
+            state,
+            exclusive: false,
+        }
+    }
+
+    pub(super) fn new_exclusive(state: &'a ExecutionState) -> Option<Self> {
+        if !state.enter_exclusive() {
+            return None;
+        }
+        Some(Self {
+            state,
+            exclusive: true,
+        })
+    }
+}
+
+impl<'a> Drop for ExecutionGuard<'a> {
+    fn drop(&mut self) {
+        if self.exclusive {
+            self.state.exit_exclusive();
+        } else {
+            self.state.exit();
+        }
+    }
+}
+
+/// The values of globals of an instance saved when a call enters its
+/// code, restored when it's dropped, whether the call returned or
+/// trapped.
+///
+/// See [`InstanceRef::save_middleware_globals`].
+///
+/// [`InstanceRef::save_middleware_globals`]: super::InstanceRef::save_middleware_globals
+#[derive(Debug)]
+pub struct SavedGlobals<'a> {
+    globals: Vec<(NonNull<VMGlobalDefinition>, VMGlobalDefinition)>,
+    instance: PhantomData<&'a ()>,
+}
+
+impl<'a> SavedGlobals<'a> {
+    /// # Safety
+    ///
+    /// The definitions must be the ones of globals of an instance
+    /// living for `'a`.
+    pub(super) unsafe fn new(
+        definitions: impl Iterator<Item = NonNull<VMGlobalDefinition>>,
+    ) -> Self {
+        Self {
+            globals: definitions
+                .map(|definition| (definition, definition.as_ref().clone()))
+                .collect(),
+            instance: PhantomData,
+        }
+    }
+}
+
+impl<'a> Drop for SavedGlobals<'a> {
+    fn drop(&mut self) {
+        for (definition, value) in self.globals.drain(..) {
+            // SAFETY: The instance of the globals outlives `self`.
+            unsafe { *definition.as_ptr() = value };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn wait_idle() {
+        let state = Arc::new(ExecutionState::default());
+        assert!(!state.is_executing());
+        assert!(state.wait_idle(Duration::from_millis(0)));
+
+        state.enter();
+        state.enter();
+        assert!(state.is_executing());
+        assert!(!state.wait_idle(Duration::from_millis(10)));
+
+        let waiter = {
+            let state = state.clone();
+            thread::spawn(move || state.wait_idle(Duration::from_secs(60)))
+        };
+        state.exit();
+        assert!(state.is_executing());
+        state.exit();
+        assert!(waiter.join().unwrap());
+        assert!(!state.is_executing());
+    }
+
+    #[test]
+    fn exclusive_calls() {
+        let state = Arc::new(ExecutionState::default());
+        let outer = ExecutionGuard::new_exclusive(&state).unwrap();
+        let inner = ExecutionGuard::new_exclusive(&state).unwrap();
+        assert!(state.is_executing());
+
+        let other_thread = {
+            let state = state.clone();
+            move || ExecutionGuard::new_exclusive(&state).is_some()
+        };
+        assert!(!thread::spawn(other_thread.clone()).join().unwrap());
+        drop(inner);
+        assert!(!thread::spawn(other_thread.clone()).join().unwrap());
+        drop(outer);
+        assert!(!state.is_executing());
+        assert!(thread::spawn(other_thread).join().unwrap());
+    }
+}
@@ -8,15 +8,19 @@
 //! wrapper around an `InstanceRef`.
 
 mod allocator;
+mod execution;
 
 pub use allocator::InstanceAllocator;
+pub use execution::{ExecutionGuard, SavedGlobals};
 
 use crate::export::VMExport;
 use crate::global::Global;
 use crate::imports::Imports;
+use crate::libcalls::Libcalls;
 use crate::memory::{Memory, MemoryError};
+use crate::stack_limit::StackLimit;
 use crate::table::Table;
-use crate::trap::{catch_traps, init_traps, Trap, TrapCode};
+use crate::trap::{catch_traps, init_traps_on_instantiation, Trap, TrapCode};
 use crate::vmcontext::{
     VMBuiltinFunctionsArray, VMCallerCheckedAnyfunc, VMContext, VMFunctionBody,
     VMFunctionEnvironment, VMFunctionImport, VMFunctionKind, VMGlobalDefinition, VMGlobalImport,
@@ -25,6 +29,7 @@ use crate::vmcontext::{
 };
 use crate::{FunctionBodyPtr, ModuleInfo, VMOffsets};
 use crate::{VMExportFunction, VMExportGlobal, VMExportMemory, VMExportTable};
+use execution::ExecutionState;
 use memoffset::offset_of;
 use more_asserts::assert_lt;
 use std::alloc::Layout;
@@ -35,7 +40,9 @@ use std::convert::{TryFrom, TryInto};
 use std::ffi;
 use std::fmt;
 use std::ptr::NonNull;
-use std::sync::{atomic, Arc};
+use std::sync::atomic::{self, AtomicUsize};
+use std::sync::Arc;
+use std::time::Duration;
 use std::{mem, ptr, slice};
 use wasmer_types::entity::{packed_option::ReservedValue, BoxedSlice, EntityRef, PrimaryMap};
 use wasmer_types::{
@@ -83,6 +90,9 @@ pub(crate) struct Instance {
     /// empty slice.
     passive_elements: RefCell<HashMap<ElemIndex, Box<[VMCallerCheckedAnyfunc]>>>,
 
+    /// The functions referenced by the globals, which point to them.
+    global_anyfuncs: RefCell<Vec<Box<VMCallerCheckedAnyfunc>>>,
+
     /// Passive data segments from our module. As `data.drop`s happen, entries
     /// get removed. A missing entry is considered equivalent to an empty slice.
     passive_data: RefCell<HashMap<DataIndex, Arc<[u8]>>>,
@@ -100,6 +110,17 @@ pub(crate) struct Instance {
     /// functions from other Wasm modules.
     imported_function_envs: BoxedSlice<FunctionIndex, ImportFunctionEnv>,
 
+    /// The host environments replaced with
+    /// [`InstanceHandle::replace_imported_function_env`], kept until
+    /// the instance is dropped as they may still be in use.
+    retired_function_envs: Vec<ImportFunctionEnv>,
+
+    /// The calls in progress in this instance.
+    execution: ExecutionState,
+
+    /// The stack limit that the `vmctx` points to, if any.
+    stack_limit: Option<Arc<StackLimit>>,
+
     /// Additional context used by compiled WebAssembly code. This
     /// field is last, and represents a dynamically-sized array that
     /// extends beyond the nominal end of the struct (similar to a
@@ -146,6 +167,37 @@ impl ImportFunctionEnv {
             _ => None,
         }
     }
+
+    /// Calls the `initializer` of the host env if it hasn't been
+    /// called yet.
+    ///
+    /// # Safety
+    /// - This function must be called with the correct `Err` type parameter: the error type is not
+    ///   visible to code in `wasmer_vm`, so it's the caller's responsibility to ensure these
+    ///   functions are called with the correct type.
+    /// - `instance_ptr` must point to a valid `wasmer::Instance`.
+    pub unsafe fn initialize<Err: Sized>(
+        &mut self,
+        instance_ptr: *const ffi::c_void,
+    ) -> Result<(), Err> {
+        if let Self::Env {
+            env,
+            ref mut initializer,
+            ..
+        } = self
+        {
+            if let Some(f) = initializer {
+                // transmute our function pointer into one with the correct error type
+                let f = mem::transmute::<
+                    &ImportInitializerFuncPtr,
+                    &fn(*mut ffi::c_void, *const ffi::c_void) -> Result<(), Err>,
+                >(f);
+                f(*env, instance_ptr)?;
+            }
+            *initializer = None;
+        }
+        Ok(())
+    }
 }
 
 impl Clone for ImportFunctionEnv {
@@ -364,6 +416,11 @@ impl Instance {
         unsafe { self.vmctx_plus_offset(self.offsets.vmctx_builtin_functions_begin()) }
     }
 
+    /// Return a pointer to the pointer to the stack limit.
+    fn stack_limit_ptr(&self) -> *mut *const AtomicUsize {
+        unsafe { self.vmctx_plus_offset(self.offsets.vmctx_stack_limit()) }
+    }
+
     /// Return a reference to the vmctx used by compiled wasm code.
     fn vmctx(&self) -> &VMContext {
         &self.vmctx
@@ -409,6 +466,8 @@ impl Instance {
         };
 
         // Make the call.
+        let _execution = ExecutionGuard::new(&self.execution);
+        let _stack_limit = self.stack_limit.as_ref().map(|limit| limit.enter());
         unsafe {
             catch_traps(callee_vmctx, || {
                 mem::transmute::<*const VMFunctionBody, unsafe extern "C" fn(VMFunctionEnvironment)>(
@@ -874,6 +933,62 @@ impl InstanceRef {
     unsafe fn as_mut<'a>(&'a mut self) -> &'a mut Instance {
         self.instance.as_mut()
     }
+
+    /// The host state of the instance, given to
+    /// [`InstanceHandle::new`].
+    pub fn host_state(&self) -> &dyn Any {
+        self.as_ref().host_state()
+    }
+
+    /// Mark a call into the code of the instance as in progress,
+    /// until the returned guard is dropped.
+    pub fn enter(&self) -> ExecutionGuard<'_> {
+        ExecutionGuard::new(&self.as_ref().execution)
+    }
+
+    /// Mark a call into the code of the instance as in progress, like
+    /// [`InstanceRef::enter`], unless another thread is running a call
+    /// entered with this method. Returns `None` in that case.
+    ///
+    /// The calls entered this way run on one thread at a time, and can
+    /// re-enter the instance from the host functions they call.
+    pub fn enter_exclusive(&self) -> Option<ExecutionGuard<'_>> {
+        ExecutionGuard::new_exclusive(&self.as_ref().execution)
+    }
+
+    /// Saves the values of the globals registered under `name` by the
+    /// middlewares of the compiler, see
+    /// [`ModuleInfo::middleware_globals`], to restore them when the
+    /// returned guard is dropped. Returns `None` if the module has no
+    /// such globals.
+    pub fn save_middleware_globals(&self, name: &str) -> Option<SavedGlobals<'_>> {
+        let instance = self.as_ref();
+        let module = instance.module_ref();
+        if module.middleware_globals.is_empty() {
+            return None;
+        }
+        let globals = module.middleware_globals.get(name)?;
+        let definitions = globals.iter().filter_map(|index| {
+            let index = module.local_global_index(*index)?;
+            Some(instance.global_ptr(index))
+        });
+        // SAFETY: The globals belong to the instance, which outlives
+        // the guard.
+        Some(unsafe { SavedGlobals::new(definitions) })
+    }
+
+    /// Whether a call into the code of the instance is in progress,
+    /// on any thread.
+    pub fn is_executing(&self) -> bool {
+        self.as_ref().execution.is_executing()
+    }
+
+    /// Wait until no call into the code of the instance is in
+    /// progress, for at most `timeout`. Returns `false` if the
+    /// timeout elapsed.
+    pub fn wait_idle(&self, timeout: Duration) -> bool {
+        self.as_ref().execution.wait_idle(timeout)
+    }
 }
 
 /// TODO: Review this super carefully.
@@ -1006,6 +1121,10 @@ impl InstanceHandle {
     ///   all the local tables.
     /// - The memory at `instance.memories_ptr()` must be initialized with data for
     ///   all the local memories.
+    ///
+    /// The compiled code of the instance calls `libcalls` for its
+    /// builtin functions, and checks its stack usage against
+    /// `stack_limit` (see [`StackLimit`]).
     #[allow(clippy::too_many_arguments)]
     pub unsafe fn new(
         allocator: InstanceAllocator,
@@ -1019,6 +1138,8 @@ impl InstanceHandle {
         vmshared_signatures: BoxedSlice<SignatureIndex, VMSharedSignatureIndex>,
         host_state: Box<dyn Any>,
         imported_function_envs: BoxedSlice<FunctionIndex, ImportFunctionEnv>,
+        libcalls: &Libcalls,
+        stack_limit: Option<Arc<StackLimit>>,
     ) -> Result<Self, Trap> {
         let vmctx_globals = finished_globals
             .values()
@@ -1039,10 +1160,14 @@ impl InstanceHandle {
                 functions: finished_functions,
                 function_call_trampolines: finished_function_call_trampolines,
                 passive_elements: Default::default(),
+                global_anyfuncs: Default::default(),
                 passive_data,
                 host_state,
                 signal_handler: Cell::new(None),
                 imported_function_envs,
+                retired_function_envs: Vec::new(),
+                execution: Default::default(),
+                stack_limit,
                 vmctx: VMContext {},
             };
 
@@ -1089,11 +1214,18 @@ impl InstanceHandle {
         );
         ptr::write(
             instance.builtin_functions_ptr() as *mut VMBuiltinFunctionsArray,
-            VMBuiltinFunctionsArray::initialized(),
+            VMBuiltinFunctionsArray::from_libcalls(libcalls),
+        );
+        ptr::write(
+            instance.stack_limit_ptr(),
+            match &instance.stack_limit {
+                Some(stack_limit) => stack_limit.limit_ptr(),
+                None => StackLimit::unlimited_ptr(),
+            },
         );
 
         // Ensure that our signal handlers are ready for action.
-        init_traps();
+        init_traps_on_instantiation();
 
         // Perform infallible initialization in this constructor, while fallible
         // initialization is deferred to the `initialize` method.
@@ -1256,6 +1388,18 @@ impl InstanceHandle {
         self.instance().as_ref().host_state()
     }
 
+    /// Return a new reference to the contained `Instance`, to track
+    /// its execution without borrowing `self`.
+    pub fn instance_ref(&self) -> InstanceRef {
+        self.instance.clone()
+    }
+
+    /// Whether a call into the code of the instance is in progress,
+    /// on any thread.
+    pub fn is_executing(&self) -> bool {
+        self.instance().is_executing()
+    }
+
     /// Return the memory index for the given `VMMemoryDefinition` in this instance.
     pub fn memory_index(&self, memory: &VMMemoryDefinition) -> LocalMemoryIndex {
         self.instance().as_ref().memory_index(memory)
@@ -1331,27 +1475,48 @@ impl InstanceHandle {
         let instance_ref = self.instance.as_mut();
 
         for import_function_env in instance_ref.imported_function_envs.values_mut() {
-            match import_function_env {
-                ImportFunctionEnv::Env {
-                    env,
-                    ref mut initializer,
-                    ..
-                } => {
-                    if let Some(f) = initializer {
-                        // transmute our function pointer into one with the correct error type
-                        let f = mem::transmute::<
-                            &ImportInitializerFuncPtr,
-                            &fn(*mut ffi::c_void, *const ffi::c_void) -> Result<(), Err>,
-                        >(f);
-                        f(*env, instance_ptr)?;
-                    }
-                    *initializer = None;
-                }
-                ImportFunctionEnv::NoEnv => (),
-            }
+            import_function_env.initialize::<Err>(instance_ptr)?;
         }
         Ok(())
     }
+
+    /// Replaces the host env of the imported function `index` with
+    /// `env`, for the calls of the function made afterwards by the
+    /// code of the instance. Returns `false`, dropping `env`, if the
+    /// function has no host env to replace.
+    ///
+    /// The host env is replaced atomically: each call uses either the
+    /// previous env or the new one, even when the instance is running
+    /// on other threads. The previous env is kept until the instance
+    /// is dropped, since the calls in progress, and the references to
+    /// the function taken before, like the elements of the tables,
+    /// still use it.
+    ///
+    /// # Safety
+    /// - `env` must be an initialized host env of the same type as
+    ///   the current one, for the same host function.
+    pub unsafe fn replace_imported_function_env(
+        &mut self,
+        index: FunctionIndex,
+        env: ImportFunctionEnv,
+    ) -> bool {
+        let instance = self.instance.as_mut();
+        let new_env = match (&instance.imported_function_envs[index], &env) {
+            (ImportFunctionEnv::Env { .. }, ImportFunctionEnv::Env { env, .. }) => *env,
+            _ => return false,
+        };
+        let import = instance
+            .imported_functions_ptr()
+            .add(usize::try_from(index.as_u32()).unwrap());
+        // The code of the instance reads the env at each call, possibly
+        // on other threads.
+        let environment = &(*import).environment as *const VMFunctionEnvironment
+            as *const atomic::AtomicPtr<ffi::c_void>;
+        (*environment).store(new_env, atomic::Ordering::SeqCst);
+        let previous = mem::replace(&mut instance.imported_function_envs[index], env);
+        instance.retired_function_envs.push(previous);
+        true
+    }
 }
 
 cfg_if::cfg_if! {
@@ -1568,7 +1733,12 @@ fn initialize_globals(instance: &Instance) {
                         };
                     *to = from;
                 }
-                GlobalInit::RefNullConst | GlobalInit::RefFunc(_) => unimplemented!(),
+                GlobalInit::RefNullConst => *(*to).as_anyfunc_mut() = ptr::null(),
+                GlobalInit::RefFunc(index) => {
+                    let anyfunc = Box::new(instance.get_caller_checked_anyfunc(*index));
+                    *(*to).as_anyfunc_mut() = &*anyfunc;
+                    instance.global_anyfuncs.borrow_mut().push(anyfunc);
+                }
             }
         }
     }
@@ -29,6 +29,7 @@ mod mmap;
 mod module;
 mod probestack;
 mod sig_registry;
+mod stack_limit;
 mod table;
 mod trap;
 mod vmcontext;
@@ -40,13 +41,17 @@ pub use crate::export::*;
 pub use crate::global::*;
 pub use crate::imports::Imports;
 pub use crate::instance::{
-    ImportFunctionEnv, ImportInitializerFuncPtr, InstanceAllocator, InstanceHandle,
+    ExecutionGuard, ImportFunctionEnv, ImportInitializerFuncPtr, InstanceAllocator, InstanceHandle,
+    InstanceRef, SavedGlobals,
+};
+pub use crate::memory::{
+    BoundsCheck, CustomMemory, LinearMemory, Memory, MemoryError, MemoryStorage, MemoryStyle,
 };
-pub use crate::memory::{LinearMemory, Memory, MemoryError, MemoryStyle};
 pub use crate::mmap::Mmap;
 pub use crate::module::{ExportsIterator, ImportsIterator, ModuleInfo};
 pub use crate::probestack::PROBESTACK;
 pub use crate::sig_registry::SignatureRegistry;
+pub use crate::stack_limit::{StackLimit, StackLimitGuard};
 pub use crate::table::{LinearTable, Table, TableStyle};
 pub use crate::trap::*;
 pub use crate::vmcontext::{
@@ -402,6 +402,65 @@ pub unsafe extern "C" fn wasmer_raise_trap(trap_code: TrapCode) -> ! {
 #[no_mangle]
 pub static wasmer_probestack: unsafe extern "C" fn() = PROBESTACK;
 
+/// The implementations of the libcalls called by the compiled code
+/// through the `VMContext` of an instance.
+///
+/// They default to the `wasmer_*` functions of this module, and can
+/// be replaced with the `Tunables::libcalls` of `wasmer-engine`, e.g.
+/// to instrument them or to use hardware-accelerated versions. A
+/// replacement is called with the same arguments and must have the
+/// same semantics, which it can get by calling the default
+/// implementation.
+#[derive(Debug, Clone, Copy)]
+pub struct Libcalls {
+    /// `memory.grow` of a local memory, see [`wasmer_memory32_grow`].
+    pub memory32_grow: unsafe extern "C" fn(*mut VMContext, u32, u32) -> u32,
+    /// `memory.grow` of an imported memory, see [`wasmer_imported_memory32_grow`].
+    pub imported_memory32_grow: unsafe extern "C" fn(*mut VMContext, u32, u32) -> u32,
+    /// `memory.size` of a local memory, see [`wasmer_memory32_size`].
+    pub memory32_size: unsafe extern "C" fn(*mut VMContext, u32) -> u32,
+    /// `memory.size` of an imported memory, see [`wasmer_imported_memory32_size`].
+    pub imported_memory32_size: unsafe extern "C" fn(*mut VMContext, u32) -> u32,
+    /// `table.copy`, see [`wasmer_table_copy`].
+    pub table_copy: unsafe extern "C" fn(*mut VMContext, u32, u32, u32, u32, u32),
+    /// `table.init`, see [`wasmer_table_init`].
+    pub table_init: unsafe extern "C" fn(*mut VMContext, u32, u32, u32, u32, u32),
+    /// `elem.drop`, see [`wasmer_elem_drop`].
+    pub elem_drop: unsafe extern "C" fn(*mut VMContext, u32),
+    /// `memory.copy` of a local memory, see [`wasmer_local_memory_copy`].
+    pub local_memory_copy: unsafe extern "C" fn(*mut VMContext, u32, u32, u32, u32),
+    /// `memory.copy` of an imported memory, see [`wasmer_imported_memory_copy`].
+    pub imported_memory_copy: unsafe extern "C" fn(*mut VMContext, u32, u32, u32, u32),
+    /// `memory.fill` of a local memory, see [`wasmer_memory_fill`].
+    pub memory_fill: unsafe extern "C" fn(*mut VMContext, u32, u32, u32, u32),
+    /// `memory.fill` of an imported memory, see [`wasmer_imported_memory_fill`].
+    pub imported_memory_fill: unsafe extern "C" fn(*mut VMContext, u32, u32, u32, u32),
+    /// `memory.init`, see [`wasmer_memory_init`].
+    pub memory_init: unsafe extern "C" fn(*mut VMContext, u32, u32, u32, u32, u32),
+    /// `data.drop`, see [`wasmer_data_drop`].
+    pub data_drop: unsafe extern "C" fn(*mut VMContext, u32),
+}
+
+impl Default for Libcalls {
+    fn default() -> Self {
+        Self {
+            memory32_grow: wasmer_memory32_grow,
+            imported_memory32_grow: wasmer_imported_memory32_grow,
+            memory32_size: wasmer_memory32_size,
+            imported_memory32_size: wasmer_imported_memory32_size,
+            table_copy: wasmer_table_copy,
+            table_init: wasmer_table_init,
+            elem_drop: wasmer_elem_drop,
+            local_memory_copy: wasmer_local_memory_copy,
+            imported_memory_copy: wasmer_imported_memory_copy,
+            memory_fill: wasmer_memory_fill,
+            imported_memory_fill: wasmer_imported_memory_fill,
+            memory_init: wasmer_memory_init,
+            data_drop: wasmer_data_drop,
+        }
+    }
+}
+
 /// The name of a runtime library routine.
 ///
 /// This list is likely to grow over time.
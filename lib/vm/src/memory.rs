@@ -95,6 +95,58 @@ impl MemoryStyle {
     }
 }
 
+/// How the compiled code checks that the accesses to a linear memory
+/// are in bounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BoundsCheck {
+    /// Rely on the reserved address space and the guard pages of the
+    /// [`MemoryStyle`] wherever they catch the out of bounds accesses,
+    /// and check explicitly otherwise.
+    GuardPages,
+    /// Check every access explicitly against the current size of the
+    /// memory, without relying on the guard pages.
+    Explicit,
+    /// Rely on the guard pages for the accesses with a constant offset
+    /// up to `max_guarded_offset` bytes, and check the accesses with a
+    /// larger offset explicitly.
+    Hybrid {
+        /// The largest constant offset left to the guard pages.
+        max_guarded_offset: u64,
+    },
+}
+
+impl Default for BoundsCheck {
+    fn default() -> Self {
+        Self::GuardPages
+    }
+}
+
+impl BoundsCheck {
+    /// Returns the size of the offset guard the compiled code can rely
+    /// on for a memory of the given style.
+    pub fn offset_guard_size(&self, style: &MemoryStyle) -> u64 {
+        match self {
+            Self::GuardPages => style.offset_guard_size(),
+            Self::Explicit => 0,
+            Self::Hybrid { max_guarded_offset } => {
+                (*max_guarded_offset).min(style.offset_guard_size())
+            }
+        }
+    }
+
+    /// Returns whether an access with the constant `offset` to a memory
+    /// of the given style is checked explicitly.
+    pub fn is_explicit(&self, style: &MemoryStyle, offset: u64) -> bool {
+        match (self, style) {
+            (_, MemoryStyle::Dynamic { .. }) | (Self::Explicit, _) => true,
+            (Self::GuardPages, MemoryStyle::Static { .. }) => false,
+            (Self::Hybrid { max_guarded_offset }, MemoryStyle::Static { .. }) => {
+                offset > *max_guarded_offset
+            }
+        }
+    }
+}
+
 /// Trait for implementing Wasm Memory used by Wasmer.
 pub trait Memory: fmt::Debug + Send + Sync {
     /// Returns the memory type for this memory.
@@ -412,3 +464,252 @@ impl Memory for LinearMemory {
         unsafe { self.get_vm_memory_definition() }
     }
 }
+
+/// The host-owned storage of the bytes of a [`CustomMemory`], like a
+/// buffer or a file mapping.
+///
+/// # Safety
+///
+/// The pointer returned by `as_mut_ptr` must be valid for reads and
+/// writes of `len()` bytes, and must not change until the storage
+/// grows or is dropped.
+pub unsafe trait MemoryStorage: fmt::Debug + Send + 'static {
+    /// Returns a pointer to the first byte of the storage.
+    fn as_mut_ptr(&mut self) -> *mut u8;
+
+    /// Returns the number of bytes of the storage.
+    fn len(&self) -> usize;
+
+    /// Returns whether the storage is empty.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Makes the storage at least `len` bytes long, possibly moving
+    /// it. The new bytes don't have to be zeroed. Returns `false` if
+    /// the storage can't grow, which is the default.
+    fn grow(&mut self, len: usize) -> bool {
+        let _ = len;
+        false
+    }
+}
+
+unsafe impl MemoryStorage for Vec<u8> {
+    fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.as_mut_slice().as_mut_ptr()
+    }
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn grow(&mut self, len: usize) -> bool {
+        self.resize(len, 0);
+        true
+    }
+}
+
+/// A linear memory whose bytes are held by a [`MemoryStorage`] of the
+/// host.
+///
+/// There are no guard pages around the storage, so the memory must
+/// have the [`MemoryStyle::Dynamic`] style without an offset guard:
+/// all the accesses of the compiled code are bounds-checked.
+///
+/// The bytes of the storage in the minimum size of the memory are kept,
+/// so a storage can hold the memory of a previous execution. It grows
+/// when the memory outgrows it.
+#[derive(Debug)]
+pub struct CustomMemory {
+    /// The storage, and the current size of the memory.
+    storage: Mutex<(Box<dyn MemoryStorage>, Pages)>,
+
+    /// The WebAssembly linear memory description.
+    memory: MemoryType,
+
+    /// The style of the memory, always dynamic.
+    style: MemoryStyle,
+
+    /// The memory definition used by the generated code.
+    vm_memory_definition: VMMemoryDefinitionOwnership,
+}
+
+/// This is correct because the definition is only changed with the
+/// storage locked, like for `LinearMemory`.
+unsafe impl Send for CustomMemory {}
+
+/// This is correct because all internal mutability is protected by a mutex.
+unsafe impl Sync for CustomMemory {}
+
+impl CustomMemory {
+    /// Creates a memory held by `storage`, with owned metadata: this
+    /// can be used to create a memory that will be imported into Wasm
+    /// modules.
+    pub fn new(
+        memory: &MemoryType,
+        style: &MemoryStyle,
+        storage: impl MemoryStorage,
+    ) -> Result<Self, MemoryError> {
+        unsafe { Self::new_internal(memory, style, Box::new(storage), None) }
+    }
+
+    /// Creates a memory held by `storage`, with metadata owned by a VM,
+    /// pointed to by `vm_memory_location`: this can be used to create
+    /// a local memory, from the `create_vm_memory` of custom tunables.
+    ///
+    /// # Safety
+    /// - `vm_memory_location` must point to a valid location in VM memory.
+    pub unsafe fn from_definition(
+        memory: &MemoryType,
+        style: &MemoryStyle,
+        storage: impl MemoryStorage,
+        vm_memory_location: NonNull<VMMemoryDefinition>,
+    ) -> Result<Self, MemoryError> {
+        Self::new_internal(memory, style, Box::new(storage), Some(vm_memory_location))
+    }
+
+    unsafe fn new_internal(
+        memory: &MemoryType,
+        style: &MemoryStyle,
+        mut storage: Box<dyn MemoryStorage>,
+        vm_memory_location: Option<NonNull<VMMemoryDefinition>>,
+    ) -> Result<Self, MemoryError> {
+        if *style
+            != (MemoryStyle::Dynamic {
+                offset_guard_size: 0,
+            })
+        {
+            return Err(MemoryError::InvalidMemory {
+                reason: format!(
+                    "a custom memory must be dynamic, without an offset guard, not {:?}",
+                    style
+                ),
+            });
+        }
+        if memory.minimum > Pages::max_value() {
+            return Err(MemoryError::MinimumMemoryTooLarge {
+                min_requested: memory.minimum,
+                max_allowed: Pages::max_value(),
+            });
+        }
+        if let Some(max) = memory.maximum {
+            if max > Pages::max_value() {
+                return Err(MemoryError::MaximumMemoryTooLarge {
+                    max_requested: max,
+                    max_allowed: Pages::max_value(),
+                });
+            }
+            if max < memory.minimum {
+                return Err(MemoryError::InvalidMemory {
+                    reason: format!(
+                        "the maximum ({} pages) is less than the minimum ({} pages)",
+                        max.0, memory.minimum.0
+                    ),
+                });
+            }
+        }
+
+        let minimum_bytes = memory.minimum.bytes().0;
+        if storage.len() < minimum_bytes && !storage.grow(minimum_bytes) {
+            return Err(MemoryError::InvalidMemory {
+                reason: format!(
+                    "the storage is smaller than the minimum ({} pages)",
+                    memory.minimum.0
+                ),
+            });
+        }
+        let definition = VMMemoryDefinition {
+            base: storage.as_mut_ptr(),
+            current_length: minimum_bytes.try_into().unwrap(),
+        };
+        Ok(Self {
+            storage: Mutex::new((storage, memory.minimum)),
+            memory: *memory,
+            style: style.clone(),
+            vm_memory_definition: match vm_memory_location {
+                Some(mut mem_loc) => {
+                    *mem_loc.as_mut() = definition;
+                    VMMemoryDefinitionOwnership::VMOwned(mem_loc)
+                }
+                None => {
+                    VMMemoryDefinitionOwnership::HostOwned(Box::new(UnsafeCell::new(definition)))
+                }
+            },
+        })
+    }
+
+    /// Get the `VMMemoryDefinition`.
+    ///
+    /// # Safety
+    /// - You must ensure that you have mutually exclusive access before calling
+    ///   this function. You can get this by locking the `storage` mutex.
+    unsafe fn get_vm_memory_definition(&self) -> NonNull<VMMemoryDefinition> {
+        match &self.vm_memory_definition {
+            VMMemoryDefinitionOwnership::VMOwned(ptr) => *ptr,
+            VMMemoryDefinitionOwnership::HostOwned(boxed_ptr) => {
+                NonNull::new_unchecked(boxed_ptr.get())
+            }
+        }
+    }
+}
+
+impl Memory for CustomMemory {
+    fn ty(&self) -> &MemoryType {
+        &self.memory
+    }
+
+    fn style(&self) -> &MemoryStyle {
+        &self.style
+    }
+
+    fn size(&self) -> Pages {
+        self.storage.lock().unwrap().1
+    }
+
+    fn grow(&self, delta: Pages) -> Result<Pages, MemoryError> {
+        let mut guard = self.storage.lock().unwrap();
+        let (storage, size) = &mut *guard;
+        let prev_pages = *size;
+        if delta.0 == 0 {
+            return Ok(prev_pages);
+        }
+        let could_not_grow = MemoryError::CouldNotGrow {
+            current: prev_pages,
+            attempted_delta: delta,
+        };
+        let new_pages = match prev_pages.checked_add(delta) {
+            Some(new_pages) if new_pages < Pages::max_value() => new_pages,
+            _ => return Err(could_not_grow),
+        };
+        if let Some(maximum) = self.memory.maximum {
+            if new_pages > maximum {
+                return Err(could_not_grow);
+            }
+        }
+
+        let prev_bytes = prev_pages.bytes().0;
+        let new_bytes = new_pages.bytes().0;
+        if storage.len() < new_bytes && !storage.grow(new_bytes) {
+            return Err(could_not_grow);
+        }
+        let base = storage.as_mut_ptr();
+        // The storage may hold bytes past the previous size.
+        unsafe {
+            std::ptr::write_bytes(base.add(prev_bytes), 0, new_bytes - prev_bytes);
+        }
+        *size = new_pages;
+
+        unsafe {
+            let mut md_ptr = self.get_vm_memory_definition();
+            let md = md_ptr.as_mut();
+            md.current_length = new_bytes.try_into().unwrap();
+            md.base = base;
+        }
+        Ok(prev_pages)
+    }
+
+    fn vmmemory(&self) -> NonNull<VMMemoryDefinition> {
+        let _guard = self.storage.lock().unwrap();
+        unsafe { self.get_vm_memory_definition() }
+    }
+}
@@ -81,6 +81,9 @@ pub struct ModuleInfo {
     /// WebAssembly function names.
     pub function_names: HashMap<FunctionIndex, String>,
 
+    /// WebAssembly local names, by function and by local index.
+    pub local_names: HashMap<FunctionIndex, HashMap<u32, String>>,
+
     /// WebAssembly function signatures.
     pub signatures: PrimaryMap<SignatureIndex, FunctionType>,
 
@@ -102,6 +105,17 @@ pub struct ModuleInfo {
     /// The data for each CustomSection in the module.
     pub custom_sections_data: PrimaryMap<CustomSectionIndex, Arc<[u8]>>,
 
+    /// The globals injected by the middlewares to keep their state, by
+    /// the name they registered them under. They aren't exported, so
+    /// the host reads them through the instances.
+    pub middleware_globals: IndexMap<String, Vec<GlobalIndex>>,
+
+    /// The offset of the code section contents in the original binary.
+    ///
+    /// The addresses in the DWARF sections of a module are relative
+    /// to it.
+    pub code_section_offset: usize,
+
     /// Number of imported functions in the module.
     pub num_imported_functions: usize,
 
@@ -129,6 +143,7 @@ impl ModuleInfo {
             passive_data: HashMap::new(),
             global_initializers: PrimaryMap::new(),
             function_names: HashMap::new(),
+            local_names: HashMap::new(),
             signatures: PrimaryMap::new(),
             functions: PrimaryMap::new(),
             tables: PrimaryMap::new(),
@@ -140,6 +155,8 @@ impl ModuleInfo {
             num_imported_globals: 0,
             custom_sections: IndexMap::new(),
             custom_sections_data: PrimaryMap::new(),
+            middleware_globals: IndexMap::new(),
+            code_section_offset: 0,
         }
     }
 
@@ -238,6 +255,14 @@ impl ModuleInfo {
             })
     }
 
+    /// Get the names and the data of all the custom sections of the
+    /// module, in the order they appear.
+    pub fn all_custom_sections(&self) -> impl Iterator<Item = (&str, Arc<[u8]>)> + '_ {
+        self.custom_sections
+            .iter()
+            .map(move |(name, index)| (name.as_str(), self.custom_sections_data[*index].clone()))
+    }
+
     /// Convert a `LocalFunctionIndex` into a `FunctionIndex`.
     pub fn func_index(&self, local_func: LocalFunctionIndex) -> FunctionIndex {
         FunctionIndex::new(self.num_imported_functions + local_func.index())
@@ -321,6 +346,18 @@ impl ModuleInfo {
         }
     }
 
+    /// Get the name of a function, from the `name` section if present,
+    /// falling back to the name it's exported with.
+    pub fn function_name(&self, index: FunctionIndex) -> Option<&str> {
+        if let Some(name) = self.function_names.get(&index) {
+            return Some(name);
+        }
+        self.exports.iter().find_map(|(name, export)| match export {
+            ExportIndex::Function(function) if *function == index => Some(name.as_str()),
+            _ => None,
+        })
+    }
+
     /// Get the imported function types of the module.
     pub fn imported_function_types<'a>(&'a self) -> impl Iterator<Item = FunctionType> + 'a {
         self.functions
@@ -4,7 +4,7 @@
 //! Implement a registry of function signatures, for fast indirect call
 //! signature checking.
 
-use crate::vmcontext::VMSharedSignatureIndex;
+use crate::vmcontext::{VMSharedSignatureIndex, VMTrampoline};
 use more_asserts::{assert_lt, debug_assert_lt};
 use std::collections::{hash_map, HashMap};
 use std::convert::TryFrom;
@@ -29,6 +29,7 @@ pub struct SignatureRegistry {
 struct Inner {
     signature2index: HashMap<FunctionType, VMSharedSignatureIndex>,
     index2signature: HashMap<VMSharedSignatureIndex, FunctionType>,
+    index2trampoline: HashMap<VMSharedSignatureIndex, VMTrampoline>,
 }
 
 impl SignatureRegistry {
@@ -73,4 +74,35 @@ impl SignatureRegistry {
             .get(&idx)
             .cloned()
     }
+
+    /// Registers a trampoline calling the functions of the signature
+    /// `idx` from the host. The first trampoline registered for a
+    /// signature is kept.
+    ///
+    /// # Safety
+    /// - `trampoline` must be valid as long as this registry, e.g. the
+    ///   engine keeps the code of its artifacts for its lifetime.
+    pub unsafe fn register_trampoline(
+        &self,
+        idx: VMSharedSignatureIndex,
+        trampoline: VMTrampoline,
+    ) {
+        self.inner
+            .write()
+            .unwrap()
+            .index2trampoline
+            .entry(idx)
+            .or_insert(trampoline);
+    }
+
+    /// Looks up a trampoline calling the functions of the signature
+    /// `idx` from the host, registered with `register_trampoline`.
+    pub fn lookup_trampoline(&self, idx: VMSharedSignatureIndex) -> Option<VMTrampoline> {
+        self.inner
+            .read()
+            .unwrap()
+            .index2trampoline
+            .get(&idx)
+            .copied()
+    }
 }
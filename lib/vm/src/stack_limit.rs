@@ -0,0 +1,192 @@
+//! The stack budget of the calls into WebAssembly.
+//!
+//! The compiled functions compare the stack pointer against the limit
+//! that the `vmctx` of their instance points to, in their prologue or
+//! before their calls, and trap with `TrapCode::StackOverflow` when
+//! it's below it. The limit is set when a thread calls into
+//! WebAssembly, to the stack pointer at that time minus the budget, so
+//! the budget bounds the stack used by the whole call, including the
+//! host functions it calls.
+//!
+//! A limit of 0 disables the check. The guard page of the thread
+//! stack still catches the overflows then.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread::{self, ThreadId};
+
+/// The stack limit shared by the instances of a store.
+///
+/// The limit is a single value, so it's only enforced while a single
+/// thread calls into the instances: when several threads do it at the
+/// same time, the check is disabled until a single one is left, and
+/// only the guard pages of their stacks bound their calls.
+#[derive(Debug, Default)]
+pub struct StackLimit {
+    /// The value read by the compiled code.
+    limit: AtomicUsize,
+    /// The budget of a call, in bytes, or 0 if it's unlimited.
+    size: AtomicUsize,
+    /// The threads calling into the instances.
+    threads: Mutex<Vec<EnteredThread>>,
+}
+
+#[derive(Debug)]
+struct EnteredThread {
+    id: ThreadId,
+    /// The number of nested calls of the thread.
+    depth: usize,
+    /// The limit of the thread, set by its outermost call.
+    limit: usize,
+}
+
+/// The stack limit of instances that don't share one, which is never
+/// enforced.
+static NO_STACK_LIMIT: AtomicUsize = AtomicUsize::new(0);
+
+impl StackLimit {
+    /// Creates an unlimited stack limit.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the budget of the calls, in bytes.
+    pub fn size(&self) -> Option<usize> {
+        match self.size.load(Ordering::SeqCst) {
+            0 => None,
+            size => Some(size),
+        }
+    }
+
+    /// Sets the budget of the calls, in bytes, or removes it.
+    ///
+    /// It applies to the next outermost calls, not to the ones in
+    /// progress.
+    pub fn set_size(&self, size: Option<usize>) {
+        self.size.store(size.unwrap_or(0), Ordering::SeqCst);
+    }
+
+    /// Starts a call into WebAssembly from the current thread, which
+    /// is bounded by the budget until the returned guard is dropped.
+    pub fn enter(&self) -> StackLimitGuard<'_> {
+        let size = self.size.load(Ordering::SeqCst);
+        if size == 0 {
+            return StackLimitGuard { stack_limit: None };
+        }
+        // The address of a local is close enough to the stack pointer.
+        let stack_pointer = &size as *const usize as usize;
+        let id = thread::current().id();
+        let mut threads = self.threads.lock().unwrap();
+        match threads.iter_mut().find(|thread| thread.id == id) {
+            Some(thread) => thread.depth += 1,
+            None => threads.push(EnteredThread {
+                id,
+                depth: 1,
+                // Reserve a byte so that a huge budget doesn't disable
+                // the check.
+                limit: stack_pointer.saturating_sub(size).max(1),
+            }),
+        }
+        self.update(&threads);
+        StackLimitGuard {
+            stack_limit: Some(self),
+        }
+    }
+
+    fn exit(&self) {
+        let id = thread::current().id();
+        let mut threads = self.threads.lock().unwrap();
+        if let Some(index) = threads.iter().position(|thread| thread.id == id) {
+            threads[index].depth -= 1;
+            if threads[index].depth == 0 {
+                threads.swap_remove(index);
+            }
+        }
+        self.update(&threads);
+    }
+
+    fn update(&self, threads: &[EnteredThread]) {
+        let limit = match threads {
+            [thread] => thread.limit,
+            _ => 0,
+        };
+        self.limit.store(limit, Ordering::SeqCst);
+    }
+
+    /// Returns the current limit, or 0 if it isn't enforced.
+    pub fn limit(&self) -> usize {
+        self.limit.load(Ordering::SeqCst)
+    }
+
+    /// Returns the pointer to the limit stored in the `vmctx`.
+    pub(crate) fn limit_ptr(&self) -> *const AtomicUsize {
+        &self.limit
+    }
+
+    /// Returns the pointer stored in the `vmctx` of the instances that
+    /// don't have a stack limit.
+    pub(crate) fn unlimited_ptr() -> *const AtomicUsize {
+        &NO_STACK_LIMIT
+    }
+}
+
+/// Ends a call started with [`StackLimit::enter`] when dropped.
+#[derive(Debug)]
+pub struct StackLimitGuard<'a> {
+    stack_limit: Option<&'a StackLimit>,
+}
+
+impl Drop for StackLimitGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(stack_limit) = self.stack_limit {
+            stack_limit.exit();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+    use std::sync::Arc;
+
+    #[test]
+    fn limit_of_the_calls() {
+        let stack_limit = Arc::new(StackLimit::new());
+        {
+            let _guard = stack_limit.enter();
+            assert_eq!(stack_limit.limit(), 0);
+        }
+
+        stack_limit.set_size(Some(1 << 20));
+        let outer = stack_limit.enter();
+        let limit = stack_limit.limit();
+        assert_ne!(limit, 0);
+        {
+            // The nested calls keep the limit of the outermost one.
+            let _inner = stack_limit.enter();
+            assert_eq!(stack_limit.limit(), limit);
+        }
+        assert_eq!(stack_limit.limit(), limit);
+
+        // Another thread disables the limit while it's in a call.
+        let (entered, wait_entered) = channel();
+        let (exit, wait_exit) = channel::<()>();
+        let other = {
+            let stack_limit = stack_limit.clone();
+            std::thread::spawn(move || {
+                let _guard = stack_limit.enter();
+                entered.send(()).unwrap();
+                wait_exit.recv().unwrap();
+            })
+        };
+        wait_entered.recv().unwrap();
+        assert_eq!(stack_limit.limit(), 0);
+        exit.send(()).unwrap();
+        other.join().unwrap();
+        assert_eq!(stack_limit.limit(), limit);
+
+        drop(outer);
+        assert_eq!(stack_limit.limit(), 0);
+    }
+}
@@ -17,7 +17,7 @@ use std::sync::Mutex;
 use wasmer_types::{TableType, Type as ValType};
 
 /// Implementation styles for WebAssembly tables.
-#[derive(Debug, Clone, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum TableStyle {
     /// Signatures are stored in the table and checked in the caller.
     CallerChecksSignature,
@@ -100,6 +100,26 @@ pub trait Table: fmt::Debug + Send + Sync {
 
         Ok(())
     }
+
+    /// Sets the `len` elements of the table starting at `index` to
+    /// `item`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the range is out of bounds of the table.
+    fn fill(&self, index: u32, item: VMCallerCheckedAnyfunc, len: u32) -> Result<(), Trap> {
+        // https://webassembly.github.io/reference-types/core/exec/instructions.html#exec-table-fill
+
+        if index.checked_add(len).map_or(true, |n| n > self.size()) {
+            return Err(Trap::new_from_runtime(TrapCode::TableAccessOutOfBounds));
+        }
+
+        for i in index..index + len {
+            self.set(i, item.clone())?;
+        }
+
+        Ok(())
+    }
 }
 
 /// A table instance.
@@ -7,8 +7,13 @@ mod trapcode;
 mod traphandlers;
 
 pub use trapcode::TrapCode;
+pub(crate) use traphandlers::init_traps_on_instantiation;
+#[cfg(unix)]
+pub use traphandlers::previous_signal_handler;
 pub use traphandlers::{
     catch_traps, catch_traps_with_result, raise_lib_trap, raise_user_trap, wasmer_call_trampoline,
     wasmer_call_trampoline_unchecked, Trap,
 };
-pub use traphandlers::{init_traps, resume_panic};
+pub use traphandlers::{
+    defer_init_traps, init_traps, resume_panic, set_trap_dispatcher, TrapDispatch, TrapDispatcher,
+};
@@ -14,6 +14,7 @@ use std::error::Error;
 use std::io;
 use std::mem;
 use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
 use std::sync::Once;
 
 extern "C" {
@@ -34,6 +35,44 @@ cfg_if::cfg_if! {
         static mut PREV_SIGILL: MaybeUninit<libc::sigaction> = MaybeUninit::uninit();
         static mut PREV_SIGFPE: MaybeUninit<libc::sigaction> = MaybeUninit::uninit();
 
+        /// Decides what to do with a signal before Wasmer handles it,
+        /// see [`set_trap_dispatcher`].
+        pub type TrapDispatcher =
+            dyn Fn(libc::c_int, *const libc::siginfo_t, *const libc::c_void) -> TrapDispatch
+                + Send
+                + Sync;
+
+        /// Returns the slot of the handler that was installed before
+        /// Wasmer's for `signum`, if Wasmer handles it.
+        fn previous_slot(signum: libc::c_int) -> Option<&'static MaybeUninit<libc::sigaction>> {
+            unsafe {
+                match signum {
+                    libc::SIGSEGV => Some(&PREV_SIGSEGV),
+                    libc::SIGILL => Some(&PREV_SIGILL),
+                    libc::SIGFPE if cfg!(target_arch = "x86") || cfg!(target_arch = "x86_64") => {
+                        Some(&PREV_SIGFPE)
+                    }
+                    libc::SIGBUS if cfg!(target_arch = "arm") || cfg!(target_os = "macos") => {
+                        Some(&PREV_SIGBUS)
+                    }
+                    _ => None,
+                }
+            }
+        }
+
+        /// Returns the handler that was installed for `signum` before
+        /// Wasmer's, which gets the signals that aren't raised by
+        /// WebAssembly code.
+        ///
+        /// It returns `None` if Wasmer doesn't handle `signum`, or
+        /// hasn't installed its handlers yet (see [`init_traps`]).
+        pub fn previous_signal_handler(signum: libc::c_int) -> Option<libc::sigaction> {
+            if !INSTALLED.load(Ordering::SeqCst) {
+                return None;
+            }
+            previous_slot(signum).map(|slot| unsafe { *slot.as_ptr() })
+        }
+
         unsafe fn platform_init() {
             let register = |slot: &mut MaybeUninit<libc::sigaction>, signal: i32| {
                 let mut handler: libc::sigaction = mem::zeroed();
@@ -106,13 +145,15 @@ cfg_if::cfg_if! {
             siginfo: *mut libc::siginfo_t,
             context: *mut libc::c_void,
         ) {
-            let previous = match signum {
-                libc::SIGSEGV => &PREV_SIGSEGV,
-                libc::SIGBUS => &PREV_SIGBUS,
-                libc::SIGFPE => &PREV_SIGFPE,
-                libc::SIGILL => &PREV_SIGILL,
-                _ => panic!("unknown signal: {}", signum),
+            let previous = match previous_slot(signum) {
+                Some(previous) => &*previous.as_ptr(),
+                None => panic!("unknown signal: {}", signum),
             };
+            match dispatch(|dispatcher| dispatcher(signum, siginfo, context)) {
+                TrapDispatch::Wasmer => {}
+                TrapDispatch::Handled => return,
+                TrapDispatch::Previous => return call_previous(previous, signum, siginfo, context),
+            }
             // We try to get the Code trap associated to this signal
             let maybe_signal_trap = match signum {
                 libc::SIGSEGV | libc::SIGBUS => {
@@ -169,15 +210,26 @@ cfg_if::cfg_if! {
             }
 
             // This signal is not for any compiled wasm code we expect, so we
-            // need to forward the signal to the next handler. If there is no
-            // next handler (SIG_IGN or SIG_DFL), then it's time to crash. To do
-            // this, we set the signal back to its original disposition and
-            // return. This will cause the faulting op to be re-executed which
-            // will crash in the normal way. If there is a next handler, call
-            // it. It will either crash synchronously, fix up the instruction
-            // so that execution can continue and return, or trigger a crash by
-            // returning the signal to it's original disposition and returning.
-            let previous = &*previous.as_ptr();
+            // need to forward the signal to the next handler.
+            call_previous(previous, signum, siginfo, context)
+        }
+
+        /// Forwards a signal to the handler installed before Wasmer's.
+        ///
+        /// If there is no previous handler (SIG_IGN or SIG_DFL), then it's
+        /// time to crash. To do this, we set the signal back to its original
+        /// disposition and return. This will cause the faulting op to be
+        /// re-executed which will crash in the normal way. If there is a
+        /// previous handler, call it. It will either crash synchronously, fix
+        /// up the instruction so that execution can continue and return, or
+        /// trigger a crash by returning the signal to it's original
+        /// disposition and returning.
+        unsafe fn call_previous(
+            previous: &libc::sigaction,
+            signum: libc::c_int,
+            siginfo: *mut libc::siginfo_t,
+            context: *mut libc::c_void,
+        ) {
             if previous.sa_flags & libc::SA_SIGINFO != 0 {
                 mem::transmute::<
                     usize,
@@ -282,6 +334,10 @@ cfg_if::cfg_if! {
         use winapi::um::minwinbase::*;
         use winapi::vc::excpt::*;
 
+        /// Decides what to do with an exception before Wasmer handles
+        /// it, see [`set_trap_dispatcher`].
+        pub type TrapDispatcher = dyn Fn(PEXCEPTION_POINTERS) -> TrapDispatch + Send + Sync;
+
         unsafe fn platform_init() {
             // our trap handler needs to go first, so that we can recover from
             // wasm faults and continue execution, so pass `1` as a true value
@@ -307,6 +363,12 @@ cfg_if::cfg_if! {
                 return EXCEPTION_CONTINUE_SEARCH;
             }
 
+            match dispatch(|dispatcher| dispatcher(exception_info)) {
+                TrapDispatch::Wasmer => {}
+                TrapDispatch::Handled => return EXCEPTION_CONTINUE_EXECUTION,
+                TrapDispatch::Previous => return EXCEPTION_CONTINUE_SEARCH,
+            }
+
             // FIXME: this is what the previous C++ did to make sure that TLS
             // works by the time we execute this trap handling code. This isn't
             // exactly super easy to call from Rust though and it's not clear we
@@ -345,6 +407,16 @@ cfg_if::cfg_if! {
     }
 }
 
+/// Whether the signal handlers are installed.
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// Whether the signal handlers are installed by the first call into
+/// WebAssembly rather than the first instantiation.
+static DEFERRED: AtomicBool = AtomicBool::new(false);
+
+/// The dispatcher set with [`set_trap_dispatcher`], if any.
+static DISPATCHER: AtomicPtr<Box<TrapDispatcher>> = AtomicPtr::new(ptr::null_mut());
+
 /// This function performs the low-overhead signal handler initialization that
 /// we want to do eagerly to ensure a more-deterministic global process state.
 ///
@@ -356,7 +428,69 @@ cfg_if::cfg_if! {
 /// times, having no effect after the first call.
 pub fn init_traps() {
     static INIT: Once = Once::new();
-    INIT.call_once(real_init);
+    INIT.call_once(|| {
+        real_init();
+        INSTALLED.store(true, Ordering::SeqCst);
+    });
+}
+
+/// Defers the installation of the signal handlers from the first
+/// instantiation to the first call into WebAssembly.
+///
+/// The handlers installed by the embedder in between (by a language
+/// runtime, a crash reporter or another JIT) are then the previous
+/// handlers of Wasmer, which forwards them the signals not raised by
+/// WebAssembly code. It has no effect once the handlers are installed.
+pub fn defer_init_traps() {
+    DEFERRED.store(true, Ordering::SeqCst);
+}
+
+/// Installs the signal handlers on the instantiation, unless it's
+/// deferred with [`defer_init_traps`].
+pub(crate) fn init_traps_on_instantiation() {
+    if !DEFERRED.load(Ordering::SeqCst) {
+        init_traps();
+    }
+}
+
+/// What to do with a signal (or an exception on Windows), as decided
+/// by the dispatcher set with [`set_trap_dispatcher`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrapDispatch {
+    /// Wasmer handles the signal: as a trap if it's raised while
+    /// WebAssembly is being executed on the thread, or else by
+    /// forwarding it to the previous handler.
+    Wasmer,
+    /// The dispatcher handled the signal, and the execution resumes
+    /// where it was raised.
+    Handled,
+    /// The signal isn't raised by WebAssembly code: it's forwarded to
+    /// the previous handler (the next exception handler on Windows).
+    Previous,
+}
+
+/// Sets the dispatcher called with each signal Wasmer handles, before
+/// Wasmer handles it, so the embedder can claim the signals raised by
+/// its own code, or let them go to the previous handlers.
+///
+/// It's called in a signal handler, so it must be async-signal-safe.
+/// The dispatcher it replaces, if any, is leaked, as it may still be
+/// running on another thread.
+pub fn set_trap_dispatcher(dispatcher: Option<Box<TrapDispatcher>>) {
+    let dispatcher = dispatcher.map_or(ptr::null_mut(), |dispatcher| {
+        Box::into_raw(Box::new(dispatcher))
+    });
+    DISPATCHER.store(dispatcher, Ordering::SeqCst);
+}
+
+/// Asks the dispatcher, if any, what to do with a signal.
+fn dispatch(call_dispatcher: impl FnOnce(&TrapDispatcher) -> TrapDispatch) -> TrapDispatch {
+    let dispatcher = DISPATCHER.load(Ordering::SeqCst);
+    if dispatcher.is_null() {
+        TrapDispatch::Wasmer
+    } else {
+        call_dispatcher(unsafe { &**dispatcher })
+    }
 }
 
 fn real_init() {
@@ -546,6 +680,9 @@ pub unsafe fn catch_traps<F>(vmctx: VMFunctionEnvironment, mut closure: F) -> Re
 where
     F: FnMut(),
 {
+    // The signal handlers may be deferred until the first call.
+    init_traps();
+
     // Ensure that we have our sigaltstack installed.
     #[cfg(unix)]
     setup_unix_sigaltstack()?;
@@ -907,3 +1044,54 @@ fn setup_unix_sigaltstack() -> Result<(), Trap> {
         }
     }
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    static MARKER_CALLS: AtomicUsize = AtomicUsize::new(0);
+    static DISPATCHER_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    extern "C" fn marker_handler(
+        _signum: libc::c_int,
+        _siginfo: *mut libc::siginfo_t,
+        _context: *mut libc::c_void,
+    ) {
+        MARKER_CALLS.fetch_add(1, Ordering::SeqCst);
+    }
+
+    #[test]
+    fn test_chain_to_previous_handler() {
+        unsafe {
+            let mut marker: libc::sigaction = mem::zeroed();
+            marker.sa_flags = libc::SA_SIGINFO;
+            marker.sa_sigaction = marker_handler as usize;
+            libc::sigemptyset(&mut marker.sa_mask);
+            assert_eq!(libc::sigaction(libc::SIGILL, &marker, ptr::null_mut()), 0);
+        }
+        init_traps();
+        let previous = previous_signal_handler(libc::SIGILL).unwrap();
+        assert_eq!(previous.sa_sigaction, marker_handler as usize);
+
+        set_trap_dispatcher(Some(Box::new(|signum, _, _| {
+            assert_eq!(signum, libc::SIGILL);
+            DISPATCHER_CALLS.fetch_add(1, Ordering::SeqCst);
+            TrapDispatch::Handled
+        })));
+        unsafe { libc::raise(libc::SIGILL) };
+        assert_eq!(DISPATCHER_CALLS.load(Ordering::SeqCst), 1);
+        assert_eq!(MARKER_CALLS.load(Ordering::SeqCst), 0);
+
+        set_trap_dispatcher(Some(Box::new(|_, _, _| TrapDispatch::Previous)));
+        unsafe { libc::raise(libc::SIGILL) };
+        assert_eq!(MARKER_CALLS.load(Ordering::SeqCst), 1);
+
+        // Without a dispatcher, a signal raised outside of WebAssembly
+        // goes to the previous handler too.
+        set_trap_dispatcher(None);
+        unsafe { libc::raise(libc::SIGILL) };
+        assert_eq!(MARKER_CALLS.load(Ordering::SeqCst), 2);
+        assert_eq!(DISPATCHER_CALLS.load(Ordering::SeqCst), 1);
+    }
+}
@@ -6,6 +6,7 @@
 
 use crate::global::Global;
 use crate::instance::Instance;
+use crate::libcalls::Libcalls;
 use crate::memory::Memory;
 use crate::table::Table;
 use crate::trap::{Trap, TrapCode};
@@ -488,6 +489,7 @@ pub union VMGlobalDefinitionStorage {
     as_u64: u64,
     as_f64: f64,
     as_u128: u128,
+    as_anyfunc: *const VMCallerCheckedAnyfunc,
     bytes: [u8; 16],
 }
 
@@ -685,6 +687,27 @@ impl VMGlobalDefinition {
         &mut self.storage.as_u128
     }
 
+    /// Return the value as a pointer to a `VMCallerCheckedAnyfunc`, null
+    /// for a null reference.
+    ///
+    /// If this is not a FuncRef typed global it is unspecified what value is returned.
+    pub fn to_anyfunc(&self) -> *const VMCallerCheckedAnyfunc {
+        unsafe { self.storage.as_anyfunc }
+    }
+
+    /// Return a mutable reference to the value as a pointer to a
+    /// `VMCallerCheckedAnyfunc`.
+    ///
+    /// # Safety
+    ///
+    /// It is the callers responsibility to make sure the global has FuncRef type.
+    /// Until the returned borrow is dropped, reads and writes of this global
+    /// must be done exclusively through this borrow. That includes reads and
+    /// writes of globals inside wasm functions.
+    pub unsafe fn as_anyfunc_mut(&mut self) -> &mut *const VMCallerCheckedAnyfunc {
+        &mut self.storage.as_anyfunc
+    }
+
     /// Return a reference to the value as bytes.
     pub fn to_bytes(&self) -> [u8; 16] {
         unsafe { self.storage.bytes }
@@ -889,41 +912,42 @@ impl VMBuiltinFunctionsArray {
         VMBuiltinFunctionIndex::builtin_functions_total_number() as usize
     }
 
-    pub fn initialized() -> Self {
-        use crate::libcalls::*;
+    /// The builtin functions calling `libcalls`.
+    pub fn from_libcalls(libcalls: &Libcalls) -> Self {
+        use crate::libcalls::wasmer_raise_trap;
 
         let mut ptrs = [0; Self::len()];
 
         ptrs[VMBuiltinFunctionIndex::get_memory32_grow_index().index() as usize] =
-            wasmer_memory32_grow as usize;
+            libcalls.memory32_grow as usize;
         ptrs[VMBuiltinFunctionIndex::get_imported_memory32_grow_index().index() as usize] =
-            wasmer_imported_memory32_grow as usize;
+            libcalls.imported_memory32_grow as usize;
 
         ptrs[VMBuiltinFunctionIndex::get_memory32_size_index().index() as usize] =
-            wasmer_memory32_size as usize;
+            libcalls.memory32_size as usize;
         ptrs[VMBuiltinFunctionIndex::get_imported_memory32_size_index().index() as usize] =
-            wasmer_imported_memory32_size as usize;
+            libcalls.imported_memory32_size as usize;
 
         ptrs[VMBuiltinFunctionIndex::get_table_copy_index().index() as usize] =
-            wasmer_table_copy as usize;
+            libcalls.table_copy as usize;
 
         ptrs[VMBuiltinFunctionIndex::get_table_init_index().index() as usize] =
-            wasmer_table_init as usize;
+            libcalls.table_init as usize;
         ptrs[VMBuiltinFunctionIndex::get_elem_drop_index().index() as usize] =
-            wasmer_elem_drop as usize;
+            libcalls.elem_drop as usize;
 
         ptrs[VMBuiltinFunctionIndex::get_local_memory_copy_index().index() as usize] =
-            wasmer_local_memory_copy as usize;
+            libcalls.local_memory_copy as usize;
         ptrs[VMBuiltinFunctionIndex::get_imported_memory_copy_index().index() as usize] =
-            wasmer_imported_memory_copy as usize;
+            libcalls.imported_memory_copy as usize;
         ptrs[VMBuiltinFunctionIndex::get_memory_fill_index().index() as usize] =
-            wasmer_memory_fill as usize;
+            libcalls.memory_fill as usize;
         ptrs[VMBuiltinFunctionIndex::get_imported_memory_fill_index().index() as usize] =
-            wasmer_imported_memory_fill as usize;
+            libcalls.imported_memory_fill as usize;
         ptrs[VMBuiltinFunctionIndex::get_memory_init_index().index() as usize] =
-            wasmer_memory_init as usize;
+            libcalls.memory_init as usize;
         ptrs[VMBuiltinFunctionIndex::get_data_drop_index().index() as usize] =
-            wasmer_data_drop as usize;
+            libcalls.data_drop as usize;
         ptrs[VMBuiltinFunctionIndex::get_raise_trap_index().index() as usize] =
             wasmer_raise_trap as usize;
 
@@ -346,13 +346,17 @@ impl VMOffsets {
     /// The offset of the `tables` array.
     #[allow(clippy::erasing_op)]
     pub fn vmctx_imported_functions_begin(&self) -> u32 {
-        self.vmctx_signature_ids_begin()
+        let offset = self
+            .vmctx_signature_ids_begin()
             .checked_add(
                 self.num_signature_ids
                     .checked_mul(u32::from(self.size_of_vmshared_signature_index()))
                     .unwrap(),
             )
-            .unwrap()
+            .unwrap();
+        // The signature ids are 4 bytes long, while the imports hold
+        // pointers.
+        align(offset, u32::from(self.pointer_size))
     }
 
     /// The offset of the `tables` array.
@@ -435,10 +439,11 @@ impl VMOffsets {
             .unwrap()
     }
 
-    /// Return the size of the [`VMContext`] allocation.
+    /// The offset of the pointer to the stack limit, see
+    /// [`StackLimit`].
     ///
-    /// [`VMContext`]: crate::vmcontext::VMContext
-    pub fn size_of_vmctx(&self) -> u32 {
+    /// [`StackLimit`]: crate::StackLimit
+    pub fn vmctx_stack_limit(&self) -> u32 {
         self.vmctx_builtin_functions_begin()
             .checked_add(
                 VMBuiltinFunctionIndex::builtin_functions_total_number()
@@ -448,6 +453,15 @@ impl VMOffsets {
             .unwrap()
     }
 
+    /// Return the size of the [`VMContext`] allocation.
+    ///
+    /// [`VMContext`]: crate::vmcontext::VMContext
+    pub fn size_of_vmctx(&self) -> u32 {
+        self.vmctx_stack_limit()
+            .checked_add(u32::from(self.pointer_size))
+            .unwrap()
+    }
+
     /// Return the offset to [`VMSharedSignatureIndex`] index `index`.
     ///
     /// [`VMSharedSignatureIndex`]: crate::vmcontext::VMSharedSignatureIndex
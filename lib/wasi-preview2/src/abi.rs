@@ -0,0 +1,114 @@
+//! Helpers to lift the parameters and lower the results of the host
+//! functions with the canonical ABI of the component model.
+//!
+//! The results which don't fit in a single core value are written by
+//! the host to a return area, whose address is passed by the guest
+//! as the last parameter. The lists and the strings are allocated in
+//! the memory of the guest with its `cabi_realloc` export.
+
+use crate::WasiP2Env;
+use std::convert::TryInto;
+use wasmer::RuntimeError;
+
+/// The discriminant of `result::ok`.
+pub(crate) const RESULT_OK: u8 = 0;
+
+/// The discriminant of `result::err`.
+pub(crate) const RESULT_ERR: u8 = 1;
+
+impl WasiP2Env {
+    /// Copies `len` bytes from the memory, starting at `ptr`.
+    pub(crate) fn read_bytes(&self, ptr: u32, len: u32) -> Result<Vec<u8>, RuntimeError> {
+        let mut buf = vec![0; len as usize];
+        self.memory()
+            .read(ptr.into(), &mut buf)
+            .map_err(|_| RuntimeError::new("out of bounds memory access"))?;
+        Ok(buf)
+    }
+
+    /// Lifts the string of `len` bytes starting at `ptr`.
+    pub(crate) fn read_string(&self, ptr: u32, len: u32) -> Result<String, RuntimeError> {
+        String::from_utf8(self.read_bytes(ptr, len)?)
+            .map_err(|_| RuntimeError::new("invalid utf-8 string"))
+    }
+
+    /// Copies `data` to the memory, starting at `ptr`.
+    pub(crate) fn write_bytes(&self, ptr: u32, data: &[u8]) -> Result<(), RuntimeError> {
+        self.memory()
+            .write(ptr.into(), data)
+            .map_err(|_| RuntimeError::new("out of bounds memory access"))
+    }
+
+    pub(crate) fn write_u8(&self, ptr: u32, value: u8) -> Result<(), RuntimeError> {
+        self.write_bytes(ptr, &[value])
+    }
+
+    pub(crate) fn write_u32(&self, ptr: u32, value: u32) -> Result<(), RuntimeError> {
+        self.write_bytes(ptr, &value.to_le_bytes())
+    }
+
+    pub(crate) fn write_u64(&self, ptr: u32, value: u64) -> Result<(), RuntimeError> {
+        self.write_bytes(ptr, &value.to_le_bytes())
+    }
+
+    /// Allocates `size` bytes aligned to `align` in the memory of the
+    /// guest, with its `cabi_realloc` export.
+    pub(crate) fn alloc(&self, align: u32, size: u32) -> Result<u32, RuntimeError> {
+        if size == 0 {
+            return Ok(align);
+        }
+        let realloc = self
+            .realloc
+            .get_ref()
+            .ok_or_else(|| RuntimeError::new("the instance doesn't export `cabi_realloc`"))?;
+        let ptr = realloc.call(0, 0, align, size)?;
+        if ptr % align != 0 {
+            return Err(RuntimeError::new(
+                "`cabi_realloc` returned an unaligned pointer",
+            ));
+        }
+        Ok(ptr)
+    }
+
+    /// Lowers `data` as a `list<u8>`, and writes its address and its
+    /// length at `ret`.
+    pub(crate) fn store_list(&self, ret: u32, data: &[u8]) -> Result<(), RuntimeError> {
+        let len: u32 = data
+            .len()
+            .try_into()
+            .map_err(|_| RuntimeError::new("list too long"))?;
+        let ptr = self.alloc(1, len)?;
+        self.write_bytes(ptr, data)?;
+        self.write_u32(ret, ptr)?;
+        self.write_u32(ret + 4, len)
+    }
+
+    /// Lifts the `list<u32>` of `len` items starting at `ptr`.
+    pub(crate) fn read_u32_list(&self, ptr: u32, len: u32) -> Result<Vec<u32>, RuntimeError> {
+        let size = len
+            .checked_mul(4)
+            .ok_or_else(|| RuntimeError::new("list too long"))?;
+        Ok(self
+            .read_bytes(ptr, size)?
+            .chunks_exact(4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+            .collect())
+    }
+
+    /// Lowers `items` as a `list<u32>`, and writes its address and its
+    /// length at `ret`.
+    pub(crate) fn store_u32_list(&self, ret: u32, items: &[u32]) -> Result<(), RuntimeError> {
+        let bytes = items
+            .iter()
+            .flat_map(|item| item.to_le_bytes().to_vec())
+            .collect::<Vec<_>>();
+        let size: u32 = bytes
+            .len()
+            .try_into()
+            .map_err(|_| RuntimeError::new("list too long"))?;
+        let ptr = self.alloc(4, size)?;
+        self.write_bytes(ptr, &bytes)?;
+        self.write_u32(ret, ptr)?;
+        self.write_u32(ret + 4, items.len() as u32)
+    }
+}
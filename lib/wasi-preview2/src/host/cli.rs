@@ -0,0 +1,96 @@
+//! `wasi:cli/environment`, `wasi:cli/exit`, the standard streams
+//! of `wasi:cli/stdin`, `wasi:cli/stdout` and `wasi:cli/stderr`, and
+//! the terminals of `wasi:cli/terminal-*`.
+
+use crate::table::Resource;
+use crate::{WasiP2Env, WasiP2Error};
+use std::convert::TryInto;
+use wasmer::RuntimeError;
+
+impl WasiP2Env {
+    /// Lowers `strings` as a `list<string>`, or as a
+    /// `list<tuple<string, string>>` when the strings are the pairs
+    /// of the tuples, and writes its address and its length at `ret`.
+    fn store_string_list(
+        &self,
+        ret: u32,
+        strings: &[String],
+        strings_per_item: u32,
+    ) -> Result<(), RuntimeError> {
+        let size: u32 = strings
+            .len()
+            .checked_mul(8)
+            .and_then(|size| size.try_into().ok())
+            .ok_or_else(|| RuntimeError::new("list too long"))?;
+        let list = self.alloc(4, size)?;
+        for (index, string) in strings.iter().enumerate() {
+            self.store_list(list + index as u32 * 8, string.as_bytes())?;
+        }
+        self.write_u32(ret, list)?;
+        self.write_u32(ret + 4, strings.len() as u32 / strings_per_item)
+    }
+}
+
+pub(crate) fn get_environment(env: &WasiP2Env, ret: u32) -> Result<(), RuntimeError> {
+    let strings = env
+        .state()
+        .envs
+        .iter()
+        .flat_map(|(key, value)| vec![key.clone(), value.clone()])
+        .collect::<Vec<_>>();
+    env.store_string_list(ret, &strings, 2)
+}
+
+pub(crate) fn get_arguments(env: &WasiP2Env, ret: u32) -> Result<(), RuntimeError> {
+    let strings = env.state().args.clone();
+    env.store_string_list(ret, &strings, 1)
+}
+
+/// Exits with the status `result<_, _>`, whose discriminant is the
+/// single parameter: the exit code is `0` for `ok` and `1` for `err`.
+pub(crate) fn exit(_env: &WasiP2Env, status: u32) -> Result<(), RuntimeError> {
+    let code = if status == 0 { 0 } else { 1 };
+    Err(RuntimeError::user(Box::new(WasiP2Error::Exit(code))))
+}
+
+pub(crate) fn get_stdin(env: &WasiP2Env) -> Result<u32, RuntimeError> {
+    let mut state = env.state();
+    let stdin = Box::new(state.stdin.clone());
+    state.table.push(Resource::InputStream(stdin))
+}
+
+pub(crate) fn get_stdout(env: &WasiP2Env) -> Result<u32, RuntimeError> {
+    let mut state = env.state();
+    let stdout = Box::new(state.stdout.clone());
+    state.table.push(Resource::OutputStream(stdout))
+}
+
+pub(crate) fn get_stderr(env: &WasiP2Env) -> Result<u32, RuntimeError> {
+    let mut state = env.state();
+    let stderr = Box::new(state.stderr.clone());
+    state.table.push(Resource::OutputStream(stderr))
+}
+
+/// Lowers the `option<terminal-input>` or `option<terminal-output>`
+/// of a standard stream at `ret`: the standard streams are never
+/// terminals, since they may be redirected by the embedder.
+pub(crate) fn get_terminal(env: &WasiP2Env, ret: u32) -> Result<(), RuntimeError> {
+    // `option::none`
+    env.write_u8(ret, 0)
+}
+
+/// Drops a `terminal-input`, which is never handed to the guest.
+pub(crate) fn terminal_input_drop(env: &WasiP2Env, terminal: u32) -> Result<(), RuntimeError> {
+    env.state()
+        .table
+        .delete(terminal, "terminal-input")
+        .map(drop)
+}
+
+/// Drops a `terminal-output`, which is never handed to the guest.
+pub(crate) fn terminal_output_drop(env: &WasiP2Env, terminal: u32) -> Result<(), RuntimeError> {
+    env.state()
+        .table
+        .delete(terminal, "terminal-output")
+        .map(drop)
+}
@@ -0,0 +1,60 @@
+//! `wasi:clocks/wall-clock` and `wasi:clocks/monotonic-clock`.
+
+use crate::host::io::Pollable;
+use crate::WasiP2Env;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use wasmer::RuntimeError;
+
+/// The resolution of the clocks, in nanoseconds.
+const RESOLUTION: u64 = 1;
+
+impl WasiP2Env {
+    /// Lowers a `datetime` of `seconds` and `nanoseconds` at `ret`.
+    fn store_datetime(&self, ret: u32, seconds: u64, nanoseconds: u32) -> Result<(), RuntimeError> {
+        self.write_u64(ret, seconds)?;
+        self.write_u32(ret + 8, nanoseconds)
+    }
+}
+
+pub(crate) fn wall_clock_now(env: &WasiP2Env, ret: u32) -> Result<(), RuntimeError> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|_| RuntimeError::new("the system time is before the Unix epoch"))?;
+    env.store_datetime(ret, now.as_secs(), now.subsec_nanos())
+}
+
+pub(crate) fn wall_clock_resolution(env: &WasiP2Env, ret: u32) -> Result<(), RuntimeError> {
+    env.store_datetime(ret, 0, RESOLUTION as u32)
+}
+
+/// Returns the nanoseconds elapsed since the state has been built.
+pub(crate) fn monotonic_clock_now(env: &WasiP2Env) -> u64 {
+    env.state().start.elapsed().as_nanos() as u64
+}
+
+pub(crate) fn monotonic_clock_resolution(_env: &WasiP2Env) -> u64 {
+    RESOLUTION
+}
+
+/// Returns a pollable ready at the `instant` of the monotonic clock.
+pub(crate) fn monotonic_clock_subscribe_instant(
+    env: &WasiP2Env,
+    instant: u64,
+) -> Result<u32, RuntimeError> {
+    let start = env.state().start;
+    let deadline = start
+        .checked_add(Duration::from_nanos(instant))
+        .ok_or_else(|| RuntimeError::new("the instant is out of range"))?;
+    env.push_pollable(Pollable::Deadline(deadline))
+}
+
+/// Returns a pollable ready once `duration` nanoseconds have elapsed.
+pub(crate) fn monotonic_clock_subscribe_duration(
+    env: &WasiP2Env,
+    duration: u64,
+) -> Result<u32, RuntimeError> {
+    let deadline = Instant::now()
+        .checked_add(Duration::from_nanos(duration))
+        .ok_or_else(|| RuntimeError::new("the duration is out of range"))?;
+    env.push_pollable(Pollable::Deadline(deadline))
+}
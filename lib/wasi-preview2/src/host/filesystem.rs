@@ -0,0 +1,330 @@
+//! `wasi:filesystem/types` and `wasi:filesystem/preopens`.
+//!
+//! The descriptors are confined to the preopened directory they
+//! have been opened from: the paths can't be absolute, and the files
+//! they resolve to, after following the symbolic links, must be in
+//! the preopened directory.
+
+use crate::abi::{RESULT_ERR, RESULT_OK};
+use crate::streams::FileStream;
+use crate::table::Resource;
+use crate::WasiP2Env;
+use std::convert::TryInto;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use wasmer::RuntimeError;
+
+/// The `descriptor-flags::read` flag.
+const DESCRIPTOR_FLAGS_READ: u32 = 1 << 0;
+/// The `descriptor-flags::write` flag.
+const DESCRIPTOR_FLAGS_WRITE: u32 = 1 << 1;
+/// The `descriptor-flags::mutate-directory` flag.
+const DESCRIPTOR_FLAGS_MUTATE_DIRECTORY: u32 = 1 << 5;
+
+/// The `open-flags::create` flag.
+const OPEN_FLAGS_CREATE: u32 = 1 << 0;
+/// The `open-flags::directory` flag.
+const OPEN_FLAGS_DIRECTORY: u32 = 1 << 1;
+/// The `open-flags::exclusive` flag.
+const OPEN_FLAGS_EXCLUSIVE: u32 = 1 << 2;
+/// The `open-flags::truncate` flag.
+const OPEN_FLAGS_TRUNCATE: u32 = 1 << 3;
+
+/// The cases of `error-code` returned by the host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+enum ErrorCode {
+    Access = 0,
+    BadDescriptor = 3,
+    Exist = 7,
+    Invalid = 12,
+    Io = 13,
+    IsDirectory = 14,
+    NoEntry = 20,
+    NotDirectory = 24,
+    NotPermitted = 31,
+}
+
+impl From<io::Error> for ErrorCode {
+    fn from(error: io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::NotFound => Self::NoEntry,
+            io::ErrorKind::PermissionDenied => Self::Access,
+            io::ErrorKind::AlreadyExists => Self::Exist,
+            io::ErrorKind::InvalidInput => Self::Invalid,
+            _ => Self::Io,
+        }
+    }
+}
+
+/// The cases of `descriptor-type`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+enum DescriptorType {
+    Directory = 3,
+    RegularFile = 6,
+}
+
+/// A file or a directory opened by the guest.
+#[derive(Debug)]
+pub(crate) struct Descriptor {
+    /// The canonical path of the preopened directory.
+    root: Arc<PathBuf>,
+    /// The canonical path of the file or the directory.
+    path: PathBuf,
+    /// The opened file, or `None` for a directory.
+    file: Option<File>,
+    /// The `descriptor-flags` of the descriptor.
+    flags: u32,
+}
+
+impl Descriptor {
+    /// Creates the descriptor of the preopened directory `root`,
+    /// which must be canonical.
+    pub(crate) fn preopened(root: Arc<PathBuf>) -> Self {
+        Self {
+            path: root.as_ref().clone(),
+            root,
+            file: None,
+            flags: DESCRIPTOR_FLAGS_READ | DESCRIPTOR_FLAGS_MUTATE_DIRECTORY,
+        }
+    }
+
+    fn file(&self) -> Result<&File, ErrorCode> {
+        self.file.as_ref().ok_or(ErrorCode::IsDirectory)
+    }
+
+    /// Resolves `path` relatively to the directory of the descriptor,
+    /// or fails with `not-permitted` if it's outside of the preopened
+    /// directory.
+    fn resolve(&self, path: &str) -> Result<PathBuf, ErrorCode> {
+        if self.file.is_some() {
+            return Err(ErrorCode::NotDirectory);
+        }
+        let mut resolved = self.path.clone();
+        for component in Path::new(path).components() {
+            match component {
+                Component::Normal(name) => resolved.push(name),
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    if resolved == *self.root {
+                        return Err(ErrorCode::NotPermitted);
+                    }
+                    resolved.pop();
+                }
+                Component::RootDir | Component::Prefix(_) => return Err(ErrorCode::NotPermitted),
+            }
+        }
+        // The symbolic links must not escape the preopened directory:
+        // the path is canonicalized, or its parent if it doesn't exist
+        // yet.
+        let canonical = match resolved.canonicalize() {
+            Ok(canonical) => canonical,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                let name = resolved.file_name().ok_or(ErrorCode::Invalid)?;
+                let parent = resolved.parent().ok_or(ErrorCode::Invalid)?;
+                let canonical = parent.canonicalize()?.join(name);
+                // A dangling symbolic link isn't followed by
+                // `canonicalize`, but would be by `create`, which
+                // could then write anywhere.
+                if canonical.symlink_metadata().is_ok() {
+                    return Err(ErrorCode::NotPermitted);
+                }
+                canonical
+            }
+            Err(error) => return Err(error.into()),
+        };
+        if !canonical.starts_with(self.root.as_ref()) {
+            return Err(ErrorCode::NotPermitted);
+        }
+        Ok(canonical)
+    }
+
+    fn open_at(&self, path: &str, open_flags: u32, flags: u32) -> Result<Self, ErrorCode> {
+        let path = self.resolve(path)?;
+        let creates = open_flags & (OPEN_FLAGS_CREATE | OPEN_FLAGS_TRUNCATE) != 0;
+        if (creates || flags & DESCRIPTOR_FLAGS_WRITE != 0)
+            && self.flags & DESCRIPTOR_FLAGS_MUTATE_DIRECTORY == 0
+        {
+            return Err(ErrorCode::NotPermitted);
+        }
+        if path.is_dir() {
+            if creates || flags & DESCRIPTOR_FLAGS_WRITE != 0 {
+                return Err(ErrorCode::IsDirectory);
+            }
+            return Ok(Self {
+                root: self.root.clone(),
+                path,
+                file: None,
+                flags,
+            });
+        }
+        if open_flags & OPEN_FLAGS_DIRECTORY != 0 {
+            return Err(if path.exists() {
+                ErrorCode::NotDirectory
+            } else {
+                ErrorCode::NoEntry
+            });
+        }
+        let write = flags & DESCRIPTOR_FLAGS_WRITE != 0;
+        let file = OpenOptions::new()
+            .read(flags & DESCRIPTOR_FLAGS_READ != 0 || !write)
+            .write(write)
+            .create(open_flags & OPEN_FLAGS_CREATE != 0)
+            .create_new(open_flags & OPEN_FLAGS_EXCLUSIVE != 0)
+            .truncate(open_flags & OPEN_FLAGS_TRUNCATE != 0)
+            .open(&path)?;
+        Ok(Self {
+            root: self.root.clone(),
+            path,
+            file: Some(file),
+            flags,
+        })
+    }
+}
+
+impl WasiP2Env {
+    /// Lowers `result` as a `result<own<R>, error-code>` at `ret`,
+    /// adding its resource to the table.
+    fn store_resource_result(
+        &self,
+        ret: u32,
+        result: Result<Resource, ErrorCode>,
+    ) -> Result<(), RuntimeError> {
+        match result {
+            Ok(resource) => {
+                let handle = self.state().table.push(resource)?;
+                self.write_u8(ret, RESULT_OK)?;
+                self.write_u32(ret + 4, handle)
+            }
+            Err(code) => {
+                self.write_u8(ret, RESULT_ERR)?;
+                self.write_u8(ret + 4, code as u8)
+            }
+        }
+    }
+}
+
+pub(crate) fn get_directories(env: &WasiP2Env, ret: u32) -> Result<(), RuntimeError> {
+    let preopens = env.state().preopens.clone();
+    let size: u32 = (preopens.len() * 12)
+        .try_into()
+        .map_err(|_| RuntimeError::new("list too long"))?;
+    let list = env.alloc(4, size)?;
+    for (index, (root, name)) in preopens.into_iter().enumerate() {
+        let item = list + index as u32 * 12;
+        let handle = env
+            .state()
+            .table
+            .push(Resource::Descriptor(Descriptor::preopened(root)))?;
+        env.write_u32(item, handle)?;
+        env.store_list(item + 4, name.as_bytes())?;
+    }
+    env.write_u32(ret, list)?;
+    env.write_u32(ret + 4, size / 12)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn descriptor_open_at(
+    env: &WasiP2Env,
+    descriptor: u32,
+    _path_flags: u32,
+    path_ptr: u32,
+    path_len: u32,
+    open_flags: u32,
+    flags: u32,
+    ret: u32,
+) -> Result<(), RuntimeError> {
+    let path = env.read_string(path_ptr, path_len)?;
+    let result = env
+        .state()
+        .table
+        .descriptor(descriptor)?
+        .open_at(&path, open_flags, flags);
+    env.store_resource_result(ret, result.map(Resource::Descriptor))
+}
+
+pub(crate) fn descriptor_get_type(
+    env: &WasiP2Env,
+    descriptor: u32,
+    ret: u32,
+) -> Result<(), RuntimeError> {
+    let descriptor_type = match env.state().table.descriptor(descriptor)?.file {
+        Some(_) => DescriptorType::RegularFile,
+        None => DescriptorType::Directory,
+    };
+    // The payload of the result is aligned to 1 byte by the enums.
+    env.write_u8(ret, RESULT_OK)?;
+    env.write_u8(ret + 1, descriptor_type as u8)
+}
+
+pub(crate) fn descriptor_read_via_stream(
+    env: &WasiP2Env,
+    descriptor: u32,
+    offset: u64,
+    ret: u32,
+) -> Result<(), RuntimeError> {
+    let result = {
+        let mut state = env.state();
+        let descriptor = state.table.descriptor(descriptor)?;
+        if descriptor.flags & DESCRIPTOR_FLAGS_READ == 0 {
+            Err(ErrorCode::BadDescriptor)
+        } else {
+            descriptor
+                .file()
+                .and_then(|file| Ok(file.try_clone()?))
+                .map(|file| Resource::InputStream(Box::new(FileStream::at(file, offset))))
+        }
+    };
+    env.store_resource_result(ret, result)
+}
+
+fn writable_file(
+    env: &WasiP2Env,
+    descriptor: u32,
+) -> Result<Result<File, ErrorCode>, RuntimeError> {
+    let mut state = env.state();
+    let descriptor = state.table.descriptor(descriptor)?;
+    if descriptor.flags & DESCRIPTOR_FLAGS_WRITE == 0 {
+        return Ok(Err(ErrorCode::BadDescriptor));
+    }
+    Ok(descriptor.file().and_then(|file| Ok(file.try_clone()?)))
+}
+
+pub(crate) fn descriptor_write_via_stream(
+    env: &WasiP2Env,
+    descriptor: u32,
+    offset: u64,
+    ret: u32,
+) -> Result<(), RuntimeError> {
+    let result = writable_file(env, descriptor)?
+        .map(|file| Resource::OutputStream(Box::new(FileStream::at(file, offset))));
+    env.store_resource_result(ret, result)
+}
+
+pub(crate) fn descriptor_append_via_stream(
+    env: &WasiP2Env,
+    descriptor: u32,
+    ret: u32,
+) -> Result<(), RuntimeError> {
+    let result = writable_file(env, descriptor)?
+        .map(|file| Resource::OutputStream(Box::new(FileStream::append(file))));
+    env.store_resource_result(ret, result)
+}
+
+pub(crate) fn descriptor_drop(env: &WasiP2Env, descriptor: u32) -> Result<(), RuntimeError> {
+    env.state().table.delete(descriptor, "descriptor").map(drop)
+}
+
+/// Returns `option::none`: the errors of the streams don't carry
+/// their `error-code`.
+pub(crate) fn filesystem_error_code(
+    env: &WasiP2Env,
+    error: u32,
+    ret: u32,
+) -> Result<(), RuntimeError> {
+    env.state().table.error(error)?;
+    env.write_u8(ret, 0)
+}
@@ -0,0 +1,241 @@
+//! `wasi:io/error`, `wasi:io/poll` and `wasi:io/streams`.
+
+use crate::abi::{RESULT_ERR, RESULT_OK};
+use crate::streams::StreamError;
+use crate::table::Resource;
+use crate::WasiP2Env;
+use std::convert::TryInto;
+use std::thread;
+use std::time::Instant;
+use wasmer::RuntimeError;
+
+/// The discriminant of `stream-error::last-operation-failed`.
+const STREAM_ERROR_LAST_OPERATION_FAILED: u8 = 0;
+
+/// The discriminant of `stream-error::closed`.
+const STREAM_ERROR_CLOSED: u8 = 1;
+
+/// A `pollable` of `wasi:io/poll`.
+///
+/// The streams are blocking, so their pollables are always ready:
+/// only the pollables of the monotonic clock wait for their deadline.
+#[derive(Clone, Copy)]
+pub(crate) enum Pollable {
+    /// The pollable is always ready.
+    Ready,
+    /// The pollable is ready once the instant has been reached.
+    Deadline(Instant),
+}
+
+impl Pollable {
+    fn is_ready(self) -> bool {
+        match self {
+            Self::Ready => true,
+            Self::Deadline(deadline) => deadline <= Instant::now(),
+        }
+    }
+
+    /// Blocks until the pollable is ready.
+    fn block(self) {
+        if let Self::Deadline(deadline) = self {
+            let now = Instant::now();
+            if deadline > now {
+                thread::sleep(deadline - now);
+            }
+        }
+    }
+}
+
+impl WasiP2Env {
+    /// Adds `pollable` to the table, and returns its handle.
+    pub(crate) fn push_pollable(&self, pollable: Pollable) -> Result<u32, RuntimeError> {
+        self.state().table.push(Resource::Pollable(pollable))
+    }
+
+    /// Lowers a `stream-error` at `ptr`, adding the `error` resource
+    /// of a failed operation to the table.
+    fn store_stream_error(&self, ptr: u32, error: StreamError) -> Result<(), RuntimeError> {
+        match error {
+            StreamError::Closed => self.write_u8(ptr, STREAM_ERROR_CLOSED),
+            StreamError::LastOperationFailed(error) => {
+                let handle = self
+                    .state()
+                    .table
+                    .push(Resource::Error(error.to_string()))?;
+                self.write_u8(ptr, STREAM_ERROR_LAST_OPERATION_FAILED)?;
+                self.write_u32(ptr + 4, handle)
+            }
+        }
+    }
+
+    /// Lowers a `result<_, stream-error>` at `ret`.
+    fn store_unit_result(
+        &self,
+        ret: u32,
+        result: Result<(), StreamError>,
+    ) -> Result<(), RuntimeError> {
+        match result {
+            Ok(()) => self.write_u8(ret, RESULT_OK),
+            Err(error) => {
+                self.write_u8(ret, RESULT_ERR)?;
+                self.store_stream_error(ret + 4, error)
+            }
+        }
+    }
+}
+
+pub(crate) fn error_to_debug_string(
+    env: &WasiP2Env,
+    error: u32,
+    ret: u32,
+) -> Result<(), RuntimeError> {
+    let message = env.state().table.error(error)?.clone();
+    env.store_list(ret, message.as_bytes())
+}
+
+pub(crate) fn error_drop(env: &WasiP2Env, error: u32) -> Result<(), RuntimeError> {
+    env.state().table.delete(error, "error").map(drop)
+}
+
+pub(crate) fn pollable_ready(env: &WasiP2Env, pollable: u32) -> Result<u32, RuntimeError> {
+    let pollable = *env.state().table.pollable(pollable)?;
+    Ok(pollable.is_ready() as u32)
+}
+
+pub(crate) fn pollable_block(env: &WasiP2Env, pollable: u32) -> Result<(), RuntimeError> {
+    // The state isn't locked while blocking.
+    let pollable = *env.state().table.pollable(pollable)?;
+    pollable.block();
+    Ok(())
+}
+
+pub(crate) fn pollable_drop(env: &WasiP2Env, pollable: u32) -> Result<(), RuntimeError> {
+    env.state().table.delete(pollable, "pollable").map(drop)
+}
+
+/// Blocks until one of the pollables of the list is ready, and returns
+/// the indices of the ready ones.
+pub(crate) fn poll(env: &WasiP2Env, ptr: u32, len: u32, ret: u32) -> Result<(), RuntimeError> {
+    if len == 0 {
+        return Err(RuntimeError::new("`poll` needs at least one pollable"));
+    }
+    let pollables = {
+        let mut state = env.state();
+        env.read_u32_list(ptr, len)?
+            .into_iter()
+            .map(|handle| state.table.pollable(handle).map(|pollable| *pollable))
+            .collect::<Result<Vec<_>, _>>()?
+    };
+    if !pollables.iter().any(|pollable| pollable.is_ready()) {
+        // None is ready, so they all have a deadline.
+        let first = pollables
+            .iter()
+            .min_by_key(|pollable| match pollable {
+                Pollable::Ready => None,
+                Pollable::Deadline(deadline) => Some(*deadline),
+            })
+            .unwrap();
+        first.block();
+    }
+    let ready = pollables
+        .iter()
+        .enumerate()
+        .filter(|(_, pollable)| pollable.is_ready())
+        .map(|(index, _)| index as u32)
+        .collect::<Vec<_>>();
+    env.store_u32_list(ret, &ready)
+}
+
+pub(crate) fn input_stream_read(
+    env: &WasiP2Env,
+    stream: u32,
+    len: u64,
+    ret: u32,
+) -> Result<(), RuntimeError> {
+    let size = len.try_into().unwrap_or(usize::MAX);
+    let result = env.state().table.input_stream(stream)?.read(size);
+    match result {
+        Ok(bytes) => {
+            env.write_u8(ret, RESULT_OK)?;
+            env.store_list(ret + 4, &bytes)
+        }
+        Err(error) => {
+            env.write_u8(ret, RESULT_ERR)?;
+            env.store_stream_error(ret + 4, error)
+        }
+    }
+}
+
+pub(crate) fn input_stream_subscribe(env: &WasiP2Env, stream: u32) -> Result<u32, RuntimeError> {
+    env.state().table.input_stream(stream)?;
+    env.push_pollable(Pollable::Ready)
+}
+
+pub(crate) fn input_stream_drop(env: &WasiP2Env, stream: u32) -> Result<(), RuntimeError> {
+    env.state().table.delete(stream, "input-stream").map(drop)
+}
+
+pub(crate) fn output_stream_check_write(
+    env: &WasiP2Env,
+    stream: u32,
+    ret: u32,
+) -> Result<(), RuntimeError> {
+    let result = env.state().table.output_stream(stream)?.check_write();
+    // The payload of the result is aligned to 8 bytes by its `u64`.
+    match result {
+        Ok(permitted) => {
+            env.write_u8(ret, RESULT_OK)?;
+            env.write_u64(ret + 8, permitted as u64)
+        }
+        Err(error) => {
+            env.write_u8(ret, RESULT_ERR)?;
+            env.store_stream_error(ret + 8, error)
+        }
+    }
+}
+
+pub(crate) fn output_stream_write(
+    env: &WasiP2Env,
+    stream: u32,
+    ptr: u32,
+    len: u32,
+    ret: u32,
+) -> Result<(), RuntimeError> {
+    let bytes = env.read_bytes(ptr, len)?;
+    let result = env.state().table.output_stream(stream)?.write(&bytes);
+    env.store_unit_result(ret, result)
+}
+
+pub(crate) fn output_stream_blocking_write_and_flush(
+    env: &WasiP2Env,
+    stream: u32,
+    ptr: u32,
+    len: u32,
+    ret: u32,
+) -> Result<(), RuntimeError> {
+    let bytes = env.read_bytes(ptr, len)?;
+    let result = {
+        let mut state = env.state();
+        let stream = state.table.output_stream(stream)?;
+        stream.write(&bytes).and_then(|()| stream.flush())
+    };
+    env.store_unit_result(ret, result)
+}
+
+pub(crate) fn output_stream_flush(
+    env: &WasiP2Env,
+    stream: u32,
+    ret: u32,
+) -> Result<(), RuntimeError> {
+    let result = env.state().table.output_stream(stream)?.flush();
+    env.store_unit_result(ret, result)
+}
+
+pub(crate) fn output_stream_subscribe(env: &WasiP2Env, stream: u32) -> Result<u32, RuntimeError> {
+    env.state().table.output_stream(stream)?;
+    env.push_pollable(Pollable::Ready)
+}
+
+pub(crate) fn output_stream_drop(env: &WasiP2Env, stream: u32) -> Result<(), RuntimeError> {
+    env.state().table.delete(stream, "output-stream").map(drop)
+}
@@ -0,0 +1,8 @@
+//! The host functions of the WASI preview2 interfaces, lowered with
+//! the canonical ABI.
+
+pub(crate) mod cli;
+pub(crate) mod clocks;
+pub(crate) mod filesystem;
+pub(crate) mod io;
+pub(crate) mod random;
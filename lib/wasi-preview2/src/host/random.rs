@@ -0,0 +1,25 @@
+//! `wasi:random/random`.
+
+use crate::WasiP2Env;
+use std::convert::TryInto;
+use wasmer::RuntimeError;
+
+fn random_bytes(len: usize) -> Result<Vec<u8>, RuntimeError> {
+    let mut bytes = vec![0; len];
+    getrandom::getrandom(&mut bytes)
+        .map_err(|error| RuntimeError::new(format!("failed to get random bytes: {}", error)))?;
+    Ok(bytes)
+}
+
+pub(crate) fn get_random_bytes(env: &WasiP2Env, len: u64, ret: u32) -> Result<(), RuntimeError> {
+    // The bytes must fit in the memory of the guest.
+    let len: u32 = len
+        .try_into()
+        .map_err(|_| RuntimeError::new("too many random bytes requested"))?;
+    env.store_list(ret, &random_bytes(len as usize)?)
+}
+
+pub(crate) fn get_random_u64(_env: &WasiP2Env) -> Result<u64, RuntimeError> {
+    let bytes = random_bytes(8)?;
+    Ok(u64::from_le_bytes(bytes[..].try_into().unwrap()))
+}
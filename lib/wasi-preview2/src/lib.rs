@@ -0,0 +1,248 @@
+//! The `wasmer-wasi-preview2` crate implements the host side of the
+//! WASI preview2 interfaces for the components built by `cargo
+//! component` and the recent toolchains:
+//!
+//! * `wasi:io/error`, `wasi:io/poll` and `wasi:io/streams`,
+//! * `wasi:clocks/wall-clock` and `wasi:clocks/monotonic-clock`,
+//! * `wasi:filesystem/types` and `wasi:filesystem/preopens`,
+//! * `wasi:random/random`,
+//! * `wasi:cli/environment`, `wasi:cli/exit`, the standard streams
+//!   and their terminals.
+//!
+//! The interfaces are provided as core functions lowered with the
+//! canonical ABI of the component model, under the names the core
+//! modules of a component import them with (for example
+//! `[method]output-stream.write` from `wasi:io/streams@0.2.0`). The
+//! resources are referred to by handles in a table owned by the
+//! [`WasiP2Env`], and the lists are allocated in the memory of the
+//! guest with its `cabi_realloc` export.
+//!
+//! Only the core modules are loaded, not the components themselves:
+//! the core module of a component has to be extracted first. The
+//! streams are blocking, so their pollables are always ready, and the
+//! standard streams are never terminals. Not every function of the
+//! interfaces is provided yet: for example, the directories can't be
+//! read, and `wasi:sockets` isn't provided. A module importing a
+//! missing function fails to link.
+//!
+//! ```no_run
+//! # use wasmer::{Instance, Module, Store};
+//! # use wasmer_wasi_preview2::WasiP2State;
+//! # fn main() -> anyhow::Result<()> {
+//! let store = Store::default();
+//! let module = Module::from_file(&store, "component-core.wasm")?;
+//!
+//! let env = WasiP2State::new("program-name")
+//!     .preopen_dir(".", "/")
+//!     .finalize()?;
+//! let import_object = env.import_object(&store);
+//! let instance = Instance::new(&module, &import_object)?;
+//! # Ok(())
+//! # }
+//! ```
+
+#![deny(missing_docs, trivial_numeric_casts, unused_extern_crates)]
+#![warn(unused_import_braces)]
+#![cfg_attr(
+    feature = "cargo-clippy",
+    warn(
+        clippy::float_arithmetic,
+        clippy::mut_mut,
+        clippy::nonminimal_bool,
+        clippy::option_map_unwrap_or,
+        clippy::option_map_unwrap_or_else,
+        clippy::print_stdout,
+        clippy::unicode_not_nfc,
+        clippy::use_self
+    )
+)]
+
+mod abi;
+mod host;
+mod state;
+mod streams;
+mod table;
+
+pub use crate::state::{WasiP2State, WasiP2StateBuilder, WasiP2StateCreationError};
+pub use crate::streams::{
+    HostInputStream, HostOutputStream, ReadPipe, Stderr, Stdin, Stdout, StreamError, WritePipe,
+};
+
+use crate::host::{cli, clocks, filesystem, io, random};
+use std::sync::{Arc, Mutex, MutexGuard};
+use thiserror::Error;
+use wasmer::{
+    imports, ExternType, Function, HostEnvInitError, ImportObject, Instance, LazyInit, Memory,
+    Module, NativeFunc, Store, WasmerEnv,
+};
+
+/// The version of the WASI preview2 interfaces.
+pub const WASI_PREVIEW2_VERSION: &str = "0.2.0";
+
+/// This is returned in `RuntimeError`.
+/// Use `downcast` or `downcast_ref` to retrieve the `ExitCode`.
+#[derive(Error, Debug)]
+pub enum WasiP2Error {
+    /// The guest called `wasi:cli/exit.exit`.
+    #[error("WASI exited with code: {0}")]
+    Exit(u32),
+}
+
+/// The environment provided to the WASI preview2 imports.
+#[derive(Clone)]
+pub struct WasiP2Env {
+    /// Shared state of the WASI preview2 interfaces.
+    ///
+    /// Be careful when using this in host functions that call into Wasm:
+    /// if the lock is held and the Wasm calls into a host function that tries
+    /// to lock this mutex, the program will deadlock.
+    pub state: Arc<Mutex<WasiP2State>>,
+    memory: LazyInit<Memory>,
+    realloc: LazyInit<NativeFunc<(u32, u32, u32, u32), u32>>,
+}
+
+impl WasiP2Env {
+    /// Creates an environment with `state`.
+    pub fn new(state: WasiP2State) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(state)),
+            memory: LazyInit::new(),
+            realloc: LazyInit::new(),
+        }
+    }
+
+    /// Creates an [`ImportObject`] with the WASI preview2 imports.
+    pub fn import_object(&self, store: &Store) -> ImportObject {
+        generate_import_object_from_env(store, self.clone())
+    }
+
+    /// Get the WASI preview2 state.
+    pub fn state(&self) -> MutexGuard<WasiP2State> {
+        self.state.lock().unwrap()
+    }
+
+    /// Get a reference to the memory
+    pub fn memory(&self) -> &Memory {
+        self.memory
+            .get_ref()
+            .expect("Memory should be set on `WasiP2Env` first")
+    }
+}
+
+impl WasmerEnv for WasiP2Env {
+    fn init_with_instance(&mut self, instance: &Instance) -> Result<(), HostEnvInitError> {
+        self.memory
+            .initialize(instance.exports.get_memory("memory")?.clone());
+        // The guests which never receive a list don't need to export
+        // `cabi_realloc`.
+        if let Ok(realloc) = instance.exports.get_native_function("cabi_realloc") {
+            self.realloc.initialize(realloc);
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for WasiP2Env {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WasiP2Env")
+            .field("state", &self.state)
+            .finish()
+    }
+}
+
+/// Checks whether `module` imports a WASI preview2 interface.
+pub fn is_wasi_preview2_module(module: &Module) -> bool {
+    module.imports().any(|import| {
+        matches!(import.ty(), ExternType::Function(_))
+            && import.module().starts_with("wasi:")
+            && import
+                .module()
+                .ends_with(&format!("@{}", WASI_PREVIEW2_VERSION))
+    })
+}
+
+/// Create an [`ImportObject`] with an existing [`WasiP2Env`].
+pub fn generate_import_object_from_env(store: &Store, env: WasiP2Env) -> ImportObject {
+    imports! {
+        "wasi:io/error@0.2.0" => {
+            "[method]error.to-debug-string" => Function::new_native_with_env(store, env.clone(), io::error_to_debug_string),
+            "[resource-drop]error" => Function::new_native_with_env(store, env.clone(), io::error_drop),
+        },
+        "wasi:io/poll@0.2.0" => {
+            "[method]pollable.ready" => Function::new_native_with_env(store, env.clone(), io::pollable_ready),
+            "[method]pollable.block" => Function::new_native_with_env(store, env.clone(), io::pollable_block),
+            "[resource-drop]pollable" => Function::new_native_with_env(store, env.clone(), io::pollable_drop),
+            "poll" => Function::new_native_with_env(store, env.clone(), io::poll),
+        },
+        "wasi:io/streams@0.2.0" => {
+            "[method]input-stream.read" => Function::new_native_with_env(store, env.clone(), io::input_stream_read),
+            "[method]input-stream.blocking-read" => Function::new_native_with_env(store, env.clone(), io::input_stream_read),
+            "[method]input-stream.subscribe" => Function::new_native_with_env(store, env.clone(), io::input_stream_subscribe),
+            "[resource-drop]input-stream" => Function::new_native_with_env(store, env.clone(), io::input_stream_drop),
+            "[method]output-stream.check-write" => Function::new_native_with_env(store, env.clone(), io::output_stream_check_write),
+            "[method]output-stream.write" => Function::new_native_with_env(store, env.clone(), io::output_stream_write),
+            "[method]output-stream.blocking-write-and-flush" => Function::new_native_with_env(store, env.clone(), io::output_stream_blocking_write_and_flush),
+            "[method]output-stream.flush" => Function::new_native_with_env(store, env.clone(), io::output_stream_flush),
+            "[method]output-stream.blocking-flush" => Function::new_native_with_env(store, env.clone(), io::output_stream_flush),
+            "[method]output-stream.subscribe" => Function::new_native_with_env(store, env.clone(), io::output_stream_subscribe),
+            "[resource-drop]output-stream" => Function::new_native_with_env(store, env.clone(), io::output_stream_drop),
+        },
+        "wasi:clocks/wall-clock@0.2.0" => {
+            "now" => Function::new_native_with_env(store, env.clone(), clocks::wall_clock_now),
+            "resolution" => Function::new_native_with_env(store, env.clone(), clocks::wall_clock_resolution),
+        },
+        "wasi:clocks/monotonic-clock@0.2.0" => {
+            "now" => Function::new_native_with_env(store, env.clone(), clocks::monotonic_clock_now),
+            "resolution" => Function::new_native_with_env(store, env.clone(), clocks::monotonic_clock_resolution),
+            "subscribe-instant" => Function::new_native_with_env(store, env.clone(), clocks::monotonic_clock_subscribe_instant),
+            "subscribe-duration" => Function::new_native_with_env(store, env.clone(), clocks::monotonic_clock_subscribe_duration),
+        },
+        "wasi:filesystem/types@0.2.0" => {
+            "[method]descriptor.open-at" => Function::new_native_with_env(store, env.clone(), filesystem::descriptor_open_at),
+            "[method]descriptor.get-type" => Function::new_native_with_env(store, env.clone(), filesystem::descriptor_get_type),
+            "[method]descriptor.read-via-stream" => Function::new_native_with_env(store, env.clone(), filesystem::descriptor_read_via_stream),
+            "[method]descriptor.write-via-stream" => Function::new_native_with_env(store, env.clone(), filesystem::descriptor_write_via_stream),
+            "[method]descriptor.append-via-stream" => Function::new_native_with_env(store, env.clone(), filesystem::descriptor_append_via_stream),
+            "[resource-drop]descriptor" => Function::new_native_with_env(store, env.clone(), filesystem::descriptor_drop),
+            "filesystem-error-code" => Function::new_native_with_env(store, env.clone(), filesystem::filesystem_error_code),
+        },
+        "wasi:filesystem/preopens@0.2.0" => {
+            "get-directories" => Function::new_native_with_env(store, env.clone(), filesystem::get_directories),
+        },
+        "wasi:random/random@0.2.0" => {
+            "get-random-bytes" => Function::new_native_with_env(store, env.clone(), random::get_random_bytes),
+            "get-random-u64" => Function::new_native_with_env(store, env.clone(), random::get_random_u64),
+        },
+        "wasi:cli/environment@0.2.0" => {
+            "get-environment" => Function::new_native_with_env(store, env.clone(), cli::get_environment),
+            "get-arguments" => Function::new_native_with_env(store, env.clone(), cli::get_arguments),
+        },
+        "wasi:cli/exit@0.2.0" => {
+            "exit" => Function::new_native_with_env(store, env.clone(), cli::exit),
+        },
+        "wasi:cli/stdin@0.2.0" => {
+            "get-stdin" => Function::new_native_with_env(store, env.clone(), cli::get_stdin),
+        },
+        "wasi:cli/stdout@0.2.0" => {
+            "get-stdout" => Function::new_native_with_env(store, env.clone(), cli::get_stdout),
+        },
+        "wasi:cli/stderr@0.2.0" => {
+            "get-stderr" => Function::new_native_with_env(store, env.clone(), cli::get_stderr),
+        },
+        "wasi:cli/terminal-input@0.2.0" => {
+            "[resource-drop]terminal-input" => Function::new_native_with_env(store, env.clone(), cli::terminal_input_drop),
+        },
+        "wasi:cli/terminal-output@0.2.0" => {
+            "[resource-drop]terminal-output" => Function::new_native_with_env(store, env.clone(), cli::terminal_output_drop),
+        },
+        "wasi:cli/terminal-stdin@0.2.0" => {
+            "get-terminal-stdin" => Function::new_native_with_env(store, env.clone(), cli::get_terminal),
+        },
+        "wasi:cli/terminal-stdout@0.2.0" => {
+            "get-terminal-stdout" => Function::new_native_with_env(store, env.clone(), cli::get_terminal),
+        },
+        "wasi:cli/terminal-stderr@0.2.0" => {
+            "get-terminal-stderr" => Function::new_native_with_env(store, env, cli::get_terminal),
+        },
+    }
+}
@@ -0,0 +1,208 @@
+//! The state of the WASI preview2 interfaces, and the builder to
+//! configure it.
+
+use crate::streams::{
+    HostInputStream, HostOutputStream, SharedInputStream, SharedOutputStream, Stderr, Stdin, Stdout,
+};
+use crate::table::ResourceTable;
+use crate::WasiP2Env;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use thiserror::Error;
+
+/// An error when building a [`WasiP2State`].
+#[derive(Error, Debug)]
+pub enum WasiP2StateCreationError {
+    /// A directory to preopen can't be opened.
+    #[error("failed to preopen the directory `{path}`: {source}")]
+    PreopenedDirectory {
+        /// The path of the directory on the host.
+        path: PathBuf,
+        /// The error of the host.
+        source: std::io::Error,
+    },
+    /// A path to preopen isn't a directory.
+    #[error("the preopened path `{0}` is not a directory")]
+    PreopenedPathNotDirectory(PathBuf),
+}
+
+/// The state of the WASI preview2 interfaces: the resources owned by
+/// the guest, and what the host provides to it.
+pub struct WasiP2State {
+    pub(crate) table: ResourceTable,
+    pub(crate) args: Vec<String>,
+    pub(crate) envs: Vec<(String, String)>,
+    /// The canonical paths of the preopened directories, and their
+    /// names for the guest.
+    pub(crate) preopens: Vec<(Arc<PathBuf>, String)>,
+    pub(crate) stdin: SharedInputStream,
+    pub(crate) stdout: SharedOutputStream,
+    pub(crate) stderr: SharedOutputStream,
+    /// The origin of `wasi:clocks/monotonic-clock`.
+    pub(crate) start: Instant,
+}
+
+impl WasiP2State {
+    /// Creates a [`WasiP2StateBuilder`] for a program named
+    /// `program_name`, which is its first argument.
+    pub fn new(program_name: &str) -> WasiP2StateBuilder {
+        WasiP2StateBuilder {
+            args: vec![program_name.to_string()],
+            ..WasiP2StateBuilder::default()
+        }
+    }
+}
+
+impl std::fmt::Debug for WasiP2State {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WasiP2State")
+            .field("args", &self.args)
+            .field("envs", &self.envs)
+            .field("preopens", &self.preopens)
+            .finish()
+    }
+}
+
+/// A builder to configure a [`WasiP2State`].
+///
+/// Usage:
+/// ```no_run
+/// # use wasmer_wasi_preview2::{WasiP2State, WasiP2StateCreationError};
+/// # fn main() -> Result<(), WasiP2StateCreationError> {
+/// let state = WasiP2State::new("program-name")
+///     .arg("--verbose")
+///     .env("KEY", "VALUE")
+///     .preopen_dir("path/on/host", "/data")
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Default)]
+pub struct WasiP2StateBuilder {
+    args: Vec<String>,
+    envs: Vec<(String, String)>,
+    preopens: Vec<(PathBuf, String)>,
+    stdin: Option<Box<dyn HostInputStream>>,
+    stdout: Option<Box<dyn HostOutputStream>>,
+    stderr: Option<Box<dyn HostOutputStream>>,
+}
+
+impl std::fmt::Debug for WasiP2StateBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WasiP2StateBuilder")
+            .field("args", &self.args)
+            .field("envs", &self.envs)
+            .field("preopens", &self.preopens)
+            .field("stdin exists", &self.stdin.is_some())
+            .field("stdout exists", &self.stdout.is_some())
+            .field("stderr exists", &self.stderr.is_some())
+            .finish()
+    }
+}
+
+impl WasiP2StateBuilder {
+    /// Adds an argument.
+    pub fn arg(&mut self, arg: impl Into<String>) -> &mut Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    /// Adds several arguments.
+    pub fn args<I, A>(&mut self, args: I) -> &mut Self
+    where
+        I: IntoIterator<Item = A>,
+        A: Into<String>,
+    {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Adds an environment variable.
+    pub fn env(&mut self, key: impl Into<String>, value: impl Into<String>) -> &mut Self {
+        self.envs.push((key.into(), value.into()));
+        self
+    }
+
+    /// Preopens the directory `host_path` of the host, which the guest
+    /// sees as `name`.
+    ///
+    /// The guest can read and modify the files of the directory, but
+    /// can't reach the files out of it.
+    pub fn preopen_dir(
+        &mut self,
+        host_path: impl AsRef<Path>,
+        name: impl Into<String>,
+    ) -> &mut Self {
+        self.preopens
+            .push((host_path.as_ref().to_path_buf(), name.into()));
+        self
+    }
+
+    /// Overrides the standard input, which is the one of the host by
+    /// default.
+    pub fn stdin(&mut self, stdin: Box<dyn HostInputStream>) -> &mut Self {
+        self.stdin = Some(stdin);
+        self
+    }
+
+    /// Overrides the standard output, which is the one of the host by
+    /// default.
+    pub fn stdout(&mut self, stdout: Box<dyn HostOutputStream>) -> &mut Self {
+        self.stdout = Some(stdout);
+        self
+    }
+
+    /// Overrides the standard error, which is the one of the host by
+    /// default.
+    pub fn stderr(&mut self, stderr: Box<dyn HostOutputStream>) -> &mut Self {
+        self.stderr = Some(stderr);
+        self
+    }
+
+    /// Builds the [`WasiP2State`].
+    ///
+    /// The standard streams are moved to the state, and are reset to
+    /// the ones of the host in the builder.
+    pub fn build(&mut self) -> Result<WasiP2State, WasiP2StateCreationError> {
+        let preopens = self
+            .preopens
+            .iter()
+            .map(|(path, name)| {
+                let canonical = path.canonicalize().map_err(|source| {
+                    WasiP2StateCreationError::PreopenedDirectory {
+                        path: path.clone(),
+                        source,
+                    }
+                })?;
+                if !canonical.is_dir() {
+                    return Err(WasiP2StateCreationError::PreopenedPathNotDirectory(
+                        path.clone(),
+                    ));
+                }
+                Ok((Arc::new(canonical), name.clone()))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(WasiP2State {
+            table: ResourceTable::default(),
+            args: self.args.clone(),
+            envs: self.envs.clone(),
+            preopens,
+            stdin: Arc::new(Mutex::new(
+                self.stdin.take().unwrap_or_else(|| Box::new(Stdin)),
+            )),
+            stdout: Arc::new(Mutex::new(
+                self.stdout.take().unwrap_or_else(|| Box::new(Stdout)),
+            )),
+            stderr: Arc::new(Mutex::new(
+                self.stderr.take().unwrap_or_else(|| Box::new(Stderr)),
+            )),
+            start: Instant::now(),
+        })
+    }
+
+    /// Builds the [`WasiP2State`], and wraps it in a [`WasiP2Env`].
+    pub fn finalize(&mut self) -> Result<WasiP2Env, WasiP2StateCreationError> {
+        Ok(WasiP2Env::new(self.build()?))
+    }
+}
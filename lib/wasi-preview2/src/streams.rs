@@ -0,0 +1,233 @@
+//! The byte streams behind the `input-stream` and `output-stream`
+//! resources of `wasi:io/streams`.
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+
+/// The size of the buffer accepted by the output streams, which is
+/// returned by `check-write`.
+const WRITE_BUDGET: usize = 64 * 1024;
+
+/// An error of a stream operation.
+#[derive(Error, Debug)]
+pub enum StreamError {
+    /// The stream is closed: an input stream has no more bytes, or an
+    /// output stream doesn't accept more bytes.
+    #[error("the stream is closed")]
+    Closed,
+    /// The last operation failed, and the stream is closed.
+    #[error("the last operation failed: {0}")]
+    LastOperationFailed(#[from] io::Error),
+}
+
+/// The source of the bytes of an `input-stream`.
+pub trait HostInputStream: Send {
+    /// Reads at most `size` bytes.
+    ///
+    /// Returns [`StreamError::Closed`] once all the bytes have been
+    /// read, while an empty vector means that no bytes are available
+    /// yet.
+    fn read(&mut self, size: usize) -> Result<Vec<u8>, StreamError>;
+}
+
+/// The destination of the bytes of an `output-stream`.
+pub trait HostOutputStream: Send {
+    /// Returns how many bytes can be written without blocking.
+    fn check_write(&mut self) -> Result<usize, StreamError> {
+        Ok(WRITE_BUDGET)
+    }
+
+    /// Writes all the `bytes`.
+    fn write(&mut self, bytes: &[u8]) -> Result<(), StreamError>;
+
+    /// Flushes the bytes written so far.
+    fn flush(&mut self) -> Result<(), StreamError> {
+        Ok(())
+    }
+}
+
+/// An input stream which is shared by several resources, like the
+/// standard input returned by each call to `get-stdin`.
+pub(crate) type SharedInputStream = Arc<Mutex<Box<dyn HostInputStream>>>;
+
+/// An output stream which is shared by several resources.
+pub(crate) type SharedOutputStream = Arc<Mutex<Box<dyn HostOutputStream>>>;
+
+impl HostInputStream for SharedInputStream {
+    fn read(&mut self, size: usize) -> Result<Vec<u8>, StreamError> {
+        self.lock().unwrap().read(size)
+    }
+}
+
+impl HostOutputStream for SharedOutputStream {
+    fn check_write(&mut self) -> Result<usize, StreamError> {
+        self.lock().unwrap().check_write()
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), StreamError> {
+        self.lock().unwrap().write(bytes)
+    }
+
+    fn flush(&mut self) -> Result<(), StreamError> {
+        self.lock().unwrap().flush()
+    }
+}
+
+/// Reads `size` bytes at most from `reader`.
+fn read_from(reader: &mut impl Read, size: usize) -> Result<Vec<u8>, StreamError> {
+    let mut buf = vec![0; size.min(WRITE_BUDGET)];
+    let read = reader.read(&mut buf)?;
+    if read == 0 && size != 0 {
+        return Err(StreamError::Closed);
+    }
+    buf.truncate(read);
+    Ok(buf)
+}
+
+/// The standard input of the host process.
+#[derive(Debug, Default)]
+pub struct Stdin;
+
+impl HostInputStream for Stdin {
+    fn read(&mut self, size: usize) -> Result<Vec<u8>, StreamError> {
+        read_from(&mut io::stdin(), size)
+    }
+}
+
+/// The standard output of the host process.
+#[derive(Debug, Default)]
+pub struct Stdout;
+
+impl HostOutputStream for Stdout {
+    fn write(&mut self, bytes: &[u8]) -> Result<(), StreamError> {
+        Ok(io::stdout().write_all(bytes)?)
+    }
+
+    fn flush(&mut self) -> Result<(), StreamError> {
+        Ok(io::stdout().flush()?)
+    }
+}
+
+/// The standard error of the host process.
+#[derive(Debug, Default)]
+pub struct Stderr;
+
+impl HostOutputStream for Stderr {
+    fn write(&mut self, bytes: &[u8]) -> Result<(), StreamError> {
+        Ok(io::stderr().write_all(bytes)?)
+    }
+
+    fn flush(&mut self) -> Result<(), StreamError> {
+        Ok(io::stderr().flush()?)
+    }
+}
+
+/// An input stream reading bytes provided by the host.
+#[derive(Debug, Default)]
+pub struct ReadPipe {
+    bytes: io::Cursor<Vec<u8>>,
+}
+
+impl ReadPipe {
+    /// Creates a stream which reads `bytes`, and is then closed.
+    pub fn new(bytes: impl Into<Vec<u8>>) -> Self {
+        Self {
+            bytes: io::Cursor::new(bytes.into()),
+        }
+    }
+}
+
+impl HostInputStream for ReadPipe {
+    fn read(&mut self, size: usize) -> Result<Vec<u8>, StreamError> {
+        read_from(&mut self.bytes, size)
+    }
+}
+
+/// An output stream collecting the bytes written by the guest, so the
+/// host can read them.
+///
+/// The clones of a pipe share the same bytes.
+#[derive(Debug, Default, Clone)]
+pub struct WritePipe {
+    bytes: Arc<Mutex<Vec<u8>>>,
+}
+
+impl WritePipe {
+    /// Creates an empty pipe.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a copy of the bytes written so far.
+    pub fn contents(&self) -> Vec<u8> {
+        self.bytes.lock().unwrap().clone()
+    }
+}
+
+impl HostOutputStream for WritePipe {
+    fn write(&mut self, bytes: &[u8]) -> Result<(), StreamError> {
+        self.bytes.lock().unwrap().extend_from_slice(bytes);
+        Ok(())
+    }
+}
+
+/// A stream reading or writing a file from a given position, created
+/// by `read-via-stream`, `write-via-stream` and `append-via-stream`.
+#[derive(Debug)]
+pub(crate) struct FileStream {
+    file: File,
+    /// The position of the next operation, or `None` to append.
+    position: Option<u64>,
+}
+
+impl FileStream {
+    pub(crate) fn at(file: File, position: u64) -> Self {
+        Self {
+            file,
+            position: Some(position),
+        }
+    }
+
+    pub(crate) fn append(file: File) -> Self {
+        Self {
+            file,
+            position: None,
+        }
+    }
+
+    fn seek(&mut self) -> io::Result<()> {
+        let from = match self.position {
+            Some(position) => SeekFrom::Start(position),
+            None => SeekFrom::End(0),
+        };
+        self.file.seek(from).map(|_| ())
+    }
+}
+
+impl HostInputStream for FileStream {
+    fn read(&mut self, size: usize) -> Result<Vec<u8>, StreamError> {
+        self.seek()?;
+        let bytes = read_from(&mut self.file, size)?;
+        if let Some(position) = &mut self.position {
+            *position += bytes.len() as u64;
+        }
+        Ok(bytes)
+    }
+}
+
+impl HostOutputStream for FileStream {
+    fn write(&mut self, bytes: &[u8]) -> Result<(), StreamError> {
+        self.seek()?;
+        self.file.write_all(bytes)?;
+        if let Some(position) = &mut self.position {
+            *position += bytes.len() as u64;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), StreamError> {
+        Ok(self.file.flush()?)
+    }
+}
@@ -0,0 +1,109 @@
+//! The table of the resources owned by the guest.
+//!
+//! The guest refers to the resources with handles, which are indices
+//! in the table plus one: the handle `0` is never valid.
+
+use crate::host::filesystem::Descriptor;
+use crate::host::io::Pollable;
+use crate::streams::{HostInputStream, HostOutputStream};
+use std::convert::TryInto;
+use wasmer::RuntimeError;
+
+/// A resource of the table.
+pub(crate) enum Resource {
+    /// An `input-stream` of `wasi:io/streams`.
+    InputStream(Box<dyn HostInputStream>),
+    /// An `output-stream` of `wasi:io/streams`.
+    OutputStream(Box<dyn HostOutputStream>),
+    /// An `error` of `wasi:io/error`, with its debug string.
+    Error(String),
+    /// A `descriptor` of `wasi:filesystem/types`.
+    Descriptor(Descriptor),
+    /// A `pollable` of `wasi:io/poll`.
+    Pollable(Pollable),
+}
+
+impl Resource {
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::InputStream(_) => "input-stream",
+            Self::OutputStream(_) => "output-stream",
+            Self::Error(_) => "error",
+            Self::Descriptor(_) => "descriptor",
+            Self::Pollable(_) => "pollable",
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct ResourceTable {
+    entries: Vec<Option<Resource>>,
+}
+
+/// The error raised when the guest uses an invalid handle, or a
+/// handle to a resource of another type, which traps as for any
+/// component.
+fn unknown_handle(handle: u32, expected: &str) -> RuntimeError {
+    RuntimeError::new(format!("unknown handle {} of `{}`", handle, expected))
+}
+
+macro_rules! resource_accessor {
+    ($name:ident, $variant:ident, $ty:ty, $kind:expr) => {
+        pub(crate) fn $name(&mut self, handle: u32) -> Result<&mut $ty, RuntimeError> {
+            match self.get_mut(handle) {
+                Some(Resource::$variant(resource)) => Ok(resource),
+                _ => Err(unknown_handle(handle, $kind)),
+            }
+        }
+    };
+}
+
+impl ResourceTable {
+    /// Adds `resource` to the table, and returns its handle.
+    pub(crate) fn push(&mut self, resource: Resource) -> Result<u32, RuntimeError> {
+        let index = match self.entries.iter().position(Option::is_none) {
+            Some(index) => {
+                self.entries[index] = Some(resource);
+                index
+            }
+            None => {
+                self.entries.push(Some(resource));
+                self.entries.len() - 1
+            }
+        };
+        (index + 1)
+            .try_into()
+            .map_err(|_| RuntimeError::new("too many resources"))
+    }
+
+    fn get_mut(&mut self, handle: u32) -> Option<&mut Resource> {
+        let index = (handle as usize).checked_sub(1)?;
+        self.entries.get_mut(index)?.as_mut()
+    }
+
+    /// Removes the resource of type `kind` referred to by `handle`,
+    /// when the guest drops it.
+    pub(crate) fn delete(&mut self, handle: u32, kind: &str) -> Result<Resource, RuntimeError> {
+        match self.get_mut(handle) {
+            Some(resource) if resource.kind() == kind => {}
+            _ => return Err(unknown_handle(handle, kind)),
+        }
+        Ok(self.entries[handle as usize - 1].take().unwrap())
+    }
+
+    resource_accessor!(
+        input_stream,
+        InputStream,
+        Box<dyn HostInputStream>,
+        "input-stream"
+    );
+    resource_accessor!(
+        output_stream,
+        OutputStream,
+        Box<dyn HostOutputStream>,
+        "output-stream"
+    );
+    resource_accessor!(error, Error, String, "error");
+    resource_accessor!(descriptor, Descriptor, Descriptor, "descriptor");
+    resource_accessor!(pollable, Pollable, Pollable, "pollable");
+}
@@ -0,0 +1,213 @@
+use anyhow::Result;
+use std::fs;
+use std::time::{Duration, Instant};
+use wasmer::{Instance, Module, NativeFunc, Store};
+use wasmer_wasi_preview2::{
+    is_wasi_preview2_module, WasiP2Error, WasiP2State, WasiP2StateBuilder, WritePipe,
+};
+
+/// The core module of a component writing to its standard output and
+/// to a file of a preopened directory, with a bump allocator as
+/// `cabi_realloc`.
+const GUEST: &str = r#"
+(module
+  (import "wasi:cli/stdout@0.2.0" "get-stdout" (func $get_stdout (result i32)))
+  (import "wasi:io/streams@0.2.0" "[method]output-stream.blocking-write-and-flush"
+    (func $write (param i32 i32 i32 i32)))
+  (import "wasi:filesystem/preopens@0.2.0" "get-directories" (func $get_directories (param i32)))
+  (import "wasi:filesystem/types@0.2.0" "[method]descriptor.open-at"
+    (func $open_at (param i32 i32 i32 i32 i32 i32 i32)))
+  (import "wasi:filesystem/types@0.2.0" "[method]descriptor.write-via-stream"
+    (func $write_via_stream (param i32 i64 i32)))
+  (import "wasi:random/random@0.2.0" "get-random-bytes" (func $get_random_bytes (param i64 i32)))
+  (import "wasi:cli/exit@0.2.0" "exit" (func $exit (param i32)))
+
+  (memory (export "memory") 1)
+  (global $heap (mut i32) (i32.const 1024))
+  (data (i32.const 64) "hello")
+  (data (i32.const 80) "out.txt")
+
+  (func (export "cabi_realloc") (param i32 i32 i32 i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $heap))
+    (global.set $heap (i32.add (global.get $heap) (local.get 3)))
+    (local.get $ptr))
+
+  (func (export "hello")
+    (call $write (call $get_stdout) (i32.const 64) (i32.const 5) (i32.const 0))
+    (if (i32.load8_u (i32.const 0)) (then unreachable)))
+
+  ;; Writes "hello" to `out.txt` in the first preopened directory.
+  (func (export "write_file") (result i32)
+    (call $get_directories (i32.const 0))
+    ;; open-at(create, write)
+    (call $open_at (i32.load (i32.load (i32.const 0))) (i32.const 0)
+      (i32.const 80) (i32.const 7) (i32.const 1) (i32.const 2) (i32.const 16))
+    (if (i32.load8_u (i32.const 16)) (then (return (i32.load8_u (i32.const 20)))))
+    (call $write_via_stream (i32.load (i32.const 20)) (i64.const 0) (i32.const 32))
+    (call $write (i32.load (i32.const 36)) (i32.const 64) (i32.const 5) (i32.const 48))
+    (i32.const -1))
+
+  ;; Opens `../out.txt`, which is outside of the preopened directory.
+  (func (export "escape") (result i32)
+    (call $get_directories (i32.const 0))
+    (call $open_at (i32.load (i32.load (i32.const 0))) (i32.const 0)
+      (i32.const 77) (i32.const 10) (i32.const 0) (i32.const 1) (i32.const 16))
+    (i32.load8_u (i32.const 20)))
+
+  ;; Returns the length of the list of random bytes.
+  (func (export "random") (result i32)
+    (call $get_random_bytes (i64.const 16) (i32.const 0))
+    (i32.load (i32.const 4)))
+
+  (func (export "exit")
+    (call $exit (i32.const 1))))
+"#;
+
+fn instantiate(store: &Store, state: &mut WasiP2StateBuilder) -> Result<Instance> {
+    let module = Module::new(store, GUEST)?;
+    assert!(is_wasi_preview2_module(&module));
+    let env = state.finalize()?;
+    Ok(Instance::new(&module, &env.import_object(store))?)
+}
+
+#[test]
+fn write_to_stdout() -> Result<()> {
+    let store = Store::default();
+    let stdout = WritePipe::new();
+    let instance = instantiate(
+        &store,
+        WasiP2State::new("guest").stdout(Box::new(stdout.clone())),
+    )?;
+
+    let hello: NativeFunc<(), ()> = instance.exports.get_native_function("hello")?;
+    hello.call()?;
+    assert_eq!(stdout.contents(), b"hello");
+    Ok(())
+}
+
+#[test]
+fn write_to_preopened_dir() -> Result<()> {
+    let store = Store::default();
+    let dir = tempfile::tempdir()?;
+    let instance = instantiate(
+        &store,
+        WasiP2State::new("guest").preopen_dir(dir.path(), "/"),
+    )?;
+
+    let write_file: NativeFunc<(), i32> = instance.exports.get_native_function("write_file")?;
+    assert_eq!(write_file.call()?, -1);
+    assert_eq!(fs::read(dir.path().join("out.txt"))?, b"hello");
+    Ok(())
+}
+
+#[test]
+fn paths_cannot_escape_preopened_dir() -> Result<()> {
+    let store = Store::default();
+    let dir = tempfile::tempdir()?;
+    let instance = instantiate(
+        &store,
+        WasiP2State::new("guest").preopen_dir(dir.path(), "/"),
+    )?;
+
+    // The path at 77 is `../` followed by `out.txt`.
+    instance.exports.get_memory("memory")?.write(77, b"../")?;
+    let escape: NativeFunc<(), i32> = instance.exports.get_native_function("escape")?;
+    // `error-code::not-permitted`
+    assert_eq!(escape.call()?, 31);
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn dangling_symlinks_cannot_escape_preopened_dir() -> Result<()> {
+    let store = Store::default();
+    let dir = tempfile::tempdir()?;
+    let outside = tempfile::tempdir()?;
+    let target = outside.path().join("target.txt");
+    std::os::unix::fs::symlink(&target, dir.path().join("out.txt"))?;
+    let instance = instantiate(
+        &store,
+        WasiP2State::new("guest").preopen_dir(dir.path(), "/"),
+    )?;
+
+    let write_file: NativeFunc<(), i32> = instance.exports.get_native_function("write_file")?;
+    // `error-code::not-permitted`
+    assert_eq!(write_file.call()?, 31);
+    assert!(!target.exists());
+    Ok(())
+}
+
+/// The core module of a component sleeping with the pollables of the
+/// monotonic clock.
+const SLEEPER: &str = r#"
+(module
+  (import "wasi:clocks/monotonic-clock@0.2.0" "subscribe-duration"
+    (func $subscribe_duration (param i64) (result i32)))
+  (import "wasi:io/poll@0.2.0" "poll" (func $poll (param i32 i32 i32)))
+  (import "wasi:io/poll@0.2.0" "[method]pollable.ready" (func $ready (param i32) (result i32)))
+  (import "wasi:io/poll@0.2.0" "[resource-drop]pollable" (func $drop (param i32)))
+
+  (memory (export "memory") 1)
+  (global $heap (mut i32) (i32.const 1024))
+
+  (func (export "cabi_realloc") (param i32 i32 i32 i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $heap))
+    (global.set $heap (i32.add (global.get $heap) (local.get 3)))
+    (local.get $ptr))
+
+  ;; Polls a pollable of 10ms and one of an hour, and returns the
+  ;; number of ready pollables, whose first index is at 16.
+  (func (export "sleep") (result i32)
+    (i32.store (i32.const 0) (call $subscribe_duration (i64.const 10000000)))
+    (i32.store (i32.const 4) (call $subscribe_duration (i64.const 3600000000000)))
+    (if (call $ready (i32.load (i32.const 0))) (then unreachable))
+    (call $poll (i32.const 0) (i32.const 2) (i32.const 8))
+    (if (i32.eqz (call $ready (i32.load (i32.const 0)))) (then unreachable))
+    (call $drop (i32.load (i32.const 0)))
+    (call $drop (i32.load (i32.const 4)))
+    (i32.store (i32.const 16) (i32.load (i32.load (i32.const 8))))
+    (i32.load (i32.const 12))))
+"#;
+
+#[test]
+fn poll_monotonic_clock() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(&store, SLEEPER)?;
+    let env = WasiP2State::new("guest").finalize()?;
+    let instance = Instance::new(&module, &env.import_object(&store))?;
+
+    let sleep: NativeFunc<(), i32> = instance.exports.get_native_function("sleep")?;
+    let start = Instant::now();
+    assert_eq!(sleep.call()?, 1);
+    assert!(start.elapsed() >= Duration::from_millis(10));
+    assert_eq!(
+        instance.exports.get_memory("memory")?.view::<u32>()[4].get(),
+        0
+    );
+    Ok(())
+}
+
+#[test]
+fn random_bytes() -> Result<()> {
+    let store = Store::default();
+    let instance = instantiate(&store, &mut WasiP2State::new("guest"))?;
+
+    let random: NativeFunc<(), i32> = instance.exports.get_native_function("random")?;
+    assert_eq!(random.call()?, 16);
+    Ok(())
+}
+
+#[test]
+fn exit_code() -> Result<()> {
+    let store = Store::default();
+    let instance = instantiate(&store, &mut WasiP2State::new("guest"))?;
+
+    let exit: NativeFunc<(), ()> = instance.exports.get_native_function("exit")?;
+    let error = exit.call().unwrap_err();
+    match error.downcast::<WasiP2Error>()? {
+        WasiP2Error::Exit(code) => assert_eq!(code, 1),
+    }
+    Ok(())
+}
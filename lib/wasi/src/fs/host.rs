@@ -0,0 +1,150 @@
+use super::{DirEntry, FileSystem, Metadata, OpenOptions};
+use crate::state::{HostFile, WasiFile};
+use crate::syscalls::types::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// The file system of the host, where the preopened directories are
+/// directories of the host.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HostFileSystem {
+    /// The permissions masked out of the files and directories created,
+    /// like the `umask` of a process.
+    umask: Option<u32>,
+}
+
+impl HostFileSystem {
+    /// Returns the file system of the host.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the file system of the host, where the files and the
+    /// directories are created without the permissions of `umask`.
+    pub fn with_umask(umask: u32) -> Self {
+        Self { umask: Some(umask) }
+    }
+}
+
+fn timestamp(time: io::Result<SystemTime>) -> __wasi_timestamp_t {
+    time.ok()
+        .and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map(|time| time.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+fn file_type(file_type: fs::FileType) -> __wasi_filetype_t {
+    if file_type.is_dir() {
+        return __WASI_FILETYPE_DIRECTORY;
+    } else if file_type.is_file() {
+        return __WASI_FILETYPE_REGULAR_FILE;
+    } else if file_type.is_symlink() {
+        return __WASI_FILETYPE_SYMBOLIC_LINK;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        if file_type.is_char_device() {
+            return __WASI_FILETYPE_CHARACTER_DEVICE;
+        } else if file_type.is_block_device() {
+            return __WASI_FILETYPE_BLOCK_DEVICE;
+        } else if file_type.is_socket() {
+            // TODO: how do we know if it's a `__WASI_FILETYPE_SOCKET_STREAM` or
+            // a `__WASI_FILETYPE_SOCKET_DGRAM`?
+            return __WASI_FILETYPE_SOCKET_STREAM;
+        }
+    }
+    // FIFO doesn't seem to fit any other type, so unknown
+    __WASI_FILETYPE_UNKNOWN
+}
+
+impl From<fs::Metadata> for Metadata {
+    fn from(metadata: fs::Metadata) -> Self {
+        Self {
+            file_type: file_type(metadata.file_type()),
+            len: metadata.len(),
+            accessed: timestamp(metadata.accessed()),
+            modified: timestamp(metadata.modified()),
+            created: timestamp(metadata.created()),
+        }
+    }
+}
+
+impl FileSystem for HostFileSystem {
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<DirEntry>> {
+        fs::read_dir(path)?
+            .map(|entry| {
+                let entry = entry?;
+                Ok(DirEntry {
+                    name: entry.file_name().to_string_lossy().into_owned(),
+                    file_type: file_type(entry.file_type()?),
+                })
+            })
+            .collect()
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        let mut dir_builder = fs::DirBuilder::new();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+            if let Some(umask) = self.umask {
+                dir_builder.mode(0o777 & !umask);
+            }
+        }
+        dir_builder.create(path)
+    }
+
+    fn remove_dir(&self, path: &Path) -> io::Result<()> {
+        fs::remove_dir(path)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        path.metadata().map(Into::into)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> io::Result<Metadata> {
+        path.symlink_metadata().map(Into::into)
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        path.read_link()
+    }
+
+    fn open(&self, path: &Path, options: &OpenOptions) -> io::Result<Box<dyn WasiFile>> {
+        let mut open_options = fs::OpenOptions::new();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            if let Some(umask) = self.umask {
+                open_options.mode(0o666 & !umask);
+            }
+        }
+        let file = open_options
+            .read(options.read)
+            .write(options.write)
+            .append(options.append)
+            .truncate(options.truncate)
+            .create(options.create)
+            .create_new(options.create_new)
+            .open(path)?;
+        Ok(Box::new(HostFile::new(
+            file,
+            path.to_path_buf(),
+            options.read,
+            options.write,
+            options.append,
+        )))
+    }
+}
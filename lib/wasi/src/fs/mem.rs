@@ -0,0 +1,579 @@
+use super::{DirEntry, FileSystem, Metadata, OpenOptions};
+use crate::state::{WasiFile, WasiFsError};
+use crate::syscalls::types::*;
+use serde::{de, ser, Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{self, Read, Seek, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::SystemTime;
+
+/// A file system living in memory, which is empty when it's created.
+///
+/// The file system is shared by its clones, so the host can fill it
+/// before the WASI program runs, and read the files the program created
+/// once it ran.
+#[derive(Debug, Clone, Default)]
+pub struct MemFileSystem {
+    root: Arc<Mutex<BTreeMap<String, Node>>>,
+}
+
+#[derive(Debug)]
+enum Node {
+    Dir(BTreeMap<String, Node>),
+    File(Arc<Mutex<FileData>>),
+}
+
+#[derive(Debug, Default)]
+struct FileData {
+    bytes: Vec<u8>,
+    accessed: __wasi_timestamp_t,
+    modified: __wasi_timestamp_t,
+    created: __wasi_timestamp_t,
+}
+
+impl FileData {
+    fn new(bytes: Vec<u8>) -> Self {
+        let now = now();
+        Self {
+            bytes,
+            accessed: now,
+            modified: now,
+            created: now,
+        }
+    }
+
+    fn metadata(&self) -> Metadata {
+        Metadata {
+            file_type: __WASI_FILETYPE_REGULAR_FILE,
+            len: self.bytes.len() as u64,
+            accessed: self.accessed,
+            modified: self.modified,
+            created: self.created,
+        }
+    }
+}
+
+fn now() -> __wasi_timestamp_t {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|time| time.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+fn error(kind: io::ErrorKind, message: &str) -> io::Error {
+    io::Error::new(kind, message.to_string())
+}
+
+fn not_found() -> io::Error {
+    error(io::ErrorKind::NotFound, "no such file or directory")
+}
+
+fn not_a_dir() -> io::Error {
+    error(io::ErrorKind::Other, "not a directory")
+}
+
+/// Returns the names of the entries on the way to `path` from the root.
+fn segments(path: &Path) -> io::Result<Vec<String>> {
+    let mut segments = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir | Component::CurDir => {}
+            Component::ParentDir => {
+                return Err(error(
+                    io::ErrorKind::InvalidInput,
+                    "`..` can't be used in the paths of a memory file system",
+                ))
+            }
+            Component::Normal(segment) => segments.push(
+                segment
+                    .to_str()
+                    .ok_or_else(|| error(io::ErrorKind::InvalidInput, "the path isn't UTF-8"))?
+                    .to_string(),
+            ),
+        }
+    }
+    Ok(segments)
+}
+
+/// Returns the entries of the directory at `segments`.
+fn dir_mut<'a>(
+    root: &'a mut BTreeMap<String, Node>,
+    segments: &[String],
+) -> io::Result<&'a mut BTreeMap<String, Node>> {
+    segments.iter().try_fold(root, |dir, segment| {
+        match dir.get_mut(segment).ok_or_else(not_found)? {
+            Node::Dir(entries) => Ok(entries),
+            Node::File(_) => Err(not_a_dir()),
+        }
+    })
+}
+
+/// Returns the entries of the parent directory of the entry at `path`,
+/// and the name of the entry.
+fn parent_mut<'a>(
+    root: &'a mut BTreeMap<String, Node>,
+    path: &Path,
+) -> io::Result<(&'a mut BTreeMap<String, Node>, String)> {
+    let mut segments = segments(path)?;
+    let name = segments.pop().ok_or_else(|| {
+        error(
+            io::ErrorKind::InvalidInput,
+            "the root of a memory file system can't be changed",
+        )
+    })?;
+    Ok((dir_mut(root, &segments)?, name))
+}
+
+impl MemFileSystem {
+    /// Returns an empty file system.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn root(&self) -> MutexGuard<BTreeMap<String, Node>> {
+        self.root.lock().unwrap()
+    }
+
+    /// Returns the data of the file at `path`.
+    fn file(&self, path: &Path) -> io::Result<Arc<Mutex<FileData>>> {
+        let mut root = self.root();
+        let (parent, name) = parent_mut(&mut root, path)?;
+        match parent.get(&name).ok_or_else(not_found)? {
+            Node::File(data) => Ok(data.clone()),
+            Node::Dir(_) => Err(error(io::ErrorKind::Other, "is a directory")),
+        }
+    }
+
+    /// Creates or replaces the file at `path` with `contents`, creating
+    /// its parent directories as needed.
+    pub fn write_file(
+        &self,
+        path: impl AsRef<Path>,
+        contents: impl Into<Vec<u8>>,
+    ) -> io::Result<()> {
+        let mut root = self.root();
+        let mut segments = segments(path.as_ref())?;
+        let name = segments.pop().ok_or_else(not_found)?;
+        let mut dir = &mut *root;
+        for segment in segments {
+            dir = match dir
+                .entry(segment)
+                .or_insert_with(|| Node::Dir(BTreeMap::new()))
+            {
+                Node::Dir(entries) => entries,
+                Node::File(_) => return Err(not_a_dir()),
+            };
+        }
+        match dir.get(&name) {
+            Some(Node::Dir(_)) => Err(error(io::ErrorKind::Other, "is a directory")),
+            Some(Node::File(data)) => {
+                let mut data = data.lock().unwrap();
+                data.bytes = contents.into();
+                data.modified = now();
+                Ok(())
+            }
+            None => {
+                let data = FileData::new(contents.into());
+                dir.insert(name, Node::File(Arc::new(Mutex::new(data))));
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns the contents of the file at `path`.
+    pub fn read_file(&self, path: impl AsRef<Path>) -> io::Result<Vec<u8>> {
+        Ok(self.file(path.as_ref())?.lock().unwrap().bytes.clone())
+    }
+}
+
+impl FileSystem for MemFileSystem {
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<DirEntry>> {
+        let mut root = self.root();
+        let dir = dir_mut(&mut root, &segments(path)?)?;
+        Ok(dir
+            .iter()
+            .map(|(name, node)| DirEntry {
+                name: name.clone(),
+                file_type: match node {
+                    Node::Dir(_) => __WASI_FILETYPE_DIRECTORY,
+                    Node::File(_) => __WASI_FILETYPE_REGULAR_FILE,
+                },
+            })
+            .collect())
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        let mut root = self.root();
+        let (parent, name) = parent_mut(&mut root, path)?;
+        if parent.contains_key(&name) {
+            return Err(error(io::ErrorKind::AlreadyExists, "the entry exists"));
+        }
+        parent.insert(name, Node::Dir(BTreeMap::new()));
+        Ok(())
+    }
+
+    fn remove_dir(&self, path: &Path) -> io::Result<()> {
+        let mut root = self.root();
+        let (parent, name) = parent_mut(&mut root, path)?;
+        match parent.get(&name).ok_or_else(not_found)? {
+            Node::Dir(entries) if entries.is_empty() => {}
+            Node::Dir(_) => return Err(error(io::ErrorKind::Other, "directory not empty")),
+            Node::File(_) => return Err(not_a_dir()),
+        }
+        parent.remove(&name);
+        Ok(())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let (from_segments, to_segments) = (segments(from)?, segments(to)?);
+        if to_segments.len() > from_segments.len() && to_segments.starts_with(&from_segments) {
+            return Err(error(
+                io::ErrorKind::InvalidInput,
+                "a directory can't be moved into itself",
+            ));
+        }
+        let mut root = self.root();
+        // Checks the destination before taking the source out of the tree.
+        parent_mut(&mut root, to)?;
+        let (parent, name) = parent_mut(&mut root, from)?;
+        let node = parent.remove(&name).ok_or_else(not_found)?;
+        let (parent, name) = parent_mut(&mut root, to)?;
+        parent.insert(name, node);
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        let mut root = self.root();
+        let (parent, name) = parent_mut(&mut root, path)?;
+        match parent.get(&name).ok_or_else(not_found)? {
+            Node::File(_) => {}
+            Node::Dir(_) => return Err(error(io::ErrorKind::Other, "is a directory")),
+        }
+        parent.remove(&name);
+        Ok(())
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let mut root = self.root();
+        let segments = segments(path)?;
+        if segments.is_empty() {
+            return Ok(Metadata {
+                file_type: __WASI_FILETYPE_DIRECTORY,
+                ..Metadata::default()
+            });
+        }
+        let (parent, name) = parent_mut(&mut root, path)?;
+        match parent.get(&name).ok_or_else(not_found)? {
+            Node::Dir(_) => Ok(Metadata {
+                file_type: __WASI_FILETYPE_DIRECTORY,
+                ..Metadata::default()
+            }),
+            Node::File(data) => Ok(data.lock().unwrap().metadata()),
+        }
+    }
+
+    fn open(&self, path: &Path, options: &OpenOptions) -> io::Result<Box<dyn WasiFile>> {
+        let data = {
+            let mut root = self.root();
+            let (parent, name) = parent_mut(&mut root, path)?;
+            match parent.get(&name) {
+                Some(_) if options.create_new => {
+                    return Err(error(io::ErrorKind::AlreadyExists, "the file exists"))
+                }
+                Some(Node::Dir(_)) => return Err(error(io::ErrorKind::Other, "is a directory")),
+                Some(Node::File(data)) => {
+                    if options.truncate {
+                        let mut data = data.lock().unwrap();
+                        data.bytes.clear();
+                        data.modified = now();
+                    }
+                    data.clone()
+                }
+                None if options.create || options.create_new => {
+                    let data = Arc::new(Mutex::new(FileData::new(Vec::new())));
+                    parent.insert(name, Node::File(data.clone()));
+                    data
+                }
+                None => return Err(not_found()),
+            }
+        };
+        Ok(Box::new(MemFile {
+            fs: self.clone(),
+            path: Mutex::new(path.to_path_buf()),
+            data,
+            position: 0,
+            read: options.read,
+            write: options.write,
+            append: options.append,
+        }))
+    }
+}
+
+/// A file of a [`MemFileSystem`].
+///
+/// When the state of WASI is serialized, the contents of the file are
+/// saved with it, and the deserialized file is detached from the file
+/// system.
+#[derive(Debug)]
+pub struct MemFile {
+    fs: MemFileSystem,
+    path: Mutex<PathBuf>,
+    data: Arc<Mutex<FileData>>,
+    position: u64,
+    read: bool,
+    write: bool,
+    append: bool,
+}
+
+impl MemFile {
+    fn data(&self) -> MutexGuard<FileData> {
+        self.data.lock().unwrap()
+    }
+}
+
+/// The serialized form of a [`MemFile`].
+#[derive(Serialize, Deserialize)]
+struct MemFileSnapshot {
+    path: PathBuf,
+    bytes: Vec<u8>,
+    accessed: __wasi_timestamp_t,
+    modified: __wasi_timestamp_t,
+    created: __wasi_timestamp_t,
+    position: u64,
+    read: bool,
+    write: bool,
+    append: bool,
+}
+
+impl Serialize for MemFile {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        let data = self.data();
+        MemFileSnapshot {
+            path: self.path.lock().unwrap().clone(),
+            bytes: data.bytes.clone(),
+            accessed: data.accessed,
+            modified: data.modified,
+            created: data.created,
+            position: self.position,
+            read: self.read,
+            write: self.write,
+            append: self.append,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for MemFile {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        let snapshot = MemFileSnapshot::deserialize(deserializer)?;
+        let fs = MemFileSystem::new();
+        fs.write_file(&snapshot.path, snapshot.bytes)
+            .map_err(de::Error::custom)?;
+        let data = fs.file(&snapshot.path).map_err(de::Error::custom)?;
+        {
+            let mut data = data.lock().unwrap();
+            data.accessed = snapshot.accessed;
+            data.modified = snapshot.modified;
+            data.created = snapshot.created;
+        }
+        Ok(Self {
+            fs,
+            path: Mutex::new(snapshot.path),
+            data,
+            position: snapshot.position,
+            read: snapshot.read,
+            write: snapshot.write,
+            append: snapshot.append,
+        })
+    }
+}
+
+impl Read for MemFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if !self.read {
+            return Err(error(
+                io::ErrorKind::PermissionDenied,
+                "the file isn't opened for reading",
+            ));
+        }
+        let position = self.position;
+        let mut data = self.data();
+        let start = (position as usize).min(data.bytes.len());
+        let read = (&data.bytes[start..]).read(buf)?;
+        data.accessed = now();
+        drop(data);
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl Seek for MemFile {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            io::SeekFrom::Start(offset) => offset as i128,
+            io::SeekFrom::End(offset) => self.data().bytes.len() as i128 + offset as i128,
+            io::SeekFrom::Current(offset) => self.position as i128 + offset as i128,
+        };
+        if position < 0 || position > i64::MAX as i128 {
+            return Err(error(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            ));
+        }
+        self.position = position as u64;
+        Ok(self.position)
+    }
+}
+
+impl Write for MemFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.write && !self.append {
+            return Err(error(
+                io::ErrorKind::PermissionDenied,
+                "the file isn't opened for writing",
+            ));
+        }
+        let mut data = self.data();
+        let start = if self.append {
+            data.bytes.len()
+        } else {
+            self.position as usize
+        };
+        let end = start + buf.len();
+        if data.bytes.len() < end {
+            data.bytes.resize(end, 0);
+        }
+        data.bytes[start..end].copy_from_slice(buf);
+        data.modified = now();
+        drop(data);
+        self.position = end as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[typetag::serde]
+impl WasiFile for MemFile {
+    fn last_accessed(&self) -> __wasi_timestamp_t {
+        self.data().accessed
+    }
+
+    fn set_last_accessed(&self, last_accessed: __wasi_timestamp_t) {
+        self.data().accessed = last_accessed;
+    }
+
+    fn last_modified(&self) -> __wasi_timestamp_t {
+        self.data().modified
+    }
+
+    fn set_last_modified(&self, last_modified: __wasi_timestamp_t) {
+        self.data().modified = last_modified;
+    }
+
+    fn created_time(&self) -> __wasi_timestamp_t {
+        self.data().created
+    }
+
+    fn set_created_time(&self, created_time: __wasi_timestamp_t) {
+        self.data().created = created_time;
+    }
+
+    fn size(&self) -> u64 {
+        self.data().bytes.len() as u64
+    }
+
+    fn set_len(&mut self, new_size: __wasi_filesize_t) -> Result<(), WasiFsError> {
+        if !self.write && !self.append {
+            return Err(WasiFsError::PermissionDenied);
+        }
+        let mut data = self.data();
+        data.bytes.resize(new_size as usize, 0);
+        data.modified = now();
+        Ok(())
+    }
+
+    fn unlink(&mut self) -> Result<(), WasiFsError> {
+        let path = self.path.lock().unwrap().clone();
+        self.fs.remove_file(&path).map_err(Into::into)
+    }
+
+    fn rename_file(&self, new_name: &Path) -> Result<(), WasiFsError> {
+        let mut path = self.path.lock().unwrap();
+        self.fs.rename(&path, new_name)?;
+        *path = new_name.to_path_buf();
+        Ok(())
+    }
+
+    fn bytes_available(&self) -> Result<usize, WasiFsError> {
+        let len = self.data().bytes.len() as u64;
+        Ok(len.saturating_sub(self.position) as usize)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn read_and_write_files() {
+        let fs = MemFileSystem::new();
+        fs.write_file("/a/b.txt", "hello").unwrap();
+        assert!(fs.metadata(Path::new("/a")).unwrap().is_dir());
+        assert_eq!(fs.metadata(Path::new("/a/b.txt")).unwrap().len, 5);
+
+        let mut file = fs
+            .open(
+                Path::new("a/b.txt"),
+                OpenOptions::new().read(true).append(true),
+            )
+            .unwrap();
+        file.write_all(b", world").unwrap();
+        file.seek(io::SeekFrom::Start(0)).unwrap();
+        let mut contents = String::new();
+        file.read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "hello, world");
+
+        file.rename_file(Path::new("/c.txt")).unwrap();
+        assert_eq!(fs.read_file("/c.txt").unwrap(), b"hello, world");
+        assert_eq!(
+            fs.read_dir(Path::new("/")).unwrap(),
+            vec![
+                DirEntry {
+                    name: "a".to_string(),
+                    file_type: __WASI_FILETYPE_DIRECTORY,
+                },
+                DirEntry {
+                    name: "c.txt".to_string(),
+                    file_type: __WASI_FILETYPE_REGULAR_FILE,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn directories() {
+        let fs = MemFileSystem::new();
+        fs.create_dir(Path::new("/a")).unwrap();
+        fs.create_dir(Path::new("/a/b")).unwrap();
+        assert_eq!(
+            fs.create_dir(Path::new("/c/d")).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+        assert!(fs.remove_dir(Path::new("/a")).is_err());
+        assert!(fs.rename(Path::new("/a"), Path::new("/a/b/a")).is_err());
+        fs.rename(Path::new("/a/b"), Path::new("/b")).unwrap();
+        fs.remove_dir(Path::new("/a")).unwrap();
+        assert!(fs.metadata(Path::new("/b")).unwrap().is_dir());
+        assert!(fs.metadata(Path::new("/a")).is_err());
+    }
+}
@@ -0,0 +1,187 @@
+//! The file systems backing the preopened directories.
+//!
+//! By default, a preopened directory is a directory of the host, but it
+//! can be backed by any implementation of [`FileSystem`] with
+//! [`PreopenDirBuilder::filesystem`]: a [`MemFileSystem`] for hermetic
+//! sandboxes, a [`ReadOnlyFileSystem`] over the directories of the host,
+//! or a custom one reading an archive or a remote storage.
+//!
+//! The paths given to a [`FileSystem`] are the paths of the entries in
+//! it: the path of the preopened directory followed by the path given by
+//! the WASI program, where the `.` and `..` segments have been resolved
+//! already.
+//!
+//! [`PreopenDirBuilder::filesystem`]: crate::PreopenDirBuilder::filesystem
+
+mod host;
+mod mem;
+mod read_only;
+mod restricted;
+
+pub use self::host::HostFileSystem;
+pub use self::mem::{MemFile, MemFileSystem};
+pub use self::read_only::{ReadOnlyFile, ReadOnlyFileSystem};
+pub(crate) use self::restricted::RestrictedFileSystem;
+
+use crate::state::WasiFile;
+use crate::syscalls::types::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A file system which can back a preopened directory.
+pub trait FileSystem: fmt::Debug + Send + Sync + 'static {
+    /// Returns the entries of the directory at `path`.
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<DirEntry>>;
+
+    /// Creates the directory at `path`, whose parent exists.
+    fn create_dir(&self, path: &Path) -> io::Result<()>;
+
+    /// Removes the empty directory at `path`.
+    fn remove_dir(&self, path: &Path) -> io::Result<()>;
+
+    /// Moves the file or the directory at `from` to `to`.
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+
+    /// Removes the file at `path`.
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+
+    /// Returns the metadata of the entry at `path`, following the
+    /// symlinks.
+    fn metadata(&self, path: &Path) -> io::Result<Metadata>;
+
+    /// Returns the metadata of the entry at `path`, without following
+    /// it if it's a symlink.
+    fn symlink_metadata(&self, path: &Path) -> io::Result<Metadata> {
+        self.metadata(path)
+    }
+
+    /// Returns the value of the symlink at `path`.
+    fn read_link(&self, _path: &Path) -> io::Result<PathBuf> {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "the file system has no symlinks",
+        ))
+    }
+
+    /// Opens the file at `path`.
+    fn open(&self, path: &Path, options: &OpenOptions) -> io::Result<Box<dyn WasiFile>>;
+}
+
+/// How a file is opened, like [`std::fs::OpenOptions`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OpenOptions {
+    /// The file can be read.
+    pub read: bool,
+    /// The file can be written.
+    pub write: bool,
+    /// The writes go to the end of the file.
+    pub append: bool,
+    /// The file is truncated when it's opened.
+    pub truncate: bool,
+    /// The file is created if it doesn't exist.
+    pub create: bool,
+    /// The file is created, and it must not exist.
+    pub create_new: bool,
+}
+
+impl OpenOptions {
+    /// Returns options opening nothing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether the file can be read.
+    pub fn read(&mut self, read: bool) -> &mut Self {
+        self.read = read;
+        self
+    }
+
+    /// Sets whether the file can be written.
+    pub fn write(&mut self, write: bool) -> &mut Self {
+        self.write = write;
+        self
+    }
+
+    /// Sets whether the writes go to the end of the file.
+    pub fn append(&mut self, append: bool) -> &mut Self {
+        self.append = append;
+        self
+    }
+
+    /// Sets whether the file is truncated when it's opened.
+    pub fn truncate(&mut self, truncate: bool) -> &mut Self {
+        self.truncate = truncate;
+        self
+    }
+
+    /// Sets whether the file is created if it doesn't exist.
+    pub fn create(&mut self, create: bool) -> &mut Self {
+        self.create = create;
+        self
+    }
+
+    /// Sets whether the file is created, and must not exist.
+    pub fn create_new(&mut self, create_new: bool) -> &mut Self {
+        self.create_new = create_new;
+        self
+    }
+}
+
+/// The metadata of an entry of a [`FileSystem`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Metadata {
+    /// The type of the entry, one of the `__WASI_FILETYPE_*`.
+    pub file_type: __wasi_filetype_t,
+    /// The size of the file in bytes.
+    pub len: u64,
+    /// The last time the entry was accessed in nanoseconds as a UNIX
+    /// timestamp.
+    pub accessed: __wasi_timestamp_t,
+    /// The last time the entry was modified in nanoseconds as a UNIX
+    /// timestamp.
+    pub modified: __wasi_timestamp_t,
+    /// The time at which the entry was created in nanoseconds as a UNIX
+    /// timestamp.
+    pub created: __wasi_timestamp_t,
+}
+
+impl Metadata {
+    /// Returns whether the entry is a directory.
+    pub fn is_dir(&self) -> bool {
+        self.file_type == __WASI_FILETYPE_DIRECTORY
+    }
+
+    /// Returns whether the entry is a regular file.
+    pub fn is_file(&self) -> bool {
+        self.file_type == __WASI_FILETYPE_REGULAR_FILE
+    }
+
+    /// Returns whether the entry is a symlink.
+    pub fn is_symlink(&self) -> bool {
+        self.file_type == __WASI_FILETYPE_SYMBOLIC_LINK
+    }
+}
+
+/// An entry of a directory of a [`FileSystem`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirEntry {
+    /// The name of the entry.
+    pub name: String,
+    /// The type of the entry, one of the `__WASI_FILETYPE_*`.
+    pub file_type: __wasi_filetype_t,
+}
+
+/// The file system of a file or a directory of the [`WasiFs`]: the host
+/// file system, or one of the file systems of the preopened
+/// directories.
+///
+/// [`WasiFs`]: crate::WasiFs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FsId(pub(crate) usize);
+
+impl FsId {
+    /// The file system of the host.
+    pub const HOST: Self = Self(0);
+}
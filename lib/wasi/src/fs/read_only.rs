@@ -0,0 +1,151 @@
+use super::{DirEntry, FileSystem, Metadata, OpenOptions};
+use crate::state::{WasiFile, WasiFsError};
+use crate::syscalls::types::*;
+use serde::{Deserialize, Serialize};
+use std::io::{self, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+
+fn read_only() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::PermissionDenied,
+        "the file system is read-only",
+    )
+}
+
+/// A file system which can only be read, over another file system.
+///
+/// ```
+/// # use wasmer_wasi::{HostFileSystem, ReadOnlyFileSystem, WasiState};
+/// WasiState::new("program")
+///     .preopen(|p| {
+///         p.directory(".")
+///             .alias("data")
+///             .read(true)
+///             .filesystem(ReadOnlyFileSystem::new(HostFileSystem::new()))
+///     })
+///     .unwrap();
+/// ```
+#[derive(Debug)]
+pub struct ReadOnlyFileSystem {
+    inner: Box<dyn FileSystem>,
+}
+
+impl ReadOnlyFileSystem {
+    /// Returns a file system reading the entries of `inner`.
+    pub fn new(inner: impl FileSystem) -> Self {
+        Self {
+            inner: Box::new(inner),
+        }
+    }
+}
+
+impl FileSystem for ReadOnlyFileSystem {
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<DirEntry>> {
+        self.inner.read_dir(path)
+    }
+
+    fn create_dir(&self, _path: &Path) -> io::Result<()> {
+        Err(read_only())
+    }
+
+    fn remove_dir(&self, _path: &Path) -> io::Result<()> {
+        Err(read_only())
+    }
+
+    fn rename(&self, _from: &Path, _to: &Path) -> io::Result<()> {
+        Err(read_only())
+    }
+
+    fn remove_file(&self, _path: &Path) -> io::Result<()> {
+        Err(read_only())
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        self.inner.metadata(path)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> io::Result<Metadata> {
+        self.inner.symlink_metadata(path)
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        self.inner.read_link(path)
+    }
+
+    fn open(&self, path: &Path, options: &OpenOptions) -> io::Result<Box<dyn WasiFile>> {
+        if options.write
+            || options.append
+            || options.truncate
+            || options.create
+            || options.create_new
+        {
+            return Err(read_only());
+        }
+        Ok(Box::new(ReadOnlyFile(self.inner.open(path, options)?)))
+    }
+}
+
+/// A file of a [`ReadOnlyFileSystem`], which can't be written.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReadOnlyFile(Box<dyn WasiFile>);
+
+impl Read for ReadOnlyFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl Seek for ReadOnlyFile {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        self.0.seek(pos)
+    }
+}
+
+impl Write for ReadOnlyFile {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(read_only())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[typetag::serde]
+impl WasiFile for ReadOnlyFile {
+    fn last_accessed(&self) -> __wasi_timestamp_t {
+        self.0.last_accessed()
+    }
+
+    fn last_modified(&self) -> __wasi_timestamp_t {
+        self.0.last_modified()
+    }
+
+    fn created_time(&self) -> __wasi_timestamp_t {
+        self.0.created_time()
+    }
+
+    fn size(&self) -> u64 {
+        self.0.size()
+    }
+
+    fn set_len(&mut self, _new_size: __wasi_filesize_t) -> Result<(), WasiFsError> {
+        Err(WasiFsError::PermissionDenied)
+    }
+
+    fn unlink(&mut self) -> Result<(), WasiFsError> {
+        Err(WasiFsError::PermissionDenied)
+    }
+
+    fn rename_file(&self, _new_name: &Path) -> Result<(), WasiFsError> {
+        Err(WasiFsError::PermissionDenied)
+    }
+
+    fn bytes_available(&self) -> Result<usize, WasiFsError> {
+        self.0.bytes_available()
+    }
+
+    fn get_raw_fd(&self) -> Option<i32> {
+        self.0.get_raw_fd()
+    }
+}
@@ -0,0 +1,354 @@
+use super::{DirEntry, FileSystem, Metadata, OpenOptions};
+use crate::state::{WasiFile, WasiFsError};
+use crate::syscalls::types::*;
+use glob::{MatchOptions, Pattern};
+use serde::{Deserialize, Serialize};
+use std::io::{self, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+fn hidden() -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, "the entry is filtered out")
+}
+
+/// The entries of a preopened directory seen by the WASI program.
+#[derive(Debug)]
+struct PathFilter {
+    root: PathBuf,
+    allow: Vec<Pattern>,
+    deny: Vec<Pattern>,
+}
+
+impl PathFilter {
+    fn is_visible(&self, path: &Path, is_dir: bool) -> bool {
+        let relative = match path.strip_prefix(&self.root) {
+            Ok(relative) => relative,
+            Err(_) => return false,
+        };
+        if relative.as_os_str().is_empty() {
+            return true;
+        }
+        // Everything under a denied directory is hidden too.
+        if relative.ancestors().any(|ancestor| {
+            self.deny
+                .iter()
+                .any(|pattern| pattern.matches_path_with(ancestor, MATCH_OPTIONS))
+        }) {
+            return false;
+        }
+        is_dir
+            || self.allow.is_empty()
+            || self
+                .allow
+                .iter()
+                .any(|pattern| pattern.matches_path_with(relative, MATCH_OPTIONS))
+    }
+
+    fn check(&self, path: &Path, is_dir: bool) -> io::Result<()> {
+        if self.is_visible(path, is_dir) {
+            Ok(())
+        } else {
+            Err(hidden())
+        }
+    }
+}
+
+/// A file system restricting the entries of a preopened directory seen
+/// by the WASI program, and the size of its files.
+///
+/// The patterns are matched against the paths relative to the preopened
+/// directory. An entry matching a `deny` pattern is hidden, along with
+/// everything under it when it's a directory. When there are `allow`
+/// patterns, the files which don't match any of them are hidden too; the
+/// directories stay visible so the allowed files under them can be
+/// reached.
+#[derive(Debug)]
+pub(crate) struct RestrictedFileSystem {
+    inner: Arc<dyn FileSystem>,
+    filter: Arc<PathFilter>,
+    max_file_size: Option<u64>,
+}
+
+impl RestrictedFileSystem {
+    pub(crate) fn new(
+        inner: Arc<dyn FileSystem>,
+        root: PathBuf,
+        allow: Vec<Pattern>,
+        deny: Vec<Pattern>,
+        max_file_size: Option<u64>,
+    ) -> Self {
+        Self {
+            inner,
+            filter: Arc::new(PathFilter { root, allow, deny }),
+            max_file_size,
+        }
+    }
+}
+
+impl FileSystem for RestrictedFileSystem {
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<DirEntry>> {
+        self.filter.check(path, true)?;
+        let mut entries = self.inner.read_dir(path)?;
+        entries.retain(|entry| {
+            self.filter.is_visible(
+                &path.join(&entry.name),
+                entry.file_type == __WASI_FILETYPE_DIRECTORY,
+            )
+        });
+        Ok(entries)
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        self.filter.check(path, true)?;
+        self.inner.create_dir(path)
+    }
+
+    fn remove_dir(&self, path: &Path) -> io::Result<()> {
+        self.filter.check(path, true)?;
+        self.inner.remove_dir(path)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let is_dir = self.inner.symlink_metadata(from)?.is_dir();
+        self.filter.check(from, is_dir)?;
+        self.filter.check(to, is_dir)?;
+        self.inner.rename(from, to)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        self.filter.check(path, false)?;
+        self.inner.remove_file(path)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let metadata = self.inner.metadata(path)?;
+        self.filter.check(path, metadata.is_dir())?;
+        Ok(metadata)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let metadata = self.inner.symlink_metadata(path)?;
+        self.filter.check(path, metadata.is_dir())?;
+        Ok(metadata)
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        self.filter.check(path, false)?;
+        self.inner.read_link(path)
+    }
+
+    fn open(&self, path: &Path, options: &OpenOptions) -> io::Result<Box<dyn WasiFile>> {
+        self.filter.check(path, false)?;
+        Ok(Box::new(RestrictedFile {
+            inner: self.inner.open(path, options)?,
+            filter: Some(self.filter.clone()),
+            max_file_size: self.max_file_size,
+            append: options.append,
+        }))
+    }
+}
+
+/// A file of a [`RestrictedFileSystem`], which can't grow past the
+/// maximum size, nor be moved out of sight.
+///
+/// The path filter isn't serialized, so the deserialized file can't be
+/// moved anymore.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct RestrictedFile {
+    inner: Box<dyn WasiFile>,
+    #[serde(skip)]
+    filter: Option<Arc<PathFilter>>,
+    max_file_size: Option<u64>,
+    append: bool,
+}
+
+impl RestrictedFile {
+    /// Returns whether the file can grow to `size` bytes.
+    fn fits(&self, size: u64) -> bool {
+        self.max_file_size.map_or(true, |max_file_size| {
+            size <= max_file_size || size <= self.inner.size()
+        })
+    }
+}
+
+impl Read for RestrictedFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+impl Seek for RestrictedFile {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+impl Write for RestrictedFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let start = if self.append {
+            self.inner.size()
+        } else {
+            self.inner.seek(io::SeekFrom::Current(0))?
+        };
+        if !self.fits(start.saturating_add(buf.len() as u64)) {
+            return Err(io::Error::new(
+                io::ErrorKind::WriteZero,
+                "the file would exceed its maximum size",
+            ));
+        }
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[typetag::serde]
+impl WasiFile for RestrictedFile {
+    fn last_accessed(&self) -> __wasi_timestamp_t {
+        self.inner.last_accessed()
+    }
+
+    fn set_last_accessed(&self, last_accessed: __wasi_timestamp_t) {
+        self.inner.set_last_accessed(last_accessed)
+    }
+
+    fn last_modified(&self) -> __wasi_timestamp_t {
+        self.inner.last_modified()
+    }
+
+    fn set_last_modified(&self, last_modified: __wasi_timestamp_t) {
+        self.inner.set_last_modified(last_modified)
+    }
+
+    fn created_time(&self) -> __wasi_timestamp_t {
+        self.inner.created_time()
+    }
+
+    fn set_created_time(&self, created_time: __wasi_timestamp_t) {
+        self.inner.set_created_time(created_time)
+    }
+
+    fn size(&self) -> u64 {
+        self.inner.size()
+    }
+
+    fn set_len(&mut self, new_size: __wasi_filesize_t) -> Result<(), WasiFsError> {
+        if !self.fits(new_size) {
+            return Err(WasiFsError::WriteZero);
+        }
+        self.inner.set_len(new_size)
+    }
+
+    fn unlink(&mut self) -> Result<(), WasiFsError> {
+        self.inner.unlink()
+    }
+
+    fn sync_to_disk(&self) -> Result<(), WasiFsError> {
+        self.inner.sync_to_disk()
+    }
+
+    fn rename_file(&self, new_name: &Path) -> Result<(), WasiFsError> {
+        match &self.filter {
+            Some(filter) if filter.is_visible(new_name, false) => self.inner.rename_file(new_name),
+            _ => Err(WasiFsError::PermissionDenied),
+        }
+    }
+
+    fn bytes_available(&self) -> Result<usize, WasiFsError> {
+        self.inner.bytes_available()
+    }
+
+    fn get_raw_fd(&self) -> Option<i32> {
+        self.inner.get_raw_fd()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::fs::MemFileSystem;
+
+    fn restricted(
+        allow: &[&str],
+        deny: &[&str],
+        max_file_size: Option<u64>,
+    ) -> RestrictedFileSystem {
+        let fs = MemFileSystem::new();
+        fs.write_file("/data/a.txt", "a").unwrap();
+        fs.write_file("/data/b.bin", "b").unwrap();
+        fs.write_file("/data/secrets/key.txt", "key").unwrap();
+        let patterns = |patterns: &[&str]| {
+            patterns
+                .iter()
+                .map(|pattern| Pattern::new(pattern).unwrap())
+                .collect()
+        };
+        RestrictedFileSystem::new(
+            Arc::new(fs),
+            PathBuf::from("/data"),
+            patterns(allow),
+            patterns(deny),
+            max_file_size,
+        )
+    }
+
+    fn names(fs: &RestrictedFileSystem, path: &str) -> Vec<String> {
+        fs.read_dir(Path::new(path))
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect()
+    }
+
+    #[test]
+    fn path_filters() {
+        let fs = restricted(&["**/*.txt"], &["secrets"], None);
+        assert_eq!(names(&fs, "/data"), vec!["a.txt"]);
+        assert!(fs.metadata(Path::new("/data/a.txt")).is_ok());
+        assert!(fs.metadata(Path::new("/data/b.bin")).is_err());
+        assert!(fs.metadata(Path::new("/data/secrets")).is_err());
+        assert!(fs.metadata(Path::new("/data/secrets/key.txt")).is_err());
+        assert!(fs
+            .open(Path::new("/data/b.bin"), OpenOptions::new().read(true))
+            .is_err());
+        assert!(fs
+            .open(
+                Path::new("/data/secrets/key.txt"),
+                OpenOptions::new().read(true)
+            )
+            .is_err());
+        let file = fs
+            .open(Path::new("/data/a.txt"), OpenOptions::new().read(true))
+            .unwrap();
+        assert!(file.rename_file(Path::new("/data/secrets")).is_err());
+        assert!(file.rename_file(Path::new("/data/secrets/a.txt")).is_err());
+        file.rename_file(Path::new("/data/c.txt")).unwrap();
+
+        let fs = restricted(&["*.bin"], &[], None);
+        assert_eq!(names(&fs, "/data"), vec!["b.bin", "secrets"]);
+        assert!(names(&fs, "/data/secrets").is_empty());
+    }
+
+    #[test]
+    fn max_file_size() {
+        let fs = restricted(&[], &[], Some(4));
+        let mut file = fs
+            .open(
+                Path::new("/data/a.txt"),
+                OpenOptions::new().write(true).append(true),
+            )
+            .unwrap();
+        file.write_all(b"bc").unwrap();
+        assert!(file.write_all(b"de").is_err());
+        assert!(file.set_len(8).is_err());
+        file.set_len(2).unwrap();
+    }
+}
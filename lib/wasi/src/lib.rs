@@ -14,17 +14,28 @@
 
 #[macro_use]
 mod macros;
+mod fs;
+mod net;
 mod ptr;
 mod state;
+mod syscall_policy;
 mod syscalls;
 mod utils;
 
 use crate::syscalls::*;
 
+pub use crate::fs::{
+    DirEntry, FileSystem, FsId, HostFileSystem, MemFile, MemFileSystem, Metadata, OpenOptions,
+    ReadOnlyFile, ReadOnlyFileSystem,
+};
+pub use crate::net::{FullNetworking, NetworkAllowList, NetworkPolicy, NoNetworking, Protocol};
 pub use crate::state::{
-    Fd, Pipe, Stderr, Stdin, Stdout, WasiFile, WasiFs, WasiFsError, WasiState, WasiStateBuilder,
+    bounded_pipe, AsyncReadFile, AsyncWriteFile, DeterministicClock, Fd, HostClock, HostRandom,
+    Pipe, PipeReader, PipeWriter, PreopenDirBuilder, SeededRandom, Stderr, Stdin, Stdout,
+    WasiClock, WasiFile, WasiFs, WasiFsError, WasiRandom, WasiState, WasiStateBuilder,
     WasiStateCreationError, ALL_RIGHTS, VIRTUAL_ROOT_FD,
 };
+pub use crate::syscall_policy::SyscallPolicy;
 pub use crate::syscalls::types;
 pub use crate::utils::{get_wasi_version, is_wasi_module, WasiVersion};
 
@@ -116,12 +127,19 @@ pub fn generate_import_object_from_env(
     wasi_env: WasiEnv,
     version: WasiVersion,
 ) -> ImportObject {
-    match version {
-        WasiVersion::Snapshot0 => generate_import_object_snapshot0(store, wasi_env),
-        WasiVersion::Snapshot1 | WasiVersion::Latest => {
-            generate_import_object_snapshot1(store, wasi_env)
-        }
-    }
+    let syscall_policy = wasi_env.state().syscall_policy.clone();
+    let (mut import_object, namespace) = match version {
+        WasiVersion::Snapshot0 => (
+            generate_import_object_snapshot0(store, wasi_env),
+            "wasi_unstable",
+        ),
+        WasiVersion::Snapshot1 | WasiVersion::Latest => (
+            generate_import_object_snapshot1(store, wasi_env),
+            "wasi_snapshot_preview1",
+        ),
+    };
+    syscall_policy.apply(store, &mut import_object, namespace);
+    import_object
 }
 
 // Note: we use this wrapper because native functions with more than 9 params
@@ -251,8 +269,17 @@ fn generate_import_object_snapshot1(store: &Store, env: WasiEnv) -> ImportObject
             "proc_raise" => Function::new_native_with_env(store, env.clone(), proc_raise),
             "random_get" => Function::new_native_with_env(store, env.clone(), random_get),
             "sched_yield" => Function::new_native_with_env(store, env.clone(), sched_yield),
+            "sock_accept" => Function::new_native_with_env(store, env.clone(), sock_accept),
+            "sock_addr_local" => Function::new_native_with_env(store, env.clone(), sock_addr_local),
+            "sock_addr_remote" => Function::new_native_with_env(store, env.clone(), sock_addr_remote),
+            "sock_bind" => Function::new_native_with_env(store, env.clone(), sock_bind),
+            "sock_connect" => Function::new_native_with_env(store, env.clone(), sock_connect),
+            "sock_listen" => Function::new_native_with_env(store, env.clone(), sock_listen),
+            "sock_open" => Function::new_native_with_env(store, env.clone(), sock_open),
             "sock_recv" => Function::new_native_with_env(store, env.clone(), sock_recv),
+            "sock_recv_from" => Function::new_native_with_env(store, env.clone(), sock_recv_from),
             "sock_send" => Function::new_native_with_env(store, env.clone(), sock_send),
+            "sock_send_to" => Function::new_native_with_env(store, env.clone(), sock_send_to),
             "sock_shutdown" => Function::new_native_with_env(store, env.clone(), sock_shutdown),
         }
     }
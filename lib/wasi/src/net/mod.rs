@@ -0,0 +1,155 @@
+//! The networking of the WASI programs, following the [wasi-sockets]
+//! proposal.
+//!
+//! A WASI program can open TCP and UDP sockets, but every address it
+//! binds a socket to, or connects a socket to, must be allowed by the
+//! [`NetworkPolicy`] given to [`WasiStateBuilder::network_policy`]. By
+//! default, no address is allowed.
+//!
+//! [wasi-sockets]: https://github.com/WebAssembly/wasi-sockets
+//! [`WasiStateBuilder::network_policy`]: crate::WasiStateBuilder::network_policy
+
+mod socket;
+
+pub(crate) use self::socket::WasiSocket;
+
+use std::fmt;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+/// The transport protocol of a socket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Protocol {
+    /// A TCP socket, streaming bytes over connections.
+    Tcp,
+    /// A UDP socket, sending and receiving datagrams.
+    Udp,
+}
+
+/// The policy deciding which addresses the sockets of a WASI program
+/// can use.
+pub trait NetworkPolicy: fmt::Debug + Send + Sync + 'static {
+    /// Returns whether a socket can be bound to `addr`, to listen to
+    /// connections or to receive datagrams.
+    fn allow_bind(&self, protocol: Protocol, addr: SocketAddr) -> bool;
+
+    /// Returns whether a socket can connect to `addr`, or send datagrams
+    /// to it.
+    fn allow_connect(&self, protocol: Protocol, addr: SocketAddr) -> bool;
+}
+
+/// A [`NetworkPolicy`] allowing no address, the default one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NoNetworking;
+
+impl NetworkPolicy for NoNetworking {
+    fn allow_bind(&self, _protocol: Protocol, _addr: SocketAddr) -> bool {
+        false
+    }
+
+    fn allow_connect(&self, _protocol: Protocol, _addr: SocketAddr) -> bool {
+        false
+    }
+}
+
+/// A [`NetworkPolicy`] allowing every address, like a native program.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FullNetworking;
+
+impl NetworkPolicy for FullNetworking {
+    fn allow_bind(&self, _protocol: Protocol, _addr: SocketAddr) -> bool {
+        true
+    }
+
+    fn allow_connect(&self, _protocol: Protocol, _addr: SocketAddr) -> bool {
+        true
+    }
+}
+
+/// A [`NetworkPolicy`] allowing the addresses of a list, for both TCP and
+/// UDP.
+///
+/// An unspecified IP address in the list (`0.0.0.0` or `::`) matches every
+/// IP address of its family, and the port `0` matches every port.
+///
+/// ```
+/// # use wasmer_wasi::{NetworkAllowList, WasiState};
+/// WasiState::new("proxy")
+///     .network_policy(
+///         NetworkAllowList::new()
+///             .allow_bind("0.0.0.0:8080".parse().unwrap())
+///             .allow_connect("10.0.0.2:0".parse().unwrap()),
+///     )
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NetworkAllowList {
+    bind: Vec<SocketAddr>,
+    connect: Vec<SocketAddr>,
+}
+
+impl NetworkAllowList {
+    /// Returns a list allowing no address.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allows binding the sockets to the addresses matching `addr`.
+    pub fn allow_bind(mut self, addr: SocketAddr) -> Self {
+        self.bind.push(addr);
+        self
+    }
+
+    /// Allows connecting the sockets to the addresses matching `addr`.
+    pub fn allow_connect(mut self, addr: SocketAddr) -> Self {
+        self.connect.push(addr);
+        self
+    }
+}
+
+fn matches(pattern: &SocketAddr, addr: &SocketAddr) -> bool {
+    let ip = if pattern.ip().is_unspecified() {
+        pattern.is_ipv4() == addr.is_ipv4()
+    } else {
+        pattern.ip() == addr.ip()
+    };
+    ip && (pattern.port() == 0 || pattern.port() == addr.port())
+}
+
+impl NetworkPolicy for NetworkAllowList {
+    fn allow_bind(&self, _protocol: Protocol, addr: SocketAddr) -> bool {
+        self.bind.iter().any(|pattern| matches(pattern, &addr))
+    }
+
+    fn allow_connect(&self, _protocol: Protocol, addr: SocketAddr) -> bool {
+        self.connect.iter().any(|pattern| matches(pattern, &addr))
+    }
+}
+
+pub(crate) fn no_networking() -> Arc<dyn NetworkPolicy> {
+    Arc::new(NoNetworking)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn allow_list() {
+        let policy = NetworkAllowList::new()
+            .allow_bind("0.0.0.0:8080".parse().unwrap())
+            .allow_connect("10.0.0.2:0".parse().unwrap())
+            .allow_connect("[::1]:443".parse().unwrap());
+        let allow_bind = |addr: &str| policy.allow_bind(Protocol::Tcp, addr.parse().unwrap());
+        let allow_connect = |addr: &str| policy.allow_connect(Protocol::Udp, addr.parse().unwrap());
+
+        assert!(allow_bind("127.0.0.1:8080"));
+        assert!(!allow_bind("127.0.0.1:8081"));
+        assert!(!allow_bind("[::1]:8080"));
+        assert!(allow_connect("10.0.0.2:53"));
+        assert!(!allow_connect("10.0.0.3:53"));
+        assert!(allow_connect("[::1]:443"));
+        assert!(!allow_connect("[::1]:80"));
+    }
+}
@@ -0,0 +1,465 @@
+use super::{NetworkPolicy, Protocol};
+use crate::state::{WasiFile, WasiFsError};
+use crate::syscalls::types::*;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::io::{self, Read, Seek, Write};
+use std::net::{Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr, TcpListener, TcpStream, UdpSocket};
+
+fn io_err(err: io::Error) -> __wasi_errno_t {
+    WasiFsError::from(err).into_wasi_err()
+}
+
+fn not_connected() -> io::Error {
+    io::Error::new(io::ErrorKind::NotConnected, "the socket is not connected")
+}
+
+#[derive(Debug)]
+enum State {
+    /// A socket which isn't listening nor connected yet, with the address
+    /// it's bound to, if any.
+    ///
+    /// A TCP socket is only bound to its address when it starts to
+    /// listen, and a UDP socket is bound as soon as it has an address.
+    Unbound(Option<SocketAddr>),
+    Listener(TcpListener),
+    Stream(TcpStream),
+    Datagram(UdpSocket),
+    /// A socket restored from a snapshot.
+    Closed,
+}
+
+/// A socket opened by the WASI program.
+#[derive(Debug)]
+pub(crate) struct WasiSocket {
+    family: __wasi_address_family_t,
+    sock_type: __wasi_sock_type_t,
+    nonblocking: bool,
+    state: State,
+}
+
+impl WasiSocket {
+    pub(crate) fn new(
+        family: __wasi_address_family_t,
+        sock_type: __wasi_sock_type_t,
+    ) -> Result<Self, __wasi_errno_t> {
+        match family {
+            __WASI_ADDRESS_FAMILY_INET4 | __WASI_ADDRESS_FAMILY_INET6 => (),
+            _ => return Err(__WASI_EAFNOSUPPORT),
+        }
+        match sock_type {
+            __WASI_SOCK_TYPE_DGRAM | __WASI_SOCK_TYPE_STREAM => (),
+            _ => return Err(__WASI_EPROTONOSUPPORT),
+        }
+        Ok(Self {
+            family,
+            sock_type,
+            nonblocking: false,
+            state: State::Unbound(None),
+        })
+    }
+
+    fn protocol(&self) -> Protocol {
+        if self.sock_type == __WASI_SOCK_TYPE_STREAM {
+            Protocol::Tcp
+        } else {
+            Protocol::Udp
+        }
+    }
+
+    pub(crate) fn filetype(&self) -> __wasi_filetype_t {
+        if self.sock_type == __WASI_SOCK_TYPE_STREAM {
+            __WASI_FILETYPE_SOCKET_STREAM
+        } else {
+            __WASI_FILETYPE_SOCKET_DGRAM
+        }
+    }
+
+    fn check_family(&self, addr: SocketAddr) -> Result<(), __wasi_errno_t> {
+        let family = if addr.is_ipv4() {
+            __WASI_ADDRESS_FAMILY_INET4
+        } else {
+            __WASI_ADDRESS_FAMILY_INET6
+        };
+        if family == self.family {
+            Ok(())
+        } else {
+            Err(__WASI_EAFNOSUPPORT)
+        }
+    }
+
+    /// Returns the address of the family of the socket where the host
+    /// chooses the IP address and the port.
+    fn unspecified_addr(&self) -> SocketAddr {
+        if self.family == __WASI_ADDRESS_FAMILY_INET4 {
+            (Ipv4Addr::UNSPECIFIED, 0).into()
+        } else {
+            (Ipv6Addr::UNSPECIFIED, 0).into()
+        }
+    }
+
+    fn apply_nonblocking(&self) -> io::Result<()> {
+        match &self.state {
+            State::Listener(listener) => listener.set_nonblocking(self.nonblocking),
+            State::Stream(stream) => stream.set_nonblocking(self.nonblocking),
+            State::Datagram(socket) => socket.set_nonblocking(self.nonblocking),
+            State::Unbound(_) | State::Closed => Ok(()),
+        }
+    }
+
+    pub(crate) fn set_nonblocking(&mut self, nonblocking: bool) -> Result<(), __wasi_errno_t> {
+        self.nonblocking = nonblocking;
+        self.apply_nonblocking().map_err(io_err)
+    }
+
+    fn set_state(&mut self, state: State) -> Result<(), __wasi_errno_t> {
+        self.state = state;
+        self.apply_nonblocking().map_err(io_err)
+    }
+
+    pub(crate) fn bind(
+        &mut self,
+        policy: &dyn NetworkPolicy,
+        addr: SocketAddr,
+    ) -> Result<(), __wasi_errno_t> {
+        self.check_family(addr)?;
+        if !matches!(self.state, State::Unbound(None)) {
+            return Err(__WASI_EINVAL);
+        }
+        if !policy.allow_bind(self.protocol(), addr) {
+            return Err(__WASI_EACCES);
+        }
+        match self.protocol() {
+            Protocol::Tcp => self.set_state(State::Unbound(Some(addr))),
+            Protocol::Udp => {
+                let socket = UdpSocket::bind(addr).map_err(io_err)?;
+                self.set_state(State::Datagram(socket))
+            }
+        }
+    }
+
+    /// Starts to listen to connections on the address the socket is
+    /// bound to.
+    ///
+    /// The length of the queue of pending connections is chosen by the
+    /// host.
+    pub(crate) fn listen(&mut self) -> Result<(), __wasi_errno_t> {
+        if self.protocol() != Protocol::Tcp {
+            return Err(__WASI_ENOTSUP);
+        }
+        match self.state {
+            State::Unbound(Some(addr)) => {
+                let listener = TcpListener::bind(addr).map_err(io_err)?;
+                self.set_state(State::Listener(listener))
+            }
+            State::Listener(_) => Ok(()),
+            State::Unbound(None) => Err(__WASI_EDESTADDRREQ),
+            _ => Err(__WASI_EINVAL),
+        }
+    }
+
+    pub(crate) fn accept(&mut self, nonblocking: bool) -> Result<WasiSocket, __wasi_errno_t> {
+        let (stream, _) = match &self.state {
+            State::Listener(listener) => listener.accept().map_err(io_err)?,
+            _ => return Err(__WASI_EINVAL),
+        };
+        let mut socket = Self {
+            family: self.family,
+            sock_type: self.sock_type,
+            nonblocking,
+            state: State::Closed,
+        };
+        socket.set_state(State::Stream(stream))?;
+        Ok(socket)
+    }
+
+    /// Connects the socket to `addr`.
+    ///
+    /// The connection of a TCP socket is always established before
+    /// returning, even when the socket is non-blocking.
+    pub(crate) fn connect(
+        &mut self,
+        policy: &dyn NetworkPolicy,
+        addr: SocketAddr,
+    ) -> Result<(), __wasi_errno_t> {
+        self.check_family(addr)?;
+        if !policy.allow_connect(self.protocol(), addr) {
+            return Err(__WASI_EACCES);
+        }
+        match (&self.state, self.protocol()) {
+            (State::Unbound(_), Protocol::Tcp) => {
+                let stream = TcpStream::connect(addr).map_err(io_err)?;
+                self.set_state(State::Stream(stream))
+            }
+            (State::Unbound(_), Protocol::Udp) => {
+                let socket = UdpSocket::bind(self.unspecified_addr()).map_err(io_err)?;
+                socket.connect(addr).map_err(io_err)?;
+                self.set_state(State::Datagram(socket))
+            }
+            (State::Datagram(socket), _) => socket.connect(addr).map_err(io_err),
+            (State::Stream(_), _) => Err(__WASI_EISCONN),
+            _ => Err(__WASI_EINVAL),
+        }
+    }
+
+    pub(crate) fn local_addr(&self) -> Result<SocketAddr, __wasi_errno_t> {
+        match &self.state {
+            State::Listener(listener) => listener.local_addr().map_err(io_err),
+            State::Stream(stream) => stream.local_addr().map_err(io_err),
+            State::Datagram(socket) => socket.local_addr().map_err(io_err),
+            State::Unbound(Some(addr)) => Ok(*addr),
+            State::Unbound(None) => Ok(self.unspecified_addr()),
+            State::Closed => Err(__WASI_EBADF),
+        }
+    }
+
+    pub(crate) fn peer_addr(&self) -> Result<SocketAddr, __wasi_errno_t> {
+        match &self.state {
+            State::Stream(stream) => stream.peer_addr().map_err(io_err),
+            State::Datagram(socket) => socket.peer_addr().map_err(io_err),
+            _ => Err(__WASI_ENOTCONN),
+        }
+    }
+
+    /// Receives data in `buf`, returning its length and the address of the
+    /// sender of a datagram.
+    pub(crate) fn recv_from(
+        &mut self,
+        buf: &mut [u8],
+        flags: __wasi_riflags_t,
+    ) -> Result<(usize, Option<SocketAddr>), __wasi_errno_t> {
+        let peek = flags & __WASI_SOCK_RECV_PEEK != 0;
+        match &mut self.state {
+            State::Stream(stream) if peek => Ok((stream.peek(buf).map_err(io_err)?, None)),
+            State::Stream(stream) if flags & __WASI_SOCK_RECV_WAITALL != 0 => {
+                let mut len = 0;
+                while len < buf.len() {
+                    match stream.read(&mut buf[len..]) {
+                        Ok(0) => break,
+                        Ok(read) => len += read,
+                        Err(err) if err.kind() == io::ErrorKind::Interrupted => (),
+                        Err(err) if len == 0 => return Err(io_err(err)),
+                        Err(_) => break,
+                    }
+                }
+                Ok((len, None))
+            }
+            State::Stream(stream) => Ok((stream.read(buf).map_err(io_err)?, None)),
+            State::Datagram(socket) => {
+                let (len, addr) = if peek {
+                    socket.peek_from(buf)
+                } else {
+                    socket.recv_from(buf)
+                }
+                .map_err(io_err)?;
+                Ok((len, Some(addr)))
+            }
+            _ => Err(__WASI_ENOTCONN),
+        }
+    }
+
+    /// Sends `buf` on the connection of the socket, or in a datagram to
+    /// `addr`.
+    pub(crate) fn send_to(
+        &mut self,
+        policy: &dyn NetworkPolicy,
+        buf: &[u8],
+        addr: Option<SocketAddr>,
+    ) -> Result<usize, __wasi_errno_t> {
+        if let Some(addr) = addr {
+            if self.protocol() != Protocol::Udp {
+                return Err(__WASI_EISCONN);
+            }
+            self.check_family(addr)?;
+            if !policy.allow_connect(Protocol::Udp, addr) {
+                return Err(__WASI_EACCES);
+            }
+            if let State::Unbound(_) = self.state {
+                let socket = UdpSocket::bind(self.unspecified_addr()).map_err(io_err)?;
+                self.set_state(State::Datagram(socket))?;
+            }
+        }
+        match (&mut self.state, addr) {
+            (State::Stream(stream), _) => stream.write(buf).map_err(io_err),
+            (State::Datagram(socket), Some(addr)) => socket.send_to(buf, addr).map_err(io_err),
+            (State::Datagram(socket), None) => socket.send(buf).map_err(io_err),
+            _ => Err(__WASI_ENOTCONN),
+        }
+    }
+
+    pub(crate) fn shutdown(&mut self, how: __wasi_sdflags_t) -> Result<(), __wasi_errno_t> {
+        let how = match how {
+            __WASI_SHUT_RD => Shutdown::Read,
+            __WASI_SHUT_WR => Shutdown::Write,
+            how if how == __WASI_SHUT_RD | __WASI_SHUT_WR => Shutdown::Both,
+            _ => return Err(__WASI_EINVAL),
+        };
+        match &self.state {
+            State::Stream(stream) => stream.shutdown(how).map_err(io_err),
+            _ => Err(__WASI_ENOTCONN),
+        }
+    }
+}
+
+/// Only the family and the type of the socket are saved: a restored socket
+/// is closed.
+#[derive(Serialize, Deserialize)]
+struct WasiSocketSnapshot {
+    family: __wasi_address_family_t,
+    sock_type: __wasi_sock_type_t,
+}
+
+impl Serialize for WasiSocket {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        WasiSocketSnapshot {
+            family: self.family,
+            sock_type: self.sock_type,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for WasiSocket {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let snapshot = WasiSocketSnapshot::deserialize(deserializer)?;
+        Ok(Self {
+            family: snapshot.family,
+            sock_type: snapshot.sock_type,
+            nonblocking: false,
+            state: State::Closed,
+        })
+    }
+}
+
+impl Read for WasiSocket {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match &mut self.state {
+            State::Stream(stream) => stream.read(buf),
+            State::Datagram(socket) => socket.recv(buf),
+            _ => Err(not_connected()),
+        }
+    }
+}
+
+impl Write for WasiSocket {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match &mut self.state {
+            State::Stream(stream) => stream.write(buf),
+            State::Datagram(socket) => socket.send(buf),
+            _ => Err(not_connected()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.state {
+            State::Stream(stream) => stream.flush(),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Seek for WasiSocket {
+    fn seek(&mut self, _pos: io::SeekFrom) -> io::Result<u64> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "can not seek in a socket",
+        ))
+    }
+}
+
+#[typetag::serde]
+impl WasiFile for WasiSocket {
+    fn last_accessed(&self) -> __wasi_timestamp_t {
+        0
+    }
+
+    fn last_modified(&self) -> __wasi_timestamp_t {
+        0
+    }
+
+    fn created_time(&self) -> __wasi_timestamp_t {
+        0
+    }
+
+    fn size(&self) -> u64 {
+        0
+    }
+
+    fn set_len(&mut self, _new_size: __wasi_filesize_t) -> Result<(), WasiFsError> {
+        Err(WasiFsError::NotAFile)
+    }
+
+    fn unlink(&mut self) -> Result<(), WasiFsError> {
+        Ok(())
+    }
+
+    fn bytes_available(&self) -> Result<usize, WasiFsError> {
+        Err(WasiFsError::InvalidInput)
+    }
+
+    #[cfg(unix)]
+    fn get_raw_fd(&self) -> Option<i32> {
+        use std::os::unix::io::AsRawFd;
+        match &self.state {
+            State::Listener(listener) => Some(listener.as_raw_fd()),
+            State::Stream(stream) => Some(stream.as_raw_fd()),
+            State::Datagram(socket) => Some(socket.as_raw_fd()),
+            State::Unbound(_) | State::Closed => None,
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn get_raw_fd(&self) -> Option<i32> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::net::FullNetworking;
+
+    #[test]
+    fn tcp_connection() {
+        let policy = FullNetworking;
+        let mut listener =
+            WasiSocket::new(__WASI_ADDRESS_FAMILY_INET4, __WASI_SOCK_TYPE_STREAM).unwrap();
+        listener
+            .bind(&policy, "127.0.0.1:0".parse().unwrap())
+            .unwrap();
+        listener.listen().unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let mut client =
+            WasiSocket::new(__WASI_ADDRESS_FAMILY_INET4, __WASI_SOCK_TYPE_STREAM).unwrap();
+        client.connect(&policy, addr).unwrap();
+        let mut server = listener.accept(false).unwrap();
+        assert_eq!(server.peer_addr(), client.local_addr());
+
+        assert_eq!(client.send_to(&policy, b"ping", None), Ok(4));
+        let mut buf = [0; 4];
+        assert_eq!(
+            server.recv_from(&mut buf, __WASI_SOCK_RECV_WAITALL),
+            Ok((4, None))
+        );
+        assert_eq!(&buf, b"ping");
+    }
+
+    #[test]
+    fn policy() {
+        let policy = crate::net::NoNetworking;
+        let mut socket =
+            WasiSocket::new(__WASI_ADDRESS_FAMILY_INET4, __WASI_SOCK_TYPE_DGRAM).unwrap();
+        assert_eq!(
+            socket.bind(&policy, "127.0.0.1:0".parse().unwrap()),
+            Err(__WASI_EACCES)
+        );
+        assert_eq!(
+            socket.send_to(&policy, b"ping", Some("127.0.0.1:53".parse().unwrap())),
+            Err(__WASI_EACCES)
+        );
+        assert_eq!(
+            socket.bind(&policy, "[::1]:0".parse().unwrap()),
+            Err(__WASI_EAFNOSUPPORT)
+        );
+    }
+}
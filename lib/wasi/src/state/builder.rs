@@ -1,9 +1,18 @@
 //! Builder system for configuring a [`WasiState`] and creating it.
 
-use crate::state::{WasiFile, WasiFs, WasiFsError, WasiState};
+use crate::fs::{FileSystem, HostFileSystem};
+use crate::net::NetworkPolicy;
+use crate::state::{
+    AsyncReadFile, AsyncWriteFile, HostClock, HostRandom, WasiClock, WasiFile, WasiFs, WasiFsError,
+    WasiRandom, WasiState,
+};
+use crate::syscall_policy::SyscallPolicy;
 use crate::syscalls::types::{__WASI_STDERR_FILENO, __WASI_STDIN_FILENO, __WASI_STDOUT_FILENO};
 use crate::WasiEnv;
+use futures_io::{AsyncRead, AsyncWrite};
+use glob::Pattern;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use thiserror::Error;
 
 /// Creates an empty [`WasiStateBuilder`].
@@ -42,6 +51,11 @@ pub struct WasiStateBuilder {
     stdout_override: Option<Box<dyn WasiFile>>,
     stderr_override: Option<Box<dyn WasiFile>>,
     stdin_override: Option<Box<dyn WasiFile>>,
+    umask: Option<u32>,
+    network_policy: Option<Arc<dyn NetworkPolicy>>,
+    clock: Option<Arc<dyn WasiClock>>,
+    random: Option<Arc<dyn WasiRandom>>,
+    syscall_policy: SyscallPolicy,
 }
 
 impl std::fmt::Debug for WasiStateBuilder {
@@ -54,6 +68,11 @@ impl std::fmt::Debug for WasiStateBuilder {
             .field("stdout_override exists", &self.stdout_override.is_some())
             .field("stderr_override exists", &self.stderr_override.is_some())
             .field("stdin_override exists", &self.stdin_override.is_some())
+            .field("umask", &self.umask)
+            .field("network_policy", &self.network_policy)
+            .field("clock", &self.clock)
+            .field("random", &self.random)
+            .field("syscall_policy", &self.syscall_policy)
             .finish()
     }
 }
@@ -108,6 +127,22 @@ impl WasiStateBuilder {
         self
     }
 
+    /// Set the program name, i.e. the first argument (`argv[0]`).
+    ///
+    /// It's the one given to [`WasiState::new`] by default, and can be
+    /// set independently of the host process, e.g. for the multicall
+    /// binaries picking their behavior from it.
+    ///
+    /// The program name must not contain the nul (0x0) byte
+    pub fn program_name<Name>(&mut self, name: Name) -> &mut Self
+    where
+        Name: AsRef<[u8]>,
+    {
+        self.args[0] = name.as_ref().to_vec();
+
+        self
+    }
+
     /// Add an argument.
     ///
     /// Arguments must not contain the nul (0x0) byte
@@ -282,6 +317,87 @@ impl WasiStateBuilder {
         self
     }
 
+    /// Use `reader` as the `stdin` of the WASI program, which blocks while
+    /// it waits for the data.
+    ///
+    /// See [`AsyncReadFile`] for the details.
+    pub fn stdin_async(&mut self, reader: impl AsyncRead + Send + Unpin + 'static) -> &mut Self {
+        self.stdin(Box::new(AsyncReadFile::new(reader)))
+    }
+
+    /// Use `writer` as the `stdout` of the WASI program, which blocks
+    /// while the writer isn't ready.
+    ///
+    /// See [`AsyncWriteFile`] for the details.
+    pub fn stdout_async(&mut self, writer: impl AsyncWrite + Send + Unpin + 'static) -> &mut Self {
+        self.stdout(Box::new(AsyncWriteFile::new(writer)))
+    }
+
+    /// Use `writer` as the `stderr` of the WASI program, which blocks
+    /// while the writer isn't ready.
+    ///
+    /// See [`AsyncWriteFile`] for the details.
+    pub fn stderr_async(&mut self, writer: impl AsyncWrite + Send + Unpin + 'static) -> &mut Self {
+        self.stderr(Box::new(AsyncWriteFile::new(writer)))
+    }
+
+    /// Set the permissions masked out of the files and directories
+    /// created on the host by the WASI program, like the `umask` of a
+    /// process.
+    ///
+    /// The mask applies in addition to the one of the host process, and
+    /// only on Unix.
+    pub fn umask(&mut self, umask: u32) -> &mut Self {
+        self.umask = Some(umask);
+
+        self
+    }
+
+    /// Set the policy deciding which addresses the sockets of the WASI
+    /// program can bind to and connect to.
+    ///
+    /// By default, no address is allowed.
+    pub fn network_policy(&mut self, policy: impl NetworkPolicy) -> &mut Self {
+        self.network_policy = Some(Arc::new(policy));
+
+        self
+    }
+
+    /// Set the clocks read by the WASI program, instead of the ones of
+    /// the host.
+    ///
+    /// ```
+    /// # use wasmer_wasi::{DeterministicClock, SeededRandom, WasiState};
+    /// // the same time and random bytes on every run
+    /// WasiState::new("program")
+    ///     .clock(DeterministicClock::new(1_600_000_000_000_000_000, 1_000))
+    ///     .random(SeededRandom::new(42))
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn clock(&mut self, clock: impl WasiClock) -> &mut Self {
+        self.clock = Some(Arc::new(clock));
+
+        self
+    }
+
+    /// Set the source of the randomness of the WASI program, instead of
+    /// the one of the host.
+    pub fn random(&mut self, random: impl WasiRandom) -> &mut Self {
+        self.random = Some(Arc::new(random));
+
+        self
+    }
+
+    /// Set the syscalls the WASI program can't call.
+    ///
+    /// By default, every syscall is allowed.
+    pub fn syscall_policy(&mut self, policy: SyscallPolicy) -> &mut Self {
+        self.syscall_policy = policy;
+
+        self
+    }
+
     /// Setup the WASI filesystem before running
     // TODO: improve ergonomics on this function
     pub fn setup_fs(
@@ -363,7 +479,11 @@ impl WasiStateBuilder {
 
         // this deprecation warning only applies to external callers
         #[allow(deprecated)]
-        let mut wasi_fs = WasiFs::new_with_preopen(&self.preopens)
+        let host_fs = self
+            .umask
+            .map(HostFileSystem::with_umask)
+            .unwrap_or_default();
+        let mut wasi_fs = WasiFs::new_with_preopen(&self.preopens, host_fs)
             .map_err(WasiStateCreationError::WasiFsCreationError)?;
         // set up the file system, overriding base files and calling the setup function
         if let Some(stdin_override) = self.stdin_override.take() {
@@ -399,6 +519,13 @@ impl WasiStateBuilder {
                     env
                 })
                 .collect(),
+            network_policy: self
+                .network_policy
+                .clone()
+                .unwrap_or_else(crate::net::no_networking),
+            clock: self.clock.clone().unwrap_or_else(|| Arc::new(HostClock)),
+            random: self.random.clone().unwrap_or_else(|| Arc::new(HostRandom)),
+            syscall_policy: self.syscall_policy.clone(),
         })
     }
 
@@ -419,6 +546,11 @@ pub struct PreopenDirBuilder {
     read: bool,
     write: bool,
     create: bool,
+    deny_delete: bool,
+    max_file_size: Option<u64>,
+    allow: Vec<String>,
+    deny: Vec<String>,
+    fs: Option<Arc<dyn FileSystem>>,
 }
 
 /// The built version of `PreopenDirBuilder`
@@ -429,6 +561,11 @@ pub(crate) struct PreopenedDir {
     pub(crate) read: bool,
     pub(crate) write: bool,
     pub(crate) create: bool,
+    pub(crate) deny_delete: bool,
+    pub(crate) max_file_size: Option<u64>,
+    pub(crate) allow: Vec<Pattern>,
+    pub(crate) deny: Vec<Pattern>,
+    pub(crate) fs: Option<Arc<dyn FileSystem>>,
 }
 
 impl PreopenDirBuilder {
@@ -484,25 +621,128 @@ impl PreopenDirBuilder {
         self
     }
 
+    /// Make the directory read-only: its files can be read, but nothing
+    /// in it can be written, created, removed or renamed.
+    pub fn read_only(&mut self) -> &mut Self {
+        self.read = true;
+        self.write = false;
+        self.create = false;
+
+        self
+    }
+
+    /// Deny removing and renaming the files and the directories in the
+    /// directory, even when it can be written.
+    pub fn deny_delete(&mut self, toggle: bool) -> &mut Self {
+        self.deny_delete = toggle;
+
+        self
+    }
+
+    /// Limit the size of the files written in the directory to
+    /// `max_file_size` bytes; the writes past it fail.
+    pub fn max_file_size(&mut self, max_file_size: u64) -> &mut Self {
+        self.max_file_size = Some(max_file_size);
+
+        self
+    }
+
+    /// Only show the files whose path in the directory matches the glob
+    /// `pattern`, like `**/*.txt`, to the WASI program.
+    ///
+    /// This can be called several times to allow several patterns. The
+    /// subdirectories stay visible, so the allowed files in them can be
+    /// reached.
+    pub fn allow(&mut self, pattern: &str) -> &mut Self {
+        self.allow.push(pattern.to_string());
+
+        self
+    }
+
+    /// Hide the files and the directories whose path in the directory
+    /// matches the glob `pattern` from the WASI program, along with the
+    /// contents of the hidden directories.
+    ///
+    /// The denied patterns take precedence over the allowed ones.
+    ///
+    /// ```
+    /// # use wasmer_wasi::WasiState;
+    /// WasiState::new("program")
+    ///     .preopen(|p| {
+    ///         p.directory(".")
+    ///             .alias("project")
+    ///             .read(true)
+    ///             .write(true)
+    ///             .deny_delete(true)
+    ///             .max_file_size(1 << 20)
+    ///             .deny(".git")
+    ///     })
+    ///     .unwrap();
+    /// ```
+    pub fn deny(&mut self, pattern: &str) -> &mut Self {
+        self.deny.push(pattern.to_string());
+
+        self
+    }
+
+    /// Back this preopened directory with `fs` instead of the file system
+    /// of the host; the path given by [`PreopenDirBuilder::directory`] is
+    /// then a path in `fs`, and `/` by default.
+    ///
+    /// ```
+    /// # use wasmer_wasi::{MemFileSystem, WasiState};
+    /// let fs = MemFileSystem::new();
+    /// fs.write_file("/input.txt", "hello").unwrap();
+    ///
+    /// WasiState::new("program")
+    ///     .preopen(|p| p.alias("data").read(true).filesystem(fs.clone()))
+    ///     .unwrap();
+    /// ```
+    pub fn filesystem(&mut self, fs: impl FileSystem) -> &mut Self {
+        self.fs = Some(Arc::new(fs));
+
+        self
+    }
+
     pub(crate) fn build(&self) -> Result<PreopenedDir, WasiStateCreationError> {
         // ensure at least one is set
         if !(self.read || self.write || self.create) {
             return Err(WasiStateCreationError::PreopenedDirectoryError("Preopened directories must have at least one of read, write, create permissions set".to_string()));
         }
 
-        if self.path.is_none() {
-            return Err(WasiStateCreationError::PreopenedDirectoryError(
-                "Preopened directories must point to a host directory".to_string(),
-            ));
-        }
-        let path = self.path.clone().unwrap();
+        let path = match (&self.path, &self.fs) {
+            (Some(path), _) => path.clone(),
+            (None, Some(_)) => PathBuf::from("/"),
+            (None, None) => {
+                return Err(WasiStateCreationError::PreopenedDirectoryError(
+                    "Preopened directories must point to a host directory".to_string(),
+                ))
+            }
+        };
 
-        if !path.exists() {
+        let exists = match &self.fs {
+            Some(fs) => fs.metadata(&path).is_ok(),
+            None => path.exists(),
+        };
+        if !exists {
             return Err(WasiStateCreationError::PreopenedDirectoryNotFound(path));
         }
         if let Some(alias) = &self.alias {
             validate_mapped_dir_alias(alias)?;
         }
+        let patterns = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|pattern| {
+                    Pattern::new(pattern).map_err(|e| {
+                        WasiStateCreationError::PreopenedDirectoryError(format!(
+                            "Invalid path pattern `{}`: {}",
+                            pattern, e
+                        ))
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
 
         Ok(PreopenedDir {
             path,
@@ -510,6 +750,11 @@ impl PreopenDirBuilder {
             read: self.read,
             write: self.write,
             create: self.create,
+            deny_delete: self.deny_delete,
+            max_file_size: self.max_file_size,
+            allow: patterns(&self.allow)?,
+            deny: patterns(&self.deny)?,
+            fs: self.fs.clone(),
         })
     }
 }
@@ -517,6 +762,7 @@ impl PreopenDirBuilder {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::fs::MemFileSystem;
 
     #[test]
     fn env_var_errors() {
@@ -571,5 +817,68 @@ mod test {
             Err(WasiStateCreationError::ArgumentContainsNulByte(_)) => assert!(true),
             _ => assert!(false),
         }
+        let output = create_wasi_state("test_prog")
+            .program_name("busy\0box")
+            .build();
+        match output {
+            Err(WasiStateCreationError::ArgumentContainsNulByte(_)) => assert!(true),
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn program_name() {
+        let state = create_wasi_state("test_prog")
+            .arg("--help")
+            .program_name("ls")
+            .build()
+            .unwrap();
+        assert_eq!(state.args, vec![b"ls".to_vec(), b"--help".to_vec()]);
+    }
+
+    #[test]
+    fn preopen_filesystem() {
+        let fs = MemFileSystem::new();
+        fs.write_file("/data/input.txt", "hello").unwrap();
+
+        let state = create_wasi_state("test_prog")
+            .preopen(|p| p.directory("/data").read(true).filesystem(fs.clone()))
+            .unwrap()
+            .build();
+        assert!(state.is_ok());
+
+        let output = create_wasi_state("test_prog")
+            .preopen(|p| p.directory("/missing").read(true).filesystem(fs.clone()));
+        match output {
+            Err(WasiStateCreationError::PreopenedDirectoryNotFound(_)) => assert!(true),
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn preopen_capabilities() {
+        let fs = MemFileSystem::new();
+        fs.write_file("/notes.txt", "hello").unwrap();
+
+        let state = create_wasi_state("test_prog")
+            .preopen(|p| {
+                p.alias("data")
+                    .read(true)
+                    .write(true)
+                    .deny_delete(true)
+                    .max_file_size(16)
+                    .allow("*.txt")
+                    .filesystem(fs.clone())
+            })
+            .unwrap()
+            .build();
+        assert!(state.is_ok());
+
+        let output = create_wasi_state("test_prog")
+            .preopen(|p| p.alias("data").read_only().deny("[").filesystem(fs.clone()));
+        match output {
+            Err(WasiStateCreationError::PreopenedDirectoryError(_)) => assert!(true),
+            _ => assert!(false),
+        }
     }
 }
@@ -16,10 +16,19 @@
 #![allow(clippy::cognitive_complexity, clippy::too_many_arguments)]
 
 mod builder;
+mod pipe;
+mod providers;
 mod types;
 
 pub use self::builder::*;
+pub use self::pipe::{bounded_pipe, AsyncReadFile, AsyncWriteFile, PipeReader, PipeWriter};
+pub use self::providers::{
+    DeterministicClock, HostClock, HostRandom, SeededRandom, WasiClock, WasiRandom,
+};
 pub use self::types::*;
+use crate::fs::{FileSystem, FsId, HostFileSystem, Metadata, RestrictedFileSystem};
+use crate::net::{NetworkPolicy, WasiSocket};
+use crate::syscall_policy::SyscallPolicy;
 use crate::syscalls::types::*;
 use generational_arena::Arena;
 pub use generational_arena::Index as Inode;
@@ -31,7 +40,7 @@ use std::{
     fs,
     io::Write,
     path::{Path, PathBuf},
-    time::SystemTime,
+    sync::Arc,
 };
 use tracing::debug;
 
@@ -52,6 +61,12 @@ const STDOUT_DEFAULT_RIGHTS: __wasi_rights_t = __WASI_RIGHT_FD_DATASYNC
     | __WASI_RIGHT_FD_FILESTAT_GET
     | __WASI_RIGHT_POLL_FD_READWRITE;
 const STDERR_DEFAULT_RIGHTS: __wasi_rights_t = STDOUT_DEFAULT_RIGHTS;
+const SOCKET_DEFAULT_RIGHTS: __wasi_rights_t = __WASI_RIGHT_FD_READ
+    | __WASI_RIGHT_FD_WRITE
+    | __WASI_RIGHT_FD_FDSTAT_SET_FLAGS
+    | __WASI_RIGHT_FD_FILESTAT_GET
+    | __WASI_RIGHT_POLL_FD_READWRITE
+    | __WASI_RIGHT_SOCK_SHUTDOWN;
 
 /// A completely aribtrary "big enough" number used as the upper limit for
 /// the number of symlinks that can be traversed when resolving a path
@@ -81,6 +96,9 @@ pub enum Kind {
         /// should be looked up by path
         /// TOOD: clarify here?
         fd: Option<u32>,
+        /// The file system where the file is located
+        #[serde(default)]
+        fs: FsId,
     },
     Dir {
         /// Parent directory
@@ -90,6 +108,9 @@ pub enum Kind {
         path: PathBuf,
         /// The entries of a directory are lazily filled.
         entries: HashMap<String, Inode>,
+        /// The file system where the directory is located
+        #[serde(default)]
+        fs: FsId,
     },
     /// The same as Dir but without the irrelevant bits
     /// The root is immutable after creation; generally the Kind::Root
@@ -163,6 +184,14 @@ pub struct WasiFs {
     inode_counter: Cell<u64>,
     /// for fds still open after the file has been deleted
     pub orphan_fds: HashMap<Inode, InodeVal>,
+    /// The file system of the host, which creates the files and the
+    /// directories with the `umask` of [`WasiStateBuilder::umask`].
+    #[serde(default)]
+    pub(crate) host_fs: HostFileSystem,
+    /// The file systems of the preopened directories which aren't on the
+    /// host; they are lost when the state is serialized.
+    #[serde(skip)]
+    filesystems: Vec<Arc<dyn FileSystem>>,
 }
 
 impl WasiFs {
@@ -195,6 +224,7 @@ impl WasiFs {
                     parent: Some(root_inode),
                     path: dir.clone(),
                     entries: Default::default(),
+                    fs: FsId::HOST,
                 }
             } else {
                 return Err(format!(
@@ -248,6 +278,7 @@ impl WasiFs {
                     parent: Some(root_inode),
                     path: real_dir.clone(),
                     entries: Default::default(),
+                    fs: FsId::HOST,
                 }
             } else {
                 return Err(format!(
@@ -290,8 +321,12 @@ impl WasiFs {
     }
 
     /// Created for the builder API. like `new` but with more information
-    pub(crate) fn new_with_preopen(preopens: &[PreopenedDir]) -> Result<Self, String> {
+    pub(crate) fn new_with_preopen(
+        preopens: &[PreopenedDir],
+        host_fs: HostFileSystem,
+    ) -> Result<Self, String> {
         let (mut wasi_fs, root_inode) = Self::new_init()?;
+        wasi_fs.host_fs = host_fs;
 
         for PreopenedDir {
             path,
@@ -299,6 +334,11 @@ impl WasiFs {
             read,
             write,
             create,
+            deny_delete,
+            max_file_size,
+            allow,
+            deny,
+            fs,
         } in preopens
         {
             debug!(
@@ -306,7 +346,30 @@ impl WasiFs {
                 &path.to_string_lossy(),
                 &alias
             );
-            let cur_dir_metadata = path.metadata().map_err(|e| {
+            // the path filters and the quotas are enforced by a file system
+            // wrapping the one of the directory
+            let fs = if max_file_size.is_some() || !allow.is_empty() || !deny.is_empty() {
+                let inner = fs
+                    .clone()
+                    .unwrap_or_else(|| Arc::new(wasi_fs.host_fs) as Arc<dyn FileSystem>);
+                Some(Arc::new(RestrictedFileSystem::new(
+                    inner,
+                    path.clone(),
+                    allow.clone(),
+                    deny.clone(),
+                    *max_file_size,
+                )) as Arc<dyn FileSystem>)
+            } else {
+                fs.clone()
+            };
+            let (fs, cur_dir_metadata) = match &fs {
+                Some(fs) => {
+                    wasi_fs.filesystems.push(fs.clone());
+                    (FsId(wasi_fs.filesystems.len()), fs.metadata(path))
+                }
+                None => (FsId::HOST, wasi_fs.host_fs.metadata(path)),
+            };
+            let cur_dir_metadata = cur_dir_metadata.map_err(|e| {
                 format!(
                     "Could not get metadata for file {:?}: {}",
                     path,
@@ -319,6 +382,7 @@ impl WasiFs {
                     parent: Some(root_inode),
                     path: path.clone(),
                     entries: Default::default(),
+                    fs,
                 }
             } else {
                 return Err(format!(
@@ -339,7 +403,6 @@ impl WasiFs {
                         | __WASI_RIGHT_PATH_FILESTAT_GET
                         | __WASI_RIGHT_FD_FILESTAT_GET
                         | __WASI_RIGHT_PATH_LINK_SOURCE
-                        | __WASI_RIGHT_PATH_RENAME_SOURCE
                         | __WASI_RIGHT_POLL_FD_READWRITE
                         | __WASI_RIGHT_SOCK_SHUTDOWN;
                 }
@@ -349,7 +412,7 @@ impl WasiFs {
                         | __WASI_RIGHT_FD_SYNC
                         | __WASI_RIGHT_FD_ALLOCATE
                         | __WASI_RIGHT_PATH_OPEN
-                        | __WASI_RIGHT_PATH_RENAME_TARGET
+                        | __WASI_RIGHT_PATH_RENAME_SOURCE
                         | __WASI_RIGHT_PATH_FILESTAT_SET_SIZE
                         | __WASI_RIGHT_PATH_FILESTAT_SET_TIMES
                         | __WASI_RIGHT_FD_FILESTAT_SET_SIZE
@@ -366,6 +429,11 @@ impl WasiFs {
                         | __WASI_RIGHT_PATH_OPEN
                         | __WASI_RIGHT_PATH_RENAME_TARGET;
                 }
+                if *deny_delete {
+                    rights &= !(__WASI_RIGHT_PATH_REMOVE_DIRECTORY
+                        | __WASI_RIGHT_PATH_UNLINK_FILE
+                        | __WASI_RIGHT_PATH_RENAME_SOURCE);
+                }
 
                 rights
             };
@@ -415,6 +483,19 @@ impl WasiFs {
         Ok(wasi_fs)
     }
 
+    /// Returns the file system with the given id, or `__WASI_EIO` if it
+    /// was lost when the state was serialized.
+    pub(crate) fn filesystem(&self, fs: FsId) -> Result<&dyn FileSystem, __wasi_errno_t> {
+        match fs.0 {
+            0 => Ok(&self.host_fs),
+            n => self
+                .filesystems
+                .get(n - 1)
+                .map(|fs| &**fs)
+                .ok_or(__WASI_EIO),
+        }
+    }
+
     /// Private helper function to init the filesystem, called in `new` and
     /// `new_with_preopen`
     fn new_init() -> Result<(Self, Inode), String> {
@@ -428,6 +509,8 @@ impl WasiFs {
             next_fd: Cell::new(3),
             inode_counter: Cell::new(1024),
             orphan_fds: HashMap::new(),
+            host_fs: HostFileSystem::default(),
+            filesystems: Vec::new(),
         };
         wasi_fs.create_stdin();
         wasi_fs.create_stdout();
@@ -570,6 +653,7 @@ impl WasiFs {
                         parent: Some(cur_inode),
                         path: PathBuf::from(""),
                         entries: HashMap::new(),
+                        fs: FsId::HOST,
                     };
 
                     let inode =
@@ -632,6 +716,7 @@ impl WasiFs {
                     handle: Some(file),
                     path: PathBuf::from(""),
                     fd: Some(self.next_fd.get()),
+                    fs: FsId::HOST,
                 };
 
                 let inode = self
@@ -754,8 +839,9 @@ impl WasiFs {
                         ref mut entries,
                         ref path,
                         ref parent,
-                        ..
+                        fs: dir_fs,
                     } => {
+                        let dir_fs = *dir_fs;
                         match component.as_os_str().to_string_lossy().borrow() {
                             ".." => {
                                 if let Some(p) = parent {
@@ -780,34 +866,43 @@ impl WasiFs {
                                 cd.push(component);
                                 cd
                             };
-                            let metadata = file.symlink_metadata().ok().ok_or(__WASI_EINVAL)?;
-                            let file_type = metadata.file_type();
+                            let metadata = self
+                                .filesystem(dir_fs)?
+                                .symlink_metadata(&file)
+                                .ok()
+                                .ok_or(__WASI_EINVAL)?;
                             // we want to insert newly opened dirs and files, but not transient symlinks
                             // TODO: explain why (think about this deeply when well rested)
                             let mut should_insert = false;
 
-                            let kind = if file_type.is_dir() {
+                            let kind = if metadata.is_dir() {
                                 should_insert = true;
                                 // load DIR
                                 Kind::Dir {
                                     parent: Some(cur_inode),
                                     path: file.clone(),
                                     entries: Default::default(),
+                                    fs: dir_fs,
                                 }
-                            } else if file_type.is_file() {
+                            } else if metadata.is_file() {
                                 should_insert = true;
                                 // load file
                                 Kind::File {
                                     handle: None,
                                     path: file.clone(),
                                     fd: None,
+                                    fs: dir_fs,
                                 }
-                            } else if file_type.is_symlink() {
-                                let link_value = file.read_link().ok().ok_or(__WASI_EIO)?;
+                            } else if metadata.is_symlink() {
+                                let link_value = self
+                                    .filesystem(dir_fs)?
+                                    .read_link(&file)
+                                    .ok()
+                                    .ok_or(__WASI_EIO)?;
                                 debug!("attempting to decompose path {:?}", link_value);
 
                                 let (pre_open_dir_fd, relative_path) = if link_value.is_relative() {
-                                    self.path_into_pre_open_and_relative_path(&file)?
+                                    self.path_into_pre_open_and_relative_path(&file, dir_fs)?
                                 } else {
                                     unimplemented!("Absolute symlinks are not yet supported");
                                 };
@@ -819,57 +914,38 @@ impl WasiFs {
                                     relative_path: link_value,
                                 }
                             } else {
-                                #[cfg(unix)]
+                                // a special file, such as a char device, a block device,
+                                // a fifo or a socket
+                                let kind = Kind::File {
+                                    handle: None,
+                                    path: file.clone(),
+                                    fd: None,
+                                    fs: dir_fs,
+                                };
+                                let new_inode = self.create_inode_with_stat(
+                                    kind,
+                                    false,
+                                    file.to_string_lossy().to_string(),
+                                    __wasi_filestat_t {
+                                        st_filetype: metadata.file_type,
+                                        ..__wasi_filestat_t::default()
+                                    },
+                                );
+                                if let Kind::Dir {
+                                    ref mut entries, ..
+                                } = &mut self.inodes[cur_inode].kind
                                 {
-                                    use std::os::unix::fs::FileTypeExt;
-                                    let file_type: __wasi_filetype_t = if file_type.is_char_device()
-                                    {
-                                        __WASI_FILETYPE_CHARACTER_DEVICE
-                                    } else if file_type.is_block_device() {
-                                        __WASI_FILETYPE_BLOCK_DEVICE
-                                    } else if file_type.is_fifo() {
-                                        // FIFO doesn't seem to fit any other type, so unknown
-                                        __WASI_FILETYPE_UNKNOWN
-                                    } else if file_type.is_socket() {
-                                        // TODO: how do we know if it's a `__WASI_FILETYPE_SOCKET_STREAM` or
-                                        // a `__WASI_FILETYPE_SOCKET_DGRAM`?
-                                        __WASI_FILETYPE_SOCKET_STREAM
-                                    } else {
-                                        unimplemented!("state::get_inode_at_path unknown file type: not file, directory, symlink, char device, block device, fifo, or socket");
-                                    };
-
-                                    let kind = Kind::File {
-                                        handle: None,
-                                        path: file.clone(),
-                                        fd: None,
-                                    };
-                                    let new_inode = self.create_inode_with_stat(
-                                        kind,
-                                        false,
-                                        file.to_string_lossy().to_string(),
-                                        __wasi_filestat_t {
-                                            st_filetype: file_type,
-                                            ..__wasi_filestat_t::default()
-                                        },
+                                    entries.insert(
+                                        component.as_os_str().to_string_lossy().to_string(),
+                                        new_inode,
+                                    );
+                                } else {
+                                    unreachable!(
+                                        "Attempted to insert special device into non-directory"
                                     );
-                                    if let Kind::Dir {
-                                        ref mut entries, ..
-                                    } = &mut self.inodes[cur_inode].kind
-                                    {
-                                        entries.insert(
-                                            component.as_os_str().to_string_lossy().to_string(),
-                                            new_inode,
-                                        );
-                                    } else {
-                                        unreachable!(
-                                            "Attempted to insert special device into non-directory"
-                                        );
-                                    }
-                                    // perhaps just continue with symlink resolution and return at the end
-                                    return Ok(new_inode);
                                 }
-                                #[cfg(not(unix))]
-                                unimplemented!("state::get_inode_at_path unknown file type: not file, directory, or symlink");
+                                // perhaps just continue with symlink resolution and return at the end
+                                return Ok(new_inode);
                             };
 
                             let new_inode =
@@ -968,13 +1044,18 @@ impl WasiFs {
     fn path_into_pre_open_and_relative_path(
         &self,
         path: &Path,
+        fs: FsId,
     ) -> Result<(__wasi_fd_t, PathBuf), __wasi_errno_t> {
-        // for each preopened directory
+        // for each preopened directory of the file system of the path
         for po_fd in &self.preopen_fds {
             let po_inode = self.fd_map[po_fd].inode;
             let po_path = match &self.inodes[po_inode].kind {
-                Kind::Dir { path, .. } => &**path,
-                Kind::Root { .. } => Path::new("/"),
+                Kind::Dir {
+                    path, fs: po_fs, ..
+                } if *po_fs == fs => &**path,
+                Kind::Dir { .. } => continue,
+                Kind::Root { .. } if fs == FsId::HOST => Path::new("/"),
+                Kind::Root { .. } => continue,
                 _ => unreachable!("Preopened FD that's not a directory or the root"),
             };
             // stem path based on it
@@ -1148,7 +1229,12 @@ impl WasiFs {
 
         Ok(__wasi_fdstat_t {
             fs_filetype: match self.inodes[fd.inode].kind {
-                Kind::File { .. } => __WASI_FILETYPE_REGULAR_FILE,
+                Kind::File { .. } => match self.inodes[fd.inode].stat.st_filetype {
+                    __WASI_FILETYPE_SOCKET_DGRAM | __WASI_FILETYPE_SOCKET_STREAM => {
+                        self.inodes[fd.inode].stat.st_filetype
+                    }
+                    _ => __WASI_FILETYPE_REGULAR_FILE,
+                },
                 Kind::Dir { .. } => __WASI_FILETYPE_DIRECTORY,
                 Kind::Symlink { .. } => __WASI_FILETYPE_SYMBOLIC_LINK,
                 _ => __WASI_FILETYPE_UNKNOWN,
@@ -1285,6 +1371,26 @@ impl WasiFs {
         Ok(idx)
     }
 
+    /// Creates an inode for the socket, and a file descriptor for it.
+    pub(crate) fn create_socket_fd(
+        &mut self,
+        socket: WasiSocket,
+        flags: __wasi_fdflags_t,
+    ) -> Result<__wasi_fd_t, __wasi_errno_t> {
+        let stat = __wasi_filestat_t {
+            st_filetype: socket.filetype(),
+            ..__wasi_filestat_t::default()
+        };
+        let kind = Kind::File {
+            handle: Some(Box::new(socket)),
+            path: PathBuf::new(),
+            fd: None,
+            fs: FsId::HOST,
+        };
+        let inode = self.create_inode_with_stat(kind, false, "socket".to_string(), stat);
+        self.create_fd(SOCKET_DEFAULT_RIGHTS, 0, flags, 0, inode)
+    }
+
     /// Low level function to remove an inode, that is it deletes the WASI FS's
     /// knowledge of a file.
     ///
@@ -1360,6 +1466,7 @@ impl WasiFs {
             fd: Some(raw_fd),
             handle: Some(handle),
             path: "".into(),
+            fs: FsId::HOST,
         };
         let inode = self.inodes.insert(InodeVal {
             stat,
@@ -1382,8 +1489,10 @@ impl WasiFs {
     }
 
     pub fn get_stat_for_kind(&self, kind: &Kind) -> Option<__wasi_filestat_t> {
-        let md = match kind {
-            Kind::File { handle, path, .. } => match handle {
+        let md: Metadata = match kind {
+            Kind::File {
+                handle, path, fs, ..
+            } => match handle {
                 Some(wf) => {
                     return Some(__wasi_filestat_t {
                         st_filetype: __WASI_FILETYPE_REGULAR_FILE,
@@ -1395,9 +1504,9 @@ impl WasiFs {
                         ..__wasi_filestat_t::default()
                     })
                 }
-                None => path.metadata().ok()?,
+                None => self.filesystem(*fs).ok()?.metadata(path).ok()?,
             },
-            Kind::Dir { path, .. } => path.metadata().ok()?,
+            Kind::Dir { path, fs, .. } => self.filesystem(*fs).ok()?.metadata(path).ok()?,
             Kind::Symlink {
                 base_po_dir,
                 path_to_symlink,
@@ -1407,9 +1516,9 @@ impl WasiFs {
                 let base_po_inode_v = &self.inodes[*base_po_inode];
                 match &base_po_inode_v.kind {
                     Kind::Root { .. } => {
-                        path_to_symlink.clone().symlink_metadata().ok()?
+                        self.host_fs.symlink_metadata(path_to_symlink).ok()?
                     }
-                    Kind::Dir { path, .. } => {
+                    Kind::Dir { path, fs, .. } => {
                         let mut real_path = path.clone();
                         // PHASE 1: ignore all possible symlinks in `relative_path`
                         // TODO: walk the segments of `relative_path` via the entries of the Dir
@@ -1418,7 +1527,7 @@ impl WasiFs {
                         // TODO: adjust size of symlink, too
                         //      for all paths adjusted think about this
                         real_path.push(path_to_symlink);
-                        real_path.symlink_metadata().ok()?
+                        self.filesystem(*fs).ok()?.symlink_metadata(&real_path).ok()?
                     }
                     // if this triggers, there's a bug in the symlink code
                     _ => unreachable!("Symlink pointing to something that's not a directory as its base preopened directory"),
@@ -1427,26 +1536,11 @@ impl WasiFs {
             _ => return None,
         };
         Some(__wasi_filestat_t {
-            st_filetype: host_file_type_to_wasi_file_type(md.file_type()),
-            st_size: md.len(),
-            st_atim: md
-                .accessed()
-                .ok()?
-                .duration_since(SystemTime::UNIX_EPOCH)
-                .ok()?
-                .as_nanos() as u64,
-            st_mtim: md
-                .modified()
-                .ok()?
-                .duration_since(SystemTime::UNIX_EPOCH)
-                .ok()?
-                .as_nanos() as u64,
-            st_ctim: md
-                .created()
-                .ok()
-                .and_then(|ct| ct.duration_since(SystemTime::UNIX_EPOCH).ok())
-                .map(|ct| ct.as_nanos() as u64)
-                .unwrap_or(0),
+            st_filetype: md.file_type,
+            st_size: md.len,
+            st_atim: md.accessed,
+            st_mtim: md.modified,
+            st_ctim: md.created,
             ..__wasi_filestat_t::default()
         })
     }
@@ -1540,6 +1634,30 @@ pub struct WasiState {
     pub fs: WasiFs,
     pub args: Vec<Vec<u8>>,
     pub envs: Vec<Vec<u8>>,
+    /// The policy deciding which addresses the sockets can use.
+    ///
+    /// It isn't saved by [`WasiState::freeze`]: a restored state allows
+    /// no address.
+    #[serde(skip, default = "crate::net::no_networking")]
+    pub network_policy: Arc<dyn NetworkPolicy>,
+    /// The clocks read by the program.
+    ///
+    /// It isn't saved by [`WasiState::freeze`]: a restored state reads
+    /// the clocks of the host.
+    #[serde(skip, default = "self::providers::host_clock")]
+    pub clock: Arc<dyn WasiClock>,
+    /// The source of the randomness of the program.
+    ///
+    /// It isn't saved by [`WasiState::freeze`]: a restored state uses
+    /// the randomness of the host.
+    #[serde(skip, default = "self::providers::host_random")]
+    pub random: Arc<dyn WasiRandom>,
+    /// The syscalls the program can't call.
+    ///
+    /// It isn't saved by [`WasiState::freeze`]: a restored state allows
+    /// every syscall.
+    #[serde(skip)]
+    pub syscall_policy: SyscallPolicy,
 }
 
 impl WasiState {
@@ -0,0 +1,672 @@
+//! Pipes streaming the stdio of a WASI program while it runs.
+//!
+//! A [`bounded_pipe`] connects the host and the WASI program with a buffer
+//! of a fixed capacity: the end writing to a full pipe waits for the other
+//! end to read, so a fast producer can't exhaust the memory of the host.
+//! The host can read the output of the program as it's written, from
+//! another thread, without blocking with [`PipeReader::try_read`], or
+//! from an async task as [`PipeReader`] implements [`AsyncRead`].
+//!
+//! Any [`AsyncRead`] or [`AsyncWrite`] can be the stdio of a WASI program
+//! too, see [`WasiStateBuilder::stdin_async`].
+//!
+//! [`WasiStateBuilder::stdin_async`]: crate::WasiStateBuilder::stdin_async
+
+use crate::state::{WasiFile, WasiFsError};
+use futures_io::{AsyncRead, AsyncWrite};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, Read, Seek, Write};
+use std::mem::ManuallyDrop;
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+use std::thread::{self, Thread};
+
+#[derive(Debug, Default)]
+struct Buffer {
+    data: VecDeque<u8>,
+    reader_closed: bool,
+    writer_closed: bool,
+    /// The task waiting for data to read.
+    reader_waker: Option<Waker>,
+    /// The task waiting for room to write.
+    writer_waker: Option<Waker>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    buffer: Mutex<Buffer>,
+    /// Notifies the blocking reads and writes that the buffer changed.
+    changed: Condvar,
+    capacity: usize,
+}
+
+impl Shared {
+    fn new(capacity: usize, buffer: Buffer) -> Arc<Self> {
+        assert!(capacity > 0, "the capacity of a pipe must not be 0");
+        Arc::new(Self {
+            buffer: Mutex::new(buffer),
+            changed: Condvar::new(),
+            capacity,
+        })
+    }
+
+    fn lock(&self) -> MutexGuard<Buffer> {
+        self.buffer.lock().unwrap()
+    }
+
+    fn wake_reader(&self, buffer: &mut Buffer) {
+        if let Some(waker) = buffer.reader_waker.take() {
+            waker.wake();
+        }
+        self.changed.notify_all();
+    }
+
+    fn wake_writer(&self, buffer: &mut Buffer) {
+        if let Some(waker) = buffer.writer_waker.take() {
+            waker.wake();
+        }
+        self.changed.notify_all();
+    }
+
+    /// Moves the buffered data to `buf`, or returns `None` if the read
+    /// must wait for data.
+    fn read(&self, buffer: &mut Buffer, buf: &mut [u8]) -> Option<io::Result<usize>> {
+        if buffer.data.is_empty() && !buf.is_empty() {
+            return if buffer.writer_closed {
+                Some(Ok(0))
+            } else {
+                None
+            };
+        }
+        let len = buf.len().min(buffer.data.len());
+        for (dst, src) in buf.iter_mut().zip(buffer.data.drain(..len)) {
+            *dst = src;
+        }
+        self.wake_writer(buffer);
+        Some(Ok(len))
+    }
+
+    /// Moves as much of `buf` as possible to the buffer, or returns `None`
+    /// if the write must wait for room.
+    fn write(&self, buffer: &mut Buffer, buf: &[u8]) -> Option<io::Result<usize>> {
+        if buffer.reader_closed {
+            return Some(Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "the reader of the pipe is closed",
+            )));
+        }
+        let room = self.capacity - buffer.data.len();
+        if room == 0 && !buf.is_empty() {
+            return None;
+        }
+        let len = buf.len().min(room);
+        buffer.data.extend(&buf[..len]);
+        self.wake_reader(buffer);
+        Some(Ok(len))
+    }
+}
+
+/// Returns the two ends of a pipe buffering up to `capacity` bytes.
+///
+/// ```
+/// # use wasmer_wasi::{bounded_pipe, WasiState};
+/// # use std::io::ErrorKind;
+/// let (stdout, mut output) = bounded_pipe(64 * 1024);
+/// let state = WasiState::new("program").stdout(Box::new(stdout)).build();
+/// // while the program runs on another thread
+/// let mut buf = [0; 1024];
+/// match output.try_read(&mut buf) {
+///     Ok(0) => println!("the program closed its stdout"),
+///     Ok(len) => println!("{}", String::from_utf8_lossy(&buf[..len])),
+///     Err(e) if e.kind() == ErrorKind::WouldBlock => println!("nothing yet"),
+///     Err(e) => panic!("{}", e),
+/// }
+/// ```
+///
+/// # Panics
+///
+/// Panics if `capacity` is 0.
+pub fn bounded_pipe(capacity: usize) -> (PipeWriter, PipeReader) {
+    let shared = Shared::new(capacity, Buffer::default());
+    (
+        PipeWriter {
+            shared: shared.clone(),
+        },
+        PipeReader { shared },
+    )
+}
+
+/// Only the buffered data is saved: the other end of a restored pipe is
+/// closed.
+#[derive(Serialize, Deserialize)]
+struct PipeSnapshot {
+    capacity: usize,
+    data: VecDeque<u8>,
+}
+
+impl PipeSnapshot {
+    fn new(shared: &Shared) -> Self {
+        Self {
+            capacity: shared.capacity,
+            data: shared.lock().data.clone(),
+        }
+    }
+}
+
+/// The end of a [`bounded_pipe`] where the data is written.
+///
+/// The writes block while the pipe is full, and fail once the
+/// [`PipeReader`] is dropped.
+pub struct PipeWriter {
+    shared: Arc<Shared>,
+}
+
+impl fmt::Debug for PipeWriter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PipeWriter")
+            .field("capacity", &self.shared.capacity)
+            .finish()
+    }
+}
+
+impl Drop for PipeWriter {
+    fn drop(&mut self) {
+        let mut buffer = self.shared.lock();
+        buffer.writer_closed = true;
+        self.shared.wake_reader(&mut buffer);
+    }
+}
+
+impl Write for PipeWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut buffer = self.shared.lock();
+        loop {
+            if let Some(result) = self.shared.write(&mut buffer, buf) {
+                return result;
+            }
+            buffer = self.shared.changed.wait(buffer).unwrap();
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl AsyncWrite for PipeWriter {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let mut buffer = self.shared.lock();
+        match self.shared.write(&mut buffer, buf) {
+            Some(result) => Poll::Ready(result),
+            None => {
+                buffer.writer_waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<io::Result<()>> {
+        let mut buffer = self.shared.lock();
+        buffer.writer_closed = true;
+        self.shared.wake_reader(&mut buffer);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Read for PipeWriter {
+    fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "can not read from the writer of a pipe",
+        ))
+    }
+}
+
+impl Seek for PipeWriter {
+    fn seek(&mut self, _pos: io::SeekFrom) -> io::Result<u64> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "can not seek in a pipe",
+        ))
+    }
+}
+
+impl Serialize for PipeWriter {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        PipeSnapshot::new(&self.shared).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for PipeWriter {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let snapshot = PipeSnapshot::deserialize(deserializer)?;
+        let buffer = Buffer {
+            data: snapshot.data,
+            reader_closed: true,
+            ..Buffer::default()
+        };
+        Ok(Self {
+            shared: Shared::new(snapshot.capacity, buffer),
+        })
+    }
+}
+
+#[typetag::serde]
+impl WasiFile for PipeWriter {
+    fn last_accessed(&self) -> u64 {
+        0
+    }
+    fn last_modified(&self) -> u64 {
+        0
+    }
+    fn created_time(&self) -> u64 {
+        0
+    }
+    fn size(&self) -> u64 {
+        self.shared.lock().data.len() as u64
+    }
+    fn set_len(&mut self, _len: u64) -> Result<(), WasiFsError> {
+        Err(WasiFsError::NotAFile)
+    }
+    fn unlink(&mut self) -> Result<(), WasiFsError> {
+        Ok(())
+    }
+    fn bytes_available(&self) -> Result<usize, WasiFsError> {
+        Ok(self.shared.capacity - self.shared.lock().data.len())
+    }
+}
+
+/// The end of a [`bounded_pipe`] where the data is read.
+///
+/// The reads block while the pipe is empty, and return 0 bytes once the
+/// [`PipeWriter`] is dropped and the pipe is empty.
+pub struct PipeReader {
+    shared: Arc<Shared>,
+}
+
+impl PipeReader {
+    /// Reads the data buffered in the pipe without blocking.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::WouldBlock`] if the pipe
+    /// is empty but the [`PipeWriter`] isn't dropped yet.
+    pub fn try_read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut buffer = self.shared.lock();
+        self.shared
+            .read(&mut buffer, buf)
+            .unwrap_or_else(|| Err(io::ErrorKind::WouldBlock.into()))
+    }
+}
+
+impl fmt::Debug for PipeReader {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PipeReader")
+            .field("capacity", &self.shared.capacity)
+            .finish()
+    }
+}
+
+impl Drop for PipeReader {
+    fn drop(&mut self) {
+        let mut buffer = self.shared.lock();
+        buffer.reader_closed = true;
+        self.shared.wake_writer(&mut buffer);
+    }
+}
+
+impl Read for PipeReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut buffer = self.shared.lock();
+        loop {
+            if let Some(result) = self.shared.read(&mut buffer, buf) {
+                return result;
+            }
+            buffer = self.shared.changed.wait(buffer).unwrap();
+        }
+    }
+}
+
+impl AsyncRead for PipeReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut buffer = self.shared.lock();
+        match self.shared.read(&mut buffer, buf) {
+            Some(result) => Poll::Ready(result),
+            None => {
+                buffer.reader_waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl Seek for PipeReader {
+    fn seek(&mut self, _pos: io::SeekFrom) -> io::Result<u64> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "can not seek in a pipe",
+        ))
+    }
+}
+
+impl Write for PipeReader {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "can not write to the reader of a pipe",
+        ))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Serialize for PipeReader {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        PipeSnapshot::new(&self.shared).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for PipeReader {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let snapshot = PipeSnapshot::deserialize(deserializer)?;
+        let buffer = Buffer {
+            data: snapshot.data,
+            writer_closed: true,
+            ..Buffer::default()
+        };
+        Ok(Self {
+            shared: Shared::new(snapshot.capacity, buffer),
+        })
+    }
+}
+
+#[typetag::serde]
+impl WasiFile for PipeReader {
+    fn last_accessed(&self) -> u64 {
+        0
+    }
+    fn last_modified(&self) -> u64 {
+        0
+    }
+    fn created_time(&self) -> u64 {
+        0
+    }
+    fn size(&self) -> u64 {
+        self.shared.lock().data.len() as u64
+    }
+    fn set_len(&mut self, _len: u64) -> Result<(), WasiFsError> {
+        Err(WasiFsError::NotAFile)
+    }
+    fn unlink(&mut self) -> Result<(), WasiFsError> {
+        Ok(())
+    }
+    fn bytes_available(&self) -> Result<usize, WasiFsError> {
+        Ok(self.shared.lock().data.len())
+    }
+}
+
+/// Returns a waker unparking `thread`.
+fn thread_waker(thread: Thread) -> Waker {
+    const VTABLE: RawWakerVTable = RawWakerVTable::new(clone, wake, wake_by_ref, drop);
+
+    unsafe fn clone(data: *const ()) -> RawWaker {
+        let thread = ManuallyDrop::new(Arc::from_raw(data as *const Thread));
+        RawWaker::new(Arc::into_raw(Arc::clone(&thread)) as *const (), &VTABLE)
+    }
+    unsafe fn wake(data: *const ()) {
+        Arc::from_raw(data as *const Thread).unpark();
+    }
+    unsafe fn wake_by_ref(data: *const ()) {
+        (&*(data as *const Thread)).unpark();
+    }
+    unsafe fn drop(data: *const ()) {
+        std::mem::drop(Arc::from_raw(data as *const Thread));
+    }
+
+    let data = Arc::into_raw(Arc::new(thread)) as *const ();
+    unsafe { Waker::from_raw(RawWaker::new(data, &VTABLE)) }
+}
+
+/// Polls until `poll` is ready, parking the current thread in between.
+fn block_on<T>(mut poll: impl FnMut(&mut Context) -> Poll<T>) -> T {
+    let waker = thread_waker(thread::current());
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        if let Poll::Ready(value) = poll(&mut cx) {
+            return value;
+        }
+        thread::park();
+    }
+}
+
+/// A file reading an [`AsyncRead`], to use it as the stdin of a WASI
+/// program.
+///
+/// The WASI program blocks while it waits for the data, so the
+/// [`AsyncRead`] must be driven by another thread, like the ones of an
+/// async runtime. A restored file is empty.
+#[derive(Serialize, Deserialize)]
+pub struct AsyncReadFile {
+    #[serde(skip)]
+    reader: Option<Box<dyn AsyncRead + Send + Unpin>>,
+}
+
+impl AsyncReadFile {
+    /// Returns a file reading `reader`.
+    pub fn new(reader: impl AsyncRead + Send + Unpin + 'static) -> Self {
+        Self {
+            reader: Some(Box::new(reader)),
+        }
+    }
+}
+
+impl fmt::Debug for AsyncReadFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "AsyncReadFile")
+    }
+}
+
+impl Read for AsyncReadFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match &mut self.reader {
+            Some(reader) => block_on(|cx| Pin::new(&mut *reader).poll_read(cx, buf)),
+            None => Ok(0),
+        }
+    }
+}
+
+impl Write for AsyncReadFile {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "can not write to an async reader",
+        ))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for AsyncReadFile {
+    fn seek(&mut self, _pos: io::SeekFrom) -> io::Result<u64> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "can not seek in an async reader",
+        ))
+    }
+}
+
+#[typetag::serde]
+impl WasiFile for AsyncReadFile {
+    fn last_accessed(&self) -> u64 {
+        0
+    }
+    fn last_modified(&self) -> u64 {
+        0
+    }
+    fn created_time(&self) -> u64 {
+        0
+    }
+    fn size(&self) -> u64 {
+        0
+    }
+    fn set_len(&mut self, _len: u64) -> Result<(), WasiFsError> {
+        Err(WasiFsError::NotAFile)
+    }
+    fn unlink(&mut self) -> Result<(), WasiFsError> {
+        Ok(())
+    }
+}
+
+/// A file writing to an [`AsyncWrite`], to use it as the stdout or the
+/// stderr of a WASI program.
+///
+/// The WASI program blocks while the [`AsyncWrite`] isn't ready, so it
+/// must be driven by another thread, like the ones of an async runtime.
+/// The writes to a restored file fail.
+#[derive(Serialize, Deserialize)]
+pub struct AsyncWriteFile {
+    #[serde(skip)]
+    writer: Option<Box<dyn AsyncWrite + Send + Unpin>>,
+}
+
+impl AsyncWriteFile {
+    /// Returns a file writing to `writer`.
+    pub fn new(writer: impl AsyncWrite + Send + Unpin + 'static) -> Self {
+        Self {
+            writer: Some(Box::new(writer)),
+        }
+    }
+}
+
+impl fmt::Debug for AsyncWriteFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "AsyncWriteFile")
+    }
+}
+
+fn detached() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::BrokenPipe,
+        "the async writer was not restored",
+    )
+}
+
+impl Write for AsyncWriteFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match &mut self.writer {
+            Some(writer) => block_on(|cx| Pin::new(&mut *writer).poll_write(cx, buf)),
+            None => Err(detached()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.writer {
+            Some(writer) => block_on(|cx| Pin::new(&mut *writer).poll_flush(cx)),
+            None => Err(detached()),
+        }
+    }
+}
+
+impl Read for AsyncWriteFile {
+    fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "can not read from an async writer",
+        ))
+    }
+}
+
+impl Seek for AsyncWriteFile {
+    fn seek(&mut self, _pos: io::SeekFrom) -> io::Result<u64> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "can not seek in an async writer",
+        ))
+    }
+}
+
+#[typetag::serde]
+impl WasiFile for AsyncWriteFile {
+    fn last_accessed(&self) -> u64 {
+        0
+    }
+    fn last_modified(&self) -> u64 {
+        0
+    }
+    fn created_time(&self) -> u64 {
+        0
+    }
+    fn size(&self) -> u64 {
+        0
+    }
+    fn set_len(&mut self, _len: u64) -> Result<(), WasiFsError> {
+        Err(WasiFsError::NotAFile)
+    }
+    fn unlink(&mut self) -> Result<(), WasiFsError> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn backpressure() {
+        let (mut writer, mut reader) = bounded_pipe(4);
+        let mut buf = [0; 8];
+        assert_eq!(
+            reader.try_read(&mut buf).unwrap_err().kind(),
+            io::ErrorKind::WouldBlock
+        );
+
+        let producer = thread::spawn(move || writer.write_all(b"0123456789"));
+        let mut output = Vec::new();
+        reader.read_to_end(&mut output).unwrap();
+        producer.join().unwrap().unwrap();
+        assert_eq!(output, b"0123456789");
+        assert_eq!(reader.try_read(&mut buf).unwrap(), 0);
+    }
+
+    #[test]
+    fn broken_pipe() {
+        let (mut writer, reader) = bounded_pipe(4);
+        drop(reader);
+        assert_eq!(
+            writer.write(b"0").unwrap_err().kind(),
+            io::ErrorKind::BrokenPipe
+        );
+    }
+
+    #[test]
+    fn async_files() {
+        let (mut input, stdin) = bounded_pipe(4);
+        let (stdout, mut output) = bounded_pipe(64);
+        let mut stdin = AsyncReadFile::new(stdin);
+        let mut stdout = AsyncWriteFile::new(stdout);
+
+        let host = thread::spawn(move || {
+            input.write_all(b"hello world").unwrap();
+            drop(input);
+            let mut echo = Vec::new();
+            output.read_to_end(&mut echo).unwrap();
+            echo
+        });
+        io::copy(&mut stdin, &mut stdout).unwrap();
+        drop(stdout);
+        assert_eq!(host.join().unwrap(), b"hello world");
+    }
+}
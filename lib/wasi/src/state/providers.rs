@@ -0,0 +1,224 @@
+//! The sources of the clocks and of the randomness of a WASI program.
+//!
+//! By default, they're the ones of the host. Replacing them with a
+//! [`DeterministicClock`] and a [`SeededRandom`] makes the WASI program
+//! reproducible, for record and replay testing or for consensus-critical
+//! execution. The arguments and the environment variables of the program
+//! are already the ones given to the [`WasiStateBuilder`], never the ones
+//! of the host.
+//!
+//! [`WasiStateBuilder`]: crate::WasiStateBuilder
+
+use crate::syscalls::types::*;
+use crate::syscalls::{platform_clock_res_get, platform_clock_time_get};
+use std::cell::Cell;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// The clocks of a WASI program.
+pub trait WasiClock: fmt::Debug + Send + Sync + 'static {
+    /// Returns the resolution of the clock `clock_id` in nanoseconds.
+    fn resolution(&self, clock_id: __wasi_clockid_t) -> Result<__wasi_timestamp_t, __wasi_errno_t>;
+
+    /// Returns the time of the clock `clock_id` in nanoseconds, with an
+    /// error of at most `precision` nanoseconds.
+    fn time(
+        &self,
+        clock_id: __wasi_clockid_t,
+        precision: __wasi_timestamp_t,
+    ) -> Result<__wasi_timestamp_t, __wasi_errno_t>;
+
+    /// Waits for `duration` nanoseconds of the clock `clock_id`, when
+    /// the program polls a clock subscription.
+    ///
+    /// By default, the host thread sleeps: a clock whose time isn't
+    /// the one of the host must advance its own time instead.
+    fn sleep(
+        &self,
+        clock_id: __wasi_clockid_t,
+        duration: __wasi_timestamp_t,
+    ) -> Result<(), __wasi_errno_t> {
+        let _ = clock_id;
+        std::thread::sleep(Duration::from_nanos(duration));
+        Ok(())
+    }
+}
+
+/// The clocks of the host, the default ones.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HostClock;
+
+impl WasiClock for HostClock {
+    fn resolution(&self, clock_id: __wasi_clockid_t) -> Result<__wasi_timestamp_t, __wasi_errno_t> {
+        let resolution = Cell::new(0);
+        match platform_clock_res_get(clock_id, &resolution) {
+            __WASI_ESUCCESS => Ok(resolution.get()),
+            err => Err(err),
+        }
+    }
+
+    fn time(
+        &self,
+        clock_id: __wasi_clockid_t,
+        precision: __wasi_timestamp_t,
+    ) -> Result<__wasi_timestamp_t, __wasi_errno_t> {
+        let time = Cell::new(0);
+        match platform_clock_time_get(clock_id, precision, &time) {
+            __WASI_ESUCCESS => Ok(time.get()),
+            err => Err(err),
+        }
+    }
+}
+
+/// A clock starting at a given time, which advances by a fixed step each
+/// time it's read.
+///
+/// All the clocks of the WASI program read it, so the time is the same
+/// on every run of the program.
+#[derive(Debug)]
+pub struct DeterministicClock {
+    now: AtomicU64,
+    step: u64,
+}
+
+impl DeterministicClock {
+    /// Returns a clock starting at `start` nanoseconds, and advancing by
+    /// `step` nanoseconds each time it's read.
+    pub fn new(start: __wasi_timestamp_t, step: u64) -> Self {
+        Self {
+            now: AtomicU64::new(start),
+            step,
+        }
+    }
+}
+
+fn check_clock_id(clock_id: __wasi_clockid_t) -> Result<(), __wasi_errno_t> {
+    match clock_id {
+        __WASI_CLOCK_REALTIME
+        | __WASI_CLOCK_MONOTONIC
+        | __WASI_CLOCK_PROCESS_CPUTIME_ID
+        | __WASI_CLOCK_THREAD_CPUTIME_ID => Ok(()),
+        _ => Err(__WASI_EINVAL),
+    }
+}
+
+impl WasiClock for DeterministicClock {
+    fn resolution(&self, clock_id: __wasi_clockid_t) -> Result<__wasi_timestamp_t, __wasi_errno_t> {
+        check_clock_id(clock_id)?;
+        Ok(self.step.max(1))
+    }
+
+    fn time(
+        &self,
+        clock_id: __wasi_clockid_t,
+        _precision: __wasi_timestamp_t,
+    ) -> Result<__wasi_timestamp_t, __wasi_errno_t> {
+        check_clock_id(clock_id)?;
+        Ok(self.now.fetch_add(self.step, Ordering::SeqCst))
+    }
+
+    /// Advances the clock by `duration`, without sleeping.
+    fn sleep(
+        &self,
+        clock_id: __wasi_clockid_t,
+        duration: __wasi_timestamp_t,
+    ) -> Result<(), __wasi_errno_t> {
+        check_clock_id(clock_id)?;
+        self.now.fetch_add(duration, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+/// The source of the randomness of a WASI program.
+pub trait WasiRandom: fmt::Debug + Send + Sync + 'static {
+    /// Fills `buf` with random bytes.
+    fn fill(&self, buf: &mut [u8]) -> Result<(), __wasi_errno_t>;
+}
+
+/// The randomness of the host, the default one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HostRandom;
+
+impl WasiRandom for HostRandom {
+    fn fill(&self, buf: &mut [u8]) -> Result<(), __wasi_errno_t> {
+        getrandom::getrandom(buf).map_err(|_| __WASI_EIO)
+    }
+}
+
+/// A pseudo-random generator, which generates the same bytes for the same
+/// seed.
+///
+/// It's not cryptographically secure.
+#[derive(Debug)]
+pub struct SeededRandom {
+    state: Mutex<u64>,
+}
+
+impl SeededRandom {
+    /// Returns a generator seeded with `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: Mutex::new(seed),
+        }
+    }
+}
+
+impl WasiRandom for SeededRandom {
+    fn fill(&self, buf: &mut [u8]) -> Result<(), __wasi_errno_t> {
+        let mut state = self.state.lock().unwrap();
+        for chunk in buf.chunks_mut(8) {
+            // SplitMix64
+            *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = *state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^= z >> 31;
+            chunk.copy_from_slice(&z.to_le_bytes()[..chunk.len()]);
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn host_clock() -> Arc<dyn WasiClock> {
+    Arc::new(HostClock)
+}
+
+pub(crate) fn host_random() -> Arc<dyn WasiRandom> {
+    Arc::new(HostRandom)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn deterministic_clock() {
+        let clock = DeterministicClock::new(1_000, 10);
+        assert_eq!(clock.time(__WASI_CLOCK_REALTIME, 1), Ok(1_000));
+        assert_eq!(clock.time(__WASI_CLOCK_MONOTONIC, 1), Ok(1_010));
+        assert_eq!(clock.resolution(__WASI_CLOCK_MONOTONIC), Ok(10));
+        assert_eq!(clock.time(42, 1), Err(__WASI_EINVAL));
+    }
+
+    #[test]
+    fn deterministic_clock_sleep() {
+        let clock = DeterministicClock::new(1_000, 10);
+        let start = std::time::Instant::now();
+        assert_eq!(clock.sleep(__WASI_CLOCK_REALTIME, 60_000_000_000), Ok(()));
+        assert!(start.elapsed() < Duration::from_secs(60));
+        assert_eq!(clock.time(__WASI_CLOCK_REALTIME, 1), Ok(60_000_001_000));
+    }
+
+    #[test]
+    fn seeded_random() {
+        let mut a = [0; 13];
+        let mut b = [0; 13];
+        SeededRandom::new(7).fill(&mut a).unwrap();
+        SeededRandom::new(7).fill(&mut b).unwrap();
+        assert_eq!(a, b);
+        SeededRandom::new(8).fill(&mut b).unwrap();
+        assert_ne!(a, b);
+    }
+}
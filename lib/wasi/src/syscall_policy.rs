@@ -0,0 +1,144 @@
+//! The filtering of the syscalls a WASI program can call, like a
+//! seccomp filter.
+//!
+//! A syscall denied by the [`SyscallPolicy`] given to
+//! [`WasiStateBuilder::syscall_policy`] is replaced, in the import object
+//! of the instance, by a stub returning `ENOTCAPABLE`. The syscalls without
+//! a return value, like `proc_exit`, trap instead.
+//!
+//! [`WasiStateBuilder::syscall_policy`]: crate::WasiStateBuilder::syscall_policy
+
+use crate::syscalls::types::__WASI_ENOTCAPABLE;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::Arc;
+use tracing::warn;
+use wasmer::{
+    Exports, Extern, Function, FunctionType, ImportObject, LikeNamespace, RuntimeError, Store,
+    Type, Value,
+};
+
+/// The syscalls a WASI program can't call, and what to do when it tries.
+///
+/// ```
+/// # use wasmer_wasi::{SyscallPolicy, WasiState};
+/// WasiState::new("sandboxed")
+///     .syscall_policy(
+///         SyscallPolicy::new()
+///             .deny("path_open")
+///             .deny("fd_renumber")
+///             .on_denied(|syscall| eprintln!("denied call to `{}`", syscall)),
+///     )
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Clone, Default)]
+pub struct SyscallPolicy {
+    denied: BTreeSet<String>,
+    on_denied: Option<Arc<dyn Fn(&str) + Send + Sync>>,
+}
+
+impl fmt::Debug for SyscallPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SyscallPolicy")
+            .field("denied", &self.denied)
+            .field("on_denied exists", &self.on_denied.is_some())
+            .finish()
+    }
+}
+
+impl SyscallPolicy {
+    /// Returns a policy allowing every syscall, the default one.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Denies the syscall named `syscall`, like `path_open`.
+    ///
+    /// A name which isn't a syscall of the WASI version of the program
+    /// is reported with a warning when its import object is generated.
+    pub fn deny(mut self, syscall: impl Into<String>) -> Self {
+        self.denied.insert(syscall.into());
+        self
+    }
+
+    /// Calls `callback` with the name of the syscall each time the WASI
+    /// program calls a denied syscall.
+    pub fn on_denied(mut self, callback: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        self.on_denied = Some(Arc::new(callback));
+        self
+    }
+
+    /// Returns whether the syscall named `syscall` is denied.
+    pub fn is_denied(&self, syscall: &str) -> bool {
+        self.denied.contains(syscall)
+    }
+
+    /// Replaces the denied syscalls of the namespace `namespace` of
+    /// `import_object` with stubs, and warns about the denied names
+    /// which aren't syscalls of the namespace, likely misspelled.
+    pub(crate) fn apply(&self, store: &Store, import_object: &mut ImportObject, namespace: &str) {
+        if self.denied.is_empty() {
+            return;
+        }
+        let syscalls = match import_object.register(namespace, Exports::new()) {
+            Some(syscalls) => syscalls,
+            None => return,
+        };
+        let syscalls = syscalls.get_namespace_exports();
+        for unknown in self
+            .denied
+            .iter()
+            .filter(|denied| !syscalls.iter().any(|(name, _)| name == *denied))
+        {
+            warn!(
+                "the denied syscall `{}` isn't a syscall of `{}`",
+                unknown, namespace
+            );
+        }
+        let mut exports = Exports::new();
+        for (name, export) in syscalls {
+            match Extern::from_vm_export(store, export) {
+                Extern::Function(function) if self.is_denied(&name) => {
+                    let stub = self.stub(store, &name, function.ty());
+                    exports.insert(name, stub);
+                }
+                export => exports.insert(name, export),
+            }
+        }
+        import_object.register(namespace, exports);
+    }
+
+    fn stub(&self, store: &Store, syscall: &str, ty: &FunctionType) -> Function {
+        let syscall = syscall.to_string();
+        let on_denied = self.on_denied.clone();
+        let returns_errno = ty.results() == [Type::I32];
+        Function::new(store, ty, move |_args| {
+            if let Some(on_denied) = &on_denied {
+                on_denied(&syscall);
+            }
+            if returns_errno {
+                Ok(vec![Value::I32(__WASI_ENOTCAPABLE as i32)])
+            } else {
+                Err(RuntimeError::new(format!(
+                    "the WASI syscall `{}` is denied",
+                    syscall
+                )))
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn deny_list() {
+        let policy = SyscallPolicy::new().deny("path_open").deny("fd_renumber");
+        assert!(policy.is_denied("path_open"));
+        assert!(policy.is_denied("fd_renumber"));
+        assert!(!policy.is_denied("fd_read"));
+        assert!(!SyscallPolicy::new().is_denied("path_open"));
+    }
+}
@@ -15,10 +15,12 @@ pub mod legacy;
 
 use self::types::*;
 use crate::{
+    fs::{FileSystem, OpenOptions},
+    net::WasiSocket,
     ptr::{Array, WasmPtr},
     state::{
         self, host_file_type_to_wasi_file_type, iterate_poll_events, poll, Fd, HostFile, Inode,
-        InodeVal, Kind, PollEvent, PollEventBuilder, WasiFile, WasiFsError, WasiState,
+        InodeVal, Kind, PollEvent, PollEventBuilder, WasiClock, WasiFile, WasiFsError, WasiState,
         MAX_SYMLINKS,
     },
     WasiEnv, WasiError,
@@ -117,12 +119,8 @@ fn write_buffer_array(
     __WASI_ESUCCESS
 }
 
-fn get_current_time_in_nanos() -> Result<__wasi_timestamp_t, __wasi_errno_t> {
-    let now = std::time::SystemTime::now();
-    let duration = now
-        .duration_since(std::time::SystemTime::UNIX_EPOCH)
-        .map_err(|_| __WASI_EIO)?;
-    Ok(duration.as_nanos() as __wasi_timestamp_t)
+fn get_current_time_in_nanos(clock: &dyn WasiClock) -> Result<__wasi_timestamp_t, __wasi_errno_t> {
+    clock.time(__WASI_CLOCK_REALTIME, 1)
 }
 
 /// ### `args_get()`
@@ -205,9 +203,11 @@ pub fn clock_res_get(
 ) -> __wasi_errno_t {
     debug!("wasi::clock_res_get");
     let memory = env.memory();
+    let clock = env.state().clock.clone();
 
     let out_addr = wasi_try!(resolution.deref(memory));
-    platform_clock_res_get(clock_id, out_addr)
+    out_addr.set(wasi_try!(clock.resolution(clock_id)));
+    __WASI_ESUCCESS
 }
 
 /// ### `clock_time_get()`
@@ -231,15 +231,12 @@ pub fn clock_time_get(
         clock_id, precision
     );
     let memory = env.memory();
+    let clock = env.state().clock.clone();
 
     let out_addr = wasi_try!(time.deref(memory));
-    let result = platform_clock_time_get(clock_id, precision, out_addr);
-    debug!(
-        "time: {} => {}",
-        wasi_try!(time.deref(memory)).get(),
-        result
-    );
-    result
+    out_addr.set(wasi_try!(clock.time(clock_id, precision)));
+    debug!("time: {}", out_addr.get());
+    __WASI_ESUCCESS
 }
 
 /// ### `environ_get()`
@@ -453,6 +450,17 @@ pub fn fd_fdstat_set_flags(
     }
 
     fd_entry.flags = flags;
+    let inode = fd_entry.inode;
+
+    if let Kind::File {
+        handle: Some(handle),
+        ..
+    } = &mut state.fs.inodes[inode].kind
+    {
+        if let Some(socket) = handle.downcast_mut::<WasiSocket>() {
+            wasi_try!(socket.set_nonblocking(flags & __WASI_FDFLAG_NONBLOCK != 0));
+        }
+    }
     __WASI_ESUCCESS
 }
 
@@ -574,6 +582,7 @@ pub fn fd_filestat_set_times(
 ) -> __wasi_errno_t {
     debug!("wasi::fd_filestat_set_times");
     let (memory, mut state) = env.get_memory_and_wasi_state(0);
+    let clock = state.clock.clone();
     let fd_entry = wasi_try!(state.fs.fd_map.get_mut(&fd).ok_or(__WASI_EBADF));
 
     if !has_rights(fd_entry.rights, __WASI_RIGHT_FD_FILESTAT_SET_TIMES) {
@@ -594,7 +603,7 @@ pub fn fd_filestat_set_times(
         let time_to_set = if fst_flags & __WASI_FILESTAT_SET_ATIM != 0 {
             st_atim
         } else {
-            wasi_try!(get_current_time_in_nanos())
+            wasi_try!(get_current_time_in_nanos(&*clock))
         };
         inode.stat.st_atim = time_to_set;
         // TODO: set it for more than just files
@@ -609,7 +618,7 @@ pub fn fd_filestat_set_times(
         let time_to_set = if fst_flags & __WASI_FILESTAT_SET_MTIM != 0 {
             st_mtim
         } else {
-            wasi_try!(get_current_time_in_nanos())
+            wasi_try!(get_current_time_in_nanos(&*clock))
         };
         inode.stat.st_mtim = time_to_set;
         // TODO: set it for more than just files
@@ -975,22 +984,26 @@ pub fn fd_readdir(
     let mut buf_idx = 0;
 
     let entries: Vec<(String, u8, u64)> = match &state.fs.inodes[working_dir.inode].kind {
-        Kind::Dir { path, entries, .. } => {
+        Kind::Dir {
+            path, entries, fs, ..
+        } => {
             // TODO: refactor this code
             // we need to support multiple calls,
             // simple and obviously correct implementation for now:
             // maintain consistent order via lexacographic sorting
-            let fs_info = wasi_try!(wasi_try!(std::fs::read_dir(path).map_err(|_| __WASI_EIO))
-                .collect::<Result<Vec<_>, _>>()
+            let fs_info = wasi_try!(wasi_try!(state.fs.filesystem(*fs))
+                .read_dir(path)
                 .map_err(|_| __WASI_EIO));
-            let mut entry_vec = wasi_try!(fs_info
+            let mut entry_vec = fs_info
                 .into_iter()
-                .map(|entry| Ok((
-                    entry.file_name().to_string_lossy().to_string(),
-                    host_file_type_to_wasi_file_type(entry.file_type().map_err(|_| __WASI_EIO)?),
-                    0, // TODO: inode
-                )))
-                .collect::<Result<Vec<(String, u8, u64)>, _>>());
+                .map(|entry| {
+                    (
+                        entry.name,
+                        entry.file_type,
+                        0, // TODO: inode
+                    )
+                })
+                .collect::<Vec<(String, u8, u64)>>();
             entry_vec.extend(
                 entries
                     .iter()
@@ -1375,7 +1388,9 @@ pub fn path_create_directory(
                 ref mut entries,
                 path,
                 parent,
+                fs,
             } => {
+                let fs = *fs;
                 match comp.borrow() {
                     ".." => {
                         if let Some(p) = parent {
@@ -1392,15 +1407,19 @@ pub fn path_create_directory(
                     let mut adjusted_path = path.clone();
                     // TODO: double check this doesn't risk breaking the sandbox
                     adjusted_path.push(comp);
-                    if adjusted_path.exists() && !adjusted_path.is_dir() {
-                        return __WASI_ENOTDIR;
-                    } else if !adjusted_path.exists() {
-                        wasi_try!(std::fs::create_dir(&adjusted_path).ok(), __WASI_EIO);
+                    let filesystem = wasi_try!(state.fs.filesystem(fs));
+                    match filesystem.metadata(&adjusted_path) {
+                        Ok(metadata) if !metadata.is_dir() => return __WASI_ENOTDIR,
+                        Ok(_) => (),
+                        Err(_) => {
+                            wasi_try!(filesystem.create_dir(&adjusted_path).ok(), __WASI_EIO);
+                        }
                     }
                     let kind = Kind::Dir {
                         parent: Some(cur_dir_inode),
                         path: adjusted_path,
                         entries: Default::default(),
+                        fs,
                     };
                     let new_inode = wasi_try!(state.fs.create_inode(kind, false, comp.to_string()));
                     // reborrow to insert
@@ -1504,6 +1523,7 @@ pub fn path_filestat_set_times(
 ) -> __wasi_errno_t {
     debug!("wasi::path_filestat_set_times");
     let (memory, mut state) = env.get_memory_and_wasi_state(0);
+    let clock = state.clock.clone();
     let fd_entry = wasi_try!(state.fs.get_fd(fd));
     let fd_inode = fd_entry.inode;
     if !has_rights(fd_entry.rights, __WASI_RIGHT_PATH_FILESTAT_SET_TIMES) {
@@ -1535,7 +1555,7 @@ pub fn path_filestat_set_times(
         let time_to_set = if fst_flags & __WASI_FILESTAT_SET_ATIM != 0 {
             st_atim
         } else {
-            wasi_try!(get_current_time_in_nanos())
+            wasi_try!(get_current_time_in_nanos(&*clock))
         };
         inode.stat.st_atim = time_to_set;
         // TODO: set it for more than just files
@@ -1549,7 +1569,7 @@ pub fn path_filestat_set_times(
         let time_to_set = if fst_flags & __WASI_FILESTAT_SET_MTIM != 0 {
             st_mtim
         } else {
-            wasi_try!(get_current_time_in_nanos())
+            wasi_try!(get_current_time_in_nanos(&*clock))
         };
         inode.stat.st_mtim = time_to_set;
         // TODO: set it for more than just files
@@ -1694,10 +1714,11 @@ pub fn path_open(
     // - __WASI_O_TRUNC (truncate size to 0)
 
     let working_dir = wasi_try!(state.fs.get_fd(dirfd));
+    let working_dir_rights = working_dir.rights;
     let working_dir_rights_inheriting = working_dir.rights_inheriting;
 
     // ASSUMPTION: open rights apply recursively
-    if !has_rights(working_dir.rights, __WASI_RIGHT_PATH_OPEN) {
+    if !has_rights(working_dir_rights, __WASI_RIGHT_PATH_OPEN) {
         return __WASI_EACCES;
     }
     let path_string = unsafe { get_input_str!(memory, path, path_len) };
@@ -1716,13 +1737,15 @@ pub fn path_open(
     // COMMENTED OUT: WASI isn't giving appropriate rights here when opening
     //              TODO: look into this; file a bug report if this is a bug
     let adjusted_rights = /*fs_rights_base &*/ working_dir_rights_inheriting;
+    let mut open_options = OpenOptions::new();
     let inode = if let Ok(inode) = maybe_inode {
         // Happy path, we found the file we're trying to open
-        match &mut state.fs.inodes[inode].kind {
+        match &state.fs.inodes[inode].kind {
             Kind::File {
-                ref mut handle,
+                handle,
                 path,
                 fd,
+                fs,
             } => {
                 if let Some(special_fd) = fd {
                     // short circuit if we're dealing with a special file
@@ -1733,10 +1756,10 @@ pub fn path_open(
                 if o_flags & __WASI_O_DIRECTORY != 0 {
                     return __WASI_ENOTDIR;
                 }
-                if o_flags & __WASI_O_EXCL != 0 && path.exists() {
+                let filesystem = wasi_try!(state.fs.filesystem(*fs));
+                if o_flags & __WASI_O_EXCL != 0 && filesystem.metadata(path).is_ok() {
                     return __WASI_EEXIST;
                 }
-                let mut open_options = std::fs::OpenOptions::new();
                 let write_permission = adjusted_rights & __WASI_RIGHT_FD_WRITE != 0;
                 // append, truncate, and create all require the permission to write
                 let (append_permission, truncate_permission, create_permission) =
@@ -1766,13 +1789,11 @@ pub fn path_open(
                 if o_flags & __WASI_O_TRUNC != 0 {
                     open_flags |= Fd::TRUNCATE;
                 }
-                *handle = Some(Box::new(HostFile::new(
-                    wasi_try!(open_options.open(&path).map_err(|_| __WASI_EIO)),
-                    path.to_path_buf(),
-                    true,
-                    adjusted_rights & __WASI_RIGHT_FD_WRITE != 0,
-                    false,
-                )));
+                let file = wasi_try!(filesystem.open(path, open_options).map_err(|_| __WASI_EIO));
+                // reborrow to store the opened file
+                if let Kind::File { handle, .. } = &mut state.fs.inodes[inode].kind {
+                    *handle = Some(file);
+                }
             }
             Kind::Buffer { .. } => unimplemented!("wasi::path_open for Buffer type files"),
             Kind::Dir { .. } | Kind::Root { .. } => {
@@ -1799,6 +1820,9 @@ pub fn path_open(
             if o_flags & __WASI_O_DIRECTORY != 0 {
                 return __WASI_ENOTDIR;
             }
+            if !has_rights(working_dir_rights, __WASI_RIGHT_PATH_CREATE_FILE) {
+                return __WASI_EACCES;
+            }
             debug!("Creating file");
             // strip end file name
 
@@ -1807,11 +1831,11 @@ pub fn path_open(
                 &path_arg,
                 dirflags & __WASI_LOOKUP_SYMLINK_FOLLOW != 0
             ));
-            let new_file_host_path = match &state.fs.inodes[parent_inode].kind {
-                Kind::Dir { path, .. } => {
+            let (new_file_host_path, new_file_fs) = match &state.fs.inodes[parent_inode].kind {
+                Kind::Dir { path, fs, .. } => {
                     let mut new_path = path.clone();
                     new_path.push(&new_entity_name);
-                    new_path
+                    (new_path, *fs)
                 }
                 Kind::Root { .. } => return __WASI_EACCES,
                 _ => return __WASI_EINVAL,
@@ -1819,7 +1843,6 @@ pub fn path_open(
             // once we got the data we need from the parent, we lookup the host file
             // todo: extra check that opening with write access is okay
             let handle = {
-                let mut open_options = std::fs::OpenOptions::new();
                 let open_options = open_options
                     .read(true)
                     .append(fs_flags & __WASI_FDFLAG_APPEND != 0)
@@ -1829,16 +1852,12 @@ pub fn path_open(
                     .create_new(true);
                 open_flags |= Fd::READ | Fd::WRITE | Fd::CREATE | Fd::TRUNCATE;
 
-                Some(Box::new(HostFile::new(
-                    wasi_try!(open_options.open(&new_file_host_path).map_err(|e| {
+                Some(wasi_try!(wasi_try!(state.fs.filesystem(new_file_fs))
+                    .open(&new_file_host_path, open_options)
+                    .map_err(|e| {
                         debug!("Error opening file {}", e);
                         __WASI_EIO
-                    })),
-                    new_file_host_path.clone(),
-                    true,
-                    true,
-                    true,
-                )) as Box<dyn WasiFile>)
+                    })))
             };
 
             let new_inode = {
@@ -1846,6 +1865,7 @@ pub fn path_open(
                     handle,
                     path: new_file_host_path,
                     fd: None,
+                    fs: new_file_fs,
                 };
                 wasi_try!(state.fs.create_inode(kind, false, new_entity_name.clone()))
             };
@@ -1870,9 +1890,10 @@ pub fn path_open(
 
     // TODO: check and reduce these
     // TODO: ensure a mutable fd to root can never be opened
+    // the opened directories can't give more rights than the one they are in
     let out_fd = wasi_try!(state.fs.create_fd(
         adjusted_rights,
-        fs_rights_inheriting,
+        fs_rights_inheriting & working_dir_rights_inheriting,
         fs_flags,
         open_flags,
         inode
@@ -1983,6 +2004,9 @@ pub fn path_remove_directory(
     let (memory, mut state) = env.get_memory_and_wasi_state(0);
 
     let base_dir = wasi_try!(state.fs.fd_map.get(&fd), __WASI_EBADF);
+    if !has_rights(base_dir.rights, __WASI_RIGHT_PATH_REMOVE_DIRECTORY) {
+        return __WASI_EACCES;
+    }
     let path_str = unsafe { get_input_str!(memory, path, path_len) };
 
     let inode = wasi_try!(state.fs.get_inode_at_path(fd, path_str, false));
@@ -1991,14 +2015,19 @@ pub fn path_remove_directory(
             .fs
             .get_parent_inode_at_path(fd, std::path::Path::new(path_str), false));
 
-    let host_path_to_remove = match &state.fs.inodes[inode].kind {
-        Kind::Dir { entries, path, .. } => {
+    let (host_path_to_remove, fs) = match &state.fs.inodes[inode].kind {
+        Kind::Dir {
+            entries, path, fs, ..
+        } => {
             if !entries.is_empty()
-                || wasi_try!(std::fs::read_dir(path).ok(), __WASI_EIO).count() != 0
+                || !wasi_try!(wasi_try!(state.fs.filesystem(*fs))
+                    .read_dir(path)
+                    .map_err(|_| __WASI_EIO))
+                .is_empty()
             {
                 return __WASI_ENOTEMPTY;
             }
-            path.clone()
+            (path.clone(), *fs)
         }
         Kind::Root { .. } => return __WASI_EACCES,
         _ => return __WASI_ENOTDIR,
@@ -2018,7 +2047,10 @@ pub fn path_remove_directory(
         ),
     }
 
-    if std::fs::remove_dir(path_str).is_err() {
+    if wasi_try!(state.fs.filesystem(fs))
+        .remove_dir(&host_path_to_remove)
+        .is_err()
+    {
         // reinsert to prevent FS from being in bad state
         if let Kind::Dir {
             ref mut entries, ..
@@ -2084,20 +2116,27 @@ pub fn path_rename(
     let (target_parent_inode, target_entry_name) =
         wasi_try!(state.fs.get_parent_inode_at_path(new_fd, target_path, true));
 
-    let host_adjusted_target_path = match &state.fs.inodes[target_parent_inode].kind {
-        Kind::Dir { entries, path, .. } => {
+    let (host_adjusted_target_path, target_fs) = match &state.fs.inodes[target_parent_inode].kind {
+        Kind::Dir {
+            entries, path, fs, ..
+        } => {
             if entries.contains_key(&target_entry_name) {
                 return __WASI_EEXIST;
             }
             let mut out_path = path.clone();
             out_path.push(target_path);
-            out_path
+            (out_path, *fs)
         }
         Kind::Root { .. } => return __WASI_ENOTCAPABLE,
         Kind::Symlink { .. } | Kind::File { .. } | Kind::Buffer { .. } => {
             unreachable!("Fatal internal logic error: parent of inode is not a directory")
         }
     };
+    // entries can't be moved between the file systems of the preopened directories
+    match &state.fs.inodes[source_parent_inode].kind {
+        Kind::Dir { fs, .. } if *fs != target_fs => return __WASI_EXDEV,
+        _ => (),
+    }
     let source_entry = match &mut state.fs.inodes[source_parent_inode].kind {
         Kind::Dir { entries, .. } => wasi_try!(entries.remove(&source_entry_name), __WASI_EINVAL),
         Kind::Root { .. } => return __WASI_ENOTCAPABLE,
@@ -2107,19 +2146,16 @@ pub fn path_rename(
     };
 
     match &mut state.fs.inodes[source_entry].kind {
-        Kind::File {
-            handle,
-            ref mut path,
-            ..
-        } => {
+        Kind::File { handle, path, .. } => {
             let result = if let Some(h) = handle {
                 h.rename_file(&host_adjusted_target_path)
                     .map_err(|e| e.into_wasi_err())
             } else {
-                let out =
-                    std::fs::rename(&path, &host_adjusted_target_path).map_err(|_| __WASI_EIO);
-                *path = host_adjusted_target_path;
-                out
+                let path = path.clone();
+                state.fs.filesystem(target_fs).and_then(|fs| {
+                    fs.rename(&path, &host_adjusted_target_path)
+                        .map_err(|_| __WASI_EIO)
+                })
             };
             // if the above operation failed we have to revert the previous change and then fail
             if let Err(e) = result {
@@ -2128,6 +2164,9 @@ pub fn path_rename(
                     return e;
                 }
             }
+            if let Kind::File { path, .. } = &mut state.fs.inodes[source_entry].kind {
+                *path = host_adjusted_target_path;
+            }
         }
         Kind::Dir { path, .. } => unimplemented!("wasi::path_rename on Directories"),
         Kind::Buffer { .. } => {}
@@ -2279,14 +2318,19 @@ pub fn path_unlink_file(
     state.fs.inodes[removed_inode].stat.st_nlink -= 1;
     if state.fs.inodes[removed_inode].stat.st_nlink == 0 {
         match &mut state.fs.inodes[removed_inode].kind {
-            Kind::File { handle, path, .. } => {
+            Kind::File {
+                handle, path, fs, ..
+            } => {
                 if let Some(h) = handle {
                     wasi_try!(h.unlink().map_err(WasiFsError::into_wasi_err));
                 } else {
                     // File is closed
                     // problem with the abstraction, we can't call unlink because there's no handle
                     // TODO: replace this code
-                    wasi_try!(std::fs::remove_file(path).map_err(|_| __WASI_EIO));
+                    let (path, fs) = (path.clone(), *fs);
+                    wasi_try!(wasi_try!(state.fs.filesystem(fs))
+                        .remove_file(&path)
+                        .map_err(|_| __WASI_EIO));
                 }
             }
             Kind::Dir { .. } | Kind::Root { .. } => return __WASI_EISDIR,
@@ -2445,8 +2489,9 @@ pub fn poll_oneoff(
             let remaining_ns = ns_to_sleep as i64 - total_ns_slept as i64;
             if remaining_ns > 0 {
                 debug!("Sleeping for {} nanoseconds", remaining_ns);
-                let duration = std::time::Duration::from_nanos(remaining_ns as u64);
-                std::thread::sleep(duration);
+                wasi_try!(state
+                    .clock
+                    .sleep(__WASI_CLOCK_REALTIME, remaining_ns as u64));
                 total_ns_slept += remaining_ns;
             }
         }
@@ -2540,17 +2585,13 @@ pub fn proc_raise(env: &WasiEnv, sig: __wasi_signal_t) -> __wasi_errno_t {
 pub fn random_get(env: &WasiEnv, buf: WasmPtr<u8, Array>, buf_len: u32) -> __wasi_errno_t {
     debug!("wasi::random_get buf_len: {}", buf_len);
     let memory = env.memory();
+    let random = env.state().random.clone();
 
     let buf = wasi_try!(buf.deref(memory, 0, buf_len));
 
-    let res = unsafe {
-        let u8_buffer = &mut *(buf as *const [_] as *mut [_] as *mut [u8]);
-        getrandom::getrandom(u8_buffer)
-    };
-    match res {
-        Ok(()) => __WASI_ESUCCESS,
-        Err(_) => __WASI_EIO,
-    }
+    let u8_buffer = unsafe { &mut *(buf as *const [_] as *mut [_] as *mut [u8]) };
+    wasi_try!(random.fill(u8_buffer));
+    __WASI_ESUCCESS
 }
 
 /// ### `sched_yield()`
@@ -2561,6 +2602,208 @@ pub fn sched_yield(env: &WasiEnv) -> __wasi_errno_t {
     __WASI_ESUCCESS
 }
 
+/// Returns the socket of `fd`, if `fd` has the `rights`.
+fn get_socket_mut(
+    state: &mut WasiState,
+    fd: __wasi_fd_t,
+    rights: __wasi_rights_t,
+) -> Result<&mut WasiSocket, __wasi_errno_t> {
+    let fd_entry = state.fs.get_fd(fd)?;
+    if !has_rights(fd_entry.rights, rights) {
+        return Err(__WASI_EACCES);
+    }
+    let inode = fd_entry.inode;
+
+    match &mut state.fs.inodes[inode].kind {
+        Kind::File {
+            handle: Some(handle),
+            ..
+        } => handle.downcast_mut::<WasiSocket>().ok_or(__WASI_ENOTSOCK),
+        _ => Err(__WASI_ENOTSOCK),
+    }
+}
+
+/// ### `sock_open()`
+/// Create a socket
+/// Inputs:
+/// - `__wasi_address_family_t af`
+///     The address family of the socket, IPv4 or IPv6
+/// - `__wasi_sock_type_t socktype`
+///     The type of the socket, stream (TCP) or datagram (UDP)
+/// Output:
+/// - `__wasi_fd_t *ro_sock`
+///     The file descriptor of the socket
+pub fn sock_open(
+    env: &WasiEnv,
+    af: __wasi_address_family_t,
+    socktype: __wasi_sock_type_t,
+    ro_sock: WasmPtr<__wasi_fd_t>,
+) -> __wasi_errno_t {
+    debug!("wasi::sock_open: af={}, socktype={}", af, socktype);
+    let (memory, mut state) = env.get_memory_and_wasi_state(0);
+    let ro_sock = wasi_try!(ro_sock.deref(memory));
+
+    let socket = wasi_try!(WasiSocket::new(af, socktype));
+    let fd = wasi_try!(state.fs.create_socket_fd(socket, 0));
+    ro_sock.set(fd);
+
+    __WASI_ESUCCESS
+}
+
+/// ### `sock_bind()`
+/// Bind a socket to an address, if the network policy allows it
+/// Inputs:
+/// - `__wasi_fd_t sock`
+///     The socket to bind
+/// - `const __wasi_addr_t *addr`
+///     The address to bind the socket to
+pub fn sock_bind(env: &WasiEnv, sock: __wasi_fd_t, addr: WasmPtr<__wasi_addr_t>) -> __wasi_errno_t {
+    debug!("wasi::sock_bind: sock={}", sock);
+    let (memory, mut state) = env.get_memory_and_wasi_state(0);
+    let addr = wasi_try!(addr.deref(memory)).get();
+    let addr = wasi_try!(addr.to_socket_addr(), __WASI_EINVAL);
+    let policy = state.network_policy.clone();
+
+    let socket = wasi_try!(get_socket_mut(&mut state, sock, 0));
+    wasi_try!(socket.bind(&*policy, addr));
+
+    __WASI_ESUCCESS
+}
+
+/// ### `sock_listen()`
+/// Listen to the connections on a bound stream socket
+/// Inputs:
+/// - `__wasi_fd_t sock`
+///     The socket which will listen
+/// - `u32 backlog`
+///     The maximum length of the queue of pending connections, which is
+///     ignored: the host chooses it
+pub fn sock_listen(env: &WasiEnv, sock: __wasi_fd_t, backlog: u32) -> __wasi_errno_t {
+    debug!("wasi::sock_listen: sock={}, backlog={}", sock, backlog);
+    let (memory, mut state) = env.get_memory_and_wasi_state(0);
+
+    let socket = wasi_try!(get_socket_mut(&mut state, sock, 0));
+    wasi_try!(socket.listen());
+
+    __WASI_ESUCCESS
+}
+
+/// ### `sock_accept()`
+/// Accept a connection on a listening socket
+/// Inputs:
+/// - `__wasi_fd_t sock`
+///     The listening socket
+/// - `__wasi_fdflags_t fd_flags`
+///     The flags of the file descriptor of the connection
+/// Output:
+/// - `__wasi_fd_t *ro_fd`
+///     The file descriptor of the connection
+pub fn sock_accept(
+    env: &WasiEnv,
+    sock: __wasi_fd_t,
+    fd_flags: __wasi_fdflags_t,
+    ro_fd: WasmPtr<__wasi_fd_t>,
+) -> __wasi_errno_t {
+    debug!("wasi::sock_accept: sock={}", sock);
+    let (memory, mut state) = env.get_memory_and_wasi_state(0);
+    let ro_fd = wasi_try!(ro_fd.deref(memory));
+
+    let listener = wasi_try!(get_socket_mut(&mut state, sock, 0));
+    let socket = wasi_try!(listener.accept(fd_flags & __WASI_FDFLAG_NONBLOCK != 0));
+    let fd = wasi_try!(state.fs.create_socket_fd(socket, fd_flags));
+    ro_fd.set(fd);
+
+    __WASI_ESUCCESS
+}
+
+/// ### `sock_connect()`
+/// Connect a socket to an address, if the network policy allows it
+/// Inputs:
+/// - `__wasi_fd_t sock`
+///     The socket to connect
+/// - `const __wasi_addr_t *addr`
+///     The address to connect the socket to
+pub fn sock_connect(
+    env: &WasiEnv,
+    sock: __wasi_fd_t,
+    addr: WasmPtr<__wasi_addr_t>,
+) -> __wasi_errno_t {
+    debug!("wasi::sock_connect: sock={}", sock);
+    let (memory, mut state) = env.get_memory_and_wasi_state(0);
+    let addr = wasi_try!(addr.deref(memory)).get();
+    let addr = wasi_try!(addr.to_socket_addr(), __WASI_EINVAL);
+    let policy = state.network_policy.clone();
+
+    let socket = wasi_try!(get_socket_mut(&mut state, sock, 0));
+    wasi_try!(socket.connect(&*policy, addr));
+
+    __WASI_ESUCCESS
+}
+
+/// ### `sock_addr_local()`
+/// Get the local address of a socket
+/// Inputs:
+/// - `__wasi_fd_t sock`
+///     The socket
+/// Output:
+/// - `__wasi_addr_t *ro_addr`
+///     The address the socket is bound to
+pub fn sock_addr_local(
+    env: &WasiEnv,
+    sock: __wasi_fd_t,
+    ro_addr: WasmPtr<__wasi_addr_t>,
+) -> __wasi_errno_t {
+    debug!("wasi::sock_addr_local: sock={}", sock);
+    let (memory, mut state) = env.get_memory_and_wasi_state(0);
+    let ro_addr = wasi_try!(ro_addr.deref(memory));
+
+    let socket = wasi_try!(get_socket_mut(&mut state, sock, 0));
+    let addr = wasi_try!(socket.local_addr());
+    ro_addr.set(__wasi_addr_t::from_socket_addr(addr));
+
+    __WASI_ESUCCESS
+}
+
+/// ### `sock_addr_remote()`
+/// Get the remote address of a connected socket
+/// Inputs:
+/// - `__wasi_fd_t sock`
+///     The socket
+/// Output:
+/// - `__wasi_addr_t *ro_addr`
+///     The address the socket is connected to
+pub fn sock_addr_remote(
+    env: &WasiEnv,
+    sock: __wasi_fd_t,
+    ro_addr: WasmPtr<__wasi_addr_t>,
+) -> __wasi_errno_t {
+    debug!("wasi::sock_addr_remote: sock={}", sock);
+    let (memory, mut state) = env.get_memory_and_wasi_state(0);
+    let ro_addr = wasi_try!(ro_addr.deref(memory));
+
+    let socket = wasi_try!(get_socket_mut(&mut state, sock, 0));
+    let addr = wasi_try!(socket.peer_addr());
+    ro_addr.set(__wasi_addr_t::from_socket_addr(addr));
+
+    __WASI_ESUCCESS
+}
+
+/// ### `sock_recv()`
+/// Receive a message from a socket
+/// Inputs:
+/// - `__wasi_fd_t sock`
+///     The socket on which to receive data
+/// - `const __wasi_iovec_t *ri_data`
+///     List of scatter/gather vectors to which to store data
+/// - `u32 ri_data_len`
+///     The length of the `ri_data` array
+/// - `__wasi_riflags_t ri_flags`
+///     Message flags
+/// Output:
+/// - `u32 *ro_datalen`
+///     Number of bytes stored in `ri_data`
+/// - `__wasi_roflags_t *ro_flags`
+///     Message flags
 pub fn sock_recv(
     env: &WasiEnv,
     sock: __wasi_fd_t,
@@ -2570,9 +2813,95 @@ pub fn sock_recv(
     ro_datalen: WasmPtr<u32>,
     ro_flags: WasmPtr<__wasi_roflags_t>,
 ) -> __wasi_errno_t {
-    debug!("wasi::sock_recv");
-    unimplemented!("wasi::sock_recv")
+    debug!("wasi::sock_recv: sock={}", sock);
+    let (memory, mut state) = env.get_memory_and_wasi_state(0);
+    let iovs_arr_cell = wasi_try!(ri_data.deref(memory, 0, ri_data_len));
+    let ro_datalen = wasi_try!(ro_datalen.deref(memory));
+    let ro_flags = wasi_try!(ro_flags.deref(memory));
+
+    let socket = wasi_try!(get_socket_mut(&mut state, sock, __WASI_RIGHT_FD_READ));
+    let mut buf = vec![
+        0;
+        iovs_arr_cell
+            .iter()
+            .map(|iov| iov.get().buf_len as usize)
+            .sum()
+    ];
+    let (len, _) = wasi_try!(socket.recv_from(&mut buf, ri_flags));
+    let bytes_read = wasi_try!(read_bytes(&buf[..len], memory, iovs_arr_cell));
+    ro_datalen.set(bytes_read);
+    ro_flags.set(0);
+
+    __WASI_ESUCCESS
 }
+
+/// ### `sock_recv_from()`
+/// Receive a message from a socket, with the address of its sender
+/// Inputs:
+/// - `__wasi_fd_t sock`
+///     The socket on which to receive data
+/// - `const __wasi_iovec_t *ri_data`
+///     List of scatter/gather vectors to which to store data
+/// - `u32 ri_data_len`
+///     The length of the `ri_data` array
+/// - `__wasi_riflags_t ri_flags`
+///     Message flags
+/// Output:
+/// - `__wasi_addr_t *ro_addr`
+///     The address of the sender
+/// - `u32 *ro_datalen`
+///     Number of bytes stored in `ri_data`
+/// - `__wasi_roflags_t *ro_flags`
+///     Message flags
+pub fn sock_recv_from(
+    env: &WasiEnv,
+    sock: __wasi_fd_t,
+    ri_data: WasmPtr<__wasi_iovec_t, Array>,
+    ri_data_len: u32,
+    ri_flags: __wasi_riflags_t,
+    ro_addr: WasmPtr<__wasi_addr_t>,
+    ro_datalen: WasmPtr<u32>,
+    ro_flags: WasmPtr<__wasi_roflags_t>,
+) -> __wasi_errno_t {
+    debug!("wasi::sock_recv_from: sock={}", sock);
+    let (memory, mut state) = env.get_memory_and_wasi_state(0);
+    let iovs_arr_cell = wasi_try!(ri_data.deref(memory, 0, ri_data_len));
+    let ro_addr = wasi_try!(ro_addr.deref(memory));
+    let ro_datalen = wasi_try!(ro_datalen.deref(memory));
+    let ro_flags = wasi_try!(ro_flags.deref(memory));
+
+    let socket = wasi_try!(get_socket_mut(&mut state, sock, __WASI_RIGHT_FD_READ));
+    let mut buf = vec![
+        0;
+        iovs_arr_cell
+            .iter()
+            .map(|iov| iov.get().buf_len as usize)
+            .sum()
+    ];
+    let (len, addr) = wasi_try!(socket.recv_from(&mut buf, ri_flags));
+    let addr = wasi_try!(addr.map_or_else(|| socket.peer_addr(), Ok));
+    let bytes_read = wasi_try!(read_bytes(&buf[..len], memory, iovs_arr_cell));
+    ro_addr.set(__wasi_addr_t::from_socket_addr(addr));
+    ro_datalen.set(bytes_read);
+    ro_flags.set(0);
+
+    __WASI_ESUCCESS
+}
+
+/// ### `sock_send()`
+/// Send a message on a socket
+/// Inputs:
+/// - `__wasi_fd_t sock`
+///     The socket on which to send data
+/// - `const __wasi_ciovec_t *si_data`
+///     List of scatter/gather vectors to which to retrieve data
+/// - `u32 si_data_len`
+///     The length of the `si_data` array
+/// - `__wasi_siflags_t si_flags`
+///     Message flags
+/// Output:
+/// - `u32 *so_datalen`
+///     Number of bytes transmitted
 pub fn sock_send(
     env: &WasiEnv,
     sock: __wasi_fd_t,
@@ -2581,10 +2910,77 @@ pub fn sock_send(
     si_flags: __wasi_siflags_t,
     so_datalen: WasmPtr<u32>,
 ) -> __wasi_errno_t {
-    debug!("wasi::sock_send");
-    unimplemented!("wasi::sock_send")
+    debug!("wasi::sock_send: sock={}", sock);
+    let (memory, mut state) = env.get_memory_and_wasi_state(0);
+    let iovs_arr_cell = wasi_try!(si_data.deref(memory, 0, si_data_len));
+    let so_datalen = wasi_try!(so_datalen.deref(memory));
+    let policy = state.network_policy.clone();
+
+    let mut buf = Vec::new();
+    wasi_try!(write_bytes(&mut buf, memory, iovs_arr_cell));
+    let socket = wasi_try!(get_socket_mut(&mut state, sock, __WASI_RIGHT_FD_WRITE));
+    let bytes_written = wasi_try!(socket.send_to(&*policy, &buf, None));
+    so_datalen.set(bytes_written as u32);
+
+    __WASI_ESUCCESS
 }
+
+/// ### `sock_send_to()`
+/// Send a message on a socket to an address, if the network policy allows
+/// it
+/// Inputs:
+/// - `__wasi_fd_t sock`
+///     The socket on which to send data
+/// - `const __wasi_ciovec_t *si_data`
+///     List of scatter/gather vectors to which to retrieve data
+/// - `u32 si_data_len`
+///     The length of the `si_data` array
+/// - `__wasi_siflags_t si_flags`
+///     Message flags
+/// - `const __wasi_addr_t *addr`
+///     The address to send the message to
+/// Output:
+/// - `u32 *so_datalen`
+///     Number of bytes transmitted
+pub fn sock_send_to(
+    env: &WasiEnv,
+    sock: __wasi_fd_t,
+    si_data: WasmPtr<__wasi_ciovec_t, Array>,
+    si_data_len: u32,
+    si_flags: __wasi_siflags_t,
+    addr: WasmPtr<__wasi_addr_t>,
+    so_datalen: WasmPtr<u32>,
+) -> __wasi_errno_t {
+    debug!("wasi::sock_send_to: sock={}", sock);
+    let (memory, mut state) = env.get_memory_and_wasi_state(0);
+    let iovs_arr_cell = wasi_try!(si_data.deref(memory, 0, si_data_len));
+    let addr = wasi_try!(addr.deref(memory)).get();
+    let addr = wasi_try!(addr.to_socket_addr(), __WASI_EINVAL);
+    let so_datalen = wasi_try!(so_datalen.deref(memory));
+    let policy = state.network_policy.clone();
+
+    let mut buf = Vec::new();
+    wasi_try!(write_bytes(&mut buf, memory, iovs_arr_cell));
+    let socket = wasi_try!(get_socket_mut(&mut state, sock, __WASI_RIGHT_FD_WRITE));
+    let bytes_written = wasi_try!(socket.send_to(&*policy, &buf, Some(addr)));
+    so_datalen.set(bytes_written as u32);
+
+    __WASI_ESUCCESS
+}
+
+/// ### `sock_shutdown()`
+/// Shut down socket send and receive channels
+/// Inputs:
+/// - `__wasi_fd_t sock`
+///     The socket to shut down
+/// - `__wasi_sdflags_t how`
+///     Which channels on the socket to shut down
 pub fn sock_shutdown(env: &WasiEnv, sock: __wasi_fd_t, how: __wasi_sdflags_t) -> __wasi_errno_t {
-    debug!("wasi::sock_shutdown");
-    unimplemented!("wasi::sock_shutdown")
+    debug!("wasi::sock_shutdown: sock={}", sock);
+    let (memory, mut state) = env.get_memory_and_wasi_state(0);
+
+    let socket = wasi_try!(get_socket_mut(&mut state, sock, __WASI_RIGHT_SOCK_SHUTDOWN));
+    wasi_try!(socket.shutdown(how));
+
+    __WASI_ESUCCESS
 }
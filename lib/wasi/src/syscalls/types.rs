@@ -5,8 +5,107 @@ use byteorder::{ReadBytesExt, WriteBytesExt, LE};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::mem;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
 use wasmer::ValueType;
 
+pub type __wasi_address_family_t = u8;
+pub const __WASI_ADDRESS_FAMILY_INET4: u8 = 0;
+pub const __WASI_ADDRESS_FAMILY_INET6: u8 = 1;
+
+pub type __wasi_addr_type_t = u8;
+pub const __WASI_ADDR_TYPE_IP4: u8 = 0;
+pub const __WASI_ADDR_TYPE_IP6: u8 = 1;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(C)]
+pub struct __wasi_addr_ip4_port_t {
+    pub addr: [u8; 4],
+    pub port: u16,
+}
+
+unsafe impl ValueType for __wasi_addr_ip4_port_t {}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(C)]
+pub struct __wasi_addr_ip6_port_t {
+    pub addr: [u16; 8],
+    pub port: u16,
+}
+
+unsafe impl ValueType for __wasi_addr_ip6_port_t {}
+
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub union __wasi_addr_u {
+    ip4: __wasi_addr_ip4_port_t,
+    ip6: __wasi_addr_ip6_port_t,
+}
+
+impl fmt::Debug for __wasi_addr_u {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "__wasi_addr_u")
+    }
+}
+
+unsafe impl ValueType for __wasi_addr_u {}
+
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct __wasi_addr_t {
+    pub tag: __wasi_addr_type_t,
+    pub u: __wasi_addr_u,
+}
+
+impl __wasi_addr_t {
+    pub fn from_socket_addr(addr: SocketAddr) -> Self {
+        match addr {
+            SocketAddr::V4(addr) => Self {
+                tag: __WASI_ADDR_TYPE_IP4,
+                u: __wasi_addr_u {
+                    ip4: __wasi_addr_ip4_port_t {
+                        addr: addr.ip().octets(),
+                        port: addr.port(),
+                    },
+                },
+            },
+            SocketAddr::V6(addr) => Self {
+                tag: __WASI_ADDR_TYPE_IP6,
+                u: __wasi_addr_u {
+                    ip6: __wasi_addr_ip6_port_t {
+                        addr: addr.ip().segments(),
+                        port: addr.port(),
+                    },
+                },
+            },
+        }
+    }
+
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    pub fn to_socket_addr(&self) -> Option<SocketAddr> {
+        match self.tag {
+            __WASI_ADDR_TYPE_IP4 => {
+                let ip4 = unsafe { self.u.ip4 };
+                Some(SocketAddr::V4(SocketAddrV4::new(
+                    Ipv4Addr::from(ip4.addr),
+                    ip4.port,
+                )))
+            }
+            __WASI_ADDR_TYPE_IP6 => {
+                let ip6 = unsafe { self.u.ip6 };
+                Some(SocketAddr::V6(SocketAddrV6::new(
+                    Ipv6Addr::from(ip6.addr),
+                    ip6.port,
+                    0,
+                    0,
+                )))
+            }
+            _ => None,
+        }
+    }
+}
+
+unsafe impl ValueType for __wasi_addr_t {}
+
 pub type __wasi_advice_t = u8;
 pub const __WASI_ADVICE_NORMAL: u8 = 0;
 pub const __WASI_ADVICE_SEQUENTIAL: u8 = 1;
@@ -522,6 +621,10 @@ pub const __WASI_SHUT_WR: u8 = 1 << 1;
 
 pub type __wasi_siflags_t = u16;
 
+pub type __wasi_sock_type_t = u8;
+pub const __WASI_SOCK_TYPE_DGRAM: u8 = 0;
+pub const __WASI_SOCK_TYPE_STREAM: u8 = 1;
+
 pub type __wasi_signal_t = u8;
 pub const __WASI_SIGHUP: u8 = 1;
 pub const __WASI_SIGINT: u8 = 2;
@@ -0,0 +1,59 @@
+use anyhow::Result;
+use std::sync::{Arc, Mutex};
+use wasmer::{Instance, Module, NativeFunc, Store};
+use wasmer_wasi::types::__WASI_ENOTCAPABLE;
+use wasmer_wasi::{SyscallPolicy, WasiState};
+
+/// A WASI program calling `fd_renumber`, which returns an errno, and
+/// `proc_exit`, which doesn't.
+const PROGRAM: &str = r#"
+(module
+  (import "wasi_snapshot_preview1" "fd_renumber" (func $fd_renumber (param i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+  (memory (export "memory") 1)
+  (func (export "renumber") (result i32)
+    (call $fd_renumber (i32.const 1) (i32.const 2)))
+  (func (export "exit")
+    (call $proc_exit (i32.const 0))))
+"#;
+
+#[test]
+fn denied_syscalls() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(&store, PROGRAM)?;
+    let denied = Arc::new(Mutex::new(Vec::new()));
+    let on_denied = denied.clone();
+    let mut wasi_env = WasiState::new("sandboxed")
+        .syscall_policy(
+            SyscallPolicy::new()
+                .deny("fd_renumber")
+                .deny("proc_exit")
+                .on_denied(move |syscall| on_denied.lock().unwrap().push(syscall.to_string())),
+        )
+        .finalize()?;
+    let instance = Instance::new(&module, &wasi_env.import_object(&module)?)?;
+
+    let renumber: NativeFunc<(), i32> = instance.exports.get_native_function("renumber")?;
+    assert_eq!(renumber.call()?, __WASI_ENOTCAPABLE as i32);
+
+    let exit: NativeFunc<(), ()> = instance.exports.get_native_function("exit")?;
+    let error = exit.call().unwrap_err();
+    assert_eq!(error.message(), "the WASI syscall `proc_exit` is denied");
+
+    assert_eq!(*denied.lock().unwrap(), ["fd_renumber", "proc_exit"]);
+    Ok(())
+}
+
+#[test]
+fn allowed_syscalls() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(&store, PROGRAM)?;
+    let mut wasi_env = WasiState::new("sandboxed")
+        .syscall_policy(SyscallPolicy::new().deny("path_open").deny("not_a_syscall"))
+        .finalize()?;
+    let instance = Instance::new(&module, &wasi_env.import_object(&module)?)?;
+
+    let renumber: NativeFunc<(), i32> = instance.exports.get_native_function("renumber")?;
+    assert_ne!(renumber.call()?, __WASI_ENOTCAPABLE as i32);
+    Ok(())
+}
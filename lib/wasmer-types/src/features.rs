@@ -75,6 +75,10 @@ impl Features {
     /// being in a module. Note that enabling the reference types feature will
     /// also enable the bulk memory feature.
     ///
+    /// The `ref.func` and `table.get` instructions aren't supported in the
+    /// function bodies yet. Neither are the typed references of the
+    /// function references proposal, `(ref $t)` and `call_ref`.
+    ///
     /// This is `false` by default.
     ///
     /// [proposal]: https://github.com/webassembly/reference-types
@@ -74,7 +74,7 @@ pub use crate::indexes::{
 pub use crate::initializers::{
     DataInitializer, DataInitializerLocation, OwnedDataInitializer, TableInitializer,
 };
-pub use crate::memory_view::{Atomically, MemoryView};
+pub use crate::memory_view::{Atomically, MemoryAccessError, MemoryView};
 pub use crate::native::{NativeWasmType, ValueType};
 pub use crate::r#ref::{ExternRef, HostInfo, HostRef};
 pub use crate::units::{
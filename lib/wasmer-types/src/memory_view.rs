@@ -1,11 +1,14 @@
 use crate::lib::std::cell::Cell;
 use crate::lib::std::marker::PhantomData;
 use crate::lib::std::ops::Deref;
+use crate::lib::std::ptr;
 use crate::lib::std::slice;
 use crate::lib::std::sync::atomic::{
     AtomicI16, AtomicI32, AtomicI64, AtomicI8, AtomicU16, AtomicU32, AtomicU64, AtomicU8,
 };
 use crate::native::ValueType;
+use core::mem::{align_of, size_of};
+use thiserror::Error;
 
 pub trait Atomic {
     type Output;
@@ -45,6 +48,17 @@ impl Atomicity for Atomically {}
 pub struct NonAtomically;
 impl Atomicity for NonAtomically {}
 
+/// An error accessing a memory through a [`MemoryView`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum MemoryAccessError {
+    /// The accessed range is out of the bounds of the memory.
+    #[error("The accessed range is out of the bounds of the memory")]
+    OutOfBounds,
+    /// The accessed offset isn't aligned for the type of the values.
+    #[error("The accessed offset is misaligned for the type of the values")]
+    Misaligned,
+}
+
 /// A view into a memory.
 pub struct MemoryView<'a, T: 'a, A = NonAtomically> {
     ptr: *mut T,
@@ -64,6 +78,56 @@ where
             _phantom: PhantomData,
         }
     }
+
+    /// Returns a view of the `len` values of type `U` starting at the
+    /// byte `offset` of this view, without copying them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the values aren't all in this view, or if
+    /// `offset` isn't aligned for `U`.
+    pub fn slice<U: ValueType>(
+        &self,
+        offset: usize,
+        len: usize,
+    ) -> Result<MemoryView<'a, U>, MemoryAccessError> {
+        let end = len
+            .checked_mul(size_of::<U>())
+            .and_then(|size| size.checked_add(offset))
+            .ok_or(MemoryAccessError::OutOfBounds)?;
+        if end > self.length * size_of::<T>() {
+            return Err(MemoryAccessError::OutOfBounds);
+        }
+        let ptr = unsafe { (self.ptr as *mut u8).add(offset) };
+        if ptr as usize % align_of::<U>() != 0 {
+            return Err(MemoryAccessError::Misaligned);
+        }
+        Ok(MemoryView {
+            ptr: ptr as *mut U,
+            length: len,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Copies the values of the view to `buf`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buf` doesn't have the length of the view.
+    pub fn copy_to(&self, buf: &mut [T]) {
+        assert_eq!(buf.len(), self.length, "the lengths must be the same");
+        unsafe { ptr::copy(self.ptr, buf.as_mut_ptr(), self.length) }
+    }
+
+    /// Copies the values of `data` to the view.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `data` doesn't have the length of the view.
+    pub fn copy_from(&self, data: &[T]) {
+        assert_eq!(data.len(), self.length, "the lengths must be the same");
+        unsafe { ptr::copy(data.as_ptr(), self.ptr, self.length) }
+    }
 }
 
 impl<'a, T: Atomic> MemoryView<'a, T> {
@@ -92,3 +156,34 @@ impl<'a, T> Deref for MemoryView<'a, T, Atomically> {
         unsafe { slice::from_raw_parts(self.ptr as *const T, self.length) }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slice() {
+        let mut bytes = [0u64; 4];
+        let view = unsafe { MemoryView::new(bytes.as_mut_ptr() as *mut u8, 32) };
+        let words = view.slice::<u32>(8, 4).unwrap();
+        words.copy_from(&[1, 2, 3, 4]);
+        assert_eq!(words[3].get(), 4);
+
+        let mut buf = [0; 2];
+        view.slice::<u32>(12, 2).unwrap().copy_to(&mut buf);
+        assert_eq!(buf, [2, 3]);
+
+        assert_eq!(
+            view.slice::<u32>(28, 2).err(),
+            Some(MemoryAccessError::OutOfBounds)
+        );
+        assert_eq!(
+            view.slice::<u8>(usize::MAX, 2).err(),
+            Some(MemoryAccessError::OutOfBounds)
+        );
+        assert_eq!(
+            view.slice::<u32>(2, 1).err(),
+            Some(MemoryAccessError::Misaligned)
+        );
+    }
+}
@@ -0,0 +1,73 @@
+use crate::utils::get_store;
+use anyhow::Result;
+use wasmer::*;
+
+const SELECT: &str = r#"
+    (func $select (param i32) (result i32)
+        (block
+            (block
+                (block
+                    (br_table 0 1 2 (local.get 0)))
+                (return (i32.const 10)))
+            (return (i32.const 20)))
+        (i32.const 30))"#;
+
+fn call(instance: &Instance, name: &str, arg: i32) -> Result<i32> {
+    let function: NativeFunc<i32, i32> = instance.exports.get_native_function(name)?;
+    Ok(function.call(arg)?)
+}
+
+#[test]
+fn compile_batch() -> Result<()> {
+    let store = get_store(false);
+    // The same function body, at another index in the second module.
+    let first = wat2wasm(
+        format!(
+            r#"(module
+                {}
+                (func (export "double") (param i32) (result i32)
+                    (i32.mul (local.get 0) (i32.const 2)))
+                (export "select" (func $select)))"#,
+            SELECT
+        )
+        .as_bytes(),
+    )?;
+    let second = wat2wasm(
+        format!(
+            r#"(module
+                (func (export "triple") (param i32) (result i32)
+                    (i32.mul (local.get 0) (i32.const 3)))
+                {}
+                (export "pick" (func $select)))"#,
+            SELECT
+        )
+        .as_bytes(),
+    )?;
+
+    let (modules, stats) = Module::compile_batch(&store, &[&first, &second, &first])?;
+    assert_eq!(modules.len(), 3);
+    assert_eq!(stats.modules, 3);
+    assert_eq!(stats.compiled_modules, 2);
+    assert_eq!(stats.deduplicated_modules(), 1);
+    assert_eq!(stats.function_bodies, 6);
+    // Only Cranelift reuses the code of the identical function bodies.
+    let reused_function_bodies = if cfg!(all(feature = "test-cranelift", feature = "test-jit")) {
+        3
+    } else {
+        2
+    };
+    assert_eq!(stats.reused_function_bodies, reused_function_bodies);
+    assert_eq!(stats.compiled_function_bodies(), 6 - reused_function_bodies);
+
+    let first = Instance::new(&modules[0], &imports! {})?;
+    let second = Instance::new(&modules[1], &imports! {})?;
+    for (arg, result) in &[(0, 10), (1, 20), (2, 30), (3, 30)] {
+        assert_eq!(call(&first, "select", *arg)?, *result);
+        assert_eq!(call(&second, "pick", *arg)?, *result);
+    }
+    assert_eq!(call(&first, "double", 4)?, 8);
+    assert_eq!(call(&second, "triple", 4)?, 12);
+    let third = Instance::new(&modules[2], &imports! {})?;
+    assert_eq!(call(&third, "select", 1)?, 20);
+    Ok(())
+}
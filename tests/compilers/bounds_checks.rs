@@ -0,0 +1,54 @@
+use crate::utils::get_engine;
+use anyhow::Result;
+
+use wasmer::vm::BoundsCheck;
+use wasmer::*;
+
+const WAT: &str = r#"(module
+    (memory 1 2)
+    (func (export "load") (param i32) (result i32)
+        (i32.load (local.get 0)))
+    (func (export "load_far") (param i32) (result i32)
+        (i32.load offset=0x10000 (local.get 0)))
+    (func (export "grow") (param i32) (result i32)
+        (memory.grow (local.get 0))))"#;
+
+fn check_accesses(instance: &Instance) -> Result<()> {
+    let load: NativeFunc<i32, i32> = instance.exports.get_native_function("load")?;
+    let load_far: NativeFunc<i32, i32> = instance.exports.get_native_function("load_far")?;
+    let grow: NativeFunc<i32, i32> = instance.exports.get_native_function("grow")?;
+
+    assert_eq!(load.call(65532)?, 0);
+    assert!(load.call(65533).is_err());
+    assert!(load_far.call(0).is_err());
+
+    assert_eq!(grow.call(1)?, 1);
+    assert_eq!(load.call(65536)?, 0);
+    assert_eq!(load_far.call(65532)?, 0);
+    assert!(load_far.call(65533).is_err());
+    Ok(())
+}
+
+#[test]
+fn bounds_checks() -> Result<()> {
+    let engine = get_engine(false);
+    for &bounds_check in &[
+        BoundsCheck::GuardPages,
+        BoundsCheck::Explicit,
+        BoundsCheck::Hybrid {
+            max_guarded_offset: 0x1000,
+        },
+    ] {
+        let mut tunables = BaseTunables::for_target(engine.target());
+        tunables.memory_bounds_check = bounds_check;
+        let store = Store::new_with_tunables(&engine, tunables);
+        let module = Module::new(&store, WAT)?;
+        check_accesses(&Instance::new(&module, &imports! {})?)?;
+
+        // The bounds checks are part of the serialized module.
+        let serialized = module.serialize()?;
+        let module = unsafe { Module::deserialize(&store, &serialized)? };
+        check_accesses(&Instance::new(&module, &imports! {})?)?;
+    }
+    Ok(())
+}
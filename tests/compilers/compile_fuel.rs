@@ -0,0 +1,41 @@
+use crate::utils::get_store_with_compile_fuel;
+use anyhow::Result;
+use wasmer::*;
+
+const WAT: &str = r#"(module
+    (func (export "add") (result i32)
+        (i32.add (i32.const 1) (i32.const 2))))"#;
+
+/// The operators of the function bodies of `WAT`, with their `end`.
+const OPERATORS: u64 = 4;
+
+fn compile_with_fuel(limit: u64) -> Result<Module, CompileError> {
+    let store = get_store_with_compile_fuel(limit);
+    Module::new(&store, WAT)
+}
+
+#[test]
+fn compile_within_fuel() -> Result<()> {
+    let bytes = wat2wasm(WAT.as_bytes())?.len() as u64;
+    let module = compile_with_fuel(bytes + OPERATORS)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let add: NativeFunc<(), i32> = instance.exports.get_native_function("add")?;
+    assert_eq!(add.call()?, 3);
+    Ok(())
+}
+
+#[test]
+fn compile_out_of_fuel() -> Result<()> {
+    let bytes = wat2wasm(WAT.as_bytes())?.len() as u64;
+    // Not enough fuel to validate the module.
+    match compile_with_fuel(bytes - 1) {
+        Err(CompileError::FuelExhausted(limit)) => assert_eq!(limit, bytes - 1),
+        other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    }
+    // Not enough fuel to compile the function.
+    match compile_with_fuel(bytes + OPERATORS - 1) {
+        Err(CompileError::FuelExhausted(limit)) => assert_eq!(limit, bytes + OPERATORS - 1),
+        other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    }
+    Ok(())
+}
@@ -0,0 +1,39 @@
+use crate::utils::get_store_with_middlewares;
+use anyhow::Result;
+use wasmer_middlewares::coverage::{get_counters, reset_counters};
+use wasmer_middlewares::Coverage;
+
+use std::sync::Arc;
+use wasmer::*;
+
+#[test]
+fn coverage_counts_loop_iterations() -> Result<()> {
+    let coverage = Arc::new(Coverage::new());
+    let store = get_store_with_middlewares(std::iter::once(
+        coverage.clone() as Arc<dyn ModuleMiddleware>
+    ));
+    let wat = r#"(module
+        (func (export "sum") (param $n i32) (result i32) (local $sum i32)
+            (block $done
+                (loop $next
+                    (br_if $done (i32.eqz (local.get $n)))
+                    (local.set $sum (i32.add (local.get $sum) (local.get $n)))
+                    (local.set $n (i32.sub (local.get $n) (i32.const 1)))
+                    (br $next)))
+            (local.get $sum))
+)"#;
+    let module = Module::new(&store, wat)?;
+    // The entry, the loop, after the `br_if`, after the loop (unreachable)
+    // and after the block.
+    assert_eq!(coverage.blocks().len(), 5);
+
+    let instance = Instance::new(&module, &imports! {})?;
+    let sum: NativeFunc<i32, i32> = instance.exports.get_native_function("sum")?;
+    assert_eq!(sum.call(3)?, 6);
+    assert_eq!(get_counters(&instance), vec![1, 4, 3, 0, 1]);
+
+    reset_counters(&instance);
+    assert_eq!(sum.call(0)?, 0);
+    assert_eq!(get_counters(&instance), vec![1, 1, 0, 0, 1]);
+    Ok(())
+}
@@ -0,0 +1,105 @@
+use crate::utils::get_engine;
+use anyhow::Result;
+
+use std::ptr::NonNull;
+use std::sync::Arc;
+use wasmer::vm::{
+    CustomMemory, Memory, MemoryError, MemoryStyle, Table, TableStyle, VMMemoryDefinition,
+    VMTableDefinition,
+};
+use wasmer::*;
+
+/// Tunables holding the memories in vectors of the host.
+struct VecTunables {
+    base: BaseTunables,
+}
+
+impl Tunables for VecTunables {
+    fn memory_style(&self, _memory: &MemoryType) -> MemoryStyle {
+        MemoryStyle::Dynamic {
+            offset_guard_size: 0,
+        }
+    }
+
+    fn table_style(&self, table: &TableType) -> TableStyle {
+        self.base.table_style(table)
+    }
+
+    fn create_host_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+    ) -> Result<Arc<dyn Memory>, MemoryError> {
+        Ok(Arc::new(CustomMemory::new(ty, style, Vec::new())?))
+    }
+
+    unsafe fn create_vm_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+        vm_definition_location: NonNull<VMMemoryDefinition>,
+    ) -> Result<Arc<dyn Memory>, MemoryError> {
+        Ok(Arc::new(CustomMemory::from_definition(
+            ty,
+            style,
+            Vec::new(),
+            vm_definition_location,
+        )?))
+    }
+
+    fn create_host_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+    ) -> Result<Arc<dyn Table>, String> {
+        self.base.create_host_table(ty, style)
+    }
+
+    unsafe fn create_vm_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+        vm_definition_location: NonNull<VMTableDefinition>,
+    ) -> Result<Arc<dyn Table>, String> {
+        self.base.create_vm_table(ty, style, vm_definition_location)
+    }
+}
+
+#[test]
+fn custom_instance_memory() -> Result<()> {
+    let engine = get_engine(false);
+    let tunables = VecTunables {
+        base: BaseTunables::for_target(engine.target()),
+    };
+    let store = Store::new_with_tunables(&engine, tunables);
+    let wat = r#"(module
+        (memory (export "memory") 1 4)
+        (data (i32.const 8) "\2a")
+        (func (export "grow") (param i32) (result i32)
+            (memory.grow (local.get 0)))
+        (func (export "load") (param i32) (result i32)
+            (i32.load8_u (local.get 0)))
+        (func (export "store") (param i32 i32)
+            (i32.store8 (local.get 0) (local.get 1))))"#;
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &imports! {})?;
+
+    let grow: NativeFunc<i32, i32> = instance.exports.get_native_function("grow")?;
+    let load: NativeFunc<i32, i32> = instance.exports.get_native_function("load")?;
+    let store_fn: NativeFunc<(i32, i32), ()> = instance.exports.get_native_function("store")?;
+    assert_eq!(load.call(8)?, 42);
+    assert!(load.call(65536).is_err());
+
+    // Growing moves the vector, with its contents.
+    assert_eq!(grow.call(2)?, 1);
+    store_fn.call(2 * 65536, 7)?;
+    assert_eq!(load.call(8)?, 42);
+    assert_eq!(load.call(2 * 65536)?, 7);
+    assert!(load.call(3 * 65536).is_err());
+    assert_eq!(grow.call(2)?, -1);
+
+    let memory = instance.exports.get_memory("memory")?;
+    assert_eq!(memory.size(), Pages(3));
+    assert_eq!(memory.view::<u8>()[2 * 65536].get(), 7);
+    Ok(())
+}
@@ -0,0 +1,153 @@
+use crate::utils::{get_compiler, get_deterministic_store, get_store, get_store_with_middlewares};
+use anyhow::Result;
+
+use std::sync::{Arc, Barrier};
+use std::thread;
+use wasmer::*;
+use wasmer_compiler::{CallDepthLimit, CompilerConfig};
+#[cfg(feature = "test-jit")]
+use wasmer_engine_jit::JIT;
+#[cfg(feature = "test-native")]
+use wasmer_engine_native::Native;
+
+/// The bits of the canonical NaN of `f32`.
+const CANONICAL_NAN_F32: i32 = 0x7fc0_0000;
+
+#[test]
+fn deterministic_nans() -> Result<()> {
+    let store = get_deterministic_store();
+    let wat = r#"(module
+        (func (export "nan") (param f32 f32) (result i32)
+            (i32.reinterpret_f32 (f32.div (local.get 0) (local.get 1)))))"#;
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &imports! {})?;
+
+    let nan: NativeFunc<(f32, f32), i32> = instance.exports.get_native_function("nan")?;
+    assert_eq!(nan.call(0.0, 0.0)?, CANONICAL_NAN_F32);
+    assert_eq!(nan.call(-0.0, 0.0)?, CANONICAL_NAN_F32);
+    Ok(())
+}
+
+#[test]
+fn deterministic_without_threads() -> Result<()> {
+    let store = get_deterministic_store();
+    let wat = r#"(module (memory 1 1 shared))"#;
+    assert!(Module::new(&store, wat).is_err());
+    Ok(())
+}
+
+#[test]
+fn call_depth_limit() -> Result<()> {
+    let store = get_store_with_middlewares(std::iter::once(
+        Arc::new(CallDepthLimit::new(100)) as Arc<dyn ModuleMiddleware>
+    ));
+    let wat = r#"(module
+        (func $depth (export "depth") (param i32) (result i32)
+            (if (result i32) (i32.eqz (local.get 0))
+                (then (i32.const 0))
+                (else (i32.add (call $depth (i32.sub (local.get 0) (i32.const 1)))
+                               (i32.const 1))))))"#;
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &imports! {})?;
+
+    let depth: NativeFunc<i32, i32> = instance.exports.get_native_function("depth")?;
+    // The host calls `depth` at a depth of 0, so it can recurse 99 times.
+    assert_eq!(depth.call(99)?, 99);
+    assert_eq!(depth.call(99)?, 99);
+    assert!(depth.call(100).is_err());
+
+    // The trap doesn't leave the depth of the frames it unwound.
+    assert_eq!(depth.call(99)?, 99);
+    let depth = instance.exports.get_function("depth")?;
+    assert!(depth.call(&[Value::I32(100)]).is_err());
+    assert_eq!(
+        depth.call(&[Value::I32(99)])?.to_vec(),
+        vec![Value::I32(99)]
+    );
+    Ok(())
+}
+
+#[test]
+fn enable_deterministic_twice() -> Result<()> {
+    let mut compiler_config = get_compiler(false);
+    compiler_config.enable_deterministic();
+    compiler_config.enable_deterministic();
+    #[cfg(feature = "test-jit")]
+    let engine = JIT::new(compiler_config).engine();
+    #[cfg(feature = "test-native")]
+    let engine = Native::new(compiler_config).engine();
+    let store = Store::new(&engine);
+    let wat = r#"(module
+        (func $depth (export "depth") (param i32) (result i32)
+            (if (result i32) (i32.eqz (local.get 0))
+                (then (i32.const 0))
+                (else (i32.add (call $depth (i32.sub (local.get 0) (i32.const 1)))
+                               (i32.const 1))))))"#;
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &imports! {})?;
+
+    // A single limit counts each call once.
+    let depth: NativeFunc<i32, i32> = instance.exports.get_native_function("depth")?;
+    assert_eq!(depth.call(6000)?, 6000);
+    Ok(())
+}
+
+#[test]
+fn deterministic_store() -> Result<()> {
+    let store = get_deterministic_store();
+    assert!(store.is_deterministic());
+    Module::new(&store, "(module)")?;
+
+    let store = get_store(false);
+    assert!(!store.is_deterministic());
+    store.set_deterministic(true);
+    match Module::new(&store, "(module)") {
+        Err(CompileError::Validate(message)) => {
+            assert!(message.contains("deterministic"), "{}", message)
+        }
+        result => panic!("unexpected result: {:?}", result.map(|_| ())),
+    }
+    Ok(())
+}
+
+#[test]
+fn deterministic_store_rejects_concurrent_calls() -> Result<()> {
+    #[derive(WasmerEnv, Clone)]
+    struct Env {
+        barrier: Arc<Barrier>,
+    }
+
+    let store = get_deterministic_store();
+    let wat = r#"(module
+        (import "host" "block" (func $block))
+        (func (export "run") (call $block))
+        (func (export "one") (result i32) (i32.const 1)))"#;
+    let module = Module::new(&store, wat)?;
+    let barrier = Arc::new(Barrier::new(2));
+    let block = Function::new_native_with_env(
+        &store,
+        Env {
+            barrier: barrier.clone(),
+        },
+        |env: &Env| {
+            // Entered, then released.
+            env.barrier.wait();
+            env.barrier.wait();
+        },
+    );
+    let instance = Instance::new(&module, &imports! { "host" => { "block" => block } })?;
+    let one: NativeFunc<(), i32> = instance.exports.get_native_function("one")?;
+
+    let run = instance.exports.get_native_function::<(), ()>("run")?;
+    let thread = thread::spawn(move || run.call());
+    barrier.wait();
+    // The depth of the calls is counted per instance, so another
+    // thread can't enter it.
+    assert!(one.call().is_err());
+    assert!(instance.exports.get_function("one")?.call(&[]).is_err());
+
+    barrier.wait();
+    thread.join().unwrap()?;
+    assert_eq!(one.call()?, 1);
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use crate::utils::{get_store, get_store_with_function_counters};
+use anyhow::Result;
+
+use std::collections::HashMap;
+use wasmer::*;
+
+const WAT: &str = r#"(module
+    (func $square (param i32) (result i32)
+        (i32.mul (local.get 0) (local.get 0)))
+    (func (export "sum_of_squares") (param i32 i32) (result i32)
+        (i32.add (call $square (local.get 0)) (call $square (local.get 1))))
+    (func (param i32) (result i32)
+        (local.get 0))
+    (export "unused" (func 2)))"#;
+
+#[test]
+fn function_counts() -> Result<()> {
+    let store = get_store_with_function_counters();
+    let module = Module::new(&store, WAT)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let counts = |square, sum_of_squares| {
+        vec![(0, square), (1, sum_of_squares), (2, 0)]
+            .into_iter()
+            .collect::<HashMap<_, _>>()
+    };
+    assert_eq!(instance.function_counts(), counts(0, 0));
+    assert_eq!(module.function_name(0), Some("square"));
+
+    // The counters aren't exported.
+    let exports = instance
+        .exports
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(exports, vec!["sum_of_squares", "unused"]);
+
+    let sum_of_squares: NativeFunc<(i32, i32), i32> =
+        instance.exports.get_native_function("sum_of_squares")?;
+    assert_eq!(sum_of_squares.call(3, 4)?, 25);
+    assert_eq!(sum_of_squares.call(1, 2)?, 5);
+    assert_eq!(instance.function_counts(), counts(4, 2));
+    Ok(())
+}
+
+#[test]
+fn function_counts_disabled() -> Result<()> {
+    let store = get_store(false);
+    let module = Module::new(&store, WAT)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    assert!(instance.function_counts().is_empty());
+    Ok(())
+}
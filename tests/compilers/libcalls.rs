@@ -0,0 +1,105 @@
+use crate::utils::get_engine;
+use anyhow::Result;
+
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use wasmer::vm::{
+    Libcalls, Memory, MemoryError, MemoryStyle, Table, TableStyle, VMContext, VMMemoryDefinition,
+    VMTableDefinition,
+};
+use wasmer::*;
+
+/// The pages requested from `counting_memory32_grow`.
+static REQUESTED: AtomicU32 = AtomicU32::new(0);
+
+unsafe extern "C" fn counting_memory32_grow(
+    vmctx: *mut VMContext,
+    delta: u32,
+    memory_index: u32,
+) -> u32 {
+    REQUESTED.fetch_add(delta, Ordering::SeqCst);
+    (Libcalls::default().memory32_grow)(vmctx, delta, memory_index)
+}
+
+/// Tunables replacing the `memory.grow` libcall with `counting_memory32_grow`.
+struct CountingTunables {
+    base: BaseTunables,
+}
+
+impl Tunables for CountingTunables {
+    fn memory_style(&self, memory: &MemoryType) -> MemoryStyle {
+        self.base.memory_style(memory)
+    }
+
+    fn table_style(&self, table: &TableType) -> TableStyle {
+        self.base.table_style(table)
+    }
+
+    fn create_host_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+    ) -> Result<Arc<dyn Memory>, MemoryError> {
+        self.base.create_host_memory(ty, style)
+    }
+
+    unsafe fn create_vm_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+        vm_definition_location: NonNull<VMMemoryDefinition>,
+    ) -> Result<Arc<dyn Memory>, MemoryError> {
+        self.base
+            .create_vm_memory(ty, style, vm_definition_location)
+    }
+
+    fn create_host_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+    ) -> Result<Arc<dyn Table>, String> {
+        self.base.create_host_table(ty, style)
+    }
+
+    unsafe fn create_vm_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+        vm_definition_location: NonNull<VMTableDefinition>,
+    ) -> Result<Arc<dyn Table>, String> {
+        self.base.create_vm_table(ty, style, vm_definition_location)
+    }
+
+    fn libcalls(&self) -> Libcalls {
+        Libcalls {
+            memory32_grow: counting_memory32_grow,
+            ..Libcalls::default()
+        }
+    }
+}
+
+#[test]
+fn replaced_libcall() -> Result<()> {
+    let engine = get_engine(false);
+    let tunables = CountingTunables {
+        base: BaseTunables::for_target(engine.target()),
+    };
+    let store = Store::new_with_tunables(&engine, tunables);
+    let wat = r#"(module
+        (memory (export "memory") 1)
+        (func (export "grow") (param i32) (result i32)
+            (memory.grow (local.get 0))))"#;
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &imports! {})?;
+
+    let grow: NativeFunc<i32, i32> = instance.exports.get_native_function("grow")?;
+    assert_eq!(grow.call(2)?, 1);
+    assert_eq!(grow.call(3)?, 3);
+    assert_eq!(REQUESTED.load(Ordering::SeqCst), 5);
+
+    // The replacement keeps the semantics of the default implementation.
+    let memory = instance.exports.get_memory("memory")?;
+    assert_eq!(memory.size(), Pages(6));
+    Ok(())
+}
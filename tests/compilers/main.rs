@@ -4,11 +4,21 @@
 //! implementation, such as: singlepass, cranelift or llvm depending
 //! on what's available on the target.
 
+mod batch;
+mod bounds_checks;
+mod compile_fuel;
+mod coverage;
+mod custom_memory;
+mod deterministic;
+mod function_counters;
 mod imports;
+mod libcalls;
 mod metering;
 mod middlewares;
 mod multi_value_imports;
 mod native_functions;
+mod prepass;
+mod reference_types;
 mod serialize;
 mod traps;
 mod utils;
@@ -88,6 +88,66 @@ impl FunctionMiddleware for Fusion {
     }
 }
 
+/// Counts the calls in an injected global, and mirrors the count in an
+/// injected memory.
+#[derive(Debug, Default)]
+struct CallCounterGen {
+    items: std::sync::Mutex<Option<(GlobalIndex, MemoryIndex)>>,
+}
+
+#[derive(Debug)]
+struct CallCounter {
+    global: GlobalIndex,
+    memory: MemoryIndex,
+}
+
+impl ModuleMiddleware for CallCounterGen {
+    fn generate_function_middleware(&self, _: LocalFunctionIndex) -> Box<dyn FunctionMiddleware> {
+        let (global, memory) = self.items.lock().unwrap().unwrap();
+        Box::new(CallCounter { global, memory })
+    }
+
+    fn inject_module_items(&self, injector: &mut ModuleItemInjector) {
+        let global = injector.add_global(
+            GlobalType::new(Type::I32, Mutability::Var),
+            GlobalInit::I32Const(0),
+        );
+        injector.export("calls", ExportIndex::Global(global));
+        let memory = injector.add_memory(MemoryType::new(1, None, false));
+        injector.export("calls_memory", ExportIndex::Memory(memory));
+        *self.items.lock().unwrap() = Some((global, memory));
+    }
+}
+
+impl FunctionMiddleware for CallCounter {
+    fn feed<'a>(
+        &mut self,
+        operator: Operator<'a>,
+        state: &mut MiddlewareReaderState<'a>,
+    ) -> Result<(), MiddlewareError> {
+        if let Operator::Call { .. } = operator {
+            let global_index = self.global.as_u32();
+            state.extend(&[
+                Operator::GlobalGet { global_index },
+                Operator::I32Const { value: 1 },
+                Operator::I32Add,
+                Operator::GlobalSet { global_index },
+                Operator::I32Const { value: 0 },
+                Operator::GlobalGet { global_index },
+                Operator::I32Store {
+                    memarg: wasmer::wasmparser::MemoryImmediate {
+                        align: 2,
+                        offset: 0,
+                        memory: self.memory.as_u32(),
+                    },
+                },
+            ]);
+        }
+        state.push_operator(operator);
+        Ok(())
+    }
+}
+
 #[test]
 fn middleware_basic() -> Result<()> {
     let store = get_store_with_middlewares(std::iter::once(
@@ -208,3 +268,30 @@ fn middleware_chain_order_2() -> Result<()> {
     assert_eq!(result, 48);
     Ok(())
 }
+
+#[test]
+fn middleware_injected_items() -> Result<()> {
+    let store = get_store_with_middlewares(std::iter::once(
+        Arc::new(CallCounterGen::default()) as Arc<dyn ModuleMiddleware>
+    ));
+    let wat = r#"(module
+        (func $one (result i32) (i32.const 1))
+        (func (export "run") (result i32)
+           (i32.add (call $one) (call $one)))
+)"#;
+    let module = Module::new(&store, wat).unwrap();
+
+    let import_object = imports! {};
+
+    let instance = Instance::new(&module, &import_object)?;
+
+    let f: NativeFunc<(), i32> = instance.exports.get_native_function("run")?;
+    assert_eq!(f.call()?, 2);
+    f.call()?;
+    assert_eq!(instance.exports.get_global("calls")?.get(), Value::I32(4));
+    let memory = instance.exports.get_memory("calls_memory")?;
+    assert_eq!(memory.size(), Pages(1));
+    let count = memory.view::<i32>()[0].get();
+    assert_eq!(count, 4);
+    Ok(())
+}
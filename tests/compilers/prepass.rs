@@ -0,0 +1,63 @@
+use crate::utils::get_store_with_middlewares;
+use anyhow::Result;
+
+use std::sync::Arc;
+use wasmer::*;
+use wasmer_compiler::Prepass;
+
+const WAT: &str = r#"(module
+    (global $scale i32 (i32.const 3))
+    (global $offset f64 (f64.const 0.5))
+    (memory (export "memory") 1)
+    (data (i32.const 0) "ab")
+    (data (i32.const 2) "cd")
+    (data (i32.const 8) "ef")
+    (func $scale (param i32) (result i32)
+        (i32.mul (local.get 0) (global.get $scale)))
+    (func (export "scaled") (param i32) (result i32)
+        (call $scale (local.get 0)))
+    (func (export "offset") (param f64) (result f64)
+        (f64.add (local.get 0) (global.get $offset)))
+    (func $helper (result i32)
+        (block (loop (br 1)))
+        (i32.const 42))
+    (func (export "unused") (result i32)
+        (call $helper)))"#;
+
+fn instantiate(prepass: Prepass) -> Result<Instance> {
+    let store = get_store_with_middlewares(std::iter::once(
+        Arc::new(prepass) as Arc<dyn ModuleMiddleware>
+    ));
+    let module = Module::new(&store, WAT)?;
+    Ok(Instance::new(&module, &imports! {})?)
+}
+
+#[test]
+fn prepass_keeps_behavior() -> Result<()> {
+    let instance = instantiate(Prepass::new())?;
+
+    let scaled: NativeFunc<i32, i32> = instance.exports.get_native_function("scaled")?;
+    assert_eq!(scaled.call(5)?, 15);
+    let offset: NativeFunc<f64, f64> = instance.exports.get_native_function("offset")?;
+    assert_eq!(offset.call(1.0)?, 1.5);
+    let unused: NativeFunc<(), i32> = instance.exports.get_native_function("unused")?;
+    assert_eq!(unused.call()?, 42);
+
+    let memory = instance.exports.get_memory("memory")?;
+    let view = memory.view::<u8>();
+    let bytes = view[..10].iter().map(|cell| cell.get()).collect::<Vec<_>>();
+    assert_eq!(bytes, b"abcd\0\0\0\0ef");
+    Ok(())
+}
+
+#[test]
+fn prepass_with_roots() -> Result<()> {
+    let instance = instantiate(Prepass::with_roots(vec!["scaled", "memory"]))?;
+
+    let scaled: NativeFunc<i32, i32> = instance.exports.get_native_function("scaled")?;
+    assert_eq!(scaled.call(5)?, 15);
+    assert!(instance.exports.get_function("offset").is_err());
+    assert!(instance.exports.get_function("unused").is_err());
+    assert!(instance.exports.get_memory("memory").is_ok());
+    Ok(())
+}
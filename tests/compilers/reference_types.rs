@@ -0,0 +1,40 @@
+use crate::utils::get_store_with_features;
+use anyhow::Result;
+use wasmer::*;
+
+const WAT: &str = r#"
+(module
+  (func $add_one (param i32) (result i32)
+    (i32.add (local.get 0) (i32.const 1)))
+  (global (export "add_one") funcref (ref.func $add_one))
+  (global (export "callback") (mut funcref) (ref.null func)))
+"#;
+
+fn function(global: &Global) -> Function {
+    match global.get() {
+        Value::FuncRef(function) => function,
+        value => panic!("the global holds {:?} rather than a function", value),
+    }
+}
+
+#[test]
+fn reference_globals() -> Result<()> {
+    let store = get_store_with_features(Features::new().reference_types(true).clone());
+    let module = Module::new(&store, WAT)?;
+    let instance = Instance::new(&module, &imports! {})?;
+
+    let add_one = function(instance.exports.get_global("add_one")?);
+    assert_eq!(add_one.native::<i32, i32>()?.call(41)?, 42);
+
+    let callback = instance.exports.get_global("callback")?;
+    assert!(matches!(callback.get(), Value::ExternRef(ExternRef::Null)));
+    callback.set(Value::FuncRef(Function::new_native(&store, |num: i32| {
+        num * 2
+    })))?;
+    assert_eq!(function(callback).native::<i32, i32>()?.call(21)?, 42);
+    callback.set(Value::FuncRef(add_one))?;
+    assert_eq!(function(callback).native::<i32, i32>()?.call(1)?, 2);
+    callback.set(Value::ExternRef(ExternRef::Null))?;
+    assert!(matches!(callback.get(), Value::ExternRef(ExternRef::Null)));
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use crate::utils::{get_headless_store, get_store};
+use crate::utils::{get_headless_store, get_store, get_store_with_embedded_wasm};
 use anyhow::Result;
 use wasmer::*;
 
@@ -75,3 +75,82 @@ fn test_deserialize() -> Result<()> {
     assert_eq!(result.to_vec(), vec![Value::I64(1500)]);
     Ok(())
 }
+
+#[test]
+fn test_convert() -> Result<()> {
+    let wat = r#"
+        (module $name
+            (func (export "add") (param i32 i32) (result i32)
+                (i32.add (local.get 0) (local.get 1))))
+    "#;
+
+    // Without the embedded wasm, the module can't be converted.
+    let store = get_store(false);
+    let module = Module::new(&store, wat)?;
+    assert!(module.wasm().is_none());
+    assert!(matches!(
+        module.convert(&store),
+        Err(ConvertError::WasmNotEmbedded)
+    ));
+
+    let store = get_store_with_embedded_wasm();
+    let mut module = Module::new(&store, wat)?;
+    module.set_name("renamed");
+    let serialized_bytes = module.serialize()?;
+
+    let headless_store = get_headless_store();
+    let deserialized_module = unsafe { Module::deserialize(&headless_store, &serialized_bytes)? };
+    assert_eq!(
+        deserialized_module.wasm(),
+        Some(&wat2wasm(wat.as_bytes())?[..])
+    );
+
+    let converted_module = deserialized_module.convert(&get_store(false))?;
+    assert_eq!(converted_module.name(), Some("renamed"));
+    assert!(converted_module.wasm().is_none());
+    let instance = Instance::new(&converted_module, &imports! {})?;
+    let add = instance
+        .exports
+        .get_native_function::<(i32, i32), i32>("add")?;
+    assert_eq!(add.call(1, 2)?, 3);
+    Ok(())
+}
+
+#[test]
+fn test_deserialize_untrusted() -> Result<()> {
+    let store = get_store(false);
+    let wat = r#"
+        (module
+            (memory 1)
+            (data (i32.const 16) "\2a")
+            (table 2 funcref)
+            (elem (i32.const 0) $load $trap)
+            (type $t (func (param i32) (result i32)))
+            (func $load (param i32) (result i32) (i32.load8_u (local.get 0)))
+            (func $trap (param i32) (result i32) unreachable)
+            (func (export "call") (param i32 i32) (result i32)
+                (call_indirect (type $t) (local.get 1) (local.get 0))))
+    "#;
+    let module = Module::new(&store, wat)?;
+    let serialized_bytes = module.serialize()?;
+
+    let headless_store = get_headless_store();
+    let deserialized_module =
+        unsafe { Module::deserialize_untrusted(&headless_store, &serialized_bytes)? };
+    let instance = Instance::new(&deserialized_module, &imports! {})?;
+    let call = instance
+        .exports
+        .get_native_function::<(i32, i32), i32>("call")?;
+    assert_eq!(call.call(0, 16)?, 42);
+    let error = call.call(1, 0).unwrap_err();
+    assert!(error.message().contains("unreachable"));
+
+    // A truncated module is reported as an error.
+    for len in (0..serialized_bytes.len()).step_by(serialized_bytes.len() / 64 + 1) {
+        assert!(unsafe {
+            Module::deserialize_untrusted(&headless_store, &serialized_bytes[..len])
+        }
+        .is_err());
+    }
+    Ok(())
+}
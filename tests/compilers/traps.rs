@@ -71,7 +71,7 @@ fn test_trap_trace() -> Result<()> {
     assert_eq!(trace[0].function_name(), Some("hello"));
     assert_eq!(trace[1].module_name(), "hello_mod");
     assert_eq!(trace[1].func_index(), 0);
-    assert_eq!(trace[1].function_name(), None);
+    assert_eq!(trace[1].function_name(), Some("run"));
     assert!(
         e.message().contains("unreachable"),
         "wrong message: {}",
@@ -81,6 +81,59 @@ fn test_trap_trace() -> Result<()> {
     Ok(())
 }
 
+#[test]
+#[cfg_attr(
+    any(
+        feature = "test-singlepass",
+        feature = "test-native",
+        target_arch = "aarch64",
+    ),
+    ignore
+)]
+fn test_trap_trace_source_map() -> Result<()> {
+    let store = get_store(false);
+    let wat = r#"
+        (module $hello_mod
+            (func (export "run") (call $hello))
+            (func $hello (unreachable))
+        )
+    "#;
+
+    let mut module = Module::new(&store, wat)?;
+    // Maps the whole module to the first line of `main.ts`.
+    let source_map = SourceMap::from_json(
+        r#"{"version": 3, "sources": ["main.ts"], "names": [], "mappings": "AAAA"}"#,
+    )?;
+    assert!(module.set_source_map(source_map));
+    assert_eq!(module.source_map().unwrap().sources(), &["main.ts"]);
+
+    let instance = Instance::new(&module, &imports! {})?;
+    let run_func = instance
+        .exports
+        .get_function("run")
+        .expect("expected function export");
+
+    let e = run_func.call(&[]).err().expect("error calling function");
+
+    let trace = e.trace();
+    assert_eq!(trace.len(), 2);
+    for frame in trace {
+        let location = frame.source_location().expect("expected a source location");
+        assert_eq!(location.source(), "main.ts");
+        assert_eq!(location.line(), 1);
+    }
+    assert!(
+        e.to_string().contains("[main.ts:1:1]"),
+        "wrong display: {}",
+        e
+    );
+
+    // The module is shared with the instance.
+    assert!(!module.set_source_map(SourceMap::from_json(r#"{"version": 3, "mappings": ""}"#)?));
+
+    Ok(())
+}
+
 #[test]
 fn test_trap_trace_cb() -> Result<()> {
     let store = get_store(false);
@@ -162,6 +215,47 @@ fn test_trap_stack_overflow() -> Result<()> {
     Ok(())
 }
 
+#[test]
+#[cfg_attr(feature = "test-llvm", ignore)]
+fn test_trap_stack_size() -> Result<()> {
+    let store = get_store(false);
+    let wat = r#"
+        (module
+            (func $depth (export "depth") (param i32) (result i32)
+                (if (result i32) (local.get 0)
+                    (then (i32.add (call $depth (i32.sub (local.get 0) (i32.const 1)))
+                                   (i32.const 1)))
+                    (else (i32.const 0))))
+        )
+    "#;
+    store.set_stack_size(Some(64 * 1024));
+
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let depth = instance.exports.get_native_function::<i32, i32>("depth")?;
+
+    assert_eq!(depth.call(100)?, 100);
+    // A recursion that fits in the stack of the thread, but not in the
+    // limit.
+    let e = depth.call(10_000).err().expect("error calling function");
+    assert!(e.message().contains("call stack exhausted"));
+    let e = instance
+        .exports
+        .get_function("depth")?
+        .call(&[Val::I32(10_000)])
+        .err()
+        .expect("error calling function");
+    assert!(e.message().contains("call stack exhausted"));
+
+    // The instance can still be used after the overflow, and the limit
+    // applies to the next calls.
+    assert_eq!(depth.call(100)?, 100);
+    store.set_stack_size(None);
+    assert_eq!(depth.call(10_000)?, 10_000);
+
+    Ok(())
+}
+
 #[test]
 #[cfg_attr(
     any(
@@ -198,7 +292,7 @@ RuntimeError: unreachable
     at die (m[0]:0x23)
     at <unnamed> (m[1]:0x27)
     at foo (m[2]:0x2c)
-    at <unnamed> (m[3]:0x31)"
+    at bar (m[3]:0x31)"
     );
     Ok(())
 }
@@ -257,9 +351,9 @@ RuntimeError: unreachable
     at die (a[0]:0x23)
     at <unnamed> (a[1]:0x27)
     at foo (a[2]:0x2c)
-    at <unnamed> (a[3]:0x31)
+    at bar (a[3]:0x31)
     at middle (b[1]:0x29)
-    at <unnamed> (b[2]:0x2e)"
+    at bar2 (b[2]:0x2e)"
     );
     Ok(())
 }
@@ -518,3 +612,77 @@ fn present_after_module_drop() -> Result<()> {
         // assert_eq!(t.trace()[0].func_index(), 0);
     }
 }
+
+#[test]
+fn user_error_through_wasm() -> Result<()> {
+    #[derive(Debug, PartialEq)]
+    enum HostError {
+        Denied(i32),
+    }
+
+    impl std::fmt::Display for HostError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                Self::Denied(code) => write!(f, "denied {}", code),
+            }
+        }
+    }
+
+    impl std::error::Error for HostError {}
+
+    let store = get_store(false);
+    let wat = r#"
+        (module
+        (func $dynamic (import "host" "dynamic") (param i32))
+        (func $native (import "host" "native") (param i32) (result i32))
+        (func (export "call_dynamic") (param i32) (call $dynamic (local.get 0)))
+        (func (export "call_native") (param i32) (result i32) (call $native (local.get 0)))
+        (export "native" (func $native))
+        )
+    "#;
+
+    let module = Module::new(&store, wat)?;
+    let dynamic_type = FunctionType::new(vec![Type::I32], vec![]);
+    let instance = Instance::new(
+        &module,
+        &imports! {
+            "host" => {
+                "dynamic" => Function::new(&store, &dynamic_type, |params| {
+                    Err(RuntimeError::user(Box::new(HostError::Denied(params[0].unwrap_i32()))))
+                }),
+                "native" => Function::new_native(&store, |code: i32| -> Result<i32, HostError> {
+                    Err(HostError::Denied(code))
+                }),
+            }
+        },
+    )?;
+
+    let call_dynamic = instance.exports.get_function("call_dynamic")?;
+    let error = call_dynamic.call(&[Value::I32(1)]).unwrap_err();
+    assert_eq!(error.message(), "denied 1");
+    assert_eq!(
+        error.downcast_ref::<HostError>(),
+        Some(&HostError::Denied(1))
+    );
+    assert_eq!(error.downcast::<HostError>().unwrap(), HostError::Denied(1));
+
+    let call_native = instance
+        .exports
+        .get_native_function::<i32, i32>("call_native")?;
+    let error = call_native.call(2).unwrap_err();
+    assert!(error.is::<HostError>());
+    assert_eq!(error.downcast::<HostError>().unwrap(), HostError::Denied(2));
+
+    let native = instance.exports.get_native_function::<i32, i32>("native")?;
+    let error = native.call(3).unwrap_err();
+    assert_eq!(error.downcast::<HostError>().unwrap(), HostError::Denied(3));
+
+    // The host function called directly by the host.
+    let host_function = Function::new_native(&store, |code: i32| -> Result<i32, HostError> {
+        Err(HostError::Denied(code))
+    });
+    let error = host_function.native::<i32, i32>()?.call(4).unwrap_err();
+    assert_eq!(error.downcast::<HostError>().unwrap(), HostError::Denied(4));
+
+    Ok(())
+}
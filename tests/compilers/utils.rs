@@ -1,5 +1,5 @@
 use std::sync::Arc;
-use wasmer::{ModuleMiddleware, Store};
+use wasmer::{Features, ModuleMiddleware, Store};
 use wasmer_compiler::CompilerConfig;
 use wasmer_engine::Engine;
 #[cfg(feature = "test-jit")]
@@ -64,6 +64,54 @@ pub fn get_store_with_middlewares<I: Iterator<Item = Arc<dyn ModuleMiddleware>>>
     Store::new(&engine)
 }
 
+pub fn get_store_with_features(features: Features) -> Store {
+    let compiler_config = get_compiler(false);
+    #[cfg(feature = "test-jit")]
+    let engine = JIT::new(compiler_config).features(features).engine();
+    #[cfg(feature = "test-native")]
+    let engine = Native::new(compiler_config).features(features).engine();
+    Store::new(&engine)
+}
+
+pub fn get_store_with_compile_fuel(limit: u64) -> Store {
+    let compiler_config = get_compiler(false);
+    #[cfg(feature = "test-jit")]
+    let engine = JIT::new(compiler_config).compile_fuel(limit).engine();
+    #[cfg(feature = "test-native")]
+    let engine = Native::new(compiler_config).compile_fuel(limit).engine();
+    Store::new(&engine)
+}
+
+pub fn get_store_with_embedded_wasm() -> Store {
+    let compiler_config = get_compiler(false);
+    #[cfg(feature = "test-jit")]
+    let engine = JIT::new(compiler_config).embed_wasm(true).engine();
+    #[cfg(feature = "test-native")]
+    let engine = Native::new(compiler_config).embed_wasm(true).engine();
+    Store::new(&engine)
+}
+
+pub fn get_store_with_function_counters() -> Store {
+    let compiler_config = get_compiler(false);
+    #[cfg(feature = "test-jit")]
+    let engine = JIT::new(compiler_config).function_counters(true).engine();
+    #[cfg(feature = "test-native")]
+    let engine = Native::new(compiler_config)
+        .function_counters(true)
+        .engine();
+    Store::new(&engine)
+}
+
+pub fn get_deterministic_store() -> Store {
+    let mut compiler_config = get_compiler(false);
+    compiler_config.enable_deterministic();
+    #[cfg(feature = "test-jit")]
+    let engine = JIT::new(compiler_config).engine();
+    #[cfg(feature = "test-native")]
+    let engine = Native::new(compiler_config).engine();
+    Store::new(&engine)
+}
+
 #[cfg(feature = "test-jit")]
 pub fn get_headless_store() -> Store {
     Store::new(&JIT::headless().engine())